@@ -0,0 +1,364 @@
+//! `acc` reads and writes the serialization formats of the `rsa` crate so
+//! keys, accumulators, witnesses and proofs can be created and checked
+//! from scripts or compared with other implementations.
+//!
+//! Values are read from and written to files, or stdin and stdout when the
+//! path is omitted or `-`. Members are one per line and each line is hashed
+//! to a prime the same way `Accumulator::insert` does.
+#![deny(
+    warnings,
+    missing_docs,
+    unsafe_code,
+    unused_import_braces,
+    unused_lifetimes,
+    unused_qualifications,
+)]
+
+use clap::{Parser, Subcommand};
+use accumulator_rsa::{
+    format::{Header, Tag},
+    prelude::*,
+};
+use std::{
+    convert::TryFrom,
+    error::Error,
+    fs,
+    io::{self, BufRead, Read, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::AtomicBool,
+};
+
+type CliResult<T> = Result<T, Box<dyn Error>>;
+
+/// RSA accumulator operations
+#[derive(Debug, Parser)]
+#[command(name = "acc", version)]
+struct Cli {
+    /// Read and write values as hex text instead of raw bytes
+    #[arg(long, global = true)]
+    hex: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Generate a secret key, this takes a while
+    Keygen {
+        /// Where to write the key
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// Create an accumulator
+    New {
+        /// The secret key
+        #[arg(short, long)]
+        key: PathBuf,
+        /// Initial members, one per line
+        #[arg(short, long)]
+        members: Option<PathBuf>,
+        /// Where to write the accumulator
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// Add members to an accumulator
+    Add {
+        /// The accumulator to update
+        #[arg(short, long)]
+        accumulator: PathBuf,
+        /// Members to add, one per line
+        #[arg(short, long)]
+        members: Option<PathBuf>,
+        /// Where to write the updated accumulator
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// Remove members from an accumulator
+    Remove {
+        /// The secret key
+        #[arg(short, long)]
+        key: PathBuf,
+        /// The accumulator to update
+        #[arg(short, long)]
+        accumulator: PathBuf,
+        /// Members to remove, one per line
+        #[arg(short, long)]
+        members: Option<PathBuf>,
+        /// Where to write the updated accumulator
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// Write the public part of an accumulator without its members
+    Public {
+        /// The accumulator
+        #[arg(short, long)]
+        accumulator: PathBuf,
+        /// Where to write the public accumulator
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// Create a membership witness
+    Witness {
+        /// The accumulator
+        #[arg(short, long)]
+        accumulator: PathBuf,
+        /// The secret key, computing the witness is faster with it
+        #[arg(short, long)]
+        key: Option<PathBuf>,
+        /// The member
+        #[arg(long)]
+        member: String,
+        /// Where to write the witness
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// Create a membership proof from a witness or a non-membership proof for a value
+    Prove {
+        /// The accumulator
+        #[arg(short, long)]
+        accumulator: PathBuf,
+        /// A membership witness
+        #[arg(short, long, conflicts_with = "non_member", required_unless_present = "non_member")]
+        witness: Option<PathBuf>,
+        /// A value that is not a member
+        #[arg(long)]
+        non_member: Option<String>,
+        /// The verifier's nonce
+        #[arg(long)]
+        nonce: String,
+        /// Where to write the proof
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// Verify a membership or non-membership proof, exits with 1 if it is invalid
+    Verify {
+        /// The accumulator or its public part
+        #[arg(short, long)]
+        accumulator: PathBuf,
+        /// The proof
+        #[arg(short, long)]
+        proof: PathBuf,
+        /// The verifier's nonce
+        #[arg(long)]
+        nonce: String,
+    },
+}
+
+fn main() {
+    match run(Cli::parse()) {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("acc: {}", e);
+            process::exit(2);
+        }
+    }
+}
+
+/// Run a command. False if a proof didn't verify
+fn run(cli: Cli) -> CliResult<bool> {
+    let io = Io { hex: cli.hex };
+    match cli.command {
+        Command::Keygen { out } => {
+            let key = AccumulatorSecretKey::generate_with(
+                |p| {
+                    if p.found {
+                        eprintln!("found factor {} after {} candidates", p.factor + 1, p.candidates);
+                    }
+                },
+                &AtomicBool::new(false),
+            )?;
+            io.write(out, &key.to_bytes()?)?;
+        }
+        Command::New { key, members, out } => {
+            let key = AccumulatorSecretKey::try_from(io.read(Some(key))?)?;
+            let acc = match members {
+                Some(path) => Accumulator::with_members(&key, read_members(Some(path))?)?,
+                None => Accumulator::new(&key),
+            };
+            io.write(out, &acc.to_bytes()?)?;
+        }
+        Command::Add { accumulator, members, out } => {
+            let mut acc = Accumulator::try_from(io.read(Some(accumulator))?)?;
+            for m in read_members(members)? {
+                acc.insert_assign(m)?;
+            }
+            io.write(out, &acc.to_bytes()?)?;
+        }
+        Command::Remove { key, accumulator, members, out } => {
+            let key = AccumulatorSecretKey::try_from(io.read(Some(key))?)?;
+            let mut acc = Accumulator::try_from(io.read(Some(accumulator))?)?;
+            for m in read_members(members)? {
+                acc.remove_assign(&key, m)?;
+            }
+            io.write(out, &acc.to_bytes()?)?;
+        }
+        Command::Public { accumulator, out } => {
+            let acc = Accumulator::try_from(io.read(Some(accumulator))?)?;
+            io.write(out, &acc.to_public_bytes()?)?;
+        }
+        Command::Witness { accumulator, key, member, out } => {
+            let acc = Accumulator::try_from(io.read(Some(accumulator))?)?;
+            let witness = match key {
+                Some(key) => {
+                    let key = AccumulatorSecretKey::try_from(io.read(Some(key))?)?;
+                    // with_secret_key doesn't reject non-members, removing does
+                    acc.remove(&key, &member)?;
+                    MembershipWitness::with_secret_key(&acc, &key, &member)?
+                }
+                None => MembershipWitness::new(&acc, &member)?,
+            };
+            io.write(out, &witness.to_bytes()?)?;
+        }
+        Command::Prove { accumulator, witness, non_member, nonce, out } => {
+            let acc = Accumulator::try_from(io.read(Some(accumulator))?)?;
+            let proof = match (witness, non_member) {
+                (Some(witness), _) => {
+                    let witness = MembershipWitness::try_from(io.read(Some(witness))?.as_slice())?;
+                    MembershipProof::new(&witness, &acc, &nonce)?.to_bytes()?
+                }
+                (None, Some(value)) => {
+                    let witness = NonMembershipWitness::new(&acc, &value)?;
+                    NonMembershipProof::new(&witness, &acc, &nonce)?.to_bytes()?
+                }
+                (None, None) => unreachable!("clap requires one of them"),
+            };
+            io.write(out, &proof)?;
+        }
+        Command::Verify { accumulator, proof, nonce } => {
+            let acc = read_verifier(&io.read(Some(accumulator))?)?;
+            let proof = io.read(Some(proof))?;
+            let valid = match Header::parse(&proof)?.tag {
+                Tag::MembershipProof => MembershipProof::try_from(proof.as_slice())?.verify(&acc, &nonce),
+                Tag::NonMembershipProof => NonMembershipProof::try_from(proof.as_slice())?.verify(&acc, &nonce),
+                tag => return Err(format!("expected a proof, found {:?}", tag).into()),
+            };
+            println!("{}", if valid { "valid" } else { "invalid" });
+            return Ok(valid);
+        }
+    }
+    Ok(true)
+}
+
+/// The public state from either the full or public serialization
+fn read_verifier(data: &[u8]) -> CliResult<AccumulatorPublic> {
+    Ok(match Header::parse(data)?.tag {
+        Tag::AccumulatorPublic => AccumulatorPublic::try_from(data)?,
+        _ => Accumulator::try_from(data)?.public(),
+    })
+}
+
+/// Non-empty lines from `path` or stdin
+fn read_members(path: Option<PathBuf>) -> CliResult<Vec<Vec<u8>>> {
+    let reader: Box<dyn BufRead> = match path {
+        Some(p) if p != Path::new("-") => Box::new(io::BufReader::new(fs::File::open(p)?)),
+        _ => Box::new(io::BufReader::new(io::stdin())),
+    };
+    let mut members = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim_end_matches('\r');
+        if !line.is_empty() {
+            members.push(line.as_bytes().to_vec());
+        }
+    }
+    Ok(members)
+}
+
+/// Reads and writes values as raw bytes or hex text
+struct Io {
+    hex: bool,
+}
+
+impl Io {
+    fn read(&self, path: Option<PathBuf>) -> CliResult<Vec<u8>> {
+        let data = match path {
+            Some(p) if p != Path::new("-") => fs::read(p)?,
+            _ => {
+                let mut data = Vec::new();
+                io::stdin().read_to_end(&mut data)?;
+                data
+            }
+        };
+        if self.hex {
+            Ok(hex::decode(String::from_utf8(data)?.trim())?)
+        } else {
+            Ok(data)
+        }
+    }
+
+    fn write(&self, path: Option<PathBuf>, data: &[u8]) -> CliResult<()> {
+        let data = if self.hex {
+            let mut text = hex::encode(data).into_bytes();
+            text.push(b'\n');
+            text
+        } else {
+            data.to_vec()
+        };
+        match path {
+            Some(p) if p != Path::new("-") => fs::write(p, data)?,
+            _ => io::stdout().write_all(&data)?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_bytes() -> Vec<u8> {
+        // Fixed safe primes so the test doesn't spend minutes on key generation
+        let p = BigInteger::from("132590288326793330806752358172617836030510421524323425886695490513600853466362871997907908739315399849138190997738786757721539635477379820932279026029679011350046717599386392663749253953274352000157227488895139775977945940993648470523136879899410690348931562489237825925601577159953591977449106730133820825719");
+        let q = BigInteger::from("149253707427499607752440533538420296779167710000842829107795675900185486091323606384260179778233711456748787559527972657213022998726578510459854530854900733457277643303592216900588246498239579922221956281290954735600574251392801029419096160964874150455156365996536205549377586240264971604869515447059744740119");
+        AccumulatorSecretKey::from_primes(p, q).to_bytes().unwrap()
+    }
+
+    /// Run `acc` with `@name` arguments replaced by paths in `dir`
+    fn try_acc(dir: &Path, args: &[&str]) -> CliResult<bool> {
+        let mut argv = vec!["acc".to_string()];
+        for a in args {
+            argv.push(match a.strip_prefix('@') {
+                Some(name) => dir.join(name).display().to_string(),
+                None => a.to_string(),
+            });
+        }
+        run(Cli::parse_from(argv))
+    }
+
+    fn acc(dir: &Path, args: &[&str]) -> bool {
+        try_acc(dir, args).unwrap()
+    }
+
+    #[test]
+    fn commands() {
+        let dir = std::env::temp_dir().join(format!("acc-cli-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("key"), hex::encode(key_bytes())).unwrap();
+        fs::write(dir.join("initial"), "alice\nbob\n\n").unwrap();
+        fs::write(dir.join("more"), "carol\r\ndave\n").unwrap();
+        fs::write(dir.join("revoked"), "bob\n").unwrap();
+
+        assert!(acc(&dir, &["new", "--hex", "-k", "@key", "-m", "@initial", "-o", "@acc"]));
+        assert!(acc(&dir, &["add", "--hex", "-a", "@acc", "-m", "@more", "-o", "@acc"]));
+        assert!(acc(&dir, &["remove", "--hex", "-k", "@key", "-a", "@acc", "-m", "@revoked", "-o", "@acc"]));
+        assert!(acc(&dir, &["public", "--hex", "-a", "@acc", "-o", "@public"]));
+        assert!(acc(&dir, &["witness", "--hex", "-a", "@acc", "--member", "carol", "-o", "@w1"]));
+        assert!(acc(&dir, &["witness", "--hex", "-a", "@acc", "-k", "@key", "--member", "carol", "-o", "@w2"]));
+        assert_eq!(fs::read(dir.join("w1")).unwrap(), fs::read(dir.join("w2")).unwrap());
+
+        assert!(acc(&dir, &["prove", "--hex", "-a", "@acc", "-w", "@w1", "--nonce", "n", "-o", "@mem"]));
+        assert!(acc(&dir, &["verify", "--hex", "-a", "@public", "-p", "@mem", "--nonce", "n"]));
+        assert!(!acc(&dir, &["verify", "--hex", "-a", "@acc", "-p", "@mem", "--nonce", "other"]));
+        assert!(acc(&dir, &["prove", "--hex", "-a", "@acc", "--non-member", "bob", "--nonce", "n", "-o", "@non"]));
+        assert!(acc(&dir, &["verify", "--hex", "-a", "@acc", "-p", "@non", "--nonce", "n"]));
+
+        let stored = Accumulator::try_from(hex::decode(fs::read_to_string(dir.join("acc")).unwrap().trim()).unwrap()).unwrap();
+        assert_eq!(stored.members.len(), 3);
+        assert!(try_acc(&dir, &["witness", "--hex", "-a", "@acc", "--member", "bob"]).is_err());
+        assert!(try_acc(&dir, &["witness", "--hex", "-a", "@acc", "-k", "@key", "--member", "bob"]).is_err());
+        assert!(try_acc(&dir, &["verify", "--hex", "-a", "@acc", "-p", "@w1", "--nonce", "n"]).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
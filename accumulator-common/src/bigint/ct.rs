@@ -0,0 +1,960 @@
+use super::GcdResult;
+use crate::error::{AccumulatorError, AccumulatorErrorKind};
+use crypto_bigint::{
+    modular::runtime_mod::{DynResidue, DynResidueParams},
+    subtle::{Choice, ConditionallySelectable, ConstantTimeEq},
+    Encoding, Integer, Limb, NonZero, Random, RandomMod, Uint as FixedUint, Zero, U1024, U2048,
+    U4096, U512,
+};
+use rand_core::OsRng;
+use std::{
+    ops::{Add, AddAssign, Sub, SubAssign, Mul, MulAssign, Div, DivAssign, Rem, RemAssign},
+    cmp::Ordering,
+    convert::TryFrom,
+    str::FromStr
+};
+use zeroize::Zeroize;
+
+/// The fixed width used for every value. Moduli must be at most half
+/// of this so products can be reduced without overflowing.
+type Uint = U4096;
+
+/// Primes below this are used to sieve candidates before Miller-Rabin
+const SMALL_PRIMES: [u32; 53] = [
+    3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191,
+    193, 197, 199, 211, 223, 227, 229, 233, 239, 241, 251,
+];
+
+/// Miller-Rabin rounds used by `is_prime`
+const PRIME_ROUNDS: usize = 15;
+
+/// Exponent bits consumed per table lookup in `CtMontContext::multi_exp`
+const MULTI_EXP_WINDOW: usize = 4;
+
+/// Call the const generic `$f` with the smallest width that holds `$bits` bits.
+/// Only bit lengths are used to pick the width so at most they are leaked
+/// through timing, the same as the exponent length in `pow_bounded_exp`.
+macro_rules! sized {
+    ($bits:expr, $f:ident($($arg:expr),*)) => {
+        match $bits {
+            0..=512 => Self::$f::<{ U512::LIMBS }>($($arg),*),
+            513..=1024 => Self::$f::<{ U1024::LIMBS }>($($arg),*),
+            1025..=2048 => Self::$f::<{ U2048::LIMBS }>($($arg),*),
+            _ => Self::$f::<{ Uint::LIMBS }>($($arg),*),
+        }
+    };
+}
+
+/// A Big Integer Implementation backed by crypto-bigint.
+/// Modular arithmetic runs in constant time with respect to the operands,
+/// only their bit lengths may leak. The sign is kept separately and is not treated as secret.
+/// Values are limited to 4096 bits and operations that would exceed it panic.
+#[derive(Debug)]
+pub struct CtBigInt {
+    pub(crate) value: Uint,
+    negative: bool,
+}
+
+impl std::fmt::Display for CtBigInt {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        super::pad_signed(f, "", &self.to_dec_string())
+    }
+}
+
+impl Clone for CtBigInt {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value,
+            negative: self.negative,
+        }
+    }
+}
+
+impl PartialEq for CtBigInt {
+    fn eq(&self, other: &Self) -> bool {
+        (self.value.ct_eq(&other.value) & Choice::from((self.negative == other.negative) as u8)).into()
+    }
+}
+
+impl Eq for CtBigInt {}
+
+impl PartialOrd for CtBigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CtBigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => self.value.cmp(&other.value),
+            (true, true) => other.value.cmp(&self.value),
+        }
+    }
+}
+
+impl Default for CtBigInt {
+    fn default() -> Self {
+        Self { value: Uint::ZERO, negative: false }
+    }
+}
+
+impl CtBigInt {
+    fn new(value: Uint, negative: bool) -> Self {
+        let is_zero: bool = value.is_zero().into();
+        Self { value, negative: negative && !is_zero }
+    }
+
+    fn one() -> Self {
+        Self::new(Uint::ONE, false)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.value.is_zero().into()
+    }
+
+    /// Add two values with signs `a_neg` and `b_neg`
+    fn signed_add(a: &Uint, a_neg: bool, b: &Uint, b_neg: bool) -> Self {
+        if a_neg == b_neg {
+            let (value, carry) = a.adc(b, Limb::ZERO);
+            assert_eq!(carry, Limb::ZERO, "CtBigInt overflow");
+            return Self::new(value, a_neg);
+        }
+        if a >= b {
+            Self::new(a.wrapping_sub(b), a_neg)
+        } else {
+            Self::new(b.wrapping_sub(a), b_neg)
+        }
+    }
+
+    fn nonzero<const LIMBS: usize>(value: &FixedUint<LIMBS>) -> NonZero<FixedUint<LIMBS>> {
+        Option::<NonZero<FixedUint<LIMBS>>>::from(NonZero::new(*value)).expect("division by zero")
+    }
+
+    /// `self mod |modulus|` in the range `[0, |modulus|)`
+    fn reduce(&self, modulus: &Self) -> Uint {
+        let bits = std::cmp::max(self.value.bits_vartime(), modulus.value.bits_vartime());
+        let r = sized!(bits, rem_sized(&self.value, &modulus.value));
+        if self.negative && !bool::from(r.is_zero()) {
+            modulus.value.wrapping_sub(&r)
+        } else {
+            r
+        }
+    }
+
+    fn rem_sized<const LIMBS: usize>(a: &Uint, m: &Uint) -> Uint {
+        let m = Self::nonzero(&m.resize::<LIMBS>());
+        a.resize::<LIMBS>().rem(&m).resize()
+    }
+
+    fn div_rem_sized<const LIMBS: usize>(a: &Uint, m: &Uint) -> (Uint, Uint) {
+        let m = Self::nonzero(&m.resize::<LIMBS>());
+        let (q, r) = a.resize::<LIMBS>().div_rem(&m);
+        (q.resize(), r.resize())
+    }
+
+    fn inv_mod_sized<const LIMBS: usize>(a: &Uint, m: &Uint) -> (Uint, bool) {
+        let (inv, exists) = a.resize::<LIMBS>().inv_mod(&m.resize());
+        (inv.resize(), exists.into())
+    }
+
+    /// `a * b mod m` for `a, b < m`
+    fn mul_reduce(a: &Uint, b: &Uint, m: &Uint) -> Uint {
+        sized!(m.bits_vartime(), mul_reduce_sized(a, b, m))
+    }
+
+    fn mul_reduce_sized<const LIMBS: usize>(a: &Uint, b: &Uint, m: &Uint) -> Uint {
+        let a = a.resize::<LIMBS>();
+        let (lo, hi) = a.mul_wide(&b.resize::<LIMBS>());
+        let (r, _) = FixedUint::<LIMBS>::const_rem_wide((lo, hi), &m.resize());
+        r.resize()
+    }
+
+    /// Square and multiply for even moduli where Montgomery form cannot be used.
+    /// Every bit of the exponent costs the same regardless of its value.
+    fn pow_even_mod(base: &Uint, exponent: &Uint, m: &Uint) -> Uint {
+        let mut result = Uint::ONE.rem(&Self::nonzero(m));
+        let bits = exponent.bits();
+        for i in (0..bits).rev() {
+            result = Self::mul_reduce(&result, &result, m);
+            let product = Self::mul_reduce(&result, base, m);
+            result = Uint::conditional_select(&result, &product, Choice::from(exponent.bit(i)));
+        }
+        result
+    }
+
+    /// Montgomery exponentiation using `LIMBS` limbs instead of the full width.
+    /// `base` and `m` must fit in `LIMBS`
+    fn pow_odd_mod<const LIMBS: usize>(base: &Uint, exponent: &Uint, m: &Uint) -> Uint {
+        let params = DynResidueParams::new(&m.resize::<LIMBS>());
+        DynResidue::new(&base.resize::<LIMBS>(), params)
+            .pow_bounded_exp(exponent, exponent.bits())
+            .retrieve()
+            .resize()
+    }
+
+    fn pow_mod(base: &Uint, exponent: &Uint, m: &Uint) -> Uint {
+        if !bool::from(m.is_odd()) {
+            return Self::pow_even_mod(base, exponent, m);
+        }
+        sized!(m.bits_vartime(), pow_odd_mod(base, exponent, m))
+    }
+
+    fn inverse(&self, modulus: &Self) -> Uint {
+        if modulus.is_zero() || modulus.value == Uint::ONE {
+            panic!("Invalid modulus");
+        }
+        self.checked_inverse(modulus).expect("Not invertible")
+    }
+
+    fn checked_inverse(&self, modulus: &Self) -> Option<Uint> {
+        if modulus.is_zero() || modulus.value == Uint::ONE {
+            return None;
+        }
+        let a = self.reduce(modulus);
+        let (inv, exists) = sized!(modulus.value.bits_vartime(), inv_mod_sized(&a, &modulus.value));
+        if exists {
+            Some(inv)
+        } else {
+            None
+        }
+    }
+
+    fn to_dec_string(&self) -> String {
+        if self.is_zero() {
+            return "0".to_string();
+        }
+        let ten = Option::from(NonZero::new(Limb::from(10u32))).unwrap();
+        let mut digits = Vec::new();
+        let mut v = self.value;
+        while !bool::from(v.is_zero()) {
+            let (q, r) = v.div_rem_limb(ten);
+            digits.push(b'0' + r.0 as u8);
+            v = q;
+        }
+        if self.negative {
+            digits.push(b'-');
+        }
+        digits.reverse();
+        String::from_utf8(digits).unwrap()
+    }
+
+    fn from_dec_str(s: &str) -> Result<Self, AccumulatorError> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(d) => (true, d),
+            None => (false, s),
+        };
+        if digits.is_empty() {
+            return Err(AccumulatorError::from_msg(AccumulatorErrorKind::InvalidType, "empty string"));
+        }
+        let ten = Uint::from_u8(10);
+        let mut value = Uint::ZERO;
+        for c in digits.chars() {
+            let d = c.to_digit(10).ok_or_else(|| {
+                AccumulatorError::from_msg(AccumulatorErrorKind::InvalidType, format!("invalid digit {}", c))
+            })?;
+            let (lo, hi) = value.mul_wide(&ten);
+            let (next, carry) = lo.adc(&Uint::from_u32(d), Limb::ZERO);
+            if !bool::from(hi.is_zero()) || carry != Limb::ZERO {
+                return Err(AccumulatorError::from_msg(AccumulatorErrorKind::InvalidType, "value is too large"));
+            }
+            value = next;
+        }
+        Ok(Self::new(value, negative))
+    }
+
+    /// Used by the std::ops::Add methods
+    fn add_(&self, rhs: &Self) -> Self {
+        Self::signed_add(&self.value, self.negative, &rhs.value, rhs.negative)
+    }
+
+    /// Used by the std::ops::AddAssign methods
+    fn add_assign_(&mut self, rhs: &Self) {
+        *self = self.add_(rhs);
+    }
+
+    /// Used by the std::ops::Sub methods
+    fn sub_(&self, rhs: &Self) -> Self {
+        Self::signed_add(&self.value, self.negative, &rhs.value, !rhs.negative)
+    }
+
+    /// Used by the std::ops::SubAssign methods
+    fn sub_assign_(&mut self, rhs: &Self) {
+        *self = self.sub_(rhs);
+    }
+
+    /// Used by the std::ops::Mul methods
+    fn mul_(&self, rhs: &Self) -> Self {
+        let (lo, hi) = self.value.mul_wide(&rhs.value);
+        assert!(bool::from(hi.is_zero()), "CtBigInt overflow");
+        Self::new(lo, self.negative ^ rhs.negative)
+    }
+
+    /// Used by the std::ops::MulAssign methods
+    fn mul_assign_(&mut self, rhs: &Self) {
+        *self = self.mul_(rhs);
+    }
+
+    /// Used by the std::ops::Div methods
+    fn div_(&self, rhs: &Self) -> Self {
+        self.div_rem(rhs).0
+    }
+
+    /// Used by the std::ops::DivAssign methods
+    fn div_assign_(&mut self, rhs: &Self) {
+        *self = self.div_(rhs);
+    }
+
+    /// Used by the std::ops::Rem methods
+    fn rem_(&self, rhs: &Self) -> Self {
+        Self::new(self.reduce(rhs), false)
+    }
+
+    /// Used by the std::ops::RemAssign methods
+    fn rem_assign_(&mut self, rhs: &Self) {
+        *self = self.rem_(rhs);
+    }
+
+    /// Compute the quotient and remainder and return both
+    pub fn div_rem(&self, rhs: &Self) -> (Self, Self) {
+        let bits = std::cmp::max(self.value.bits_vartime(), rhs.value.bits_vartime());
+        let (q, r) = sized!(bits, div_rem_sized(&self.value, &rhs.value));
+        (Self::new(q, self.negative ^ rhs.negative), Self::new(r, self.negative))
+    }
+
+    /// Compute modular exponentiation and return the result
+    /// result = self ^ rhs mod order
+    pub fn mod_exp(&self, exponent: &Self, modulus: &Self) -> Self {
+        if exponent.is_zero() {
+            return Self::one();
+        }
+        if exponent.value == Uint::ONE && !exponent.negative {
+            return self.clone();
+        }
+        let base = if exponent.negative {
+            self.inverse(modulus)
+        } else {
+            self.reduce(modulus)
+        };
+        Self::new(Self::pow_mod(&base, &exponent.value, &modulus.value), false)
+    }
+
+    /// Compute modular exponentiation and assign it to self
+    /// self = self ^ exponent mod order
+    pub fn mod_exp_assign(&mut self, exponent: &Self, modulus: &Self) {
+        *self = self.mod_exp(exponent, modulus);
+    }
+
+    /// Compute modular square and return the result
+    /// result = self ^ 2 mod order
+    pub fn mod_sqr(&self, modulus: &Self) -> Self {
+        let a = self.reduce(modulus);
+        Self::new(Self::mul_reduce(&a, &a, &modulus.value), false)
+    }
+
+    /// Compute modular exponentiation and assign it to self
+    /// self = self ^ 2 mod order
+    pub fn mod_sqr_assign(&mut self, modulus: &Self) {
+        *self = self.mod_sqr(modulus);
+    }
+
+    /// Compute modular inverse and return the result
+    /// result = self ^ -1 mod order
+    pub fn mod_inverse(&self, modulus: &Self) -> Self {
+        Self::new(self.inverse(modulus), false)
+    }
+
+    /// Compute modular inverse and assign it to self
+    /// self = self ^ -1 mod order
+    pub fn mod_inverse_assign(&mut self, modulus: &Self) {
+        *self = self.mod_inverse(modulus);
+    }
+
+    /// Compute modular inverse and return the result or `None`
+    /// if self has no inverse for the modulus
+    pub fn checked_mod_inverse(&self, modulus: &Self) -> Option<Self> {
+        self.checked_inverse(modulus).map(|v| Self::new(v, false))
+    }
+
+    /// Compute modular multiplication and return the result
+    /// result = self * rhs mod order
+    pub fn mod_mul(&self, rhs: &Self, modulus: &Self) -> Self {
+        let a = self.reduce(modulus);
+        let b = rhs.reduce(modulus);
+        Self::new(Self::mul_reduce(&a, &b, &modulus.value), false)
+    }
+
+    /// Compute modular exponentiation and assign it to self
+    /// self = self * rhs mod order
+    pub fn mod_mul_assign(&mut self, rhs: &Self, modulus: &Self) {
+        *self = self.mod_mul(rhs, modulus);
+    }
+
+    /// A random odd value with exactly `size` bits
+    fn random_odd(size: usize) -> Uint {
+        assert!(size >= 2 && size <= Uint::BITS, "invalid size");
+        let mut value = Uint::random(&mut OsRng);
+        value = value.shr_vartime(Uint::BITS - size);
+        value | Uint::ONE.shl_vartime(size - 1) | Uint::ONE
+    }
+
+    /// True if `n` is divisible by one of the `SMALL_PRIMES` other than itself
+    fn has_small_factor(n: &Uint) -> bool {
+        SMALL_PRIMES.iter().any(|p| {
+            let p = Limb::from(*p);
+            let (_, r) = n.div_rem_limb(Option::from(NonZero::new(p)).unwrap());
+            r == Limb::ZERO && *n != Uint::from(p.0)
+        })
+    }
+
+    /// Miller-Rabin with random bases, `n` must be odd and greater than 3
+    fn miller_rabin(n: &Uint, rounds: usize) -> bool {
+        sized!(n.bits_vartime(), miller_rabin_sized(n, rounds))
+    }
+
+    fn miller_rabin_sized<const LIMBS: usize>(n: &Uint, rounds: usize) -> bool {
+        let n = n.resize::<LIMBS>();
+        let n_1 = n.wrapping_sub(&FixedUint::<LIMBS>::ONE);
+        let s = n_1.trailing_zeros();
+        let d = n_1.shr_vartime(s);
+        let params = DynResidueParams::new(&n);
+        let one = DynResidue::one(params);
+        let minus_one = DynResidue::new(&n_1, params);
+        let bound = Self::nonzero(&n.wrapping_sub(&FixedUint::<LIMBS>::from_u8(3)));
+
+        for _ in 0..rounds {
+            // a in [2, n - 2]
+            let a = FixedUint::<LIMBS>::random_mod(&mut OsRng, &bound).wrapping_add(&FixedUint::<LIMBS>::from_u8(2));
+            let mut x = DynResidue::new(&a, params).pow_bounded_exp(&d, d.bits());
+            if x == one || x == minus_one {
+                continue;
+            }
+            let mut composite = true;
+            for _ in 1..s {
+                x = x.square();
+                if x == minus_one {
+                    composite = false;
+                    break;
+                }
+            }
+            if composite {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn check_prime(n: &Uint, rounds: usize) -> bool {
+        if *n < Uint::from_u8(4) {
+            return *n > Uint::ONE;
+        }
+        if !bool::from(n.is_odd()) || Self::has_small_factor(n) {
+            return false;
+        }
+        Self::miller_rabin(n, rounds)
+    }
+
+    /// Generate a prime number of `size` bits
+    pub fn generate_prime(size: usize) -> Self {
+        loop {
+            let value = Self::random_odd(size);
+            if Self::check_prime(&value, PRIME_ROUNDS) {
+                return Self::new(value, false);
+            }
+        }
+    }
+
+    /// Generate a safe prime number of `size` bits
+    pub fn generate_safe_prime(size: usize) -> Self {
+        loop {
+            let q = Self::random_odd(size - 1);
+            let p = q.shl_vartime(1) | Uint::ONE;
+            // Cheap tests on both before the expensive ones
+            if Self::has_small_factor(&q) || Self::has_small_factor(&p) {
+                continue;
+            }
+            if Self::miller_rabin(&q, 1) && Self::miller_rabin(&p, 1) &&
+                Self::check_prime(&q, PRIME_ROUNDS) && Self::check_prime(&p, PRIME_ROUNDS) {
+                return Self::new(p, false);
+            }
+        }
+    }
+
+    /// Generate a random value less than `self`
+    pub fn rand_range(&self) -> Self {
+        Self::new(Uint::random_mod(&mut OsRng, &Self::nonzero(&self.value)), false)
+    }
+
+    /// Determine if `self` is a prime number
+    pub fn is_prime(&self) -> bool {
+        self.is_prime_rounds(PRIME_ROUNDS)
+    }
+
+    /// Determine if `self` is a prime number with `rounds` of Miller-Rabin
+    pub fn is_prime_rounds(&self, rounds: usize) -> bool {
+        if self.negative {
+            return false;
+        }
+        Self::check_prime(&self.value, rounds)
+    }
+
+    /// Computes Bézout's coefficients and returns `s` and `t`
+    /// using the extended euclidean algorithm
+    pub fn bezouts_coefficients(&self, rhs: &Self) -> GcdResult {
+        if self.is_zero() && rhs.is_zero() {
+            return GcdResult {
+                value: Self::default(),
+                a: Self::default(),
+                b: Self::default()
+            };
+        }
+        if self.is_zero() {
+            return GcdResult {
+                value: Self::default(),
+                a: Self::default(),
+                b: Self::one(),
+            };
+        }
+        if rhs.is_zero() {
+            return GcdResult {
+                value: Self::default(),
+                a: Self::one(),
+                b: Self::default()
+            };
+        }
+
+        let mut s = Self::default();
+        let mut old_s = Self::one();
+        let mut t = Self::one();
+        let mut old_t = Self::default();
+        let mut r = rhs.clone();
+        let mut old_r = self.clone();
+
+        while !r.is_zero() {
+            let q = &old_r / &r;
+
+            let temp_r = old_r.clone();
+            old_r = r.clone();
+            r = &temp_r - &(&q * &r);
+
+            let temp_s = old_s.clone();
+            old_s = s.clone();
+            s = &temp_s - &(&q * &s);
+
+            let temp_t = old_t.clone();
+            old_t = t.clone();
+            t = &temp_t - &(&q * &t);
+        }
+
+        GcdResult {
+            value: old_r,
+            a: old_s,
+            b: old_t
+        }
+    }
+
+    /// The number of bits needed to represent `self`
+    pub fn bits(&self) -> usize {
+        self.value.bits()
+    }
+
+    /// Serialize to big-endian byte array
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let bytes = self.value.to_be_bytes();
+        let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+        bytes[start..].to_vec()
+    }
+}
+
+/// Montgomery parameters for one modulus at the width it needs
+#[derive(Clone, Copy, Debug)]
+enum MontParams {
+    W512(DynResidueParams<{ U512::LIMBS }>),
+    W1024(DynResidueParams<{ U1024::LIMBS }>),
+    W2048(DynResidueParams<{ U2048::LIMBS }>),
+    W4096(DynResidueParams<{ Uint::LIMBS }>),
+}
+
+/// Call the const generic `$f` with the parameters of the matching width
+macro_rules! with_params {
+    ($params:expr, $f:ident($($arg:expr),*)) => {
+        match $params {
+            MontParams::W512(p) => CtMontContext::$f(p, $($arg),*),
+            MontParams::W1024(p) => CtMontContext::$f(p, $($arg),*),
+            MontParams::W2048(p) => CtMontContext::$f(p, $($arg),*),
+            MontParams::W4096(p) => CtMontContext::$f(p, $($arg),*),
+        }
+    };
+}
+
+/// Repeated operations against one modulus.
+/// Odd moduli keep their Montgomery parameters so each multiplication
+/// is a Montgomery product instead of a wide division.
+#[derive(Debug)]
+pub struct CtMontContext {
+    modulus: CtBigInt,
+    params: Option<MontParams>,
+}
+
+impl CtMontContext {
+    /// Create a context for `modulus`
+    pub fn new(modulus: &CtBigInt) -> Self {
+        let m = &modulus.value;
+        let params = if bool::from(m.is_odd()) {
+            Some(match m.bits_vartime() {
+                0..=512 => MontParams::W512(DynResidueParams::new(&m.resize())),
+                513..=1024 => MontParams::W1024(DynResidueParams::new(&m.resize())),
+                1025..=2048 => MontParams::W2048(DynResidueParams::new(&m.resize())),
+                _ => MontParams::W4096(DynResidueParams::new(m)),
+            })
+        } else {
+            None
+        };
+        Self { modulus: modulus.clone(), params }
+    }
+
+    /// base ^ exponent mod modulus
+    pub fn exp(&self, base: &CtBigInt, exponent: &CtBigInt) -> CtBigInt {
+        let params = match self.params {
+            Some(p) => p,
+            None => return base.mod_exp(exponent, &self.modulus),
+        };
+        if exponent.is_zero() {
+            return CtBigInt::one();
+        }
+        if exponent.value == Uint::ONE && !exponent.negative {
+            return base.clone();
+        }
+        let base = if exponent.negative {
+            base.inverse(&self.modulus)
+        } else {
+            self.reduce(base)
+        };
+        CtBigInt::new(with_params!(params, pow_sized(&base, &exponent.value)), false)
+    }
+
+    /// a * b mod modulus
+    pub fn mul(&self, a: &CtBigInt, b: &CtBigInt) -> CtBigInt {
+        let params = match self.params {
+            Some(p) => p,
+            None => return a.mod_mul(b, &self.modulus),
+        };
+        let (a, b) = (self.reduce(a), self.reduce(b));
+        CtBigInt::new(with_params!(params, mul_sized(&a, &b)), false)
+    }
+
+    /// b_1^e_1 * b_2^e_2 * ... mod modulus.
+    /// Interleaved fixed window exponentiation: all bases share one chain
+    /// of squarings and table lookups scan every entry.
+    pub fn multi_exp(&self, pairs: &[(&CtBigInt, &CtBigInt)]) -> CtBigInt {
+        let one = self.mul(&CtBigInt::one(), &CtBigInt::one());
+        let params = match self.params {
+            Some(p) => p,
+            None => return pairs.iter().fold(one, |acc, (b, e)| self.mul(&acc, &self.exp(b, e))),
+        };
+        let mut bases = Vec::with_capacity(pairs.len());
+        let mut exponents = Vec::with_capacity(pairs.len());
+        for (b, e) in pairs {
+            bases.push(if e.negative { b.inverse(&self.modulus) } else { self.reduce(b) });
+            exponents.push(e.value);
+        }
+        let value = with_params!(params, multi_exp_sized(&bases, &exponents));
+        CtBigInt::new(value, false)
+    }
+
+    fn multi_exp_sized<const LIMBS: usize>(
+        params: DynResidueParams<LIMBS>,
+        bases: &[Uint],
+        exponents: &[Uint],
+    ) -> Uint {
+        let tables: Vec<Vec<DynResidue<LIMBS>>> = bases
+            .iter()
+            .map(|b| {
+                let b = DynResidue::new(&b.resize::<LIMBS>(), params);
+                let mut t = vec![DynResidue::one(params); 1 << MULTI_EXP_WINDOW];
+                for i in 1..t.len() {
+                    t[i] = t[i - 1] * b;
+                }
+                t
+            })
+            .collect();
+        let lengths: Vec<usize> = exponents.iter().map(|e| e.bits_vartime()).collect();
+        let windows = (lengths.iter().max().copied().unwrap_or(0) + MULTI_EXP_WINDOW - 1) / MULTI_EXP_WINDOW;
+
+        let mut acc = DynResidue::one(params);
+        for w in (0..windows).rev() {
+            for _ in 0..MULTI_EXP_WINDOW {
+                acc = acc.square();
+            }
+            for ((table, e), len) in tables.iter().zip(exponents).zip(&lengths) {
+                if *len <= w * MULTI_EXP_WINDOW {
+                    continue;
+                }
+                let mut index = 0u32;
+                for k in 0..MULTI_EXP_WINDOW {
+                    let bit: Choice = e.bit(w * MULTI_EXP_WINDOW + k).into();
+                    index |= (bit.unwrap_u8() as u32) << k;
+                }
+                let mut factor = table[0];
+                for (i, t) in table.iter().enumerate() {
+                    factor.conditional_assign(t, (i as u32).ct_eq(&index));
+                }
+                acc *= factor;
+            }
+        }
+        acc.retrieve().resize()
+    }
+
+    /// Values already below the modulus bit length need at most one subtraction
+    fn reduce(&self, a: &CtBigInt) -> Uint {
+        let m = &self.modulus.value;
+        if a.negative || a.value.bits_vartime() > m.bits_vartime() {
+            return a.reduce(&self.modulus);
+        }
+        let (diff, borrow) = a.value.sbb(m, Limb::ZERO);
+        Uint::conditional_select(&diff, &a.value, Choice::from((borrow.0 & 1) as u8))
+    }
+
+    fn pow_sized<const LIMBS: usize>(
+        params: DynResidueParams<LIMBS>,
+        base: &Uint,
+        exponent: &Uint,
+    ) -> Uint {
+        DynResidue::new(&base.resize::<LIMBS>(), params)
+            .pow_bounded_exp(exponent, exponent.bits())
+            .retrieve()
+            .resize()
+    }
+
+    fn mul_sized<const LIMBS: usize>(params: DynResidueParams<LIMBS>, a: &Uint, b: &Uint) -> Uint {
+        let a = DynResidue::new(&a.resize::<LIMBS>(), params);
+        let b = DynResidue::new(&b.resize::<LIMBS>(), params);
+        (a * b).retrieve().resize()
+    }
+}
+
+impl std::iter::Product<CtBigInt> for CtBigInt {
+    fn product<I: Iterator<Item=CtBigInt>>(iter: I) -> Self {
+        let mut value = CtBigInt::one();
+        for i in iter {
+            value *= &i;
+        }
+        value
+    }
+}
+
+impl<'a> std::iter::Product<&'a CtBigInt> for CtBigInt {
+    fn product<I: Iterator<Item=&'a CtBigInt>>(iter: I) -> Self {
+        let mut value = CtBigInt::one();
+        for i in iter {
+            value *= i;
+        }
+        value
+    }
+}
+
+impl std::iter::Sum<CtBigInt> for CtBigInt {
+    fn sum<I: Iterator<Item=CtBigInt>>(iter: I) -> Self {
+        let mut value = CtBigInt::default();
+        for i in iter {
+            value += &i;
+        }
+        value
+    }
+}
+
+impl<'a> std::iter::Sum<&'a CtBigInt> for CtBigInt {
+    fn sum<I: Iterator<Item=&'a CtBigInt>>(iter: I) -> Self {
+        let mut value = CtBigInt::default();
+        for i in iter {
+            value += i;
+        }
+        value
+    }
+}
+
+impl Zeroize for CtBigInt {
+    fn zeroize(&mut self) {
+        self.value.zeroize();
+        self.negative = false;
+    }
+}
+
+impl TryFrom<&[u8]> for CtBigInt {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        if data.len() > Uint::BYTES {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidType,
+                format!("value is larger than {} bytes", Uint::BYTES),
+            ));
+        }
+        let mut bytes = vec![0u8; Uint::BYTES];
+        bytes[(Uint::BYTES - data.len())..].copy_from_slice(data);
+        let value = Uint::from_be_slice(&bytes);
+        bytes.zeroize();
+        Ok(Self::new(value, false))
+    }
+}
+
+impl FromStr for CtBigInt {
+    type Err = AccumulatorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_dec_str(s)
+    }
+}
+
+macro_rules! from_impl {
+    ($ty:ty, $uty:ty) => {
+        impl From<$ty> for CtBigInt {
+            fn from(value: $ty) -> Self {
+                #[allow(unused_comparisons)]
+                let negative = value < 0;
+                let magnitude = if negative { (0 as $uty).wrapping_sub(value as $uty) } else { value as $uty };
+                Self::new(Uint::from_u64(magnitude as u64), negative)
+            }
+        }
+    };
+}
+macro_rules! ops_impl {
+    ($t:ident, $ts:ident, $f:ident, $fs:ident,$i:ident, $is:ident) => {
+        impl $t for CtBigInt {
+            type Output = CtBigInt;
+
+            fn $f(self, rhs: Self::Output) -> Self::Output {
+                self.$i(&rhs)
+            }
+        }
+
+        impl<'a, 'b> $t<&'b CtBigInt> for &'a CtBigInt {
+            type Output = CtBigInt;
+
+            fn $f(self, rhs: &'b Self::Output) -> Self::Output {
+                self.$i(rhs)
+            }
+        }
+
+        impl $ts for CtBigInt {
+            fn $fs(&mut self, rhs: CtBigInt) {
+                self.$is(&rhs)
+            }
+        }
+
+        impl $ts<&CtBigInt> for CtBigInt {
+            fn $fs(&mut self, rhs: &CtBigInt) {
+                self.$is(rhs)
+            }
+        }
+    };
+}
+
+impl From<Vec<u8>> for CtBigInt {
+    fn from(value: Vec<u8>) -> Self {
+        Self::try_from(value.as_slice()).unwrap()
+    }
+}
+
+impl Into<Vec<u8>> for CtBigInt {
+    fn into(self) -> Vec<u8> {
+        self.to_bytes()
+    }
+}
+
+impl From<&str> for CtBigInt {
+    fn from(value: &str) -> Self {
+        Self::from_dec_str(value).unwrap()
+    }
+}
+
+from_impl!(u64, u64);
+from_impl!(u32, u32);
+from_impl!(u16, u16);
+from_impl!(u8, u8);
+from_impl!(i64, u64);
+from_impl!(i32, u32);
+from_impl!(i16, u16);
+from_impl!(i8, u8);
+
+ops_impl!(Add, AddAssign, add, add_assign, add_, add_assign_);
+ops_impl!(Sub, SubAssign, sub, sub_assign, sub_, sub_assign_);
+ops_impl!(Mul, MulAssign, mul, mul_assign, mul_, mul_assign_);
+ops_impl!(Div, DivAssign, div, div_assign, div_, div_assign_);
+ops_impl!(Rem, RemAssign, rem, rem_assign, rem_, rem_assign_);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bigint::BigInteger;
+
+    #[test]
+    fn test_bezouts_coefficients() {
+        let a = CtBigInt::from(31);
+        let b = CtBigInt::from(37);
+        let gcdres = a.bezouts_coefficients(&b);
+        assert_eq!(gcdres.a, CtBigInt::from(6));
+        assert_eq!(gcdres.b, CtBigInt::from(-5));
+
+        let a = CtBigInt::from("63156515965705215668198135979702445890399855958342988288023717346298762458519");
+        let b = CtBigInt::from("88222503113609549383110571557868679926843894352175049520163164425194315455087");
+        let gcdres = a.bezouts_coefficients(&b);
+        assert_eq!(&a * &gcdres.a + &b * &gcdres.b, CtBigInt::from(1));
+    }
+
+    #[test]
+    fn test_signed_arithmetic() {
+        let a = CtBigInt::from(-7);
+        let b = CtBigInt::from(3);
+        assert_eq!(&a + &b, CtBigInt::from(-4));
+        assert_eq!(&b - &a, CtBigInt::from(10));
+        assert_eq!(&a * &b, CtBigInt::from(-21));
+        assert_eq!(&a / &b, CtBigInt::from(-2));
+        assert_eq!(&a % &b, CtBigInt::from(2));
+        assert!(a < b);
+        assert_eq!(CtBigInt::from("-1234567890123456789012345").to_string(), "-1234567890123456789012345");
+    }
+
+    #[test]
+    fn test_mod_exp() {
+        let n = CtBigInt::from(3233u32);
+        let m = CtBigInt::from(65u32);
+        let c = m.mod_exp(&CtBigInt::from(17u32), &n);
+        assert_eq!(c, CtBigInt::from(2790u32));
+        assert_eq!(c.mod_exp(&CtBigInt::from(413u32), &n), m);
+        // negative exponents invert first
+        let inv = m.mod_exp(&CtBigInt::from(-1), &n);
+        assert_eq!(inv.mod_mul(&m, &n), CtBigInt::from(1u32));
+        // even moduli
+        assert_eq!(CtBigInt::from(3u32).mod_exp(&CtBigInt::from(5u32), &CtBigInt::from(100u32)), CtBigInt::from(43u32));
+    }
+
+    #[test]
+    fn test_primes() {
+        let p = CtBigInt::generate_prime(128);
+        assert_eq!(p.bits(), 128);
+        assert!(p.is_prime());
+        assert!(!(&p * &CtBigInt::from(3u32)).is_prime());
+        let p = CtBigInt::generate_safe_prime(64);
+        assert_eq!(p.bits(), 64);
+        assert!(((&p - &CtBigInt::from(1u32)) / CtBigInt::from(2u32)).is_prime());
+    }
+
+    #[test]
+    fn test_bytes() {
+        let a = CtBigInt::from(65537u32);
+        assert_eq!(a.to_bytes(), vec![1, 0, 1]);
+        assert_eq!(CtBigInt::try_from(a.to_bytes().as_slice()).unwrap(), a);
+        assert_eq!(CtBigInt::default().to_bytes(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_product() {
+        let values = vec![BigInteger::from(2u32), BigInteger::from(3u32)];
+        let res: BigInteger = values.iter().product();
+        assert_eq!(res, BigInteger::from(6u32));
+    }
+}
@@ -1,9 +1,20 @@
+use crate::error::{AccumulatorError, AccumulatorErrorKind};
+#[cfg(not(feature = "std"))]
+use alloc::{format, string::{String, ToString}, vec, vec::Vec};
+use core::convert::TryFrom;
+
 /// Use Big Integer implementation backed by OpenSSL BigNum
 #[cfg(feature = "openssl")]
 pub mod ossl;
 /// Use Big Integer implementation backed by GMP Mpz
 #[cfg(feature = "rust-gmp")]
 pub mod mpz;
+/// Use Big Integer implementation backed by GMP through rug
+#[cfg(feature = "rug")]
+pub mod rug;
+/// Use constant time Big Integer implementation backed by crypto-bigint
+#[cfg(feature = "crypto-bigint")]
+pub mod ct;
 /// Use Big Integer implementation backed by rust's num-bigint
 #[cfg(feature = "bi-rust")]
 pub mod rust;
@@ -19,10 +30,623 @@ pub struct GcdResult {
     pub b: BigInteger
 }
 
+/// Write a number given as a decimal or hex string that may start with `-`
+pub(crate) fn pad_signed(f: &mut core::fmt::Formatter, prefix: &str, s: &str) -> core::fmt::Result {
+    match s.strip_prefix('-') {
+        Some(digits) => f.pad_integral(false, prefix, digits),
+        None => f.pad_integral(true, prefix, s),
+    }
+}
+
+impl BigInteger {
+    /// Parse a big-endian hex string with an optional leading `-`
+    pub fn from_hex(s: &str) -> Result<Self, AccumulatorError> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(d) => (true, d),
+            None => (false, s),
+        };
+        if digits.is_empty() || !digits.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidType,
+                format!("invalid hex string: {}", s),
+            ));
+        }
+        let padded = if digits.len() % 2 == 1 {
+            format!("0{}", digits)
+        } else {
+            digits.to_string()
+        };
+        let bytes: Vec<u8> = (0..padded.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&padded[i..i + 2], 16).unwrap())
+            .collect();
+        let value = Self::try_from(bytes.as_slice())?;
+        if negative {
+            Ok(&Self::default() - &value)
+        } else {
+            Ok(value)
+        }
+    }
+
+    /// Lower case big-endian hex with a leading `-` when negative
+    pub fn to_hex(&self) -> String {
+        format!("{:x}", self)
+    }
+}
+
+impl core::fmt::LowerHex for BigInteger {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let bytes = self.to_bytes();
+        let mut digits: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        let trimmed = digits.trim_start_matches('0').len();
+        digits.drain(..digits.len() - trimmed);
+        if digits.is_empty() {
+            digits.push('0');
+        }
+        if self < &Self::default() {
+            digits.insert(0, '-');
+        }
+        pad_signed(f, "0x", &digits)
+    }
+}
+
+/// How a number is tested for primality
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PrimalityTest {
+    /// Miller-Rabin with this many random bases, each lets a composite
+    /// through with probability at most 1/4
+    MillerRabin(usize),
+    /// Baillie-PSW, a strong probable prime test to base 2 then an extra
+    /// strong Lucas test. Deterministic, no composite is known to pass it
+    /// and it gives the same answer with every backend
+    BailliePsw,
+}
+
+impl Default for PrimalityTest {
+    /// The test `is_prime` uses
+    fn default() -> Self {
+        PrimalityTest::MillerRabin(15)
+    }
+}
+
+/// The primes below 100, for trial division before the expensive tests
+const TRIAL_PRIMES: [u32; 25] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+];
+
+impl BigInteger {
+    /// Determine if `self` is a prime number with `test`
+    pub fn is_prime_with(&self, test: PrimalityTest) -> bool {
+        match test {
+            PrimalityTest::MillerRabin(rounds) => self.is_prime_rounds(rounds),
+            PrimalityTest::BailliePsw => baillie_psw(self),
+        }
+    }
+}
+
+pub(super) fn baillie_psw(n: &BigInteger) -> bool {
+    if n < &BigInteger::from(2u32) {
+        return false;
+    }
+    for p in TRIAL_PRIMES.iter().map(|p| BigInteger::from(*p)) {
+        if n == &p {
+            return true;
+        }
+        if n % &p == BigInteger::default() {
+            return false;
+        }
+    }
+    strong_probable_prime(n, &BigInteger::from(2u32)) && extra_strong_lucas(n)
+}
+
+/// `m = 2^s * d` with `d` odd, returns `(d, s)`
+fn split_twos(m: &BigInteger) -> (BigInteger, usize) {
+    let two = BigInteger::from(2u32);
+    let mut d = m.clone();
+    let mut s = 0;
+    while &d % &two == BigInteger::default() {
+        d = &d / &two;
+        s += 1;
+    }
+    (d, s)
+}
+
+/// One round of Miller-Rabin with base `a`
+fn strong_probable_prime(n: &BigInteger, a: &BigInteger) -> bool {
+    let one = BigInteger::from(1u32);
+    let n_1 = n - &one;
+    let (d, s) = split_twos(&n_1);
+    let mut x = a.mod_exp(&d, n);
+    if x == one || x == n_1 {
+        return true;
+    }
+    for _ in 1..s {
+        x = x.mod_sqr(n);
+        if x == n_1 {
+            return true;
+        }
+    }
+    false
+}
+
+/// The extra strong Lucas test with `Q = 1` and the first `P` from 3 up
+/// where `P^2 - 4` is not a square mod `n`, as in section 2.3 of
+/// <https://www.ams.org/journals/mcom/2001-70-234/S0025-5718-00-01197-2/>.
+/// `n` is odd and has no factor below 100
+fn extra_strong_lucas(n: &BigInteger) -> bool {
+    // No such `P` exists for squares
+    if is_square(n) {
+        return false;
+    }
+    let field = crate::Field::new(n);
+    let mut p = 3u32;
+    loop {
+        match field.jacobi(&BigInteger::from(p * p - 4)) {
+            -1 => break,
+            // (P - 2)(P + 2) shares a factor with n
+            0 => return n == &BigInteger::from(p + 2),
+            _ => p += 1,
+        }
+    }
+    let zero = BigInteger::default();
+    let two = BigInteger::from(2u32);
+    let p = BigInteger::from(p);
+    let n_2 = n - &two;
+    let (t, s) = split_twos(&(n + &BigInteger::from(1u32)));
+
+    // V_k and V_(k+1) for the bits of t from the top,
+    // V_2k = V_k^2 - 2 and V_(2k+1) = V_k V_(k+1) - P
+    let (mut v, mut v1) = (two.clone(), p.clone());
+    for byte in t.to_bytes() {
+        for i in (0..8).rev() {
+            let odd = &(&(&v * &v1) - &p) % n;
+            if (byte >> i) & 1 == 1 {
+                v = odd;
+                v1 = &(&(&v1 * &v1) - &two) % n;
+            } else {
+                v1 = odd;
+                v = &(&(&v * &v) - &two) % n;
+            }
+        }
+    }
+    // U_t = (P V_t - 2 V_(t+1)) / D
+    if (v == two || v == n_2) && &(&(&p * &v) - &(&two * &v1)) % n == zero {
+        return true;
+    }
+    for _ in 1..s {
+        if v == zero {
+            return true;
+        }
+        if v == two {
+            return false;
+        }
+        v = &(&(&v * &v) - &two) % n;
+    }
+    false
+}
+
+/// True if `n` is a perfect square, with Newton's method from above
+fn is_square(n: &BigInteger) -> bool {
+    let two = BigInteger::from(2u32);
+    let mut x = BigInteger::from(1u32);
+    for _ in 0..n.bits().div_ceil(2) {
+        x = &x * &two;
+    }
+    loop {
+        let y = &(&x + &(n / &x)) / &two;
+        if y >= x {
+            return &x * &x == *n;
+        }
+        x = y;
+    }
+}
+
+/// Exponent bits consumed per table lookup in `multi_exp`
+#[cfg(not(feature = "crypto-bigint"))]
+const MULTI_EXP_WINDOW: usize = 4;
+
+/// b_1^e_1 * b_2^e_2 * ... mod `modulus` with Straus' method: all bases
+/// share one chain of squarings, each adds a multiplication from its table
+/// of small powers per window of exponent bits. `into` maps a base reduced
+/// to `[0, modulus)` to the representation `mul` works in, `one` is one in
+/// that representation and the result is left in it
+#[cfg(not(feature = "crypto-bigint"))]
+pub(crate) fn multi_exp<T, I, M>(
+    pairs: &[(&BigInteger, &BigInteger)],
+    modulus: &BigInteger,
+    one: T,
+    into: I,
+    mul: M,
+) -> T
+where
+    T: Clone,
+    I: Fn(&BigInteger) -> T,
+    M: Fn(&T, &T) -> T,
+{
+    let zero = BigInteger::default();
+    let minus_one = &zero - &BigInteger::from(1u32);
+    let mut tables = Vec::with_capacity(pairs.len());
+    let mut exponents = Vec::with_capacity(pairs.len());
+    for &(b, e) in pairs {
+        // A negative exponent raises the inverse
+        let (b, e) = if e < &zero {
+            (b.mod_exp(&minus_one, modulus), &zero - e)
+        } else {
+            (b % modulus, e.clone())
+        };
+        let b = into(&if b < zero { &b + modulus } else { b });
+        // table[i] = b^i
+        let mut table = vec![one.clone(); 1 << MULTI_EXP_WINDOW];
+        for i in 1..table.len() {
+            table[i] = mul(&table[i - 1], &b);
+        }
+        tables.push(table);
+        exponents.push(e.to_bytes());
+    }
+    let windows = exponents.iter().map(|e| e.len() * 8 / MULTI_EXP_WINDOW).max().unwrap_or(0);
+    let digit = |e: &[u8], w: usize| match e.len().checked_sub(1 + w / 2) {
+        Some(i) => ((e[i] >> (MULTI_EXP_WINDOW * (w % 2))) & 0xF) as usize,
+        None => 0,
+    };
+
+    let mut acc = one;
+    for w in (0..windows).rev() {
+        if w + 1 < windows {
+            for _ in 0..MULTI_EXP_WINDOW {
+                acc = mul(&acc, &acc);
+            }
+        }
+        for (table, e) in tables.iter().zip(&exponents) {
+            let d = digit(e, w);
+            if d != 0 {
+                acc = mul(&acc, &table[d]);
+            }
+        }
+    }
+    acc
+}
+
+/// Human readable formats get the hex string from `to_hex`, others get
+/// a sign byte, one when negative, followed by the big-endian magnitude
+impl serde::Serialize for BigInteger {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        if serializer.is_human_readable() {
+            return serializer.serialize_str(&self.to_hex());
+        }
+        let magnitude = self.to_bytes();
+        let mut bytes = Vec::with_capacity(magnitude.len() + 1);
+        bytes.push((self < &Self::default()) as u8);
+        bytes.extend_from_slice(&magnitude);
+        serializer.serialize_bytes(&bytes)
+    }
+}
+
+impl<'a> serde::Deserialize<'a> for BigInteger {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'a>,
+    {
+        struct DeserializeVisitor;
+
+        impl<'a> serde::de::Visitor<'a> for DeserializeVisitor {
+            type Value = BigInteger;
+
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+                formatter.write_str("expected hex string or sign and magnitude bytes")
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<BigInteger, E>
+            where
+                E: serde::de::Error,
+            {
+                BigInteger::from_hex(value).map_err(|_| {
+                    serde::de::Error::invalid_value(serde::de::Unexpected::Str(value), &self)
+                })
+            }
+
+            fn visit_bytes<E>(self, value: &[u8]) -> Result<BigInteger, E>
+            where
+                E: serde::de::Error,
+            {
+                let invalid = || serde::de::Error::invalid_value(serde::de::Unexpected::Bytes(value), &self);
+                let (sign, magnitude) = value.split_first().ok_or_else(invalid)?;
+                let magnitude = BigInteger::try_from(magnitude).map_err(|_| invalid())?;
+                match sign {
+                    0 => Ok(magnitude),
+                    1 => Ok(&BigInteger::default() - &magnitude),
+                    _ => Err(invalid()),
+                }
+            }
+
+            fn visit_seq<A>(self, seq: A) -> Result<BigInteger, A::Error>
+            where
+                A: serde::de::SeqAccess<'a>,
+            {
+                self.visit_bytes(&crate::macros::read_seq(seq)?)
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(DeserializeVisitor)
+        } else {
+            deserializer.deserialize_byte_buf(DeserializeVisitor)
+        }
+    }
+}
+
 #[cfg(feature = "openssl")]
-pub use ossl::OsslBigInt as BigInteger;
+pub use ossl::{OsslBigInt as BigInteger, OsslModulusContext as ModulusContext};
 #[cfg(feature = "rust-gmp")]
-pub use mpz::MpzBigInt as BigInteger;
+pub use mpz::{MpzBigInt as BigInteger, MpzModulusContext as ModulusContext};
+#[cfg(feature = "rug")]
+pub use self::rug::{RugBigInt as BigInteger, RugModulusContext as ModulusContext};
+#[cfg(feature = "crypto-bigint")]
+pub use ct::{CtBigInt as BigInteger, CtMontContext as ModulusContext};
 #[cfg(feature = "bi-rust")]
-pub use rust::RustBigInt as BigInteger;
+pub use rust::{RustBigInt as BigInteger, RustModulusContext as ModulusContext};
+
+
+/// Known answers every backend must reproduce exactly so accumulators
+/// and proofs serialize the same regardless of the library chosen.
+/// Expected values follow the OpenSSL semantics.
+#[cfg(test)]
+mod conformance {
+    use super::*;
+    use std::convert::TryFrom;
+
+    fn bi(s: &str) -> BigInteger {
+        BigInteger::from(s)
+    }
+
+    #[test]
+    fn remainders() {
+        // `%` is never negative, `div_rem` truncates
+        assert_eq!(&bi("-7") % &bi("5"), bi("3"));
+        assert_eq!(&bi("7") % &bi("5"), bi("2"));
+        let (q, r) = bi("-7").div_rem(&bi("5"));
+        assert_eq!(q, bi("-1"));
+        assert_eq!(r, bi("-2"));
+        assert_eq!(&bi("-7") / &bi("5"), bi("-1"));
+        // Copies keep the sign
+        assert_eq!(bi("-7").clone(), bi("-7"));
+        assert!(bi("-7").clone() < BigInteger::default());
+    }
+
+    #[test]
+    fn modular_arithmetic() {
+        let m = bi("497");
+        assert_eq!(bi("4").mod_exp(&bi("13"), &m), bi("445"));
+        assert_eq!(bi("3").mod_exp(&bi("-1"), &bi("7")), bi("5"));
+        assert_eq!(bi("3").mod_exp(&bi("-2"), &bi("7")), bi("4"));
+        assert_eq!(bi("10").mod_exp(&bi("0"), &bi("7")), bi("1"));
+        // An exponent of one is not reduced
+        assert_eq!(bi("10").mod_exp(&bi("1"), &bi("7")), bi("10"));
+        assert_eq!(bi("-3").mod_mul(&bi("4"), &bi("7")), bi("2"));
+        assert_eq!(bi("-3").mod_sqr(&bi("7")), bi("2"));
+        assert_eq!(bi("3").mod_inverse(&bi("7")), bi("5"));
+        assert_eq!(bi("-3").mod_inverse(&bi("7")), bi("2"));
+
+        let mut a = bi("4");
+        a.mod_exp_assign(&bi("13"), &m);
+        assert_eq!(a, bi("445"));
+        a.mod_exp_assign(&bi("0"), &m);
+        assert_eq!(a, bi("1"));
+    }
+
+    #[test]
+    fn field_matches_modular_arithmetic() {
+        let values = [bi("0"), bi("1"), bi("-3"), bi("12345678901234567890"), bi("98765432109876543210987")];
+        for m in &[bi("1000000007"), bi("340282366920938463463374607431768211507"), bi("1000000006")] {
+            let f = crate::Field::new(m);
+            for a in &values {
+                for b in &values {
+                    assert_eq!(f.mul(a, b), a.mod_mul(b, m));
+                }
+                for e in &[bi("0"), bi("1"), bi("2"), bi("65537"), bi("-1")] {
+                    if e < &bi("0") && a.bezouts_coefficients(m).value != bi("1") {
+                        continue;
+                    }
+                    assert_eq!(f.exp(a, e), a.mod_exp(e, m));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn inv_batch() {
+        let m = bi("340282366920938463463374607431768211507");
+        let f = crate::Field::new(&m);
+        let values = [bi("3"), bi("12345678901234567890"), bi("-5"), bi("1"), bi("98765432109876543210987")];
+        let expected: Vec<BigInteger> = values.iter().map(|v| f.inv(v).unwrap()).collect();
+        assert_eq!(f.inv_batch(&values).unwrap(), expected);
+        assert_eq!(f.inv_batch(&values[..1]).unwrap(), expected[..1].to_vec());
+        assert!(f.inv_batch(&[]).unwrap().is_empty());
+    }
 
+    #[test]
+    fn not_invertible() {
+        let m = bi("1000000006");
+        let f = crate::Field::new(&m);
+        assert_eq!(f.inv(&bi("0")).unwrap_err().kind(), AccumulatorErrorKind::NotInvertible);
+        assert_eq!(f.inv(&bi("4")).unwrap_err().kind(), AccumulatorErrorKind::NotInvertible);
+        assert!(f.inv_batch(&[bi("3"), bi("2"), bi("5")]).is_err());
+        assert_eq!(bi("2").checked_mod_inverse(&m), None);
+        assert_eq!(bi("3").checked_mod_inverse(&bi("7")), Some(bi("5")));
+        assert_eq!(bi("3").checked_mod_inverse(&bi("1")), None);
+    }
+
+    #[test]
+    fn quadratic_residues() {
+        let f = |m: &str| crate::Field::new(&bi(m));
+        assert_eq!(f("9907").jacobi(&bi("1001")), -1);
+        assert_eq!(f("45").jacobi(&bi("19")), 1);
+        assert_eq!(f("21").jacobi(&bi("8")), -1);
+        assert_eq!(f("21").jacobi(&bi("5")), 1);
+        assert_eq!(f("21").jacobi(&bi("7")), 0);
+        assert!(!f("21").is_quadratic_residue(&bi("7")));
+
+        // 3 mod 4 and 1 mod 8 primes take different paths
+        for m in &["1000000007", "1000000009", "340282366920938463463374607431768211507"] {
+            let field = f(m);
+            assert_eq!(field.sqrt_mod(&bi("0")), Some(bi("0")));
+            for a in &[bi("2"), bi("3"), bi("-7"), bi("12345678901234567890")] {
+                let square = field.mul(a, a);
+                assert!(field.is_quadratic_residue(&square));
+                let root = field.sqrt_mod(&square).unwrap();
+                assert_eq!(field.mul(&root, &root), square);
+            }
+            let mut non_residue = bi("2");
+            while field.is_quadratic_residue(&non_residue) {
+                non_residue = &non_residue + &bi("1");
+            }
+            assert_eq!(field.sqrt_mod(&non_residue), None);
+        }
+    }
+
+    #[test]
+    fn field_exp() {
+        for m in ["340282366920938463463374607431768211507", "340282366920938463463374607431768211508", "3"] {
+            let m = bi(m);
+            let f = crate::Field::new(&m);
+            for b in ["2", "-5", "98765432109876543210987654321098765432109876543210"] {
+                for e in ["0", "1", "-1", "2", "65537", "-12345678901234567890123"] {
+                    if e.starts_with('-') && bi(b).checked_mod_inverse(&m).is_none() {
+                        continue;
+                    }
+                    assert_eq!(f.exp(&bi(b), &bi(e)), bi(b).mod_exp(&bi(e), &m), "{}^{} mod {}", b, e, m);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn multi_exp() {
+        let m = bi("340282366920938463463374607431768211507");
+        let f = crate::Field::new(&m);
+        let bases = [bi("3"), bi("12345678901234567890"), bi("-5"), bi("7"), bi("98765432109876543210987")];
+        let exps = [bi("65537"), bi("0"), bi("12345678901234567890123"), bi("-3"), bi("1")];
+        let pairs: Vec<(&BigInteger, &BigInteger)> = bases.iter().zip(exps.iter()).collect();
+        let expected = pairs
+            .iter()
+            .fold(bi("1"), |acc, (b, e)| f.mul(&acc, &(*b).mod_exp(e, &m)));
+        assert_eq!(f.multi_exp(&pairs), expected);
+        assert_eq!(f.multi_exp(&[]), bi("1"));
+        for e in ["16", "256", "-4096"] {
+            assert_eq!(f.multi_exp(&[(&bases[0], &bi(e))]), bases[0].mod_exp(&bi(e), &m));
+        }
+    }
+
+    #[test]
+    fn fixed_base() {
+        let m = bi("340282366920938463463374607431768211507");
+        let g = bi("12345678901234567890");
+        let table = crate::FixedBase::new(&g, &m, 64);
+        for e in &["0", "1", "2", "-3", "65537", "18446744073709551615", "12345678901234567890123456789"] {
+            assert_eq!(table.exp(&bi(e)), g.mod_exp(&bi(e), &m));
+        }
+        let bytes = table.to_bytes();
+        assert_eq!(crate::FixedBase::try_from(bytes.as_slice()).unwrap(), table);
+        assert!(crate::FixedBase::try_from(&bytes[1..]).is_err());
+        // size and count prefix, the modulus, then 16 bytes per entry
+        for entry in [0, 3, 15] {
+            let mut tampered = bytes.clone();
+            tampered[8 + 16 * (entry + 2) - 1] ^= 1;
+            assert!(crate::FixedBase::try_from(tampered.as_slice()).is_err());
+        }
+        let mut huge = bytes.clone();
+        huge[..8].copy_from_slice(&[0xff; 8]);
+        assert!(crate::FixedBase::try_from(huge.as_slice()).is_err());
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!(bi("-12345").to_string(), "-12345");
+        assert_eq!(format!("{:>8}", bi("42")), "      42");
+        assert_eq!(format!("{:x}", bi("255")), "ff");
+        assert_eq!(format!("{:#x}", bi("-4096")), "-0x1000");
+        assert_eq!(bi("0").to_hex(), "0");
+        assert_eq!(BigInteger::from_hex("-abc").unwrap(), bi("-2748"));
+        assert_eq!(BigInteger::from_hex("0001").unwrap(), bi("1"));
+        assert!(BigInteger::from_hex("xyz").is_err());
+        assert!(BigInteger::from_hex("").is_err());
+    }
+
+    #[test]
+    fn zeroize() {
+        use zeroize::Zeroize;
+
+        for v in &["0", "1", "-98765432109876543210", "340282366920938463463374607431768211457"] {
+            let mut x = bi(v);
+            x.zeroize();
+            assert_eq!(x, BigInteger::default());
+            assert!(x.to_bytes().is_empty());
+        }
+    }
+
+    #[test]
+    fn bezouts_coefficients() {
+        let res = bi("240").bezouts_coefficients(&bi("46"));
+        assert_eq!(res.value, bi("2"));
+        assert_eq!(res.a, bi("-9"));
+        assert_eq!(res.b, bi("47"));
+
+        let res = bi("0").bezouts_coefficients(&bi("5"));
+        assert_eq!(res.value, bi("0"));
+        assert_eq!(res.a, bi("0"));
+        assert_eq!(res.b, bi("1"));
+    }
+
+    #[test]
+    fn bytes() {
+        assert!(BigInteger::default().to_bytes().is_empty());
+        assert_eq!(bi("256").to_bytes(), vec![1u8, 0]);
+        assert_eq!(bi("-5").to_bytes(), vec![5u8]);
+        assert_eq!(BigInteger::try_from(&[][..]).unwrap(), BigInteger::default());
+        assert_eq!(BigInteger::try_from(&[0u8, 0, 1][..]).unwrap(), bi("1"));
+        let v: Vec<u8> = bi("65535").into();
+        assert_eq!(v, vec![0xffu8, 0xff]);
+        assert_eq!(bi("65535").bits(), 16);
+    }
+
+    #[test]
+    fn primes() {
+        assert!(bi("2").is_prime());
+        assert!(!bi("1").is_prime());
+        assert!(!bi("561").is_prime());
+        assert!(!bi("-7").is_prime());
+        assert!(bi("170141183460469231731687303715884105727").is_prime());
+
+        let p = BigInteger::generate_prime(128);
+        assert_eq!(p.bits(), 128);
+        assert!(p.is_prime());
+
+        let p = BigInteger::generate_safe_prime(64);
+        assert_eq!(p.bits(), 64);
+        assert!(p.is_prime());
+        assert!((&(&p - &bi("1")) / &bi("2")).is_prime());
+    }
+
+    #[test]
+    fn baillie_psw() {
+        for n in 0u32..3000 {
+            let n = BigInteger::from(n);
+            assert_eq!(n.is_prime_with(PrimalityTest::BailliePsw), n.is_prime(), "{}", n);
+        }
+        for p in &["170141183460469231731687303715884105727", "618970019642690137449562111"] {
+            assert!(bi(p).is_prime_with(PrimalityTest::BailliePsw));
+        }
+        // Strong pseudoprimes to base 2 and a square, none with a factor below 100
+        for c in &["3215031751", "2152302898747", "3825123056546413051", "10201"] {
+            assert!(!bi(c).is_prime_with(PrimalityTest::BailliePsw));
+            assert!(!bi(c).is_prime_with(PrimalityTest::MillerRabin(20)));
+        }
+        assert!(!bi("-7").is_prime_with(PrimalityTest::BailliePsw));
+        let p = BigInteger::generate_prime(256);
+        assert!(p.is_prime_with(PrimalityTest::BailliePsw));
+        assert!(!(&p * &p).is_prime_with(PrimalityTest::BailliePsw));
+        assert!(!(&p * &BigInteger::generate_prime(256)).is_prime_with(PrimalityTest::BailliePsw));
+    }
+}
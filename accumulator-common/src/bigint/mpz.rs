@@ -27,7 +27,7 @@ pub struct MpzBigInt {
 
 impl std::fmt::Display for MpzBigInt {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "MpzBigInt {{ value: {} }}", self.value.to_str_radix(10))
+        super::pad_signed(f, "", &self.value.to_str_radix(10))
     }
 }
 
@@ -175,6 +175,12 @@ impl MpzBigInt {
         self.value = self.value.invert(&modulus.value).unwrap();
     }
 
+    /// Compute modular inverse and return the result or `None`
+    /// if self has no inverse for the modulus
+    pub fn checked_mod_inverse(&self, modulus: &Self) -> Option<Self> {
+        self.value.invert(&modulus.value).map(|value| Self { value })
+    }
+
     /// Compute modular multiplication and return the result
     /// result = self * rhs mod order
     pub fn mod_mul(&self, rhs: &Self, modulus: &Self) -> Self {
@@ -214,7 +220,12 @@ impl MpzBigInt {
 
     /// Determine if `self` is a prime number
     pub fn is_prime(&self) -> bool {
-        match self.value.probab_prime(15) {
+        self.is_prime_rounds(15)
+    }
+
+    /// Determine if `self` is a prime number with `rounds` of Miller-Rabin
+    pub fn is_prime_rounds(&self, rounds: usize) -> bool {
+        match self.value.probab_prime(rounds as i32) {
             ProbabPrimeResult::Prime | ProbabPrimeResult::ProbablyPrime => true,
             _ => false
         }
@@ -246,6 +257,36 @@ impl MpzBigInt {
     }
 }
 
+/// Repeated operations against one modulus. GMP has no per modulus
+/// state to keep so this forwards to the `MpzBigInt` operations.
+#[derive(Debug)]
+pub struct MpzModulusContext {
+    modulus: MpzBigInt,
+}
+
+impl MpzModulusContext {
+    /// Create a context for `modulus`
+    pub fn new(modulus: &MpzBigInt) -> Self {
+        Self { modulus: modulus.clone() }
+    }
+
+    /// base ^ exponent mod modulus
+    pub fn exp(&self, base: &MpzBigInt, exponent: &MpzBigInt) -> MpzBigInt {
+        base.mod_exp(exponent, &self.modulus)
+    }
+
+    /// a * b mod modulus
+    pub fn mul(&self, a: &MpzBigInt, b: &MpzBigInt) -> MpzBigInt {
+        a.mod_mul(b, &self.modulus)
+    }
+
+    /// b_1^e_1 * b_2^e_2 * ... mod modulus
+    pub fn multi_exp(&self, pairs: &[(&MpzBigInt, &MpzBigInt)]) -> MpzBigInt {
+        let one = &MpzBigInt::from(1u32) % &self.modulus;
+        super::multi_exp(pairs, &self.modulus, one, MpzBigInt::clone, |a, b| self.mul(a, b))
+    }
+}
+
 impl std::iter::Product<MpzBigInt> for MpzBigInt {
     fn product<I: Iterator<Item=MpzBigInt>>(iter: I) -> Self {
         let mut value = Mpz::one();
@@ -288,7 +329,15 @@ impl<'a> std::iter::Sum<&'a MpzBigInt> for MpzBigInt {
 
 impl Zeroize for MpzBigInt {
     fn zeroize(&mut self) {
-        self.value ^= &self.value.clone();
+        // Overwrite the limbs in place with a public mask so no copy
+        // of the secret value is left behind
+        if self.value < Mpz::new() {
+            let value = std::mem::replace(&mut self.value, Mpz::new());
+            self.value = -value;
+        }
+        let mask = (Mpz::one() << self.value.bit_length()) - Mpz::one();
+        self.value |= &mask;
+        self.value ^= &mask;
     }
 }
 
@@ -1,17 +1,22 @@
 use super::GcdResult;
 use crate::error::AccumulatorError;
-use openssl::bn::*;
+use foreign_types::ForeignTypeRef;
+use openssl::{bn::*, error::ErrorStack};
+use openssl_sys as ffi;
 use std::{
+    cell::RefCell,
+    os::raw::c_int,
     ops::{Add, AddAssign, Sub, SubAssign, Mul, MulAssign, Div, DivAssign, Rem, RemAssign},
     cmp::Ordering,
     convert::TryFrom,
-    str::FromStr
+    str::FromStr,
 };
 use zeroize::Zeroize;
 
 #[inline]
+/// A copy that keeps the sign, the bytes of `to_vec` are only the magnitude
 fn clone_bignum(b: &BigNum) -> BigNum {
-    BigNum::from_slice(b.to_vec().as_slice()).unwrap()
+    BigNumRef::to_owned(b).unwrap()
 }
 
 /// A Big Integer Implementation backed by OpenSSL BigNum
@@ -22,7 +27,7 @@ pub struct OsslBigInt {
 
 impl std::fmt::Display for OsslBigInt {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "OsslBigInt {{ value: {} }}", self.value.to_dec_str().unwrap())
+        super::pad_signed(f, "", &self.value.to_dec_str().unwrap())
     }
 }
 
@@ -144,6 +149,12 @@ impl OsslBigInt {
     /// Compute modular exponentiation and return the result
     /// result = self ^ rhs mod order
     pub fn mod_exp(&self, exponent: &Self, modulus: &Self) -> Self {
+        let mut ctx = BigNumContext::new().unwrap();
+        self.mod_exp_with(exponent, modulus, &mut ctx)
+    }
+
+    /// `mod_exp` using an existing context
+    fn mod_exp_with(&self, exponent: &Self, modulus: &Self, ctx: &mut BigNumContextRef) -> Self {
         let one = BigNum::from_u32(1u32).unwrap();
         if exponent.value == BigNum::new().unwrap() {
             return Self { value: one };
@@ -152,15 +163,14 @@ impl OsslBigInt {
             return self.clone();
         }
         let mut value = BigNum::new().unwrap();
-        let mut ctx = BigNumContext::new().unwrap();
         if exponent.value.is_negative() {
             let mut exp = clone_bignum(&exponent.value);
             let mut temp = BigNum::new().unwrap();
-            BigNumRef::mod_inverse(&mut temp, &self.value, &modulus.value, &mut ctx).unwrap();
+            BigNumRef::mod_inverse(&mut temp, &self.value, &modulus.value, ctx).unwrap();
             exp.set_negative(false);
-            BigNumRef::mod_exp(&mut value, &temp, &exp, &modulus.value, &mut ctx).unwrap();
+            BigNumRef::mod_exp(&mut value, &temp, &exp, &modulus.value, ctx).unwrap();
         } else {
-            BigNumRef::mod_exp(&mut value, &self.value, &exponent.value,  &modulus.value, &mut ctx).unwrap();
+            BigNumRef::mod_exp(&mut value, &self.value, &exponent.value,  &modulus.value, ctx).unwrap();
         }
         Self { value }
     }
@@ -223,12 +233,26 @@ impl OsslBigInt {
         BigNumRef::mod_inverse(&mut self.value, &value, &modulus.value, &mut ctx).unwrap();
     }
 
+    /// Compute modular inverse and return the result or `None`
+    /// if self has no inverse for the modulus
+    pub fn checked_mod_inverse(&self, modulus: &Self) -> Option<Self> {
+        let mut value = BigNum::new().ok()?;
+        let mut ctx = BigNumContext::new().ok()?;
+        BigNumRef::mod_inverse(&mut value, &self.value, &modulus.value, &mut ctx).ok()?;
+        Some(Self { value })
+    }
+
     /// Compute modular multiplication and return the result
     /// result = self * rhs mod order
     pub fn mod_mul(&self, rhs: &Self, modulus: &Self) -> Self {
-        let mut value = BigNum::new().unwrap();
         let mut ctx = BigNumContext::new().unwrap();
-        BigNumRef::mod_mul(&mut value, &self.value, &rhs.value, &modulus.value, &mut ctx).unwrap();
+        self.mod_mul_with(rhs, modulus, &mut ctx)
+    }
+
+    /// `mod_mul` using an existing context
+    fn mod_mul_with(&self, rhs: &Self, modulus: &Self, ctx: &mut BigNumContextRef) -> Self {
+        let mut value = BigNum::new().unwrap();
+        BigNumRef::mod_mul(&mut value, &self.value, &rhs.value, &modulus.value, ctx).unwrap();
         Self {
             value
         }
@@ -265,8 +289,13 @@ impl OsslBigInt {
 
     /// Determine if `self` is a prime number
     pub fn is_prime(&self) -> bool {
+        self.is_prime_rounds(15)
+    }
+
+    /// Determine if `self` is a prime number with `rounds` of Miller-Rabin
+    pub fn is_prime_rounds(&self, rounds: usize) -> bool {
         let mut ctx = BigNumContext::new().unwrap();
-        self.value.is_prime(15, &mut ctx).unwrap()
+        self.value.is_prime(rounds as i32, &mut ctx).unwrap()
     }
 
     /// Computes Bézout's coefficients and returns `s` and `t`
@@ -536,6 +565,185 @@ impl OsslBigInt {
     }
 }
 
+// The Montgomery functions aren't bound by openssl-sys, libcrypto exports them
+extern "C" {
+    fn BN_MONT_CTX_new() -> *mut ffi::BN_MONT_CTX;
+    fn BN_MONT_CTX_free(mont: *mut ffi::BN_MONT_CTX);
+    fn BN_MONT_CTX_set(mont: *mut ffi::BN_MONT_CTX, m: *const ffi::BIGNUM, ctx: *mut ffi::BN_CTX) -> c_int;
+    fn BN_mod_exp_mont(
+        r: *mut ffi::BIGNUM,
+        a: *const ffi::BIGNUM,
+        p: *const ffi::BIGNUM,
+        m: *const ffi::BIGNUM,
+        ctx: *mut ffi::BN_CTX,
+        mont: *mut ffi::BN_MONT_CTX,
+    ) -> c_int;
+    fn BN_mod_mul_montgomery(
+        r: *mut ffi::BIGNUM,
+        a: *const ffi::BIGNUM,
+        b: *const ffi::BIGNUM,
+        mont: *mut ffi::BN_MONT_CTX,
+        ctx: *mut ffi::BN_CTX,
+    ) -> c_int;
+    fn BN_to_montgomery(
+        r: *mut ffi::BIGNUM,
+        a: *const ffi::BIGNUM,
+        mont: *mut ffi::BN_MONT_CTX,
+        ctx: *mut ffi::BN_CTX,
+    ) -> c_int;
+    fn BN_from_montgomery(
+        r: *mut ffi::BIGNUM,
+        a: *const ffi::BIGNUM,
+        mont: *mut ffi::BN_MONT_CTX,
+        ctx: *mut ffi::BN_CTX,
+    ) -> c_int;
+}
+
+fn cvt(r: c_int) -> Result<(), ErrorStack> {
+    if r == 1 {
+        Ok(())
+    } else {
+        Err(ErrorStack::get())
+    }
+}
+
+thread_local! {
+    /// Scratch space for the context operations. One per thread so
+    /// parallel proofs don't wait on each other
+    static SCRATCH: RefCell<BigNumContext> = RefCell::new(BigNumContext::new().unwrap());
+}
+
+fn with_scratch<R, F: FnOnce(&mut BigNumContextRef) -> R>(f: F) -> R {
+    SCRATCH.with(|ctx| f(&mut ctx.borrow_mut()))
+}
+
+/// The Montgomery parameters of an odd modulus. OpenSSL only reads
+/// them once they are set so they are shared between threads
+struct MontContext(*mut ffi::BN_MONT_CTX);
+
+#[allow(unsafe_code)]
+unsafe impl Send for MontContext {}
+#[allow(unsafe_code)]
+unsafe impl Sync for MontContext {}
+
+impl Drop for MontContext {
+    #[allow(unsafe_code)]
+    fn drop(&mut self) {
+        unsafe { BN_MONT_CTX_free(self.0) }
+    }
+}
+
+#[allow(unsafe_code)]
+impl MontContext {
+    fn new(modulus: &BigNumRef) -> Self {
+        let mont = Self(unsafe { BN_MONT_CTX_new() });
+        assert!(!mont.0.is_null(), "out of memory");
+        with_scratch(|ctx| cvt(unsafe { BN_MONT_CTX_set(mont.0, modulus.as_ptr(), ctx.as_ptr()) })).unwrap();
+        mont
+    }
+
+    /// base ^ exponent mod modulus for a non-negative exponent
+    fn exp(&self, base: &BigNumRef, exponent: &BigNumRef, modulus: &BigNumRef) -> BigNum {
+        let value = BigNum::new().unwrap();
+        with_scratch(|ctx| {
+            cvt(unsafe {
+                BN_mod_exp_mont(value.as_ptr(), base.as_ptr(), exponent.as_ptr(), modulus.as_ptr(), ctx.as_ptr(), self.0)
+            })
+        })
+        .unwrap();
+        value
+    }
+
+    /// a * b / R mod modulus
+    fn mul(&self, a: &BigNumRef, b: &BigNumRef) -> BigNum {
+        let value = BigNum::new().unwrap();
+        with_scratch(|ctx| cvt(unsafe { BN_mod_mul_montgomery(value.as_ptr(), a.as_ptr(), b.as_ptr(), self.0, ctx.as_ptr()) }))
+            .unwrap();
+        value
+    }
+
+    /// Into Montgomery form, a * R mod modulus for `a` in `[0, modulus)`
+    fn enter(&self, a: &BigNumRef) -> BigNum {
+        let value = BigNum::new().unwrap();
+        with_scratch(|ctx| cvt(unsafe { BN_to_montgomery(value.as_ptr(), a.as_ptr(), self.0, ctx.as_ptr()) })).unwrap();
+        value
+    }
+
+    /// Out of Montgomery form, a / R mod modulus
+    fn leave(&self, a: &BigNumRef) -> BigNum {
+        let value = BigNum::new().unwrap();
+        with_scratch(|ctx| cvt(unsafe { BN_from_montgomery(value.as_ptr(), a.as_ptr(), self.0, ctx.as_ptr()) })).unwrap();
+        value
+    }
+}
+
+/// Repeated operations against one modulus. The Montgomery parameters of
+/// an odd modulus are computed once, OpenSSL would otherwise redo them for
+/// every exponentiation.
+pub struct OsslModulusContext {
+    modulus: OsslBigInt,
+    mont: Option<MontContext>,
+}
+
+impl std::fmt::Debug for OsslModulusContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "OsslModulusContext {{ modulus: {} }}", self.modulus)
+    }
+}
+
+impl OsslModulusContext {
+    /// Create a context for `modulus`
+    pub fn new(modulus: &OsslBigInt) -> Self {
+        let odd = modulus.value.is_bit_set(0) && modulus.value.num_bits() > 1;
+        Self {
+            modulus: modulus.clone(),
+            mont: if odd { Some(MontContext::new(&modulus.value)) } else { None },
+        }
+    }
+
+    /// base ^ exponent mod modulus
+    pub fn exp(&self, base: &OsslBigInt, exponent: &OsslBigInt) -> OsslBigInt {
+        match &self.mont {
+            // 0 and ±1 keep the results of `mod_exp`
+            Some(mont) if exponent.value.num_bits() > 1 => {
+                let mut e = clone_bignum(&exponent.value);
+                let b = if e.is_negative() {
+                    e.set_negative(false);
+                    base.mod_exp(&OsslBigInt::from(-1), &self.modulus)
+                } else {
+                    base.clone()
+                };
+                OsslBigInt { value: mont.exp(&b.value, &e, &self.modulus.value) }
+            }
+            _ => with_scratch(|ctx| base.mod_exp_with(exponent, &self.modulus, ctx)),
+        }
+    }
+
+    /// a * b mod modulus
+    pub fn mul(&self, a: &OsslBigInt, b: &OsslBigInt) -> OsslBigInt {
+        with_scratch(|ctx| a.mod_mul_with(b, &self.modulus, ctx))
+    }
+
+    /// b_1^e_1 * b_2^e_2 * ... mod modulus, in Montgomery form for an odd modulus
+    pub fn multi_exp(&self, pairs: &[(&OsslBigInt, &OsslBigInt)]) -> OsslBigInt {
+        let one = &OsslBigInt::from(1u32) % &self.modulus;
+        match &self.mont {
+            Some(mont) => {
+                let wrap = |value| OsslBigInt { value };
+                let value = super::multi_exp(
+                    pairs,
+                    &self.modulus,
+                    wrap(mont.enter(&one.value)),
+                    |b| wrap(mont.enter(&b.value)),
+                    |a, b| wrap(mont.mul(&a.value, &b.value)),
+                );
+                wrap(mont.leave(&value.value))
+            }
+            None => super::multi_exp(pairs, &self.modulus, one, OsslBigInt::clone, |a, b| self.mul(a, b)),
+        }
+    }
+}
+
 /// Attempts to simulate several Euclidean update steps using leading digits of `a` and `b`.
 /// It returns `u0`, `u1`, `v0`, `v1` such that `a` and `b` can be updated as:
 ///     a = u0 * a + v0 * b
@@ -754,6 +962,7 @@ impl<'a> std::iter::Sum<&'a OsslBigInt> for OsslBigInt {
 
 impl Zeroize for OsslBigInt {
     fn zeroize(&mut self) {
+        // BN_clear wipes the limbs before resetting the value
         self.value.clear();
     }
 }
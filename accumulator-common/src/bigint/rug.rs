@@ -0,0 +1,484 @@
+use super::GcdResult;
+use crate::error::{AccumulatorError, AccumulatorErrorKind};
+use rand::RngCore;
+use rug::{
+    integer::{IsPrime, Order},
+    rand::RandState,
+    Assign, Integer,
+};
+use std::{
+    ops::{Add, AddAssign, Sub, SubAssign, Mul, MulAssign, Div, DivAssign, Rem, RemAssign},
+    cmp::Ordering,
+    convert::TryFrom,
+    str::FromStr
+};
+use zeroize::Zeroize;
+
+/// Create a GMP random state seeded from the operating system
+fn rand_state<'a>() -> RandState<'a> {
+    let mut seed = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut seed);
+    let mut state = RandState::new();
+    state.seed(&Integer::from_digits(&seed, Order::Msf));
+    seed.zeroize();
+    state
+}
+
+/// A Big Integer Implementation backed by GMP through rug
+#[derive(Debug)]
+pub struct RugBigInt {
+    pub(crate) value: Integer,
+}
+
+impl std::fmt::Display for RugBigInt {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        super::pad_signed(f, "", &self.value.to_string_radix(10))
+    }
+}
+
+impl Clone for RugBigInt {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+        }
+    }
+}
+
+impl PartialEq for RugBigInt {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl Eq for RugBigInt {}
+
+impl PartialOrd for RugBigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.value.partial_cmp(&other.value)
+    }
+}
+
+impl Ord for RugBigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+impl Default for RugBigInt {
+    fn default() -> Self {
+        Self { value: Integer::new() }
+    }
+}
+
+impl RugBigInt {
+    /// Used by the std::ops::Add methods
+    fn add_(&self, rhs: &Self) -> Self {
+        Self { value: Integer::from(&self.value + &rhs.value) }
+    }
+
+    /// Used by the std::ops::AddAssign methods
+    fn add_assign_(&mut self, rhs: &Self) {
+        self.value += &rhs.value;
+    }
+
+    /// Used by the std::ops::Sub methods
+    fn sub_(&self, rhs: &Self) -> Self {
+        Self { value: Integer::from(&self.value - &rhs.value) }
+    }
+
+    /// Used by the std::ops::SubAssign methods
+    fn sub_assign_(&mut self, rhs: &Self) {
+        self.value -= &rhs.value;
+    }
+
+    /// Used by the std::ops::Mul methods
+    fn mul_(&self, rhs: &Self) -> Self {
+        Self { value: Integer::from(&self.value * &rhs.value) }
+    }
+
+    /// Used by the std::ops::MulAssign methods
+    fn mul_assign_(&mut self, rhs: &Self) {
+        self.value *= &rhs.value;
+    }
+
+    /// Used by the std::ops::Div methods
+    fn div_(&self, rhs: &Self) -> Self {
+        Self { value: Integer::from(&self.value / &rhs.value) }
+    }
+
+    /// Used by the std::ops::DivAssign methods
+    fn div_assign_(&mut self, rhs: &Self) {
+        self.value /= &rhs.value;
+    }
+
+    /// Used by the std::ops::Rem methods
+    fn rem_(&self, rhs: &Self) -> Self {
+        Self { value: Integer::from(self.value.rem_euc_ref(&rhs.value)) }
+    }
+
+    /// Used by the std::ops::RemAssign methods
+    fn rem_assign_(&mut self, rhs: &Self) {
+        self.value.rem_euc_mut(&rhs.value);
+    }
+
+    /// Compute the quotient and remainder and return both
+    pub fn div_rem(&self, rhs: &Self) -> (Self, Self) {
+        let (q, r) = <(Integer, Integer)>::from(self.value.div_rem_ref(&rhs.value));
+        (Self { value: q }, Self { value: r })
+    }
+
+    /// Compute modular exponentiation and return the result
+    /// result = self ^ rhs mod order
+    pub fn mod_exp(&self, exponent: &Self, modulus: &Self) -> Self {
+        if exponent.value == 0 {
+            return Self { value: Integer::from(1) };
+        }
+        if exponent.value == 1 {
+            return self.clone();
+        }
+        // Negative exponents are handled by rug by first inverting self
+        let value = Integer::from(self.value.pow_mod_ref(&exponent.value, &modulus.value).unwrap());
+        Self { value }
+    }
+
+    /// Compute modular exponentiation and assign it to self
+    /// self = self ^ exponent mod order
+    pub fn mod_exp_assign(&mut self, exponent: &Self, modulus: &Self) {
+        if exponent.value == 0 {
+            self.value.assign(1);
+            return;
+        } else if exponent.value == 1 {
+            return;
+        }
+
+        self.value.pow_mod_mut(&exponent.value, &modulus.value).unwrap();
+    }
+
+    /// Compute modular square and return the result
+    /// result = self ^ 2 mod order
+    pub fn mod_sqr(&self, modulus: &Self) -> Self {
+        let mut value = self.value.clone().square();
+        value.rem_euc_mut(&modulus.value);
+        Self { value }
+    }
+
+    /// Compute modular exponentiation and assign it to self
+    /// self = self ^ 2 mod order
+    pub fn mod_sqr_assign(&mut self, modulus: &Self) {
+        self.value.square_mut();
+        self.value.rem_euc_mut(&modulus.value);
+    }
+
+    /// Compute modular inverse and return the result
+    /// result = self ^ -1 mod order
+    pub fn mod_inverse(&self, modulus: &Self) -> Self {
+        Self { value: Integer::from(self.value.invert_ref(&modulus.value).unwrap()) }
+    }
+
+    /// Compute modular inverse and assign it to self
+    /// self = self ^ -1 mod order
+    pub fn mod_inverse_assign(&mut self, modulus: &Self) {
+        self.value.invert_mut(&modulus.value).unwrap();
+    }
+
+    /// Compute modular inverse and return the result or `None`
+    /// if self has no inverse for the modulus
+    pub fn checked_mod_inverse(&self, modulus: &Self) -> Option<Self> {
+        self.value.invert_ref(&modulus.value).map(|r| Self { value: Integer::from(r) })
+    }
+
+    /// Compute modular multiplication and return the result
+    /// result = self * rhs mod order
+    pub fn mod_mul(&self, rhs: &Self, modulus: &Self) -> Self {
+        let mut value = Integer::from(&self.value * &rhs.value);
+        value.rem_euc_mut(&modulus.value);
+        Self { value }
+    }
+
+    /// Compute modular exponentiation and assign it to self
+    /// self = self * rhs mod order
+    pub fn mod_mul_assign(&mut self, rhs: &Self, modulus: &Self) {
+        self.value *= &rhs.value;
+        self.value.rem_euc_mut(&modulus.value);
+    }
+
+    /// Generate a prime number of `size` bits
+    pub fn generate_prime(size: usize) -> Self {
+        let mut state = rand_state();
+        loop {
+            let mut value = Integer::from(Integer::random_bits(size as u32, &mut state));
+            value.set_bit(size as u32 - 1, true);
+            value.next_prime_mut();
+            if value.significant_bits() as usize == size {
+                return Self { value };
+            }
+        }
+    }
+
+    /// Generate a safe prime number of `size` bits
+    pub fn generate_safe_prime(size: usize) -> Self {
+        let mut state = rand_state();
+        loop {
+            let mut q = Integer::from(Integer::random_bits(size as u32 - 1, &mut state));
+            q.set_bit(size as u32 - 2, true);
+            q.next_prime_mut();
+            let value = Integer::from(&q << 1) + 1u32;
+            if value.significant_bits() as usize == size
+                && value.is_probably_prime(15) != IsPrime::No
+            {
+                return Self { value };
+            }
+        }
+    }
+
+    /// Generate a random value less than `self`
+    pub fn rand_range(&self) -> Self {
+        let mut state = rand_state();
+        Self { value: Integer::from(self.value.random_below_ref(&mut state)) }
+    }
+
+    /// Determine if `self` is a prime number
+    pub fn is_prime(&self) -> bool {
+        self.is_prime_rounds(15)
+    }
+
+    /// Determine if `self` is a prime number with `rounds` of Miller-Rabin
+    pub fn is_prime_rounds(&self, rounds: usize) -> bool {
+        self.value.is_probably_prime(rounds as u32) != IsPrime::No
+    }
+
+    /// Computes Bézout's coefficients and returns `s` and `t`
+    /// using the extended euclidean algorithm
+    pub fn bezouts_coefficients(&self, rhs: &Self) -> GcdResult {
+        let (g, s, t) = <(Integer, Integer, Integer)>::from(self.value.gcd_cofactors_ref(&rhs.value));
+
+        GcdResult {
+            value: Self { value: g },
+            a: Self { value: s },
+            b: Self { value: t }
+        }
+    }
+
+    /// The number of bits needed to represent `self`
+    pub fn bits(&self) -> usize {
+        self.value.significant_bits() as usize
+    }
+
+    /// Serialize to big-endian byte array
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.value.to_digits::<u8>(Order::Msf)
+    }
+}
+
+/// Repeated operations against one modulus. GMP has no per modulus
+/// state to keep so this forwards to the `RugBigInt` operations.
+#[derive(Debug)]
+pub struct RugModulusContext {
+    modulus: RugBigInt,
+}
+
+impl RugModulusContext {
+    /// Create a context for `modulus`
+    pub fn new(modulus: &RugBigInt) -> Self {
+        Self { modulus: modulus.clone() }
+    }
+
+    /// base ^ exponent mod modulus
+    pub fn exp(&self, base: &RugBigInt, exponent: &RugBigInt) -> RugBigInt {
+        base.mod_exp(exponent, &self.modulus)
+    }
+
+    /// a * b mod modulus
+    pub fn mul(&self, a: &RugBigInt, b: &RugBigInt) -> RugBigInt {
+        a.mod_mul(b, &self.modulus)
+    }
+
+    /// b_1^e_1 * b_2^e_2 * ... mod modulus
+    pub fn multi_exp(&self, pairs: &[(&RugBigInt, &RugBigInt)]) -> RugBigInt {
+        let one = &RugBigInt::from(1u32) % &self.modulus;
+        super::multi_exp(pairs, &self.modulus, one, RugBigInt::clone, |a, b| self.mul(a, b))
+    }
+}
+
+impl std::iter::Product<RugBigInt> for RugBigInt {
+    fn product<I: Iterator<Item=RugBigInt>>(iter: I) -> Self {
+        let mut value = Integer::from(1);
+        for i in iter {
+            value *= &i.value;
+        }
+        Self { value }
+    }
+}
+
+impl<'a> std::iter::Product<&'a RugBigInt> for RugBigInt {
+    fn product<I: Iterator<Item=&'a RugBigInt>>(iter: I) -> Self {
+        let mut value = Integer::from(1);
+        for i in iter {
+            value *= &i.value;
+        }
+        Self { value }
+    }
+}
+
+impl std::iter::Sum<RugBigInt> for RugBigInt {
+    fn sum<I: Iterator<Item=RugBigInt>>(iter: I) -> Self {
+        let mut value = Integer::new();
+        for i in iter {
+            value += &i.value;
+        }
+        Self { value }
+    }
+}
+
+impl<'a> std::iter::Sum<&'a RugBigInt> for RugBigInt {
+    fn sum<I: Iterator<Item=&'a RugBigInt>>(iter: I) -> Self {
+        let mut value = Integer::new();
+        for i in iter {
+            value += &i.value;
+        }
+        Self { value }
+    }
+}
+
+impl Zeroize for RugBigInt {
+    fn zeroize(&mut self) {
+        // Overwrite the limbs in place with a public mask so no copy
+        // of the secret value is left behind
+        self.value.abs_mut();
+        let mask = Integer::from(Integer::from(1) << self.value.significant_bits()) - 1u32;
+        self.value |= &mask;
+        self.value ^= &mask;
+    }
+}
+
+impl TryFrom<&[u8]> for RugBigInt {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        Ok(Self { value: Integer::from_digits(data, Order::Msf) })
+    }
+}
+
+impl FromStr for RugBigInt {
+    type Err = AccumulatorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self { value: Integer::from_str_radix(s, 10).map_err(|e| AccumulatorError::from_msg(AccumulatorErrorKind::InvalidType, e.to_string()))? })
+    }
+}
+
+macro_rules! from_impl {
+    ($ty:ty) => {
+        impl From<$ty> for RugBigInt {
+            fn from(value: $ty) -> Self {
+                Self { value: Integer::from(value) }
+            }
+        }
+    };
+}
+macro_rules! ops_impl {
+    ($t:ident, $ts:ident, $f:ident, $fs:ident,$i:ident, $is:ident) => {
+        impl $t for RugBigInt {
+            type Output = RugBigInt;
+
+            fn $f(self, rhs: Self::Output) -> Self::Output {
+                self.$i(&rhs)
+            }
+        }
+
+        impl<'a, 'b> $t<&'b RugBigInt> for &'a RugBigInt {
+            type Output = RugBigInt;
+
+            fn $f(self, rhs: &'b Self::Output) -> Self::Output {
+                self.$i(rhs)
+            }
+        }
+
+        impl $ts for RugBigInt {
+            fn $fs(&mut self, rhs: RugBigInt) {
+                self.$is(&rhs)
+            }
+        }
+
+        impl $ts<&RugBigInt> for RugBigInt {
+            fn $fs(&mut self, rhs: &RugBigInt) {
+                self.$is(rhs)
+            }
+        }
+    };
+}
+
+impl From<Vec<u8>> for RugBigInt {
+    fn from(value: Vec<u8>) -> Self {
+        Self { value: Integer::from_digits(value.as_slice(), Order::Msf) }
+    }
+}
+
+impl Into<Vec<u8>> for RugBigInt {
+    fn into(self) -> Vec<u8> {
+        self.to_bytes()
+    }
+}
+
+impl From<&str> for RugBigInt {
+    fn from(value: &str) -> Self {
+        Self { value: Integer::from_str_radix(value, 10).unwrap() }
+    }
+}
+
+from_impl!(u64);
+from_impl!(u32);
+from_impl!(u16);
+from_impl!(u8);
+from_impl!(i64);
+from_impl!(i32);
+from_impl!(i16);
+from_impl!(i8);
+
+ops_impl!(Add, AddAssign, add, add_assign, add_, add_assign_);
+ops_impl!(Sub, SubAssign, sub, sub_assign, sub_, sub_assign_);
+ops_impl!(Mul, MulAssign, mul, mul_assign, mul_, mul_assign_);
+ops_impl!(Div, DivAssign, div, div_assign, div_, div_assign_);
+ops_impl!(Rem, RemAssign, rem, rem_assign, rem_, rem_assign_);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bigint::BigInteger;
+
+    #[test]
+    fn test_bezouts_coefficients() {
+        let a = RugBigInt::from(31);
+        let b = RugBigInt::from(37);
+        let gcdres = a.bezouts_coefficients(&b);
+        assert_eq!(&a * &gcdres.a + &b * &gcdres.b, RugBigInt::from(1));
+
+        let a = RugBigInt::from("63156515965705215668198135979702445890399855958342988288023717346298762458519");
+        let b = RugBigInt::from("88222503113609549383110571557868679926843894352175049520163164425194315455087");
+        let gcdres = a.bezouts_coefficients(&b);
+        assert_eq!(&a * &gcdres.a + &b * &gcdres.b, RugBigInt::from(1));
+    }
+
+    #[test]
+    fn test_safe_prime() {
+        let p = RugBigInt::generate_safe_prime(128);
+        assert_eq!(p.bits(), 128);
+        let q = (&p - &RugBigInt::from(1)) / RugBigInt::from(2);
+        assert!(p.is_prime());
+        assert!(q.is_prime());
+    }
+
+    #[test]
+    fn test_bytes() {
+        let a = RugBigInt::from(65537u32);
+        assert_eq!(a.to_bytes(), vec![1, 0, 1]);
+        assert_eq!(RugBigInt::try_from(a.to_bytes().as_slice()).unwrap(), a);
+    }
+
+    #[test]
+    fn test_product() {
+        let values = vec![BigInteger::from(2u32), BigInteger::from(3u32)];
+        let res: BigInteger = values.iter().product();
+        assert_eq!(res, BigInteger::from(6u32));
+    }
+}
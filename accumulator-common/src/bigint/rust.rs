@@ -1,10 +1,15 @@
 use super::GcdResult;
 use crate::error::AccumulatorError;
-use num_bigint::{BigInt, RandBigInt, ToBigInt, Sign};
+use num_bigint::{BigInt, BigUint, Sign};
+#[cfg(feature = "std")]
+use num_bigint::{RandBigInt, ToBigInt};
 use num_traits::{Zero, One, Signed, Num};
 use num_integer::Integer;
+#[cfg(feature = "std")]
 use rand::prelude::*;
-use std::{
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+use core::{
     ops::{Add, AddAssign, Sub, SubAssign, Mul, MulAssign, Div, DivAssign, Rem, RemAssign},
     cmp::Ordering,
     convert::TryFrom,
@@ -12,15 +17,26 @@ use std::{
 };
 use zeroize::Zeroize;
 
-/// A Big Integer Implementation backed by OpenSSL BigNum
+/// Miller-Rabin rounds, the same count the OpenSSL backend uses
+const PRIME_ROUNDS: usize = 15;
+
+/// Trial division before running Miller-Rabin
+#[cfg(feature = "std")]
+const SMALL_PRIMES: [u32; 53] = [
+    3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191,
+    193, 197, 199, 211, 223, 227, 229, 233, 239, 241, 251,
+];
+
+/// A Big Integer Implementation backed by num-bigint
 #[derive(Debug)]
 pub struct RustBigInt {
     pub(crate) value: BigInt,
 }
 
-impl std::fmt::Display for RustBigInt {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "RustBigInt {{ value: {} }}", self.value.to_str_radix(10))
+impl core::fmt::Display for RustBigInt {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        super::pad_signed(f, "", &self.value.to_str_radix(10))
     }
 }
 
@@ -101,12 +117,12 @@ impl RustBigInt {
 
     /// Used by the std::ops::Rem methods
     fn rem_(&self, rhs: &Self) -> Self {
-        Self { value: &self.value % &rhs.value }
+        Self { value: nnmod(&self.value, &rhs.value) }
     }
 
     /// Used by the std::ops::RemAssign methods
     fn rem_assign_(&mut self, rhs: &Self) {
-        self.value %= &rhs.value;
+        self.value = nnmod(&self.value, &rhs.value);
     }
 
     /// Compute the quotient and remainder and return both
@@ -140,6 +156,7 @@ impl RustBigInt {
     pub fn mod_exp_assign(&mut self, exponent: &Self, modulus: &Self) {
         if exponent.value.is_zero() {
             self.value.set_one();
+            return;
         }
         if exponent.value.is_one() {
             return;
@@ -156,13 +173,13 @@ impl RustBigInt {
     /// Compute modular square and return the result
     /// result = self ^ 2 mod order
     pub fn mod_sqr(&self, modulus: &Self) -> Self {
-        Self { value: (&self.value * &self.value) % &modulus.value }
+        Self { value: nnmod(&(&self.value * &self.value), &modulus.value) }
     }
 
     /// Compute modular exponentiation and assign it to self
     /// self = self ^ 2 mod order
     pub fn mod_sqr_assign(&mut self, modulus: &Self) {
-        self.value = (&self.value * &self.value) % &modulus.value
+        self.value = nnmod(&(&self.value * &self.value), &modulus.value)
     }
 
     /// Compute modular inverse and return the result
@@ -177,14 +194,28 @@ impl RustBigInt {
         self.value = self.inverse(modulus);
     }
 
+    /// Compute modular inverse and return the result or `None`
+    /// if self has no inverse for the modulus
+    pub fn checked_mod_inverse(&self, modulus: &Self) -> Option<Self> {
+        self.checked_inverse(modulus).map(|value| Self { value })
+    }
+
     fn inverse(&self, modulus: &Self) -> BigInt {
         if modulus.value.is_zero() ||
             modulus.value.is_one() {
             panic!("Invalid modulus");
         }
+        self.checked_inverse(modulus).expect("Not invertible")
+    }
+
+    fn checked_inverse(&self, modulus: &Self) -> Option<BigInt> {
+        if modulus.value.is_zero() ||
+            modulus.value.is_one() {
+            return None;
+        }
 
         let (mut t, mut new_t) = (BigInt::zero(), BigInt::one());
-        let (mut r, mut new_r) = (modulus.value.clone(), self.value.clone());
+        let (mut r, mut new_r) = (modulus.value.abs(), nnmod(&self.value, &modulus.value));
 
         while !new_r.is_zero() {
             let q = &r / &new_r;
@@ -199,36 +230,55 @@ impl RustBigInt {
         }
 
         if r > BigInt::one() {
-            panic!("Not invertible")
+            return None;
         } else if t.is_negative() {
-            t += &modulus.value;
+            t += modulus.value.abs();
         }
-        t
+        Some(t)
     }
 
     /// Compute modular multiplication and return the result
     /// result = self * rhs mod order
     pub fn mod_mul(&self, rhs: &Self, modulus: &Self) -> Self {
-        Self { value: (&self.value * &rhs.value) % &modulus.value }
+        Self { value: nnmod(&(&self.value * &rhs.value), &modulus.value) }
     }
 
     /// Compute modular exponentiation and assign it to self
     /// self = self * rhs mod order
     pub fn mod_mul_assign(&mut self, rhs: &Self, modulus: &Self) {
-        self.value = (&self.value * &rhs.value) % &modulus.value;
+        self.value = nnmod(&(&self.value * &rhs.value), &modulus.value);
     }
 
     /// Generate a prime number of `size` bits
+    #[cfg(feature = "std")]
     pub fn generate_prime(size: usize) -> Self {
-        Self { value: glass_pumpkin::prime::new(size).unwrap().to_bigint().unwrap() }
+        loop {
+            let value = random_candidate(size);
+            if is_probable_prime(&value, PRIME_ROUNDS) {
+                return Self { value: value.to_bigint().unwrap() };
+            }
+        }
     }
 
     /// Generate a safe prime number of `size` bits
+    #[cfg(feature = "std")]
     pub fn generate_safe_prime(size: usize) -> Self {
-        Self { value: glass_pumpkin::safe_prime::new(size).unwrap().to_bigint().unwrap() }
+        // p = 3 mod 4 so (p - 1) / 2 is odd
+        let three = BigUint::from(3u32);
+        loop {
+            let p = random_candidate(size) | &three;
+            let q = &p >> 1;
+            if has_small_factor(&q) || has_small_factor(&p) {
+                continue;
+            }
+            if miller_rabin(&q, PRIME_ROUNDS) && miller_rabin(&p, PRIME_ROUNDS) {
+                return Self { value: p.to_bigint().unwrap() };
+            }
+        }
     }
 
     /// Generate a random value less than `self`
+    #[cfg(feature = "std")]
     pub fn rand_range(&self) -> Self {
         let mut rng = thread_rng();
         let value = rng.gen_bigint_range(&BigInt::zero(), &self.value);
@@ -237,10 +287,24 @@ impl RustBigInt {
 
     /// Determine if `self` is a prime number
     pub fn is_prime(&self) -> bool {
+        self.is_prime_rounds(PRIME_ROUNDS)
+    }
+
+    /// Determine if `self` is a prime number with `rounds` of Miller-Rabin
+    #[cfg(feature = "std")]
+    pub fn is_prime_rounds(&self, rounds: usize) -> bool {
         if self.value.is_negative() {
             return false;
         }
-        glass_pumpkin::prime::check(&self.value.to_biguint().unwrap())
+        is_probable_prime(&self.value.to_biguint().unwrap(), rounds)
+    }
+
+    /// Determine if `self` is a prime number. Without an entropy source
+    /// there are no random Miller-Rabin bases, so this ignores `rounds`
+    /// and runs the deterministic Baillie-PSW test
+    #[cfg(not(feature = "std"))]
+    pub fn is_prime_rounds(&self, _rounds: usize) -> bool {
+        super::baillie_psw(self)
     }
 
     /// Computes Bézout's coefficients and returns `s` and `t`
@@ -308,12 +372,237 @@ impl RustBigInt {
 
     /// Serialize to big-endian byte array
     pub fn to_bytes(&self) -> Vec<u8> {
-        let (_, r) = self.value.to_bytes_be();
+        magnitude_bytes(&self.value)
+    }
+}
+
+/// The non-negative remainder `a mod |m|` like OpenSSL's BN_nnmod
+fn nnmod(a: &BigInt, m: &BigInt) -> BigInt {
+    let r = a % m;
+    if r.is_negative() {
+        r + m.abs()
+    } else {
         r
     }
 }
 
-impl std::iter::Product<RustBigInt> for RustBigInt {
+/// Big-endian magnitude, zero is the empty array like OpenSSL's BN_bn2bin
+fn magnitude_bytes(value: &BigInt) -> Vec<u8> {
+    if value.is_zero() {
+        return Vec::new();
+    }
+    let (_, r) = value.to_bytes_be();
+    r
+}
+
+/// A random odd number of `size` bits with the top two bits set
+/// so the product of two such numbers has exactly `2 * size` bits
+#[cfg(feature = "std")]
+fn random_candidate(size: usize) -> BigUint {
+    let mut rng = thread_rng();
+    let top = BigUint::from(3u32) << (size - 2);
+    (rng.gen_biguint(size as u64) >> 2) | top | BigUint::one()
+}
+
+/// True if `n` is divisible by one of the `SMALL_PRIMES` other than itself
+#[cfg(feature = "std")]
+fn has_small_factor(n: &BigUint) -> bool {
+    SMALL_PRIMES.iter().any(|p| (n % *p).is_zero() && *n != BigUint::from(*p))
+}
+
+#[cfg(feature = "std")]
+fn is_probable_prime(n: &BigUint, rounds: usize) -> bool {
+    let two = BigUint::from(2u32);
+    if *n < two {
+        return false;
+    }
+    if *n == two {
+        return true;
+    }
+    if n.is_even() || has_small_factor(n) {
+        return false;
+    }
+    miller_rabin(n, rounds)
+}
+
+/// Miller-Rabin with `rounds` random bases, `n` must be odd
+#[cfg(feature = "std")]
+fn miller_rabin(n: &BigUint, rounds: usize) -> bool {
+    let one = BigUint::one();
+    let two = BigUint::from(2u32);
+    if *n <= BigUint::from(3u32) {
+        return *n > one;
+    }
+    let n_1 = n - &one;
+    let mut d = n_1.clone();
+    let mut s = 0usize;
+    while d.is_even() {
+        d >>= 1;
+        s += 1;
+    }
+    let mut rng = thread_rng();
+    'witness: for _ in 0..rounds {
+        let a = rng.gen_biguint_range(&two, &n_1);
+        let mut x = a.modpow(&d, n);
+        if x == one || x == n_1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = x.modpow(&two, n);
+            if x == n_1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// Montgomery form for an odd modulus `n` of `k` 64 bit words with
+/// `R = 2^(64k)`. A value `a` is kept as the words of `aR mod n` and
+/// products are reduced word by word instead of with a division
+#[derive(Debug)]
+struct Montgomery {
+    n: Vec<u64>,
+    /// -n^-1 mod 2^64
+    n0: u64,
+    /// R^2 mod n
+    r2: Vec<u64>,
+}
+
+impl Montgomery {
+    fn new(n: &BigUint) -> Self {
+        let words = n.to_u64_digits();
+        // Each Newton step doubles the correct low bits of n^-1 mod 2^64,
+        // an odd n makes the first bit correct
+        let mut inv = 1u64;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(words[0].wrapping_mul(inv)));
+        }
+        let r2 = (BigUint::one() << (128 * words.len())) % n;
+        Self { r2: Self::pad(&r2, words.len()), n0: inv.wrapping_neg(), n: words }
+    }
+
+    /// The words of `a < n`
+    fn pad(a: &BigUint, k: usize) -> Vec<u64> {
+        let mut words = a.to_u64_digits();
+        words.resize(k, 0);
+        words
+    }
+
+    /// a * b / R mod n, the product and reduction interleaved (CIOS)
+    fn mul(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let k = self.n.len();
+        let n = &self.n[..];
+        let b = &b[..k];
+        let mut t = vec![0u64; k + 1];
+        for &ai in a {
+            let mut carry = 0u64;
+            for (tj, &bj) in t.iter_mut().zip(b) {
+                let s = *tj as u128 + ai as u128 * bj as u128 + carry as u128;
+                *tj = s as u64;
+                carry = (s >> 64) as u64;
+            }
+            let s = t[k] as u128 + carry as u128;
+            t[k] = s as u64;
+            let top = (s >> 64) as u64;
+
+            // Add a multiple of n that clears the low word and shift it out
+            let m = t[0].wrapping_mul(self.n0);
+            let mut carry = ((t[0] as u128 + m as u128 * n[0] as u128) >> 64) as u64;
+            for j in 1..k {
+                let s = t[j] as u128 + m as u128 * n[j] as u128 + carry as u128;
+                t[j - 1] = s as u64;
+                carry = (s >> 64) as u64;
+            }
+            let s = t[k] as u128 + carry as u128;
+            t[k - 1] = s as u64;
+            t[k] = top + (s >> 64) as u64;
+        }
+        // t < 2n
+        if t[k] != 0 || t[..k].iter().rev().cmp(n.iter().rev()) != Ordering::Less {
+            let mut borrow = false;
+            for (tj, &nj) in t.iter_mut().zip(n) {
+                let (d, b1) = tj.overflowing_sub(nj);
+                let (d, b2) = d.overflowing_sub(borrow as u64);
+                *tj = d;
+                borrow = b1 || b2;
+            }
+        }
+        t.truncate(k);
+        t
+    }
+
+    /// Into Montgomery form, a * R mod n for `a` in `[0, n)`
+    fn enter(&self, a: &BigUint) -> Vec<u64> {
+        self.mul(&Self::pad(a, self.n.len()), &self.r2)
+    }
+
+    /// Out of Montgomery form, a / R mod n
+    fn leave(&self, a: &[u64]) -> BigUint {
+        let mut one = vec![0u64; self.n.len()];
+        one[0] = 1;
+        let words = self.mul(a, &one);
+        BigUint::from_slice(&words.iter().flat_map(|w| [*w as u32, (*w >> 32) as u32]).collect::<Vec<u32>>())
+    }
+}
+
+/// Repeated operations against one modulus. The Montgomery parameters of
+/// an odd modulus are computed once and used by `multi_exp`, where every
+/// base shares them. A single product is cheaper reduced directly.
+#[derive(Debug)]
+pub struct RustModulusContext {
+    modulus: RustBigInt,
+    mont: Option<Montgomery>,
+}
+
+impl RustModulusContext {
+    /// Create a context for `modulus`
+    pub fn new(modulus: &RustBigInt) -> Self {
+        let odd = modulus.value.is_odd() && modulus.value > BigInt::one();
+        Self {
+            modulus: modulus.clone(),
+            mont: if odd { Some(Montgomery::new(modulus.value.magnitude())) } else { None },
+        }
+    }
+
+    /// base ^ exponent mod modulus. num-bigint's own exponentiation also
+    /// works in Montgomery form, with a larger window than `multi_exp`
+    pub fn exp(&self, base: &RustBigInt, exponent: &RustBigInt) -> RustBigInt {
+        base.mod_exp(exponent, &self.modulus)
+    }
+
+    /// a * b mod modulus
+    pub fn mul(&self, a: &RustBigInt, b: &RustBigInt) -> RustBigInt {
+        a.mod_mul(b, &self.modulus)
+    }
+
+    /// b_1^e_1 * b_2^e_2 * ... mod modulus, in Montgomery form for an odd modulus
+    pub fn multi_exp(&self, pairs: &[(&RustBigInt, &RustBigInt)]) -> RustBigInt {
+        match &self.mont {
+            Some(mont) => {
+                let words = super::multi_exp(
+                    pairs,
+                    &self.modulus,
+                    mont.enter(&BigUint::one()),
+                    |b| mont.enter(b.value.magnitude()),
+                    |a, b| mont.mul(a, b),
+                );
+                Self::wrap(mont.leave(&words))
+            }
+            None => {
+                let one = &RustBigInt::from(1u32) % &self.modulus;
+                super::multi_exp(pairs, &self.modulus, one, RustBigInt::clone, |a, b| self.mul(a, b))
+            }
+        }
+    }
+
+    fn wrap(value: BigUint) -> RustBigInt {
+        RustBigInt { value: BigInt::from(value) }
+    }
+}
+
+impl core::iter::Product<RustBigInt> for RustBigInt {
     fn product<I: Iterator<Item=RustBigInt>>(iter: I) -> Self {
         let mut value = BigInt::one();
         for i in iter {
@@ -323,7 +612,7 @@ impl std::iter::Product<RustBigInt> for RustBigInt {
     }
 }
 
-impl<'a> std::iter::Product<&'a RustBigInt> for RustBigInt {
+impl<'a> core::iter::Product<&'a RustBigInt> for RustBigInt {
     fn product<I: Iterator<Item=&'a RustBigInt>>(iter: I) -> Self {
         let mut value = BigInt::one();
         for i in iter {
@@ -333,7 +622,7 @@ impl<'a> std::iter::Product<&'a RustBigInt> for RustBigInt {
     }
 }
 
-impl std::iter::Sum<RustBigInt> for RustBigInt {
+impl core::iter::Sum<RustBigInt> for RustBigInt {
     fn sum<I: Iterator<Item=RustBigInt>>(iter: I) -> Self {
         let mut value = BigInt::zero();
         for i in iter {
@@ -343,7 +632,7 @@ impl std::iter::Sum<RustBigInt> for RustBigInt {
     }
 }
 
-impl<'a> std::iter::Sum<&'a RustBigInt> for RustBigInt {
+impl<'a> core::iter::Sum<&'a RustBigInt> for RustBigInt {
     fn sum<I: Iterator<Item=&'a RustBigInt>>(iter: I) -> Self {
         let mut value = BigInt::zero();
         for i in iter {
@@ -355,7 +644,15 @@ impl<'a> std::iter::Sum<&'a RustBigInt> for RustBigInt {
 
 impl Zeroize for RustBigInt {
     fn zeroize(&mut self) {
-        self.value.set_zero();
+        // num-bigint doesn't expose its digits, so clear them bit by bit
+        // from the lowest up. The top digit is the last one to become zero
+        // so the buffer is only shrunk or freed once it holds nothing
+        if self.value.sign() == Sign::Minus {
+            self.value = -core::mem::take(&mut self.value);
+        }
+        for bit in 0..self.value.bits() {
+            self.value.set_bit(bit, false);
+        }
     }
 }
 
@@ -424,8 +721,7 @@ impl From<Vec<u8>> for RustBigInt {
 
 impl Into<Vec<u8>> for RustBigInt {
     fn into(self) -> Vec<u8> {
-        let (_, r) = self.value.to_bytes_be();
-        r
+        magnitude_bytes(&self.value)
     }
 }
 
@@ -0,0 +1,66 @@
+use crate::error::AccumulatorError;
+
+// Accumulator schemes differ in what they can do. Each ability is its own
+// trait so a type only implements the ones it supports and generic code
+// states what it needs with bounds, instead of calling a method that
+// always fails at runtime. An add-only accumulator implements just
+// `Accumulate`, a scheme with no trapdoor leaves out `RequiresTrapdoor`
+
+/// Adding members and creating their witnesses, which every accumulator supports
+pub trait Accumulate {
+    /// The values that are accumulated
+    type Member: ?Sized;
+    /// Shows a member is in the accumulator
+    type Witness;
+
+    /// Add `member`
+    fn accumulate(&mut self, member: &Self::Member) -> Result<(), AccumulatorError>;
+
+    /// True if `member` has been added and not removed
+    fn is_member(&self, member: &Self::Member) -> Result<bool, AccumulatorError>;
+
+    /// The witness for `member` at the current state
+    fn membership_witness(&self, member: &Self::Member) -> Result<Self::Witness, AccumulatorError>;
+}
+
+/// Removing members, the accumulator is dynamic. Anyone holding the
+/// current witness of a member can remove it
+pub trait Dynamic: Accumulate {
+    /// Remove the member of `witness`
+    fn delete(&mut self, witness: &Self::Witness) -> Result<(), AccumulatorError>;
+}
+
+/// Witnesses that a value is not a member, the accumulator is universal
+pub trait Universal: Accumulate {
+    /// Shows a value is not in the accumulator
+    type NonMembershipWitness;
+
+    /// The non-membership witness for `value` at the current state
+    fn non_membership_witness(
+        &self,
+        value: &Self::Member,
+    ) -> Result<Self::NonMembershipWitness, AccumulatorError>;
+}
+
+/// The accumulator was set up with a secret trapdoor. Whoever holds it
+/// can remove members and create witnesses without the other members,
+/// so verifiers have to trust the holder. Trustless accumulators don't
+/// implement this
+pub trait RequiresTrapdoor: Accumulate {
+    /// The secret trapdoor
+    type Trapdoor: ?Sized;
+
+    /// Remove `member` with the trapdoor
+    fn delete_with_trapdoor(
+        &mut self,
+        trapdoor: &Self::Trapdoor,
+        member: &Self::Member,
+    ) -> Result<(), AccumulatorError>;
+
+    /// The witness for `member` created with the trapdoor
+    fn membership_witness_with_trapdoor(
+        &self,
+        trapdoor: &Self::Trapdoor,
+        member: &Self::Member,
+    ) -> Result<Self::Witness, AccumulatorError>;
+}
@@ -1,76 +1,135 @@
-use failure::{Backtrace, Context, Fail};
+#[cfg(not(feature = "std"))]
+use alloc::{format, string::{String, ToString}};
+use core::fmt;
 
 /// The error types
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Fail)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[non_exhaustive]
 pub enum AccumulatorErrorKind {
     /// Type cannot be converted to an BigInteger
-    #[fail(display = "Type cannot be converted to BigInteger")]
     InvalidType,
     /// When trying to add a member that already exists in the accumulator
-    #[fail(display = "The value supplied already exists in the accumulator")]
     DuplicateValueSupplied,
     /// When trying to create a witness to a value not in the accumulator
     /// or when trying to remove an invalid value from the accumulator
-    #[fail(display = "Member is not currently in the accumulator")]
     InvalidMemberSupplied,
     /// An incorrect number of bytes was supplied when trying to deserialize from bytes
-    #[fail(display = "Invalid bytes supplied when deserializing")]
     SerializationError,
+    /// A long running operation was stopped by the caller
+    Cancelled,
+    /// The input has the wrong number of bytes. For variable length
+    /// encodings `expected` is the minimum
+    InvalidLength {
+        /// The number of bytes required
+        expected: usize,
+        /// The number of bytes supplied
+        actual: usize,
+    },
+    /// A proof or transcript did not verify
+    ProofVerificationFailed,
+    /// The nonce does not match the one the proof was created with
+    InvalidNonce,
+    /// Values were created for different parameters, like another
+    /// modulus, generator or set of parties
+    ParameterMismatch,
+    /// The operation needs the accumulator secret key
+    TrapdoorRequired,
+    /// A value was created for a different accumulator epoch
+    EpochMismatch {
+        /// The epoch of the accumulator
+        expected: u64,
+        /// The epoch of the supplied value
+        actual: u64,
+    },
+    /// The value has no inverse for the modulus
+    NotInvertible,
+    /// Reading or writing the member store failed
+    StorageError,
+    /// A thread panicked while holding a lock on shared state
+    Poisoned,
 }
 
-/// Error wrapper to add context and backtrace
-#[derive(Debug)]
-pub struct AccumulatorError {
-    inner: Context<AccumulatorErrorKind>,
+impl fmt::Display for AccumulatorErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AccumulatorErrorKind::InvalidType => f.write_str("Type cannot be converted to BigInteger"),
+            AccumulatorErrorKind::DuplicateValueSupplied => f.write_str("The value supplied already exists in the accumulator"),
+            AccumulatorErrorKind::InvalidMemberSupplied => f.write_str("Member is not currently in the accumulator"),
+            AccumulatorErrorKind::SerializationError => f.write_str("Invalid bytes supplied when deserializing"),
+            AccumulatorErrorKind::Cancelled => f.write_str("The operation was cancelled"),
+            AccumulatorErrorKind::InvalidLength { expected, actual } => {
+                write!(f, "Invalid length, expected {} bytes, found {}", expected, actual)
+            }
+            AccumulatorErrorKind::ProofVerificationFailed => f.write_str("The proof is not valid"),
+            AccumulatorErrorKind::InvalidNonce => f.write_str("The nonce does not match"),
+            AccumulatorErrorKind::ParameterMismatch => f.write_str("The values were created with different parameters"),
+            AccumulatorErrorKind::TrapdoorRequired => f.write_str("The secret key is required"),
+            AccumulatorErrorKind::EpochMismatch { expected, actual } => {
+                write!(f, "Expected accumulator epoch {}, found {}", expected, actual)
+            }
+            AccumulatorErrorKind::NotInvertible => f.write_str("The value is not invertible for the modulus"),
+            AccumulatorErrorKind::StorageError => f.write_str("The member store failed"),
+            AccumulatorErrorKind::Poisoned => f.write_str("A thread panicked while holding the lock"),
+        }
+    }
 }
 
-impl Fail for AccumulatorError {
-    fn cause(&self) -> Option<&dyn Fail> {
-        self.inner.cause()
-    }
+#[cfg(feature = "std")]
+impl std::error::Error for AccumulatorErrorKind {}
 
-    fn backtrace(&self) -> Option<&Backtrace> {
-        self.inner.backtrace()
-    }
+/// An error kind with a message and the underlying error if there is one
+#[derive(Debug)]
+pub struct AccumulatorError {
+    kind: AccumulatorErrorKind,
+    msg: String,
+    #[cfg(feature = "std")]
+    source: Option<Box<dyn std::error::Error + Send + Sync + 'static>>,
 }
 
 impl AccumulatorError {
     /// Convert from a kind with msg string
-    pub fn from_msg<D>(kind: AccumulatorErrorKind, msg: D) -> AccumulatorError
-        where
-            D: std::fmt::Display + std::fmt::Debug + Send + Sync + 'static,
+    pub fn from_msg<D: fmt::Display>(kind: AccumulatorErrorKind, msg: D) -> AccumulatorError {
+        AccumulatorError {
+            kind,
+            msg: msg.to_string(),
+            #[cfg(feature = "std")]
+            source: None,
+        }
+    }
+
+    /// Convert from a kind with the error that caused it
+    #[cfg(feature = "std")]
+    pub fn from_source<E>(kind: AccumulatorErrorKind, err: E) -> AccumulatorError
+    where
+        E: std::error::Error + Send + Sync + 'static,
     {
         AccumulatorError {
-            inner: Context::new(msg).context(kind),
+            kind,
+            msg: err.to_string(),
+            source: Some(Box::new(err)),
         }
     }
 
     /// Get the inner error kind
     pub fn kind(&self) -> AccumulatorErrorKind {
-        *self.inner.get_context()
+        self.kind
     }
 }
 
-impl std::fmt::Display for AccumulatorError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let mut first = true;
-
-        for cause in Fail::iter_chain(&self.inner) {
-            if first {
-                first = false;
-                writeln!(f, "Error: {}", cause)?;
-            } else {
-                writeln!(f, "Caused by: {}", cause)?;
-            }
+impl fmt::Display for AccumulatorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.msg.is_empty() {
+            write!(f, "{}", self.kind)
+        } else {
+            write!(f, "{}: {}", self.kind, self.msg)
         }
-
-        Ok(())
     }
 }
 
-impl From<Context<AccumulatorErrorKind>> for AccumulatorError {
-    fn from(inner: Context<AccumulatorErrorKind>) -> Self {
-        AccumulatorError { inner }
+#[cfg(feature = "std")]
+impl std::error::Error for AccumulatorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_ref().map(|e| e.as_ref() as &(dyn std::error::Error + 'static))
     }
 }
 
@@ -83,7 +142,7 @@ impl From<AccumulatorErrorKind> for AccumulatorError {
 #[cfg(feature = "openssl")]
 impl From<openssl::error::ErrorStack> for AccumulatorError {
     fn from(err: openssl::error::ErrorStack) -> Self {
-        AccumulatorError::from_msg(AccumulatorErrorKind::InvalidType, err.errors().iter().map(|e| e.reason().unwrap_or("")).collect::<Vec<&str>>().join(","))
+        AccumulatorError::from_source(AccumulatorErrorKind::InvalidType, err)
     }
 }
 
@@ -96,7 +155,42 @@ impl From<gmp::mpz::ParseMpzError> for AccumulatorError {
 
 #[cfg(feature = "bi-rust")]
 impl From<num_bigint::ParseBigIntError> for AccumulatorError {
+    #[cfg(feature = "std")]
+    fn from(err: num_bigint::ParseBigIntError) -> Self {
+        AccumulatorError::from_source(AccumulatorErrorKind::InvalidType, err)
+    }
+
+    #[cfg(not(feature = "std"))]
     fn from(err: num_bigint::ParseBigIntError) -> Self {
         AccumulatorError::from_msg(AccumulatorErrorKind::InvalidType, format!("{:?}", err))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn display_and_source() {
+        let err: AccumulatorError = AccumulatorErrorKind::Cancelled.into();
+        assert_eq!(err.kind(), AccumulatorErrorKind::Cancelled);
+        assert_eq!(err.to_string(), "The operation was cancelled");
+        assert!(err.source().is_none());
+
+        let err: AccumulatorError = AccumulatorErrorKind::InvalidLength { expected: 64, actual: 3 }.into();
+        assert_eq!(err.to_string(), "Invalid length, expected 64 bytes, found 3");
+        match err.kind() {
+            AccumulatorErrorKind::InvalidLength { expected, actual } => assert_eq!((expected, actual), (64, 3)),
+            _ => panic!("unexpected kind"),
+        }
+
+        let err = AccumulatorError::from_msg(AccumulatorErrorKind::SerializationError, "expected 4 bytes");
+        assert_eq!(err.to_string(), "Invalid bytes supplied when deserializing: expected 4 bytes");
+
+        let cause = std::io::Error::other("token removed");
+        let err = AccumulatorError::from_source(AccumulatorErrorKind::InvalidType, cause);
+        assert_eq!(err.kind(), AccumulatorErrorKind::InvalidType);
+        assert_eq!(err.source().unwrap().to_string(), "token removed");
+    }
+}
@@ -7,8 +7,15 @@ unused_lifetimes,
 unused_qualifications,
 )]
 #![cfg_attr(feature = "nightly", feature(doc_cfg))]
+#![cfg_attr(not(feature = "std"), no_std)]
 //! This crate is only meant to be used internally across
 //! accumulator types.
+//!
+//! Without the default `std` feature the crate builds with `alloc` only.
+//! That requires the pure rust `bi-rust` backend.
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
 
 /// Common macros
 pub mod macros;
@@ -16,39 +23,339 @@ pub mod macros;
 pub mod bigint;
 /// Accumulator errors that can be thrown
 pub mod error;
+/// Traits for the operations an accumulator supports
+pub mod capability;
 
-#[cfg(not(any(feature = "openssl", feature = "rust-gmp", feature = "num-bigint")))]
-compile_error!("A big number library must be chosen: either bigint-rust, openssl, or rust-gmp");
-#[cfg(any(all(feature = "openssl", feature = "rust-gmp", feature = "bigint-rust"),
+#[cfg(not(any(feature = "openssl", feature = "rust-gmp", feature = "num-bigint", feature = "rug", feature = "crypto-bigint")))]
+compile_error!("A big number library must be chosen: either bi-rust, openssl, rust-gmp, rug, or crypto-bigint");
+#[cfg(any(all(feature = "openssl", feature = "rust-gmp", feature = "bi-rust"),
 all(feature = "openssl", feature = "rust-gmp"),
-all(feature = "openssl", feature = "bigint-rust"),
-all(feature = "bigint-rust", feature = "rust-gmp")))]
-compile_error!("Only one big number library must be chosen: either bigint-rust, openssl, or rust-gmp");
+all(feature = "openssl", feature = "bi-rust"),
+all(feature = "bi-rust", feature = "rust-gmp"),
+all(feature = "rug", any(feature = "openssl", feature = "rust-gmp", feature = "bi-rust")),
+all(feature = "crypto-bigint", any(feature = "openssl", feature = "rust-gmp", feature = "bi-rust", feature = "rug"))))]
+compile_error!("Only one big number library must be chosen: either bi-rust, openssl, rust-gmp, rug, or crypto-bigint");
+
+#[cfg(all(target_arch = "wasm32", any(feature = "openssl", feature = "rust-gmp", feature = "rug")))]
+compile_error!("OpenSSL and GMP are not available on wasm32, use bi-rust or bi-ct");
 
-use bigint::BigInteger;
+use bigint::{BigInteger, ModulusContext};
+use error::{AccumulatorError, AccumulatorErrorKind};
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+use core::convert::TryFrom;
 
-/// Helper class that always reduces operations by a modulus
+/// Helper class that always reduces operations by a modulus.
+/// Whatever per modulus state the backend has is built once and reused:
+/// the Montgomery parameters of an odd modulus with `bi-ct`, OpenSSL and
+/// num-bigint. GMP keeps none.
 #[derive(Debug)]
 pub struct Field {
-    modulus: BigInteger
+    modulus: BigInteger,
+    ctx: ModulusContext,
 }
 
 impl Field {
     /// Construct a new field
     pub fn new(modulus: &BigInteger) -> Self {
-        Self { modulus: modulus.clone() }
+        Self { modulus: modulus.clone(), ctx: ModulusContext::new(modulus) }
     }
 
     /// b^e mod r
     pub fn exp(&self, base: &BigInteger, exp: &BigInteger) -> BigInteger {
-        base.mod_exp(exp, &self.modulus)
+        self.ctx.exp(base, exp)
     }
 
     /// (a * b) mod r
     pub fn mul(&self, a: &BigInteger, b: &BigInteger) -> BigInteger {
-        a.mod_mul(&b, &self.modulus)
+        self.ctx.mul(a, b)
+    }
+
+    /// a^-1 mod r. Fails if a and r are not coprime.
+    pub fn inv(&self, a: &BigInteger) -> Result<BigInteger, AccumulatorError> {
+        a.checked_mod_inverse(&self.modulus)
+            .ok_or_else(|| AccumulatorErrorKind::NotInvertible.into())
+    }
+
+    /// a_i^-1 mod r for every value using Montgomery's trick:
+    /// a single inversion plus 3(n - 1) multiplications.
+    /// Fails if any value is not invertible.
+    pub fn inv_batch(&self, values: &[BigInteger]) -> Result<Vec<BigInteger>, AccumulatorError> {
+        if values.is_empty() {
+            return Ok(Vec::new());
+        }
+        // prefix[i] = a_0 * a_1 * ... * a_i
+        let mut prefix = Vec::with_capacity(values.len());
+        prefix.push(values[0].clone());
+        for v in &values[1..] {
+            let p = self.mul(&prefix[prefix.len() - 1], v);
+            prefix.push(p);
+        }
+        let mut inv = self.inv(&prefix[prefix.len() - 1])?;
+        let mut result = vec![BigInteger::default(); values.len()];
+        for i in (1..values.len()).rev() {
+            result[i] = self.mul(&inv, &prefix[i - 1]);
+            inv = self.mul(&inv, &values[i]);
+        }
+        result[0] = inv;
+        Ok(result)
+    }
+
+    /// b_1^e_1 * b_2^e_2 * ... mod r.
+    /// All bases share one chain of squarings (Straus' method), each adds
+    /// a multiplication from its table of small powers per window of
+    /// exponent bits. With `bi-ct` the chain runs in constant time.
+    pub fn multi_exp(&self, pairs: &[(&BigInteger, &BigInteger)]) -> BigInteger {
+        self.ctx.multi_exp(pairs)
+    }
+
+    /// The Jacobi symbol (a / r). The modulus must be odd.
+    pub fn jacobi(&self, a: &BigInteger) -> i32 {
+        let zero = BigInteger::default();
+        let two = BigInteger::from(2u32);
+        let three = BigInteger::from(3u32);
+        let four = BigInteger::from(4u32);
+        let five = BigInteger::from(5u32);
+        let eight = BigInteger::from(8u32);
+        let mut a = a % &self.modulus;
+        let mut n = self.modulus.clone();
+        let mut result = 1;
+        while a != zero {
+            while &a % &two == zero {
+                a = &a / &two;
+                let r = &n % &eight;
+                if r == three || r == five {
+                    result = -result;
+                }
+            }
+            core::mem::swap(&mut a, &mut n);
+            if &a % &four == three && &n % &four == three {
+                result = -result;
+            }
+            a = &a % &n;
+        }
+        if n == BigInteger::from(1u32) {
+            result
+        } else {
+            0
+        }
+    }
+
+    /// Is `a` a nonzero square mod r. Exact when r is an odd prime,
+    /// for composite moduli a Jacobi symbol of 1 does not imply a square.
+    pub fn is_quadratic_residue(&self, a: &BigInteger) -> bool {
+        self.jacobi(a) == 1
+    }
+
+    /// A square root of `a` mod r using Tonelli-Shanks.
+    /// The modulus must be an odd prime. Returns `None` if `a` is not a square.
+    pub fn sqrt_mod(&self, a: &BigInteger) -> Option<BigInteger> {
+        let zero = BigInteger::default();
+        let one = BigInteger::from(1u32);
+        let two = BigInteger::from(2u32);
+        let four = BigInteger::from(4u32);
+        let a = a % &self.modulus;
+        if a == zero {
+            return Some(zero);
+        }
+        if !self.is_quadratic_residue(&a) {
+            return None;
+        }
+        let p_1 = &self.modulus - &one;
+        // r = 3 mod 4 has the direct solution a^((r + 1) / 4)
+        if &self.modulus % &four == BigInteger::from(3u32) {
+            return Some(self.exp(&a, &(&(&self.modulus + &one) / &four)));
+        }
+        // r - 1 = q * 2^s with q odd
+        let mut s = 0;
+        let mut q = p_1.clone();
+        while &q % &two == zero {
+            q = &q / &two;
+            s += 1;
+        }
+        let mut z = BigInteger::from(2u32);
+        while self.jacobi(&z) != -1 {
+            z = &z + &one;
+        }
+        let mut m = s;
+        let mut c = self.exp(&z, &q);
+        let mut t = self.exp(&a, &q);
+        let mut root = self.exp(&a, &(&(&q + &one) / &two));
+        while t != one {
+            // Find the least i with t^(2^i) = 1
+            let mut i = 0;
+            let mut t2 = t.clone();
+            while t2 != one {
+                t2 = self.mul(&t2, &t2);
+                i += 1;
+            }
+            let mut b = c;
+            for _ in 0..(m - i - 1) {
+                b = self.mul(&b, &b);
+            }
+            m = i;
+            c = self.mul(&b, &b);
+            t = self.mul(&t, &c);
+            root = self.mul(&root, &b);
+        }
+        Some(root)
+    }
+}
+/// Exponent bits covered by each entry of a `FixedBase` table
+const FIXED_BASE_WINDOW: usize = 4;
+
+/// Precomputed powers `base^(16^i) mod r` for repeated exponentiations
+/// with the same base. Uses Yao's method so an exponentiation costs about
+/// one multiplication per 4 exponent bits instead of a squaring per bit.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FixedBase {
+    modulus: BigInteger,
+    powers: Vec<BigInteger>,
+}
+
+impl FixedBase {
+    /// Precompute the table for exponents up to `bits` long.
+    /// Longer exponents still work, their excess bits use a regular exponentiation.
+    pub fn new(base: &BigInteger, modulus: &BigInteger, bits: usize) -> Self {
+        let f = Field::new(modulus);
+        let count = core::cmp::max(1, bits.div_ceil(FIXED_BASE_WINDOW));
+        let mut powers = Vec::with_capacity(count);
+        powers.push(base.clone());
+        for i in 1..count {
+            let mut p = powers[i - 1].clone();
+            for _ in 0..FIXED_BASE_WINDOW {
+                p = f.mul(&p, &p);
+            }
+            powers.push(p);
+        }
+        Self { modulus: modulus.clone(), powers }
+    }
+
+    /// The base of this table
+    pub fn base(&self) -> &BigInteger {
+        &self.powers[0]
+    }
+
+    /// The modulus of this table
+    pub fn modulus(&self) -> &BigInteger {
+        &self.modulus
+    }
+
+    /// The longest exponent covered by the table
+    pub fn bits(&self) -> usize {
+        self.powers.len() * FIXED_BASE_WINDOW
+    }
+
+    /// base^e mod r, gives the same result as `BigInteger::mod_exp`
+    pub fn exp(&self, e: &BigInteger) -> BigInteger {
+        if e <= &BigInteger::from(1u32) {
+            return self.base().mod_exp(e, &self.modulus);
+        }
+        let f = Field::new(&self.modulus);
+        let bytes = e.to_bytes();
+        let digit = |i: usize| (bytes[bytes.len() - 1 - i / 2] >> (4 * (i % 2))) & 0xF;
+        let digits = e.bits().div_ceil(FIXED_BASE_WINDOW);
+        let covered = core::cmp::min(digits, self.powers.len());
+
+        let one = &BigInteger::from(1u32) % &self.modulus;
+        let mut result = one.clone();
+        let mut acc = one;
+        let mut started = false;
+        for d in (1..(1u8 << FIXED_BASE_WINDOW)).rev() {
+            for i in 0..covered {
+                if digit(i) == d {
+                    acc = f.mul(&acc, &self.powers[i]);
+                    started = true;
+                }
+            }
+            if started {
+                result = f.mul(&result, &acc);
+            }
+        }
+
+        if digits > covered {
+            let shift = covered * FIXED_BASE_WINDOW;
+            let mut top = self.powers[covered - 1].clone();
+            for _ in 0..FIXED_BASE_WINDOW {
+                top = f.mul(&top, &top);
+            }
+            let mut divisor = vec![0u8; shift / 8 + 1];
+            divisor[0] = 1 << (shift % 8);
+            let high = e / &BigInteger::try_from(divisor.as_slice()).unwrap();
+            result = f.mul(&result, &f.exp(&top, &high));
+        }
+        result
     }
 
-    /// a^-1 mod r
-    pub fn inv(&self, a: &BigInteger) -> BigInteger { a.mod_inverse(&self.modulus) }
-}
\ No newline at end of file
+    /// Serialize to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let size = self.modulus.to_bytes().len();
+        let mut output = Vec::with_capacity(8 + size * (self.powers.len() + 1));
+        output.extend_from_slice(&(size as u32).to_be_bytes());
+        output.extend_from_slice(&(self.powers.len() as u32).to_be_bytes());
+        for v in core::iter::once(&self.modulus).chain(self.powers.iter()) {
+            let b = v.to_bytes();
+            output.resize(output.len() + size - b.len(), 0);
+            output.extend_from_slice(b.as_slice());
+        }
+        output
+    }
+}
+
+impl TryFrom<&[u8]> for FixedBase {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        if data.len() < 8 {
+            return Err(AccumulatorErrorKind::SerializationError.into());
+        }
+        let mut size = [0u8; 4];
+        let mut count = [0u8; 4];
+        size.copy_from_slice(&data[..4]);
+        count.copy_from_slice(&data[4..8]);
+        let size = u32::from_be_bytes(size) as usize;
+        let count = u32::from_be_bytes(count) as usize;
+        if size == 0 || count == 0 {
+            return Err(AccumulatorErrorKind::SerializationError.into());
+        }
+        // Both come from the input, on 32 bit targets the length overflows
+        let expected = count
+            .checked_add(1)
+            .and_then(|c| c.checked_mul(size))
+            .and_then(|l| l.checked_add(8))
+            .unwrap_or(usize::MAX);
+        if data.len() != expected {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected,
+                actual: data.len(),
+            }
+            .into());
+        }
+        let mut values = data[8..]
+            .chunks(size)
+            .map(BigInteger::try_from)
+            .collect::<Result<Vec<BigInteger>, AccumulatorError>>()?;
+        let modulus = values.remove(0);
+        if modulus <= BigInteger::from(1u32) || values.iter().any(|v| v >= &modulus) {
+            return Err(AccumulatorErrorKind::SerializationError.into());
+        }
+        // A bad entry would silently give wrong exponentiations,
+        // so every entry is checked against the one before it
+        let f = Field::new(&modulus);
+        for w in values.windows(2) {
+            let mut p = w[0].clone();
+            for _ in 0..FIXED_BASE_WINDOW {
+                p = f.mul(&p, &p);
+            }
+            if p != w[1] {
+                return Err(AccumulatorError::from_msg(
+                    AccumulatorErrorKind::SerializationError,
+                    "table entries are not successive powers of the base",
+                ));
+            }
+        }
+        Ok(Self { modulus, powers: values })
+    }
+}
+
+serdes_impl!(FixedBase);
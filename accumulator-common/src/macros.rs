@@ -1,4 +1,50 @@
-/// Implement Serialization methods based on TryFrom
+use crate::error::AccumulatorError;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[doc(hidden)]
+#[cfg(not(feature = "std"))]
+pub use alloc::string::String;
+#[doc(hidden)]
+#[cfg(feature = "std")]
+pub use std::string::String;
+#[doc(hidden)]
+pub use hex;
+
+/// The output of a `to_bytes` method, which may fail
+/// if a value does not fit its fixed size encoding
+#[doc(hidden)]
+pub trait IntoBytes {
+    /// Get the bytes or the encoding error
+    fn into_bytes(self) -> Result<Vec<u8>, AccumulatorError>;
+}
+
+impl IntoBytes for Vec<u8> {
+    fn into_bytes(self) -> Result<Vec<u8>, AccumulatorError> {
+        Ok(self)
+    }
+}
+
+impl IntoBytes for Result<Vec<u8>, AccumulatorError> {
+    fn into_bytes(self) -> Result<Vec<u8>, AccumulatorError> {
+        self
+    }
+}
+
+/// Collect bytes some formats give as a sequence of integers
+#[doc(hidden)]
+pub fn read_seq<'a, A: serde::de::SeqAccess<'a>>(mut seq: A) -> Result<Vec<u8>, A::Error> {
+    // Don't trust the hint with a large allocation
+    let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+    while let Some(b) = seq.next_element::<u8>()? {
+        bytes.push(b);
+    }
+    Ok(bytes)
+}
+
+/// Implement Serialization methods based on TryFrom and `to_bytes`.
+/// Human readable formats like JSON get a hex string, others get the raw
+/// bytes as one byte string, never a sequence of integers. Sequences are
+/// still read for formats that write bytes that way.
 #[macro_export]
 macro_rules! serdes_impl {
     ($name:ident) => {
@@ -7,7 +53,13 @@ macro_rules! serdes_impl {
             where
                 S: serde::Serializer,
             {
-                serializer.serialize_bytes(&self.to_bytes()[..])
+                let bytes = $crate::macros::IntoBytes::into_bytes(self.to_bytes())
+                    .map_err(serde::ser::Error::custom)?;
+                if serializer.is_human_readable() {
+                    serializer.serialize_str(&$crate::macros::hex::encode(&bytes[..]))
+                } else {
+                    serializer.serialize_bytes(&bytes[..])
+                }
             }
         }
 
@@ -21,8 +73,20 @@ macro_rules! serdes_impl {
                 impl<'a> serde::de::Visitor<'a> for DeserializeVisitor {
                     type Value = $name;
 
-                    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-                        formatter.write_str("expected byte array")
+                    fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+                        formatter.write_str("expected byte array or hex string")
+                    }
+
+                    fn visit_str<E>(self, value: &str) -> Result<$name, E>
+                    where
+                        E: serde::de::Error,
+                    {
+                        let bytes = $crate::macros::hex::decode(value).map_err(|_| {
+                            serde::de::Error::invalid_value(serde::de::Unexpected::Str(value), &self)
+                        })?;
+                        $name::try_from(&bytes[..]).map_err(|_| {
+                            serde::de::Error::invalid_value(serde::de::Unexpected::Str(value), &self)
+                        })
                     }
 
                     fn visit_bytes<E>(self, value: &[u8]) -> Result<$name, E>
@@ -33,10 +97,105 @@ macro_rules! serdes_impl {
                             serde::de::Error::invalid_value(serde::de::Unexpected::Bytes(value), &self)
                         })
                     }
+
+                    fn visit_seq<A>(self, seq: A) -> Result<$name, A::Error>
+                    where
+                        A: serde::de::SeqAccess<'a>,
+                    {
+                        self.visit_bytes(&$crate::macros::read_seq(seq)?)
+                    }
                 }
 
-                deserializer.deserialize_bytes(DeserializeVisitor)
+                if deserializer.is_human_readable() {
+                    deserializer.deserialize_str(DeserializeVisitor)
+                } else {
+                    // Owned so readers with a small scratch buffer take large values
+                    deserializer.deserialize_byte_buf(DeserializeVisitor)
+                }
             }
         }
     };
-}
\ No newline at end of file
+}
+
+/// Implement `to_hex` and `FromStr` over the bytes from `to_bytes` and
+/// `TryFrom` so public values can be pasted into configs, command lines
+/// and environment variables. Parsing takes either case, an optional `0x`
+/// and surrounding whitespace.
+#[macro_export]
+macro_rules! hex_impl {
+    ($name:ident) => {
+        impl $name {
+            /// Lowercase hex of the bytes from `to_bytes`
+            pub fn to_hex(&self) -> Result<$crate::macros::String, $crate::error::AccumulatorError> {
+                let bytes = $crate::macros::IntoBytes::into_bytes(self.to_bytes())?;
+                Ok($crate::macros::hex::encode(&bytes[..]))
+            }
+        }
+
+        impl core::str::FromStr for $name {
+            type Err = $crate::error::AccumulatorError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let s = s.trim();
+                let s = s.strip_prefix("0x").unwrap_or(s);
+                let bytes = $crate::macros::hex::decode(s).map_err(|_| {
+                    $crate::error::AccumulatorError::from_msg(
+                        $crate::error::AccumulatorErrorKind::SerializationError,
+                        "invalid hex string",
+                    )
+                })?;
+                <$name as core::convert::TryFrom<&[u8]>>::try_from(&bytes[..])
+            }
+        }
+    };
+}
+
+/// Implement `Hash`, `PartialOrd` and `Ord` over the bytes from `to_bytes`
+/// so values can be map keys and are ordered the same everywhere.
+/// Values that don't fit their fixed size encoding, which parsing never
+/// produces, come before all others and are ordered by the listed fields
+/// so only equal values compare equal.
+#[macro_export]
+macro_rules! hash_ord_impl {
+    ($name:ident; $($field:tt),+) => {
+        impl core::hash::Hash for $name {
+            fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+                $crate::macros::IntoBytes::into_bytes(self.to_bytes()).ok().hash(state)
+            }
+        }
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+                let a = $crate::macros::IntoBytes::into_bytes(self.to_bytes()).ok();
+                let b = $crate::macros::IntoBytes::into_bytes(other.to_bytes()).ok();
+                match (a, b) {
+                    (Some(a), Some(b)) => a.cmp(&b),
+                    (None, None) => core::cmp::Ordering::Equal
+                        $(.then_with(|| self.$field.cmp(&other.$field)))+,
+                    (a, b) => a.is_some().cmp(&b.is_some()),
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bigint::BigInteger, FixedBase};
+
+    #[test]
+    fn human_readable() {
+        let table = FixedBase::new(&BigInteger::from(3u32), &BigInteger::from(1019u32), 16);
+        let json = serde_json::to_string(&table).unwrap();
+        assert_eq!(json, format!("\"{}\"", hex::encode(table.to_bytes())));
+        assert_eq!(serde_json::from_str::<FixedBase>(&json).unwrap(), table);
+        assert!(serde_json::from_str::<FixedBase>("\"0g\"").is_err());
+    }
+}
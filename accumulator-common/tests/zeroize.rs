@@ -0,0 +1,51 @@
+//! Zeroizing must clear the digits where they are. A counting global
+//! allocator checks no buffer is allocated or moved while doing it,
+//! which would leave a copy of the digits behind in the old one.
+//! It's a test of its own since the allocator replaces the one of the
+//! whole test binary
+#![cfg(feature = "bi-rust")]
+
+use accumulator_common::bigint::BigInteger;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+use zeroize::Zeroize;
+
+thread_local!(static ALLOCATIONS: Cell<usize> = const { Cell::new(0) });
+
+/// Counts allocations and reallocations made by the current thread
+struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|a| a.set(a.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|a| a.set(a.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+#[test]
+fn zeroize_in_place() {
+    for v in &["1", "-98765432109876543210", "340282366920938463463374607431768211457"] {
+        let mut x = BigInteger::from(*v);
+        let mut y = &x * &x;
+        let before = ALLOCATIONS.with(Cell::get);
+        x.zeroize();
+        y.zeroize();
+        assert_eq!(ALLOCATIONS.with(Cell::get), before);
+        assert_eq!(x, BigInteger::default());
+        assert_eq!(y, BigInteger::default());
+    }
+}
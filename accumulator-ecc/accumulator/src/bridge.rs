@@ -0,0 +1,197 @@
+//! Proofs that one hidden element is in an RSA accumulator from `accumulator-rsa`
+//! and in a pairing accumulator, so a registry can move between the families
+//! while holders keep proving membership in both.
+//!
+//! The pairing accumulator holds the RSA prime of a member reduced into the
+//! scalar field, see `element`. The RSA half answers with s = k + cx over the
+//! integers. The membership proof here blinds its element with k reduced into
+//! the field and answers the same challenge, so its response for the element
+//! is s reduced into the field. Both halves commit to the element with their
+//! own Pedersen commitments and the matching responses bridge the two.
+use crate::{
+    accumulator::{Accumulator, Element},
+    error::Error,
+    key::PublicKey,
+    proof::{MembershipProof, MembershipProofCommitting, ProofParams},
+    witness::MembershipWitness,
+};
+use accumulator_rsa::{
+    accumulator::AccumulatorPublic,
+    bridge::{member_prime, BridgeCommitting, BridgeProof, BRIDGE_CHALLENGE_BYTES},
+    common::{bigint::BigInteger, error::AccumulatorError},
+    memwitness::MembershipWitness as RsaMembershipWitness,
+};
+use blake2::{Blake2b, Digest};
+use pairings::{bls12_381::Fr, serdes::SerDes};
+use std::convert::TryFrom;
+
+/// The order of the BLS12-381 scalar field
+const FIELD_ORDER: &str =
+    "52435875175126190479447740508185965837690552500527637822603658699938581184513";
+
+/// The element a pairing accumulator holds for `member` when it is bridged
+/// to an RSA accumulator holding the same member
+pub fn element<B: AsRef<[u8]>>(member: B) -> Element {
+    Element(reduce(&member_prime(member)))
+}
+
+/// A proof the same hidden element is a member of an RSA accumulator
+/// and of a pairing accumulator
+#[derive(Debug, Clone)]
+pub struct CrossFamilyProof {
+    rsa: BridgeProof,
+    ecc: MembershipProof,
+}
+
+impl CrossFamilyProof {
+    /// Prove the member of `rsa_witness` in `registry` is the element of
+    /// `witness` in `accumulator`. The pairing accumulator must hold the
+    /// member as returned by `element`
+    pub fn new(
+        registry: &AccumulatorPublic,
+        rsa_witness: &RsaMembershipWitness,
+        witness: &MembershipWitness,
+        accumulator: Accumulator,
+        proof_params: ProofParams,
+        pubkey: PublicKey,
+        nonce: &[u8],
+    ) -> Result<Self, Error> {
+        let rsa = BridgeCommitting::new(registry, rsa_witness).map_err(rsa_error)?;
+        let blinding = Element(reduce(rsa.member_blinding()));
+        let ecc = MembershipProofCommitting::new(
+            witness,
+            accumulator,
+            proof_params,
+            pubkey,
+            Some(blinding),
+        );
+        let c = challenge(
+            &rsa.commitment().map_err(rsa_error)?,
+            &ecc.get_bytes_for_challenge(),
+            nonce,
+        );
+        Ok(Self {
+            ecc: ecc.gen_proof(Element(reduce(&c))),
+            rsa: rsa.respond(&c).map_err(rsa_error)?,
+        })
+    }
+
+    /// Check the proof against the RSA `registry` and the pairing `accumulator`
+    pub fn verify(
+        &self,
+        registry: &AccumulatorPublic,
+        accumulator: Accumulator,
+        proof_params: ProofParams,
+        pubkey: PublicKey,
+        nonce: &[u8],
+    ) -> bool {
+        // The element here is the member there reduced into the field
+        if self.ecc.s_y != reduce(self.rsa.member_response()) {
+            return false;
+        }
+        let c = Element(reduce(self.rsa.challenge()));
+        let ecc = self
+            .ecc
+            .finalize(accumulator, proof_params, pubkey, c)
+            .get_bytes_for_challenge();
+        self.rsa
+            .verify(registry, |rsa| challenge(rsa, &ecc, nonce))
+    }
+
+    /// Get the byte representation of the proof, the RSA half with its
+    /// header followed by the membership proof
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut out = self.rsa.to_bytes().map_err(rsa_error)?;
+        out.extend_from_slice(&self.ecc.to_bytes());
+        Ok(out)
+    }
+}
+
+impl TryFrom<&[u8]> for CrossFamilyProof {
+    type Error = Error;
+
+    fn try_from(d: &[u8]) -> Result<Self, Self::Error> {
+        if d.len() < MembershipProof::BYTES {
+            return Err(Error::from_msg(
+                1,
+                &format!("Invalid number of bytes: {}", d.len()),
+            ));
+        }
+        let (rsa, ecc) = d.split_at(d.len() - MembershipProof::BYTES);
+        Ok(Self {
+            rsa: BridgeProof::try_from(rsa).map_err(rsa_error)?,
+            ecc: MembershipProof::try_from(ecc)?,
+        })
+    }
+}
+
+/// The challenge shared by both halves, small enough for the integer
+/// responses of the RSA half
+fn challenge(rsa: &[u8], ecc: &[u8], nonce: &[u8]) -> BigInteger {
+    let mut transcript = b"cross family membership".to_vec();
+    for part in &[rsa, ecc, nonce] {
+        transcript.extend_from_slice(&(part.len() as u64).to_be_bytes());
+        transcript.extend_from_slice(part);
+    }
+    let digest = Blake2b::digest(&transcript);
+    BigInteger::from(digest[..BRIDGE_CHALLENGE_BYTES].to_vec())
+}
+
+/// `v` mod the scalar field order
+fn reduce(v: &BigInteger) -> Fr {
+    let r = BigInteger::from(FIELD_ORDER);
+    let bytes = (v % &r).to_bytes();
+    let mut d = [0u8; 32];
+    d[(32 - bytes.len())..].copy_from_slice(&bytes);
+    let mut c = std::io::Cursor::new(d);
+    Fr::deserialize(&mut c, true).unwrap()
+}
+
+fn rsa_error(e: AccumulatorError) -> Error {
+    Error::from_msg(1, &format!("{:?}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::SecretKey;
+    use accumulator_rsa::{accumulator::Accumulator as RsaAccumulator, key::AccumulatorSecretKey};
+
+    // Generating safe primes takes too long for a test
+    fn rsa_key() -> AccumulatorSecretKey {
+        AccumulatorSecretKey::from_primes(
+            BigInteger::from("132590288326793330806752358172617836030510421524323425886695490513600853466362871997907908739315399849138190997738786757721539635477379820932279026029679011350046717599386392663749253953274352000157227488895139775977945940993648470523136879899410690348931562489237825925601577159953591977449106730133820825719"),
+            BigInteger::from("149253707427499607752440533538420296779167710000842829107795675900185486091323606384260179778233711456748787559527972657213022998726578510459854530854900733457277643303592216900588246498239579922221956281290954735600574251392801029419096160964874150455156365996536205549377586240264971604869515447059744740119"),
+        )
+    }
+
+    #[test]
+    fn bridges_families() {
+        let members = [b"alice".as_ref(), b"bob".as_ref(), b"carol".as_ref()];
+        let rsa_acc = RsaAccumulator::with_members(&rsa_key(), members).unwrap();
+        let registry = rsa_acc.public();
+        let rsa_witness = RsaMembershipWitness::new(&rsa_acc, b"bob").unwrap();
+
+        let sk = SecretKey::new(None);
+        let pk = PublicKey::from(&sk);
+        let params = ProofParams::new(pk, None);
+        let elements: Vec<Element> = members.iter().map(|m| element(m)).collect();
+        let acc = Accumulator::with_elements(&sk, 0, &elements);
+        let witness = MembershipWitness::new(&elements[1], acc, &sk);
+
+        let proof =
+            CrossFamilyProof::new(&registry, &rsa_witness, &witness, acc, params, pk, b"bridge")
+                .unwrap();
+        assert!(proof.verify(&registry, acc, params, pk, b"bridge"));
+        assert!(!proof.verify(&registry, acc, params, pk, b"other"));
+        let parsed = CrossFamilyProof::try_from(proof.to_bytes().unwrap().as_slice()).unwrap();
+        assert!(parsed.verify(&registry, acc, params, pk, b"bridge"));
+
+        // Different members in the two accumulators don't bridge
+        let other = MembershipWitness::new(&elements[2], acc, &sk);
+        let proof =
+            CrossFamilyProof::new(&registry, &rsa_witness, &other, acc, params, pk, b"bridge")
+                .unwrap();
+        assert!(!proof.verify(&registry, acc, params, pk, b"bridge"));
+    }
+}
@@ -0,0 +1,422 @@
+//! Conversions to and from the byte layouts of other accumulator implementations
+//! so mixed deployments can exchange artifacts.
+//!
+//! Only values, keys and witnesses are converted. Proofs from different implementations
+//! hash different transcripts into their Fiat-Shamir challenge so a proof rewritten
+//! into another layout would still fail to verify there.
+use crate::{
+    accumulator::{Accumulator, Element},
+    error::Error,
+    key::{PublicKey, SecretKey},
+    witness::{MembershipWitness, NonMembershipWitness},
+};
+use pairings::{
+    bls12_381::{Fr, G1},
+    serdes::SerDes,
+};
+use std::convert::TryFrom;
+
+fn fr_to_be(f: &Fr) -> [u8; 32] {
+    let mut d = [0u8; 32];
+    f.serialize(&mut d.as_mut(), true).unwrap();
+    d
+}
+
+fn fr_from_be(d: &[u8]) -> Result<Fr, Error> {
+    let mut c = std::io::Cursor::new(d);
+    Fr::deserialize(&mut c, true).map_err(|e| Error::from_msg(1, &format!("{:?}", e)))
+}
+
+fn g1_from_compressed(d: &[u8]) -> Result<G1, Error> {
+    let mut c = std::io::Cursor::new(d);
+    G1::deserialize(&mut c, true).map_err(|e| Error::from_msg(1, &format!("{:?}", e)))
+}
+
+fn check_len(d: &[u8], expected: usize) -> Result<(), Error> {
+    if d.len() != expected {
+        return Err(Error::from_msg(
+            1,
+            &format!("Invalid number of bytes: {}", d.len()),
+        ));
+    }
+    Ok(())
+}
+
+/// The layouts of the dock.io `vb_accumulator` crate which uses
+/// arkworks `CanonicalSerialize` in compressed mode.
+///
+/// Since ark-bls12-381 0.4 points are encoded like the zcash format used here
+/// so only scalars differ, arkworks writes them little-endian.
+/// Witnesses in `vb_accumulator` don't carry the element so it has to be
+/// supplied when importing.
+pub mod vb {
+    use super::*;
+
+    /// Size of a scalar
+    pub const SCALAR_BYTES: usize = 32;
+    /// Size of a compressed G1 point
+    pub const G1_BYTES: usize = 48;
+    /// Size of a compressed G2 point
+    pub const G2_BYTES: usize = 96;
+
+    fn fr_to_le(f: &Fr) -> [u8; SCALAR_BYTES] {
+        let mut d = fr_to_be(f);
+        d.reverse();
+        d
+    }
+
+    fn fr_from_le(d: &[u8]) -> Result<Fr, Error> {
+        check_len(d, SCALAR_BYTES)?;
+        let mut t = [0u8; SCALAR_BYTES];
+        t.copy_from_slice(d);
+        t.reverse();
+        fr_from_be(&t)
+    }
+
+    /// Export an element as a `vb_accumulator` member
+    pub fn element_to_bytes(e: &Element) -> [u8; SCALAR_BYTES] {
+        fr_to_le(&e.0)
+    }
+
+    /// Import a `vb_accumulator` member
+    pub fn element_from_bytes(d: &[u8]) -> Result<Element, Error> {
+        Ok(Element(fr_from_le(d)?))
+    }
+
+    /// Export a secret key as a `vb_accumulator::setup::SecretKey`
+    pub fn secret_key_to_bytes(sk: &SecretKey) -> [u8; SCALAR_BYTES] {
+        fr_to_le(&sk.0)
+    }
+
+    /// Import a `vb_accumulator::setup::SecretKey`
+    pub fn secret_key_from_bytes(d: &[u8]) -> Result<SecretKey, Error> {
+        Ok(SecretKey(fr_from_le(d)?))
+    }
+
+    /// Export a public key as a `vb_accumulator::setup::PublicKey`
+    pub fn public_key_to_bytes(pk: &PublicKey) -> [u8; G2_BYTES] {
+        pk.to_bytes()
+    }
+
+    /// Import a `vb_accumulator::setup::PublicKey`
+    pub fn public_key_from_bytes(d: &[u8]) -> Result<PublicKey, Error> {
+        PublicKey::try_from(d)
+    }
+
+    /// Export an accumulator value as a `vb_accumulator` accumulator value
+    pub fn accumulator_to_bytes(acc: &Accumulator) -> [u8; G1_BYTES] {
+        acc.to_bytes()
+    }
+
+    /// Import a `vb_accumulator` accumulator value
+    pub fn accumulator_from_bytes(d: &[u8]) -> Result<Accumulator, Error> {
+        Accumulator::try_from(d)
+    }
+
+    /// Export a witness as a `vb_accumulator::witness::MembershipWitness`
+    pub fn membership_witness_to_bytes(w: &MembershipWitness) -> [u8; G1_BYTES] {
+        let mut d = [0u8; G1_BYTES];
+        w.c.serialize(&mut d.as_mut(), true).unwrap();
+        d
+    }
+
+    /// Import a `vb_accumulator::witness::MembershipWitness` for `element`
+    pub fn membership_witness_from_bytes(
+        d: &[u8],
+        element: &Element,
+    ) -> Result<MembershipWitness, Error> {
+        check_len(d, G1_BYTES)?;
+        Ok(MembershipWitness {
+            c: g1_from_compressed(d)?,
+            y: element.0,
+        })
+    }
+
+    /// Export a witness as a `vb_accumulator::witness::NonMembershipWitness`
+    /// which is `d || C`
+    pub fn non_membership_witness_to_bytes(
+        w: &NonMembershipWitness,
+    ) -> [u8; SCALAR_BYTES + G1_BYTES] {
+        let mut d = [0u8; SCALAR_BYTES + G1_BYTES];
+        d[..SCALAR_BYTES].copy_from_slice(&fr_to_le(&w.d));
+        w.c.serialize(&mut &mut d[SCALAR_BYTES..], true).unwrap();
+        d
+    }
+
+    /// Import a `vb_accumulator::witness::NonMembershipWitness` for `element`
+    pub fn non_membership_witness_from_bytes(
+        d: &[u8],
+        element: &Element,
+    ) -> Result<NonMembershipWitness, Error> {
+        check_len(d, SCALAR_BYTES + G1_BYTES)?;
+        Ok(NonMembershipWitness {
+            c: g1_from_compressed(&d[SCALAR_BYTES..])?,
+            d: fr_from_le(&d[..SCALAR_BYTES])?,
+            y: element.0,
+        })
+    }
+}
+
+/// The AMCL layouts used by Hyperledger Ursa's BLS12-381 types.
+///
+/// Scalars are the size of the base field, 48 bytes big-endian,
+/// and G1 points are uncompressed as `0x04 || x || y`.
+/// Ursa's CL revocation accumulator is over BN254 so its values
+/// can't be converted to this curve at all.
+pub mod ursa {
+    use super::*;
+
+    /// Size of a scalar
+    pub const SCALAR_BYTES: usize = 48;
+    /// Size of an uncompressed G1 point with its tag
+    pub const G1_BYTES: usize = 97;
+
+    const UNCOMPRESSED_TAG: u8 = 0x04;
+
+    fn fr_to_amcl(f: &Fr) -> [u8; SCALAR_BYTES] {
+        let mut d = [0u8; SCALAR_BYTES];
+        d[SCALAR_BYTES - 32..].copy_from_slice(&fr_to_be(f));
+        d
+    }
+
+    fn fr_from_amcl(d: &[u8]) -> Result<Fr, Error> {
+        check_len(d, SCALAR_BYTES)?;
+        if d[..SCALAR_BYTES - 32].iter().any(|b| *b != 0) {
+            return Err(Error::from_msg(1, "Scalar is larger than the group order"));
+        }
+        fr_from_be(&d[SCALAR_BYTES - 32..])
+    }
+
+    fn g1_to_amcl(g: &G1) -> [u8; G1_BYTES] {
+        let mut d = [0u8; G1_BYTES];
+        d[0] = UNCOMPRESSED_TAG;
+        g.serialize(&mut &mut d[1..], false).unwrap();
+        d
+    }
+
+    fn g1_from_amcl(d: &[u8]) -> Result<G1, Error> {
+        check_len(d, G1_BYTES)?;
+        if d[0] != UNCOMPRESSED_TAG {
+            return Err(Error::from_msg(1, "Expected an uncompressed point"));
+        }
+        let mut c = std::io::Cursor::new(&d[1..]);
+        G1::deserialize(&mut c, false).map_err(|e| Error::from_msg(1, &format!("{:?}", e)))
+    }
+
+    /// Export an element as an AMCL scalar
+    pub fn element_to_bytes(e: &Element) -> [u8; SCALAR_BYTES] {
+        fr_to_amcl(&e.0)
+    }
+
+    /// Import an AMCL scalar as an element
+    pub fn element_from_bytes(d: &[u8]) -> Result<Element, Error> {
+        Ok(Element(fr_from_amcl(d)?))
+    }
+
+    /// Export a secret key as an AMCL scalar
+    pub fn secret_key_to_bytes(sk: &SecretKey) -> [u8; SCALAR_BYTES] {
+        fr_to_amcl(&sk.0)
+    }
+
+    /// Import an AMCL scalar as a secret key
+    pub fn secret_key_from_bytes(d: &[u8]) -> Result<SecretKey, Error> {
+        Ok(SecretKey(fr_from_amcl(d)?))
+    }
+
+    /// Export an accumulator value as an AMCL G1 point
+    pub fn accumulator_to_bytes(acc: &Accumulator) -> [u8; G1_BYTES] {
+        g1_to_amcl(&acc.0)
+    }
+
+    /// Import an AMCL G1 point as an accumulator value
+    pub fn accumulator_from_bytes(d: &[u8]) -> Result<Accumulator, Error> {
+        Ok(Accumulator(g1_from_amcl(d)?))
+    }
+
+    /// Export a witness as `C || y`
+    pub fn membership_witness_to_bytes(w: &MembershipWitness) -> [u8; G1_BYTES + SCALAR_BYTES] {
+        let mut d = [0u8; G1_BYTES + SCALAR_BYTES];
+        d[..G1_BYTES].copy_from_slice(&g1_to_amcl(&w.c));
+        d[G1_BYTES..].copy_from_slice(&fr_to_amcl(&w.y));
+        d
+    }
+
+    /// Import a witness written as `C || y`
+    pub fn membership_witness_from_bytes(d: &[u8]) -> Result<MembershipWitness, Error> {
+        check_len(d, G1_BYTES + SCALAR_BYTES)?;
+        Ok(MembershipWitness {
+            c: g1_from_amcl(&d[..G1_BYTES])?,
+            y: fr_from_amcl(&d[G1_BYTES..])?,
+        })
+    }
+
+    /// Export a witness as `C || d || y`
+    pub fn non_membership_witness_to_bytes(
+        w: &NonMembershipWitness,
+    ) -> [u8; G1_BYTES + 2 * SCALAR_BYTES] {
+        let mut d = [0u8; G1_BYTES + 2 * SCALAR_BYTES];
+        d[..G1_BYTES].copy_from_slice(&g1_to_amcl(&w.c));
+        d[G1_BYTES..G1_BYTES + SCALAR_BYTES].copy_from_slice(&fr_to_amcl(&w.d));
+        d[G1_BYTES + SCALAR_BYTES..].copy_from_slice(&fr_to_amcl(&w.y));
+        d
+    }
+
+    /// Import a witness written as `C || d || y`
+    pub fn non_membership_witness_from_bytes(d: &[u8]) -> Result<NonMembershipWitness, Error> {
+        check_len(d, G1_BYTES + 2 * SCALAR_BYTES)?;
+        Ok(NonMembershipWitness {
+            c: g1_from_amcl(&d[..G1_BYTES])?,
+            d: fr_from_amcl(&d[G1_BYTES..G1_BYTES + SCALAR_BYTES])?,
+            y: fr_from_amcl(&d[G1_BYTES + SCALAR_BYTES..])?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Fixtures written by arkworks 0.4 (what `vb_accumulator` serializes with) and by
+    // the Milagro AMCL BLS12-381 code Ursa uses. The secret key is 123456789, the
+    // members are 3, 4 and 5, the witness is for 4 and the non-member is 6.
+    const VB_SECRET_KEY: &str = "15cd5b0700000000000000000000000000000000000000000000000000000000";
+    const VB_PUBLIC_KEY: &str = "b068ad1be382009ac2dce123ec62dca8337d6b93b909b3ee52e31cb9e4098d1b56d596bf3c08166c7b46cb3aa85c23381380055ab9f1a87786f2508f3e4ce5caa5abcdae0a80141ee8ccc3626311e0a53be5d873fa964fd85ad56771f2984579";
+    const VB_ACCUMULATOR: &str = "959fa5fdbfc9b2c020f0e5febec6b3397ee4cc37519c708341c45efe1a6fd59bd94039d31764e23e6d92f86bc7e2984a";
+    const VB_WITNESS: &str = "95168e6d96d0ec89749b91486c8abaf5ffd4a982e93b8f981cc510318882f5bcd6de3701ef95b720a4ae0a8580e05700";
+    const VB_NON_WITNESS: &str = "fbfffffffefffffffe5bfeff02a4bd5305d8a10908d83933487d9d2953a7ed73939c38c7a847ff67c745a35266abb573a64e7440b22f65b1f9d63c12451219f1351e292b4a1266743c3771f8773193b0";
+    const URSA_SECRET_KEY: &str = "0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000075bcd15";
+    const URSA_ACCUMULATOR: &str = "04159fa5fdbfc9b2c020f0e5febec6b3397ee4cc37519c708341c45efe1a6fd59bd94039d31764e23e6d92f86bc7e2984a08ec8abd9ea32bea5b396d35bc31c3c68c37eea2155c9f5acfd93f2eff6df19609c160a139133b564fd03a424e094664";
+    const URSA_WITNESS: &str = "0415168e6d96d0ec89749b91486c8abaf5ffd4a982e93b8f981cc510318882f5bcd6de3701ef95b720a4ae0a8580e057000993c0db84f42712c6a2b06f682be8987426f90e3071c3225dfe5eda2d77e3210e561cb1c1233c311f15adb01ff200e2000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000004";
+    const URSA_NON_WITNESS: &str = "04139c38c7a847ff67c745a35266abb573a64e7440b22f65b1f9d63c12451219f1351e292b4a1266743c3771f8773193b0021efec6b727cde6bf923c5aa6972ee287f04feffc83a439ef546bb39a2d56a428c21792e170f5aeeb414718362b26540000000000000000000000000000000073eda753299d7d483339d80809a1d80553bda402fffe5bfefffffffefffffffb000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000006";
+
+    fn small(n: u8) -> Element {
+        let mut d = [0u8; vb::SCALAR_BYTES];
+        d[0] = n;
+        vb::element_from_bytes(&d).unwrap()
+    }
+
+    #[test]
+    fn vb_fixtures() {
+        let key = vb::secret_key_from_bytes(&hex::decode(VB_SECRET_KEY).unwrap()).unwrap();
+        let elements = [small(3), small(4), small(5)];
+        let acc = Accumulator::with_elements(&key, 0, &elements);
+
+        let pubkey = vb::public_key_from_bytes(&hex::decode(VB_PUBLIC_KEY).unwrap()).unwrap();
+        assert_eq!(pubkey, PublicKey::from(&key));
+        let value = vb::accumulator_from_bytes(&hex::decode(VB_ACCUMULATOR).unwrap()).unwrap();
+        assert_eq!(value, acc);
+
+        let bytes = hex::decode(VB_WITNESS).unwrap();
+        let wit = vb::membership_witness_from_bytes(&bytes, &elements[1]).unwrap();
+        assert!(wit.verify(pubkey, value));
+        assert_eq!(vb::membership_witness_to_bytes(&wit)[..], bytes[..]);
+
+        let bytes = hex::decode(VB_NON_WITNESS).unwrap();
+        let nm = vb::non_membership_witness_from_bytes(&bytes, &small(6)).unwrap();
+        assert!(nm.verify(pubkey, value));
+        assert_eq!(vb::non_membership_witness_to_bytes(&nm)[..], bytes[..]);
+
+        assert_eq!(hex::encode(vb::secret_key_to_bytes(&key)), VB_SECRET_KEY);
+        assert_eq!(hex::encode(&vb::public_key_to_bytes(&pubkey)[..]), VB_PUBLIC_KEY);
+        assert_eq!(hex::encode(&vb::accumulator_to_bytes(&acc)[..]), VB_ACCUMULATOR);
+    }
+
+    #[test]
+    fn ursa_fixtures() {
+        let key = ursa::secret_key_from_bytes(&hex::decode(URSA_SECRET_KEY).unwrap()).unwrap();
+        let pubkey = PublicKey::from(&key);
+        let elements = [small(3), small(4), small(5)];
+        let acc = Accumulator::with_elements(&key, 0, &elements);
+
+        let value = ursa::accumulator_from_bytes(&hex::decode(URSA_ACCUMULATOR).unwrap()).unwrap();
+        assert_eq!(value, acc);
+
+        let bytes = hex::decode(URSA_WITNESS).unwrap();
+        let wit = ursa::membership_witness_from_bytes(&bytes).unwrap();
+        assert!(wit.verify(pubkey, value));
+        assert_eq!(ursa::membership_witness_to_bytes(&wit)[..], bytes[..]);
+
+        let bytes = hex::decode(URSA_NON_WITNESS).unwrap();
+        let nm = ursa::non_membership_witness_from_bytes(&bytes).unwrap();
+        assert!(nm.verify(pubkey, value));
+        assert_eq!(ursa::non_membership_witness_to_bytes(&nm)[..], bytes[..]);
+
+        assert_eq!(hex::encode(&ursa::secret_key_to_bytes(&key)[..]), URSA_SECRET_KEY);
+        assert_eq!(hex::encode(&ursa::accumulator_to_bytes(&acc)[..]), URSA_ACCUMULATOR);
+    }
+
+    #[test]
+    fn vb_round_trip() {
+        let key = SecretKey::new(Some(b"1234567890"));
+        let pubkey = PublicKey::from(&key);
+        let elements = [Element::hash(b"3"), Element::hash(b"4"), Element::hash(b"5")];
+        let acc = Accumulator::with_elements(&key, 0, &elements);
+
+        let e = vb::element_to_bytes(&elements[0]);
+        let mut be = elements[0].to_bytes();
+        be.reverse();
+        assert_eq!(e, be);
+        assert_eq!(vb::element_from_bytes(&e).unwrap(), elements[0]);
+
+        let sk = vb::secret_key_from_bytes(&vb::secret_key_to_bytes(&key)).unwrap();
+        assert_eq!(sk.to_bytes(), key.to_bytes());
+        let pk = vb::public_key_from_bytes(&vb::public_key_to_bytes(&pubkey)).unwrap();
+        assert_eq!(pk, pubkey);
+        let acc2 = vb::accumulator_from_bytes(&vb::accumulator_to_bytes(&acc)).unwrap();
+        assert_eq!(acc2, acc);
+
+        let wit = MembershipWitness::new(&elements[1], acc, &key);
+        let bytes = vb::membership_witness_to_bytes(&wit);
+        let wit2 = vb::membership_witness_from_bytes(&bytes, &elements[1]).unwrap();
+        assert_eq!(wit2.to_bytes()[..], wit.to_bytes()[..]);
+        assert!(wit2.verify(pubkey, acc));
+
+        let y = Element::hash(b"6");
+        let nm = NonMembershipWitness::new(&y, &elements, &key);
+        let bytes = vb::non_membership_witness_to_bytes(&nm);
+        let nm2 = vb::non_membership_witness_from_bytes(&bytes, &y).unwrap();
+        assert_eq!(nm2.to_bytes()[..], nm.to_bytes()[..]);
+        assert!(nm2.verify(pubkey, acc));
+
+        assert!(vb::element_from_bytes(&[0xFFu8; 32]).is_err());
+        assert!(vb::membership_witness_from_bytes(&bytes, &y).is_err());
+    }
+
+    #[test]
+    fn ursa_round_trip() {
+        let key = SecretKey::new(Some(b"1234567890"));
+        let pubkey = PublicKey::from(&key);
+        let elements = [Element::hash(b"3"), Element::hash(b"4"), Element::hash(b"5")];
+        let acc = Accumulator::with_elements(&key, 0, &elements);
+
+        let e = ursa::element_to_bytes(&elements[0]);
+        assert_eq!(e[..16], [0u8; 16]);
+        assert_eq!(e[16..], elements[0].to_bytes()[..]);
+        assert_eq!(ursa::element_from_bytes(&e).unwrap(), elements[0]);
+
+        let sk = ursa::secret_key_from_bytes(&ursa::secret_key_to_bytes(&key)).unwrap();
+        assert_eq!(sk.to_bytes(), key.to_bytes());
+
+        let a = ursa::accumulator_to_bytes(&acc);
+        assert_eq!(a[0], 0x04);
+        assert_eq!(ursa::accumulator_from_bytes(&a).unwrap(), acc);
+
+        let wit = MembershipWitness::new(&elements[1], acc, &key);
+        let bytes = ursa::membership_witness_to_bytes(&wit);
+        let wit2 = ursa::membership_witness_from_bytes(&bytes).unwrap();
+        assert!(wit2.verify(pubkey, acc));
+
+        let y = Element::hash(b"6");
+        let nm = NonMembershipWitness::new(&y, &elements, &key);
+        let bytes = ursa::non_membership_witness_to_bytes(&nm);
+        let nm2 = ursa::non_membership_witness_from_bytes(&bytes).unwrap();
+        assert!(nm2.verify(pubkey, acc));
+
+        let mut bad = e;
+        bad[0] = 1;
+        assert!(ursa::element_from_bytes(&bad).is_err());
+        let mut bad = a;
+        bad[0] = 0x02;
+        assert!(ursa::accumulator_from_bytes(&bad).is_err());
+    }
+}
@@ -236,7 +236,10 @@ impl MulAssign<Fr> for Polynomial {
 mod macros;
 
 pub mod accumulator;
+#[cfg(feature = "rsa-bridge")]
+pub mod bridge;
 pub mod error;
+pub mod interop;
 pub mod key;
 pub mod proof;
 pub mod witness;
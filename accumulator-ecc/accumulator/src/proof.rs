@@ -260,7 +260,7 @@ MembershipProof, MembershipProofInner,
 display_impl!(MembershipProof);
 
 impl MembershipProof {
-    const BYTES: usize = 304;
+    pub const BYTES: usize = 304;
 
     /// Generate the structure that can be used in the challenge hash
     /// returns a struct to avoid recomputing
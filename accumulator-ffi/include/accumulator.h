@@ -0,0 +1,425 @@
+#ifndef ACCUMULATOR_H
+#define ACCUMULATOR_H
+
+/* Generated with cbindgen:0.26.0 */
+
+/* Generated by cbindgen from src/lib.rs, do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The result of every call. The values are part of the
+ * ABI and never change, new codes are only appended
+ */
+typedef enum AccumulatorStatus {
+  /**
+   * The call succeeded
+   */
+  ACCUMULATOR_STATUS_OK = 0,
+  /**
+   * A required pointer argument was null
+   */
+  ACCUMULATOR_STATUS_NULL_POINTER = 1,
+  /**
+   * Type cannot be converted to an BigInteger
+   */
+  ACCUMULATOR_STATUS_INVALID_TYPE = 2,
+  /**
+   * The value already exists in the accumulator
+   */
+  ACCUMULATOR_STATUS_DUPLICATE_VALUE = 3,
+  /**
+   * The value is not a member of the accumulator
+   */
+  ACCUMULATOR_STATUS_INVALID_MEMBER = 4,
+  /**
+   * The bytes could not be deserialized
+   */
+  ACCUMULATOR_STATUS_SERIALIZATION = 5,
+  /**
+   * A long running operation was stopped
+   */
+  ACCUMULATOR_STATUS_CANCELLED = 6,
+  /**
+   * The input has the wrong number of bytes
+   */
+  ACCUMULATOR_STATUS_INVALID_LENGTH = 7,
+  /**
+   * A proof or transcript did not verify
+   */
+  ACCUMULATOR_STATUS_PROOF_VERIFICATION_FAILED = 8,
+  /**
+   * The nonce does not match the one the proof was created with
+   */
+  ACCUMULATOR_STATUS_INVALID_NONCE = 9,
+  /**
+   * Values were created for different parameters
+   */
+  ACCUMULATOR_STATUS_PARAMETER_MISMATCH = 10,
+  /**
+   * The operation needs the accumulator secret key
+   */
+  ACCUMULATOR_STATUS_TRAPDOOR_REQUIRED = 11,
+  /**
+   * A value was created for a different accumulator epoch
+   */
+  ACCUMULATOR_STATUS_EPOCH_MISMATCH = 12,
+  /**
+   * The value has no inverse for the modulus
+   */
+  ACCUMULATOR_STATUS_NOT_INVERTIBLE = 13,
+  /**
+   * The member store failed
+   */
+  ACCUMULATOR_STATUS_STORAGE = 14,
+  /**
+   * An error this version of the bindings doesn't know about
+   */
+  ACCUMULATOR_STATUS_UNKNOWN = 100,
+  /**
+   * The library panicked, the arguments are left unchanged
+   */
+  ACCUMULATOR_STATUS_PANIC = 101,
+} AccumulatorStatus;
+
+/**
+ * An accumulator with its members
+ */
+typedef struct RsaAccumulator RsaAccumulator;
+
+/**
+ * A membership proof
+ */
+typedef struct RsaMembershipProof RsaMembershipProof;
+
+/**
+ * A membership witness
+ */
+typedef struct RsaMembershipWitness RsaMembershipWitness;
+
+/**
+ * A non-membership proof
+ */
+typedef struct RsaNonMembershipProof RsaNonMembershipProof;
+
+/**
+ * A non-membership witness
+ */
+typedef struct RsaNonMembershipWitness RsaNonMembershipWitness;
+
+/**
+ * An accumulator secret key
+ */
+typedef struct RsaSecretKey RsaSecretKey;
+
+/**
+ * Bytes allocated by the library
+ */
+typedef struct ByteBuffer {
+  /**
+   * The number of bytes
+   */
+  size_t len;
+  /**
+   * The bytes, null when empty
+   */
+  uint8_t *data;
+} ByteBuffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Release bytes returned by the library. The bytes are zeroed
+ * first since they may hold a secret key.
+ *
+ * # Safety
+ * `buffer` must have been returned by this library and not freed before
+ */
+void accumulator_byte_buffer_free(struct ByteBuffer buffer);
+
+/**
+ * Generate a new secret key. This searches for two safe primes and takes a while.
+ *
+ * # Safety
+ * `out` must be valid for writes
+ */
+enum AccumulatorStatus accumulator_secret_key_new(struct RsaSecretKey **out);
+
+/**
+ * Read a secret key from bytes
+ *
+ * # Safety
+ * `data` must point to `len` readable bytes and `out` must be valid for writes
+ */
+enum AccumulatorStatus accumulator_secret_key_from_bytes(const uint8_t *data,
+                                                         size_t len,
+                                                         struct RsaSecretKey **out);
+
+/**
+ * Serialize a secret key
+ *
+ * # Safety
+ * `key` must be a live handle and `out` must be valid for writes
+ */
+enum AccumulatorStatus accumulator_secret_key_to_bytes(const struct RsaSecretKey *key,
+                                                       struct ByteBuffer *out);
+
+/**
+ * Release a secret key, its factors are zeroed
+ *
+ * # Safety
+ * `key` must be null or a handle that has not been freed before
+ */
+void accumulator_secret_key_free(struct RsaSecretKey *key);
+
+/**
+ * Create an empty accumulator for a secret key
+ *
+ * # Safety
+ * `key` must be a live handle and `out` must be valid for writes
+ */
+enum AccumulatorStatus accumulator_new(const struct RsaSecretKey *key, struct RsaAccumulator **out);
+
+/**
+ * Read an accumulator from bytes
+ *
+ * # Safety
+ * `data` must point to `len` readable bytes and `out` must be valid for writes
+ */
+enum AccumulatorStatus accumulator_from_bytes(const uint8_t *data,
+                                              size_t len,
+                                              struct RsaAccumulator **out);
+
+/**
+ * Serialize an accumulator with its members
+ *
+ * # Safety
+ * `accumulator` must be a live handle and `out` must be valid for writes
+ */
+enum AccumulatorStatus accumulator_to_bytes(const struct RsaAccumulator *accumulator,
+                                            struct ByteBuffer *out);
+
+/**
+ * Add a value to the accumulator
+ *
+ * # Safety
+ * `accumulator` must be a live handle and `value` must point to `len` readable bytes
+ */
+enum AccumulatorStatus accumulator_insert(struct RsaAccumulator *accumulator,
+                                          const uint8_t *value,
+                                          size_t len);
+
+/**
+ * Remove a value from the accumulator
+ *
+ * # Safety
+ * `accumulator` and `key` must be live handles and `value` must point to `len` readable bytes
+ */
+enum AccumulatorStatus accumulator_remove(struct RsaAccumulator *accumulator,
+                                          const struct RsaSecretKey *key,
+                                          const uint8_t *value,
+                                          size_t len);
+
+/**
+ * Release an accumulator
+ *
+ * # Safety
+ * `accumulator` must be null or a handle that has not been freed before
+ */
+void accumulator_free(struct RsaAccumulator *accumulator);
+
+/**
+ * Create a witness that a value is in the accumulator
+ *
+ * # Safety
+ * `accumulator` must be a live handle, `value` must point to
+ * `len` readable bytes and `out` must be valid for writes
+ */
+enum AccumulatorStatus accumulator_membership_witness_new(const struct RsaAccumulator *accumulator,
+                                                          const uint8_t *value,
+                                                          size_t len,
+                                                          struct RsaMembershipWitness **out);
+
+/**
+ * Read a membership witness from bytes
+ *
+ * # Safety
+ * `data` must point to `len` readable bytes and `out` must be valid for writes
+ */
+enum AccumulatorStatus accumulator_membership_witness_from_bytes(const uint8_t *data,
+                                                                 size_t len,
+                                                                 struct RsaMembershipWitness **out);
+
+/**
+ * Serialize a membership witness
+ *
+ * # Safety
+ * `witness` must be a live handle and `out` must be valid for writes
+ */
+enum AccumulatorStatus accumulator_membership_witness_to_bytes(const struct RsaMembershipWitness *witness,
+                                                               struct ByteBuffer *out);
+
+/**
+ * Release a membership witness
+ *
+ * # Safety
+ * `witness` must be null or a handle that has not been freed before
+ */
+void accumulator_membership_witness_free(struct RsaMembershipWitness *witness);
+
+/**
+ * Create a witness that a value is not in the accumulator
+ *
+ * # Safety
+ * `accumulator` must be a live handle, `value` must point to
+ * `len` readable bytes and `out` must be valid for writes
+ */
+enum AccumulatorStatus accumulator_nonmembership_witness_new(const struct RsaAccumulator *accumulator,
+                                                             const uint8_t *value,
+                                                             size_t len,
+                                                             struct RsaNonMembershipWitness **out);
+
+/**
+ * Read a non-membership witness from bytes
+ *
+ * # Safety
+ * `data` must point to `len` readable bytes and `out` must be valid for writes
+ */
+enum AccumulatorStatus accumulator_nonmembership_witness_from_bytes(const uint8_t *data,
+                                                                    size_t len,
+                                                                    struct RsaNonMembershipWitness **out);
+
+/**
+ * Serialize a non-membership witness
+ *
+ * # Safety
+ * `witness` must be a live handle and `out` must be valid for writes
+ */
+enum AccumulatorStatus accumulator_nonmembership_witness_to_bytes(const struct RsaNonMembershipWitness *witness,
+                                                                  struct ByteBuffer *out);
+
+/**
+ * Release a non-membership witness
+ *
+ * # Safety
+ * `witness` must be null or a handle that has not been freed before
+ */
+void accumulator_nonmembership_witness_free(struct RsaNonMembershipWitness *witness);
+
+/**
+ * Prove knowledge of a member of the accumulator
+ *
+ * # Safety
+ * `witness` and `accumulator` must be live handles, `nonce` must
+ * point to `nonce_len` readable bytes and `out` must be valid for writes
+ */
+enum AccumulatorStatus accumulator_membership_proof_new(const struct RsaMembershipWitness *witness,
+                                                        const struct RsaAccumulator *accumulator,
+                                                        const uint8_t *nonce,
+                                                        size_t nonce_len,
+                                                        struct RsaMembershipProof **out);
+
+/**
+ * Check a membership proof, `valid` is set to the result
+ *
+ * # Safety
+ * `proof` and `accumulator` must be live handles, `nonce` must point
+ * to `nonce_len` readable bytes and `valid` must be valid for writes
+ */
+enum AccumulatorStatus accumulator_membership_proof_verify(const struct RsaMembershipProof *proof,
+                                                           const struct RsaAccumulator *accumulator,
+                                                           const uint8_t *nonce,
+                                                           size_t nonce_len,
+                                                           bool *valid);
+
+/**
+ * Read a membership proof from bytes
+ *
+ * # Safety
+ * `data` must point to `len` readable bytes and `out` must be valid for writes
+ */
+enum AccumulatorStatus accumulator_membership_proof_from_bytes(const uint8_t *data,
+                                                               size_t len,
+                                                               struct RsaMembershipProof **out);
+
+/**
+ * Serialize a membership proof
+ *
+ * # Safety
+ * `proof` must be a live handle and `out` must be valid for writes
+ */
+enum AccumulatorStatus accumulator_membership_proof_to_bytes(const struct RsaMembershipProof *proof,
+                                                             struct ByteBuffer *out);
+
+/**
+ * Release a membership proof
+ *
+ * # Safety
+ * `proof` must be null or a handle that has not been freed before
+ */
+void accumulator_membership_proof_free(struct RsaMembershipProof *proof);
+
+/**
+ * Prove a value is not a member of the accumulator
+ *
+ * # Safety
+ * `witness` and `accumulator` must be live handles, `nonce` must
+ * point to `nonce_len` readable bytes and `out` must be valid for writes
+ */
+enum AccumulatorStatus accumulator_nonmembership_proof_new(const struct RsaNonMembershipWitness *witness,
+                                                           const struct RsaAccumulator *accumulator,
+                                                           const uint8_t *nonce,
+                                                           size_t nonce_len,
+                                                           struct RsaNonMembershipProof **out);
+
+/**
+ * Check a non-membership proof, `valid` is set to the result
+ *
+ * # Safety
+ * `proof` and `accumulator` must be live handles, `nonce` must point
+ * to `nonce_len` readable bytes and `valid` must be valid for writes
+ */
+enum AccumulatorStatus accumulator_nonmembership_proof_verify(const struct RsaNonMembershipProof *proof,
+                                                              const struct RsaAccumulator *accumulator,
+                                                              const uint8_t *nonce,
+                                                              size_t nonce_len,
+                                                              bool *valid);
+
+/**
+ * Read a non-membership proof from bytes
+ *
+ * # Safety
+ * `data` must point to `len` readable bytes and `out` must be valid for writes
+ */
+enum AccumulatorStatus accumulator_nonmembership_proof_from_bytes(const uint8_t *data,
+                                                                  size_t len,
+                                                                  struct RsaNonMembershipProof **out);
+
+/**
+ * Serialize a non-membership proof
+ *
+ * # Safety
+ * `proof` must be a live handle and `out` must be valid for writes
+ */
+enum AccumulatorStatus accumulator_nonmembership_proof_to_bytes(const struct RsaNonMembershipProof *proof,
+                                                                struct ByteBuffer *out);
+
+/**
+ * Release a non-membership proof
+ *
+ * # Safety
+ * `proof` must be null or a handle that has not been freed before
+ */
+void accumulator_nonmembership_proof_free(struct RsaNonMembershipProof *proof);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* ACCUMULATOR_H */
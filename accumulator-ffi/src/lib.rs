@@ -0,0 +1,731 @@
+#![deny(
+    missing_docs,
+    unused_import_braces,
+    unused_lifetimes,
+    unused_qualifications
+)]
+//! C bindings for the RSA accumulator
+//!
+//! Values are passed as opaque handles that must be released with their
+//! `_free` function. Functions return an [`AccumulatorStatus`] and write
+//! results to out pointers. Bytes returned in a [`ByteBuffer`] are owned
+//! by the caller and released with [`accumulator_byte_buffer_free`].
+//!
+//! `include/accumulator.h` is generated from this file by cbindgen.
+use accumulator_rsa::prelude::{
+    Accumulator, AccumulatorError, AccumulatorErrorKind, AccumulatorSecretKey, MembershipProof,
+    MembershipWitness, NonMembershipProof, NonMembershipWitness,
+};
+use std::{
+    convert::TryFrom,
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr, slice,
+};
+use zeroize::Zeroize;
+
+/// The result of every call. The values are part of the
+/// ABI and never change, new codes are only appended
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AccumulatorStatus {
+    /// The call succeeded
+    Ok = 0,
+    /// A required pointer argument was null
+    NullPointer = 1,
+    /// Type cannot be converted to an BigInteger
+    InvalidType = 2,
+    /// The value already exists in the accumulator
+    DuplicateValue = 3,
+    /// The value is not a member of the accumulator
+    InvalidMember = 4,
+    /// The bytes could not be deserialized
+    Serialization = 5,
+    /// A long running operation was stopped
+    Cancelled = 6,
+    /// The input has the wrong number of bytes
+    InvalidLength = 7,
+    /// A proof or transcript did not verify
+    ProofVerificationFailed = 8,
+    /// The nonce does not match the one the proof was created with
+    InvalidNonce = 9,
+    /// Values were created for different parameters
+    ParameterMismatch = 10,
+    /// The operation needs the accumulator secret key
+    TrapdoorRequired = 11,
+    /// A value was created for a different accumulator epoch
+    EpochMismatch = 12,
+    /// The value has no inverse for the modulus
+    NotInvertible = 13,
+    /// The member store failed
+    Storage = 14,
+    /// A thread panicked while holding a lock on shared state
+    Poisoned = 15,
+    /// An error this version of the bindings doesn't know about
+    Unknown = 100,
+    /// The library panicked, the arguments are left unchanged
+    Panic = 101,
+}
+
+impl From<AccumulatorErrorKind> for AccumulatorStatus {
+    fn from(kind: AccumulatorErrorKind) -> Self {
+        match kind {
+            AccumulatorErrorKind::InvalidType => AccumulatorStatus::InvalidType,
+            AccumulatorErrorKind::DuplicateValueSupplied => AccumulatorStatus::DuplicateValue,
+            AccumulatorErrorKind::InvalidMemberSupplied => AccumulatorStatus::InvalidMember,
+            AccumulatorErrorKind::SerializationError => AccumulatorStatus::Serialization,
+            AccumulatorErrorKind::Cancelled => AccumulatorStatus::Cancelled,
+            AccumulatorErrorKind::InvalidLength { .. } => AccumulatorStatus::InvalidLength,
+            AccumulatorErrorKind::ProofVerificationFailed => AccumulatorStatus::ProofVerificationFailed,
+            AccumulatorErrorKind::InvalidNonce => AccumulatorStatus::InvalidNonce,
+            AccumulatorErrorKind::ParameterMismatch => AccumulatorStatus::ParameterMismatch,
+            AccumulatorErrorKind::TrapdoorRequired => AccumulatorStatus::TrapdoorRequired,
+            AccumulatorErrorKind::EpochMismatch { .. } => AccumulatorStatus::EpochMismatch,
+            AccumulatorErrorKind::NotInvertible => AccumulatorStatus::NotInvertible,
+            AccumulatorErrorKind::StorageError => AccumulatorStatus::Storage,
+            AccumulatorErrorKind::Poisoned => AccumulatorStatus::Poisoned,
+            _ => AccumulatorStatus::Unknown,
+        }
+    }
+}
+
+impl From<AccumulatorError> for AccumulatorStatus {
+    fn from(err: AccumulatorError) -> Self {
+        err.kind().into()
+    }
+}
+
+/// Bytes allocated by the library
+#[repr(C)]
+#[derive(Debug)]
+pub struct ByteBuffer {
+    /// The number of bytes
+    pub len: usize,
+    /// The bytes, null when empty
+    pub data: *mut u8,
+}
+
+/// An accumulator secret key
+pub struct RsaSecretKey(AccumulatorSecretKey);
+/// An accumulator with its members
+pub struct RsaAccumulator(Accumulator);
+/// A membership witness
+pub struct RsaMembershipWitness(MembershipWitness);
+/// A non-membership witness
+pub struct RsaNonMembershipWitness(NonMembershipWitness);
+/// A membership proof
+pub struct RsaMembershipProof(MembershipProof);
+/// A non-membership proof
+pub struct RsaNonMembershipProof(NonMembershipProof);
+
+type Outcome = Result<(), AccumulatorStatus>;
+
+/// Run `f` and turn its result or a panic into a status
+fn run<F: FnOnce() -> Outcome>(f: F) -> AccumulatorStatus {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => AccumulatorStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => AccumulatorStatus::Panic,
+    }
+}
+
+unsafe fn input<'a>(data: *const u8, len: usize) -> Result<&'a [u8], AccumulatorStatus> {
+    if len == 0 {
+        Ok(&[])
+    } else if data.is_null() {
+        Err(AccumulatorStatus::NullPointer)
+    } else {
+        Ok(slice::from_raw_parts(data, len))
+    }
+}
+
+unsafe fn get<'a, T>(handle: *const T) -> Result<&'a T, AccumulatorStatus> {
+    handle.as_ref().ok_or(AccumulatorStatus::NullPointer)
+}
+
+unsafe fn get_mut<'a, T>(handle: *mut T) -> Result<&'a mut T, AccumulatorStatus> {
+    handle.as_mut().ok_or(AccumulatorStatus::NullPointer)
+}
+
+unsafe fn put<T>(out: *mut *mut T, value: Result<T, AccumulatorError>) -> Outcome {
+    if out.is_null() {
+        return Err(AccumulatorStatus::NullPointer);
+    }
+    *out = Box::into_raw(Box::new(value?));
+    Ok(())
+}
+
+unsafe fn put_bytes(out: *mut ByteBuffer, bytes: Result<Vec<u8>, AccumulatorError>) -> Outcome {
+    if out.is_null() {
+        return Err(AccumulatorStatus::NullPointer);
+    }
+    let bytes = bytes?.into_boxed_slice();
+    let len = bytes.len();
+    let data = if len == 0 {
+        ptr::null_mut()
+    } else {
+        Box::into_raw(bytes) as *mut u8
+    };
+    *out = ByteBuffer { len, data };
+    Ok(())
+}
+
+unsafe fn free<T>(handle: *mut T) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Release bytes returned by the library. The bytes are zeroed
+/// first since they may hold a secret key.
+///
+/// # Safety
+/// `buffer` must have been returned by this library and not freed before
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_byte_buffer_free(buffer: ByteBuffer) {
+    if !buffer.data.is_null() {
+        let mut bytes = Box::from_raw(ptr::slice_from_raw_parts_mut(buffer.data, buffer.len));
+        bytes.zeroize();
+    }
+}
+
+/// Generate a new secret key. This searches for two safe primes and takes a while.
+///
+/// # Safety
+/// `out` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_secret_key_new(out: *mut *mut RsaSecretKey) -> AccumulatorStatus {
+    run(|| put(out, Ok(RsaSecretKey(AccumulatorSecretKey::new()))))
+}
+
+/// Read a secret key from bytes
+///
+/// # Safety
+/// `data` must point to `len` readable bytes and `out` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_secret_key_from_bytes(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut RsaSecretKey,
+) -> AccumulatorStatus {
+    run(|| {
+        let data = input(data, len)?;
+        put(out, AccumulatorSecretKey::try_from(data).map(RsaSecretKey))
+    })
+}
+
+/// Serialize a secret key
+///
+/// # Safety
+/// `key` must be a live handle and `out` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_secret_key_to_bytes(
+    key: *const RsaSecretKey,
+    out: *mut ByteBuffer,
+) -> AccumulatorStatus {
+    run(|| put_bytes(out, get(key)?.0.to_bytes()))
+}
+
+/// Release a secret key, its factors are zeroed
+///
+/// # Safety
+/// `key` must be null or a handle that has not been freed before
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_secret_key_free(key: *mut RsaSecretKey) {
+    free(key)
+}
+
+/// Create an empty accumulator for a secret key
+///
+/// # Safety
+/// `key` must be a live handle and `out` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_new(
+    key: *const RsaSecretKey,
+    out: *mut *mut RsaAccumulator,
+) -> AccumulatorStatus {
+    run(|| {
+        let key = get(key)?;
+        put(out, Ok(RsaAccumulator(Accumulator::new(&key.0))))
+    })
+}
+
+/// Read an accumulator from bytes
+///
+/// # Safety
+/// `data` must point to `len` readable bytes and `out` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_from_bytes(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut RsaAccumulator,
+) -> AccumulatorStatus {
+    run(|| {
+        let data = input(data, len)?;
+        put(out, Accumulator::try_from(data).map(RsaAccumulator))
+    })
+}
+
+/// Serialize an accumulator with its members
+///
+/// # Safety
+/// `accumulator` must be a live handle and `out` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_to_bytes(
+    accumulator: *const RsaAccumulator,
+    out: *mut ByteBuffer,
+) -> AccumulatorStatus {
+    run(|| put_bytes(out, get(accumulator)?.0.to_bytes()))
+}
+
+/// Add a value to the accumulator
+///
+/// # Safety
+/// `accumulator` must be a live handle and `value` must point to `len` readable bytes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_insert(
+    accumulator: *mut RsaAccumulator,
+    value: *const u8,
+    len: usize,
+) -> AccumulatorStatus {
+    run(|| {
+        let value = input(value, len)?;
+        Ok(get_mut(accumulator)?.0.insert_assign(value)?)
+    })
+}
+
+/// Remove a value from the accumulator
+///
+/// # Safety
+/// `accumulator` and `key` must be live handles and `value` must point to `len` readable bytes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_remove(
+    accumulator: *mut RsaAccumulator,
+    key: *const RsaSecretKey,
+    value: *const u8,
+    len: usize,
+) -> AccumulatorStatus {
+    run(|| {
+        let value = input(value, len)?;
+        let key = get(key)?;
+        Ok(get_mut(accumulator)?.0.remove_assign(&key.0, value)?)
+    })
+}
+
+/// Release an accumulator
+///
+/// # Safety
+/// `accumulator` must be null or a handle that has not been freed before
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_free(accumulator: *mut RsaAccumulator) {
+    free(accumulator)
+}
+
+/// Create a witness that a value is in the accumulator
+///
+/// # Safety
+/// `accumulator` must be a live handle, `value` must point to
+/// `len` readable bytes and `out` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_membership_witness_new(
+    accumulator: *const RsaAccumulator,
+    value: *const u8,
+    len: usize,
+    out: *mut *mut RsaMembershipWitness,
+) -> AccumulatorStatus {
+    run(|| {
+        let value = input(value, len)?;
+        let accumulator = get(accumulator)?;
+        put(out, MembershipWitness::new(&accumulator.0, value).map(RsaMembershipWitness))
+    })
+}
+
+/// Read a membership witness from bytes
+///
+/// # Safety
+/// `data` must point to `len` readable bytes and `out` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_membership_witness_from_bytes(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut RsaMembershipWitness,
+) -> AccumulatorStatus {
+    run(|| {
+        let data = input(data, len)?;
+        put(out, MembershipWitness::try_from(data).map(RsaMembershipWitness))
+    })
+}
+
+/// Serialize a membership witness
+///
+/// # Safety
+/// `witness` must be a live handle and `out` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_membership_witness_to_bytes(
+    witness: *const RsaMembershipWitness,
+    out: *mut ByteBuffer,
+) -> AccumulatorStatus {
+    run(|| put_bytes(out, get(witness)?.0.to_bytes()))
+}
+
+/// Release a membership witness
+///
+/// # Safety
+/// `witness` must be null or a handle that has not been freed before
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_membership_witness_free(witness: *mut RsaMembershipWitness) {
+    free(witness)
+}
+
+/// Create a witness that a value is not in the accumulator
+///
+/// # Safety
+/// `accumulator` must be a live handle, `value` must point to
+/// `len` readable bytes and `out` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_nonmembership_witness_new(
+    accumulator: *const RsaAccumulator,
+    value: *const u8,
+    len: usize,
+    out: *mut *mut RsaNonMembershipWitness,
+) -> AccumulatorStatus {
+    run(|| {
+        let value = input(value, len)?;
+        let accumulator = get(accumulator)?;
+        put(out, NonMembershipWitness::new(&accumulator.0, value).map(RsaNonMembershipWitness))
+    })
+}
+
+/// Read a non-membership witness from bytes
+///
+/// # Safety
+/// `data` must point to `len` readable bytes and `out` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_nonmembership_witness_from_bytes(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut RsaNonMembershipWitness,
+) -> AccumulatorStatus {
+    run(|| {
+        let data = input(data, len)?;
+        put(out, NonMembershipWitness::try_from(data).map(RsaNonMembershipWitness))
+    })
+}
+
+/// Serialize a non-membership witness
+///
+/// # Safety
+/// `witness` must be a live handle and `out` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_nonmembership_witness_to_bytes(
+    witness: *const RsaNonMembershipWitness,
+    out: *mut ByteBuffer,
+) -> AccumulatorStatus {
+    run(|| put_bytes(out, get(witness)?.0.to_bytes()))
+}
+
+/// Release a non-membership witness
+///
+/// # Safety
+/// `witness` must be null or a handle that has not been freed before
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_nonmembership_witness_free(witness: *mut RsaNonMembershipWitness) {
+    free(witness)
+}
+
+/// Prove knowledge of a member of the accumulator
+///
+/// # Safety
+/// `witness` and `accumulator` must be live handles, `nonce` must
+/// point to `nonce_len` readable bytes and `out` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_membership_proof_new(
+    witness: *const RsaMembershipWitness,
+    accumulator: *const RsaAccumulator,
+    nonce: *const u8,
+    nonce_len: usize,
+    out: *mut *mut RsaMembershipProof,
+) -> AccumulatorStatus {
+    run(|| {
+        let nonce = input(nonce, nonce_len)?;
+        let (witness, accumulator) = (get(witness)?, get(accumulator)?);
+        put(
+            out,
+            MembershipProof::new(&witness.0, &accumulator.0, nonce).map(RsaMembershipProof),
+        )
+    })
+}
+
+/// Check a membership proof, `valid` is set to the result
+///
+/// # Safety
+/// `proof` and `accumulator` must be live handles, `nonce` must point
+/// to `nonce_len` readable bytes and `valid` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_membership_proof_verify(
+    proof: *const RsaMembershipProof,
+    accumulator: *const RsaAccumulator,
+    nonce: *const u8,
+    nonce_len: usize,
+    valid: *mut bool,
+) -> AccumulatorStatus {
+    run(|| {
+        let nonce = input(nonce, nonce_len)?;
+        let (proof, accumulator) = (get(proof)?, get(accumulator)?);
+        *get_mut(valid)? = proof.0.verify(&accumulator.0, nonce);
+        Ok(())
+    })
+}
+
+/// Read a membership proof from bytes
+///
+/// # Safety
+/// `data` must point to `len` readable bytes and `out` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_membership_proof_from_bytes(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut RsaMembershipProof,
+) -> AccumulatorStatus {
+    run(|| {
+        let data = input(data, len)?;
+        put(out, MembershipProof::try_from(data).map(RsaMembershipProof))
+    })
+}
+
+/// Serialize a membership proof
+///
+/// # Safety
+/// `proof` must be a live handle and `out` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_membership_proof_to_bytes(
+    proof: *const RsaMembershipProof,
+    out: *mut ByteBuffer,
+) -> AccumulatorStatus {
+    run(|| put_bytes(out, get(proof)?.0.to_bytes()))
+}
+
+/// Release a membership proof
+///
+/// # Safety
+/// `proof` must be null or a handle that has not been freed before
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_membership_proof_free(proof: *mut RsaMembershipProof) {
+    free(proof)
+}
+
+/// Prove a value is not a member of the accumulator
+///
+/// # Safety
+/// `witness` and `accumulator` must be live handles, `nonce` must
+/// point to `nonce_len` readable bytes and `out` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_nonmembership_proof_new(
+    witness: *const RsaNonMembershipWitness,
+    accumulator: *const RsaAccumulator,
+    nonce: *const u8,
+    nonce_len: usize,
+    out: *mut *mut RsaNonMembershipProof,
+) -> AccumulatorStatus {
+    run(|| {
+        let nonce = input(nonce, nonce_len)?;
+        let (witness, accumulator) = (get(witness)?, get(accumulator)?);
+        put(
+            out,
+            NonMembershipProof::new(&witness.0, &accumulator.0, nonce).map(RsaNonMembershipProof),
+        )
+    })
+}
+
+/// Check a non-membership proof, `valid` is set to the result
+///
+/// # Safety
+/// `proof` and `accumulator` must be live handles, `nonce` must point
+/// to `nonce_len` readable bytes and `valid` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_nonmembership_proof_verify(
+    proof: *const RsaNonMembershipProof,
+    accumulator: *const RsaAccumulator,
+    nonce: *const u8,
+    nonce_len: usize,
+    valid: *mut bool,
+) -> AccumulatorStatus {
+    run(|| {
+        let nonce = input(nonce, nonce_len)?;
+        let (proof, accumulator) = (get(proof)?, get(accumulator)?);
+        *get_mut(valid)? = proof.0.verify(&accumulator.0, nonce);
+        Ok(())
+    })
+}
+
+/// Read a non-membership proof from bytes
+///
+/// # Safety
+/// `data` must point to `len` readable bytes and `out` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_nonmembership_proof_from_bytes(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut RsaNonMembershipProof,
+) -> AccumulatorStatus {
+    run(|| {
+        let data = input(data, len)?;
+        put(out, NonMembershipProof::try_from(data).map(RsaNonMembershipProof))
+    })
+}
+
+/// Serialize a non-membership proof
+///
+/// # Safety
+/// `proof` must be a live handle and `out` must be valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_nonmembership_proof_to_bytes(
+    proof: *const RsaNonMembershipProof,
+    out: *mut ByteBuffer,
+) -> AccumulatorStatus {
+    run(|| put_bytes(out, get(proof)?.0.to_bytes()))
+}
+
+/// Release a non-membership proof
+///
+/// # Safety
+/// `proof` must be null or a handle that has not been freed before
+#[no_mangle]
+pub unsafe extern "C" fn accumulator_nonmembership_proof_free(proof: *mut RsaNonMembershipProof) {
+    free(proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use accumulator_rsa::prelude::BigInteger;
+
+    fn key_bytes() -> Vec<u8> {
+        // Fixed safe primes so the test doesn't spend minutes on key generation
+        let p = BigInteger::from("132590288326793330806752358172617836030510421524323425886695490513600853466362871997907908739315399849138190997738786757721539635477379820932279026029679011350046717599386392663749253953274352000157227488895139775977945940993648470523136879899410690348931562489237825925601577159953591977449106730133820825719");
+        let q = BigInteger::from("149253707427499607752440533538420296779167710000842829107795675900185486091323606384260179778233711456748787559527972657213022998726578510459854530854900733457277643303592216900588246498239579922221956281290954735600574251392801029419096160964874150455156365996536205549377586240264971604869515447059744740119");
+        AccumulatorSecretKey::from_primes(p, q).to_bytes().unwrap()
+    }
+
+    #[test]
+    fn membership() {
+        unsafe {
+            let bytes = key_bytes();
+            let mut key = ptr::null_mut();
+            assert_eq!(accumulator_secret_key_from_bytes(bytes.as_ptr(), bytes.len(), &mut key), AccumulatorStatus::Ok);
+            let mut acc = ptr::null_mut();
+            assert_eq!(accumulator_new(key, &mut acc), AccumulatorStatus::Ok);
+            for m in &[b"3", b"7", b"9"] {
+                assert_eq!(accumulator_insert(acc, m.as_ptr(), m.len()), AccumulatorStatus::Ok);
+            }
+            assert_eq!(accumulator_insert(acc, b"3".as_ptr(), 1), AccumulatorStatus::DuplicateValue);
+            assert_eq!(accumulator_remove(acc, key, b"9".as_ptr(), 1), AccumulatorStatus::Ok);
+            assert_eq!(accumulator_remove(acc, key, b"9".as_ptr(), 1), AccumulatorStatus::InvalidMember);
+
+            let mut buffer = ByteBuffer { len: 0, data: ptr::null_mut() };
+            assert_eq!(accumulator_to_bytes(acc, &mut buffer), AccumulatorStatus::Ok);
+            let mut copy = ptr::null_mut();
+            assert_eq!(accumulator_from_bytes(buffer.data, buffer.len, &mut copy), AccumulatorStatus::Ok);
+            assert_eq!((*copy).0, (*acc).0);
+            accumulator_byte_buffer_free(buffer);
+            accumulator_free(copy);
+
+            let mut witness = ptr::null_mut();
+            assert_eq!(accumulator_membership_witness_new(acc, b"7".as_ptr(), 1, &mut witness), AccumulatorStatus::Ok);
+            let mut proof = ptr::null_mut();
+            assert_eq!(
+                accumulator_membership_proof_new(witness, acc, b"ffi".as_ptr(), 3, &mut proof),
+                AccumulatorStatus::Ok
+            );
+            let mut buffer = ByteBuffer { len: 0, data: ptr::null_mut() };
+            assert_eq!(accumulator_membership_proof_to_bytes(proof, &mut buffer), AccumulatorStatus::Ok);
+            let mut parsed = ptr::null_mut();
+            assert_eq!(
+                accumulator_membership_proof_from_bytes(buffer.data, buffer.len, &mut parsed),
+                AccumulatorStatus::Ok
+            );
+            accumulator_byte_buffer_free(buffer);
+            let mut valid = false;
+            assert_eq!(
+                accumulator_membership_proof_verify(parsed, acc, b"ffi".as_ptr(), 3, &mut valid),
+                AccumulatorStatus::Ok
+            );
+            assert!(valid);
+            assert_eq!(
+                accumulator_membership_proof_verify(parsed, acc, b"other".as_ptr(), 5, &mut valid),
+                AccumulatorStatus::Ok
+            );
+            assert!(!valid);
+
+            accumulator_membership_proof_free(parsed);
+            accumulator_membership_proof_free(proof);
+            accumulator_membership_witness_free(witness);
+            accumulator_free(acc);
+            accumulator_secret_key_free(key);
+        }
+    }
+
+    #[test]
+    fn nonmembership() {
+        unsafe {
+            let bytes = key_bytes();
+            let mut key = ptr::null_mut();
+            assert_eq!(accumulator_secret_key_from_bytes(bytes.as_ptr(), bytes.len(), &mut key), AccumulatorStatus::Ok);
+            let mut acc = ptr::null_mut();
+            assert_eq!(accumulator_new(key, &mut acc), AccumulatorStatus::Ok);
+            for m in &[b"3", b"7"] {
+                assert_eq!(accumulator_insert(acc, m.as_ptr(), m.len()), AccumulatorStatus::Ok);
+            }
+
+            let mut witness = ptr::null_mut();
+            assert_eq!(
+                accumulator_nonmembership_witness_new(acc, b"3".as_ptr(), 1, &mut witness),
+                AccumulatorStatus::InvalidMember
+            );
+            assert_eq!(accumulator_nonmembership_witness_new(acc, b"5".as_ptr(), 1, &mut witness), AccumulatorStatus::Ok);
+            let mut buffer = ByteBuffer { len: 0, data: ptr::null_mut() };
+            assert_eq!(accumulator_nonmembership_witness_to_bytes(witness, &mut buffer), AccumulatorStatus::Ok);
+            let mut copy = ptr::null_mut();
+            assert_eq!(
+                accumulator_nonmembership_witness_from_bytes(buffer.data, buffer.len, &mut copy),
+                AccumulatorStatus::Ok
+            );
+            assert_eq!((*copy).0, (*witness).0);
+            accumulator_byte_buffer_free(buffer);
+            accumulator_nonmembership_witness_free(witness);
+            witness = copy;
+            let mut proof = ptr::null_mut();
+            assert_eq!(
+                accumulator_nonmembership_proof_new(witness, acc, b"ffi".as_ptr(), 3, &mut proof),
+                AccumulatorStatus::Ok
+            );
+            let mut valid = false;
+            assert_eq!(
+                accumulator_nonmembership_proof_verify(proof, acc, b"ffi".as_ptr(), 3, &mut valid),
+                AccumulatorStatus::Ok
+            );
+            assert!(valid);
+
+            accumulator_nonmembership_proof_free(proof);
+            accumulator_nonmembership_witness_free(witness);
+            accumulator_free(acc);
+            accumulator_secret_key_free(key);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        unsafe {
+            let mut acc = ptr::null_mut();
+            assert_eq!(accumulator_new(ptr::null(), &mut acc), AccumulatorStatus::NullPointer);
+            assert!(acc.is_null());
+            assert_eq!(accumulator_from_bytes(ptr::null(), 4, &mut acc), AccumulatorStatus::NullPointer);
+            assert_eq!(accumulator_from_bytes(b"RSAA".as_ptr(), 4, &mut acc), AccumulatorStatus::InvalidLength);
+            assert_eq!(
+                accumulator_membership_witness_from_bytes(b"nope".as_ptr(), 4, ptr::null_mut()),
+                AccumulatorStatus::NullPointer
+            );
+            accumulator_free(ptr::null_mut());
+            accumulator_byte_buffer_free(ByteBuffer { len: 0, data: ptr::null_mut() });
+        }
+    }
+}
@@ -1,35 +1,66 @@
 use crate::{
-    b2fa, hash::hash_to_prime, key::AccumulatorSecretKey, FACTOR_SIZE, MEMBER_SIZE,
-    MEMBER_SIZE_BITS, MIN_BYTES,
+    b2fa,
+    batch::{aggregate, BatchUpdate},
+    format::{decode_member, encode_member, use_member_deltas, Header, Tag, HEADER_SIZE, MEMBERS_DELTA, MEMBERS_FIXED},
+    hash::hash_to_prime_with,
+    key::{AccumulatorSecretKey, SecretKeyOps},
+    memproof::MembershipProof,
+    memwitness::MembershipWitness,
+    nonmemproof::NonMembershipProof,
+    nonwitness::NonMembershipWitness,
+    prime::PrimeMember,
+    store::{product, product_tree, MemberIter, MemberStore},
+    telemetry,
+    FACTOR_SIZE, MEMBER_PRIMALITY, MEMBER_SIZE, MEMBER_SIZE_BITS, MIN_BYTES,
 };
 use common::{
-    bigint::BigInteger,
+    bigint::{BigInteger, PrimalityTest},
+    capability::{Accumulate, Dynamic, RequiresTrapdoor, Universal},
     error::{AccumulatorError, AccumulatorErrorKind},
+    Field, FixedBase,
 };
-use rayon::prelude::*;
+use crate::par::*;
+#[cfg(feature = "std")]
+use crate::builder::AccumulatorBuilder;
+#[cfg(feature = "std")]
+use crate::format::write_element;
+#[cfg(all(feature = "std", not(test)))]
+use crate::random_below;
+#[cfg(feature = "std")]
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+#[cfg(not(feature = "std"))]
+use alloc::{collections::BTreeSet, sync::Arc, vec, vec::Vec};
+use core::{
+    convert::TryFrom,
+    ops::{Add, AddAssign, Sub, SubAssign},
+};
+#[cfg(feature = "std")]
 use std::{
     collections::BTreeSet,
-    convert::TryFrom,
-    ops::{Add, AddAssign},
+    io::{Read, Write},
+    sync::Arc,
 };
 
 macro_rules! remove_type {
     ($remove:ident, $remove_mut:ident, $ty:ty) => {
         /// Remove a stringify!($ty) from the accumulator if it exists
-        pub fn $remove(
+        pub fn $remove<K: SecretKeyOps>(
             &self,
-            key: &AccumulatorSecretKey,
+            key: &K,
             v: $ty,
-        ) -> Result<Self, AccumulatorError> {
+        ) -> Result<Self, AccumulatorError>
+        where
+            S: Clone,
+        {
             let mut a = self.clone();
             a.remove_assign(key, v.to_be_bytes())?;
             Ok(a)
         }
 
         /// Remove a stringify!($ty) from the accumulator if it exists
-        pub fn $remove_mut(
+        pub fn $remove_mut<K: SecretKeyOps>(
             &mut self,
-            key: &AccumulatorSecretKey,
+            key: &K,
             v: $ty,
         ) -> Result<(), AccumulatorError> {
             self.remove_assign(key, v.to_be_bytes())
@@ -38,76 +69,100 @@ macro_rules! remove_type {
 }
 
 /// Represents a Universal RSA Accumulator.
-#[derive(Debug, Eq, PartialEq)]
-pub struct Accumulator {
+///
+/// `generator` and `modulus` are `Arc<BigInteger>` so clones share them.
+/// Read them through `&*acc.modulus` or `acc.modulus.as_ref()`, and
+/// `(*acc.modulus).clone()` gives an owned copy
+#[derive(Debug)]
+pub struct Accumulator<S = BTreeSet<BigInteger>> {
     /// The initial value of the accumulator and the generator
-    /// to be used for generating proofs
-    pub generator: BigInteger,
+    /// to be used for generating proofs. Shared by clones
+    pub generator: Arc<BigInteger>,
     /// The current set of members in the accumulator
-    pub members: BTreeSet<BigInteger>,
-    /// The RSA modulus
-    pub modulus: BigInteger,
+    pub members: S,
+    /// The RSA modulus. Shared by clones
+    pub modulus: Arc<BigInteger>,
     /// The current accumulator value with all `members`
     pub value: BigInteger,
+    /// The number of updates applied to `value`. Starts at zero
+    /// and increases by one with every insert, removal or batch
+    pub epoch: u64,
+    /// Precomputed tables for `generator` and `value`
+    pub(crate) fixed_bases: Vec<FixedBase>,
+    /// The product of `members` if cached by `cache_product`
+    pub(crate) product: Option<BigInteger>,
+    /// How members are tested for primality, see `set_primality`
+    pub(crate) primality: PrimalityTest,
 }
 
 impl Accumulator {
+    /// Configure a new accumulator
+    #[cfg(feature = "std")]
+    pub fn builder() -> AccumulatorBuilder {
+        AccumulatorBuilder::new()
+    }
+
     /// Create a new accumulator
-    pub fn new(key: &AccumulatorSecretKey) -> Self {
+    #[cfg(feature = "std")]
+    pub fn new<K: SecretKeyOps>(key: &K) -> Self {
         let modulus = key.modulus();
-        let generator = random_qr(&modulus);
+        // A value below the modulus always converts back from its bytes
+        let generator = random_qr(&modulus, &mut OsRng).expect("the generator is below the modulus");
         let value = generator.clone();
         Self {
-            generator,
+            generator: Arc::new(generator),
             members: BTreeSet::new(),
-            modulus,
+            modulus: Arc::new(modulus),
             value,
+            epoch: 0,
+            fixed_bases: Vec::new(),
+            product: None,
+            primality: MEMBER_PRIMALITY,
         }
     }
 
     /// Initialize a new accumulator prefilled with entries
-    pub fn with_members<M: AsRef<[B]>, B: AsRef<[u8]>>(key: &AccumulatorSecretKey, m: M) -> Self {
+    #[cfg(feature = "std")]
+    pub fn with_members<K: SecretKeyOps, M: AsRef<[B]>, B: AsRef<[u8]>>(
+        key: &K,
+        m: M,
+    ) -> Result<Self, AccumulatorError> {
         let m: Vec<&[u8]> = m.as_ref().iter().map(|b| b.as_ref()).collect();
-        let members: BTreeSet<BigInteger> = m.par_iter().map(|b| hash_to_prime(b)).collect();
+        let members: BTreeSet<BigInteger> = m.par_iter().map(|b| hash_to_prime_with(b, MEMBER_PRIMALITY)).collect();
         Self::_add_members(key, members)
     }
 
     /// Add prehash members which doesn't do hash_to_prime but just checks for prime
-    pub fn with_prime_members(
-        key: &AccumulatorSecretKey,
+    #[cfg(feature = "std")]
+    pub fn with_prime_members<K: SecretKeyOps>(
+        key: &K,
         m: &[BigInteger],
     ) -> Result<Self, AccumulatorError> {
         let members: BTreeSet<BigInteger> = m.par_iter().cloned().collect();
-        if members.par_iter().any(|b| !b.is_prime()) {
+        if members.par_iter().any(|b| !b.is_prime_with(MEMBER_PRIMALITY)) {
             return Err(AccumulatorError::from_msg(
                 AccumulatorErrorKind::InvalidMemberSupplied,
                 "Some values are not prime and cannot be added",
             ));
         }
-        Ok(Self::_add_members(key, members))
-    }
-
-    /// Add many members
-    pub fn add_prime_members(&self, m: &[BigInteger]) -> Result<Self, AccumulatorError> {
-        let mut acc = self.clone();
-        acc.add_prime_members_assign(m)?;
-        Ok(acc)
+        Self::_add_members(key, members)
     }
 
-    /// Add many members
-    pub fn add_prime_members_assign(&mut self, m: &[BigInteger]) -> Result<(), AccumulatorError> {
-        if m.par_iter().any(|b| !b.is_prime() || self.members.contains(&b)) {
-            return Err(AccumulatorError::from_msg(AccumulatorErrorKind::InvalidMemberSupplied, "Some values are not prime and already exist in the set"));
-        }
-
-        for i in m {
-            self.insert_prime_assign(i)?;
-        }
-
-        Ok(())
+    /// Initialize a new accumulator with prime representatives the caller
+    /// manages, already checked to be primes of the member size
+    #[cfg(feature = "std")]
+    pub fn with_checked_primes<K: SecretKeyOps>(
+        key: &K,
+        m: &[PrimeMember],
+    ) -> Result<Self, AccumulatorError> {
+        Self::_add_members(key, m.iter().map(|p| p.value().clone()).collect())
     }
 
-    fn _add_members(key: &AccumulatorSecretKey, members: BTreeSet<BigInteger>) -> Self {
+    #[cfg(feature = "std")]
+    fn _add_members<K: SecretKeyOps>(
+        key: &K,
+        members: BTreeSet<BigInteger>,
+    ) -> Result<Self, AccumulatorError> {
         // From section 3.2 in https://cs.brown.edu/people/alysyans/papers/camlys02.pdf
         // For Update of the accumulator value:
         // N = p * q
@@ -115,24 +170,237 @@ impl Accumulator {
         // To batch add values to the exponent, compute
         // \pi_add = (x_1 * ... * x_n) \mod (\varphi)
         // v ^ {\pi_add} mod N
-        let totient = key.totient();
-        let exp = members
-            .par_iter()
-            .cloned()
-            .reduce(|| BigInteger::from(1u32), |v, m| v.mod_mul(&m, &totient));
         let modulus = key.modulus();
-        let generator = random_qr(&modulus);
-        let value = (&generator).mod_exp(&exp, &modulus);
+        let generator = random_qr(&modulus, &mut OsRng)?;
+        let exponents: Vec<BigInteger> = members.iter().cloned().collect();
+        let value = key.exp_product(&generator, &exponents)?;
+        Ok(Self {
+            generator: Arc::new(generator),
+            members,
+            modulus: Arc::new(modulus),
+            value,
+            epoch: 0,
+            fixed_bases: Vec::new(),
+            product: None,
+            primality: MEMBER_PRIMALITY,
+        })
+    }
+
+    /// Read an accumulator written by `write_to` or `to_bytes` from `r`
+    #[cfg(feature = "std")]
+    pub fn read_from<R: Read>(r: &mut R) -> Result<Self, AccumulatorError> {
+        let mut header = [0u8; HEADER_SIZE];
+        r.read_exact(&mut header).map_err(io_error)?;
+        Header::check(&header, Tag::Accumulator)?;
+
+        let mut factor = [0u8; FACTOR_SIZE * 2];
+        let mut read_factor = |r: &mut R| -> Result<BigInteger, AccumulatorError> {
+            r.read_exact(&mut factor).map_err(io_error)?;
+            BigInteger::try_from(&factor[..])
+        };
+        let generator = read_factor(r)?;
+        let value = read_factor(r)?;
+        let modulus = read_factor(r)?;
+        check_parameters(&generator, &value, &modulus)?;
+
+        let mut epoch = [0u8; 8];
+        r.read_exact(&mut epoch).map_err(io_error)?;
+        let mut count = [0u8; 5];
+        r.read_exact(&mut count).map_err(io_error)?;
+        let deltas = member_deltas(count[4])?;
+
+        let mut members = BTreeSet::new();
+        let mut prev = None;
+        for _ in 0..u32::from_be_bytes(*array_ref![count, 0, 4]) {
+            let m = decode_member(prev.as_ref(), || {
+                let mut b = [0u8; 1];
+                r.read_exact(&mut b).map_err(io_error)?;
+                Ok(b[0])
+            })?;
+            if deltas {
+                prev = Some(m.clone());
+            }
+            push_member(&mut members, m)?;
+        }
+        Ok(Self {
+            generator: Arc::new(generator),
+            members,
+            modulus: Arc::new(modulus),
+            value,
+            epoch: u64::from_be_bytes(epoch),
+            fixed_bases: Vec::new(),
+            product: None,
+            primality: MEMBER_PRIMALITY,
+        })
+    }
+
+    /// Read member primes written by `export_primes` from `r` and return
+    /// the epoch they were exported at with the primes in ascending order.
+    /// Fails if they are not strictly ascending or any isn't prime
+    #[cfg(feature = "std")]
+    pub fn import_primes<R: Read>(mut r: R) -> Result<(u64, Vec<BigInteger>), AccumulatorError> {
+        let mut header = [0u8; HEADER_SIZE + 12];
+        r.read_exact(&mut header).map_err(io_error)?;
+        Header::check(&header, Tag::MemberPrimes)?;
+        let epoch = u64::from_be_bytes(*array_ref![header, HEADER_SIZE, 8]);
+        let count = u32::from_be_bytes(*array_ref![header, HEADER_SIZE + 8, 4]);
+
+        let mut members = Vec::new();
+        let mut member = [0u8; MEMBER_SIZE];
+        for _ in 0..count {
+            r.read_exact(&mut member).map_err(io_error)?;
+            let m = BigInteger::try_from(&member[..])?;
+            if members.last().is_some_and(|prev| prev >= &m) {
+                return Err(AccumulatorError::from_msg(
+                    AccumulatorErrorKind::SerializationError,
+                    "members are not in ascending order",
+                ));
+            }
+            members.push(m);
+        }
+        if members.par_iter().any(|m| !m.is_prime_with(MEMBER_PRIMALITY)) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "Some values are not prime",
+            ));
+        }
+        Ok((epoch, members))
+    }
+
+    /// Rebuild the accumulator at a later epoch from `checkpoint` and the
+    /// batch updates published after it, in order. The checkpoint's value
+    /// must be its generator raised to its members, see `Checkpoint::verify`.
+    /// Each update's proofs are checked against the state before it, its
+    /// deletions must be members and its additions must not be
+    pub fn replay<'a, I>(checkpoint: &Checkpoint, updates: I) -> Result<Self, AccumulatorError>
+    where
+        I: IntoIterator<Item = &'a BatchUpdate>,
+    {
+        if !checkpoint.verify() {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::ProofVerificationFailed,
+                "checkpoint value does not match its members",
+            ));
+        }
+        let mut public = checkpoint.public();
+        let mut members = checkpoint.0.members.clone();
+        for update in updates {
+            let next = update.apply(&public)?;
+            if !update.deletions.iter().all(|d| members.remove(d)) {
+                return Err(AccumulatorError::from_msg(
+                    AccumulatorErrorKind::InvalidMemberSupplied,
+                    "update deletes a value that is not a member",
+                ));
+            }
+            if !update.additions.iter().all(|a| members.insert(a.clone())) {
+                return Err(AccumulatorErrorKind::DuplicateValueSupplied.into());
+            }
+            public = next;
+        }
+        Ok(Self::from_parts(public, members))
+    }
+
+    /// An accumulator in the state `public` holding `members`. The value
+    /// isn't checked against the members, callers make sure they match
+    pub(crate) fn from_parts(public: AccumulatorPublic, members: BTreeSet<BigInteger>) -> Self {
         Self {
-            generator,
+            generator: Arc::new(public.generator),
             members,
-            modulus,
+            modulus: Arc::new(public.modulus),
+            value: public.value,
+            epoch: public.epoch,
+            fixed_bases: Vec::new(),
+            product: None,
+            primality: MEMBER_PRIMALITY,
+        }
+    }
+}
+
+impl<S: MemberStore> Accumulator<S> {
+    /// Create an accumulator for the members already in `store`.
+    /// The members are read in batches so they never all have to be in memory
+    #[cfg(feature = "std")]
+    pub fn with_store<K: SecretKeyOps>(key: &K, store: S) -> Result<Self, AccumulatorError> {
+        Self::with_store_and_rng(key, store, &mut OsRng)
+    }
+
+    /// Same as `with_store` but the generator is sampled with `rng`
+    #[cfg(feature = "std")]
+    pub fn with_store_and_rng<K: SecretKeyOps, R: RngCore + CryptoRng>(
+        key: &K,
+        store: S,
+        rng: &mut R,
+    ) -> Result<Self, AccumulatorError> {
+        const BATCH: usize = 4096;
+
+        let modulus = key.modulus();
+        let generator = random_qr(&modulus, rng)?;
+        let mut value = generator.clone();
+        let mut batch = Vec::with_capacity(BATCH);
+        for m in store.iter() {
+            batch.push(m?);
+            if batch.len() == BATCH {
+                value = key.exp_product(&value, &batch)?;
+                batch.clear();
+            }
+        }
+        if !batch.is_empty() {
+            value = key.exp_product(&value, &batch)?;
+        }
+        Ok(Self {
+            generator: Arc::new(generator),
+            members: store,
+            modulus: Arc::new(modulus),
             value,
+            epoch: 0,
+            fixed_bases: Vec::new(),
+            product: None,
+            primality: MEMBER_PRIMALITY,
+        })
+    }
+
+    /// Add many members
+    pub fn add_prime_members(&self, m: &[BigInteger]) -> Result<Self, AccumulatorError>
+    where
+        S: Clone,
+    {
+        let mut acc = self.clone();
+        acc.add_prime_members_assign(m)?;
+        Ok(acc)
+    }
+
+    /// Add many members as a single update, the epoch only increases by one.
+    /// Nothing changes if any value isn't a prime of the member size, is
+    /// already a member or is repeated
+    pub fn add_prime_members_assign(&mut self, m: &[BigInteger]) -> Result<(), AccumulatorError> {
+        let primality = self.primality;
+        if m.par_iter().any(|b| b.bits() < MEMBER_SIZE_BITS || !b.is_prime_with(primality)) {
+            return Err(AccumulatorError::from_msg(AccumulatorErrorKind::InvalidMemberSupplied, "Some values are not primes of the member size"));
+        }
+        let mut seen = BTreeSet::new();
+        for b in m {
+            if !seen.insert(b) || self.members.contains(b)? {
+                return Err(AccumulatorError::from_msg(AccumulatorErrorKind::InvalidMemberSupplied, "Some values already exist in the set or are repeated"));
+            }
         }
+
+        let x_hat = product_tree(m, None);
+        self.apply_to_store(m, &[])?;
+        self.value.mod_exp_assign(&x_hat, &self.modulus);
+        if let Some(p) = &mut self.product {
+            *p *= &x_hat;
+        }
+        self.epoch += 1;
+        self.drop_stale_tables();
+        telemetry::count(telemetry::INSERTIONS, m.len());
+        Ok(())
     }
 
     /// Add a value to the accumulator, the value will be hashed to a prime number first
-    pub fn insert<B: AsRef<[u8]>>(&self, value: B) -> Result<Self, AccumulatorError> {
+    pub fn insert<B: AsRef<[u8]>>(&self, value: B) -> Result<Self, AccumulatorError>
+    where
+        S: Clone,
+    {
         let mut a = self.clone();
         a.insert_assign(value)?;
         Ok(a)
@@ -140,12 +408,15 @@ impl Accumulator {
 
     /// Add a value an update this accumulator
     pub fn insert_assign<B: AsRef<[u8]>>(&mut self, value: B) -> Result<(), AccumulatorError> {
-        let p = hash_to_prime(value);
-        self._insert(&p)
+        let p = self.hash_member(&value);
+        self._insert(&p, Some(value.as_ref()))
     }
 
     /// Add a prime value to the accumulator, the value will be checked for primality first
-    pub fn insert_prime(&self, value: &BigInteger) -> Result<Self, AccumulatorError> {
+    pub fn insert_prime(&self, value: &BigInteger) -> Result<Self, AccumulatorError>
+    where
+        S: Clone,
+    {
         let mut a = self.clone();
         a.insert_prime_assign(value)?;
         Ok(a)
@@ -153,7 +424,7 @@ impl Accumulator {
 
     /// Add a prime value an update this accumulator
     pub fn insert_prime_assign(&mut self, value: &BigInteger) -> Result<(), AccumulatorError> {
-        if !value.is_prime() {
+        if !value.is_prime_with(self.primality) {
             return Err(AccumulatorError::from_msg(
                 AccumulatorErrorKind::InvalidMemberSupplied,
                 "value is not prime",
@@ -165,116 +436,1092 @@ impl Accumulator {
                 "value is not sufficiently large to be safely accumulated",
             ));
         }
-        self._insert(value)
+        self._insert(value, None)
+    }
+
+    /// Add a prime representative already checked to be a prime of the member size
+    pub fn insert_checked_prime_assign(
+        &mut self,
+        value: &PrimeMember,
+    ) -> Result<(), AccumulatorError> {
+        self._insert(value.value(), None)
     }
 
-    fn _insert(&mut self, value: &BigInteger) -> Result<(), AccumulatorError> {
-        if self.members.contains(&value) {
+    fn _insert(&mut self, value: &BigInteger, preimage: Option<&[u8]>) -> Result<(), AccumulatorError> {
+        if self.members.contains(value)? {
             return Err(AccumulatorErrorKind::DuplicateValueSupplied.into());
         }
-        self.members.insert(value.clone());
+        match preimage {
+            Some(preimage) => self.members.insert_preimage(value.clone(), preimage)?,
+            None => self.members.insert(value.clone())?,
+        };
         self.value.mod_exp_assign(&value, &self.modulus);
+        if let Some(p) = &mut self.product {
+            *p *= value;
+        }
+        self.epoch += 1;
+        self.drop_stale_tables();
+        telemetry::count(telemetry::INSERTIONS, 1);
         Ok(())
     }
 
     /// Remove a value from the accumulator and return
     /// a new accumulator without `value`
-    pub fn remove<B: AsRef<[u8]>>(
+    pub fn remove<K: SecretKeyOps, B: AsRef<[u8]>>(
         &self,
-        key: &AccumulatorSecretKey,
+        key: &K,
         value: B,
-    ) -> Result<Self, AccumulatorError> {
+    ) -> Result<Self, AccumulatorError>
+    where
+        S: Clone,
+    {
         let mut a = self.clone();
         a.remove_assign(key, value)?;
         Ok(a)
     }
 
     /// Remove a value from the accumulator if it exists
-    pub fn remove_assign<B: AsRef<[u8]>>(
+    pub fn remove_assign<K: SecretKeyOps, B: AsRef<[u8]>>(
         &mut self,
-        key: &AccumulatorSecretKey,
+        key: &K,
         value: B,
     ) -> Result<(), AccumulatorError> {
-        let v = hash_to_prime(value);
+        let v = self.hash_member(value);
         self._remove(key, &v)
     }
 
     /// Remove a prime value from the accumulator and return
     /// a new accumulator without `value`
-    pub fn remove_prime(
+    pub fn remove_prime<K: SecretKeyOps>(
         &self,
-        key: &AccumulatorSecretKey,
+        key: &K,
         value: &BigInteger,
-    ) -> Result<Self, AccumulatorError> {
+    ) -> Result<Self, AccumulatorError>
+    where
+        S: Clone,
+    {
         let mut a = self.clone();
         a.remove_prime_assign(key, value)?;
         Ok(a)
     }
 
-    /// Remove a prime value from the accumulator if it exists
-    pub fn remove_prime_assign(
-        &mut self,
-        key: &AccumulatorSecretKey,
-        value: &BigInteger,
-    ) -> Result<(), AccumulatorError> {
-        self._remove(key, value)
+    /// Remove a prime value from the accumulator if it exists
+    pub fn remove_prime_assign<K: SecretKeyOps>(
+        &mut self,
+        key: &K,
+        value: &BigInteger,
+    ) -> Result<(), AccumulatorError> {
+        self._remove(key, value)
+    }
+
+    /// Remove the member of `witness` without the secret key and return
+    /// a new accumulator. The witness is the accumulator value without
+    /// its member, it must be up to date with this accumulator
+    pub fn remove_with_witness(&self, witness: &MembershipWitness) -> Result<Self, AccumulatorError>
+    where
+        S: Clone,
+    {
+        let mut a = self.clone();
+        a.remove_with_witness_assign(witness)?;
+        Ok(a)
+    }
+
+    /// Remove the member of `witness` without the secret key
+    pub fn remove_with_witness_assign(&mut self, witness: &MembershipWitness) -> Result<(), AccumulatorError> {
+        self._remove_with_root(&witness.x, &witness.u)
+    }
+
+    /// Remove the members of `witnesses` without the secret key and return
+    /// a new accumulator. See `remove_with_witnesses_assign`
+    pub fn remove_with_witnesses(&self, witnesses: &[MembershipWitness]) -> Result<Self, AccumulatorError>
+    where
+        S: Clone,
+    {
+        let mut a = self.clone();
+        a.remove_with_witnesses_assign(witnesses)?;
+        Ok(a)
+    }
+
+    /// Remove the members of `witnesses` without the secret key as a single
+    /// update, the epoch only increases by one. The witnesses are combined
+    /// with the Shamir trick into the root of the accumulator value by the
+    /// product of their members. Nothing changes if any witness isn't up to
+    /// date with this accumulator or two witnesses are for the same member,
+    /// and an empty slice leaves the epoch where it is
+    pub fn remove_with_witnesses_assign(&mut self, witnesses: &[MembershipWitness]) -> Result<(), AccumulatorError> {
+        if witnesses.is_empty() {
+            return Ok(());
+        }
+        let mut seen = BTreeSet::new();
+        for w in witnesses {
+            if !seen.insert(&w.x) || !self.members.contains(&w.x)? {
+                return Err(AccumulatorError::from_msg(
+                    AccumulatorErrorKind::InvalidMemberSupplied,
+                    "Some witnesses are not for members or are repeated",
+                ));
+            }
+        }
+        let (root, x_hat) = aggregate(witnesses, &self.value, &self.modulus)?;
+        let members: Vec<BigInteger> = witnesses.iter().map(|w| w.x.clone()).collect();
+        self.apply_to_store(&[], &members)?;
+        if let Some(p) = &mut self.product {
+            *p = &*p / &x_hat;
+        }
+        self.value = root;
+        self.epoch += 1;
+        self.drop_stale_tables();
+        telemetry::count(telemetry::REMOVALS, witnesses.len());
+        Ok(())
+    }
+
+    /// Remove `value` where `root` is `self.value^{1/value}`
+    pub(crate) fn _remove_with_root(&mut self, value: &BigInteger, root: &BigInteger) -> Result<(), AccumulatorError> {
+        if !self.members.contains(value)? || root.mod_exp(value, &self.modulus) != self.value {
+            return Err(AccumulatorErrorKind::InvalidMemberSupplied.into());
+        }
+        self.value = root.clone();
+        self.members.remove(value)?;
+        if let Some(p) = &mut self.product {
+            *p = &*p / value;
+        }
+        self.epoch += 1;
+        self.drop_stale_tables();
+        telemetry::count(telemetry::REMOVALS, 1);
+        Ok(())
+    }
+
+    fn _remove<K: SecretKeyOps>(
+        &mut self,
+        key: &K,
+        value: &BigInteger,
+    ) -> Result<(), AccumulatorError> {
+        if !self.members.contains(value)? {
+            return Err(AccumulatorErrorKind::InvalidMemberSupplied.into());
+        }
+        self.value = key.root(&self.value, value)?;
+        self.members.remove(value)?;
+        if let Some(p) = &mut self.product {
+            *p = &*p / value;
+        }
+        self.epoch += 1;
+        self.drop_stale_tables();
+        telemetry::count(telemetry::REMOVALS, 1);
+        Ok(())
+    }
+
+    /// Add `additions` and remove `deletions` as a single update,
+    /// the epoch only increases by one. Nothing changes if any
+    /// addition isn't prime or is already a member, or any deletion isn't a member
+    pub fn update_batch_assign<K: SecretKeyOps>(
+        &mut self,
+        key: &K,
+        additions: &[BigInteger],
+        deletions: &[BigInteger],
+    ) -> Result<(), AccumulatorError> {
+        let primality = self.primality;
+        if additions.par_iter().any(|b| !b.is_prime_with(primality)) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "Some additions are not prime",
+            ));
+        }
+        let mut seen = BTreeSet::new();
+        for b in additions {
+            if !seen.insert(b) || self.members.contains(b)? {
+                return Err(AccumulatorErrorKind::DuplicateValueSupplied.into());
+            }
+        }
+        for b in deletions {
+            if !seen.insert(b) || !self.members.contains(b)? {
+                return Err(AccumulatorError::from_msg(
+                    AccumulatorErrorKind::InvalidMemberSupplied,
+                    "Some deletions are not members or are repeated",
+                ));
+            }
+        }
+
+        let mut value = key.exp_product(&self.value, additions)?;
+        let x_hat = product_tree(deletions, None);
+        if !deletions.is_empty() {
+            value = key.root(&value, &x_hat)?;
+        }
+        for b in additions {
+            self.members.insert(b.clone())?;
+        }
+        for b in deletions {
+            self.members.remove(b)?;
+        }
+        self.members.batch_applied()?;
+        if let Some(p) = &mut self.product {
+            *p = &(&*p * &product_tree(additions, None)) / &x_hat;
+        }
+        self.value = value;
+        self.epoch += 1;
+        self.drop_stale_tables();
+        telemetry::count(telemetry::INSERTIONS, additions.len());
+        telemetry::count(telemetry::REMOVALS, deletions.len());
+        Ok(())
+    }
+
+    /// Precompute tables for the generator and the current value that cover
+    /// exponents up to `bits` long. Witness and proof code use them automatically.
+    /// The value table is dropped when the value changes, call this again to rebuild it.
+    pub fn precompute(&mut self, bits: usize) {
+        self.fixed_bases = vec![
+            FixedBase::new(&self.generator, &self.modulus, bits),
+            FixedBase::new(&self.value, &self.modulus, bits),
+        ];
+    }
+
+    /// Test members hashed or given as primes from now on with `test`
+    /// instead of `MEMBER_PRIMALITY`. The setting isn't serialized, an
+    /// accumulator that is read back uses the default
+    pub fn set_primality(&mut self, test: PrimalityTest) {
+        self.primality = test;
+    }
+
+    /// How members are tested for primality
+    pub fn primality(&self) -> PrimalityTest {
+        self.primality
+    }
+
+    /// Hash `value` to the prime it's a member as, with this accumulator's primality test
+    pub(crate) fn hash_member<B: AsRef<[u8]>>(&self, value: B) -> BigInteger {
+        hash_to_prime_with(value, self.primality)
+    }
+
+    /// Keep the product of the members so witnesses don't multiply every
+    /// member each time. It's kept up to date by inserts, removals and batch
+    /// updates but not by changing `members` directly, call this again after.
+    /// The product grows by 32 bytes per member
+    pub fn cache_product(&mut self) -> Result<(), AccumulatorError> {
+        let members = self.members.iter().collect::<Result<Vec<_>, _>>()?;
+        self.product = Some(product_tree(&members, None));
+        Ok(())
+    }
+
+    /// Recompute the value from the generator and the stored members with
+    /// the totient and compare it to the current value. Long running services
+    /// can call this after a crash or migration to find corrupted state,
+    /// the report says which parts disagree. Fails if `key` is for another
+    /// modulus or the members can't be read
+    pub fn verify_consistency<K: SecretKeyOps>(&self, key: &K) -> Result<ConsistencyReport, AccumulatorError> {
+        if key.modulus() != *self.modulus {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::ParameterMismatch,
+                "key is not for this accumulator",
+            ));
+        }
+        let members = self.members.iter().collect::<Result<Vec<_>, _>>()?;
+        let primality = self.primality;
+        let not_prime = members
+            .par_iter()
+            .filter(|m| !m.is_prime_with(primality))
+            .cloned()
+            .collect();
+        let expected = key.exp_product(&self.generator, &members)?;
+        let product = self.product.as_ref().map(|p| *p == product_tree(&members, None));
+        Ok(ConsistencyReport {
+            members: members.len(),
+            value_matches: expected == self.value,
+            expected,
+            product_matches: product,
+            not_prime,
+        })
+    }
+
+    /// The product of the members except `except`, from the cache if there is one
+    pub(crate) fn member_product(&self, except: Option<&BigInteger>) -> Result<BigInteger, AccumulatorError> {
+        match (&self.product, except) {
+            (Some(p), Some(x)) if self.members.contains(x)? => Ok(p / x),
+            (Some(p), _) => Ok(p.clone()),
+            (None, _) => product(&self.members, except),
+        }
+    }
+
+    /// The precomputed tables, serialize these to store them alongside the accumulator
+    pub fn fixed_bases(&self) -> &[FixedBase] {
+        self.fixed_bases.as_slice()
+    }
+
+    /// Use a previously stored table for the generator or the current value.
+    /// Decoding a table already checks its entries are powers of its base
+    pub fn add_fixed_base(&mut self, table: FixedBase) -> Result<(), AccumulatorError> {
+        if table.modulus() != self.modulus.as_ref()
+            || (table.base() != self.generator.as_ref() && table.base() != &self.value)
+        {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::ParameterMismatch,
+                "table is not for the generator or value of this accumulator",
+            ));
+        }
+        self.fixed_bases.retain(|t| t.base() != table.base());
+        self.fixed_bases.push(table);
+        Ok(())
+    }
+
+    /// The tables and cached product to store and give to `restore`
+    /// after a restart instead of computing them again
+    pub fn precomputed(&self) -> Precomputed {
+        Precomputed {
+            value: self.value.clone(),
+            epoch: self.epoch,
+            fixed_bases: self.fixed_bases.clone(),
+            product: self.product.clone(),
+        }
+    }
+
+    /// Use tables and a product saved by `precomputed`. They must be from
+    /// this accumulator at its current epoch. The product isn't checked
+    /// against the members so only restore values this service stored
+    pub fn restore(&mut self, precomputed: Precomputed) -> Result<(), AccumulatorError> {
+        if precomputed.value != self.value || precomputed.epoch != self.epoch {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::ParameterMismatch,
+                "precomputed values are for another accumulator state",
+            ));
+        }
+        for t in precomputed.fixed_bases {
+            self.add_fixed_base(t)?;
+        }
+        if precomputed.product.is_some() {
+            self.product = precomputed.product;
+        }
+        Ok(())
+    }
+
+    /// The public state and members to give to `replay`. Tables and
+    /// the cached product aren't included
+    pub fn checkpoint(&self) -> Result<Checkpoint, AccumulatorError> {
+        let members = self.members.iter().collect::<Result<_, _>>()?;
+        Ok(Checkpoint(Accumulator::from_parts(self.public(), members)))
+    }
+
+    /// base^e mod N using the table for `base` if there is one
+    pub(crate) fn fixed_exp(&self, base: &BigInteger, e: &BigInteger) -> BigInteger {
+        match self.fixed_bases.iter().find(|t| t.base() == base) {
+            Some(t) => t.exp(e),
+            None => base.mod_exp(e, &self.modulus),
+        }
+    }
+
+    /// base^-e mod N using the table for `base` if there is one
+    pub(crate) fn fixed_exp_inv(&self, base: &BigInteger, e: &BigInteger) -> BigInteger {
+        let zero = BigInteger::default();
+        if e < &zero {
+            self.fixed_exp(base, &(&zero - e))
+        } else {
+            self.fixed_exp(base, e).mod_inverse(&self.modulus)
+        }
+    }
+
+    /// Add `additions` to and remove `deletions` from the member store.
+    /// If the store fails part way the changes made so far are undone
+    /// so the members still match the accumulator value
+    fn apply_to_store(&mut self, additions: &[BigInteger], deletions: &[BigInteger]) -> Result<(), AccumulatorError> {
+        let (mut added, mut removed) = (0, 0);
+        let result = additions
+            .iter()
+            .try_for_each(|b| {
+                self.members.insert(b.clone())?;
+                added += 1;
+                Ok(())
+            })
+            .and_then(|_| {
+                deletions.iter().try_for_each(|b| {
+                    self.members.remove(b)?;
+                    removed += 1;
+                    Ok(())
+                })
+            })
+            .and_then(|_| self.members.batch_applied());
+        if result.is_err() {
+            // The store already failed, undoing is as much as it allows
+            for b in &deletions[..removed] {
+                let _ = self.members.insert(b.clone());
+            }
+            for b in &additions[..added] {
+                let _ = self.members.remove(b);
+            }
+            let _ = self.members.batch_applied();
+        }
+        result
+    }
+
+    fn drop_stale_tables(&mut self) {
+        let (generator, value) = (self.generator.as_ref(), &self.value);
+        self.fixed_bases.retain(|t| t.base() == generator || t.base() == value);
+    }
+
+    /// The number of members
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// True if there are no members
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// True if `value` is a member, the value is hashed to a prime first
+    pub fn contains<B: AsRef<[u8]>>(&self, value: B) -> Result<bool, AccumulatorError> {
+        self.members.contains(&self.hash_member(value))
+    }
+
+    /// True if the prime `value` is a member
+    pub fn contains_prime(&self, value: &BigInteger) -> Result<bool, AccumulatorError> {
+        self.members.contains(value)
+    }
+
+    /// Prove `value` is a member without handling its witness.
+    /// The witness is made from the other members, see `MembershipWitness::new`
+    pub fn prove_membership<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &self,
+        value: B,
+        nonce: N,
+    ) -> Result<MembershipProof, AccumulatorError> {
+        let witness = MembershipWitness::new(self, value)?;
+        MembershipProof::new(&witness, self, nonce)
+    }
+
+    /// Prove `value` is a member with the witness made from `key`,
+    /// which doesn't need the other members
+    pub fn prove_membership_with_secret_key<K: SecretKeyOps, B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &self,
+        key: &K,
+        value: B,
+        nonce: N,
+    ) -> Result<MembershipProof, AccumulatorError> {
+        let x = self.hash_member(value);
+        if !self.members.contains(&x)? {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "value is not in the accumulator",
+            ));
+        }
+        let witness = MembershipWitness::with_prime_and_secret_key(self, key, &x)?;
+        MembershipProof::new(&witness, self, nonce)
+    }
+
+    /// Prove `value` is not a member without handling its witness
+    pub fn prove_non_membership<B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &self,
+        value: B,
+        nonce: N,
+    ) -> Result<NonMembershipProof, AccumulatorError> {
+        let witness = NonMembershipWitness::new(self, value)?;
+        NonMembershipProof::new(&witness, self, nonce)
+    }
+
+    /// The value `member` was hashed from if the store keeps them,
+    /// see `PreimageStore`
+    pub fn preimage(&self, member: &BigInteger) -> Result<Option<Vec<u8>>, AccumulatorError> {
+        self.members.preimage(member)
+    }
+
+    /// The member primes in ascending order
+    pub fn members(&self) -> MemberIter<'_> {
+        self.members.iter()
+    }
+
+    /// Up to `limit` member primes greater than `after` in ascending order.
+    /// Pass the last prime of a page as `after` to get the next one
+    pub fn members_page(&self, after: Option<&BigInteger>, limit: usize) -> Result<Vec<BigInteger>, AccumulatorError> {
+        let members = match after {
+            Some(after) => self.members.iter_after(after),
+            None => self.members.iter(),
+        };
+        members.take(limit).collect()
+    }
+
+    /// Convert accumulator to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut out = Vec::with_capacity(HEADER_SIZE + MIN_BYTES + MEMBER_SIZE * self.members.len());
+        out.append(&mut Header::new(Tag::Accumulator).to_bytes());
+
+        out.append(b2fa(&self.generator, FACTOR_SIZE * 2)?.as_mut());
+        out.append(b2fa(&self.value, FACTOR_SIZE * 2)?.as_mut());
+        out.append(b2fa(&self.modulus, FACTOR_SIZE * 2)?.as_mut());
+        out.extend_from_slice(&self.epoch.to_be_bytes());
+
+        let m_len = self.members.len() as u32;
+        out.extend_from_slice(m_len.to_be_bytes().as_ref());
+
+        let deltas = use_member_deltas(self.members.iter())?;
+        out.push(if deltas { MEMBERS_DELTA } else { MEMBERS_FIXED });
+        let mut prev = None;
+        for m in self.members.iter() {
+            let m = m?;
+            encode_member(prev.as_ref(), &m, &mut out)?;
+            prev = if deltas { Some(m) } else { None };
+        }
+
+        Ok(out)
+    }
+
+    /// Write the same bytes as `to_bytes` to `w` one member at a time
+    /// so large sets don't need one contiguous buffer
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, w: &mut W) -> Result<(), AccumulatorError> {
+        Header::new(Tag::Accumulator).write_to(w)?;
+        for v in &[&self.generator, &self.value, &self.modulus] {
+            write_element(w, v, FACTOR_SIZE * 2)?;
+        }
+        w.write_all(&self.epoch.to_be_bytes()).map_err(io_error)?;
+        w.write_all(&(self.members.len() as u32).to_be_bytes()).map_err(io_error)?;
+        let deltas = use_member_deltas(self.members.iter())?;
+        w.write_all(&[if deltas { MEMBERS_DELTA } else { MEMBERS_FIXED }]).map_err(io_error)?;
+        let mut prev = None;
+        let mut member = Vec::with_capacity(MEMBER_SIZE);
+        for m in self.members.iter() {
+            let m = m?;
+            member.clear();
+            encode_member(prev.as_ref(), &m, &mut member)?;
+            w.write_all(&member).map_err(io_error)?;
+            prev = if deltas { Some(m) } else { None };
+        }
+        Ok(())
+    }
+
+    /// Same as `write_to` taking the writer by value like the proofs
+    /// and witnesses
+    #[cfg(feature = "std")]
+    pub fn write_bytes<W: Write>(&self, mut w: W) -> Result<(), AccumulatorError> {
+        self.write_to(&mut w)
+    }
+
+    /// Write the member primes to `w` for jobs that process the set
+    /// without this crate, like computing witnesses in a batch pipeline.
+    /// Everything is big endian:
+    ///
+    /// ```text
+    /// header      11 bytes, tag 30 (MemberPrimes)
+    /// epoch       8 bytes
+    /// count       4 bytes
+    /// members     count * MEMBER_SIZE bytes, strictly ascending,
+    ///             each left padded with zeros
+    /// ```
+    ///
+    /// The header is the 4 bytes `RSAA`, the format version, the tag, the
+    /// modulus and member sizes in bits as 2 bytes each and the hash id.
+    /// `Accumulator::import_primes` reads it back
+    #[cfg(feature = "std")]
+    pub fn export_primes<W: Write>(&self, mut w: W) -> Result<(), AccumulatorError> {
+        Header::new(Tag::MemberPrimes).write_to(&mut w)?;
+        w.write_all(&self.epoch.to_be_bytes()).map_err(io_error)?;
+        w.write_all(&(self.members.len() as u32).to_be_bytes()).map_err(io_error)?;
+        for m in self.members.iter() {
+            write_element(&mut w, &m?, MEMBER_SIZE)?;
+        }
+        Ok(())
+    }
+
+    /// The generator, modulus, value and epoch without the members
+    pub fn public(&self) -> AccumulatorPublic {
+        AccumulatorPublic {
+            generator: self.generator.as_ref().clone(),
+            modulus: self.modulus.as_ref().clone(),
+            value: self.value.clone(),
+            epoch: self.epoch,
+        }
+    }
+
+    /// Convert the public part of the accumulator to bytes. The size does not
+    /// depend on the number of members and the members are not disclosed
+    pub fn to_public_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        self.public().to_bytes()
+    }
+
+    remove_type!(remove_u64, remove_u64_assign, u64);
+    remove_type!(remove_u32, remove_u32_assign, u32);
+    remove_type!(remove_u16, remove_u16_assign, u16);
+    remove_type!(remove_u8, remove_u8_assign, u8);
+    remove_type!(remove_i64, remove_i64_assign, i64);
+    remove_type!(remove_i32, remove_i32_assign, i32);
+    remove_type!(remove_i16, remove_i16_assign, i16);
+    remove_type!(remove_i8, remove_i8_assign, i8);
+}
+
+impl<S: Clone> Clone for Accumulator<S> {
+    fn clone(&self) -> Self {
+        Self {
+            generator: self.generator.clone(),
+            modulus: self.modulus.clone(),
+            members: self.members.clone(),
+            value: self.value.clone(),
+            epoch: self.epoch,
+            fixed_bases: self.fixed_bases.clone(),
+            product: self.product.clone(),
+            primality: self.primality,
+        }
+    }
+}
+
+/// Consumes the accumulator and yields its member primes
+impl<S: IntoIterator> IntoIterator for Accumulator<S> {
+    type Item = S::Item;
+    type IntoIter = S::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.members.into_iter()
+    }
+}
+
+impl PartialEq for Accumulator {
+    fn eq(&self, other: &Self) -> bool {
+        self.generator == other.generator
+            && self.members == other.members
+            && self.modulus == other.modulus
+            && self.value == other.value
+            && self.epoch == other.epoch
+    }
+}
+
+impl Eq for Accumulator {}
+
+impl TryFrom<Vec<u8>> for Accumulator {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+impl TryFrom<&[u8]> for Accumulator {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::Accumulator)?;
+        if data.len() < MIN_BYTES {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: MIN_BYTES,
+                actual: data.len(),
+            }
+            .into());
+        }
+
+        let mut offset = 0;
+        let mut end = FACTOR_SIZE * 2;
+
+        let generator = BigInteger::try_from(&data[offset..end])?;
+
+        offset = end;
+        end = offset + 2 * FACTOR_SIZE;
+
+        let value = BigInteger::try_from(&data[offset..end])?;
+
+        offset = end;
+        end = offset + 2 * FACTOR_SIZE;
+
+        let modulus = BigInteger::try_from(&data[offset..end])?;
+        check_parameters(&generator, &value, &modulus)?;
+
+        offset = end;
+        end = offset + 8;
+
+        let epoch = u64::from_be_bytes(*array_ref![data, offset, 8]);
+
+        offset = end;
+        end = offset + 5;
+
+        let member_count = u32::from_be_bytes(*array_ref![data, offset, 4]) as usize;
+        let deltas = member_deltas(data[offset + 4])?;
+        let mut members = BTreeSet::new();
+
+        let mut bytes = data[end..].iter();
+        let mut prev = None;
+        for _ in 0..member_count {
+            let m = decode_member(prev.as_ref(), || {
+                bytes.next().copied().ok_or_else(|| {
+                    AccumulatorError::from_msg(AccumulatorErrorKind::SerializationError, "member list is truncated")
+                })
+            })?;
+            if deltas {
+                prev = Some(m.clone());
+            }
+            push_member(&mut members, m)?;
+        }
+        if !bytes.as_slice().is_empty() {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::SerializationError,
+                "unexpected bytes after the member list",
+            ));
+        }
+        Ok(Self {
+            generator: Arc::new(generator),
+            members,
+            modulus: Arc::new(modulus),
+            value,
+            epoch,
+            fixed_bases: Vec::new(),
+            product: None,
+            primality: MEMBER_PRIMALITY,
+        })
+    }
+}
+
+serdes_impl!(Accumulator);
+hash_ord_impl!(Accumulator; generator, members, modulus, value, epoch);
+
+/// Add a decoded member to the end of `members`. Lists are written in
+/// ascending order so a member that isn't larger than the last is an error
+fn push_member(members: &mut BTreeSet<BigInteger>, member: BigInteger) -> Result<(), AccumulatorError> {
+    if members.last().is_some_and(|prev| prev >= &member) {
+        return Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::SerializationError,
+            "members are not in ascending order",
+        ));
+    }
+    members.insert(member);
+    Ok(())
+}
+
+fn member_deltas(encoding: u8) -> Result<bool, AccumulatorError> {
+    match encoding {
+        MEMBERS_FIXED => Ok(false),
+        MEMBERS_DELTA => Ok(true),
+        _ => Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::SerializationError,
+            "unknown member list encoding",
+        )),
+    }
+}
+
+/// Check parsed parameters could have come from `new`. The modulus must
+/// be odd and of the size this build uses, the generator a quadratic
+/// residue candidate and the value reduced
+pub(crate) fn check_parameters(
+    generator: &BigInteger,
+    value: &BigInteger,
+    modulus: &BigInteger,
+) -> Result<(), AccumulatorError> {
+    let err = |msg| Err(AccumulatorError::from_msg(AccumulatorErrorKind::SerializationError, msg));
+    let bits = FACTOR_SIZE * 16;
+    let modulus_bits = modulus.bits();
+    if modulus_bits > bits || modulus_bits < bits - 1 {
+        return err("modulus is not the size this build uses");
+    }
+    let one = BigInteger::from(1u32);
+    if modulus % &BigInteger::from(2u32) != one {
+        return err("modulus is even");
+    }
+    if generator <= &one || generator >= modulus {
+        return err("generator is not in (1, n)");
+    }
+    if Field::new(modulus).jacobi(generator) != 1 {
+        return err("generator is not a quadratic residue");
+    }
+    if value == &BigInteger::default() || value >= modulus {
+        return err("value is not reduced mod n");
+    }
+    Ok(())
+}
+
+#[cfg(feature = "std")]
+fn io_error(e: std::io::Error) -> AccumulatorError {
+    AccumulatorError::from_source(AccumulatorErrorKind::SerializationError, e)
+}
+
+/// The part of an accumulator verifiers need: the generator,
+/// modulus, current value and epoch but not the members
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct AccumulatorPublic {
+    /// The initial value of the accumulator
+    pub generator: BigInteger,
+    /// The RSA modulus
+    pub modulus: BigInteger,
+    /// The current accumulator value
+    pub value: BigInteger,
+    /// The number of updates applied to `value`
+    pub epoch: u64,
+}
+
+impl AccumulatorPublic {
+    /// The number of bytes this serializes to
+    pub const SIZE_BYTES: usize = HEADER_SIZE + 6 * FACTOR_SIZE + 8;
+
+    /// Convert to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut out = Vec::with_capacity(Self::SIZE_BYTES);
+        out.append(&mut Header::new(Tag::AccumulatorPublic).to_bytes());
+        out.append(b2fa(&self.generator, FACTOR_SIZE * 2)?.as_mut());
+        out.append(b2fa(&self.value, FACTOR_SIZE * 2)?.as_mut());
+        out.append(b2fa(&self.modulus, FACTOR_SIZE * 2)?.as_mut());
+        out.extend_from_slice(&self.epoch.to_be_bytes());
+        Ok(out)
+    }
+
+    /// Write the same bytes as `to_bytes` to `w`
+    #[cfg(feature = "std")]
+    pub fn write_bytes<W: Write>(&self, mut w: W) -> Result<(), AccumulatorError> {
+        Header::new(Tag::AccumulatorPublic).write_to(&mut w)?;
+        for v in &[&self.generator, &self.value, &self.modulus] {
+            write_element(&mut w, v, FACTOR_SIZE * 2)?;
+        }
+        w.write_all(&self.epoch.to_be_bytes()).map_err(io_error)
+    }
+}
+
+impl TryFrom<&[u8]> for AccumulatorPublic {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::AccumulatorPublic)?;
+        if data.len() != Self::SIZE_BYTES - HEADER_SIZE {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: Self::SIZE_BYTES - HEADER_SIZE,
+                actual: data.len(),
+            }
+            .into());
+        }
+        let generator = BigInteger::try_from(&data[..(2 * FACTOR_SIZE)])?;
+        let value = BigInteger::try_from(&data[(2 * FACTOR_SIZE)..(4 * FACTOR_SIZE)])?;
+        let modulus = BigInteger::try_from(&data[(4 * FACTOR_SIZE)..(6 * FACTOR_SIZE)])?;
+        check_parameters(&generator, &value, &modulus)?;
+        let epoch = u64::from_be_bytes(*array_ref![data, 6 * FACTOR_SIZE, 8]);
+        Ok(Self {
+            generator,
+            modulus,
+            value,
+            epoch,
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for AccumulatorPublic {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+serdes_impl!(AccumulatorPublic);
+hash_ord_impl!(AccumulatorPublic; generator, modulus, value, epoch);
+hex_impl!(AccumulatorPublic);
+
+/// The state proofs and witness updates are checked against. Verifiers
+/// that only have the published `AccumulatorPublic` use it in place of
+/// the full accumulator, neither needs the members
+pub trait PublicState {
+    /// The initial value of the accumulator
+    fn generator(&self) -> &BigInteger;
+
+    /// The RSA modulus
+    fn modulus(&self) -> &BigInteger;
+
+    /// The current accumulator value
+    fn value(&self) -> &BigInteger;
+
+    /// The number of updates applied to `value`
+    fn epoch(&self) -> u64;
+
+    /// base^e mod N, with a precomputed table for `base` if there is one
+    fn fixed_exp(&self, base: &BigInteger, e: &BigInteger) -> BigInteger {
+        base.mod_exp(e, self.modulus())
+    }
+}
+
+impl PublicState for AccumulatorPublic {
+    fn generator(&self) -> &BigInteger {
+        &self.generator
+    }
+
+    fn modulus(&self) -> &BigInteger {
+        &self.modulus
+    }
+
+    fn value(&self) -> &BigInteger {
+        &self.value
+    }
+
+    fn epoch(&self) -> u64 {
+        self.epoch
+    }
+}
+
+impl<S: MemberStore> PublicState for Accumulator<S> {
+    fn generator(&self) -> &BigInteger {
+        &self.generator
+    }
+
+    fn modulus(&self) -> &BigInteger {
+        &self.modulus
+    }
+
+    fn value(&self) -> &BigInteger {
+        &self.value
+    }
+
+    fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    fn fixed_exp(&self, base: &BigInteger, e: &BigInteger) -> BigInteger {
+        Accumulator::fixed_exp(self, base, e)
+    }
+}
+
+/// The precomputed tables and cached member product of an accumulator
+/// at one epoch. Building them can take minutes for large sets
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Precomputed {
+    value: BigInteger,
+    epoch: u64,
+    fixed_bases: Vec<FixedBase>,
+    product: Option<BigInteger>,
+}
+
+impl Precomputed {
+    /// Serialize to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut out = Header::new(Tag::Precomputed).to_bytes();
+        out.append(&mut b2fa(&self.value, FACTOR_SIZE * 2)?);
+        out.extend_from_slice(&self.epoch.to_be_bytes());
+        out.extend_from_slice(&(self.fixed_bases.len() as u32).to_be_bytes());
+        let tables = self.fixed_bases.iter().map(FixedBase::to_bytes);
+        let product = self.product.iter().map(BigInteger::to_bytes);
+        for mut b in tables.chain(product) {
+            out.extend_from_slice(&(b.len() as u32).to_be_bytes());
+            out.append(&mut b);
+        }
+        Ok(out)
+    }
+}
+
+impl TryFrom<&[u8]> for Precomputed {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::Precomputed)?;
+        let fixed = FACTOR_SIZE * 2 + 12;
+        if data.len() < fixed {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: fixed,
+                actual: data.len(),
+            }
+            .into());
+        }
+        let value = BigInteger::try_from(&data[..(FACTOR_SIZE * 2)])?;
+        let epoch = u64::from_be_bytes(*array_ref![data, FACTOR_SIZE * 2, 8]);
+        let count = u32::from_be_bytes(*array_ref![data, FACTOR_SIZE * 2 + 8, 4]) as usize;
+
+        // Each table and then the product are prefixed by their length
+        let mut rest = &data[fixed..];
+        let mut fixed_bases = Vec::with_capacity(count.min(2));
+        for _ in 0..count {
+            fixed_bases.push(FixedBase::try_from(length_prefixed(&mut rest)?)?);
+        }
+        let product = if rest.is_empty() {
+            None
+        } else {
+            Some(BigInteger::try_from(length_prefixed(&mut rest)?)?)
+        };
+        if !rest.is_empty() {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::SerializationError,
+                "unexpected bytes after the precomputed values",
+            ));
+        }
+        Ok(Self {
+            value,
+            epoch,
+            fixed_bases,
+            product,
+        })
+    }
+}
+
+/// Split off the bytes after a u32 length from the front of `data`
+fn length_prefixed<'a>(data: &mut &'a [u8]) -> Result<&'a [u8], AccumulatorError> {
+    if data.len() < 4 || data.len() - 4 < u32::from_be_bytes(*array_ref![data, 0, 4]) as usize {
+        return Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::SerializationError,
+            "precomputed values are truncated",
+        ));
     }
+    let (item, rest) = data[4..].split_at(u32::from_be_bytes(*array_ref![data, 0, 4]) as usize);
+    *data = rest;
+    Ok(item)
+}
 
-    fn _remove(
-        &mut self,
-        key: &AccumulatorSecretKey,
-        value: &BigInteger,
-    ) -> Result<(), AccumulatorError> {
-        if !self.members.contains(&value) {
-            return Err(AccumulatorErrorKind::InvalidMemberSupplied.into());
-        }
-        let t = key.totient();
-        self.members.remove(&value);
-        let v_inv = value.mod_inverse(&t);
-        self.value.mod_exp_assign(&v_inv, &self.modulus);
-        Ok(())
+impl TryFrom<Vec<u8>> for Precomputed {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
     }
+}
 
-    /// Convert accumulator to bytes
-    pub fn to_bytes(&self) -> Vec<u8> {
-        let mut out = Vec::with_capacity(MIN_BYTES + MEMBER_SIZE * self.members.len());
+serdes_impl!(Precomputed);
 
-        out.append(b2fa(&self.generator, FACTOR_SIZE * 2).as_mut());
-        out.append(b2fa(&self.value, FACTOR_SIZE * 2).as_mut());
-        out.append(b2fa(&self.modulus, FACTOR_SIZE * 2).as_mut());
+/// The public state and members of an accumulator at one epoch.
+/// New replicas and auditors start from one and `Accumulator::replay`
+/// the updates published after it
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Checkpoint(Accumulator);
 
-        let m_len = self.members.len() as u32;
-        out.extend_from_slice(m_len.to_be_bytes().as_ref());
+impl Checkpoint {
+    /// The generator, modulus, value and epoch
+    pub fn public(&self) -> AccumulatorPublic {
+        self.0.public()
+    }
 
-        for b in &self.members {
-            out.append(b2fa(b, MEMBER_SIZE).as_mut());
-        }
+    /// The member primes in ascending order
+    pub fn members(&self) -> impl Iterator<Item = &BigInteger> {
+        self.0.members.iter()
+    }
 
-        out
+    /// Check the value is the generator raised to the product of the
+    /// members, so the member list can be trusted without the secret key
+    pub fn verify(&self) -> bool {
+        let x: BigInteger = self.0.members.par_iter().product();
+        self.0.generator.mod_exp(&x, &self.0.modulus) == self.0.value
     }
 
-    remove_type!(remove_u64, remove_u64_assign, u64);
-    remove_type!(remove_u32, remove_u32_assign, u32);
-    remove_type!(remove_u16, remove_u16_assign, u16);
-    remove_type!(remove_u8, remove_u8_assign, u8);
-    remove_type!(remove_i64, remove_i64_assign, i64);
-    remove_type!(remove_i32, remove_i32_assign, i32);
-    remove_type!(remove_i16, remove_i16_assign, i16);
-    remove_type!(remove_i8, remove_i8_assign, i8);
+    /// Serialize to bytes. The layout is the same as an accumulator
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut out = Header::new(Tag::Checkpoint).to_bytes();
+        out.extend_from_slice(&self.0.to_bytes()?[HEADER_SIZE..]);
+        Ok(out)
+    }
 }
 
-impl Clone for Accumulator {
-    fn clone(&self) -> Self {
-        Self {
-            generator: self.generator.clone(),
-            modulus: self.modulus.clone(),
-            members: self.members.iter().cloned().collect(),
-            value: self.value.clone(),
-        }
+/// The result of `Accumulator::verify_consistency`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ConsistencyReport {
+    /// The number of stored members
+    pub members: usize,
+    /// The value recomputed from the generator and the members
+    pub expected: BigInteger,
+    /// True if the current value is `expected`
+    pub value_matches: bool,
+    /// True if the cached member product is the product of the members,
+    /// `None` if there is no cached product
+    pub product_matches: Option<bool>,
+    /// Stored members that aren't prime
+    pub not_prime: Vec<BigInteger>,
+}
+
+impl ConsistencyReport {
+    /// True if nothing disagrees
+    pub fn is_consistent(&self) -> bool {
+        self.value_matches && self.product_matches != Some(false) && self.not_prime.is_empty()
     }
 }
 
-impl TryFrom<Vec<u8>> for Accumulator {
+impl TryFrom<&[u8]> for Checkpoint {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::Checkpoint)?;
+        let mut accumulator = Header::new(Tag::Accumulator).to_bytes();
+        accumulator.extend_from_slice(data);
+        Accumulator::try_from(accumulator).map(Self)
+    }
+}
+
+impl TryFrom<Vec<u8>> for Checkpoint {
     type Error = AccumulatorError;
 
     fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
@@ -282,57 +1529,67 @@ impl TryFrom<Vec<u8>> for Accumulator {
     }
 }
 
-impl TryFrom<&[u8]> for Accumulator {
-    type Error = AccumulatorError;
+serdes_impl!(Checkpoint);
+hash_ord_impl!(Checkpoint; 0);
 
-    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
-        if data.len() < MIN_BYTES {
-            return Err(AccumulatorError::from_msg(
-                AccumulatorErrorKind::InvalidType,
-                format!("Expected size {}, found {}", MIN_BYTES, data.len()),
-            ));
-        }
+/// Members are byte strings hashed to primes
+impl<S: MemberStore> Accumulate for Accumulator<S> {
+    type Member = [u8];
+    type Witness = MembershipWitness;
 
-        let mut offset = 0;
-        let mut end = FACTOR_SIZE * 2;
+    fn accumulate(&mut self, member: &[u8]) -> Result<(), AccumulatorError> {
+        self.insert_assign(member)
+    }
 
-        let generator = BigInteger::try_from(&data[offset..end])?;
+    fn is_member(&self, member: &[u8]) -> Result<bool, AccumulatorError> {
+        self.contains(member)
+    }
 
-        offset = end;
-        end = offset + 2 * FACTOR_SIZE;
+    fn membership_witness(&self, member: &[u8]) -> Result<MembershipWitness, AccumulatorError> {
+        MembershipWitness::new(self, member)
+    }
+}
 
-        let value = BigInteger::try_from(&data[offset..end])?;
+impl<S: MemberStore> Dynamic for Accumulator<S> {
+    fn delete(&mut self, witness: &MembershipWitness) -> Result<(), AccumulatorError> {
+        self.remove_with_witness_assign(witness)
+    }
+}
 
-        offset = end;
-        end = offset + 2 * FACTOR_SIZE;
+impl<S: MemberStore> Universal for Accumulator<S> {
+    type NonMembershipWitness = NonMembershipWitness;
 
-        let modulus = BigInteger::try_from(&data[offset..end])?;
+    fn non_membership_witness(&self, value: &[u8]) -> Result<NonMembershipWitness, AccumulatorError> {
+        NonMembershipWitness::new(self, value)
+    }
+}
 
-        offset = end;
-        end = offset + 4;
+impl<S: MemberStore> RequiresTrapdoor for Accumulator<S> {
+    type Trapdoor = AccumulatorSecretKey;
 
-        let member_count = u32::from_be_bytes(*array_ref![data, offset, 4]) as usize;
-        let mut members = BTreeSet::new();
+    fn delete_with_trapdoor(
+        &mut self,
+        trapdoor: &AccumulatorSecretKey,
+        member: &[u8],
+    ) -> Result<(), AccumulatorError> {
+        self.remove_assign(trapdoor, member)
+    }
 
-        offset = end;
-        end = offset + MEMBER_SIZE;
-        for _ in 0..member_count {
-            let m = BigInteger::try_from(&data[offset..end])?;
-            members.insert(m);
-            offset = end;
-            end = offset + MEMBER_SIZE;
+    fn membership_witness_with_trapdoor(
+        &self,
+        trapdoor: &AccumulatorSecretKey,
+        member: &[u8],
+    ) -> Result<MembershipWitness, AccumulatorError> {
+        if !self.contains(member)? {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "value is not in the accumulator",
+            ));
         }
-        Ok(Self {
-            generator,
-            members,
-            modulus,
-            value,
-        })
+        MembershipWitness::with_secret_key(self, trapdoor, member)
     }
 }
 
-serdes_impl!(Accumulator);
-
 macro_rules! add_impl {
     ($ty:ty,$c:expr) => {
         impl Add<$ty> for Accumulator {
@@ -425,20 +1682,83 @@ impl AddAssign<&str> for Accumulator {
     }
 }
 
-#[cfg(not(test))]
-fn random_qr(modulus: &BigInteger) -> BigInteger {
-    modulus.rand_range().mod_sqr(modulus)
+macro_rules! sub_impl {
+    ($ty:ty, $c:expr) => {
+        impl<K: SecretKeyOps> Sub<($ty, &K)> for Accumulator {
+            type Output = Self;
+
+            fn sub(self, (rhs, key): ($ty, &K)) -> Self::Output {
+                self.remove(key, $c(rhs)).unwrap()
+            }
+        }
+
+        impl<K: SecretKeyOps> Sub<($ty, &K)> for &Accumulator {
+            type Output = Accumulator;
+
+            fn sub(self, (rhs, key): ($ty, &K)) -> Self::Output {
+                self.remove(key, $c(rhs)).unwrap()
+            }
+        }
+
+        impl<K: SecretKeyOps> SubAssign<($ty, &K)> for Accumulator {
+            fn sub_assign(&mut self, (rhs, key): ($ty, &K)) {
+                self.remove_assign(key, $c(rhs)).unwrap()
+            }
+        }
+    };
+}
+
+sub_impl!(&[u8], core::convert::identity);
+sub_impl!(&str, str::as_bytes);
+sub_impl!(&BigInteger, |rhs: &BigInteger| rhs.to_bytes());
+sub_impl!(BigInteger, |rhs: BigInteger| rhs.to_bytes());
+sub_impl!(u64, |rhs: u64| rhs.to_be_bytes());
+sub_impl!(u32, |rhs: u32| rhs.to_be_bytes());
+sub_impl!(u16, |rhs: u16| rhs.to_be_bytes());
+sub_impl!(u8, |rhs: u8| rhs.to_be_bytes());
+sub_impl!(i64, |rhs: i64| rhs.to_be_bytes());
+sub_impl!(i32, |rhs: i32| rhs.to_be_bytes());
+sub_impl!(i16, |rhs: i16| rhs.to_be_bytes());
+sub_impl!(i8, |rhs: i8| rhs.to_be_bytes());
+
+impl Sub<&MembershipWitness> for Accumulator {
+    type Output = Self;
+
+    fn sub(self, rhs: &MembershipWitness) -> Self::Output {
+        self.remove_with_witness(rhs).unwrap()
+    }
+}
+
+impl Sub<&MembershipWitness> for &Accumulator {
+    type Output = Accumulator;
+
+    fn sub(self, rhs: &MembershipWitness) -> Self::Output {
+        self.remove_with_witness(rhs).unwrap()
+    }
+}
+
+impl SubAssign<&MembershipWitness> for Accumulator {
+    fn sub_assign(&mut self, rhs: &MembershipWitness) {
+        self.remove_with_witness_assign(rhs).unwrap()
+    }
+}
+
+#[cfg(all(feature = "std", not(test)))]
+fn random_qr<R: RngCore + CryptoRng>(modulus: &BigInteger, rng: &mut R) -> Result<BigInteger, AccumulatorError> {
+    Ok(random_below(modulus, rng)?.mod_sqr(modulus))
 }
 
 #[cfg(test)]
-fn random_qr(_: &BigInteger) -> BigInteger {
-    BigInteger::from("14704636934945556701768512807551423295322582742569915447814799887124081071907200872335609067929374365739431897167843056332163350393002363708099338086779834093915457533918974672301807866170744567165914488714662630606969571079578621114846946738744248553592341323021622081633613877276260780228049365644707168869864141334294382732324161900480737159963215787522448535242091782123958096148785498247442279692457260152226937644334131336333591710801691378317437656720683740403317736682333718644263548862631065373402082561872142789630529965385994523257973199774135347149127815031308836928060871953543688304190675878204079994222")
+fn random_qr<R: RngCore + CryptoRng>(_: &BigInteger, _: &mut R) -> Result<BigInteger, AccumulatorError> {
+    Ok(BigInteger::from("14704636934945556701768512807551423295322582742569915447814799887124081071907200872335609067929374365739431897167843056332163350393002363708099338086779834093915457533918974672301807866170744567165914488714662630606969571079578621114846946738744248553592341323021622081633613877276260780228049365644707168869864141334294382732324161900480737159963215787522448535242091782123958096148785498247442279692457260152226937644334131336333591710801691378317437656720683740403317736682333718644263548862631065373402082561872142789630529965385994523257973199774135347149127815031308836928060871953543688304190675878204079994222"))
     // BigInteger::from("9")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{hash::hash_to_prime, key::AccumulatorSecretKey};
+    use blake2::{digest::Digest, Blake2b};
 
     macro_rules! add_type_test {
         ($name:ident, $c:expr) => {
@@ -456,19 +1776,349 @@ mod tests {
     fn bytes_test() {
         let key = AccumulatorSecretKey::default();
         let acc = Accumulator::new(&key);
-        let bytes = acc.to_bytes();
-        assert_eq!(bytes.len(), MIN_BYTES);
+        let bytes = acc.to_bytes().unwrap();
+        assert_eq!(bytes.len(), HEADER_SIZE + MIN_BYTES);
         let res = Accumulator::try_from(bytes);
         assert!(res.is_ok());
         let acc2 = res.unwrap();
         assert_eq!(acc, acc2);
     }
 
+    #[test]
+    fn public_bytes() {
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = vec![3u64.to_be_bytes(), 7u64.to_be_bytes()];
+        let mut acc = Accumulator::with_members(&key, &members).unwrap();
+        assert_eq!(acc.epoch, 0);
+        acc.insert_assign(11u64.to_be_bytes()).unwrap();
+        acc.remove_assign(&key, members[0]).unwrap();
+        assert_eq!(acc.epoch, 2);
+
+        let bytes = acc.to_public_bytes().unwrap();
+        assert_eq!(bytes.len(), AccumulatorPublic::SIZE_BYTES);
+        let public = AccumulatorPublic::try_from(bytes).unwrap();
+        assert_eq!(public, acc.public());
+        assert!(Accumulator::try_from(acc.to_public_bytes().unwrap()).is_err());
+
+        // Verifiers only need the public state
+        let witness = MembershipWitness::new(&acc, 11u64.to_be_bytes()).unwrap();
+        let proof = MembershipProof::new(&witness, &acc, b"public_bytes").unwrap();
+        assert!(proof.verify(&public, b"public_bytes"));
+    }
+
+    #[test]
+    fn streaming() {
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = vec![3u64.to_be_bytes(), 7u64.to_be_bytes(), 11u64.to_be_bytes()];
+        let acc = Accumulator::with_members(&key, &members).unwrap();
+        let mut out = Vec::new();
+        acc.write_to(&mut out).unwrap();
+        assert_eq!(out, acc.to_bytes().unwrap());
+        assert_eq!(Accumulator::read_from(&mut out.as_slice()).unwrap(), acc);
+        let err = Accumulator::read_from(&mut &out[..out.len() - 1]).unwrap_err();
+        assert_eq!(err.kind(), AccumulatorErrorKind::SerializationError);
+    }
+
+    #[test]
+    fn member_list_bytes() {
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<BigInteger> = (0..20).map(|i| hash_to_prime([i as u8])).collect();
+        let acc = Accumulator::with_prime_members(&key, &members).unwrap();
+        let bytes = acc.to_bytes().unwrap();
+        assert_eq!(bytes.len(), HEADER_SIZE + MIN_BYTES + MEMBER_SIZE * members.len());
+        assert_eq!(Accumulator::try_from(bytes.as_slice()).unwrap(), acc);
+
+        let primes: Vec<BigInteger> = [3u32, 5, 7, 11, 13, 17, 19, 23].iter().map(|p| BigInteger::from(*p)).collect();
+        let dense = Accumulator::with_prime_members(&key, &primes).unwrap();
+        let mut out = Vec::new();
+        dense.write_to(&mut out).unwrap();
+        assert_eq!(out, dense.to_bytes().unwrap());
+        assert_eq!(out.len(), HEADER_SIZE + MIN_BYTES + MEMBER_SIZE + primes.len() - 1);
+        assert_eq!(Accumulator::try_from(out.as_slice()).unwrap(), dense);
+        assert_eq!(Accumulator::read_from(&mut out.as_slice()).unwrap(), dense);
+
+        assert!(Accumulator::try_from(&bytes[..bytes.len() - 1]).is_err());
+        let mut extra = bytes.clone();
+        extra.push(1);
+        assert!(Accumulator::try_from(extra).is_err());
+
+        // Fixed size members must be strictly ascending too
+        let start = HEADER_SIZE + MIN_BYTES;
+        let mut swapped = bytes.clone();
+        swapped[start..start + 2 * MEMBER_SIZE].rotate_left(MEMBER_SIZE);
+        let mut repeated = bytes.clone();
+        repeated.copy_within(start..start + MEMBER_SIZE, start + MEMBER_SIZE);
+        for bad in [swapped, repeated] {
+            assert!(Accumulator::try_from(bad.as_slice()).is_err());
+            assert!(Accumulator::read_from(&mut bad.as_slice()).is_err());
+        }
+    }
+
+    #[test]
+    fn bytes_oversized_value() {
+        let key = AccumulatorSecretKey::default();
+        let mut acc = Accumulator::new(&key);
+        acc.value = acc.modulus.as_ref() * acc.modulus.as_ref();
+        match acc.to_bytes().unwrap_err().kind() {
+            AccumulatorErrorKind::InvalidLength { expected, actual } => {
+                assert_eq!(expected, FACTOR_SIZE * 2);
+                assert!(actual > expected);
+            }
+            k => panic!("unexpected kind {:?}", k),
+        }
+    }
+
+    #[test]
+    fn bytes_bad_parameters() {
+        let key = AccumulatorSecretKey::default();
+        let acc = Accumulator::with_members(&key, ["a"]).unwrap();
+        let bytes = acc.to_bytes().unwrap();
+        let field = |i: usize| (HEADER_SIZE + 2 * FACTOR_SIZE * i)..(HEADER_SIZE + 2 * FACTOR_SIZE * (i + 1));
+        let with = |i: usize, v: &BigInteger| {
+            let mut b = bytes.clone();
+            b[field(i)].copy_from_slice(&b2fa(v, 2 * FACTOR_SIZE).unwrap());
+            b
+        };
+        let n = acc.modulus.as_ref();
+        let one = BigInteger::from(1u32);
+        let bad = [
+            with(2, &(n - &one)),
+            with(2, &BigInteger::from(3u32)),
+            with(0, &one),
+            with(0, n),
+            with(0, &BigInteger::from(4u32).mod_mul(&key.p, n)),
+            with(1, n),
+            with(1, &BigInteger::default()),
+        ];
+        for b in bad.iter() {
+            let err = Accumulator::try_from(b.as_slice()).unwrap_err();
+            assert_eq!(err.kind(), AccumulatorErrorKind::SerializationError);
+            assert!(Accumulator::read_from(&mut b.as_slice()).is_err());
+        }
+        let mut public = acc.to_public_bytes().unwrap();
+        public[field(0)].copy_from_slice(&b2fa(&one, 2 * FACTOR_SIZE).unwrap());
+        assert!(AccumulatorPublic::try_from(public).is_err());
+    }
+
+    #[test]
+    fn checkpoint_replay_test() {
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = (0..6u64).map(|m| m.to_be_bytes()).collect();
+        let mut acc = Accumulator::with_members(&key, &members).unwrap();
+        let checkpoint = acc.checkpoint().unwrap();
+        assert!(checkpoint.verify());
+        assert_eq!(Checkpoint::try_from(checkpoint.to_bytes().unwrap()).unwrap(), checkpoint);
+        assert!(Accumulator::try_from(checkpoint.to_bytes().unwrap()).is_err());
+
+        let mut updates = Vec::new();
+        for i in 0..3u64 {
+            let deletion = MembershipWitness::new(&acc, members[i as usize]).unwrap();
+            let addition = hash_to_prime((10 + i).to_be_bytes());
+            let (additions, deletions) = (core::slice::from_ref(&addition), core::slice::from_ref(&deletion));
+            updates.push(BatchUpdate::new(&acc.public(), additions, deletions).unwrap());
+            acc.update_batch_assign(&key, &[addition], &[deletion.x]).unwrap();
+        }
+        assert_eq!(Accumulator::replay(&checkpoint, &updates).unwrap(), acc);
+        assert_eq!(Accumulator::replay(&checkpoint, &updates[..0]).unwrap().epoch, 0);
+        assert!(acc.checkpoint().unwrap().verify());
+
+        // Out of order, or deleting a value that isn't a member
+        assert!(Accumulator::replay(&checkpoint, updates.iter().rev()).is_err());
+        let mut empty = Accumulator::from_parts(checkpoint.public(), BTreeSet::new()).checkpoint().unwrap();
+        assert!(!empty.verify());
+        assert!(Accumulator::replay(&empty, &updates).is_err());
+        empty = acc.checkpoint().unwrap();
+        assert!(Accumulator::replay(&empty, &updates).is_err());
+
+        // A member list that doesn't match the value is rejected up front
+        let mut tampered: BTreeSet<_> = checkpoint.members().cloned().collect();
+        let first = tampered.iter().next().cloned().unwrap();
+        tampered.remove(&first);
+        tampered.insert(hash_to_prime(99u64.to_be_bytes()));
+        let tampered = Accumulator::from_parts(checkpoint.public(), tampered).checkpoint().unwrap();
+        assert!(!tampered.verify());
+        assert!(Accumulator::replay(&tampered, &updates[..0]).is_err());
+    }
+
+    #[test]
+    fn capability_test() {
+        fn churn<A: Dynamic<Member = [u8]>>(acc: &mut A, member: &[u8]) -> Result<(), AccumulatorError> {
+            acc.accumulate(member)?;
+            let witness = acc.membership_witness(member)?;
+            acc.delete(&witness)
+        }
+
+        let key = AccumulatorSecretKey::default();
+        let mut acc = Accumulator::with_members(&key, ["a", "b"]).unwrap();
+        let before = acc.value.clone();
+        churn(&mut acc, b"c").unwrap();
+        assert_eq!(acc.value, before);
+        assert!(!acc.is_member(b"c").unwrap());
+        assert!(churn(&mut acc, b"a").is_err());
+
+        let witness = acc.membership_witness_with_trapdoor(&key, b"a").unwrap();
+        assert_eq!(witness, MembershipWitness::new(&acc, "a").unwrap());
+        assert!(acc.membership_witness_with_trapdoor(&key, b"c").is_err());
+        assert!(acc.non_membership_witness(b"c").is_ok());
+        acc.delete_with_trapdoor(&key, b"a").unwrap();
+        assert!(!acc.is_member(b"a").unwrap());
+    }
+
+    #[test]
+    fn prove_test() {
+        let key = AccumulatorSecretKey::default();
+        let acc = Accumulator::with_members(&key, ["a", "b", "c"]).unwrap();
+        let proof = acc.prove_membership("a", b"nonce").unwrap();
+        assert!(proof.verify(&acc, b"nonce"));
+        let proof = acc.prove_membership_with_secret_key(&key, "b", b"nonce").unwrap();
+        assert!(proof.verify(&acc, b"nonce"));
+        assert!(!proof.verify(&acc, b"other"));
+        let proof = acc.prove_non_membership("d", b"nonce").unwrap();
+        assert!(proof.verify(&acc, b"nonce"));
+
+        assert!(acc.prove_membership("d", b"nonce").is_err());
+        assert!(acc.prove_membership_with_secret_key(&key, "d", b"nonce").is_err());
+        assert!(acc.prove_non_membership("a", b"nonce").is_err());
+    }
+
+    /// Every BigInteger backend must serialize the same accumulator and proofs
+    #[test]
+    fn conformance_test() {
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = vec![
+            3u64.to_be_bytes(),
+            7u64.to_be_bytes(),
+            11u64.to_be_bytes(),
+            13u64.to_be_bytes(),
+        ];
+        let acc = Accumulator::with_members(&key, &members).unwrap();
+        let witness = MembershipWitness::new(&acc, members[0]).unwrap();
+        let non_witness = NonMembershipWitness::new(&acc, 17u64.to_be_bytes()).unwrap();
+        let cases = [
+            (
+                "accumulator",
+                acc.to_bytes().unwrap(),
+                "98144601f5ea27dca23ff9b9cb9f3e12371304de1ce852366de5987e1cfdb42dec620e027ba15f70350954faa54d6e30771f7621a47da720d5616de893b5ec25",
+            ),
+            (
+                "membership proof",
+                MembershipProof::new(&witness, &acc, b"conformance_test").unwrap().to_bytes().unwrap(),
+                "5aaa7301d40285ea9adc1cd29852acfa10630a3bd4f2431ecb9022d66f68722c8890f2c916ebb5f2ed4787a3a6456787708c69fd11a9fdd691da923a1a33273f",
+            ),
+            (
+                "non-membership proof",
+                NonMembershipProof::new(&non_witness, &acc, b"conformance_test").unwrap().to_bytes().unwrap(),
+                "d2266d7a8ea68d20f83957297610df0997da837311599bf455dc4641f220858f29806889c1a69108069522d8bcf5ff25990dade7fb10271d0b138dfb90236400",
+            ),
+        ];
+        for (name, bytes, expected) in cases.iter() {
+            assert_eq!(hex::encode(Blake2b::digest(bytes).as_slice()), *expected, "{}", name);
+        }
+    }
+
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    #[test]
+    fn custom_pool_test() {
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = (0..64u64).map(|m| m.to_be_bytes()).collect();
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+        let acc = crate::in_pool(&pool, || Accumulator::with_members(&key, &members)).unwrap();
+        assert_eq!(acc, Accumulator::with_members(&key, &members).unwrap());
+    }
+
+    #[test]
+    fn precomputed_test() {
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = vec![3u64.to_be_bytes(), 7u64.to_be_bytes(), 11u64.to_be_bytes()];
+        let mut acc = Accumulator::with_members(&key, &members).unwrap();
+        acc.precompute(512);
+        acc.cache_product().unwrap();
+        let saved = acc.precomputed().to_bytes().unwrap();
+
+        let mut restarted = Accumulator::try_from(acc.to_bytes().unwrap()).unwrap();
+        let precomputed = Precomputed::try_from(saved.as_slice()).unwrap();
+        assert_eq!(precomputed, acc.precomputed());
+        restarted.restore(precomputed.clone()).unwrap();
+        assert_eq!(restarted.fixed_bases(), acc.fixed_bases());
+        assert_eq!(restarted.product, acc.product);
+
+        assert!(Precomputed::try_from(&saved[..saved.len() - 1]).is_err());
+        restarted.insert_assign(13u64.to_be_bytes()).unwrap();
+        assert!(restarted.restore(precomputed).is_err());
+
+        // Without tables or a product
+        let empty = Accumulator::try_from(acc.to_bytes().unwrap()).unwrap().precomputed();
+        assert_eq!(Precomputed::try_from(empty.to_bytes().unwrap()).unwrap(), empty);
+    }
+
+    #[test]
+    fn product_cache_test() {
+        use crate::{memwitness::MembershipWitness, nonwitness::NonMembershipWitness};
+
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = vec![3u64.to_be_bytes(), 7u64.to_be_bytes(), 11u64.to_be_bytes()];
+        let acc = Accumulator::with_members(&key, &members).unwrap();
+        let mut cached = acc.clone();
+        cached.cache_product().unwrap();
+        let mut expected = acc;
+
+        let additions = vec![hash_to_prime(17u64.to_be_bytes()), hash_to_prime(19u64.to_be_bytes())];
+        let deletions = vec![hash_to_prime(members[1])];
+        for acc in [&mut cached, &mut expected] {
+            acc.insert_assign(13u64.to_be_bytes()).unwrap();
+            acc.remove_assign(&key, members[0]).unwrap();
+            acc.update_batch_assign(&key, &additions, &deletions).unwrap();
+        }
+        assert_eq!(cached.product, Some(product(&cached.members, None).unwrap()));
+        assert_eq!(
+            MembershipWitness::new(&cached, members[2]).unwrap(),
+            MembershipWitness::new(&expected, members[2]).unwrap()
+        );
+        assert_eq!(
+            NonMembershipWitness::new(&cached, members[0]).unwrap(),
+            NonMembershipWitness::new(&expected, members[0]).unwrap()
+        );
+    }
+
+    #[test]
+    fn fixed_base_test() {
+        use crate::{memwitness::MembershipWitness, nonwitness::NonMembershipWitness};
+
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = vec![3u64.to_be_bytes(), 7u64.to_be_bytes(), 11u64.to_be_bytes()];
+        let acc = Accumulator::with_members(&key, &members).unwrap();
+        let mut fast = acc.clone();
+        fast.precompute(1024);
+        assert_eq!(fast.fixed_bases().len(), 2);
+        assert_eq!(
+            MembershipWitness::new(&fast, members[0]).unwrap(),
+            MembershipWitness::new(&acc, members[0]).unwrap()
+        );
+        assert_eq!(
+            NonMembershipWitness::new(&fast, 13u64.to_be_bytes()).unwrap(),
+            NonMembershipWitness::new(&acc, 13u64.to_be_bytes()).unwrap()
+        );
+
+        let mut restored = acc.clone();
+        for t in fast.fixed_bases() {
+            let t = FixedBase::try_from(t.to_bytes().as_slice()).unwrap();
+            restored.add_fixed_base(t).unwrap();
+        }
+        assert_eq!(restored.fixed_bases(), fast.fixed_bases());
+        assert!(restored
+            .add_fixed_base(FixedBase::new(&BigInteger::from(5u32), &acc.modulus, 8))
+            .is_err());
+
+        fast += 17u64;
+        assert_eq!(fast.fixed_bases().len(), 1);
+        assert_eq!(fast.fixed_bases()[0].base(), fast.generator.as_ref());
+    }
+
     #[test]
     fn default_test() {
         let key = AccumulatorSecretKey::default();
         let acc = Accumulator::new(&key);
-        assert_eq!(acc.generator, acc.value);
+        assert_eq!(*acc.generator, acc.value);
     }
 
     #[test]
@@ -484,7 +2134,7 @@ mod tests {
         for m in &members {
             acc.insert_assign(m).unwrap();
         }
-        let acc1 = Accumulator::with_members(&key, members.as_slice());
+        let acc1 = Accumulator::with_members(&key, members.as_slice()).unwrap();
         assert_eq!(acc.value, acc1.value);
     }
 
@@ -501,6 +2151,252 @@ mod tests {
         assert!(members.iter().all(|m| acc.members.contains(m)));
     }
 
+    #[test]
+    fn members_test() {
+        let key = AccumulatorSecretKey::default();
+        let acc = Accumulator::with_members(&key, ["a", "b", "c", "d", "e"]).unwrap();
+        let members = acc.members().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(members.len(), 5);
+        assert!(members.windows(2).all(|w| w[0] < w[1]));
+
+        let mut pages = Vec::new();
+        let mut after = None;
+        loop {
+            let page = acc.members_page(after.as_ref(), 2).unwrap();
+            if page.is_empty() {
+                break;
+            }
+            after = page.last().cloned();
+            pages.push(page);
+        }
+        assert_eq!(pages.len(), 3);
+        assert_eq!(pages.concat(), members);
+        assert_eq!(acc.into_iter().collect::<Vec<_>>(), members);
+    }
+
+    #[test]
+    fn contains_test() {
+        let key = AccumulatorSecretKey::default();
+        let mut acc = Accumulator::new(&key);
+        assert!(acc.is_empty());
+        acc.insert_assign("a").unwrap();
+        assert_eq!(acc.len(), 1);
+        assert!(acc.contains("a").unwrap());
+        assert!(acc.contains_prime(&hash_to_prime("a")).unwrap());
+        assert!(!acc.contains("b").unwrap());
+        acc.remove_assign(&key, "a").unwrap();
+        assert!(!acc.contains("a").unwrap());
+        assert!(acc.is_empty());
+    }
+
+    #[test]
+    fn sub_test() {
+        let key = AccumulatorSecretKey::default();
+        let acc = Accumulator::with_members(&key, ["a", "b", "c"]).unwrap();
+        let expected = acc.remove(&key, "a").unwrap();
+        assert_eq!((&acc - ("a", &key)).value, expected.value);
+
+        let mut acc1 = acc.clone();
+        acc1 -= ("a", &key);
+        assert_eq!(acc1.value, expected.value);
+
+        let witness = MembershipWitness::new(&acc, "a").unwrap();
+        let acc2 = &acc - &witness;
+        assert_eq!(acc2.value, expected.value);
+        assert_eq!(acc2.epoch, expected.epoch);
+        assert!(acc2.remove_with_witness(&witness).is_err());
+
+        let mut acc3 = acc + 5u64 - (5u64, &key);
+        acc3 -= &MembershipWitness::new(&acc3, "b").unwrap();
+        assert_eq!(acc3.value, Accumulator::with_members(&key, ["a", "c"]).unwrap().value);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn export_primes_test() {
+        let key = AccumulatorSecretKey::default();
+        let acc = Accumulator::with_members(&key, ["a", "b", "c"]).unwrap() + "d";
+        let mut out = Vec::new();
+        acc.export_primes(&mut out).unwrap();
+        assert_eq!(out.len(), HEADER_SIZE + 12 + 4 * MEMBER_SIZE);
+        assert_eq!(out[5], Tag::MemberPrimes as u8);
+
+        let (epoch, members) = Accumulator::import_primes(&out[..]).unwrap();
+        assert_eq!(epoch, 1);
+        assert_eq!(members, acc.members.iter().cloned().collect::<Vec<_>>());
+        assert!(Accumulator::import_primes(&out[..out.len() - 1]).is_err());
+
+        let mut swapped = out.clone();
+        let first = HEADER_SIZE + 12;
+        let (a, b) = swapped[first..first + 2 * MEMBER_SIZE].split_at_mut(MEMBER_SIZE);
+        a.swap_with_slice(b);
+        assert!(Accumulator::import_primes(&swapped[..]).is_err());
+
+        let mut composite = out.clone();
+        composite[first + MEMBER_SIZE - 1] ^= 1;
+        assert!(Accumulator::import_primes(&composite[..]).is_err());
+    }
+
+    #[test]
+    fn verify_consistency_test() {
+        let key = AccumulatorSecretKey::default();
+        let mut acc = Accumulator::with_members(&key, ["a", "b", "c"]).unwrap();
+        let report = acc.verify_consistency(&key).unwrap();
+        assert!(report.is_consistent());
+        assert_eq!(report.members, 3);
+        assert_eq!(report.expected, acc.value);
+        assert_eq!(report.product_matches, None);
+
+        acc.cache_product().unwrap();
+        acc.members.insert(BigInteger::from(15u32));
+        let report = acc.verify_consistency(&key).unwrap();
+        assert!(!report.is_consistent());
+        assert!(!report.value_matches);
+        assert_eq!(report.product_matches, Some(false));
+        assert_eq!(report.not_prime, vec![BigInteger::from(15u32)]);
+
+        let other = AccumulatorSecretKey::from_primes(BigInteger::from(23u32), BigInteger::from(47u32));
+        assert!(acc.verify_consistency(&other).is_err());
+    }
+
+    /// A store that fails the change after `budget` others, then works again
+    #[derive(Debug, Clone, Default)]
+    struct FailingStore {
+        members: BTreeSet<BigInteger>,
+        budget: usize,
+    }
+
+    impl FailingStore {
+        fn spend(&mut self) -> Result<(), AccumulatorError> {
+            if self.budget == 0 {
+                self.budget = usize::MAX;
+                return Err(AccumulatorErrorKind::StorageError.into());
+            }
+            self.budget -= 1;
+            Ok(())
+        }
+    }
+
+    impl MemberStore for FailingStore {
+        fn contains(&self, member: &BigInteger) -> Result<bool, AccumulatorError> {
+            Ok(self.members.contains(member))
+        }
+
+        fn insert(&mut self, member: BigInteger) -> Result<bool, AccumulatorError> {
+            self.spend()?;
+            Ok(self.members.insert(member))
+        }
+
+        fn remove(&mut self, member: &BigInteger) -> Result<bool, AccumulatorError> {
+            self.spend()?;
+            Ok(self.members.remove(member))
+        }
+
+        fn len(&self) -> usize {
+            self.members.len()
+        }
+
+        fn iter(&self) -> MemberIter<'_> {
+            Box::new(self.members.iter().cloned().map(Ok))
+        }
+    }
+
+    #[test]
+    fn add_prime_members_test() {
+        let key = AccumulatorSecretKey::default();
+        let mut acc = Accumulator::with_store(&key, FailingStore::default()).unwrap();
+        let primes: Vec<BigInteger> = (0..4).map(|_| BigInteger::generate_prime(MEMBER_SIZE_BITS)).collect();
+        acc.members.budget = usize::MAX;
+        acc.add_prime_members_assign(&primes[..2]).unwrap();
+        assert_eq!(acc.epoch, 1);
+        let (value, epoch) = (acc.value.clone(), acc.epoch);
+
+        // Checked as a whole before anything is added
+        let small = BigInteger::from(65537u32);
+        for batch in [
+            vec![primes[2].clone(), small],
+            vec![primes[2].clone(), primes[2].clone()],
+            vec![primes[2].clone(), primes[0].clone()],
+        ] {
+            assert!(acc.add_prime_members_assign(&batch).is_err());
+            assert_eq!((&acc.value, acc.epoch, acc.len()), (&value, epoch, 2));
+        }
+
+        // A store failing part way keeps neither member
+        acc.members.budget = 1;
+        assert!(acc.add_prime_members_assign(&primes[2..]).is_err());
+        assert_eq!((&acc.value, acc.epoch, acc.len()), (&value, epoch, 2));
+
+        acc.add_prime_members_assign(&primes[2..]).unwrap();
+        assert_eq!(acc.epoch, epoch + 1);
+        let mut expected = Accumulator::new(&key);
+        for p in &primes {
+            expected.insert_prime_assign(p).unwrap();
+        }
+        assert_eq!(acc.value, expected.value);
+    }
+
+    #[test]
+    fn primality_used_for_lookups() {
+        let key = AccumulatorSecretKey::default();
+        let mut acc = Accumulator::new(&key);
+        acc.set_primality(PrimalityTest::BailliePsw);
+        acc.insert_assign("a").unwrap();
+        let x = hash_to_prime_with("a", PrimalityTest::BailliePsw);
+        assert!(acc.contains_prime(&x).unwrap());
+        assert!(acc.contains("a").unwrap());
+        assert_eq!(MembershipWitness::new(&acc, "a").unwrap().x, x);
+        let proof = acc.prove_membership_with_secret_key(&key, "a", b"nonce").unwrap();
+        assert!(proof.verify(&acc, b"nonce"));
+        acc.remove_assign(&key, "a").unwrap();
+        assert!(acc.is_empty());
+    }
+
+    #[test]
+    fn remove_with_witnesses_test() {
+        let key = AccumulatorSecretKey::default();
+        let acc = Accumulator::with_members(&key, ["a", "b", "c", "d"]).unwrap();
+        let witnesses: Vec<MembershipWitness> = ["a", "c", "d"]
+            .iter()
+            .map(|m| MembershipWitness::new(&acc, m).unwrap())
+            .collect();
+        let acc1 = acc.remove_with_witnesses(&witnesses).unwrap();
+        assert_eq!(acc1.value, Accumulator::with_members(&key, ["b"]).unwrap().value);
+        assert_eq!(acc1.epoch, acc.epoch + 1);
+        assert_eq!(acc1.len(), 1);
+        assert!(acc1.remove_with_witnesses(&witnesses[..1]).is_err());
+
+        let repeated = [witnesses[0].clone(), witnesses[0].clone()];
+        assert!(acc.remove_with_witnesses(&repeated).is_err());
+
+        let acc2 = acc.remove_with_witness(&witnesses[2]).unwrap();
+        assert!(acc2.remove_with_witnesses(&witnesses[..2]).is_err());
+
+        let acc3 = acc.remove_with_witnesses(&[]).unwrap();
+        assert_eq!(acc3.epoch, acc.epoch);
+        assert_eq!(acc3.value, acc.value);
+
+        // A store failing part way keeps every member
+        let mut store = FailingStore {
+            budget: usize::MAX,
+            ..FailingStore::default()
+        };
+        for m in ["a", "b", "c", "d"] {
+            store.members.insert(hash_to_prime(m));
+        }
+        let mut acc = Accumulator::with_store(&key, store).unwrap();
+        let witnesses: Vec<MembershipWitness> = ["a", "c", "d"]
+            .iter()
+            .map(|m| MembershipWitness::new(&acc, m).unwrap())
+            .collect();
+        let (value, epoch) = (acc.value.clone(), acc.epoch);
+        acc.members.budget = 2;
+        assert!(acc.remove_with_witnesses_assign(&witnesses).is_err());
+        assert_eq!((&acc.value, acc.epoch, acc.len()), (&value, epoch, 4));
+        acc.remove_with_witnesses_assign(&witnesses).unwrap();
+        assert_eq!(acc.len(), 1);
+    }
+
     #[test]
     fn add_error_test() {
         let biguint = BigInteger::from("345617283975612837561827365");
@@ -0,0 +1,341 @@
+// An append-only log of the deltas an issuer published, each entry signed
+// and linked to the one before it by hash, the first to the genesis
+// definition. An auditor replays the log from genesis and checks
+//
+// - every entry is signed by the issuer and links to the previous one
+// - the epochs follow each other
+// - each new value is the old value with exactly the listed changes,
+//   new^(∏ deletions) = old^(∏ additions), which doesn't need the members
+// - the values the issuer published match the replayed ones
+//
+// so an issuer can't revoke a credential without it showing in the log
+use crate::{
+    accumulator::AccumulatorPublic,
+    format::{Header, Tag},
+    registry::RegistryDelta,
+};
+use blake2::{digest::Digest, Blake2b};
+use common::error::{AccumulatorError, AccumulatorErrorKind};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey, SIGNATURE_LENGTH};
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+/// The number of bytes of the hash linking entries
+pub const AUDIT_HASH_BYTES: usize = 64;
+
+/// One published delta with the hash of the entry before it
+/// and the issuer's signature over both
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct AuditEntry {
+    /// The delta published for the epoch
+    pub delta: RegistryDelta,
+    /// The hash of the previous entry or of the genesis definition
+    pub previous: [u8; AUDIT_HASH_BYTES],
+    signature: Signature,
+}
+
+impl AuditEntry {
+    /// The hash the next entry links to
+    pub fn hash(&self) -> Result<[u8; AUDIT_HASH_BYTES], AccumulatorError> {
+        Ok(hash(&self.to_bytes()?))
+    }
+
+    /// Check the entry was signed by `key`
+    pub fn verify(&self, key: &VerifyingKey) -> Result<(), AccumulatorError> {
+        key.verify_strict(&message(&self.previous, &self.delta)?, &self.signature)
+            .map_err(|_| {
+                AccumulatorError::from_msg(
+                    AccumulatorErrorKind::ProofVerificationFailed,
+                    "invalid audit entry signature",
+                )
+            })
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Header::new(Tag::AuditEntry).to_bytes();
+        output.extend_from_slice(&self.previous);
+        output.extend_from_slice(&self.signature.to_bytes());
+        output.append(&mut self.delta.to_bytes()?);
+        Ok(output)
+    }
+}
+
+impl TryFrom<&[u8]> for AuditEntry {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::AuditEntry)?;
+        let fixed = AUDIT_HASH_BYTES + SIGNATURE_LENGTH;
+        if data.len() < fixed {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: fixed,
+                actual: data.len(),
+            }
+            .into());
+        }
+        Ok(Self {
+            previous: *array_ref![data, 0, AUDIT_HASH_BYTES],
+            signature: Signature::from_bytes(array_ref![data, AUDIT_HASH_BYTES, SIGNATURE_LENGTH]),
+            delta: RegistryDelta::try_from(&data[fixed..])?,
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for AuditEntry {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+serdes_impl!(AuditEntry);
+
+/// The signed history of a registry from its genesis definition
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct AuditLog {
+    genesis: AccumulatorPublic,
+    entries: Vec<AuditEntry>,
+}
+
+impl AuditLog {
+    /// Start a log for a registry published as `genesis`
+    pub fn new(genesis: AccumulatorPublic) -> Self {
+        Self {
+            genesis,
+            entries: Vec::new(),
+        }
+    }
+
+    /// The definition the log starts from
+    pub fn genesis(&self) -> &AccumulatorPublic {
+        &self.genesis
+    }
+
+    /// The entries in the order they were appended
+    pub fn entries(&self) -> &[AuditEntry] {
+        &self.entries
+    }
+
+    /// The definition after the last entry
+    pub fn head(&self) -> Result<AccumulatorPublic, AccumulatorError> {
+        self.entries
+            .iter()
+            .try_fold(self.genesis.clone(), |d, e| e.delta.apply(&d))
+    }
+
+    /// Sign `delta` with the issuer's `key` and append it. The delta
+    /// must follow the last entry
+    pub fn append(&mut self, delta: RegistryDelta, key: &SigningKey) -> Result<&AuditEntry, AccumulatorError> {
+        let head = self.head()?;
+        delta.verify(&head)?;
+        let previous = self.last_hash()?;
+        let signature = key.sign(&message(&previous, &delta)?);
+        self.entries.push(AuditEntry {
+            delta,
+            previous,
+            signature,
+        });
+        Ok(&self.entries[self.entries.len() - 1])
+    }
+
+    /// Replay the log from genesis checking every entry was signed by
+    /// `key`, links to the one before it and changes the value by exactly
+    /// its additions and deletions. Each of `published` must match the
+    /// replayed definition at its epoch. Returns the definition after
+    /// the last entry
+    pub fn verify(
+        &self,
+        key: &VerifyingKey,
+        published: &[AccumulatorPublic],
+    ) -> Result<AccumulatorPublic, AccumulatorError> {
+        let mut definition = self.genesis.clone();
+        let mut previous = hash(&self.genesis.to_bytes()?);
+        let mut history = Vec::with_capacity(self.entries.len() + 1);
+        history.push(definition.clone());
+        for entry in &self.entries {
+            if entry.previous != previous {
+                return Err(AccumulatorError::from_msg(
+                    AccumulatorErrorKind::ProofVerificationFailed,
+                    "audit entry does not link to the previous entry",
+                ));
+            }
+            entry.verify(key)?;
+            definition = entry.delta.verify(&definition)?;
+            previous = entry.hash()?;
+            history.push(definition.clone());
+        }
+        for p in published {
+            match history.iter().find(|d| d.epoch == p.epoch) {
+                Some(d) if d == p => {}
+                Some(_) => {
+                    return Err(AccumulatorError::from_msg(
+                        AccumulatorErrorKind::ProofVerificationFailed,
+                        "published value is not the value in the audit log",
+                    ))
+                }
+                None => {
+                    return Err(AccumulatorErrorKind::EpochMismatch {
+                        expected: definition.epoch,
+                        actual: p.epoch,
+                    }
+                    .into())
+                }
+            }
+        }
+        Ok(definition)
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Header::new(Tag::AuditLog).to_bytes();
+        output.append(&mut self.genesis.to_bytes()?);
+        output.extend_from_slice(&(self.entries.len() as u32).to_be_bytes());
+        for e in &self.entries {
+            let mut entry = e.to_bytes()?;
+            output.extend_from_slice(&(entry.len() as u32).to_be_bytes());
+            output.append(&mut entry);
+        }
+        Ok(output)
+    }
+
+    fn last_hash(&self) -> Result<[u8; AUDIT_HASH_BYTES], AccumulatorError> {
+        match self.entries.last() {
+            Some(e) => e.hash(),
+            None => Ok(hash(&self.genesis.to_bytes()?)),
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for AuditLog {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::AuditLog)?;
+        let mut offset = AccumulatorPublic::SIZE_BYTES;
+        let genesis = AccumulatorPublic::try_from(take(data, 0, offset)?)?;
+        let count = u32::from_be_bytes(*array_ref![take(data, offset, 4)?, 0, 4]);
+        offset += 4;
+        // Every entry is at least its length
+        let mut entries = Vec::with_capacity((count as usize).min((data.len() - offset) / 4));
+        for _ in 0..count {
+            let len = u32::from_be_bytes(*array_ref![take(data, offset, 4)?, 0, 4]) as usize;
+            offset += 4;
+            entries.push(AuditEntry::try_from(take(data, offset, len)?)?);
+            offset += len;
+        }
+        if offset != data.len() {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: offset,
+                actual: data.len(),
+            }
+            .into());
+        }
+        Ok(Self { genesis, entries })
+    }
+}
+
+impl TryFrom<Vec<u8>> for AuditLog {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+serdes_impl!(AuditLog);
+
+/// The `len` bytes of `data` at `offset`
+fn take(data: &[u8], offset: usize, len: usize) -> Result<&[u8], AccumulatorError> {
+    offset
+        .checked_add(len)
+        .filter(|end| *end <= data.len())
+        .map(|end| &data[offset..end])
+        .ok_or_else(|| {
+            AccumulatorErrorKind::InvalidLength {
+                expected: offset.saturating_add(len),
+                actual: data.len(),
+            }
+            .into()
+        })
+}
+
+fn hash(data: &[u8]) -> [u8; AUDIT_HASH_BYTES] {
+    let mut h = [0u8; AUDIT_HASH_BYTES];
+    h.copy_from_slice(Blake2b::digest(data).as_slice());
+    h
+}
+
+/// What the issuer signs, the link and the delta
+fn message(previous: &[u8; AUDIT_HASH_BYTES], delta: &RegistryDelta) -> Result<Vec<u8>, AccumulatorError> {
+    let mut message = previous.to_vec();
+    message.append(&mut delta.to_bytes()?);
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hash::hash_to_prime, key::AccumulatorSecretKey, registry::RevocationRegistry};
+
+    #[test]
+    fn replays_log() {
+        let key = AccumulatorSecretKey::default();
+        let issuer = SigningKey::from_bytes(&[7u8; 32]);
+        let mut registry = RevocationRegistry::new(&key);
+        let mut log = AuditLog::new(registry.definition());
+        let mut published = vec![registry.definition()];
+        let epochs: [(&[&str], &[&str]); 3] = [(&["alice", "bob", "carol"], &[]), (&["dave"], &["bob"]), (&[], &["alice"])];
+        for (add, revoke) in epochs {
+            add.iter().for_each(|h| registry.register(h).unwrap());
+            revoke.iter().for_each(|h| registry.revoke(h).unwrap());
+            let delta = registry.publish(&key).unwrap().unwrap().clone();
+            log.append(delta, &issuer).unwrap();
+            published.push(registry.definition());
+        }
+        let head = log.verify(&issuer.verifying_key(), &published).unwrap();
+        assert_eq!(head, registry.definition());
+        assert_eq!(log.head().unwrap(), head);
+
+        let parsed = AuditLog::try_from(log.to_bytes().unwrap()).unwrap();
+        assert_eq!(parsed, log);
+        let entry = &log.entries()[1];
+        assert_eq!(&AuditEntry::try_from(entry.to_bytes().unwrap()).unwrap(), entry);
+
+        // Another issuer, a published value that isn't in the log
+        let other = SigningKey::from_bytes(&[8u8; 32]);
+        assert!(log.verify(&other.verifying_key(), &published).is_err());
+        let mut spoofed = published.clone();
+        spoofed[2].value = published[1].value.clone();
+        assert!(log.verify(&issuer.verifying_key(), &spoofed).is_err());
+        let mut ahead = registry.definition();
+        ahead.epoch += 1;
+        assert!(log.verify(&issuer.verifying_key(), &[ahead]).is_err());
+
+        // A changed entry, a reordered log and a delta with an unlisted change
+        let mut hidden = log.clone();
+        hidden.entries[2].delta.deletions.clear();
+        assert!(hidden.verify(&issuer.verifying_key(), &[]).is_err());
+        let mut reordered = log.clone();
+        reordered.entries.swap(1, 2);
+        assert!(reordered.verify(&issuer.verifying_key(), &[]).is_err());
+        let mut unlisted = AuditLog::new(log.genesis().clone());
+        let mut delta = log.entries()[0].delta.clone();
+        delta.additions.pop();
+        assert!(unlisted.append(delta.clone(), &issuer).is_err());
+
+        // A prime both added and deleted cancels out of the value
+        let mut cancelled = log.entries()[0].delta.clone();
+        let extra = hash_to_prime(b"erin");
+        cancelled.additions.push(extra.clone());
+        cancelled.deletions.push(extra);
+        assert!(unlisted.append(cancelled, &issuer).is_err());
+
+        // A value outside the group
+        let mut outside = log.entries()[0].delta.clone();
+        outside.value = log.genesis().modulus.clone();
+        assert!(unlisted.append(outside, &issuer).is_err());
+    }
+}
@@ -0,0 +1,1024 @@
+use crate::{
+    accumulator::{Accumulator, AccumulatorPublic},
+    b2fa, bound_nonce, in_group, proof_element,
+    format::{read_members, write_members, Header, Tag},
+    hash::hash_to_prime,
+    memwitness::{Freshness, MembershipWitness},
+    store::MemberStore,
+    telemetry, Poke2Proof, ProofSize, FACTOR_SIZE, MEMBER_PRIMALITY, MEMBER_SIZE,
+};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+    Field,
+};
+use crate::par::*;
+#[cfg(not(feature = "std"))]
+use alloc::{collections::BTreeSet, vec::Vec};
+use core::convert::TryFrom;
+#[cfg(feature = "std")]
+use std::collections::BTreeSet;
+#[cfg(feature = "std")]
+use crate::format::write_element;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+/// A non-interactive proof of exponentiation that `u^x = w` from
+/// section 3.1 in <https://eprint.iacr.org/2018/1188.pdf>.
+/// Checking it costs two small exponentiations no matter how large `x` is
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone)]
+pub struct PoeProof {
+    q: BigInteger,
+}
+
+impl PoeProof {
+    /// The size of this proof serialized
+    pub const SIZE_BYTES: usize = 2 * FACTOR_SIZE;
+
+    /// Prove `u^x = w` mod `n`
+    pub fn new(u: &BigInteger, x: &BigInteger, w: &BigInteger, n: &BigInteger) -> Self {
+        Self::new_with_context(u, x, w, n, &[])
+    }
+
+    /// Prove `u^x = w` mod `n` bound to `context`, which must be
+    /// given again to verify
+    pub fn new_with_context(u: &BigInteger, x: &BigInteger, w: &BigInteger, n: &BigInteger, context: &[u8]) -> Self {
+        let l = Self::challenge(u, x, w, n, context);
+        // Q = u ^ (x / l)
+        let (whole, _) = BigInteger::div_rem(x, &l);
+        Self {
+            q: u.mod_exp(&whole, n),
+        }
+    }
+
+    /// Check `u^x = w` mod `n`
+    pub fn verify(&self, u: &BigInteger, x: &BigInteger, w: &BigInteger, n: &BigInteger) -> bool {
+        self.verify_with_context(u, x, w, n, &[])
+    }
+
+    /// Check `u^x = w` mod `n` for a proof made with `context`
+    pub fn verify_with_context(
+        &self,
+        u: &BigInteger,
+        x: &BigInteger,
+        w: &BigInteger,
+        n: &BigInteger,
+        context: &[u8],
+    ) -> bool {
+        if !in_group(&[&self.q], n) {
+            return false;
+        }
+        let l = Self::challenge(u, x, w, n, context);
+        let (_, r) = BigInteger::div_rem(x, &l);
+        // Q^l * u^r == w
+        Field::new(n).multi_exp(&[(&self.q, &l), (u, &r)]) == w % n
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        b2fa(&self.q, Self::SIZE_BYTES)
+    }
+
+    /// Write the same bytes as `to_bytes` to `w`
+    #[cfg(feature = "std")]
+    pub fn write_bytes<W: Write>(&self, mut w: W) -> Result<(), AccumulatorError> {
+        write_element(&mut w, &self.q, Self::SIZE_BYTES)
+    }
+
+    /// l = H2P( n || u || x || w || context ) with every field length
+    /// prefixed. An empty context isn't written
+    fn challenge(u: &BigInteger, x: &BigInteger, w: &BigInteger, n: &BigInteger, context: &[u8]) -> BigInteger {
+        let mut data = b"poe".to_vec();
+        for e in &[n, u, x, w] {
+            let bytes = e.to_bytes();
+            data.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+            data.extend_from_slice(&bytes);
+        }
+        if !context.is_empty() {
+            data.extend_from_slice(&(context.len() as u32).to_be_bytes());
+            data.extend_from_slice(context);
+        }
+        hash_to_prime(data.as_slice())
+    }
+}
+
+impl TryFrom<&[u8]> for PoeProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        if data.len() != Self::SIZE_BYTES {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: Self::SIZE_BYTES,
+                actual: data.len(),
+            }
+            .into());
+        }
+        Ok(Self {
+            q: proof_element(data)?,
+        })
+    }
+}
+
+/// `A^(1 / (x_1 x_2))` from `w_1^x_1 = A` and `w_2^x_2 = A`
+/// where `x_1` and `x_2` are coprime, see section 3.2 in
+/// <https://eprint.iacr.org/2018/1188.pdf>
+pub(crate) fn shamir_trick(
+    w1: &BigInteger,
+    x1: &BigInteger,
+    w2: &BigInteger,
+    x2: &BigInteger,
+    n: &BigInteger,
+) -> Result<BigInteger, AccumulatorError> {
+    let gcd_res = x1.bezouts_coefficients(x2);
+    if gcd_res.value != BigInteger::from(1u32) {
+        return Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::InvalidMemberSupplied,
+            "values are not coprime",
+        ));
+    }
+    // a * x_1 + b * x_2 = 1 so (w_1^b * w_2^a)^(x_1 * x_2) = A^(b * x_2) * A^(a * x_1) = A
+    Ok(w1.mod_exp(&gcd_res.b, n).mod_mul(&w2.mod_exp(&gcd_res.a, n), n))
+}
+
+/// Combine witnesses for `value` into `(u, x)` where `u^x = value` and `x`
+/// is the product of their members. Fails if a witness is not valid for `value`
+pub(crate) fn aggregate(
+    witnesses: &[MembershipWitness],
+    value: &BigInteger,
+    n: &BigInteger,
+) -> Result<(BigInteger, BigInteger), AccumulatorError> {
+    if witnesses.par_iter().any(|w| &w.u.mod_exp(&w.x, n) != value) {
+        return Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::InvalidMemberSupplied,
+            "witness does not match the accumulator",
+        ));
+    }
+    let mut u = value.clone();
+    let mut x = BigInteger::from(1u32);
+    for w in witnesses {
+        u = shamir_trick(&u, &x, &w.u, &w.x, n)?;
+        x *= &w.x;
+    }
+    Ok((u, x))
+}
+
+pub(crate) fn all_prime(values: &[BigInteger]) -> bool {
+    values.par_iter().all(|v| v.is_prime_with(MEMBER_PRIMALITY))
+}
+
+/// Check `next` is `accumulator` with exactly `additions` added and
+/// `deletions` removed, without the secret key or a proof, so anyone can
+/// audit a transparent registry from its published values and deltas.
+///
+/// With A and D the products of the additions and deletions this checks
+/// `next^D = accumulator^A`. Distinct primes make A and D coprime, so
+/// with Bézout coefficients `aA + bD = 1` both values are powers of
+/// `r = next^a * accumulator^b`: `accumulator = r^D` and `next = r^A`.
+/// The cost grows with the size of the block, `BatchUpdate` carries
+/// proofs of exponentiation that check in constant time instead
+pub fn verify_transition(
+    accumulator: &AccumulatorPublic,
+    next: &AccumulatorPublic,
+    additions: &[BigInteger],
+    deletions: &[BigInteger],
+) -> Result<(), AccumulatorError> {
+    if next.modulus != accumulator.modulus || next.generator != accumulator.generator {
+        return Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::ParameterMismatch,
+            "accumulators have different parameters",
+        ));
+    }
+    if next.epoch != accumulator.epoch + 1 {
+        return Err(AccumulatorErrorKind::EpochMismatch {
+            expected: accumulator.epoch + 1,
+            actual: next.epoch,
+        }
+        .into());
+    }
+    let mut seen = BTreeSet::new();
+    if !additions.iter().chain(deletions).all(|x| seen.insert(x)) {
+        return Err(AccumulatorErrorKind::DuplicateValueSupplied.into());
+    }
+    if !all_prime(additions) || !all_prime(deletions) {
+        return Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::InvalidMemberSupplied,
+            "Some members are not prime",
+        ));
+    }
+    let n = &accumulator.modulus;
+    if !in_group(&[&accumulator.value, &next.value], n) {
+        return Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::InvalidType,
+            "value is not an element of the group",
+        ));
+    }
+    let x_add: BigInteger = additions.par_iter().product();
+    let x_del: BigInteger = deletions.par_iter().product();
+    if next.value.mod_exp(&x_del, n) != accumulator.value.mod_exp(&x_add, n) {
+        return Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::ProofVerificationFailed,
+            "the values differ by more than the claimed changes",
+        ));
+    }
+    Ok(())
+}
+
+/// A block of additions and deletions applied to an accumulator without
+/// the trapdoor or the member set, as in section 4.2 in
+/// <https://eprint.iacr.org/2018/1188.pdf>. The deletions are applied
+/// first then the additions, each with a proof of exponentiation so nodes
+/// verify the block in constant time and users update their own witnesses.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct BatchUpdate {
+    /// Member primes added by this block
+    pub additions: Vec<BigInteger>,
+    /// Member primes removed by this block
+    pub deletions: Vec<BigInteger>,
+    /// The accumulator value after the deletions and before the additions
+    pub intermediate: BigInteger,
+    /// The accumulator value after the block
+    pub value: BigInteger,
+    deletion_proof: PoeProof,
+    addition_proof: PoeProof,
+}
+
+impl BatchUpdate {
+    /// Apply a block to `accumulator`. Deleting a member needs its current
+    /// witness, the witnesses are aggregated to remove them all at once
+    pub fn new(
+        accumulator: &AccumulatorPublic,
+        additions: &[BigInteger],
+        deletions: &[MembershipWitness],
+    ) -> Result<Self, AccumulatorError> {
+        if !all_prime(additions) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "Some additions are not prime",
+            ));
+        }
+        let mut seen = BTreeSet::new();
+        if !additions.iter().chain(deletions.iter().map(|w| &w.x)).all(|x| seen.insert(x)) {
+            return Err(AccumulatorErrorKind::DuplicateValueSupplied.into());
+        }
+        let n = &accumulator.modulus;
+        let (intermediate, x_del) = aggregate(deletions, &accumulator.value, n)?;
+        let x_add: BigInteger = additions.par_iter().product();
+        let value = intermediate.mod_exp(&x_add, n);
+        Ok(Self {
+            additions: additions.to_vec(),
+            deletions: deletions.iter().map(|w| w.x.clone()).collect(),
+            deletion_proof: PoeProof::new(&intermediate, &x_del, &accumulator.value, n),
+            addition_proof: PoeProof::new(&intermediate, &x_add, &value, n),
+            intermediate,
+            value,
+        })
+    }
+
+    /// Check this block turns `accumulator` into `value`
+    pub fn verify(&self, accumulator: &AccumulatorPublic) -> bool {
+        if !all_prime(&self.additions) || !all_prime(&self.deletions) {
+            return false;
+        }
+        let n = &accumulator.modulus;
+        if !in_group(&[&accumulator.value, &self.intermediate, &self.value], n) {
+            return false;
+        }
+        let x_del: BigInteger = self.deletions.par_iter().product();
+        let x_add: BigInteger = self.additions.par_iter().product();
+        self.deletion_proof.verify(&self.intermediate, &x_del, &accumulator.value, n)
+            && self.addition_proof.verify(&self.intermediate, &x_add, &self.value, n)
+    }
+
+    /// Verify this block and return the accumulator after it
+    pub fn apply(&self, accumulator: &AccumulatorPublic) -> Result<AccumulatorPublic, AccumulatorError> {
+        if !self.verify(accumulator) {
+            return Err(AccumulatorErrorKind::ProofVerificationFailed.into());
+        }
+        Ok(self.next(accumulator))
+    }
+
+    /// Update a witness for `accumulator` to match the accumulator after
+    /// this block. Fails if the member was deleted. Verify the block first
+    pub fn update_witness_assign(
+        &self,
+        witness: &mut MembershipWitness,
+        accumulator: &AccumulatorPublic,
+    ) -> Result<(), AccumulatorError> {
+        if self.deletions.contains(&witness.x) || self.additions.contains(&witness.x) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "member was changed by this block",
+            ));
+        }
+        if !in_group(&[&witness.u], &accumulator.modulus) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidType,
+                "witness is not an element of the group",
+            ));
+        }
+        let next = self.next(accumulator);
+        witness.apply_changes(self.additions.iter().collect(), self.deletions.iter().collect(), &next);
+        Ok(())
+    }
+
+    /// Same as `update_witness_assign` but returns a new witness
+    pub fn update_witness(
+        &self,
+        witness: &MembershipWitness,
+        accumulator: &AccumulatorPublic,
+    ) -> Result<MembershipWitness, AccumulatorError> {
+        let mut w = witness.clone();
+        self.update_witness_assign(&mut w, accumulator)?;
+        Ok(w)
+    }
+
+    /// The witness for a member this block added, computed from the block alone
+    pub fn addition_witness(
+        &self,
+        x: &BigInteger,
+        accumulator: &AccumulatorPublic,
+    ) -> Result<MembershipWitness, AccumulatorError> {
+        if !self.additions.contains(x) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "value was not added by this block",
+            ));
+        }
+        let others: BigInteger = self.additions.par_iter().filter(|a| *a != x).product();
+        let next = self.next(accumulator);
+        Ok(MembershipWitness {
+            u: self.intermediate.mod_exp(&others, &accumulator.modulus),
+            x: x.clone(),
+            freshness: Freshness::new(next.epoch, &next.value),
+        })
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Header::new(Tag::BatchUpdate).to_bytes();
+        output.append(&mut b2fa(&self.intermediate, FACTOR_SIZE * 2)?);
+        output.append(&mut b2fa(&self.value, FACTOR_SIZE * 2)?);
+        output.append(&mut self.deletion_proof.to_bytes()?);
+        output.append(&mut self.addition_proof.to_bytes()?);
+        write_members(&self.additions, &mut output)?;
+        write_members(&self.deletions, &mut output)?;
+        Ok(output)
+    }
+
+    fn next(&self, accumulator: &AccumulatorPublic) -> AccumulatorPublic {
+        AccumulatorPublic {
+            value: self.value.clone(),
+            epoch: accumulator.epoch + 1,
+            ..accumulator.clone()
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for BatchUpdate {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::BatchUpdate)?;
+        let size = FACTOR_SIZE * 2;
+        let mut offset = size * 4;
+        let additions = read_members(data, &mut offset)?;
+        let deletions = read_members(data, &mut offset)?;
+        if offset != data.len() {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: offset,
+                actual: data.len(),
+            }
+            .into());
+        }
+        Ok(Self {
+            additions,
+            deletions,
+            intermediate: BigInteger::try_from(&data[..size])?,
+            value: BigInteger::try_from(&data[size..(size * 2)])?,
+            deletion_proof: PoeProof::try_from(&data[(size * 2)..(size * 3)])?,
+            addition_proof: PoeProof::try_from(&data[(size * 3)..(size * 4)])?,
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for BatchUpdate {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+serdes_impl!(BatchUpdate);
+
+/// A proof covering consecutive blocks, so a light verifier checks the
+/// move from one epoch to a later one with a single proof instead of one
+/// per block. With `A` the product of every addition and `D` the product
+/// of every deletion, the value `w` after the blocks and the value `v`
+/// before them satisfy `w^D = v^A`, shown with two proofs of exponentiation
+/// to the same `t`. A member added then deleted inside the range is in both
+/// lists and cancels out. Both proofs are bound to `blocks` so the epoch
+/// span can't be changed without the proof failing
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct UpdateProof {
+    /// Member primes added by the blocks
+    pub additions: Vec<BigInteger>,
+    /// Member primes removed by the blocks
+    pub deletions: Vec<BigInteger>,
+    /// The number of blocks covered
+    pub blocks: u64,
+    /// The accumulator value after the blocks
+    pub value: BigInteger,
+    t: BigInteger,
+    deletion_proof: PoeProof,
+    addition_proof: PoeProof,
+}
+
+impl UpdateProof {
+    /// Aggregate `blocks`, published in order starting at `accumulator`.
+    /// Each block is checked against the state before it
+    pub fn aggregate(
+        accumulator: &AccumulatorPublic,
+        blocks: &[BatchUpdate],
+    ) -> Result<Self, AccumulatorError> {
+        if blocks.is_empty() {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidType,
+                "no blocks to aggregate",
+            ));
+        }
+        let mut state = accumulator.clone();
+        for block in blocks {
+            state = block.apply(&state)?;
+        }
+        let additions: Vec<BigInteger> = blocks.iter().flat_map(|b| b.additions.clone()).collect();
+        let deletions: Vec<BigInteger> = blocks.iter().flat_map(|b| b.deletions.clone()).collect();
+        let n = &accumulator.modulus;
+        let x_add: BigInteger = additions.par_iter().product();
+        let x_del: BigInteger = deletions.par_iter().product();
+        let t = accumulator.value.mod_exp(&x_add, n);
+        let span = blocks.len() as u64;
+        let context = Self::context(span);
+        Ok(Self {
+            deletion_proof: PoeProof::new_with_context(&state.value, &x_del, &t, n, &context),
+            addition_proof: PoeProof::new_with_context(&accumulator.value, &x_add, &t, n, &context),
+            additions,
+            deletions,
+            blocks: span,
+            value: state.value,
+            t,
+        })
+    }
+
+    /// Check the blocks turn `accumulator` into `value`
+    pub fn verify(&self, accumulator: &AccumulatorPublic) -> bool {
+        telemetry::verify("update", || {
+            if self.blocks == 0 || !all_prime(&self.additions) || !all_prime(&self.deletions) {
+                return false;
+            }
+            let n = &accumulator.modulus;
+            if !in_group(&[&self.value, &self.t], n) {
+                return false;
+            }
+            let x_del: BigInteger = self.deletions.par_iter().product();
+            let x_add: BigInteger = self.additions.par_iter().product();
+            let context = Self::context(self.blocks);
+            self.deletion_proof.verify_with_context(&self.value, &x_del, &self.t, n, &context)
+                && self.addition_proof.verify_with_context(&accumulator.value, &x_add, &self.t, n, &context)
+        })
+    }
+
+    /// The transcript context binding the number of blocks
+    fn context(blocks: u64) -> Vec<u8> {
+        let mut context = b"update".to_vec();
+        context.extend_from_slice(&blocks.to_be_bytes());
+        context
+    }
+
+    /// Verify this proof and return the accumulator after the blocks
+    pub fn apply(&self, accumulator: &AccumulatorPublic) -> Result<AccumulatorPublic, AccumulatorError> {
+        if !self.verify(accumulator) {
+            return Err(AccumulatorErrorKind::ProofVerificationFailed.into());
+        }
+        Ok(AccumulatorPublic {
+            value: self.value.clone(),
+            epoch: accumulator.epoch + self.blocks,
+            ..accumulator.clone()
+        })
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Header::new(Tag::UpdateProof).to_bytes();
+        output.extend_from_slice(&self.blocks.to_be_bytes());
+        output.append(&mut b2fa(&self.value, FACTOR_SIZE * 2)?);
+        output.append(&mut b2fa(&self.t, FACTOR_SIZE * 2)?);
+        output.append(&mut self.deletion_proof.to_bytes()?);
+        output.append(&mut self.addition_proof.to_bytes()?);
+        write_members(&self.additions, &mut output)?;
+        write_members(&self.deletions, &mut output)?;
+        Ok(output)
+    }
+}
+
+impl TryFrom<&[u8]> for UpdateProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::UpdateProof)?;
+        let size = FACTOR_SIZE * 2;
+        let mut offset = 8 + size * 4;
+        let additions = read_members(data, &mut offset)?;
+        let deletions = read_members(data, &mut offset)?;
+        if offset != data.len() {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: offset,
+                actual: data.len(),
+            }
+            .into());
+        }
+        let blocks = u64::from_be_bytes(*array_ref![data, 0, 8]);
+        let data = &data[8..];
+        Ok(Self {
+            additions,
+            deletions,
+            blocks,
+            value: BigInteger::try_from(&data[..size])?,
+            t: BigInteger::try_from(&data[size..(size * 2)])?,
+            deletion_proof: PoeProof::try_from(&data[(size * 2)..(size * 3)])?,
+            addition_proof: PoeProof::try_from(&data[(size * 3)..(size * 4)])?,
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for UpdateProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+serdes_impl!(UpdateProof);
+
+/// A constant size proof that several values are all members,
+/// made by aggregating their witnesses
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct AggregateMembershipProof {
+    u: BigInteger,
+    proof: PoeProof,
+}
+
+impl AggregateMembershipProof {
+    /// Aggregate `witnesses` for `accumulator` into one proof
+    pub fn new(witnesses: &[MembershipWitness], accumulator: &AccumulatorPublic) -> Result<Self, AccumulatorError> {
+        if witnesses.is_empty() {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "no witnesses to aggregate",
+            ));
+        }
+        let n = &accumulator.modulus;
+        let (u, x) = aggregate(witnesses, &accumulator.value, n)?;
+        let proof = PoeProof::new(&u, &x, &accumulator.value, n);
+        Ok(Self { u, proof })
+    }
+
+    /// Check every one of `members` is in `accumulator`
+    pub fn verify(&self, members: &[BigInteger], accumulator: &AccumulatorPublic) -> bool {
+        telemetry::verify("aggregate_membership", || {
+            if members.is_empty() || !all_prime(members) {
+                return false;
+            }
+            if !in_group(&[&self.u], &accumulator.modulus) {
+                return false;
+            }
+            let x: BigInteger = members.par_iter().product();
+            self.proof.verify(&self.u, &x, &accumulator.value, &accumulator.modulus)
+        })
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Header::new(Tag::AggregateMembershipProof).to_bytes();
+        output.append(&mut b2fa(&self.u, FACTOR_SIZE * 2)?);
+        output.append(&mut self.proof.to_bytes()?);
+        Ok(output)
+    }
+
+    /// Write the same bytes as `to_bytes` to `w`
+    #[cfg(feature = "std")]
+    pub fn write_bytes<W: Write>(&self, mut w: W) -> Result<(), AccumulatorError> {
+        Header::new(Tag::AggregateMembershipProof).write_to(&mut w)?;
+        write_element(&mut w, &self.u, FACTOR_SIZE * 2)?;
+        self.proof.write_bytes(w)
+    }
+}
+
+impl TryFrom<&[u8]> for AggregateMembershipProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::AggregateMembershipProof)?;
+        if data.len() != FACTOR_SIZE * 2 + PoeProof::SIZE_BYTES {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: FACTOR_SIZE * 2 + PoeProof::SIZE_BYTES,
+                actual: data.len(),
+            }
+            .into());
+        }
+        Ok(Self {
+            u: proof_element(&data[..(FACTOR_SIZE * 2)])?,
+            proof: PoeProof::try_from(&data[(FACTOR_SIZE * 2)..])?,
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for AggregateMembershipProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+serdes_impl!(AggregateMembershipProof);
+hash_ord_impl!(AggregateMembershipProof; u, proof);
+hex_impl!(AggregateMembershipProof);
+
+/// A constant size proof that none of several values are members, the
+/// non-membership of the product of their primes from section 4.1 of
+/// <https://eprint.iacr.org/2018/1188.pdf>. For a value A the prover knows
+/// a and B with A^a = B^x g where x is the product. It sends v = A^a with a
+/// PoKE2 for a and B with a PoE for B^x = g^-1 v, so checking it costs the
+/// same for any number of values
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct BatchNonMembershipProof {
+    v: BigInteger,
+    b: BigInteger,
+    z: BigInteger,
+    q: BigInteger,
+    r: BigInteger,
+    proof: PoeProof,
+}
+
+impl BatchNonMembershipProof {
+    /// The size of this proof serialized without the header
+    pub const SIZE_BYTES: usize = 10 * FACTOR_SIZE + MEMBER_SIZE;
+
+    /// Prove none of `values` are in `accumulator`
+    pub fn new<S: MemberStore>(values: &[BigInteger], accumulator: &Accumulator<S>) -> Result<Self, AccumulatorError> {
+        if values.is_empty() || !all_prime(values) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "values must be primes and there must be at least one",
+            ));
+        }
+        let n = &accumulator.modulus;
+        let f = Field::new(n);
+        let x: BigInteger = values.par_iter().product();
+        let gcd = accumulator.member_product(None)?.bezouts_coefficients(&x);
+        if gcd.value != BigInteger::from(1u32) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "some values are in the accumulator",
+            ));
+        }
+        // The PoKE2 needs a positive exponent. a' = a mod x and B' = B A^((a' - a) / x)
+        // satisfy A^a' = B'^x g the same as a and B
+        let b = accumulator.fixed_exp_inv(&accumulator.generator, &gcd.b);
+        let a = &(&(&gcd.a % &x) + &x) % &x;
+        let k = &(&a - &gcd.a) / &x;
+        let b = f.mul(&b, &accumulator.fixed_exp(&accumulator.value, &k));
+        let v = accumulator.fixed_exp(&accumulator.value, &a);
+        let nonce = bound_nonce(accumulator.epoch, &[], &[], &[]);
+        let proof_v = Poke2Proof::new(&a, &accumulator.value, &v, n, &nonce)?;
+        let gv_inv = f.mul(&f.inv(&accumulator.generator)?, &v);
+        let proof = PoeProof::new(&b, &x, &gv_inv, n);
+        Ok(Self {
+            v,
+            b,
+            z: proof_v.z,
+            q: proof_v.q,
+            r: proof_v.r,
+            proof,
+        })
+    }
+
+    /// Check none of `values` are in `accumulator`
+    pub fn verify(&self, values: &[BigInteger], accumulator: &AccumulatorPublic) -> bool {
+        telemetry::verify("batch_non_membership", || {
+            if values.is_empty() || !all_prime(values) {
+                return false;
+            }
+            let n = &accumulator.modulus;
+            if !in_group(&[&self.v, &self.b], n) {
+                return false;
+            }
+            let f = Field::new(n);
+            let gv_inv = match f.inv(&accumulator.generator) {
+                Ok(g_inv) => f.mul(&g_inv, &self.v),
+                Err(_) => return false,
+            };
+            // The base is the current value so the proof fails once it changes
+            let proof_v = Poke2Proof {
+                u: accumulator.value.clone(),
+                z: self.z.clone(),
+                q: self.q.clone(),
+                r: self.r.clone(),
+                size: ProofSize::Standard,
+            };
+            let x: BigInteger = values.par_iter().product();
+            proof_v.verify(&self.v, n, bound_nonce(accumulator.epoch, &[], &[], &[]))
+                && self.proof.verify(&self.b, &x, &gv_inv, n)
+        })
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Header::new(Tag::BatchNonMembershipProof).to_bytes();
+        for e in &[&self.v, &self.b, &self.z, &self.q] {
+            output.append(&mut b2fa(e, FACTOR_SIZE * 2)?);
+        }
+        output.append(&mut b2fa(&self.r, MEMBER_SIZE)?);
+        output.append(&mut self.proof.to_bytes()?);
+        Ok(output)
+    }
+
+    /// Write the same bytes as `to_bytes` to `w`
+    #[cfg(feature = "std")]
+    pub fn write_bytes<W: Write>(&self, mut w: W) -> Result<(), AccumulatorError> {
+        Header::new(Tag::BatchNonMembershipProof).write_to(&mut w)?;
+        for e in &[&self.v, &self.b, &self.z, &self.q] {
+            write_element(&mut w, e, FACTOR_SIZE * 2)?;
+        }
+        write_element(&mut w, &self.r, MEMBER_SIZE)?;
+        self.proof.write_bytes(w)
+    }
+}
+
+impl TryFrom<&[u8]> for BatchNonMembershipProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::BatchNonMembershipProof)?;
+        if data.len() != Self::SIZE_BYTES {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: Self::SIZE_BYTES,
+                actual: data.len(),
+            }
+            .into());
+        }
+        let size = FACTOR_SIZE * 2;
+        let r_end = size * 4 + MEMBER_SIZE;
+        Ok(Self {
+            v: proof_element(&data[..size])?,
+            b: proof_element(&data[size..(size * 2)])?,
+            z: proof_element(&data[(size * 2)..(size * 3)])?,
+            q: proof_element(&data[(size * 3)..(size * 4)])?,
+            r: BigInteger::try_from(&data[(size * 4)..r_end])?,
+            proof: PoeProof::try_from(&data[r_end..])?,
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for BatchNonMembershipProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+serdes_impl!(BatchNonMembershipProof);
+hash_ord_impl!(BatchNonMembershipProof; v, b, z, q, r, proof);
+hex_impl!(BatchNonMembershipProof);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::AccumulatorSecretKey;
+
+    fn primes(range: core::ops::Range<u64>) -> Vec<BigInteger> {
+        range.map(|i| hash_to_prime(i.to_be_bytes())).collect()
+    }
+
+    #[test]
+    fn poe() {
+        let n = &BigInteger::from(1_000_000_007u32) * &BigInteger::from(998_244_353u32);
+        let u = BigInteger::from(5u32);
+        let x = primes(0..20).iter().product();
+        let w = u.mod_exp(&x, &n);
+        let proof = PoeProof::new(&u, &x, &w, &n);
+        assert!(proof.verify(&u, &x, &w, &n));
+        assert!(!proof.verify(&u, &(&x + &BigInteger::from(2u32)), &w, &n));
+        assert!(!proof.verify(&u, &x, &u, &n));
+        let proof = PoeProof::try_from(proof.to_bytes().unwrap().as_slice()).unwrap();
+        assert!(proof.verify(&u, &x, &w, &n));
+        // The modulus is bound into the challenge
+        let m = &n * &BigInteger::from(3u32);
+        assert!(!proof.verify(&u, &x, &w, &m));
+    }
+
+    #[test]
+    fn out_of_group() {
+        let key = AccumulatorSecretKey::default();
+        let genesis = Accumulator::with_prime_members(&key, &primes(0..4)).unwrap();
+        let state = genesis.public();
+        let x = genesis.members.iter().next().unwrap().clone();
+        let witness = MembershipWitness::new_prime(&genesis, &x).unwrap();
+        let block = BatchUpdate::new(&state, &primes(4..6), &[]).unwrap();
+        assert!(block.verify(&state));
+
+        let mut bad = block.clone();
+        bad.intermediate = &bad.intermediate + &state.modulus;
+        assert!(!bad.verify(&state));
+        let mut bad = block.clone();
+        bad.value = BigInteger::default();
+        assert!(!bad.verify(&state));
+
+        let mut bad_witness = witness.clone();
+        bad_witness.u = &witness.u + &state.modulus;
+        assert!(block.update_witness(&bad_witness, &state).is_err());
+        assert!(block.update_witness(&witness, &state).is_ok());
+    }
+
+    #[test]
+    fn blocks() {
+        let key = AccumulatorSecretKey::default();
+        let genesis = Accumulator::with_prime_members(&key, &primes(0..4)).unwrap();
+        let genesis_members: Vec<BigInteger> = genesis.members.iter().cloned().collect();
+        let mut state = genesis.public();
+        let mut witnesses: Vec<MembershipWitness> = genesis_members
+            .iter()
+            .map(|x| MembershipWitness::new_prime(&genesis, x).unwrap())
+            .collect();
+
+        // The first block only adds
+        let block = BatchUpdate::new(&state, &primes(4..7), &[]).unwrap();
+        let block = BatchUpdate::try_from(block.to_bytes().unwrap()).unwrap();
+        for w in witnesses.iter_mut() {
+            block.update_witness_assign(w, &state).unwrap();
+        }
+        for x in &block.additions {
+            witnesses.push(block.addition_witness(x, &state).unwrap());
+        }
+        state = block.apply(&state).unwrap();
+        assert_eq!(state.epoch, 1);
+
+        // The second block deletes two members and adds one
+        let deleted = vec![witnesses.remove(1), witnesses.remove(4)];
+        let block = BatchUpdate::new(&state, &primes(7..8), &deleted).unwrap();
+        assert!(block.update_witness(&deleted[0], &state).is_err());
+        for w in witnesses.iter_mut() {
+            block.update_witness_assign(w, &state).unwrap();
+        }
+        witnesses.push(block.addition_witness(&block.additions[0], &state).unwrap());
+        assert!(!block.verify(&genesis.public()));
+        state = block.apply(&state).unwrap();
+
+        // The result matches maintaining the whole set with the trapdoor
+        let remaining: Vec<BigInteger> = primes(0..8)
+            .into_iter()
+            .filter(|x| deleted.iter().all(|w| &w.x != x))
+            .collect();
+        let expected = Accumulator::with_prime_members(&key, &remaining).unwrap();
+        assert_eq!(state.value, expected.value);
+        for w in &witnesses {
+            assert_eq!(w.u, MembershipWitness::new_prime(&expected, &w.x).unwrap().u);
+        }
+
+        let members: Vec<BigInteger> = witnesses.iter().map(|w| w.x.clone()).collect();
+        let proof = AggregateMembershipProof::new(&witnesses, &state).unwrap();
+        let proof = AggregateMembershipProof::try_from(proof.to_bytes().unwrap()).unwrap();
+        assert!(proof.verify(&members, &state));
+        assert!(!proof.verify(&members[1..], &state));
+        assert!(!proof.verify(&[deleted[0].x.clone()], &state));
+        assert!(AggregateMembershipProof::new(&deleted, &state).is_err());
+    }
+
+    #[test]
+    fn aggregated_updates() {
+        let key = AccumulatorSecretKey::default();
+        let genesis = Accumulator::with_prime_members(&key, &primes(0..4)).unwrap();
+        let start = genesis.public();
+        let x = primes(0..1).remove(0);
+        let mut w = MembershipWitness::new_prime(&genesis, &x).unwrap();
+
+        // The second block deletes a member the first one added
+        let first = BatchUpdate::new(&start, &primes(4..6), &[]).unwrap();
+        let added = first.addition_witness(&first.additions[0], &start).unwrap();
+        first.update_witness_assign(&mut w, &start).unwrap();
+        let state = first.apply(&start).unwrap();
+        let second = BatchUpdate::new(&state, &primes(6..7), &[w, added]).unwrap();
+        let state = second.apply(&state).unwrap();
+        let third = BatchUpdate::new(&state, &primes(7..8), &[]).unwrap();
+        let end = third.apply(&state).unwrap();
+        let blocks = [first, second, third];
+
+        let proof = UpdateProof::aggregate(&start, &blocks).unwrap();
+        let proof = UpdateProof::try_from(proof.to_bytes().unwrap()).unwrap();
+        assert!(proof.verify(&start));
+        assert_eq!(proof.apply(&start).unwrap(), end);
+        let dropped = primes(4..5).remove(0);
+        let remaining: Vec<BigInteger> = primes(1..8).into_iter().filter(|p| p != &dropped).collect();
+        let expected = Accumulator::with_prime_members(&key, &remaining).unwrap();
+        assert_eq!(end.value, expected.value);
+
+        // Not from another state, with a member dropped or out of order
+        assert!(!proof.verify(&blocks[0].apply(&start).unwrap()));
+        let mut bad = proof.clone();
+        bad.additions.pop();
+        assert!(!bad.verify(&start));
+        let mut longer = proof.clone();
+        longer.blocks += 1;
+        assert!(!longer.verify(&start));
+        longer.blocks = u64::MAX;
+        assert!(!longer.verify(&start));
+        assert!(UpdateProof::aggregate(&start, &blocks[1..]).is_err());
+        assert!(UpdateProof::aggregate(&start, &[]).is_err());
+    }
+
+    #[test]
+    fn batch_non_membership() {
+        let key = AccumulatorSecretKey::default();
+        let acc = Accumulator::with_prime_members(&key, &primes(0..10)).unwrap();
+        let state = acc.public();
+        let values = primes(10..40);
+        let proof = BatchNonMembershipProof::new(&values, &acc).unwrap();
+        assert!(proof.verify(&values, &state));
+        assert!(!proof.verify(&values[1..], &state));
+        assert!(!proof.verify(&primes(0..30), &state));
+        let parsed = BatchNonMembershipProof::try_from(proof.to_bytes().unwrap()).unwrap();
+        assert_eq!(parsed, proof);
+        assert!(parsed.verify(&values, &state));
+        assert!(!proof.verify(&values, &acc.insert("other").unwrap().public()));
+
+        // Any member among the values fails
+        let mut with_member = values.clone();
+        with_member.push(primes(3..4)[0].clone());
+        assert!(BatchNonMembershipProof::new(&with_member, &acc).is_err());
+        assert!(BatchNonMembershipProof::new(&[], &acc).is_err());
+        assert!(BatchNonMembershipProof::new(&[BigInteger::from(9u32)], &acc).is_err());
+    }
+
+    #[test]
+    fn invalid_blocks() {
+        let key = AccumulatorSecretKey::default();
+        let acc = Accumulator::with_prime_members(&key, &primes(0..2)).unwrap();
+        let state = acc.public();
+        let x = acc.members.iter().next().unwrap().clone();
+        let w = MembershipWitness::new_prime(&acc, &x).unwrap();
+        assert!(BatchUpdate::new(&state, &[BigInteger::from(9u32)], &[]).is_err());
+        assert!(BatchUpdate::new(&state, core::slice::from_ref(&x), core::slice::from_ref(&w)).is_err());
+        assert!(BatchUpdate::new(&state, &[], &[w.clone(), w.clone()]).is_err());
+        let bad = MembershipWitness {
+            u: acc.generator.as_ref().clone(),
+            x,
+            freshness: w.freshness,
+        };
+        assert!(BatchUpdate::new(&state, &[], &[bad]).is_err());
+
+        let mut block = BatchUpdate::new(&state, &primes(2..3), &[w]).unwrap();
+        block.value = acc.generator.as_ref().clone();
+        assert!(block.apply(&state).is_err());
+    }
+
+    #[test]
+    fn public_audit() {
+        let key = AccumulatorSecretKey::default();
+        let mut acc = Accumulator::with_prime_members(&key, &primes(0..4)).unwrap();
+        let before = acc.public();
+        let members: Vec<BigInteger> = acc.members.iter().cloned().collect();
+        let (additions, deletions) = (primes(4..6), members[1..3].to_vec());
+        acc.update_batch_assign(&key, &additions, &deletions).unwrap();
+        let after = acc.public();
+        verify_transition(&before, &after, &additions, &deletions).unwrap();
+
+        // Any other claim fails
+        let fails = |additions: &[BigInteger], deletions: &[BigInteger]| {
+            verify_transition(&before, &after, additions, deletions).is_err()
+        };
+        assert!(fails(&additions[..1], &deletions));
+        assert!(fails(&additions, &deletions[..1]));
+        assert!(fails(&deletions, &additions));
+        assert!(fails(&[&additions[..], &members[..1]].concat(), &[&deletions[..], &members[..1]].concat()));
+        assert!(fails(&[additions[0].clone(), BigInteger::from(9u32)], &deletions));
+        assert!(verify_transition(&after, &before, &deletions, &additions).is_err());
+        let skipped = AccumulatorPublic { epoch: after.epoch + 1, ..after.clone() };
+        assert!(verify_transition(&before, &skipped, &additions, &deletions).is_err());
+
+        // Registry deltas are audited the same way
+        let mut registry = crate::registry::RevocationRegistry::new(&key);
+        registry.register("alice").unwrap();
+        registry.register("bob").unwrap();
+        registry.publish(&key).unwrap();
+        let definition = registry.definition();
+        registry.revoke("bob").unwrap();
+        registry.register("carol").unwrap();
+        let mut delta = registry.publish(&key).unwrap().unwrap().clone();
+        assert_eq!(delta.verify(&definition).unwrap(), registry.definition());
+        delta.deletions.clear();
+        assert!(delta.verify(&definition).is_err());
+    }
+}
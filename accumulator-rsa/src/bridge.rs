@@ -0,0 +1,241 @@
+// The RSA half of a proof that one hidden element is in this accumulator
+// and in an accumulator of another family, like the pairing accumulator
+// in `accumulator-ecc`, for moving registries between families.
+//
+// The member is proven with a branch of the federated proof, which commits
+// to it in the group of the registry and answers with s = k + cx over the
+// integers. The other proof blinds its element with k reduced into its
+// scalar field and answers the same challenge, so its response is s
+// reduced into the field. A verifier checking the responses agree knows
+// the element there is the member here reduced into the field. The
+// other family has to accumulate that reduction of `member_prime`
+use crate::{
+    accumulator::AccumulatorPublic,
+    federation::{Branch, Group, Opening, CHALLENGE_BITS},
+    format::{Header, Tag},
+    hash::hash_to_prime,
+    memwitness::MembershipWitness,
+    pow2, telemetry,
+};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use std::convert::TryFrom;
+
+/// The size of the challenge shared by both halves of a bridged proof
+pub const BRIDGE_CHALLENGE_BYTES: usize = CHALLENGE_BITS / 8;
+
+/// The prime this accumulator uses for `member`
+pub fn member_prime<B: AsRef<[u8]>>(member: B) -> BigInteger {
+    hash_to_prime(member)
+}
+
+/// The commit phase of the RSA half of a bridged proof
+pub struct BridgeCommitting {
+    branch: Branch,
+    opening: Opening,
+    first: Vec<BigInteger>,
+}
+
+impl BridgeCommitting {
+    /// Commit to the member of `witness` in `registry`
+    pub fn new(
+        registry: &AccumulatorPublic,
+        witness: &MembershipWitness,
+    ) -> Result<Self, AccumulatorError> {
+        Self::new_with_rng(registry, witness, &mut OsRng)
+    }
+
+    /// Same as `new` but the blinding values are drawn from `rng`
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        registry: &AccumulatorPublic,
+        witness: &MembershipWitness,
+        rng: &mut R,
+    ) -> Result<Self, AccumulatorError> {
+        let (branch, opening, first) = Group::new(registry)?.commit(witness, rng)?;
+        Ok(Self {
+            branch,
+            opening,
+            first,
+        })
+    }
+
+    /// The mask of the member. The other half blinds its element with
+    /// this reduced into its scalar field
+    pub fn member_blinding(&self) -> &BigInteger {
+        self.opening.member_mask()
+    }
+
+    /// The bytes to hash into the shared challenge
+    pub fn commitment(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut transcript = Vec::new();
+        self.branch.transcript(&self.first, &mut transcript)?;
+        Ok(transcript)
+    }
+
+    /// Answer `challenge`, a value of `BRIDGE_CHALLENGE_BYTES` the other
+    /// half answers as well
+    pub fn respond(self, challenge: &BigInteger) -> Result<BridgeProof, AccumulatorError> {
+        if challenge >= &pow2(CHALLENGE_BITS)? {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::ParameterMismatch,
+                "the challenge is larger than a bridge challenge",
+            ));
+        }
+        let mut branch = self.branch;
+        branch.respond(&self.opening, challenge.clone());
+        Ok(BridgeProof { branch })
+    }
+}
+
+/// The RSA half of a bridged proof
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct BridgeProof {
+    branch: Branch,
+}
+
+impl BridgeProof {
+    /// The number of bytes this serializes to without the header
+    pub const SIZE_BYTES: usize = Branch::SIZE_BYTES;
+
+    /// The challenge the proof answers
+    pub fn challenge(&self) -> &BigInteger {
+        self.branch.challenge()
+    }
+
+    /// k + cx over the integers, the response of the other half must be
+    /// this reduced into its scalar field
+    pub fn member_response(&self) -> &BigInteger {
+        self.branch.member_response()
+    }
+
+    /// The bytes the prover hashed into the challenge, recomputed from the
+    /// proof. The caller hashes them with the other half and compares the
+    /// result to `challenge`. Fails if the proof is malformed for `registry`
+    pub fn commitment(&self, registry: &AccumulatorPublic) -> Result<Vec<u8>, AccumulatorError> {
+        let first = Group::new(registry)?.check(&self.branch).ok_or_else(|| {
+            AccumulatorError::from_msg(
+                AccumulatorErrorKind::ProofVerificationFailed,
+                "bridge proof is not for the registry",
+            )
+        })?;
+        let mut transcript = Vec::new();
+        self.branch.transcript(&first, &mut transcript)?;
+        Ok(transcript)
+    }
+
+    /// Check `commitment` with the hash of the other half gives the challenge.
+    /// `challenge` is that hash reduced to `BRIDGE_CHALLENGE_BYTES`
+    pub fn verify<F>(&self, registry: &AccumulatorPublic, challenge: F) -> bool
+    where
+        F: FnOnce(&[u8]) -> BigInteger,
+    {
+        telemetry::verify("bridge", || match self.commitment(registry) {
+            Ok(transcript) => &challenge(&transcript) == self.challenge(),
+            Err(_) => false,
+        })
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Header::new(Tag::BridgeProof).to_bytes();
+        output.append(&mut self.branch.to_bytes()?);
+        Ok(output)
+    }
+}
+
+impl TryFrom<&[u8]> for BridgeProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::BridgeProof)?;
+        if data.len() != Self::SIZE_BYTES {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: Self::SIZE_BYTES,
+                actual: data.len(),
+            }
+            .into());
+        }
+        Ok(Self {
+            branch: Branch::parse(data)?,
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for BridgeProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+serdes_impl!(BridgeProof);
+hash_ord_impl!(BridgeProof; branch);
+hex_impl!(BridgeProof);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{accumulator::Accumulator, key::AccumulatorSecretKey};
+    use blake2::{Blake2b, Digest};
+
+    // The BLS12-381 scalar field the pairing accumulator works in
+    fn scalar_field() -> BigInteger {
+        BigInteger::from(
+            "52435875175126190479447740508185965837690552500527637822603658699938581184513",
+        )
+    }
+
+    // Stands in for the other half, a Schnorr response r + cy in the field
+    fn scalar_response(k: &BigInteger, y: &BigInteger, challenge: &BigInteger) -> BigInteger {
+        &(k + &(challenge * y)) % &scalar_field()
+    }
+
+    fn hash(transcript: &[u8]) -> BigInteger {
+        let digest = Blake2b::digest(transcript);
+        BigInteger::try_from(&digest[..BRIDGE_CHALLENGE_BYTES]).unwrap()
+    }
+
+    #[test]
+    fn links_responses() {
+        let key = AccumulatorSecretKey::default();
+        let acc = Accumulator::with_members(&key, ["a", "b", "c"]).unwrap();
+        let registry = acc.public();
+        let witness = MembershipWitness::new(&acc, "b").unwrap();
+        let element = member_prime("b");
+
+        let committing = BridgeCommitting::new(&registry, &witness).unwrap();
+        let blinding = committing.member_blinding().clone();
+        let challenge = hash(&committing.commitment().unwrap());
+        let proof = committing.respond(&challenge).unwrap();
+        assert!(proof.verify(&registry, hash));
+        let r = scalar_field();
+        assert_eq!(
+            scalar_response(&blinding, &element, &challenge),
+            proof.member_response() % &r
+        );
+        // Another element gives another response
+        let other = member_prime("a");
+        assert_ne!(
+            scalar_response(&blinding, &other, &challenge),
+            proof.member_response() % &r
+        );
+
+        let parsed = BridgeProof::try_from(proof.to_bytes().unwrap()).unwrap();
+        assert_eq!(parsed, proof);
+        assert!(parsed.verify(&registry, hash));
+
+        // Not for another registry value or a larger challenge
+        let mut moved = registry.clone();
+        moved.value = acc.insert("d").unwrap().value;
+        assert!(!proof.verify(&moved, hash));
+        let committing = BridgeCommitting::new(&registry, &witness).unwrap();
+        assert!(committing.respond(&pow2(CHALLENGE_BITS).unwrap()).is_err());
+        let single = Accumulator::with_members(&key, ["a"]).unwrap();
+        let other = MembershipWitness::new(&single, "a").unwrap();
+        assert!(BridgeCommitting::new(&registry, &other).is_err());
+    }
+}
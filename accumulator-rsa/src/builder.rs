@@ -0,0 +1,194 @@
+use crate::{
+    accumulator::Accumulator, hash::hash_to_prime, key::SecretKeyOps, store::MemberStore, FACTOR_SIZE,
+    MEMBER_PRIMALITY,
+};
+use common::{
+    bigint::{BigInteger, PrimalityTest},
+    error::{AccumulatorError, AccumulatorErrorKind},
+};
+use crate::par::*;
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use std::collections::BTreeSet;
+
+/// Configures and creates an accumulator.
+///
+/// Members can be added from several sources and are put in the storage
+/// before the accumulator value is computed. To run the work on a
+/// custom thread pool call `build` inside `in_pool`.
+///
+/// The modulus size and `hash_to_prime` are fixed when the crate is built
+/// so they aren't settings, `build` fails for keys of another size
+#[derive(Debug)]
+pub struct AccumulatorBuilder<S = BTreeSet<BigInteger>> {
+    store: S,
+    members: Vec<(BigInteger, Option<Vec<u8>>)>,
+    precompute: Option<usize>,
+    cache_product: bool,
+    primality: PrimalityTest,
+}
+
+impl AccumulatorBuilder {
+    /// A builder for an empty accumulator kept in memory
+    pub fn new() -> Self {
+        Self {
+            store: BTreeSet::new(),
+            members: Vec::new(),
+            precompute: None,
+            cache_product: false,
+            primality: MEMBER_PRIMALITY,
+        }
+    }
+}
+
+impl Default for AccumulatorBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: MemberStore> AccumulatorBuilder<S> {
+    /// Keep the members in `store`. Members already in it are accumulated too
+    pub fn storage<T: MemberStore>(self, store: T) -> AccumulatorBuilder<T> {
+        AccumulatorBuilder {
+            store,
+            members: self.members,
+            precompute: self.precompute,
+            cache_product: self.cache_product,
+            primality: self.primality,
+        }
+    }
+
+    /// Add `members`, each is hashed to a prime. The values are given
+    /// to the storage as preimages, see `PreimageStore`
+    pub fn members<I, B>(mut self, members: I) -> Self
+    where
+        I: IntoIterator<Item = B>,
+        B: AsRef<[u8]>,
+    {
+        let members: Vec<Vec<u8>> = members.into_iter().map(|b| b.as_ref().to_vec()).collect();
+        let primes: Vec<BigInteger> = members.par_iter().map(hash_to_prime).collect();
+        self.members.extend(primes.into_iter().zip(members.into_iter().map(Some)));
+        self
+    }
+
+    /// Add members that are already primes. `build` fails if one isn't
+    pub fn prime_members<I: IntoIterator<Item = BigInteger>>(mut self, members: I) -> Self {
+        self.members.extend(members.into_iter().map(|m| (m, None)));
+        self
+    }
+
+    /// Precompute fixed base tables with windows for `bits` bit exponents,
+    /// see `Accumulator::precompute`
+    pub fn precompute(mut self, bits: usize) -> Self {
+        self.precompute = Some(bits);
+        self
+    }
+
+    /// Cache the product of the members, see `Accumulator::cache_product`
+    pub fn cache_product(mut self) -> Self {
+        self.cache_product = true;
+        self
+    }
+
+    /// Test the members with `test` instead of `MEMBER_PRIMALITY`,
+    /// see `Accumulator::set_primality`
+    pub fn primality(mut self, test: PrimalityTest) -> Self {
+        self.primality = test;
+        self
+    }
+
+    /// Create the accumulator for `key`
+    pub fn build<K: SecretKeyOps>(self, key: &K) -> Result<Accumulator<S>, AccumulatorError> {
+        self.build_with_rng(key, &mut OsRng)
+    }
+
+    /// Create the accumulator for `key` with the generator sampled from `rng`
+    pub fn build_with_rng<K: SecretKeyOps, R: RngCore + CryptoRng>(
+        self,
+        key: &K,
+        rng: &mut R,
+    ) -> Result<Accumulator<S>, AccumulatorError> {
+        let bits = FACTOR_SIZE * 16;
+        let modulus_bits = key.modulus().bits();
+        if modulus_bits > bits || modulus_bits < bits - 1 {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::ParameterMismatch,
+                format!("this build uses {} bit moduli", bits),
+            ));
+        }
+        let primality = self.primality;
+        if self.members.par_iter().any(|(m, _)| !m.is_prime_with(primality)) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "Some values are not prime and cannot be added",
+            ));
+        }
+        let mut store = self.store;
+        for (m, preimage) in self.members {
+            match preimage {
+                Some(preimage) => store.insert_preimage(m, &preimage)?,
+                None => store.insert(m)?,
+            };
+        }
+        let mut accumulator = Accumulator::with_store_and_rng(key, store, rng)?;
+        accumulator.set_primality(primality);
+        if let Some(bits) = self.precompute {
+            accumulator.precompute(bits);
+        }
+        if self.cache_product {
+            accumulator.cache_product()?;
+        }
+        Ok(accumulator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{key::AccumulatorSecretKey, store::PreimageStore};
+
+    #[test]
+    fn build() {
+        let key = AccumulatorSecretKey::default();
+        let members = ["a", "b", "c"];
+        let acc = Accumulator::builder()
+            .members(&members[..2])
+            .prime_members(vec![hash_to_prime("c")])
+            .precompute(256)
+            .cache_product()
+            .build(&key)
+            .unwrap();
+        let expected = Accumulator::with_members(&key, members).unwrap();
+        assert_eq!(acc.value, expected.value);
+        assert_eq!(acc.fixed_bases().len(), 2);
+
+        let mut store = BTreeSet::new();
+        store.insert(hash_to_prime("a"));
+        let acc = Accumulator::builder()
+            .storage(store)
+            .members(&members[1..])
+            .build(&key)
+            .unwrap();
+        assert_eq!(acc.value, expected.value);
+
+        let acc = Accumulator::builder()
+            .storage(PreimageStore::new(BTreeSet::new()))
+            .members(members)
+            .build(&key)
+            .unwrap();
+        assert_eq!(acc.preimage(&hash_to_prime("b")).unwrap(), Some(b"b".to_vec()));
+        assert_eq!(acc.primality(), MEMBER_PRIMALITY);
+
+        let primality = PrimalityTest::MillerRabin(40);
+        let acc = Accumulator::builder().members(members).primality(primality).build(&key).unwrap();
+        assert_eq!(acc.value, expected.value);
+        assert_eq!(acc.insert("d").unwrap().primality(), primality);
+
+        let small = AccumulatorSecretKey::from_primes(BigInteger::from(23u32), BigInteger::from(47u32));
+        assert!(Accumulator::builder().build(&small).is_err());
+        assert!(Accumulator::builder()
+            .prime_members(vec![BigInteger::from(4u32)])
+            .build(&key)
+            .is_err());
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{
+    accumulator::Accumulator, key::SecretKeyOps, memwitness::MembershipWitness,
+    store::MemberStore,
+};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+};
+use std::collections::{BTreeMap, HashMap};
+
+/// A bounded cache of the witnesses an issuer has created, keyed by the
+/// member bytes.
+///
+/// Creating a witness takes a root of the accumulator value so popular
+/// members are worth keeping. Entries are for one accumulator state, when
+/// the epoch or value changes the cache is emptied on the next lookup.
+/// The least recently used entry is dropped when the cache is full
+#[derive(Debug, Clone)]
+pub struct WitnessCache {
+    capacity: usize,
+    state: Option<(u64, BigInteger)>,
+    entries: HashMap<Vec<u8>, (MembershipWitness, u64)>,
+    recent: BTreeMap<u64, Vec<u8>>,
+    tick: u64,
+}
+
+impl WitnessCache {
+    /// A cache holding at most `capacity` witnesses
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: None,
+            entries: HashMap::new(),
+            recent: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    /// The most witnesses kept
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of witnesses kept
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// True if no witnesses are kept
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Drop every witness
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recent.clear();
+    }
+
+    /// The cached witness for `member` if it was created at the current
+    /// state of `accumulator`
+    pub fn get<S, B: AsRef<[u8]>>(&mut self, accumulator: &Accumulator<S>, member: B) -> Option<MembershipWitness> {
+        self.sync(accumulator);
+        let tick = self.next_tick();
+        let (witness, used) = self.entries.get_mut(member.as_ref())?;
+        let key = self.recent.remove(used).expect("every entry is in the recent list");
+        *used = tick;
+        self.recent.insert(tick, key);
+        Some(witness.clone())
+    }
+
+    /// The witness for `member` at the current state of `accumulator`,
+    /// created with `key` and cached if it isn't already
+    pub fn witness<S, K, B>(
+        &mut self,
+        accumulator: &Accumulator<S>,
+        key: &K,
+        member: B,
+    ) -> Result<MembershipWitness, AccumulatorError>
+    where
+        S: MemberStore,
+        K: SecretKeyOps,
+        B: AsRef<[u8]>,
+    {
+        if let Some(witness) = self.get(accumulator, member.as_ref()) {
+            return Ok(witness);
+        }
+        let x = accumulator.hash_member(member.as_ref());
+        if !accumulator.members.contains(&x)? {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "value is not in the accumulator",
+            ));
+        }
+        let witness = MembershipWitness::with_prime_and_secret_key(accumulator, key, &x)?;
+        self.insert(member.as_ref().to_vec(), witness.clone());
+        Ok(witness)
+    }
+
+    /// Empty the cache if `accumulator` changed since the entries were made
+    fn sync<S>(&mut self, accumulator: &Accumulator<S>) {
+        let current = self
+            .state
+            .as_ref()
+            .is_some_and(|(epoch, value)| *epoch == accumulator.epoch && *value == accumulator.value);
+        if !current {
+            self.clear();
+            self.state = Some((accumulator.epoch, accumulator.value.clone()));
+        }
+    }
+
+    fn insert(&mut self, member: Vec<u8>, witness: MembershipWitness) {
+        if self.capacity == 0 {
+            return;
+        }
+        while self.entries.len() >= self.capacity {
+            let (_, oldest) = self.recent.pop_first().expect("a full cache has entries");
+            self.entries.remove(&oldest);
+        }
+        let tick = self.next_tick();
+        self.recent.insert(tick, member.clone());
+        self.entries.insert(member, (witness, tick));
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::AccumulatorSecretKey;
+
+    #[test]
+    fn evicts_and_invalidates() {
+        let key = AccumulatorSecretKey::default();
+        let mut acc = Accumulator::with_members(&key, ["a", "b", "c"]).unwrap();
+        let mut cache = WitnessCache::new(2);
+
+        let a = cache.witness(&acc, &key, "a").unwrap();
+        assert_eq!(a, MembershipWitness::new(&acc, "a").unwrap());
+        cache.witness(&acc, &key, "b").unwrap();
+        // "a" is used more recently than "b" so "b" is dropped for "c"
+        assert_eq!(cache.get(&acc, "a"), Some(a));
+        cache.witness(&acc, &key, "c").unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&acc, "b").is_none());
+        assert!(cache.get(&acc, "c").is_some());
+        assert!(cache.witness(&acc, &key, "d").is_err());
+
+        acc.insert_assign("d").unwrap();
+        assert!(cache.get(&acc, "a").is_none());
+        assert!(cache.is_empty());
+        let d = cache.witness(&acc, &key, "d").unwrap();
+        assert_eq!(d, MembershipWitness::new(&acc, "d").unwrap());
+
+        let mut none = WitnessCache::new(0);
+        none.witness(&acc, &key, "a").unwrap();
+        assert!(none.is_empty());
+    }
+}
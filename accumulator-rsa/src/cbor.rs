@@ -0,0 +1,441 @@
+use crate::{
+    accumulator::{check_parameters, Accumulator, AccumulatorPublic},
+    format::Tag,
+    key::AccumulatorSecretKey,
+    memproof::MembershipProof,
+    memwitness::{Freshness, MembershipWitness},
+    nonmemproof::NonMembershipProof,
+    nonwitness::NonMembershipWitness,
+    Poke2Proof, ProofSize, MEMBER_PRIMALITY,
+};
+use ciborium::value::{Integer, Value};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+};
+use core::convert::TryFrom;
+use std::{collections::BTreeSet, sync::Arc};
+
+/// CBOR tag for an unsigned bignum
+const POSITIVE_BIGNUM: u64 = 2;
+/// CBOR tag for a negative bignum
+const NEGATIVE_BIGNUM: u64 = 3;
+
+/// Canonical CBOR encoding of a wire type.
+///
+/// Every value is a map with unsigned integer keys in ascending order.
+/// Key 0 holds the value tag from `format::Tag` so one type is never decoded
+/// as another, the remaining keys are fixed per type and never reused.
+/// Integers use the shortest form: plain CBOR integers when they fit
+/// in 64 bits and bignums (tags 2 and 3) otherwise.
+/// Decoding rejects anything that is not in this canonical form.
+pub trait Cbor: Sized {
+    /// Encode as canonical CBOR
+    fn to_cbor(&self) -> Result<Vec<u8>, AccumulatorError>;
+
+    /// Decode from canonical CBOR
+    fn from_cbor(data: &[u8]) -> Result<Self, AccumulatorError>;
+}
+
+fn cbor_error<E: core::fmt::Debug>(e: E) -> AccumulatorError {
+    AccumulatorError::from_msg(AccumulatorErrorKind::SerializationError, format!("{:?}", e))
+}
+
+fn uint(v: u64) -> Value {
+    Value::Integer(v.into())
+}
+
+fn int(v: &BigInteger) -> Value {
+    let zero = BigInteger::default();
+    let negative = v < &zero;
+    // The negative bignum encoding stores -1 - v
+    let magnitude = if negative { &(&zero - v) - &BigInteger::from(1u32) } else { v.clone() };
+    let bytes = magnitude.to_bytes();
+    if bytes.len() <= 8 {
+        let mut be = [0u8; 8];
+        be[(8 - bytes.len())..].copy_from_slice(&bytes);
+        let n = u64::from_be_bytes(be);
+        let i = if negative { -1 - i128::from(n) } else { i128::from(n) };
+        // Always in range for a CBOR integer
+        Value::Integer(Integer::try_from(i).unwrap())
+    } else if negative {
+        Value::Tag(NEGATIVE_BIGNUM, Box::new(Value::Bytes(bytes)))
+    } else {
+        Value::Tag(POSITIVE_BIGNUM, Box::new(Value::Bytes(bytes)))
+    }
+}
+
+fn read_int(v: &Value) -> Result<BigInteger, AccumulatorError> {
+    let zero = BigInteger::default();
+    match v {
+        Value::Integer(i) => {
+            let i = i128::from(*i);
+            let magnitude = BigInteger::try_from(&i.unsigned_abs().to_be_bytes()[..])?;
+            Ok(if i < 0 { &zero - &magnitude } else { magnitude })
+        }
+        Value::Tag(POSITIVE_BIGNUM, b) => match b.as_ref() {
+            Value::Bytes(b) => BigInteger::try_from(b.as_slice()),
+            _ => Err(cbor_error("bignum is not a byte string")),
+        },
+        Value::Tag(NEGATIVE_BIGNUM, b) => match b.as_ref() {
+            Value::Bytes(b) => Ok(&(&zero - &BigInteger::try_from(b.as_slice())?) - &BigInteger::from(1u32)),
+            _ => Err(cbor_error("bignum is not a byte string")),
+        },
+        _ => Err(cbor_error("expected an integer")),
+    }
+}
+
+fn read_uint(v: &Value) -> Result<u64, AccumulatorError> {
+    match v {
+        Value::Integer(i) => u64::try_from(*i).map_err(cbor_error),
+        _ => Err(cbor_error("expected an unsigned integer")),
+    }
+}
+
+fn encode(tag: Tag, fields: Vec<Value>) -> Result<Vec<u8>, AccumulatorError> {
+    let mut out = Vec::new();
+    ciborium::ser::into_writer(&map(tag, fields), &mut out).map_err(cbor_error)?;
+    Ok(out)
+}
+
+fn map(tag: Tag, fields: Vec<Value>) -> Value {
+    let entries = core::iter::once(uint(tag as u64))
+        .chain(fields)
+        .enumerate()
+        .map(|(k, v)| (uint(k as u64), v))
+        .collect();
+    Value::Map(entries)
+}
+
+/// Check `value` is a map for `tag` with keys 0 to `count` in order and return the fields
+fn fields(value: Value, tag: Tag, count: usize) -> Result<Vec<Value>, AccumulatorError> {
+    let entries = match value {
+        Value::Map(entries) => entries,
+        _ => return Err(cbor_error("expected a map")),
+    };
+    if entries.len() != count + 1 {
+        return Err(AccumulatorErrorKind::InvalidLength {
+            expected: count + 1,
+            actual: entries.len(),
+        }
+        .into());
+    }
+    let mut fields = Vec::with_capacity(count);
+    for (i, (k, v)) in entries.into_iter().enumerate() {
+        if read_uint(&k)? != i as u64 {
+            return Err(cbor_error("unexpected map key"));
+        }
+        if i == 0 {
+            if read_uint(&v)? != tag as u64 {
+                return Err(cbor_error("unexpected value tag"));
+            }
+        } else {
+            fields.push(v);
+        }
+    }
+    Ok(fields)
+}
+
+/// Parse `data`, check it is canonical by encoding the result again
+fn decode<T: Cbor>(
+    data: &[u8],
+    tag: Tag,
+    count: usize,
+    f: impl FnOnce(Vec<Value>) -> Result<T, AccumulatorError>,
+) -> Result<T, AccumulatorError> {
+    let value: Value = ciborium::de::from_reader(data).map_err(cbor_error)?;
+    let result = f(fields(value, tag, count)?)?;
+    if result.to_cbor()?.as_slice() != data {
+        return Err(cbor_error("not canonical CBOR"));
+    }
+    Ok(result)
+}
+
+/// The proof elements followed by the format version of its size
+fn poke2_fields(p: &Poke2Proof) -> Vec<Value> {
+    vec![int(&p.u), int(&p.z), int(&p.q), int(&p.r), uint(p.size.version() as u64)]
+}
+
+fn read_poke2(f: &[Value]) -> Result<Poke2Proof, AccumulatorError> {
+    let version = u8::try_from(read_uint(&f[4])?).map_err(cbor_error)?;
+    Ok(Poke2Proof {
+        u: read_int(&f[0])?,
+        z: read_int(&f[1])?,
+        q: read_int(&f[2])?,
+        r: read_int(&f[3])?,
+        size: ProofSize::from_version(version)?,
+    })
+}
+
+fn freshness_fields(f: &Freshness) -> Vec<Value> {
+    vec![uint(f.epoch), Value::Bytes(f.value_hash.to_vec())]
+}
+
+fn read_freshness(f: &[Value]) -> Result<Freshness, AccumulatorError> {
+    let value_hash = match &f[1] {
+        Value::Bytes(h) if h.len() == Freshness::HASH_BYTES => *array_ref![h, 0, Freshness::HASH_BYTES],
+        _ => return Err(cbor_error("expected a byte string of 32 bytes")),
+    };
+    Ok(Freshness {
+        epoch: read_uint(&f[0])?,
+        value_hash,
+    })
+}
+
+impl Cbor for Accumulator {
+    fn to_cbor(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let members = self.members.iter().map(int).collect();
+        encode(
+            Tag::Accumulator,
+            vec![
+                int(&self.generator),
+                int(&self.modulus),
+                int(&self.value),
+                uint(self.epoch),
+                Value::Array(members),
+            ],
+        )
+    }
+
+    fn from_cbor(data: &[u8]) -> Result<Self, AccumulatorError> {
+        decode(data, Tag::Accumulator, 5, |f| {
+            let members = match &f[4] {
+                Value::Array(m) => m.iter().map(read_int).collect::<Result<BTreeSet<_>, _>>()?,
+                _ => return Err(cbor_error("expected an array")),
+            };
+            let generator = read_int(&f[0])?;
+            let modulus = read_int(&f[1])?;
+            let value = read_int(&f[2])?;
+            check_parameters(&generator, &value, &modulus)?;
+            Ok(Self {
+                generator: Arc::new(generator),
+                modulus: Arc::new(modulus),
+                value,
+                epoch: read_uint(&f[3])?,
+                members,
+                fixed_bases: Vec::new(),
+                product: None,
+                primality: MEMBER_PRIMALITY,
+            })
+        })
+    }
+}
+
+impl Cbor for AccumulatorPublic {
+    fn to_cbor(&self) -> Result<Vec<u8>, AccumulatorError> {
+        encode(
+            Tag::AccumulatorPublic,
+            vec![int(&self.generator), int(&self.modulus), int(&self.value), uint(self.epoch)],
+        )
+    }
+
+    fn from_cbor(data: &[u8]) -> Result<Self, AccumulatorError> {
+        decode(data, Tag::AccumulatorPublic, 4, |f| {
+            let public = Self {
+                generator: read_int(&f[0])?,
+                modulus: read_int(&f[1])?,
+                value: read_int(&f[2])?,
+                epoch: read_uint(&f[3])?,
+            };
+            check_parameters(&public.generator, &public.value, &public.modulus)?;
+            Ok(public)
+        })
+    }
+}
+
+impl Cbor for AccumulatorSecretKey {
+    fn to_cbor(&self) -> Result<Vec<u8>, AccumulatorError> {
+        encode(Tag::SecretKey, vec![int(&self.p), int(&self.q)])
+    }
+
+    fn from_cbor(data: &[u8]) -> Result<Self, AccumulatorError> {
+        decode(data, Tag::SecretKey, 2, |f| {
+            Ok(Self::from_primes(read_int(&f[0])?, read_int(&f[1])?))
+        })
+    }
+}
+
+impl Cbor for MembershipWitness {
+    fn to_cbor(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut fields = vec![int(&self.u), int(&self.x)];
+        fields.append(&mut freshness_fields(&self.freshness));
+        encode(Tag::MembershipWitness, fields)
+    }
+
+    fn from_cbor(data: &[u8]) -> Result<Self, AccumulatorError> {
+        decode(data, Tag::MembershipWitness, 4, |f| {
+            Ok(Self {
+                u: read_int(&f[0])?,
+                x: read_int(&f[1])?,
+                freshness: read_freshness(&f[2..])?,
+            })
+        })
+    }
+}
+
+impl Cbor for NonMembershipWitness {
+    fn to_cbor(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut fields = vec![int(&self.a), int(&self.b), int(&self.x)];
+        fields.append(&mut freshness_fields(&self.freshness));
+        encode(Tag::NonMembershipWitness, fields)
+    }
+
+    fn from_cbor(data: &[u8]) -> Result<Self, AccumulatorError> {
+        decode(data, Tag::NonMembershipWitness, 5, |f| {
+            Ok(Self {
+                a: read_int(&f[0])?,
+                b: read_int(&f[1])?,
+                x: read_int(&f[2])?,
+                freshness: read_freshness(&f[3..])?,
+            })
+        })
+    }
+}
+
+impl Cbor for MembershipProof {
+    fn to_cbor(&self) -> Result<Vec<u8>, AccumulatorError> {
+        encode(Tag::MembershipProof, poke2_fields(&self.0))
+    }
+
+    fn from_cbor(data: &[u8]) -> Result<Self, AccumulatorError> {
+        decode(data, Tag::MembershipProof, 5, |f| Ok(Self(read_poke2(&f)?)))
+    }
+}
+
+impl Cbor for NonMembershipProof {
+    fn to_cbor(&self) -> Result<Vec<u8>, AccumulatorError> {
+        encode(
+            Tag::NonMembershipProof,
+            vec![
+                int(&self.v),
+                int(&self.z),
+                int(&self.q),
+                int(&self.r),
+                Value::Array(poke2_fields(&self.proof_g)),
+            ],
+        )
+    }
+
+    fn from_cbor(data: &[u8]) -> Result<Self, AccumulatorError> {
+        decode(data, Tag::NonMembershipProof, 5, |f| {
+            let proof_g = match &f[4] {
+                Value::Array(p) if p.len() == 5 => read_poke2(p)?,
+                _ => return Err(cbor_error("expected an array of 5 integers")),
+            };
+            Ok(Self {
+                v: read_int(&f[0])?,
+                z: read_int(&f[1])?,
+                q: read_int(&f[2])?,
+                r: read_int(&f[3])?,
+                proof_g,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integers() {
+        for v in &["0", "1", "23", "24", "18446744073709551615", "18446744073709551616", "-1", "-18446744073709551616", "-18446744073709551617"] {
+            let b = BigInteger::from(*v);
+            assert_eq!(read_int(&int(&b)).unwrap(), b);
+        }
+        let mut out = Vec::new();
+        ciborium::ser::into_writer(&int(&BigInteger::from("18446744073709551616")), &mut out).unwrap();
+        assert_eq!(out, vec![0xc2, 0x49, 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+        out.clear();
+        ciborium::ser::into_writer(&int(&BigInteger::from("-24")), &mut out).unwrap();
+        assert_eq!(out, vec![0x37]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = vec![
+            3u64.to_be_bytes(),
+            7u64.to_be_bytes(),
+            11u64.to_be_bytes(),
+            13u64.to_be_bytes(),
+        ];
+        let acc = Accumulator::with_members(&key, &members).unwrap();
+        let data = acc.to_cbor().unwrap();
+        assert_eq!(Accumulator::from_cbor(&data).unwrap(), acc);
+        assert_eq!(AccumulatorPublic::from_cbor(&acc.public().to_cbor().unwrap()).unwrap(), acc.public());
+        assert_eq!(AccumulatorSecretKey::from_cbor(&key.to_cbor().unwrap()).unwrap(), key);
+
+        let witness = MembershipWitness::new(&acc, members[0]).unwrap();
+        assert_eq!(MembershipWitness::from_cbor(&witness.to_cbor().unwrap()).unwrap(), witness);
+        let proof = MembershipProof::new(&witness, &acc, b"cbor").unwrap();
+        let proof = MembershipProof::from_cbor(&proof.to_cbor().unwrap()).unwrap();
+        assert!(proof.verify(&acc, b"cbor"));
+
+        let witness = NonMembershipWitness::new(&acc, 17u64.to_be_bytes()).unwrap();
+        assert_eq!(NonMembershipWitness::from_cbor(&witness.to_cbor().unwrap()).unwrap(), witness);
+        let proof = NonMembershipProof::new(&witness, &acc, b"cbor").unwrap();
+        let proof = NonMembershipProof::from_cbor(&proof.to_cbor().unwrap()).unwrap();
+        assert!(proof.verify(&acc, b"cbor"));
+
+        // Compact proofs keep their size
+        let witness = MembershipWitness::new(&acc, members[1]).unwrap();
+        let compact = MembershipProof::new_compact(&witness, &acc, b"cbor").unwrap();
+        let copy = MembershipProof::from_cbor(&compact.to_cbor().unwrap()).unwrap();
+        assert_eq!(copy, compact);
+        assert!(copy.verify(&acc, b"cbor"));
+        let witness = NonMembershipWitness::new(&acc, 19u64.to_be_bytes()).unwrap();
+        let compact = NonMembershipProof::new_compact(&witness, &acc, b"cbor").unwrap();
+        let copy = NonMembershipProof::from_cbor(&compact.to_cbor().unwrap()).unwrap();
+        assert_eq!(copy.size(), ProofSize::Compact);
+        assert!(copy.verify(&acc, b"cbor"));
+
+        // Another type, trailing bytes and a non canonical length are rejected
+        assert!(MembershipWitness::from_cbor(&data).is_err());
+        let mut extra = data.clone();
+        extra.push(0);
+        assert!(Accumulator::from_cbor(&extra).is_err());
+        let mut long = vec![0xb9, 0x00, 0x06];
+        long.extend_from_slice(&data[1..]);
+        assert!(Accumulator::from_cbor(&long).is_err());
+    }
+
+    #[test]
+    fn serde_bytes() {
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<String> = (0..200).map(|i| i.to_string()).collect();
+        let acc = Accumulator::with_members(&key, &members).unwrap();
+        let witness = MembershipWitness::new(&acc, "0").unwrap();
+        let proof = MembershipProof::new(&witness, &acc, b"serde").unwrap();
+
+        // One byte string with a two byte length, not an array of integers
+        let mut out = Vec::new();
+        ciborium::ser::into_writer(&proof, &mut out).unwrap();
+        let bytes = proof.to_bytes().unwrap();
+        assert_eq!(out[..3], [0x59, (bytes.len() >> 8) as u8, bytes.len() as u8]);
+        assert_eq!(out[3..], bytes[..]);
+        assert_eq!(ciborium::de::from_reader::<MembershipProof, _>(out.as_slice()).unwrap(), proof);
+
+        // Larger than the reader's scratch buffer
+        let mut out = Vec::new();
+        ciborium::ser::into_writer(&acc, &mut out).unwrap();
+        assert!(out.len() > 4096);
+        assert_eq!(ciborium::de::from_reader::<Accumulator, _>(out.as_slice()).unwrap(), acc);
+
+        // Bytes written as a sequence of integers are still read
+        let seq = Value::Array(bytes.iter().map(|b| Value::from(*b)).collect());
+        let mut out = Vec::new();
+        ciborium::ser::into_writer(&seq, &mut out).unwrap();
+        assert_eq!(ciborium::de::from_reader::<MembershipProof, _>(out.as_slice()).unwrap(), proof);
+
+        for v in &["0", "-1", "18446744073709551617"] {
+            let b = BigInteger::from(*v);
+            let mut out = Vec::new();
+            ciborium::ser::into_writer(&b, &mut out).unwrap();
+            assert_eq!(out[0] >> 5, 2);
+            assert_eq!(ciborium::de::from_reader::<BigInteger, _>(out.as_slice()).unwrap(), b);
+        }
+        assert!(Value::Bytes(vec![2, 1]).deserialized::<BigInteger>().is_err());
+        assert!(Value::Bytes(vec![]).deserialized::<BigInteger>().is_err());
+    }
+}
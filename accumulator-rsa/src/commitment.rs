@@ -0,0 +1,174 @@
+// Pedersen commitments to the accumulator value on the Ristretto group so
+// a blockchain or other verifier short on space keeps 32 bytes instead of
+// the 256 byte value. The value is split into 31 byte chunks, each below
+// the group order, and chunk i is committed with generator G_i:
+//
+//   C = sum(m_i * G_i) + r * H
+//
+// The generators are hashed to the group so nobody knows a relation
+// between them. The opening is r, the holder of an opening sends it with
+// the value and the verifier recomputes C
+use crate::{b2fa, FACTOR_SIZE};
+use blake2::{digest::Digest, Blake2b};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+};
+use core::convert::TryFrom;
+use curve25519_dalek::{
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar,
+    traits::MultiscalarMul,
+};
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use rand::{CryptoRng, RngCore};
+
+/// Bytes of the value in each chunk, less than the group order
+const CHUNK_BYTES: usize = 31;
+/// The number of chunks of a value
+const CHUNKS: usize = (FACTOR_SIZE * 2).div_ceil(CHUNK_BYTES);
+
+/// A commitment to an accumulator value
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ValueCommitment(CompressedRistretto);
+
+impl ValueCommitment {
+    /// The number of bytes in a commitment
+    pub const BYTES: usize = 32;
+
+    /// The compressed point
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        self.0.to_bytes()
+    }
+}
+
+impl TryFrom<&[u8]> for ValueCommitment {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let point = CompressedRistretto::from_slice(data).map_err(|_| {
+            AccumulatorError::from(AccumulatorErrorKind::InvalidLength {
+                expected: Self::BYTES,
+                actual: data.len(),
+            })
+        })?;
+        if point.decompress().is_none() {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::SerializationError,
+                "not a Ristretto point",
+            ));
+        }
+        Ok(Self(point))
+    }
+}
+
+/// The blinding factor that opens a `ValueCommitment`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ValueOpening(Scalar);
+
+impl ValueOpening {
+    /// The number of bytes in an opening
+    pub const BYTES: usize = 32;
+
+    /// True if `commitment` is to `value` with this opening
+    pub fn verify(&self, commitment: &ValueCommitment, value: &BigInteger) -> bool {
+        match commit(value, &self.0) {
+            Ok(c) => c == *commitment,
+            Err(_) => false,
+        }
+    }
+
+    /// The canonical bytes of the blinding factor
+    pub fn to_bytes(&self) -> [u8; Self::BYTES] {
+        self.0.to_bytes()
+    }
+}
+
+impl TryFrom<&[u8]> for ValueOpening {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        if data.len() != Self::BYTES {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: Self::BYTES,
+                actual: data.len(),
+            }
+            .into());
+        }
+        Option::from(Scalar::from_canonical_bytes(*array_ref![data, 0, 32]))
+            .map(Self)
+            .ok_or_else(|| {
+                AccumulatorError::from_msg(AccumulatorErrorKind::SerializationError, "not a canonical scalar")
+            })
+    }
+}
+
+/// Commit to an accumulator `value` with a random opening
+#[cfg(feature = "std")]
+pub fn commit_value(value: &BigInteger) -> Result<(ValueCommitment, ValueOpening), AccumulatorError> {
+    commit_value_with_rng(value, &mut rand::rngs::OsRng)
+}
+
+/// Commit to an accumulator `value` with an opening from `rng`
+pub fn commit_value_with_rng<R: RngCore + CryptoRng>(
+    value: &BigInteger,
+    rng: &mut R,
+) -> Result<(ValueCommitment, ValueOpening), AccumulatorError> {
+    let mut wide = [0u8; 64];
+    rng.fill_bytes(&mut wide);
+    let blinding = Scalar::from_bytes_mod_order_wide(&wide);
+    Ok((commit(value, &blinding)?, ValueOpening(blinding)))
+}
+
+fn commit(value: &BigInteger, blinding: &Scalar) -> Result<ValueCommitment, AccumulatorError> {
+    let bytes = b2fa(value, FACTOR_SIZE * 2)?;
+    let mut scalars: Vec<Scalar> = bytes
+        .chunks(CHUNK_BYTES)
+        .map(|c| {
+            let mut s = [0u8; 32];
+            s[(32 - c.len())..].copy_from_slice(c);
+            s.reverse();
+            Scalar::from_bytes_mod_order(s)
+        })
+        .collect();
+    scalars.push(*blinding);
+    let generators = (0..=CHUNKS).map(generator);
+    Ok(ValueCommitment(RistrettoPoint::multiscalar_mul(scalars, generators).compress()))
+}
+
+/// Generator `i`, the last is `H`
+fn generator(i: usize) -> RistrettoPoint {
+    let mut h = Blake2b::new();
+    h.input(b"accumulator value commitment");
+    h.input((i as u32).to_be_bytes());
+    let digest = h.result();
+    RistrettoPoint::from_uniform_bytes(array_ref![digest, 0, 64])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{accumulator::Accumulator, key::AccumulatorSecretKey};
+
+    #[test]
+    fn opens() {
+        let key = AccumulatorSecretKey::default();
+        let acc = Accumulator::with_members(&key, ["a", "b"]).unwrap();
+        let (commitment, opening) = commit_value(&acc.value).unwrap();
+        assert!(opening.verify(&commitment, &acc.value));
+
+        let other = acc.insert("c").unwrap();
+        assert!(!opening.verify(&commitment, &other.value));
+        let (again, other_opening) = commit_value(&acc.value).unwrap();
+        assert_ne!(again, commitment);
+        assert!(!other_opening.verify(&commitment, &acc.value));
+
+        let commitment = ValueCommitment::try_from(&commitment.to_bytes()[..]).unwrap();
+        let opening = ValueOpening::try_from(&opening.to_bytes()[..]).unwrap();
+        assert!(opening.verify(&commitment, &acc.value));
+        assert!(ValueOpening::try_from(&[0xffu8; 32][..]).is_err());
+        assert!(ValueCommitment::try_from(&[0u8; 31][..]).is_err());
+        assert!(!opening.verify(&commitment, &BigInteger::from(vec![1u8; 300])));
+    }
+}
@@ -0,0 +1,354 @@
+// Proves the hidden member of a bridged membership proof was derived from
+// an input the holder committed to, so a holder can't prove membership
+// with a prime unrelated to its credential.
+//
+// Showing a hash was computed correctly needs a general circuit, so these
+// members are derived with an injective map a sigma proof can follow
+// instead of `hash_to_prime`. The input m is a `INPUT_BITS` long value,
+// like `derivation_input` of the credential data, and its prime is
+//
+//   x = 2^COUNTER_BITS m + j
+//
+// for the smallest odd j that gives a prime. The counter j is published
+// with the proof, it reveals no more about m than the gap to the next prime.
+// This is a different relation than `hash_to_prime`, members proven this
+// way must be added with `derive_prime`.
+//
+// The proof can't show j is the smallest counter, testing the smaller ones
+// needs m. The issuer enforces it instead: a member is only accumulated
+// after `check_derived`, so each input has one member in the accumulator
+// and a proof with any other counter has no witness.
+// The input is committed in the group of the registry as C = g^m h^ρ with
+// the generators of the federated proofs. The membership branch answers
+// s_x = k_x + cx, here t = g^k_x h^k_ρ is answered with s_ρ = k_ρ + c 2^T ρ
+// and the verifier checks
+//
+//   g^(s_x - cj) h^s_ρ = t (C^(2^T))^c
+//
+// which holds because C^(2^T) = g^(x - j) h^(2^T ρ). The challenge is
+// hashed from both so neither half can be swapped out
+use crate::{
+    accumulator::AccumulatorPublic,
+    b2fa,
+    bridge::{BridgeCommitting, BridgeProof},
+    federation::{Group, BLINDING_BITS, CHALLENGE_BITS, SLACK_BITS},
+    format::{Header, Tag, HEADER_SIZE},
+    in_group,
+    memwitness::MembershipWitness,
+    pow2, proof_element, random_below, telemetry, FACTOR_SIZE, MEMBER_PRIMALITY, MEMBER_SIZE_BITS,
+};
+use blake2::{Blake2b, Digest};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use std::convert::TryFrom;
+
+/// The bits of the counter appended to the input to find a prime
+pub const COUNTER_BITS: usize = 16;
+/// The size of an input, members are exactly `MEMBER_SIZE_BITS` long
+pub const INPUT_BITS: usize = MEMBER_SIZE_BITS - COUNTER_BITS;
+/// The bound on the mask of 2^T ρ
+const MASK_BITS: usize = BLINDING_BITS + COUNTER_BITS + CHALLENGE_BITS + SLACK_BITS;
+/// The size of the response for 2^T ρ
+const RESPONSE_BYTES: usize = (MASK_BITS + 1).div_ceil(8);
+
+/// The input for `data`, its hash cut to `INPUT_BITS` with the top bit set
+pub fn derivation_input<B: AsRef<[u8]>>(data: B) -> BigInteger {
+    let mut hash = Blake2b::digest(data.as_ref());
+    hash[0] |= 0x80;
+    BigInteger::try_from(&hash[..(INPUT_BITS / 8)]).unwrap()
+}
+
+/// The member prime of `input` and the counter that found it. Fails
+/// if `input` isn't exactly `INPUT_BITS` long
+pub fn derive_prime(input: &BigInteger) -> Result<(BigInteger, u16), AccumulatorError> {
+    if input.bits() != INPUT_BITS {
+        return Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::InvalidMemberSupplied,
+            "input is not INPUT_BITS long",
+        ));
+    }
+    let shifted = input * &pow2(COUNTER_BITS)?;
+    for j in (1..=u16::MAX).step_by(2) {
+        let x = &shifted + &BigInteger::from(j as u32);
+        if x.is_prime_with(MEMBER_PRIMALITY) {
+            return Ok((x, j));
+        }
+    }
+    // The gap between primes this size is hundreds on average
+    Err(AccumulatorError::from_msg(
+        AccumulatorErrorKind::InvalidMemberSupplied,
+        "no prime within the counter range",
+    ))
+}
+
+/// The counter of `x` if it's the prime `derive_prime` gives for `input`.
+/// Any other prime of the form `2^COUNTER_BITS input + j`, like one with a
+/// larger counter, is rejected. Issuers check members with this before
+/// accumulating them
+pub fn check_derived(input: &BigInteger, x: &BigInteger) -> Result<u16, AccumulatorError> {
+    let (derived, counter) = derive_prime(input)?;
+    if &derived != x {
+        return Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::InvalidMemberSupplied,
+            "the member is not the smallest prime derived from the input",
+        ));
+    }
+    Ok(counter)
+}
+
+/// A commitment g^m h^ρ to an input in the group of a registry
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct InputCommitment(pub BigInteger);
+
+impl InputCommitment {
+    /// Commit to `input` for `registry`, returns the commitment and ρ
+    pub fn new(registry: &AccumulatorPublic, input: &BigInteger) -> Result<(Self, BigInteger), AccumulatorError> {
+        Self::new_with_rng(registry, input, &mut OsRng)
+    }
+
+    /// Same as `new` but ρ is drawn from `rng`
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        registry: &AccumulatorPublic,
+        input: &BigInteger,
+        rng: &mut R,
+    ) -> Result<(Self, BigInteger), AccumulatorError> {
+        let blinding = random_below(&pow2(BLINDING_BITS)?, rng)?;
+        let commitment = Group::new(registry)?.pedersen(input, &blinding);
+        Ok((Self(commitment), blinding))
+    }
+
+    /// True if this commits to `input` with `blinding`
+    pub fn open(&self, registry: &AccumulatorPublic, input: &BigInteger, blinding: &BigInteger) -> bool {
+        match Group::new(registry) {
+            Ok(group) => group.pedersen(input, blinding) == self.0,
+            Err(_) => false,
+        }
+    }
+}
+
+/// A proof the member of a bridged membership proof is the prime
+/// derived from the input of an `InputCommitment`
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct DerivationProof {
+    bridge: BridgeProof,
+    counter: u16,
+    t: BigInteger,
+    response: BigInteger,
+}
+
+impl DerivationProof {
+    /// The number of bytes this serializes to without the header
+    pub const SIZE_BYTES: usize =
+        HEADER_SIZE + BridgeProof::SIZE_BYTES + 2 + 2 * FACTOR_SIZE + RESPONSE_BYTES;
+
+    /// Prove the member of `witness` in `registry` is derived from the
+    /// `input` of `commitment`, which was made with `blinding`
+    pub fn new<B: AsRef<[u8]>>(
+        registry: &AccumulatorPublic,
+        witness: &MembershipWitness,
+        commitment: &InputCommitment,
+        input: &BigInteger,
+        blinding: &BigInteger,
+        nonce: B,
+    ) -> Result<Self, AccumulatorError> {
+        Self::new_with_rng(registry, witness, commitment, input, blinding, nonce, &mut OsRng)
+    }
+
+    /// Same as `new` but the blinding values are drawn from `rng`
+    pub fn new_with_rng<B: AsRef<[u8]>, R: RngCore + CryptoRng>(
+        registry: &AccumulatorPublic,
+        witness: &MembershipWitness,
+        commitment: &InputCommitment,
+        input: &BigInteger,
+        blinding: &BigInteger,
+        nonce: B,
+        rng: &mut R,
+    ) -> Result<Self, AccumulatorError> {
+        let counter = check_derived(input, &witness.x)?;
+        if !commitment.open(registry, input, blinding) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "the member is not derived from the committed input",
+            ));
+        }
+        let group = Group::new(registry)?;
+        let bridge = BridgeCommitting::new_with_rng(registry, witness, rng)?;
+        let mask = random_below(&pow2(MASK_BITS)?, rng)?;
+        let t = group.pedersen(bridge.member_blinding(), &mask);
+        let c = challenge(&bridge.commitment()?, commitment, counter, &t, nonce.as_ref())?;
+        let response = &mask + &(&c * &(blinding * &pow2(COUNTER_BITS)?));
+        Ok(Self {
+            bridge: bridge.respond(&c)?,
+            counter,
+            t,
+            response,
+        })
+    }
+
+    /// The counter that found the member prime, public so the verifier
+    /// can remove it
+    pub fn counter(&self) -> u16 {
+        self.counter
+    }
+
+    /// Check the hidden member is in `registry` and derived from the
+    /// input of `commitment`
+    pub fn verify<B: AsRef<[u8]>>(&self, registry: &AccumulatorPublic, commitment: &InputCommitment, nonce: B) -> bool {
+        telemetry::verify("derivation", || {
+            if !in_group(&[&commitment.0, &self.t], &registry.modulus)
+                || self.counter & 1 == 0
+                || self.response.bits() > MASK_BITS + 1
+            {
+                return false;
+            }
+            let nonce = nonce.as_ref();
+            let bound = |transcript: &[u8]| {
+                challenge(transcript, commitment, self.counter, &self.t, nonce).unwrap_or_default()
+            };
+            if !self.bridge.verify(registry, bound) {
+                return false;
+            }
+            let group = match Group::new(registry) {
+                Ok(g) => g,
+                Err(_) => return false,
+            };
+            let c = self.bridge.challenge();
+            let cj = c * &BigInteger::from(self.counter as u32);
+            let s = self.bridge.member_response();
+            // x > j for an input of INPUT_BITS, a smaller response can't be honest
+            if s <= &cj {
+                return false;
+            }
+            let f = group.field();
+            let shifted = match pow2(COUNTER_BITS) {
+                Ok(p) => f.exp(&commitment.0, &p),
+                Err(_) => return false,
+            };
+            group.pedersen(&(s - &cj), &self.response) == f.mul(&self.t, &f.exp(&shifted, c))
+        })
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Header::new(Tag::DerivationProof).to_bytes();
+        output.append(&mut self.bridge.to_bytes()?);
+        output.extend_from_slice(&self.counter.to_be_bytes());
+        output.append(&mut b2fa(&self.t, 2 * FACTOR_SIZE)?);
+        output.append(&mut b2fa(&self.response, RESPONSE_BYTES)?);
+        Ok(output)
+    }
+}
+
+impl TryFrom<&[u8]> for DerivationProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::DerivationProof)?;
+        if data.len() != Self::SIZE_BYTES {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: Self::SIZE_BYTES,
+                actual: data.len(),
+            }
+            .into());
+        }
+        let (bridge, rest) = data.split_at(HEADER_SIZE + BridgeProof::SIZE_BYTES);
+        let (counter, rest) = rest.split_at(2);
+        let (t, response) = rest.split_at(2 * FACTOR_SIZE);
+        Ok(Self {
+            bridge: BridgeProof::try_from(bridge)?,
+            counter: u16::from_be_bytes(*array_ref![counter, 0, 2]),
+            t: proof_element(t)?,
+            response: BigInteger::try_from(response)?,
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for DerivationProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+serdes_impl!(DerivationProof);
+hash_ord_impl!(DerivationProof; bridge, counter, t, response);
+hex_impl!(DerivationProof);
+
+/// The challenge over the membership branch, the commitment and `t`
+fn challenge(
+    bridge: &[u8],
+    commitment: &InputCommitment,
+    counter: u16,
+    t: &BigInteger,
+    nonce: &[u8],
+) -> Result<BigInteger, AccumulatorError> {
+    let mut transcript = b"accumulator input derivation".to_vec();
+    transcript.extend_from_slice(bridge);
+    transcript.append(&mut b2fa(&commitment.0, 2 * FACTOR_SIZE)?);
+    transcript.extend_from_slice(&counter.to_be_bytes());
+    transcript.append(&mut b2fa(t, 2 * FACTOR_SIZE)?);
+    transcript.extend_from_slice(&(nonce.len() as u64).to_be_bytes());
+    transcript.extend_from_slice(nonce);
+    let digest = Blake2b::digest(transcript.as_slice());
+    BigInteger::try_from(&digest[..(CHALLENGE_BITS / 8)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{accumulator::Accumulator, key::AccumulatorSecretKey};
+
+    #[test]
+    fn derived_members() {
+        let key = AccumulatorSecretKey::default();
+        let inputs: Vec<BigInteger> = ["alice", "bob", "carol"].iter().map(derivation_input).collect();
+        let primes: Vec<BigInteger> = inputs.iter().map(|m| derive_prime(m).unwrap().0).collect();
+        assert!(primes.iter().all(|x| x.bits() == MEMBER_SIZE_BITS));
+        let acc = Accumulator::with_prime_members(&key, &primes).unwrap();
+        let registry = acc.public();
+        let witness = MembershipWitness::new_prime(&acc, &primes[1]).unwrap();
+
+        let (commitment, blinding) = InputCommitment::new(&registry, &inputs[1]).unwrap();
+        assert!(commitment.open(&registry, &inputs[1], &blinding));
+        let proof = DerivationProof::new(&registry, &witness, &commitment, &inputs[1], &blinding, b"nonce").unwrap();
+        assert_eq!(proof.counter(), derive_prime(&inputs[1]).unwrap().1);
+        assert!(proof.verify(&registry, &commitment, b"nonce"));
+        assert!(!proof.verify(&registry, &commitment, b"other"));
+        let parsed = DerivationProof::try_from(proof.to_bytes().unwrap()).unwrap();
+        assert_eq!(parsed, proof);
+        assert!(parsed.verify(&registry, &commitment, b"nonce"));
+
+        // The proof is only for the committed input
+        let (other, other_blinding) = InputCommitment::new(&registry, &inputs[0]).unwrap();
+        assert!(!proof.verify(&registry, &other, b"nonce"));
+        assert!(
+            DerivationProof::new(&registry, &witness, &other, &inputs[0], &other_blinding, b"nonce").is_err()
+        );
+        assert!(DerivationProof::new(&registry, &witness, &commitment, &inputs[1], &other_blinding, b"nonce").is_err());
+        let mut moved = proof.clone();
+        moved.counter += 2;
+        assert!(!moved.verify(&registry, &commitment, b"nonce"));
+        assert!(derive_prime(&BigInteger::from(7u32)).is_err());
+
+        // Only the smallest counter passes
+        let (x, counter) = derive_prime(&inputs[1]).unwrap();
+        assert_eq!(check_derived(&inputs[1], &x).unwrap(), counter);
+        let shifted = &inputs[1] * &pow2(COUNTER_BITS).unwrap();
+        let larger = (counter as u32 + 2..)
+            .step_by(2)
+            .map(|j| &shifted + &BigInteger::from(j))
+            .find(|x| x.is_prime_with(MEMBER_PRIMALITY))
+            .unwrap();
+        assert!(check_derived(&inputs[1], &larger).is_err());
+        assert!(check_derived(&inputs[0], &x).is_err());
+        let acc = Accumulator::with_prime_members(&key, core::slice::from_ref(&larger)).unwrap();
+        let witness = MembershipWitness::new_prime(&acc, &larger).unwrap();
+        let registry = acc.public();
+        let (commitment, blinding) = InputCommitment::new(&registry, &inputs[1]).unwrap();
+        assert!(DerivationProof::new(&registry, &witness, &commitment, &inputs[1], &blinding, b"nonce").is_err());
+    }
+}
@@ -0,0 +1,684 @@
+// Proves membership in one of several accumulators without saying which.
+//
+// The registries of a federation are run by different issuers with their
+// own moduli so the PoKE2 proofs of `memproof` can't be combined, and they
+// can't be simulated for a chosen challenge which an OR proof needs. Each
+// registry instead gets a branch of the proof from Camenisch and Lysyanskaya
+// "Dynamic Accumulators and Application to Efficient Revocation of Anonymous
+// Credentials" <https://eprint.iacr.org/2002/092> section 3.3 in its own
+// group. The branches are joined as in Cramer, Damgård and Schoenmakers
+// "Proofs of Partial Knowledge": the holder answers the branch of its
+// registry, simulates the others and the branch challenges must add up to
+// the Fiat-Shamir hash.
+//
+// A branch commits to the witness u as C = u h^r and to the member x as
+// X = g^x h^ρ_x and shows C^x h^-rx = A. The group order is unknown so the
+// responses are integers that hide the secrets statistically. Knowing such
+// an x only shows membership when x isn't 1, so the branch also shows
+// 2^T x - 2^(T + 1) = α^2 + β for α and β it commits to. This is Boudot's
+// range proof with tolerance from "Efficient Proofs that a Committed Number
+// Lies in an Interval", the bound on the response for β gives x > 1.
+// The generators are hashed from the modulus so only the issuer of a
+// registry could open the commitments two ways, and it could forge
+// membership in its own registry anyway
+use crate::{
+    accumulator::AccumulatorPublic,
+    b2fa,
+    format::{Header, Tag},
+    hash::hash_to_generator,
+    in_group,
+    memwitness::MembershipWitness,
+    pow2, proof_element, random_below, telemetry, FACTOR_SIZE, MEMBER_SIZE_BITS,
+};
+use blake2::{Blake2b, Digest};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+    Field,
+};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use std::convert::TryFrom;
+
+/// The size of the challenge of each branch
+pub(crate) const CHALLENGE_BITS: usize = 128;
+/// Responses are masked with this many bits more than the secret times
+/// the challenge, enough that they are statistically close to uniform
+pub(crate) const SLACK_BITS: usize = 80;
+/// The member is multiplied by 2^SHIFT_BITS so the tolerance of the range
+/// proof is less than one
+const SHIFT_BITS: usize = MEMBER_SIZE_BITS + 2 * (CHALLENGE_BITS + SLACK_BITS) + 32;
+/// The size of the commitment randomness
+pub(crate) const BLINDING_BITS: usize = 16 * FACTOR_SIZE + SLACK_BITS;
+/// The size of α, the integer square root of 2^T x - 2^(T + 1)
+const ROOT_BITS: usize = (SHIFT_BITS + MEMBER_SIZE_BITS) / 2 + 1;
+/// The bound on each secret of a branch in the order of the responses:
+/// x, rx, ρ_x, α, ρ_α, ρ_z, β and ρ_β
+const SECRET_BITS: [usize; 8] = [
+    MEMBER_SIZE_BITS,
+    BLINDING_BITS + MEMBER_SIZE_BITS,
+    BLINDING_BITS,
+    ROOT_BITS,
+    BLINDING_BITS,
+    BLINDING_BITS,
+    ROOT_BITS + 1,
+    SHIFT_BITS + BLINDING_BITS + 1,
+];
+
+/// The registries a federated proof is over, each the public parameters
+/// and value of an accumulator run by its own issuer
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Federation {
+    registries: Vec<AccumulatorPublic>,
+}
+
+impl Federation {
+    /// A federation of `registries` in the order proofs list them
+    pub fn new(registries: Vec<AccumulatorPublic>) -> Result<Self, AccumulatorError> {
+        if registries.is_empty() || registries.len() > u16::MAX as usize {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::ParameterMismatch,
+                "a federation has between 1 and 65535 registries",
+            ));
+        }
+        Ok(Self { registries })
+    }
+
+    /// The registries in the federation
+    pub fn registries(&self) -> &[AccumulatorPublic] {
+        self.registries.as_slice()
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Header::new(Tag::Federation).to_bytes();
+        output.extend_from_slice(&(self.registries.len() as u16).to_be_bytes());
+        for registry in &self.registries {
+            output.append(&mut registry.to_bytes()?);
+        }
+        Ok(output)
+    }
+}
+
+impl TryFrom<&[u8]> for Federation {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::Federation)?;
+        if data.len() < 2 {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: 2,
+                actual: data.len(),
+            }
+            .into());
+        }
+        let count = u16::from_be_bytes(*array_ref![data, 0, 2]) as usize;
+        let expected = 2 + count * AccumulatorPublic::SIZE_BYTES;
+        if data.len() != expected {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected,
+                actual: data.len(),
+            }
+            .into());
+        }
+        let registries = data[2..]
+            .chunks(AccumulatorPublic::SIZE_BYTES)
+            .map(AccumulatorPublic::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(registries)
+    }
+}
+
+impl TryFrom<Vec<u8>> for Federation {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+serdes_impl!(Federation);
+hash_ord_impl!(Federation; registries);
+
+/// A proof a member is in at least one registry of a `Federation` that
+/// doesn't reveal which. It has a branch for every registry so it grows
+/// with the size of the federation
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct FederatedMembershipProof {
+    branches: Vec<Branch>,
+}
+
+/// The part of a federated proof for one registry
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone)]
+pub(crate) struct Branch {
+    /// C = u h^r, the commitment to the witness
+    witness: BigInteger,
+    /// X = g^x h^ρ_x, the commitment to the member
+    member: BigInteger,
+    /// Y = g^α h^ρ_α
+    root: BigInteger,
+    /// Z = Y^α h^ρ_z, a commitment to α^2
+    square: BigInteger,
+    /// The challenge for this branch
+    challenge: BigInteger,
+    /// The responses in the order of `SECRET_BITS`
+    responses: Vec<BigInteger>,
+}
+
+impl Branch {
+    /// The number of bytes a branch serializes to
+    pub(crate) const SIZE_BYTES: usize = 8 * FACTOR_SIZE + CHALLENGE_BITS / 8 + responses_size();
+
+    /// The challenge this branch answers
+    pub(crate) fn challenge(&self) -> &BigInteger {
+        &self.challenge
+    }
+
+    /// k + cx, the response for the member over the integers
+    pub(crate) fn member_response(&self) -> &BigInteger {
+        &self.responses[0]
+    }
+
+    /// Fill in the responses of the holder's branch to `challenge`
+    pub(crate) fn respond(&mut self, opening: &Opening, challenge: BigInteger) {
+        self.responses = opening
+            .masks
+            .iter()
+            .zip(opening.secrets.iter())
+            .map(|(k, w)| k + &(&challenge * w))
+            .collect();
+        self.challenge = challenge;
+    }
+
+    /// Append the commitments and `first` messages to a Fiat-Shamir transcript
+    pub(crate) fn transcript(
+        &self,
+        first: &[BigInteger],
+        transcript: &mut Vec<u8>,
+    ) -> Result<(), AccumulatorError> {
+        for e in [&self.witness, &self.member, &self.root, &self.square]
+            .iter()
+            .copied()
+            .chain(first.iter())
+        {
+            transcript.append(&mut b2fa(e, 2 * FACTOR_SIZE)?);
+        }
+        Ok(())
+    }
+
+    /// Serialize this to bytes without a header
+    pub(crate) fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Vec::with_capacity(Self::SIZE_BYTES);
+        for e in &[&self.witness, &self.member, &self.root, &self.square] {
+            output.append(&mut b2fa(e, 2 * FACTOR_SIZE)?);
+        }
+        output.append(&mut b2fa(&self.challenge, CHALLENGE_BITS / 8)?);
+        for (i, s) in self.responses.iter().enumerate() {
+            output.append(&mut b2fa(s, response_size(i))?);
+        }
+        Ok(output)
+    }
+
+    /// Parse a branch of exactly `SIZE_BYTES`
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, AccumulatorError> {
+        let (elements, mut rest) = data.split_at(8 * FACTOR_SIZE);
+        let mut elements = elements.chunks(2 * FACTOR_SIZE).map(proof_element);
+        let mut next = |size: usize| {
+            let (value, r) = rest.split_at(size);
+            rest = r;
+            BigInteger::try_from(value)
+        };
+        let challenge = next(CHALLENGE_BITS / 8)?;
+        let responses = (0..SECRET_BITS.len())
+            .map(|i| next(response_size(i)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            witness: elements.next().expect("four elements")?,
+            member: elements.next().expect("four elements")?,
+            root: elements.next().expect("four elements")?,
+            square: elements.next().expect("four elements")?,
+            challenge,
+            responses,
+        })
+    }
+}
+
+/// The secrets of the holder's branch and the values masking them
+pub(crate) struct Opening {
+    secrets: Vec<BigInteger>,
+    masks: Vec<BigInteger>,
+}
+
+impl Opening {
+    /// The value masking the member in its response
+    pub(crate) fn member_mask(&self) -> &BigInteger {
+        &self.masks[0]
+    }
+}
+
+impl FederatedMembershipProof {
+    /// Prove the member of `witness` is in the registry at `index` of
+    /// `federation` without revealing `index`
+    pub fn new<B: AsRef<[u8]>>(
+        federation: &Federation,
+        index: usize,
+        witness: &MembershipWitness,
+        nonce: B,
+    ) -> Result<Self, AccumulatorError> {
+        Self::new_with_rng(federation, index, witness, nonce, &mut OsRng)
+    }
+
+    /// Same as `new` but the blinding values are drawn from `rng`
+    pub fn new_with_rng<B: AsRef<[u8]>, R: RngCore + CryptoRng>(
+        federation: &Federation,
+        index: usize,
+        witness: &MembershipWitness,
+        nonce: B,
+        rng: &mut R,
+    ) -> Result<Self, AccumulatorError> {
+        let registry = federation.registries.get(index).ok_or_else(|| {
+            AccumulatorError::from_msg(
+                AccumulatorErrorKind::ParameterMismatch,
+                "the federation has no registry at the index",
+            )
+        })?;
+        // Fail before anything is simulated
+        Group::new(registry)?.check_witness(witness)?;
+
+        let mut branches = Vec::with_capacity(federation.registries.len());
+        let mut commitments = Vec::with_capacity(federation.registries.len());
+        let mut opening = None;
+        for (i, registry) in federation.registries.iter().enumerate() {
+            let group = Group::new(registry)?;
+            if i == index {
+                let (branch, o, t) = group.commit(witness, rng)?;
+                branches.push(branch);
+                commitments.push(t);
+                opening = Some(o);
+            } else {
+                let branch = group.simulate(rng)?;
+                commitments.push(group.commitments(&branch)?);
+                branches.push(branch);
+            }
+        }
+        let opening = opening.expect("the index is in the federation");
+
+        // The holder's challenge is whatever makes the sum the hash
+        let bound = pow2(CHALLENGE_BITS)?;
+        let c = challenge(federation, nonce.as_ref(), &branches, &commitments)?;
+        let simulated = branches
+            .iter()
+            .fold(BigInteger::default(), |sum, b| &sum + &b.challenge);
+        let c = &(&(&c + &bound) - &(&simulated % &bound)) % &bound;
+        branches[index].respond(&opening, c);
+        Ok(Self { branches })
+    }
+
+    /// Check the proof shows membership in one of the registries of `federation`
+    pub fn verify<B: AsRef<[u8]>>(&self, federation: &Federation, nonce: B) -> bool {
+        telemetry::verify("federated_membership", || {
+            if self.branches.len() != federation.registries.len() {
+                return false;
+            }
+            let mut commitments = Vec::with_capacity(self.branches.len());
+            let mut sum = BigInteger::default();
+            for (branch, registry) in self.branches.iter().zip(federation.registries.iter()) {
+                match Group::new(registry).ok().and_then(|g| g.check(branch)) {
+                    Some(t) => commitments.push(t),
+                    None => return false,
+                }
+                sum = &sum + &branch.challenge;
+            }
+            match (pow2(CHALLENGE_BITS), challenge(federation, nonce.as_ref(), &self.branches, &commitments)) {
+                (Ok(bound), Ok(c)) => &sum % &bound == c,
+                _ => false,
+            }
+        })
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Header::new(Tag::FederatedMembershipProof).to_bytes();
+        output.extend_from_slice(&(self.branches.len() as u16).to_be_bytes());
+        for b in &self.branches {
+            output.append(&mut b.to_bytes()?);
+        }
+        Ok(output)
+    }
+}
+
+impl TryFrom<&[u8]> for FederatedMembershipProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::FederatedMembershipProof)?;
+        if data.len() < 2 {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: 2,
+                actual: data.len(),
+            }
+            .into());
+        }
+        let count = u16::from_be_bytes(*array_ref![data, 0, 2]) as usize;
+        let expected = 2 + count * Branch::SIZE_BYTES;
+        if count == 0 || data.len() != expected {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected,
+                actual: data.len(),
+            }
+            .into());
+        }
+        let branches = data[2..]
+            .chunks(Branch::SIZE_BYTES)
+            .map(Branch::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { branches })
+    }
+}
+
+impl TryFrom<Vec<u8>> for FederatedMembershipProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+serdes_impl!(FederatedMembershipProof);
+hash_ord_impl!(FederatedMembershipProof; branches);
+hex_impl!(FederatedMembershipProof);
+
+/// The group of one registry with the generators for the commitments
+pub(crate) struct Group {
+    f: Field,
+    n: BigInteger,
+    g: BigInteger,
+    h: BigInteger,
+    h_inv: BigInteger,
+    value: BigInteger,
+    value_inv: BigInteger,
+}
+
+impl Group {
+    pub(crate) fn new(registry: &AccumulatorPublic) -> Result<Self, AccumulatorError> {
+        let n = registry.modulus.clone();
+        let f = Field::new(&n);
+        let g = hash_to_generator(generator_seed(b"g", &n), &n)?;
+        let h = hash_to_generator(generator_seed(b"h", &n), &n)?;
+        let h_inv = f.inv(&h)?;
+        let value_inv = f.inv(&registry.value)?;
+        Ok(Self {
+            f,
+            n,
+            g,
+            h,
+            h_inv,
+            value: registry.value.clone(),
+            value_inv,
+        })
+    }
+
+    /// g^a h^b
+    pub(crate) fn pedersen(&self, a: &BigInteger, b: &BigInteger) -> BigInteger {
+        self.f.multi_exp(&[(&self.g, a), (&self.h, b)])
+    }
+
+    /// The group arithmetic
+    pub(crate) fn field(&self) -> &Field {
+        &self.f
+    }
+
+    /// Check `witness` is for the value of this registry
+    pub(crate) fn check_witness(&self, witness: &MembershipWitness) -> Result<(), AccumulatorError> {
+        if !in_group(&[&witness.u], &self.n) || self.f.exp(&witness.u, &witness.x) != self.value {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "witness is not for the registry",
+            ));
+        }
+        Ok(())
+    }
+
+    /// The holder's branch with its commitments, the challenge and
+    /// responses are filled in once the challenge is known
+    pub(crate) fn commit<R: RngCore + CryptoRng>(
+        &self,
+        witness: &MembershipWitness,
+        rng: &mut R,
+    ) -> Result<(Branch, Opening, Vec<BigInteger>), AccumulatorError> {
+        self.check_witness(witness)?;
+        let f = &self.f;
+        let x = &witness.x;
+        let shift = pow2(SHIFT_BITS)?;
+        let offset = &(&shift * x) - &pow2(SHIFT_BITS + 1)?;
+        let alpha = isqrt(&offset)?;
+        let beta = &offset - &(&alpha * &alpha);
+        let blinding = pow2(BLINDING_BITS)?;
+        // ρ_β = 2^T ρ_x - α ρ_α - ρ_z, it's only negative when ρ_x is tiny
+        let (r, rho_x, rho_a, rho_z, rho_b) = loop {
+            let r = random_below(&blinding, rng)?;
+            let rho_x = random_below(&blinding, rng)?;
+            let rho_a = random_below(&blinding, rng)?;
+            let rho_z = random_below(&blinding, rng)?;
+            let low = &(&alpha * &rho_a) + &rho_z;
+            let high = &shift * &rho_x;
+            if high >= low {
+                break (r, rho_x, rho_a, rho_z, &high - &low);
+            }
+        };
+        let root = f.multi_exp(&[(&self.g, &alpha), (&self.h, &rho_a)]);
+        let branch = Branch {
+            witness: f.mul(&witness.u, &f.exp(&self.h, &r)),
+            member: f.multi_exp(&[(&self.g, x), (&self.h, &rho_x)]),
+            square: f.multi_exp(&[(&root, &alpha), (&self.h, &rho_z)]),
+            root,
+            challenge: BigInteger::default(),
+            responses: Vec::new(),
+        };
+        let masks = SECRET_BITS
+            .iter()
+            .map(|b| random_below(&pow2(b + CHALLENGE_BITS + SLACK_BITS)?, rng))
+            .collect::<Result<Vec<BigInteger>, AccumulatorError>>()?;
+        let commitments = vec![
+            f.multi_exp(&[(&branch.witness, &masks[0]), (&self.h_inv, &masks[1])]),
+            f.multi_exp(&[(&self.g, &masks[0]), (&self.h, &masks[2])]),
+            f.multi_exp(&[(&self.g, &masks[3]), (&self.h, &masks[4])]),
+            f.multi_exp(&[(&branch.root, &masks[3]), (&self.h, &masks[5])]),
+            f.multi_exp(&[(&self.g, &masks[6]), (&self.h, &masks[7])]),
+        ];
+        let secrets = vec![x.clone(), &r * x, rho_x, alpha, rho_a, rho_z, beta, rho_b];
+        Ok((branch, Opening { secrets, masks }, commitments))
+    }
+
+    /// A branch for a registry the holder isn't in, random commitments,
+    /// challenge and responses look the same as a real branch
+    fn simulate<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Result<Branch, AccumulatorError> {
+        let mut residue = || loop {
+            let e = random_below(&self.n, rng)?.mod_sqr(&self.n);
+            if e > BigInteger::from(1u32) {
+                break Ok::<_, AccumulatorError>(e);
+            }
+        };
+        let (witness, member, root, square) = (residue()?, residue()?, residue()?, residue()?);
+        Ok(Branch {
+            witness,
+            member,
+            root,
+            square,
+            challenge: random_below(&pow2(CHALLENGE_BITS)?, rng)?,
+            responses: SECRET_BITS
+                .iter()
+                .map(|b| random_below(&pow2(b + CHALLENGE_BITS + SLACK_BITS)?, rng))
+                .collect::<Result<Vec<BigInteger>, AccumulatorError>>()?,
+        })
+    }
+
+    /// The commitments `branch` answers if its elements are in the group
+    /// and its challenge and responses are in range
+    pub(crate) fn check(&self, branch: &Branch) -> Option<Vec<BigInteger>> {
+        let elements = [&branch.witness, &branch.member, &branch.root, &branch.square];
+        if !in_group(&elements, &self.n)
+            || branch.challenge >= pow2(CHALLENGE_BITS).ok()?
+            || !in_range(&branch.responses)
+        {
+            return None;
+        }
+        self.commitments(branch).ok()
+    }
+
+    /// The commitments that a branch's challenge and responses answer.
+    /// The verifier recomputes them from the proof and the holder uses
+    /// them to simulate branches
+    fn commitments(&self, branch: &Branch) -> Result<Vec<BigInteger>, AccumulatorError> {
+        let f = &self.f;
+        let (s, c) = (&branch.responses, &branch.challenge);
+        let member_inv = f.inv(&branch.member)?;
+        let root_inv = f.inv(&branch.root)?;
+        let square_inv = f.inv(&branch.square)?;
+        // W = X^(2^T) g^-(2^(T + 1)) Z^-1 = g^β h^ρ_β
+        let w_inv = f.mul(
+            &f.multi_exp(&[(&member_inv, &pow2(SHIFT_BITS)?), (&self.g, &pow2(SHIFT_BITS + 1)?)]),
+            &branch.square,
+        );
+        Ok(vec![
+            f.multi_exp(&[(&branch.witness, &s[0]), (&self.h_inv, &s[1]), (&self.value_inv, c)]),
+            f.multi_exp(&[(&self.g, &s[0]), (&self.h, &s[2]), (&member_inv, c)]),
+            f.multi_exp(&[(&self.g, &s[3]), (&self.h, &s[4]), (&root_inv, c)]),
+            f.multi_exp(&[(&branch.root, &s[3]), (&self.h, &s[5]), (&square_inv, c)]),
+            f.multi_exp(&[(&self.g, &s[6]), (&self.h, &s[7]), (&w_inv, c)]),
+        ])
+    }
+}
+
+/// The Fiat-Shamir challenge over the federation, nonce and every branch
+fn challenge(
+    federation: &Federation,
+    nonce: &[u8],
+    branches: &[Branch],
+    commitments: &[Vec<BigInteger>],
+) -> Result<BigInteger, AccumulatorError> {
+    let mut transcript = b"accumulator federation".to_vec();
+    transcript.append(&mut federation.to_bytes()?);
+    transcript.extend_from_slice(&(nonce.len() as u64).to_be_bytes());
+    transcript.extend_from_slice(nonce);
+    for (b, t) in branches.iter().zip(commitments.iter()) {
+        b.transcript(t, &mut transcript)?;
+    }
+    let digest = Blake2b::digest(transcript.as_slice());
+    BigInteger::try_from(&digest[..(CHALLENGE_BITS / 8)])
+}
+
+/// The input hashed to the generator named `name` for modulus `n`
+fn generator_seed(name: &[u8], n: &BigInteger) -> Vec<u8> {
+    let mut seed = b"accumulator federation ".to_vec();
+    seed.extend_from_slice(name);
+    seed.append(&mut n.to_bytes());
+    seed
+}
+
+/// Responses can't be larger than the secret times the challenge plus
+/// the mask. The bounds on the responses for x and β are what make the
+/// range proof sound
+fn in_range(responses: &[BigInteger]) -> bool {
+    responses.len() == SECRET_BITS.len()
+        && responses
+            .iter()
+            .zip(SECRET_BITS.iter())
+            .all(|(s, b)| s.bits() <= b + CHALLENGE_BITS + SLACK_BITS + 1)
+}
+
+/// The number of bytes the `i`-th response of a branch serializes to
+const fn response_size(i: usize) -> usize {
+    (SECRET_BITS[i] + CHALLENGE_BITS + SLACK_BITS + 1).div_ceil(8)
+}
+
+/// The number of bytes all the responses of a branch serialize to
+const fn responses_size() -> usize {
+    let mut size = 0;
+    let mut i = 0;
+    while i < SECRET_BITS.len() {
+        size += response_size(i);
+        i += 1;
+    }
+    size
+}
+
+/// The integer square root of `n` by Newton's method
+fn isqrt(n: &BigInteger) -> Result<BigInteger, AccumulatorError> {
+    let two = BigInteger::from(2u32);
+    let mut x = pow2(n.bits() / 2 + 1)?;
+    loop {
+        let y = &(&x + &(n / &x)) / &two;
+        if y >= x {
+            return Ok(x);
+        }
+        x = y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        accumulator::Accumulator, hash::hash_to_prime, key::AccumulatorSecretKey,
+        memwitness::Freshness, MIN_SIZE_PRIME,
+    };
+
+    #[test]
+    fn proves_one_of_many() {
+        let key = AccumulatorSecretKey::default();
+        let first = Accumulator::with_members(&key, ["a", "b"]).unwrap();
+        let last = Accumulator::with_members(&key, ["e"]).unwrap();
+        // Another issuer with its own modulus, the test generator is only
+        // a residue for the test key so this one is hashed
+        let (p, _) = key.expose_factors();
+        let n = p * &BigInteger::generate_prime(MIN_SIZE_PRIME);
+        let g = hash_to_generator(b"other issuer", &n).unwrap();
+        let f = Field::new(&n);
+        let u = f.exp(&g, &hash_to_prime("c"));
+        let x = hash_to_prime("d");
+        let other = AccumulatorPublic {
+            value: f.exp(&u, &x),
+            generator: g,
+            modulus: n,
+            epoch: 2,
+        };
+        let witness = MembershipWitness {
+            u,
+            x,
+            freshness: Freshness::new(other.epoch, &other.value),
+        };
+        let federation = Federation::new(vec![first.public(), other, last.public()]).unwrap();
+        let proof = FederatedMembershipProof::new(&federation, 1, &witness, b"federation").unwrap();
+        assert!(proof.verify(&federation, b"federation"));
+        assert!(!proof.verify(&federation, b"other"));
+
+        let bytes = proof.to_bytes().unwrap();
+        let parsed = FederatedMembershipProof::try_from(bytes).unwrap();
+        assert_eq!(parsed, proof);
+        assert!(parsed.verify(&federation, b"federation"));
+        let parsed = Federation::try_from(federation.to_bytes().unwrap()).unwrap();
+        assert_eq!(parsed, federation);
+
+        // Not for another federation or a changed branch
+        let mut registries = federation.registries().to_vec();
+        registries[0].epoch += 1;
+        assert!(!proof.verify(&Federation::new(registries.clone()).unwrap(), b"federation"));
+        registries.pop();
+        assert!(!proof.verify(&Federation::new(registries).unwrap(), b"federation"));
+        let mut changed = proof.clone();
+        changed.branches[2].challenge = &changed.branches[2].challenge + &BigInteger::from(1u32);
+        assert!(!changed.verify(&federation, b"federation"));
+        let mut changed = proof.clone();
+        changed.branches.swap(0, 2);
+        assert!(!changed.verify(&federation, b"federation"));
+        // The responses are bounded, a large one for β would let x = 1 pass
+        let mut changed = proof.clone();
+        changed.branches[1].responses[6] = pow2(SECRET_BITS[6] + CHALLENGE_BITS + SLACK_BITS + 1).unwrap();
+        assert!(!changed.verify(&federation, b"federation"));
+
+        // The witness must be for the registry at the index
+        assert!(FederatedMembershipProof::new(&federation, 0, &witness, b"federation").is_err());
+        assert!(FederatedMembershipProof::new(&federation, 3, &witness, b"federation").is_err());
+        assert!(Federation::new(Vec::new()).is_err());
+    }
+}
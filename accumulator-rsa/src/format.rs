@@ -0,0 +1,565 @@
+use crate::{b2fa, ProofSize, FACTOR_SIZE, MEMBER_SIZE, MEMBER_SIZE_BITS};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+};
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+/// Every serialized value starts with these bytes
+pub const MAGIC: [u8; 4] = *b"RSAA";
+/// The serialization format version written by this crate
+pub const VERSION: u8 = 1;
+/// The format version of membership and non-membership proofs with
+/// compact challenges, see `ProofSize::Compact`
+pub const VERSION_COMPACT: u8 = 2;
+/// Identifies Blake2b-512 as the hash used for hash to prime and generators
+pub const HASH_BLAKE2B: u8 = 1;
+/// The number of bytes in a serialized header
+pub const HEADER_SIZE: usize = 11;
+
+/// The kind of value that follows a header
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Tag {
+    /// An accumulator
+    Accumulator = 1,
+    /// An accumulator secret key
+    SecretKey = 2,
+    /// A membership witness
+    MembershipWitness = 3,
+    /// A non-membership witness
+    NonMembershipWitness = 4,
+    /// A membership proof
+    MembershipProof = 5,
+    /// A non-membership proof
+    NonMembershipProof = 6,
+    /// The public parameters and value of an accumulator
+    AccumulatorPublic = 7,
+    /// The changes between two published revocation registry values
+    RegistryDelta = 8,
+    /// A proof a credential is not revoked
+    NonRevocationProof = 9,
+    /// A batch of additions and deletions with proofs they were applied
+    BatchUpdate = 10,
+    /// A proof that several values are all members
+    AggregateMembershipProof = 11,
+    /// A proof that one value is a member of two accumulators
+    LinkedMembershipProof = 12,
+    /// A proof that every member of one accumulator is in another
+    SubsetProof = 13,
+    /// The published value, epoch and parameters of an accumulator
+    AccumulatorState = 14,
+    /// An accumulator state signed by its issuer
+    SignedAccumulatorState = 15,
+    /// Precomputed tables and the member product of an accumulator
+    Precomputed = 16,
+    /// The public state and members of an accumulator to replay updates from
+    Checkpoint = 17,
+    /// A membership proof for one shard of a sharded accumulator
+    ShardedMembershipProof = 18,
+    /// A proof the modulus is the product of two primes known to the issuer
+    ModulusProof = 19,
+    /// The registries of a federation run by different issuers
+    Federation = 20,
+    /// A proof a value is a member of one registry of a federation
+    FederatedMembershipProof = 21,
+    /// The RSA half of a proof linking a member to another accumulator family
+    BridgeProof = 22,
+    /// One signed entry of an audit log
+    AuditEntry = 23,
+    /// The signed history of a registry
+    AuditLog = 24,
+    /// A proof covering several blocks of updates at once
+    UpdateProof = 25,
+    /// Witness update factors for many epochs
+    TailsFile = 26,
+    /// A proof a hidden member was derived from a committed input
+    DerivationProof = 27,
+    /// A proof several values are not members
+    BatchNonMembershipProof = 28,
+    /// A message of a witness update stream
+    UpdateMessage = 29,
+    /// The member primes of an accumulator exported for external processing
+    MemberPrimes = 30,
+}
+
+impl TryFrom<u8> for Tag {
+    type Error = AccumulatorError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Tag::Accumulator),
+            2 => Ok(Tag::SecretKey),
+            3 => Ok(Tag::MembershipWitness),
+            4 => Ok(Tag::NonMembershipWitness),
+            5 => Ok(Tag::MembershipProof),
+            6 => Ok(Tag::NonMembershipProof),
+            7 => Ok(Tag::AccumulatorPublic),
+            8 => Ok(Tag::RegistryDelta),
+            9 => Ok(Tag::NonRevocationProof),
+            10 => Ok(Tag::BatchUpdate),
+            11 => Ok(Tag::AggregateMembershipProof),
+            12 => Ok(Tag::LinkedMembershipProof),
+            13 => Ok(Tag::SubsetProof),
+            14 => Ok(Tag::AccumulatorState),
+            15 => Ok(Tag::SignedAccumulatorState),
+            16 => Ok(Tag::Precomputed),
+            17 => Ok(Tag::Checkpoint),
+            18 => Ok(Tag::ShardedMembershipProof),
+            19 => Ok(Tag::ModulusProof),
+            20 => Ok(Tag::Federation),
+            21 => Ok(Tag::FederatedMembershipProof),
+            22 => Ok(Tag::BridgeProof),
+            23 => Ok(Tag::AuditEntry),
+            24 => Ok(Tag::AuditLog),
+            25 => Ok(Tag::UpdateProof),
+            26 => Ok(Tag::TailsFile),
+            27 => Ok(Tag::DerivationProof),
+            28 => Ok(Tag::BatchNonMembershipProof),
+            29 => Ok(Tag::UpdateMessage),
+            30 => Ok(Tag::MemberPrimes),
+            _ => Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::SerializationError,
+                "unknown value tag",
+            )),
+        }
+    }
+}
+
+/// The prefix of every serialized value. Describes the format version,
+/// what kind of value follows and the parameters it was created with
+/// so data from another build configuration is rejected instead of misparsed.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Header {
+    /// The format version
+    pub version: u8,
+    /// The kind of value
+    pub tag: Tag,
+    /// The size of the modulus in bits
+    pub modulus_bits: u16,
+    /// The size of hashed members in bits
+    pub member_bits: u16,
+    /// The hash function identifier
+    pub hash_id: u8,
+}
+
+impl Header {
+    /// A header for `tag` with the parameters of this build
+    pub fn new(tag: Tag) -> Self {
+        Self {
+            version: VERSION,
+            tag,
+            modulus_bits: (FACTOR_SIZE * 16) as u16,
+            member_bits: MEMBER_SIZE_BITS as u16,
+            hash_id: HASH_BLAKE2B,
+        }
+    }
+
+    /// Read the header at the start of `data`
+    pub fn parse(data: &[u8]) -> Result<Self, AccumulatorError> {
+        if data.len() < HEADER_SIZE {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: HEADER_SIZE,
+                actual: data.len(),
+            }
+            .into());
+        }
+        if data[..4] != MAGIC {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::SerializationError,
+                "missing format magic",
+            ));
+        }
+        Ok(Self {
+            version: data[4],
+            tag: Tag::try_from(data[5])?,
+            modulus_bits: u16::from_be_bytes(*array_ref![data, 6, 2]),
+            member_bits: u16::from_be_bytes(*array_ref![data, 8, 2]),
+            hash_id: data[10],
+        })
+    }
+
+    /// A header for a proof with challenges of `size`
+    pub fn with_size(tag: Tag, size: ProofSize) -> Self {
+        Self {
+            version: size.version(),
+            ..Self::new(tag)
+        }
+    }
+
+    /// Check `data` starts with a header for `tag` that matches the
+    /// parameters of this build and return the bytes after it
+    pub(crate) fn check(data: &[u8], tag: Tag) -> Result<&[u8], AccumulatorError> {
+        Self::check_version(data, Self::new(tag))
+    }
+
+    /// Same as `check` for proofs that may have compact challenges,
+    /// also returns the size the version says
+    pub(crate) fn check_sized(data: &[u8], tag: Tag) -> Result<(&[u8], ProofSize), AccumulatorError> {
+        let size = ProofSize::from_version(Self::parse(data)?.version)?;
+        Ok((Self::check_version(data, Self::with_size(tag, size))?, size))
+    }
+
+    /// Check `data` starts with `expected`
+    fn check_version(data: &[u8], expected: Self) -> Result<&[u8], AccumulatorError> {
+        let tag = expected.tag;
+        let header = Self::parse(data)?;
+        if header.version != expected.version {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::SerializationError,
+                format_args!("unsupported format version {}", header.version),
+            ));
+        }
+        if header.tag != tag {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::SerializationError,
+                format_args!("expected {:?}, found {:?}", tag, header.tag),
+            ));
+        }
+        if header != expected {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::ParameterMismatch,
+                "modulus size, member size or hash differ from this build",
+            ));
+        }
+        Ok(&data[HEADER_SIZE..])
+    }
+
+    /// Serialize to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_array().to_vec()
+    }
+
+    /// Write the header to `w`
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, w: &mut W) -> Result<(), AccumulatorError> {
+        w.write_all(&self.to_array()).map_err(io_error)
+    }
+
+    fn to_array(self) -> [u8; HEADER_SIZE] {
+        let mut out = [0u8; HEADER_SIZE];
+        out[..4].copy_from_slice(&MAGIC);
+        out[4] = self.version;
+        out[5] = self.tag as u8;
+        out[6..8].copy_from_slice(&self.modulus_bits.to_be_bytes());
+        out[8..10].copy_from_slice(&self.member_bits.to_be_bytes());
+        out[10] = self.hash_id;
+        out
+    }
+}
+
+/// Write `b` to `w` as `size` big endian bytes like `b2fa` without
+/// building the padded copy
+#[cfg(feature = "std")]
+pub(crate) fn write_element<W: Write>(w: &mut W, b: &BigInteger, size: usize) -> Result<(), AccumulatorError> {
+    const ZEROS: [u8; 64] = [0u8; 64];
+    let bt = b.to_bytes();
+    if bt.len() > size {
+        return Err(AccumulatorErrorKind::InvalidLength {
+            expected: size,
+            actual: bt.len(),
+        }
+        .into());
+    }
+    let mut padding = size - bt.len();
+    while padding > 0 {
+        let n = padding.min(ZEROS.len());
+        w.write_all(&ZEROS[..n]).map_err(io_error)?;
+        padding -= n;
+    }
+    w.write_all(&bt).map_err(io_error)
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn io_error(e: std::io::Error) -> AccumulatorError {
+    AccumulatorError::from_source(AccumulatorErrorKind::SerializationError, e)
+}
+
+/// The most bytes a member delta varint can use
+const MAX_VARINT_SIZE: usize = MEMBER_SIZE_BITS.div_ceil(7);
+
+/// Member lists start with this byte when members are `MEMBER_SIZE` bytes each
+pub(crate) const MEMBERS_FIXED: u8 = 0;
+/// Member lists start with this byte when members after the first are deltas
+pub(crate) const MEMBERS_DELTA: u8 = 1;
+
+/// True if writing the sorted `members` as deltas is shorter than fixed size.
+/// Hashed members are spread over the whole range so their deltas only
+/// get short, and save space, for very large or dense sets
+pub(crate) fn use_member_deltas<I>(members: I) -> Result<bool, AccumulatorError>
+where
+    I: IntoIterator<Item = Result<BigInteger, AccumulatorError>>,
+{
+    let (mut fixed, mut deltas) = (0usize, 0usize);
+    let mut prev: Option<BigInteger> = None;
+    for m in members {
+        let m = m?;
+        fixed += MEMBER_SIZE;
+        deltas += match &prev {
+            None => MEMBER_SIZE,
+            Some(p) => core::cmp::max(1, (&m - p).bits().div_ceil(7)),
+        };
+        prev = Some(m);
+    }
+    Ok(deltas < fixed)
+}
+
+/// Append `member` to a member list in ascending order. Without `prev`
+/// the member is written as `MEMBER_SIZE` bytes, otherwise as a LEB128
+/// varint of its difference to `prev`
+pub(crate) fn encode_member(
+    prev: Option<&BigInteger>,
+    member: &BigInteger,
+    out: &mut Vec<u8>,
+) -> Result<(), AccumulatorError> {
+    let prev = match prev {
+        None => {
+            out.append(&mut b2fa(member, MEMBER_SIZE)?);
+            return Ok(());
+        }
+        Some(p) => p,
+    };
+    let delta = (member - prev).to_bytes();
+    let mut groups = Vec::with_capacity(MAX_VARINT_SIZE);
+    let (mut acc, mut bits) = (0u32, 0);
+    for b in delta.iter().rev() {
+        acc |= (*b as u32) << bits;
+        bits += 8;
+        while bits >= 7 {
+            groups.push((acc & 0x7f) as u8);
+            acc >>= 7;
+            bits -= 7;
+        }
+    }
+    groups.push(acc as u8);
+    while groups.len() > 1 && groups[groups.len() - 1] == 0 {
+        groups.pop();
+    }
+    let last = groups.len() - 1;
+    out.extend(groups.iter().enumerate().map(|(i, g)| if i < last { g | 0x80 } else { *g }));
+    Ok(())
+}
+
+/// Read the member written by `encode_member` with `prev`, taking bytes from `next`.
+/// Rejects deltas that are zero, not minimally encoded or overflow the member size
+pub(crate) fn decode_member<F>(prev: Option<&BigInteger>, mut next: F) -> Result<BigInteger, AccumulatorError>
+where
+    F: FnMut() -> Result<u8, AccumulatorError>,
+{
+    let prev = match prev {
+        None => {
+            let mut member = [0u8; MEMBER_SIZE];
+            for b in member.iter_mut() {
+                *b = next()?;
+            }
+            return BigInteger::try_from(&member[..]);
+        }
+        Some(p) => p,
+    };
+    let mut delta = Vec::with_capacity(MEMBER_SIZE + 1);
+    let (mut acc, mut bits) = (0u32, 0);
+    for i in 0.. {
+        if i == MAX_VARINT_SIZE {
+            return Err(member_error("member delta is too long"));
+        }
+        let b = next()?;
+        acc |= ((b & 0x7f) as u32) << bits;
+        bits += 7;
+        while bits >= 8 {
+            delta.push(acc as u8);
+            acc >>= 8;
+            bits -= 8;
+        }
+        if b & 0x80 == 0 {
+            if b == 0 && i > 0 {
+                return Err(member_error("member delta is not minimally encoded"));
+            }
+            break;
+        }
+    }
+    delta.push(acc as u8);
+    delta.reverse();
+    let delta = BigInteger::try_from(&delta[..])?;
+    if delta == BigInteger::default() {
+        return Err(member_error("members are not in ascending order"));
+    }
+    let member = prev + &delta;
+    if member.bits() > MEMBER_SIZE_BITS {
+        return Err(member_error("member is larger than the member size"));
+    }
+    Ok(member)
+}
+
+fn member_error(msg: &str) -> AccumulatorError {
+    AccumulatorError::from_msg(AccumulatorErrorKind::SerializationError, msg)
+}
+
+/// Append a u32 count followed by each of `members` as `MEMBER_SIZE` bytes
+pub(crate) fn write_members(members: &[BigInteger], out: &mut Vec<u8>) -> Result<(), AccumulatorError> {
+    out.extend_from_slice(&(members.len() as u32).to_be_bytes());
+    for m in members {
+        out.append(&mut b2fa(m, MEMBER_SIZE)?);
+    }
+    Ok(())
+}
+
+/// Read a list written by `write_members` starting at `offset`
+/// and move `offset` past it
+pub(crate) fn read_members(data: &[u8], offset: &mut usize) -> Result<Vec<BigInteger>, AccumulatorError> {
+    if data.len() < *offset + 4 {
+        return Err(AccumulatorErrorKind::InvalidLength {
+            expected: *offset + 4,
+            actual: data.len(),
+        }
+        .into());
+    }
+    let count = u32::from_be_bytes(*array_ref![data, *offset, 4]) as usize;
+    let start = *offset + 4;
+    let end = count
+        .checked_mul(MEMBER_SIZE)
+        .and_then(|n| n.checked_add(start))
+        .filter(|end| *end <= data.len())
+        .ok_or(AccumulatorErrorKind::InvalidLength {
+            expected: start.saturating_add(count.saturating_mul(MEMBER_SIZE)),
+            actual: data.len(),
+        })?;
+    *offset = end;
+    data[start..end].chunks(MEMBER_SIZE).map(BigInteger::try_from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn write_bytes() {
+        use crate::{
+            accumulator::Accumulator, batch::AggregateMembershipProof, key::AccumulatorSecretKey,
+            memproof::MembershipProof, memwitness::MembershipWitness, nonmemproof::NonMembershipProof,
+            nonwitness::NonMembershipWitness,
+        };
+        let key = AccumulatorSecretKey::default();
+        let acc = Accumulator::with_members(&key, ["a", "b", "c"]).unwrap();
+        let witness = MembershipWitness::new(&acc, "a").unwrap();
+        let non_witness = NonMembershipWitness::new(&acc, "d").unwrap();
+        let mut out = Vec::new();
+        macro_rules! same {
+            ($v:expr) => {
+                out.clear();
+                $v.write_bytes(&mut out).unwrap();
+                assert_eq!(out, $v.to_bytes().unwrap());
+            };
+        }
+        same!(acc);
+        same!(acc.public());
+        same!(witness);
+        same!(non_witness);
+        same!(MembershipProof::new(&witness, &acc, b"write").unwrap());
+        same!(MembershipProof::new_compact(&witness, &acc, b"write").unwrap());
+        same!(NonMembershipProof::new(&non_witness, &acc, b"write").unwrap());
+        same!(AggregateMembershipProof::new(core::slice::from_ref(&witness), &acc.public()).unwrap());
+
+        let proof = MembershipProof::new(&witness, &acc, b"write").unwrap();
+        assert!(proof.write_bytes(&mut [0u8; HEADER_SIZE + 1][..]).is_err());
+    }
+
+    #[test]
+    fn hex_strings() {
+        use crate::{
+            accumulator::{Accumulator, AccumulatorPublic},
+            key::AccumulatorSecretKey,
+            memproof::MembershipProof,
+            memwitness::MembershipWitness,
+        };
+        let key = AccumulatorSecretKey::default();
+        let acc = Accumulator::with_members(&key, ["a", "b"]).unwrap();
+        let public = acc.public();
+        let hex = public.to_hex().unwrap();
+        assert_eq!(hex.len(), 2 * AccumulatorPublic::SIZE_BYTES);
+        assert_eq!(hex.parse::<AccumulatorPublic>().unwrap(), public);
+        let padded = format!(" 0x{}\n", hex.to_uppercase());
+        assert_eq!(padded.parse::<AccumulatorPublic>().unwrap(), public);
+
+        let witness = MembershipWitness::new(&acc, "a").unwrap();
+        let proof = MembershipProof::new(&witness, &acc, b"hex").unwrap();
+        assert_eq!(witness.to_hex().unwrap().parse::<MembershipWitness>().unwrap(), witness);
+        assert_eq!(proof.to_hex().unwrap().parse::<MembershipProof>().unwrap(), proof);
+
+        let err = "0g".parse::<MembershipProof>().unwrap_err();
+        assert_eq!(err.kind(), AccumulatorErrorKind::SerializationError);
+        assert!(hex.parse::<MembershipProof>().is_err());
+        assert!(hex[1..].parse::<AccumulatorPublic>().is_err());
+    }
+
+    #[test]
+    fn header() {
+        let bytes = Header::new(Tag::MembershipProof).to_bytes();
+        assert_eq!(bytes.len(), HEADER_SIZE);
+        assert_eq!(Header::parse(&bytes).unwrap(), Header::new(Tag::MembershipProof));
+        assert!(Header::check(&bytes, Tag::MembershipProof).unwrap().is_empty());
+
+        let err = Header::check(&bytes, Tag::MembershipWitness).unwrap_err();
+        assert_eq!(err.kind(), AccumulatorErrorKind::SerializationError);
+
+        let mut other = bytes.clone();
+        other[4] = VERSION + 1;
+        assert_eq!(Header::check(&other, Tag::MembershipProof).unwrap_err().kind(), AccumulatorErrorKind::SerializationError);
+
+        let mut other = bytes.clone();
+        other[6..8].copy_from_slice(&3072u16.to_be_bytes());
+        assert_eq!(Header::check(&other, Tag::MembershipProof).unwrap_err().kind(), AccumulatorErrorKind::ParameterMismatch);
+
+        let mut other = bytes;
+        other[0] = 0;
+        assert_eq!(Header::check(&other, Tag::MembershipProof).unwrap_err().kind(), AccumulatorErrorKind::SerializationError);
+        assert!(Header::parse(&other[..3]).is_err());
+    }
+
+    fn decode(prev: &BigInteger, data: &[u8]) -> Result<BigInteger, AccumulatorError> {
+        let mut data = data.iter();
+        decode_member(Some(prev), || {
+            data.next().copied().ok_or_else(|| AccumulatorErrorKind::SerializationError.into())
+        })
+    }
+
+    #[test]
+    fn member_deltas() {
+        let prev = BigInteger::from(1000u32);
+        for delta in &["1", "127", "128", "300", "18446744073709551616"] {
+            let member = &prev + &BigInteger::from(*delta);
+            let mut out = Vec::new();
+            encode_member(Some(&prev), &member, &mut out).unwrap();
+            assert_eq!(decode(&prev, &out).unwrap(), member);
+        }
+        let mut out = Vec::new();
+        encode_member(Some(&prev), &BigInteger::from(1300u32), &mut out).unwrap();
+        assert_eq!(out, vec![0xac, 0x02]);
+
+        let mut out = Vec::new();
+        encode_member(None, &prev, &mut out).unwrap();
+        assert_eq!(out.len(), MEMBER_SIZE);
+        let mut data = out.into_iter();
+        assert_eq!(decode_member(None, || Ok(data.next().unwrap())).unwrap(), prev);
+
+        let dense: Vec<BigInteger> = (1000u32..1100).map(BigInteger::from).collect();
+        assert!(use_member_deltas(dense.into_iter().map(Ok)).unwrap());
+        let sparse = vec![
+            BigInteger::from(3u32),
+            BigInteger::from("1809251394333065553493296640760748560207343510400633813116524750123642650624"),
+        ];
+        assert!(!use_member_deltas(sparse.into_iter().map(Ok)).unwrap());
+
+        // zero delta, non-minimal, truncated, too long and overflowing
+        assert!(decode(&prev, &[0x00]).is_err());
+        assert!(decode(&prev, &[0x81, 0x00]).is_err());
+        assert!(decode(&prev, &[0x81]).is_err());
+        assert!(decode(&prev, &[0xff; MAX_VARINT_SIZE + 1]).is_err());
+        let mut max = vec![0xff; MAX_VARINT_SIZE - 1];
+        max.push(0x0f);
+        assert!(decode(&prev, &max).is_err());
+    }
+}
@@ -0,0 +1,305 @@
+use crate::{
+    accumulator::{check_parameters, Accumulator, AccumulatorPublic},
+    memproof::MembershipProof,
+    memwitness::{Freshness, MembershipWitness},
+    nonmemproof::NonMembershipProof,
+    nonwitness::NonMembershipWitness,
+    Poke2Proof, FACTOR_SIZE, MEMBER_SIZE,
+};
+use common::bigint::BigInteger;
+use core::convert::TryFrom;
+use std::collections::BTreeSet;
+
+// Values are drawn from bytes of the serialized sizes so they always
+// round trip but are otherwise unrelated, a proof will not verify
+// and a witness won't match any accumulator
+fn int(bytes: &[u8]) -> BigInteger {
+    BigInteger::try_from(bytes).expect("any bytes are an unsigned integer")
+}
+
+// Witness members are odd and their coefficient `a` is smaller than
+// the member, the low and top bits are set and `a` is reduced
+fn member(bytes: &[u8]) -> BigInteger {
+    let mut bytes = bytes.to_vec();
+    bytes[0] |= 0x80;
+    bytes[MEMBER_SIZE - 1] |= 1;
+    int(&bytes)
+}
+
+fn coefficient(a: &BigInteger, x: &BigInteger, negative: bool) -> BigInteger {
+    let a = a % x;
+    if negative {
+        &BigInteger::default() - &a
+    } else {
+        a
+    }
+}
+
+// Accumulator parameters are checked when parsed. The modulus gets its
+// top and low bits set, the generator is squared and the value reduced,
+// `None` for the few that still fail like a generator sharing a factor
+fn parameters(generator: &[u8], value: &[u8], modulus: &[u8]) -> Option<(BigInteger, BigInteger, BigInteger)> {
+    let mut modulus = modulus.to_vec();
+    modulus[0] |= 0x80;
+    modulus[2 * FACTOR_SIZE - 1] |= 1;
+    let modulus = int(&modulus);
+    let generator = int(generator).mod_sqr(&modulus);
+    let value = &int(value) % &modulus;
+    check_parameters(&generator, &value, &modulus).ok()?;
+    Some((generator, value, modulus))
+}
+
+#[cfg(feature = "arbitrary")]
+mod impls {
+    use super::*;
+    use arbitrary::{Arbitrary, Result, Unstructured};
+
+    fn int_of(u: &mut Unstructured<'_>, size: usize) -> Result<BigInteger> {
+        Ok(int(u.bytes(size)?))
+    }
+
+    impl<'a> Arbitrary<'a> for AccumulatorPublic {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            let generator = u.bytes(2 * FACTOR_SIZE)?.to_vec();
+            let value = u.bytes(2 * FACTOR_SIZE)?.to_vec();
+            let modulus = u.bytes(2 * FACTOR_SIZE)?;
+            let (generator, value, modulus) =
+                parameters(&generator, &value, modulus).ok_or(arbitrary::Error::IncorrectFormat)?;
+            Ok(Self {
+                generator,
+                modulus,
+                value,
+                epoch: u.arbitrary()?,
+            })
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for Accumulator {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            let public = AccumulatorPublic::arbitrary(u)?;
+            let mut members = BTreeSet::new();
+            for m in u.arbitrary_iter::<[u8; MEMBER_SIZE]>()? {
+                members.insert(int(&m?));
+            }
+            Ok(Self::from_parts(public, members))
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for MembershipWitness {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(Self {
+                u: int_of(u, 2 * FACTOR_SIZE)?,
+                x: member(u.bytes(MEMBER_SIZE)?),
+                freshness: Freshness::default(),
+            })
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for NonMembershipWitness {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            let a = int_of(u, 2 * FACTOR_SIZE)?;
+            let b = int_of(u, 2 * FACTOR_SIZE)?;
+            let x = member(u.bytes(MEMBER_SIZE)?);
+            Ok(Self {
+                a: coefficient(&a, &x, u.arbitrary()?),
+                b,
+                x,
+                freshness: Freshness::default(),
+            })
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for Poke2Proof {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(Self {
+                u: int_of(u, 2 * FACTOR_SIZE)?,
+                z: int_of(u, 2 * FACTOR_SIZE)?,
+                q: int_of(u, 2 * FACTOR_SIZE)?,
+                r: int_of(u, MEMBER_SIZE)?,
+                size: crate::ProofSize::Standard,
+            })
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for MembershipProof {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(Self(Poke2Proof::arbitrary(u)?))
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for NonMembershipProof {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(Self {
+                v: int_of(u, 2 * FACTOR_SIZE)?,
+                z: int_of(u, 2 * FACTOR_SIZE)?,
+                q: int_of(u, 2 * FACTOR_SIZE)?,
+                r: int_of(u, MEMBER_SIZE)?,
+                proof_g: Poke2Proof::arbitrary(u)?,
+            })
+        }
+    }
+}
+
+/// Proptest strategies for the serializable types
+#[cfg(feature = "proptest")]
+pub mod strategies {
+    use super::*;
+    use proptest::{collection::vec, prelude::*};
+
+    fn int_of(size: usize) -> impl Strategy<Value = BigInteger> {
+        vec(any::<u8>(), size).prop_map(|b| int(&b))
+    }
+
+    fn poke2_proof() -> impl Strategy<Value = Poke2Proof> {
+        (
+            int_of(2 * FACTOR_SIZE),
+            int_of(2 * FACTOR_SIZE),
+            int_of(2 * FACTOR_SIZE),
+            int_of(MEMBER_SIZE),
+        )
+            .prop_map(|(u, z, q, r)| Poke2Proof {
+                u,
+                z,
+                q,
+                r,
+                size: crate::ProofSize::Standard,
+            })
+    }
+
+    /// Any public accumulator state
+    pub fn accumulator_public() -> impl Strategy<Value = AccumulatorPublic> {
+        (
+            vec(any::<u8>(), 2 * FACTOR_SIZE),
+            vec(any::<u8>(), 2 * FACTOR_SIZE),
+            vec(any::<u8>(), 2 * FACTOR_SIZE),
+            any::<u64>(),
+        )
+            .prop_filter_map("parameters must pass the parsing checks", |(generator, value, modulus, epoch)| {
+                let (generator, value, modulus) = parameters(&generator, &value, &modulus)?;
+                Some(AccumulatorPublic {
+                    generator,
+                    modulus,
+                    value,
+                    epoch,
+                })
+            })
+    }
+
+    /// Any accumulator with up to `max_members` members
+    pub fn accumulator(max_members: usize) -> impl Strategy<Value = Accumulator> {
+        (accumulator_public(), vec(int_of(MEMBER_SIZE), 0..=max_members)).prop_map(|(public, members)| {
+            Accumulator::from_parts(public, members.into_iter().collect())
+        })
+    }
+
+    /// Any membership witness
+    pub fn membership_witness() -> impl Strategy<Value = MembershipWitness> {
+        (int_of(2 * FACTOR_SIZE), vec(any::<u8>(), MEMBER_SIZE))
+            .prop_map(|(u, x)| MembershipWitness {
+                u,
+                x: member(&x),
+                freshness: Freshness::default(),
+            })
+    }
+
+    /// Any non-membership witness
+    pub fn non_membership_witness() -> impl Strategy<Value = NonMembershipWitness> {
+        (
+            int_of(2 * FACTOR_SIZE),
+            int_of(2 * FACTOR_SIZE),
+            vec(any::<u8>(), MEMBER_SIZE),
+            any::<bool>(),
+        )
+            .prop_map(|(a, b, x, negative)| {
+                let x = member(&x);
+                NonMembershipWitness {
+                    a: coefficient(&a, &x, negative),
+                    b,
+                    x,
+                    freshness: Freshness::default(),
+                }
+            })
+    }
+
+    /// Any membership proof
+    pub fn membership_proof() -> impl Strategy<Value = MembershipProof> {
+        poke2_proof().prop_map(MembershipProof)
+    }
+
+    /// Any non-membership proof
+    pub fn non_membership_proof() -> impl Strategy<Value = NonMembershipProof> {
+        (
+            int_of(2 * FACTOR_SIZE),
+            int_of(2 * FACTOR_SIZE),
+            int_of(2 * FACTOR_SIZE),
+            int_of(MEMBER_SIZE),
+            poke2_proof(),
+        )
+            .prop_map(|(v, z, q, r, proof_g)| NonMembershipProof { v, z, q, r, proof_g })
+    }
+
+    /// `bytes` truncated, extended or with one byte changed,
+    /// to check deserializers reject malformed input without panicking
+    pub fn malformed(bytes: Vec<u8>) -> impl Strategy<Value = Vec<u8>> {
+        let len = bytes.len();
+        let truncated = {
+            let bytes = bytes.clone();
+            (0..len).prop_map(move |n| bytes[..n].to_vec())
+        };
+        let extended = {
+            let bytes = bytes.clone();
+            vec(any::<u8>(), 1..64).prop_map(move |extra| [bytes.as_slice(), extra.as_slice()].concat())
+        };
+        let flipped = (0..len, 1..=u8::MAX).prop_map(move |(i, mask)| {
+            let mut bytes = bytes.clone();
+            bytes[i] ^= mask;
+            bytes
+        });
+        prop_oneof![truncated, extended, flipped]
+    }
+}
+
+#[cfg(all(test, feature = "proptest"))]
+mod tests {
+    use super::strategies::*;
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn accumulator_round_trip(acc in accumulator(8)) {
+            prop_assert_eq!(Accumulator::try_from(acc.to_bytes().unwrap()).unwrap(), acc);
+        }
+
+        #[test]
+        fn witness_round_trip(w in membership_witness(), n in non_membership_witness()) {
+            prop_assert_eq!(MembershipWitness::try_from(w.to_bytes().unwrap().as_slice()).unwrap(), w);
+            prop_assert_eq!(NonMembershipWitness::try_from(n.to_bytes().unwrap().as_slice()).unwrap(), n);
+        }
+
+        #[test]
+        fn proof_round_trip(p in membership_proof(), n in non_membership_proof()) {
+            prop_assert_eq!(MembershipProof::try_from(p.to_bytes().unwrap().as_slice()).unwrap(), p);
+            prop_assert_eq!(NonMembershipProof::try_from(n.to_bytes().unwrap().as_slice()).unwrap(), n);
+        }
+
+        #[test]
+        fn malformed_proof(
+            bytes in membership_proof().prop_flat_map(|p| malformed(p.to_bytes().unwrap()))
+        ) {
+            // Flipping a value byte is still well formed, only the shape must be checked
+            if let Ok(p) = MembershipProof::try_from(bytes.as_slice()) {
+                prop_assert_eq!(p.to_bytes().unwrap(), bytes);
+            }
+        }
+
+        #[test]
+        fn malformed_accumulator(
+            bytes in accumulator(4).prop_flat_map(|a| malformed(a.to_bytes().unwrap()))
+        ) {
+            let _ = Accumulator::try_from(bytes.as_slice());
+        }
+    }
+}
@@ -1,13 +1,35 @@
+use crate::{telemetry, MEMBER_PRIMALITY, MEMBER_SIZE};
 use blake2::{Blake2b, Digest};
-use common::bigint::BigInteger;
+use common::{
+    bigint::{BigInteger, PrimalityTest},
+    error::*,
+};
 use hkdf::Hkdf;
-use std::convert::TryFrom;
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+use core::convert::TryFrom;
 
 /// Hashes `input` to a prime.
 /// See Section 7 in
 /// <https://eprint.iacr.org/2018/1188.pdf>
 pub(crate) fn hash_to_prime<B: AsRef<[u8]>>(input: B) -> BigInteger {
-    let mut input = input.as_ref().to_vec();
+    hash_to_prime_with(input, MEMBER_PRIMALITY)
+}
+
+/// Same as `hash_to_prime` with candidates checked by `test`.
+/// The prime is the same for any test that doesn't let a composite through
+pub(crate) fn hash_to_prime_with<B: AsRef<[u8]>>(input: B, test: PrimalityTest) -> BigInteger {
+    hash_to_prime_of(input.as_ref(), MEMBER_SIZE, test)
+}
+
+/// Same as `hash_to_prime` with a prime of at most `bytes` bytes, like
+/// the shorter challenges of compact proofs
+pub(crate) fn hash_to_prime_bytes<B: AsRef<[u8]>>(input: B, bytes: usize) -> BigInteger {
+    hash_to_prime_of(input.as_ref(), bytes, MEMBER_PRIMALITY)
+}
+
+fn hash_to_prime_of(input: &[u8], bytes: usize, test: PrimalityTest) -> BigInteger {
+    let mut input = input.to_vec();
     let mut i = 1usize;
     let offset = input.len();
     input.extend_from_slice(&i.to_be_bytes()[..]);
@@ -19,11 +41,12 @@ pub(crate) fn hash_to_prime<B: AsRef<[u8]>>(input: B) -> BigInteger {
         let mut hash = Blake2b::digest(input.as_slice());
         // Force it to be odd
         hash[63] |= 1;
-        // Only need 256 bits just borrow the bottom 32 bytes
-        // There should be plenty of primes below 2^256
+        // Only need `bytes` bytes just borrow the bottom of the hash
+        // There should be plenty of primes of that size
         // and we want this to be reasonably fast
-        num = BigInteger::try_from(&hash[32..]).unwrap();
-        if num.is_prime() {
+        num = BigInteger::try_from(&hash[(hash.len() - bytes)..]).unwrap();
+        if num.is_prime_with(test) {
+            telemetry::record(telemetry::HASH_TO_PRIME_ITERATIONS, i);
             break;
         }
         i += 1;
@@ -35,13 +58,29 @@ pub(crate) fn hash_to_prime<B: AsRef<[u8]>>(input: B) -> BigInteger {
 
 /// Hashes `input` to a member of group `n`
 /// that can be used as a generator `g`. `g` will be QR_N.
-pub(crate) fn hash_to_generator<B: AsRef<[u8]>>(input: B, n: &BigInteger) -> BigInteger {
+pub(crate) fn hash_to_generator<B: AsRef<[u8]>>(input: B, n: &BigInteger) -> Result<BigInteger, AccumulatorError> {
     let length = n.bits() / 8;
     let h = Hkdf::<Blake2b>::new(Some(b"RSA_ACCUMULATOR_HASH_TO_GENERATOR_"), input.as_ref());
     let mut okm = vec![0u8; length];
-    h.expand(b"", &mut okm).unwrap();
+    h.expand(b"", &mut okm).map_err(|_| {
+        AccumulatorError::from_msg(AccumulatorErrorKind::InvalidType, "modulus is too large to hash to a generator")
+    })?;
 
-    BigInteger::from(okm).mod_sqr(n)
+    Ok(BigInteger::from(okm).mod_sqr(n))
+}
+
+/// Derive a proof nonce from the accumulator `value` and `epoch`, the identity
+/// of the verifier and the commitment to the element the proof carries.
+/// Like RFC 6979 for signatures the nonce is fixed by its inputs instead
+/// of relying on the caller, so a proof is only accepted by the verifier
+/// and accumulator state it was made for and can't be replayed elsewhere
+pub fn derive_nonce(value: &BigInteger, epoch: u64, verifier_id: &[u8], commitment: &BigInteger) -> Vec<u8> {
+    let mut transcript = b"RSA_ACCUMULATOR_PROOF_NONCE_".to_vec();
+    for field in &[value.to_bytes().as_slice(), &epoch.to_be_bytes()[..], verifier_id, commitment.to_bytes().as_slice()] {
+        transcript.extend_from_slice(&(field.len() as u64).to_be_bytes());
+        transcript.extend_from_slice(field);
+    }
+    Blake2b::digest(transcript.as_slice()).to_vec()
 }
 
 #[cfg(test)]
@@ -61,6 +100,18 @@ mod tests {
             let t = hash_to_prime(&bytes);
             let n = Mpz::from(t.to_bytes().as_slice());
             assert!(n.probab_prime(15) != ProbabPrimeResult::NotPrime);
+            assert_eq!(hash_to_prime_with(&bytes, PrimalityTest::MillerRabin(15)), t);
         }
     }
+
+    #[test]
+    fn test_derive_nonce() {
+        let value = BigInteger::from(17u32);
+        let commitment = BigInteger::from(19u32);
+        let nonce = derive_nonce(&value, 1, b"verifier", &commitment);
+        assert_eq!(nonce, derive_nonce(&value, 1, b"verifier", &commitment));
+        assert_ne!(nonce, derive_nonce(&value, 2, b"verifier", &commitment));
+        assert_ne!(nonce, derive_nonce(&value, 1, b"verifier2", &commitment));
+        assert_ne!(nonce, derive_nonce(&commitment, 1, b"verifier", &value));
+    }
 }
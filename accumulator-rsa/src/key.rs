@@ -1,29 +1,166 @@
-use crate::{b2fa, FACTOR_SIZE};
+use crate::{
+    b2fa,
+    format::{Header, Tag},
+    store::product_tree,
+    FACTOR_SIZE,
+};
+#[cfg(feature = "std")]
+use crate::{pow2, random_below, MIN_SIZE_PRIME};
 use common::{
     bigint::BigInteger,
     error::{AccumulatorError, AccumulatorErrorKind},
 };
-#[cfg(not(test))]
-use rayon::prelude::*;
-use std::convert::TryFrom;
+#[cfg(all(feature = "std", not(test)))]
+use crate::par::*;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+#[cfg(feature = "std")]
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+#[cfg(feature = "std")]
+use std::sync::atomic::{AtomicBool, Ordering};
 use zeroize::Zeroize;
 
-/// Represents the safe primes used in the modulus for the accumulator
-#[derive(Debug, Eq, PartialEq)]
+/// Progress reported by `AccumulatorSecretKey::generate_with`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct KeygenProgress {
+    /// Which factor is being searched for, 0 for p and 1 for q
+    pub factor: usize,
+    /// The number of candidates tested so far for this factor
+    pub candidates: usize,
+    /// True once this factor has been found
+    pub found: bool,
+}
+
+/// How the search for a safe prime picks its candidates
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum SafePrimeSearch {
+    /// Each candidate is drawn at random
+    #[default]
+    Random,
+    /// Candidates are walked up from a random start, skipping any where
+    /// `r` or `2r + 1` has a small factor before testing the rest.
+    /// Fewer primality tests are needed per safe prime found
+    Sieve,
+}
+
+/// Operations on an accumulator that require knowledge of the trapdoor.
+/// `AccumulatorSecretKey` implements these in memory but a key held elsewhere,
+/// like a hardware module, can implement them without revealing the factors.
+pub trait SecretKeyOps {
+    /// The public RSA modulus
+    fn modulus(&self) -> BigInteger;
+
+    /// Compute `base^(e_1 * ... * e_n) mod N` where the product of
+    /// the exponents is first reduced by the totient
+    fn exp_product(
+        &self,
+        base: &BigInteger,
+        exponents: &[BigInteger],
+    ) -> Result<BigInteger, AccumulatorError>;
+
+    /// Compute `base^(1/x) mod N` i.e. the value needed to
+    /// remove `x` from an accumulator equal to `base`
+    fn root(&self, base: &BigInteger, x: &BigInteger) -> Result<BigInteger, AccumulatorError>;
+}
+
+/// Represents the safe primes used in the modulus for the accumulator.
+/// The factors are left out of the `Debug` output, use `expose_factors`
+/// to read them
+#[derive(Eq, PartialEq)]
 pub struct AccumulatorSecretKey {
     /// Must be a safe prime with MIN_SIZE_PRIME bits
-    pub p: BigInteger,
+    pub(crate) p: BigInteger,
     /// Must be a safe prime with MIN_SIZE_PRIME bits
-    pub q: BigInteger,
+    pub(crate) q: BigInteger,
+    crt: CrtParams,
+}
+
+/// Values precomputed from the factors so exponentiations
+/// can be done mod p and mod q then recombined
+#[derive(Eq, PartialEq)]
+struct CrtParams {
+    p_1: BigInteger,
+    q_1: BigInteger,
+    /// q^-1 mod p
+    q_inv: BigInteger,
+}
+
+impl CrtParams {
+    fn new(p: &BigInteger, q: &BigInteger) -> Self {
+        let one = BigInteger::from(1u32);
+        Self {
+            p_1: p - &one,
+            q_1: q - &one,
+            q_inv: q.mod_inverse(p),
+        }
+    }
+}
+
+impl Zeroize for CrtParams {
+    fn zeroize(&mut self) {
+        self.p_1.zeroize();
+        self.q_1.zeroize();
+        self.q_inv.zeroize();
+    }
 }
 
 impl AccumulatorSecretKey {
     /// Create a new Accumulator secret key by generating two
     /// 1024-bit safe primes
+    #[cfg(feature = "std")]
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Create a new Accumulator secret key by searching for two
+    /// 1024-bit safe primes one candidate at a time.
+    /// `progress` is called after every candidate and the search
+    /// stops with an error as soon as `cancel` is set.
+    #[cfg(feature = "std")]
+    pub fn generate_with<F: FnMut(KeygenProgress)>(
+        progress: F,
+        cancel: &AtomicBool,
+    ) -> Result<Self, AccumulatorError> {
+        Self::generate_with_rng(&mut OsRng, progress, cancel)
+    }
+
+    /// Same as `generate_with` but the candidates are drawn from `rng`
+    /// instead of the operating system's generator
+    #[cfg(feature = "std")]
+    pub fn generate_with_rng<R: RngCore + CryptoRng, F: FnMut(KeygenProgress)>(
+        rng: &mut R,
+        progress: F,
+        cancel: &AtomicBool,
+    ) -> Result<Self, AccumulatorError> {
+        Self::generate_with_search(SafePrimeSearch::Random, rng, progress, cancel)
+    }
+
+    /// Same as `generate_with_rng` with the candidates picked by `search`
+    #[cfg(feature = "std")]
+    pub fn generate_with_search<R: RngCore + CryptoRng, F: FnMut(KeygenProgress)>(
+        search: SafePrimeSearch,
+        rng: &mut R,
+        mut progress: F,
+        cancel: &AtomicBool,
+    ) -> Result<Self, AccumulatorError> {
+        let p = search_safe_prime(MIN_SIZE_PRIME, 0, search, rng, &mut progress, cancel)?;
+        let q = search_safe_prime(MIN_SIZE_PRIME, 1, search, rng, &mut progress, cancel)?;
+        Ok(Self::from_primes(p, q))
+    }
+
+    /// Create a secret key from existing factors
+    pub fn from_primes(p: BigInteger, q: BigInteger) -> Self {
+        let crt = CrtParams::new(&p, &q);
+        Self { p, q, crt }
+    }
+
+    /// The factors p and q. Anyone holding them can remove members
+    /// and forge witnesses so they should not leave this key
+    pub fn expose_factors(&self) -> (&BigInteger, &BigInteger) {
+        (&self.p, &self.q)
+    }
+
     /// Compute p * q
     pub fn modulus(&self) -> BigInteger {
         &self.p * &self.q
@@ -31,30 +168,111 @@ impl AccumulatorSecretKey {
 
     /// Compute (p - 1) * (q - 1)
     pub fn totient(&self) -> BigInteger {
-        (&self.p - &BigInteger::from(1u32)) * (&self.q - &BigInteger::from(1u32))
+        &self.crt.p_1 * &self.crt.q_1
+    }
+
+    /// Compute `base^e mod N` from `e mod (p - 1)` and `e mod (q - 1)`
+    /// using Garner's recombination. The exponents are blinded so repeated
+    /// calls with the same exponent don't run the same operations
+    pub(crate) fn crt_exp(
+        &self,
+        base: &BigInteger,
+        e_p: &BigInteger,
+        e_q: &BigInteger,
+    ) -> Result<BigInteger, AccumulatorError> {
+        let m_p = base.mod_exp(&blind_exponent(e_p, &self.crt.p_1)?, &self.p);
+        let m_q = base.mod_exp(&blind_exponent(e_q, &self.crt.q_1)?, &self.q);
+        let diff = (&m_p + &self.p) - (&m_q % &self.p);
+        let h = self.crt.q_inv.mod_mul(&diff, &self.p);
+        Ok(&m_q + &(&h * &self.q))
     }
 
     /// Serialize to raw bytes
-    pub fn to_bytes(&self) -> Vec<u8> {
-        let mut t = b2fa(&self.p, FACTOR_SIZE);
-        t.append(b2fa(&self.q, FACTOR_SIZE).as_mut());
-        t
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut t = Header::new(Tag::SecretKey).to_bytes();
+        t.append(b2fa(&self.p, FACTOR_SIZE)?.as_mut());
+        t.append(b2fa(&self.q, FACTOR_SIZE)?.as_mut());
+        Ok(t)
     }
 }
 
+impl SecretKeyOps for AccumulatorSecretKey {
+    fn modulus(&self) -> BigInteger {
+        AccumulatorSecretKey::modulus(self)
+    }
+
+    fn exp_product(
+        &self,
+        base: &BigInteger,
+        exponents: &[BigInteger],
+    ) -> Result<BigInteger, AccumulatorError> {
+        let exp = product_tree(exponents, Some(&self.totient()));
+        let e_p = &exp % &self.crt.p_1;
+        let e_q = &exp % &self.crt.q_1;
+        self.crt_exp(base, &e_p, &e_q)
+    }
+
+    fn root(&self, base: &BigInteger, x: &BigInteger) -> Result<BigInteger, AccumulatorError> {
+        let d_p = x.mod_inverse(&self.crt.p_1);
+        let d_q = x.mod_inverse(&self.crt.q_1);
+        // (base * r^x)^(1/x) = base^(1/x) * r, so the secret exponents
+        // are applied to a value no caller chose or has seen
+        #[cfg(feature = "std")]
+        {
+            let n = self.modulus();
+            let (r, r_inv) = loop {
+                let r = random_below(&n, &mut OsRng)?;
+                if let Some(r_inv) = r.checked_mod_inverse(&n) {
+                    break (r, r_inv);
+                }
+            };
+            let blinded = base.mod_mul(&r.mod_exp(x, &n), &n);
+            Ok(self.crt_exp(&blinded, &d_p, &d_q)?.mod_mul(&r_inv, &n))
+        }
+        #[cfg(not(feature = "std"))]
+        self.crt_exp(base, &d_p, &d_q)
+    }
+}
+
+/// Random bits in the multiple of the group order added to an exponent
+#[cfg(feature = "std")]
+const BLINDING_BITS: usize = 64;
+
+/// `e + k * order` for a random `k`, the same power of any value in a
+/// group of `order` elements but a different exponent on every call.
+/// Without `std` there is no randomness and `e` is used as is
+fn blind_exponent(e: &BigInteger, order: &BigInteger) -> Result<BigInteger, AccumulatorError> {
+    #[cfg(feature = "std")]
+    {
+        Ok(e + &(order * &random_below(&pow2(BLINDING_BITS)?, &mut OsRng)?))
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        let _ = order;
+        Ok(e.clone())
+    }
+}
+
+#[cfg(feature = "std")]
 impl Default for AccumulatorSecretKey {
     fn default() -> Self {
         let (p, q) = gen_primes();
-        Self { p, q }
+        Self::from_primes(p, q)
+    }
+}
+
+impl core::fmt::Debug for AccumulatorSecretKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AccumulatorSecretKey")
+            .field("p", &"<redacted>")
+            .field("q", &"<redacted>")
+            .finish()
     }
 }
 
 impl Clone for AccumulatorSecretKey {
     fn clone(&self) -> Self {
-        Self {
-            p: self.p.clone(),
-            q: self.q.clone(),
-        }
+        Self::from_primes(self.p.clone(), self.q.clone())
     }
 }
 
@@ -62,22 +280,32 @@ impl TryFrom<&[u8]> for AccumulatorSecretKey {
     type Error = AccumulatorError;
 
     fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::SecretKey)?;
         if data.len() != 2 * FACTOR_SIZE {
-            return Err(AccumulatorError::from_msg(
-                AccumulatorErrorKind::InvalidType,
-                format!(
-                    "Invalid bytes, expected {}, got {}",
-                    2 * FACTOR_SIZE,
-                    data.len()
-                ),
-            ));
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: 2 * FACTOR_SIZE,
+                actual: data.len(),
+            }
+            .into());
         }
         let p = BigInteger::try_from(&data[..FACTOR_SIZE])?;
         let q = BigInteger::try_from(&data[FACTOR_SIZE..])?;
-        Ok(Self { p, q })
+        if p == q || !is_factor(&p) || !is_factor(&q) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::SerializationError,
+                "secret key factors are not distinct safe primes",
+            ));
+        }
+        Ok(Self::from_primes(p, q))
     }
 }
 
+/// True if `p` is a safe prime that fills a factor
+fn is_factor(p: &BigInteger) -> bool {
+    let (sophie_germain, _) = BigInteger::div_rem(p, &BigInteger::from(2u32));
+    p.bits() == FACTOR_SIZE * 8 && p.is_prime() && sophie_germain.is_prime()
+}
+
 impl TryFrom<Vec<u8>> for AccumulatorSecretKey {
     type Error = AccumulatorError;
 
@@ -90,6 +318,7 @@ impl Zeroize for AccumulatorSecretKey {
     fn zeroize(&mut self) {
         self.p.zeroize();
         self.q.zeroize();
+        self.crt.zeroize();
     }
 }
 
@@ -101,9 +330,131 @@ impl Drop for AccumulatorSecretKey {
 
 serdes_impl!(AccumulatorSecretKey);
 
-#[cfg(not(test))]
+/// Candidates walked from each start of a sieved search
+#[cfg(feature = "std")]
+const SIEVE_WINDOW: usize = 4096;
+/// A sieved search skips candidates with an odd factor below this
+#[cfg(feature = "std")]
+const SIEVE_BOUND: usize = 1 << 14;
+
+/// Find a safe prime with `size` bits, at least 16, for uses other than
+/// an accumulator key. `progress` is called after every candidate tested,
+/// always as factor 0, and the search stops with an error as soon as
+/// `cancel` is set
+#[cfg(feature = "std")]
+pub fn safe_prime<R: RngCore + CryptoRng, F: FnMut(KeygenProgress)>(
+    size: usize,
+    search: SafePrimeSearch,
+    rng: &mut R,
+    mut progress: F,
+    cancel: &AtomicBool,
+) -> Result<BigInteger, AccumulatorError> {
+    if size < 16 {
+        return Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::InvalidType,
+            "safe primes need at least 16 bits",
+        ));
+    }
+    search_safe_prime(size, 0, search, rng, &mut progress, cancel)
+}
+
+/// Find a safe prime 2r + 1 with `size` bits by testing `r` from
+/// random starts
+#[cfg(feature = "std")]
+fn search_safe_prime<R: RngCore + CryptoRng, F: FnMut(KeygenProgress)>(
+    size: usize,
+    factor: usize,
+    search: SafePrimeSearch,
+    rng: &mut R,
+    progress: &mut F,
+    cancel: &AtomicBool,
+) -> Result<BigInteger, AccumulatorError> {
+    let one = BigInteger::from(1u32);
+    let two = BigInteger::from(2u32);
+    let low = pow2(size - 2)?;
+    let high = &low * &two;
+    let primes = match search {
+        SafePrimeSearch::Random => Vec::new(),
+        SafePrimeSearch::Sieve => small_odd_primes(),
+    };
+    let mut candidates = 0;
+    loop {
+        let start = &low + &random_below(&low, rng)?;
+        let offsets = match search {
+            SafePrimeSearch::Random => vec![0],
+            SafePrimeSearch::Sieve => sieve(&start, &primes),
+        };
+        for k in offsets {
+            if cancel.load(Ordering::Relaxed) {
+                return Err(AccumulatorError::from_msg(
+                    AccumulatorErrorKind::Cancelled,
+                    "key generation was cancelled",
+                ));
+            }
+            let r = &start + &BigInteger::from(k as u32);
+            if r >= high {
+                break;
+            }
+            let p = &(&r * &two) + &one;
+            let found = r.is_prime() && p.is_prime();
+            candidates += 1;
+            progress(KeygenProgress {
+                factor,
+                candidates,
+                found,
+            });
+            if found {
+                return Ok(p);
+            }
+        }
+    }
+}
+
+/// The odd primes below `SIEVE_BOUND`
+#[cfg(feature = "std")]
+fn small_odd_primes() -> Vec<usize> {
+    let mut composite = vec![false; SIEVE_BOUND];
+    let mut primes = Vec::new();
+    for i in (3..SIEVE_BOUND).step_by(2) {
+        if !composite[i] {
+            primes.push(i);
+            for j in (i * i..SIEVE_BOUND).step_by(2 * i) {
+                composite[j] = true;
+            }
+        }
+    }
+    primes
+}
+
+/// The offsets `k` below `SIEVE_WINDOW` where `r = start + k` is odd and
+/// neither `r` nor `2r + 1` is divisible by one of `primes`.
+/// `start` must be larger than the primes
+#[cfg(feature = "std")]
+fn sieve(start: &BigInteger, primes: &[usize]) -> Vec<usize> {
+    let rem = |s: usize| {
+        (start % &BigInteger::from(s as u32))
+            .to_bytes()
+            .iter()
+            .fold(0usize, |a, b| (a << 8) | *b as usize)
+    };
+    let mut ruled_out = vec![false; SIEVE_WINDOW];
+    for k in (rem(2)..SIEVE_WINDOW).step_by(2) {
+        ruled_out[k] = true;
+    }
+    for &s in primes {
+        let r = rem(s);
+        // r = 0 and 2r + 1 = 0 mod s
+        for target in [0, (s - 1) / 2] {
+            for k in (((target + s - r) % s)..SIEVE_WINDOW).step_by(s) {
+                ruled_out[k] = true;
+            }
+        }
+    }
+    (0..SIEVE_WINDOW).filter(|k| !ruled_out[*k]).collect()
+}
+
+#[cfg(all(feature = "std", not(test)))]
 fn gen_primes() -> (BigInteger, BigInteger) {
-    use crate::MIN_SIZE_PRIME;
     let mut p: Vec<BigInteger> = (0..2)
         .collect::<Vec<usize>>()
         .par_iter()
@@ -122,3 +473,176 @@ fn gen_primes() -> (BigInteger, BigInteger) {
     let q = BigInteger::from("149253707427499607752440533538420296779167710000842829107795675900185486091323606384260179778233711456748787559527972657213022998726578510459854530854900733457277643303592216900588246498239579922221956281290954735600574251392801029419096160964874150455156365996536205549377586240264971604869515447059744740119");
     (p, q)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_with_progress() {
+        let cancel = AtomicBool::new(false);
+        let mut calls = 0;
+        let mut last = None;
+        let p = search_safe_prime(
+            64,
+            1,
+            SafePrimeSearch::Random,
+            &mut OsRng,
+            &mut |s| {
+                calls += 1;
+                last = Some(s);
+            },
+            &cancel,
+        )
+        .unwrap();
+        assert_eq!(p.bits(), 64);
+        assert!(p.is_prime());
+        assert!((&(&p - &BigInteger::from(1u32)) / &BigInteger::from(2u32)).is_prime());
+        let last = last.unwrap();
+        assert!(last.found);
+        assert_eq!(last.factor, 1);
+        assert_eq!(last.candidates, calls);
+    }
+
+    #[test]
+    fn search_with_seeded_rng() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let cancel = AtomicBool::new(false);
+        let search = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            search_safe_prime(64, 0, SafePrimeSearch::Random, &mut rng, &mut |_| {}, &cancel).unwrap()
+        };
+        let p = search(7);
+        assert_eq!(p, search(7));
+        assert_ne!(p, search(8));
+    }
+
+    #[test]
+    fn sieved_search() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let start = pow2(62).unwrap() + BigInteger::from(12345u32);
+        let primes = small_odd_primes();
+        for k in sieve(&start, &primes) {
+            let r = &start + &BigInteger::from(k as u32);
+            let p = &(&r * &BigInteger::from(2u32)) + &BigInteger::from(1u32);
+            for s in primes.iter().chain([2].iter()).map(|s| BigInteger::from(*s as u32)) {
+                assert_ne!(&r % &s, BigInteger::default());
+                assert_ne!(&p % &s, BigInteger::default());
+            }
+        }
+
+        let cancel = AtomicBool::new(false);
+        let mut tested = 0;
+        let p = safe_prime(
+            128,
+            SafePrimeSearch::Sieve,
+            &mut StdRng::seed_from_u64(3),
+            |s| tested = s.candidates,
+            &cancel,
+        )
+        .unwrap();
+        assert_eq!(p.bits(), 128);
+        assert!((&(&p - &BigInteger::from(1u32)) / &BigInteger::from(2u32)).is_prime());
+        assert!(tested > 0);
+        let again = safe_prime(128, SafePrimeSearch::Sieve, &mut StdRng::seed_from_u64(3), |_| {}, &cancel);
+        assert_eq!(again.unwrap(), p);
+
+        assert!(safe_prime(8, SafePrimeSearch::Sieve, &mut OsRng, |_| {}, &cancel).is_err());
+        cancel.store(true, Ordering::Relaxed);
+        let res = safe_prime(128, SafePrimeSearch::Sieve, &mut OsRng, |_| {}, &cancel);
+        assert_eq!(res.unwrap_err().kind(), AccumulatorErrorKind::Cancelled);
+    }
+
+    #[test]
+    fn generate_cancelled() {
+        let cancel = AtomicBool::new(false);
+        let res = AccumulatorSecretKey::generate_with(
+            |s| {
+                if s.candidates == 3 {
+                    cancel.store(true, Ordering::Relaxed);
+                }
+            },
+            &cancel,
+        );
+        assert_eq!(res.unwrap_err().kind(), AccumulatorErrorKind::Cancelled);
+    }
+
+    #[test]
+    fn crt_matches_full_modulus() {
+        let key = AccumulatorSecretKey::default();
+        let n = key.modulus();
+        let totient = key.totient();
+        let base = BigInteger::from(65537u32);
+        let exponents = vec![
+            BigInteger::generate_prime(256),
+            BigInteger::generate_prime(256),
+            BigInteger::generate_prime(256),
+        ];
+        let exp = exponents
+            .iter()
+            .fold(BigInteger::from(1u32), |a, b| a.mod_mul(b, &totient));
+        assert_eq!(
+            key.exp_product(&base, &exponents).unwrap(),
+            base.mod_exp(&exp, &n)
+        );
+
+        let x = &exponents[0];
+        let root = key.root(&base, x).unwrap();
+        assert_eq!(root, base.mod_exp(&x.mod_inverse(&totient), &n));
+        assert_eq!(root.mod_exp(x, &n), base);
+        assert_eq!(key.root(&base, x).unwrap(), root);
+    }
+
+    #[test]
+    fn blinded_exponents() {
+        let key = AccumulatorSecretKey::default();
+        let (p, p_1) = (&key.p, &key.crt.p_1);
+        let e = BigInteger::generate_prime(256);
+        let blinded = blind_exponent(&e, p_1).unwrap();
+        assert_ne!(blinded, e);
+        assert_ne!(blinded, blind_exponent(&e, p_1).unwrap());
+        assert_eq!(&blinded % p_1, e);
+        let base = BigInteger::from(65537u32);
+        assert_eq!(base.mod_exp(&blinded, p), base.mod_exp(&e, p));
+    }
+
+    #[test]
+    fn serialization() {
+        let key = AccumulatorSecretKey::default();
+        let bytes = key.to_bytes().unwrap();
+        let parsed = AccumulatorSecretKey::try_from(bytes.clone()).unwrap();
+        assert_eq!(parsed.expose_factors(), key.expose_factors());
+
+        // The same factor twice, a factor that isn't a safe prime or is too short
+        let (p, q) = key.expose_factors();
+        let mut same = Header::new(Tag::SecretKey).to_bytes();
+        same.append(&mut b2fa(p, FACTOR_SIZE).unwrap());
+        same.append(&mut b2fa(p, FACTOR_SIZE).unwrap());
+        assert!(AccumulatorSecretKey::try_from(same).is_err());
+        let mut composite = bytes.clone();
+        *composite.last_mut().unwrap() ^= 2;
+        assert!(AccumulatorSecretKey::try_from(composite).is_err());
+        let prime = BigInteger::generate_prime(MIN_SIZE_PRIME);
+        let mut unsafe_prime = Header::new(Tag::SecretKey).to_bytes();
+        unsafe_prime.append(&mut b2fa(p, FACTOR_SIZE).unwrap());
+        unsafe_prime.append(&mut b2fa(&prime, FACTOR_SIZE).unwrap());
+        assert!(prime.bits() == MIN_SIZE_PRIME && AccumulatorSecretKey::try_from(unsafe_prime).is_err());
+        let mut short = Header::new(Tag::SecretKey).to_bytes();
+        short.append(&mut b2fa(&BigInteger::generate_safe_prime(256), FACTOR_SIZE).unwrap());
+        short.append(&mut b2fa(q, FACTOR_SIZE).unwrap());
+        assert!(AccumulatorSecretKey::try_from(short).is_err());
+    }
+
+    #[test]
+    fn debug_redacts_factors() {
+        let key = AccumulatorSecretKey::default();
+        let out = format!("{:?}", key);
+        let (p, q) = key.expose_factors();
+        assert!(!out.contains(&format!("{:?}", p)));
+        assert!(!out.contains(&format!("{:?}", q)));
+        assert!(out.contains("<redacted>"));
+        assert_eq!(p * q, key.modulus());
+    }
+}
@@ -0,0 +1,262 @@
+use crate::{
+    accumulator::Accumulator,
+    format::{Header, Tag, MEMBERS_FIXED},
+    key::AccumulatorSecretKey,
+    memwitness::MembershipWitness,
+    nonwitness::NonMembershipWitness,
+    FACTOR_SIZE, MEMBER_SIZE,
+};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+};
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+// Releases before the versioned header wrote the same fields in the
+// same order without it, and accumulators had no epoch and always
+// wrote members at a fixed size. The values are converted to the
+// current layout and parsed by the usual `TryFrom` so they get the
+// same checks. Proofs aren't converted, their transcripts changed and
+// they would not verify, create new ones from the converted witnesses.
+//
+// Witnesses didn't record the accumulator they were valid for, so they
+// are checked against the accumulator the caller has and marked fresh
+// for it. Non-membership witnesses wrote only the magnitude of `a`, its
+// sign is whichever one makes the witness valid for that accumulator
+
+/// The size of an accumulator without members in the old layout
+pub const ACCUMULATOR_MIN_BYTES: usize = FACTOR_SIZE * 6 + 4;
+
+/// Read an accumulator written by an earlier release. It starts at epoch 0
+pub fn accumulator(data: &[u8]) -> Result<Accumulator, AccumulatorError> {
+    if data.len() < ACCUMULATOR_MIN_BYTES {
+        return Err(AccumulatorErrorKind::InvalidLength {
+            expected: ACCUMULATOR_MIN_BYTES,
+            actual: data.len(),
+        }
+        .into());
+    }
+    let count = u32::from_be_bytes(*array_ref![data, FACTOR_SIZE * 6, 4]) as usize;
+    let expected = count
+        .checked_mul(MEMBER_SIZE)
+        .and_then(|m| m.checked_add(ACCUMULATOR_MIN_BYTES))
+        .ok_or_else(|| AccumulatorError::from_msg(AccumulatorErrorKind::SerializationError, "member count is too large"))?;
+    if data.len() != expected {
+        return Err(AccumulatorErrorKind::InvalidLength {
+            expected,
+            actual: data.len(),
+        }
+        .into());
+    }
+    let mut out = Header::new(Tag::Accumulator).to_bytes();
+    out.extend_from_slice(&data[..(FACTOR_SIZE * 6)]);
+    out.extend_from_slice(&0u64.to_be_bytes());
+    out.extend_from_slice(&data[(FACTOR_SIZE * 6)..ACCUMULATOR_MIN_BYTES]);
+    out.push(MEMBERS_FIXED);
+    out.extend_from_slice(&data[ACCUMULATOR_MIN_BYTES..]);
+    Accumulator::try_from(out)
+}
+
+/// Read a secret key written by an earlier release
+pub fn secret_key(data: &[u8]) -> Result<AccumulatorSecretKey, AccumulatorError> {
+    AccumulatorSecretKey::try_from(with_header(Tag::SecretKey, data).as_slice())
+}
+
+/// Read a membership witness written by an earlier release that is
+/// valid for `accumulator`
+pub fn membership_witness<S>(data: &[u8], accumulator: &Accumulator<S>) -> Result<MembershipWitness, AccumulatorError> {
+    check_len(data, FACTOR_SIZE * 2 + MEMBER_SIZE)?;
+    let mut witness = MembershipWitness::try_from(with_header(Tag::MembershipWitness, data).as_slice())?;
+    witness.refresh(accumulator)?;
+    Ok(witness)
+}
+
+/// Read a non-membership witness written by an earlier release that is
+/// valid for `accumulator`
+pub fn non_membership_witness<S>(
+    data: &[u8],
+    accumulator: &Accumulator<S>,
+) -> Result<NonMembershipWitness, AccumulatorError> {
+    check_len(data, FACTOR_SIZE * 4 + MEMBER_SIZE)?;
+    if data[0] & 0x80 != 0 {
+        return Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::SerializationError,
+            "witness coefficient is not smaller than the member",
+        ));
+    }
+    let mut witness = NonMembershipWitness::try_from(with_header(Tag::NonMembershipWitness, data).as_slice())?;
+    if witness.refresh(accumulator).is_err() {
+        witness.a = &BigInteger::default() - &witness.a;
+        witness.refresh(accumulator)?;
+    }
+    Ok(witness)
+}
+
+fn check_len(data: &[u8], expected: usize) -> Result<(), AccumulatorError> {
+    if data.len() != expected {
+        return Err(AccumulatorErrorKind::InvalidLength {
+            expected,
+            actual: data.len(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+fn with_header(tag: Tag, data: &[u8]) -> Vec<u8> {
+    let mut out = Header::new(tag).to_bytes();
+    out.extend_from_slice(data);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{memproof::MembershipProof, nonmemproof::NonMembershipProof};
+
+    // Written by the release before the versioned header with the default
+    // test key, the accumulator has 3, 7 and 11
+    const OLD_SECRET_KEY: &str = "bcd09939e9debf6aace2b64279731bdd848cd08d03a4944fa02d78e4edd0bfa5\
+        174a3ef8d02a3b33fdcb5ec53f7b4a0fe08531277a4641154b984feeecac0ac9\
+        1732136215028e069d61b8dbe5f8b2d8980f91fe21d5b33afaa93de184de9409\
+        a3b36fa5139e81dd75acc74331f9bf96c71c6d93677718d2267b1e270f3c6077\
+        d48b5981c2f269a15de5b71e2b6b861be5aae377a8421003040f3f1cef833867\
+        86f00383b08c1cddc21aada1eee438a3b7df928b28f1c8e9408f56d8a45bfa55\
+        7bcddbb8529311485d4ec5f20db4d81e5282b5b817581694f644b75893a3308c\
+        0dab72593cbcd452391c6492fc8b29bb37d9436c19ed8fedc9c0b6c0138ed317";
+    // The accumulator of 3, 7 and 11 with the default test key
+    const OLD_ACCUMULATOR: &str = "747bb0822a1b0e37afcc5745a6ccd5b0ce2a1a1ce29c4d5e2aee43f1626fe0c5\
+        b20263eade7ba88ef2142bfb00162eb6303b6dde332d4980277bff2630251151\
+        58463fefae2b098b86d606a9cae030427accfb6f3a9b4afba8730f9f75e4fa39\
+        bcc10d6f7209ea01db3d4e4c1a1f17d616b3f95d1bfa4688aa9d296642f0069a\
+        4376d0a404e4bc2f93e898d23a95d8c12482c4c367c496b5a4db715abf717aac\
+        895e6e3ac3e619c416fcb8f2132d5b0cca7cf1878f3b5efa1a7d2b605f6a8711\
+        e95838d555433b17b9f31d59452738a7c0ef3b5c2b5ef8ecb4f96c46129fdcad\
+        3008f07bb33c585ee814e9e3694ac48232dec86b8e3b0b5194533b92ebd5f56e\
+        8c5290b807e6675766f9766b77304d7ab516a4a3e074a5f2d77c59ec6a782123\
+        2264b9f5ff8c7c6660b8e636761b57559d0da190701750693946ac6aeac77329\
+        82e7330b8caf9152b1f71bd705ca685538dc95ea868b5bf4e1cf3d34054d8b03\
+        42b1f8bbb2b7e8a58379ec1d97e13c945d845d7d07685456cdd34c231d38fa74\
+        cfe4beaa2589c8c875300b1303dc481cce9c28171ff84b7982ed018d503ec3bb\
+        c244ca015365afd5dfc0919372c54e7a2ab0520504a11a444f8abd9281f1e91b\
+        449659f2af1621e37410ff416ffbc64eaae79e4621b7d6af14697bcdc6121c44\
+        40d356dff4f9d738daf07f04a0589e0332b3eefe10bec61308ef554ae10eb297\
+        9cc3862b6249aa2adf265e2ea67f6a5354338aa80a113cbf58f03b8f7157d1b8\
+        48fca17e545acbdde3d40cddf892ddf4caa600308476def0bf6ac211157a0f04\
+        2e23d84f29004675d68fc650529c560da177f1ce283bc5e8655a6e7dc9a8f81c\
+        93f09009574d1ee3f84877ba2624b720251b6191223a863762f6ca0b48f1002d\
+        fd946f72d2b6390ad2c52f5ebc2325475c2b1a70b6ba38d182a8882263413703\
+        f3080aabe90f94cd6b6e618b90aadb62f3b19cba429e6c72c72f1fcd70fdbd57\
+        b72dbbc5d22d4c8cf3ff99b068d14d8c9533416940ffc38786253c7013636c5d\
+        11c4229cad13c52fa3034f0fcc967ebf9e2ab099408fea71b0d2c8f778f0bfb1\
+        0000000323fca2f5ce9f5caaf4262038ffce965e12185bb554613e383106a580\
+        eae48c8973810afd10ab55b6dc916fed2fd058134fae8ee3ea5a5ba4810ad704\
+        080c80a1d9f4a94d3dd169c772877b4b3ebdbdd09656e4ed9687fb4e61af8bb1\
+        b39a65cb";
+    // The witness for 3
+    const OLD_MEMBERSHIP_WITNESS: &str = "8bac3237f802a16e8f07e5cb3a3b05efbcb390f31eda495a96a2773b052d9d57\
+        0a3948990a018f9a57071fd35eb29724874d9d06d8b9cd1d8f3c292b08aa4073\
+        710f9955cab7ac8421dc13d17f4e33bb6a771826c949749b760710d75a97d908\
+        ee6b37cca53ef0fcf255b1df54ef52cee034941d8a131853504194231852b0ff\
+        a7b4fe33118f16c7b464ab05e59604d3935d1bd643e9afd51c683220d430497c\
+        a88d9f9095f5b831145b26afd92d707c02336bb1b66efb28754dd6ec316d1bd4\
+        c0b9cc65606cd8909b6e37defd373ab5a43e57029dfb3474639f9af28471f9e3\
+        3e8cc38255262c61bbe69511533fef95c88b571c0b6d74f49d1d6c2c252716c5\
+        d9f4a94d3dd169c772877b4b3ebdbdd09656e4ed9687fb4e61af8bb1b39a65cb";
+    // The witness for 13, its `a` is negative
+    const OLD_NON_MEMBERSHIP_WITNESS_NEGATIVE: &str = "0000000000000000000000000000000000000000000000000000000000000000\
+        0000000000000000000000000000000000000000000000000000000000000000\
+        0000000000000000000000000000000000000000000000000000000000000000\
+        0000000000000000000000000000000000000000000000000000000000000000\
+        0000000000000000000000000000000000000000000000000000000000000000\
+        0000000000000000000000000000000000000000000000000000000000000000\
+        0000000000000000000000000000000000000000000000000000000000000000\
+        2b33c16578c7ce5fe954d9eee21e6fbfca1c394be05e98066c48478ea143f68f\
+        010608a8aead78d0a3ebf8bc652c109947ecd755fb37c4b63ead3c944276e9b3\
+        1da74bfe647c0b9eea537285b1be5b4f57b9d1ee2dcbac3a10b1e903a4cf5da5\
+        49fa1d52e61629cba4dd116de627f38a9d8018884dadba47aa8c0680d233f8f5\
+        d8f2284ef119c27d5343373bdea20976be3d2b3e0dc7fc91c77b3e82fdaa6625\
+        417a107bdf4e36455926e47a7bcc97591647768a18f62ca498fab408dba633c4\
+        491c0db16d4efee9b9e62a6bcc77696b5b0386a5e477a80ebcf2cb91880ba118\
+        057f6eae8fd8c4f169ae332f6a74e81245bc4cd59343af8d2b865adf0ceebb8d\
+        6263b0853459ea1caf25456a2adc694144606c089395cce71a15e159976498f1\
+        75f59c44016d9e922fad13a300196b873de54bb8dbc98b958137cfc7ac571665";
+    // The witness for 14, its `a` is positive
+    const OLD_NON_MEMBERSHIP_WITNESS_POSITIVE: &str = "0000000000000000000000000000000000000000000000000000000000000000\
+        0000000000000000000000000000000000000000000000000000000000000000\
+        0000000000000000000000000000000000000000000000000000000000000000\
+        0000000000000000000000000000000000000000000000000000000000000000\
+        0000000000000000000000000000000000000000000000000000000000000000\
+        0000000000000000000000000000000000000000000000000000000000000000\
+        0000000000000000000000000000000000000000000000000000000000000000\
+        27db8071ce6e809d3fad91601f21b7524b42d1696f89ff036837036ed00a3412\
+        1bbfcd0817caec744cc18e3c5b87c7e1145abe368d0f521480c0a3d0a7439bd9\
+        46224dca82de76f6b0d00ac89838667d0676e49a6522e878d4b7b1585133061a\
+        26f40872f05272d528307f0de00fcb3bd29de0c9bfb5ba92c8f9208e708c3d3a\
+        64f1a3ac49b2ff134ba74c27908662b904446030d1a07438ddbf8d91f3c95339\
+        f487fa9acfb60f7f47f0f84916885975603855eb4759ab530c485e0581ccf142\
+        5b79072c6ffc8011e42ec5b436bff05866de57ed7d15db4abbc5058642fb2718\
+        6d7d218cfc5e4a41f67cced13f3109a552fbd0fb1cb8c0732bacb09ea4e0ee59\
+        cb91ce8740fe6ecb70495707265b75d1c0e389a8598b2aff8edc5098d89dab80\
+        bbaf37936e359e0a31ce18b0b2cccb6834a97eb09cb0dab2787ef4c6f447dd27";
+
+    fn decode(vector: &str) -> Vec<u8> {
+        hex::decode(vector).unwrap()
+    }
+
+    #[test]
+    fn convert() {
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = vec![3u64.to_be_bytes(), 7u64.to_be_bytes(), 11u64.to_be_bytes()];
+        let acc = Accumulator::with_members(&key, &members).unwrap();
+
+        let mut old = decode(OLD_ACCUMULATOR);
+        let converted = accumulator(&old).unwrap();
+        assert_eq!(converted, acc);
+        assert!(accumulator(&old[..(old.len() - 1)]).is_err());
+        old[FACTOR_SIZE * 6] = 0xff;
+        assert!(accumulator(&old).is_err());
+
+        let converted_key = secret_key(&decode(OLD_SECRET_KEY)).unwrap();
+        assert_eq!(converted_key.to_bytes().unwrap(), key.to_bytes().unwrap());
+
+        let old_witness = decode(OLD_MEMBERSHIP_WITNESS);
+        let witness = membership_witness(&old_witness, &converted).unwrap();
+        assert_eq!(witness, MembershipWitness::new(&acc, members[0]).unwrap());
+        let proof = MembershipProof::new(&witness, &converted, b"legacy").unwrap();
+        assert!(proof.verify(&converted, b"legacy"));
+        assert!(membership_witness(&old_witness[1..], &converted).is_err());
+        let other = Accumulator::with_members(&key, &members[1..]).unwrap();
+        assert!(membership_witness(&old_witness, &other).is_err());
+    }
+
+    #[test]
+    fn non_membership_sign() {
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = vec![3u64.to_be_bytes(), 7u64.to_be_bytes(), 11u64.to_be_bytes()];
+        let acc = accumulator(&decode(OLD_ACCUMULATOR)).unwrap();
+        let zero = BigInteger::default();
+
+        for (old, value, negative) in [
+            (OLD_NON_MEMBERSHIP_WITNESS_NEGATIVE, 13u64, true),
+            (OLD_NON_MEMBERSHIP_WITNESS_POSITIVE, 14u64, false),
+        ]
+        .iter()
+        {
+            let old = decode(old);
+            let witness = non_membership_witness(&old, &acc).unwrap();
+            assert_eq!(witness.a < zero, *negative);
+            assert!(!witness.is_stale(&acc));
+            assert_eq!(witness, NonMembershipWitness::new(&acc, value.to_be_bytes()).unwrap());
+            let proof = NonMembershipProof::new(&witness, &acc, b"legacy").unwrap();
+            assert!(proof.verify(&acc, b"legacy"));
+        }
+
+        let old = decode(OLD_NON_MEMBERSHIP_WITNESS_NEGATIVE);
+        let other = Accumulator::with_members(&key, &members[1..]).unwrap();
+        assert!(non_membership_witness(&old, &other).is_err());
+        assert!(non_membership_witness(&old[1..], &acc).is_err());
+    }
+}
@@ -7,7 +7,14 @@ unused_lifetimes,
 unused_qualifications,
 )]
 #![cfg_attr(feature = "nightly", feature(doc_cfg))]
+#![cfg_attr(not(feature = "std"), no_std)]
 //! Implementation of a dynamic universal RSA accumulator
+//!
+//! Without the default `std` feature only `alloc` is required.
+//! Key generation, the distributed setup, threshold operations and
+//! key encodings need `std`, verifying witnesses and proofs does not.
+#[cfg(not(feature = "std"))]
+extern crate alloc;
 #[macro_use]
 extern crate arrayref;
 #[macro_use]
@@ -15,63 +22,345 @@ pub extern crate common;
 
 pub(crate) const MIN_SIZE_PRIME: usize = 1024;
 pub(crate) const FACTOR_SIZE: usize = MIN_SIZE_PRIME / 8;
-pub(crate) const MIN_BYTES: usize = FACTOR_SIZE * 6 + 4;
+pub(crate) const MIN_BYTES: usize = FACTOR_SIZE * 6 + 13;
 pub(crate) const MEMBER_SIZE: usize = 32;
 pub(crate) const MEMBER_SIZE_BITS: usize = 256;
 
+/// The default primality test for member primes. Members end up in
+/// exponents reduced with the secret key, where a composite that slips
+/// through lets its factors be proven members
+pub const MEMBER_PRIMALITY: PrimalityTest = PrimalityTest::BailliePsw;
+
 /// Provides methods for creating and updating accumulators
 pub mod accumulator;
+/// A signed, hash linked log of registry deltas for auditors
+#[cfg(feature = "ed25519")]
+pub mod audit;
+/// Batched updates and aggregated proofs for stateless use
+pub mod batch;
+/// The RSA half of proofs linking a member to an accumulator of another family
+#[cfg(feature = "std")]
+pub mod bridge;
+/// Configuring and creating accumulators
+#[cfg(feature = "std")]
+pub mod builder;
+/// Caching the witnesses an issuer creates
+#[cfg(feature = "std")]
+pub mod cache;
+/// Canonical CBOR encoding of the wire types
+#[cfg(feature = "cbor")]
+pub mod cbor;
+/// Short commitments to the accumulator value on the Ristretto group
+#[cfg(feature = "ristretto")]
+pub mod commitment;
+/// Proofs a hidden member was derived from a committed input
+#[cfg(feature = "std")]
+pub mod derivation;
+/// Membership proofs over registries run by different issuers
+#[cfg(feature = "std")]
+pub mod federation;
+/// Versioned header prefixed to serialized values
+pub mod format;
+/// `Arbitrary` impls and proptest strategies for fuzzing
+#[cfg(any(feature = "arbitrary", feature = "proptest"))]
+pub mod fuzz;
 /// Provides methods for hashing to prime
 pub mod hash;
 /// Provides an accumulator secret factors
 pub mod key;
+/// Reading values serialized by releases before the versioned header
+pub mod legacy;
+/// PKCS#1 / PKCS#8 / X.509 encodings for keys
+#[cfg(feature = "std")]
+pub mod pkcs;
+/// Secret keys held in a PKCS#11 token
+#[cfg(feature = "pkcs11")]
+pub mod pkcs11;
 /// Proofs of set membership
 pub mod memproof;
+/// Async versions of the heavy operations for the tokio runtime
+#[cfg(feature = "tokio")]
+pub mod nonblocking;
 /// Proofs of set non-membership
 pub mod nonmemproof;
 /// Provides non-membership witness methods
 pub mod nonwitness;
 /// Provides witness methods
 pub mod memwitness;
+/// Proofs the modulus was set up honestly
+#[cfg(feature = "std")]
+pub mod modulus;
+/// Prime representatives chosen by the caller and checked before use
+pub mod prime;
+/// Protobuf messages for the schema in `proto/accumulator.proto`
+#[cfg(feature = "proto")]
+pub mod proto;
+/// Signed messages for pushing witness updates over publish/subscribe transports
+#[cfg(feature = "ed25519")]
+pub mod pubsub;
+/// Credential revocation registries built on an accumulator
+pub mod registry;
+/// Distributed modulus generation without a trusted dealer
+#[cfg(feature = "std")]
+pub mod setup;
+/// Members split across several independent accumulators
+#[cfg(feature = "std")]
+pub mod sharded;
+/// Sharing an accumulator between threads
+#[cfg(feature = "std")]
+pub mod shared;
+/// Commit, challenge and response phases of the membership proofs
+pub mod sigma;
+/// Published accumulator states and their signatures
+pub mod state;
+/// Storage for the accumulator member set
+pub mod store;
+/// Files of per-epoch witness update factors holders read offline
+#[cfg(feature = "std")]
+pub mod tails;
+/// Names of the metrics recorded with the `metrics` feature
+pub mod telemetry;
+/// Fixed vectors for checking other implementations against this one
+#[cfg(feature = "test-vectors")]
+pub mod test_vectors;
+/// Threshold operations with a shared trapdoor
+#[cfg(feature = "std")]
+pub mod threshold;
+/// Keeping a holder's witness up to date in the background
+#[cfg(feature = "tokio")]
+pub mod updater;
 
-use crate::hash::hash_to_prime;
+use crate::hash::{hash_to_prime, hash_to_prime_bytes};
 use blake2::{digest::Digest, Blake2b};
 use common::{
-    bigint::BigInteger,
+    bigint::{BigInteger, PrimalityTest},
     error::{AccumulatorError, AccumulatorErrorKind},
 };
-use std::convert::TryFrom;
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+use core::convert::TryFrom;
 use crate::hash::hash_to_generator;
+#[cfg(feature = "std")]
+use crate::format::write_element;
+#[cfg(feature = "std")]
+use rand::{CryptoRng, RngCore};
+#[cfg(feature = "std")]
+use std::io::Write;
+#[cfg(feature = "std")]
+use zeroize::Zeroize;
 
 /// Convenience module to include when using
 pub mod prelude {
     pub use crate::{
-        accumulator::Accumulator,
+        accumulator::{Accumulator, AccumulatorPublic, PublicState},
+        batch::{AggregateMembershipProof, BatchNonMembershipProof, BatchUpdate, UpdateProof},
         common::{
             bigint::{BigInteger, GcdResult},
             error::*,
         },
-        key::AccumulatorSecretKey,
-        memproof::MembershipProof,
+        key::{AccumulatorSecretKey, SecretKeyOps},
+        memproof::{LinkedMembershipProof, MembershipProof, SubsetProof},
         memwitness::MembershipWitness,
         nonmemproof::NonMembershipProof,
         nonwitness::NonMembershipWitness,
+        registry::{NonRevocationProof, RegistryDelta, RevocationRegistry},
+        state::AccumulatorState,
+        store::MemberStore,
+        ProofSize,
     };
 }
 
+/// Rayon's parallel iterators with the `parallel` feature, plain
+/// iterators without it or on wasm32 which has no threads
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+pub(crate) mod par {
+    pub(crate) use rayon::{join, prelude::*};
+}
+
+/// Rayon's parallel iterators with the `parallel` feature, plain
+/// iterators without it or on wasm32 which has no threads
+#[cfg(any(not(feature = "parallel"), target_arch = "wasm32"))]
+pub(crate) mod par {
+    /// Sequential stand in for `rayon::iter::IntoParallelRefIterator`
+    pub(crate) trait ParIter<'a> {
+        type Iter: Iterator;
+        fn par_iter(&'a self) -> Self::Iter;
+    }
+
+    impl<'a, T: 'a + ?Sized> ParIter<'a> for T
+    where
+        &'a T: IntoIterator,
+    {
+        type Iter = <&'a T as IntoIterator>::IntoIter;
+        fn par_iter(&'a self) -> Self::Iter {
+            self.into_iter()
+        }
+    }
+
+    /// Sequential stand in for `rayon::iter::IntoParallelRefMutIterator`
+    #[cfg(feature = "std")]
+    pub(crate) trait ParIterMut<'a> {
+        type Iter: Iterator;
+        fn par_iter_mut(&'a mut self) -> Self::Iter;
+    }
+
+    #[cfg(feature = "std")]
+    impl<'a, T: 'a + ?Sized> ParIterMut<'a> for T
+    where
+        &'a mut T: IntoIterator,
+    {
+        type Iter = <&'a mut T as IntoIterator>::IntoIter;
+        fn par_iter_mut(&'a mut self) -> Self::Iter {
+            self.into_iter()
+        }
+    }
+
+    /// Sequential stand in for `rayon::iter::IntoParallelIterator`
+    pub(crate) trait IntoParIter: IntoIterator + Sized {
+        fn into_par_iter(self) -> Self::IntoIter {
+            self.into_iter()
+        }
+    }
+
+    impl<T: IntoIterator> IntoParIter for T {}
+
+    /// Sequential stand in for `rayon::iter::ParallelIterator::map_init`
+    pub(crate) trait MapInit: Iterator + Sized {
+        fn map_init<T, I, F, R>(self, init: I, map_op: F) -> MapInitIter<Self, T, F>
+        where
+            I: Fn() -> T,
+            F: FnMut(&mut T, Self::Item) -> R,
+        {
+            MapInitIter {
+                iter: self,
+                state: init(),
+                map_op,
+            }
+        }
+    }
+
+    impl<I: Iterator> MapInit for I {}
+
+    /// The iterator returned by `MapInit::map_init`
+    pub(crate) struct MapInitIter<I, T, F> {
+        iter: I,
+        state: T,
+        map_op: F,
+    }
+
+    impl<I, T, F, R> Iterator for MapInitIter<I, T, F>
+    where
+        I: Iterator,
+        F: FnMut(&mut T, I::Item) -> R,
+    {
+        type Item = R;
+
+        fn next(&mut self) -> Option<R> {
+            let item = self.iter.next()?;
+            Some((self.map_op)(&mut self.state, item))
+        }
+    }
+
+    /// Sequential stand in for `rayon::join`
+    pub(crate) fn join<A, B, RA, RB>(a: A, b: B) -> (RA, RB)
+    where
+        A: FnOnce() -> RA,
+        B: FnOnce() -> RB,
+    {
+        (a(), b())
+    }
+}
+
+/// Run `f` with the parallel work it does on `pool` instead of rayon's
+/// global pool, for servers that size and manage their own threads
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+pub fn in_pool<R, F>(pool: &rayon::ThreadPool, f: F) -> R
+where
+    R: Send,
+    F: FnOnce() -> R + Send,
+{
+    pool.install(f)
+}
+
 /// BigUint to fixed array
-pub(crate) fn b2fa(b: &BigInteger, expected_size: usize) -> Vec<u8> {
-    let mut t = vec![0u8; expected_size];
+pub(crate) fn b2fa(b: &BigInteger, expected_size: usize) -> Result<Vec<u8>, AccumulatorError> {
     let bt = b.to_bytes();
-    assert!(
-        expected_size >= bt.len(),
-        format!("expected = {}, found = {}", expected_size, bt.len())
-    );
+    if bt.len() > expected_size {
+        return Err(AccumulatorErrorKind::InvalidLength {
+            expected: expected_size,
+            actual: bt.len(),
+        }
+        .into());
+    }
+    let mut t = vec![0u8; expected_size];
     t[(expected_size - bt.len())..].clone_from_slice(bt.as_slice());
-    t
+    Ok(t)
+}
+
+/// Parse a group element of a proof. Elements are units mod the
+/// modulus so zero is rejected before the modulus is known
+pub(crate) fn proof_element(data: &[u8]) -> Result<BigInteger, AccumulatorError> {
+    let e = BigInteger::try_from(data)?;
+    if e == BigInteger::default() {
+        return Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::SerializationError,
+            "proof element is zero",
+        ));
+    }
+    Ok(e)
+}
+
+/// Parse the prime representative of a member. Primes used as members
+/// are odd so zero, one and even values are rejected
+pub(crate) fn member_element(data: &[u8]) -> Result<BigInteger, AccumulatorError> {
+    let x = BigInteger::try_from(data)?;
+    let odd = data.last().is_some_and(|b| b & 1 == 1);
+    if x <= BigInteger::from(1u32) || !odd {
+        return Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::SerializationError,
+            "member is not an odd prime representative",
+        ));
+    }
+    Ok(x)
+}
+
+/// Check every one of `elements` is in `[1, n)`
+pub(crate) fn in_group(elements: &[&BigInteger], n: &BigInteger) -> bool {
+    let zero = BigInteger::default();
+    elements.iter().all(|e| *e > &zero && *e < n)
+}
+
+/// 2^e
+#[cfg(feature = "std")]
+pub(crate) fn pow2(e: usize) -> Result<BigInteger, AccumulatorError> {
+    let mut bytes = vec![0u8; e / 8 + 1];
+    bytes[0] = 1 << (e % 8);
+    BigInteger::try_from(bytes.as_slice())
+}
+
+/// A uniform value in `[0, bound)` drawn from `rng`
+#[cfg(feature = "std")]
+pub(crate) fn random_below<R: RngCore + CryptoRng>(bound: &BigInteger, rng: &mut R) -> Result<BigInteger, AccumulatorError> {
+    let bits = bound.bits();
+    let mut bytes = vec![0u8; bits.div_ceil(8)];
+    loop {
+        rng.fill_bytes(&mut bytes);
+        // Clear the bits above the bound so at least half the draws are kept
+        bytes[0] &= 0xff >> (bytes.len() * 8 - bits);
+        let r = BigInteger::try_from(bytes.as_slice());
+        if r.as_ref().map_or(true, |r| r < bound) {
+            bytes.zeroize();
+            return r;
+        }
+    }
 }
 
-pub(crate) fn hashed_generator<B: AsRef<[u8]>>(u: &BigInteger, a: &BigInteger, n: &BigInteger, nonce: B) -> BigInteger {
+pub(crate) fn hashed_generator<B: AsRef<[u8]>>(
+    u: &BigInteger,
+    a: &BigInteger,
+    n: &BigInteger,
+    nonce: B,
+) -> Result<BigInteger, AccumulatorError> {
     let mut transcript = u.to_bytes();
     transcript.append(&mut a.to_bytes());
     transcript.extend_from_slice(nonce.as_ref());
@@ -79,20 +368,88 @@ pub(crate) fn hashed_generator<B: AsRef<[u8]>>(u: &BigInteger, a: &BigInteger, n
     hash_to_generator(transcript.as_slice(), &n)
 }
 
+/// The nonce given to the Fiat-Shamir hash of membership and non-membership
+/// proofs. Binds the accumulator `epoch`, the `registry_id` and an application
+/// `context` so a proof for another state or registry can't verify even if
+/// the values happen to match
+pub(crate) fn bound_nonce(epoch: u64, registry_id: &[u8], context: &[u8], nonce: &[u8]) -> Vec<u8> {
+    let mut transcript = epoch.to_be_bytes().to_vec();
+    for field in &[registry_id, context, nonce] {
+        transcript.extend_from_slice(&(field.len() as u64).to_be_bytes());
+        transcript.extend_from_slice(field);
+    }
+    transcript
+}
+
+/// How large the challenges of membership and non-membership proofs are.
+/// A PoKE2 with its challenge prime drawn from the primes of λ bits is
+/// sound to about λ bits, section 3.3 in <https://eprint.iacr.org/2018/1188.pdf>,
+/// so proofs only need 128 bit challenges for 128 bit security.
+///
+/// Only the challenge prime and the remainder r shrink, the group elements
+/// u, z and Q stay the size of the modulus. A compact membership proof is
+/// 16 bytes shorter and a compact non-membership proof 32 bytes shorter.
+/// Compact proofs are written with format version `VERSION_COMPACT` and
+/// CBOR and protobuf carry the same version next to the proof
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum ProofSize {
+    /// A `MEMBER_SIZE_BITS` challenge prime and 512 bit α
+    #[default]
+    Standard,
+    /// A 128 bit challenge prime and α, the remainder r takes half the bytes
+    Compact,
+}
+
+impl ProofSize {
+    /// The bytes of the challenge prime and so the remainder r
+    pub(crate) const fn challenge_bytes(self) -> usize {
+        match self {
+            ProofSize::Standard => MEMBER_SIZE,
+            ProofSize::Compact => 16,
+        }
+    }
+
+    /// The format version proofs of this size are written with
+    pub(crate) const fn version(self) -> u8 {
+        match self {
+            ProofSize::Standard => format::VERSION,
+            ProofSize::Compact => format::VERSION_COMPACT,
+        }
+    }
+
+    /// The size of proofs written with format `version`
+    pub(crate) fn from_version(version: u8) -> Result<Self, AccumulatorError> {
+        match version {
+            format::VERSION => Ok(ProofSize::Standard),
+            format::VERSION_COMPACT => Ok(ProofSize::Compact),
+            _ => Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::SerializationError,
+                format_args!("unsupported format version {}", version),
+            )),
+        }
+    }
+}
+
 /// Represents a Proof of Knowledge of Exponents 2 from section 3.2 in
 /// <https://eprint.iacr.org/2018/1188.pdf>
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone)]
 pub(crate) struct Poke2Proof {
     u: BigInteger,
     z: BigInteger,
     q: BigInteger,
     r: BigInteger,
+    size: ProofSize,
 }
 
 impl Poke2Proof {
     /// The size of this proof serialized
     pub const SIZE_BYTES: usize = 6 * FACTOR_SIZE + MEMBER_SIZE;
 
+    /// The size of a proof of `size` serialized
+    pub(crate) const fn size_bytes(size: ProofSize) -> usize {
+        6 * FACTOR_SIZE + size.challenge_bytes()
+    }
+
     /// Create a new proof of knowledge of exponents as described in
     /// Appendix D from
     /// <https://eprint.iacr.org/2018/1188.pdf>
@@ -102,9 +459,9 @@ impl Poke2Proof {
         a: &BigInteger,
         n: &BigInteger,
         nonce: B,
-    ) -> Self {
+    ) -> Result<Self, AccumulatorError> {
         let nonce = nonce.as_ref();
-        let g = hashed_generator(u, a, n, nonce);
+        let g = hashed_generator(u, a, n, nonce)?;
         Self::create(x, u, a, &g, n, nonce)
     }
 
@@ -116,37 +473,58 @@ impl Poke2Proof {
         g: &BigInteger,
         n: &BigInteger,
         nonce: B,
-    ) -> Self {
-        let f = common::Field::new(n);
-        let z = f.exp(&g, x);
-        let (l, alpha) = Self::get_prime_and_alpha(&u, &a, &z, nonce.as_ref());
-
-        // q = x / l
-        // r = x % l
-        let (whole, r) = BigInteger::div_rem(&x, &l);
+    ) -> Result<Self, AccumulatorError> {
+        Self::create_in(&common::Field::new(n), x, u, a, g, nonce)
+    }
 
-        // Q = u ^ q * g ^ {q * alpha}
-        let q = f.mul(&f.exp(&u, &whole), &f.exp(&g, &(&alpha * &whole)));
-        Self {
-            u: u.clone(),
-            q,
-            r,
-            z,
-        }
+    /// Same as `create` with the arithmetic done in `f`, so proofs
+    /// against one modulus can share it
+    pub(crate) fn create_in<B: AsRef<[u8]>>(
+        f: &common::Field,
+        x: &BigInteger,
+        u: &BigInteger,
+        a: &BigInteger,
+        g: &BigInteger,
+        nonce: B,
+    ) -> Result<Self, AccumulatorError> {
+        Poke2Committing::new(f, x, u, a, g)?.respond(f, nonce.as_ref(), ProofSize::Standard)
     }
 
     /// Verify a proof of knowledge of exponents
     pub fn verify<B: AsRef<[u8]>>(&self, value: &BigInteger, n: &BigInteger, nonce: B) -> bool {
         let nonce = nonce.as_ref();
-        let g = hashed_generator(&self.u, &value, &n, nonce);
-        self.check(&g, &value, &n, nonce)
+        self.verify_with_challenge(value, n, nonce, nonce)
+    }
+
+    /// Same as `verify` but the prime and alpha are hashed from `challenge`
+    /// instead of the nonce the generator was hashed from
+    pub(crate) fn verify_with_challenge(
+        &self,
+        value: &BigInteger,
+        n: &BigInteger,
+        nonce: &[u8],
+        challenge: &[u8],
+    ) -> bool {
+        match hashed_generator(&self.u, value, n, nonce) {
+            Ok(g) => self.check(&g, value, n, challenge),
+            Err(_) => false,
+        }
     }
 
     /// Same as `verify` but allow custom `g`
     pub fn check<B: AsRef<[u8]>>(&self, g: &BigInteger, value: &BigInteger, n: &BigInteger, nonce: B) -> bool {
+        if !in_group(&[&self.u, &self.z, &self.q], n) {
+            return false;
+        }
         let f = common::Field::new(n);
         let nonce = nonce.as_ref();
-        let (l, alpha) = Self::get_prime_and_alpha(&self.u, &value, &self.z, nonce);
+        let (l, alpha) = match Self::get_prime_and_alpha(&self.u, value, &self.z, nonce, self.size) {
+            Ok(r) => r,
+            Err(_) => return false,
+        };
+        if self.r >= l {
+            return false;
+        }
 
         // Q ^ l
         // let p1 = f.exp(&self.q, &l);
@@ -158,7 +536,7 @@ impl Poke2Proof {
 
         // Q^l * u^r * g^{x * r}
         // let left = f.mul(&p1, &f.mul(&p2, &p3));
-        let left = f.mul(&f.mul(&f.exp(&self.q, &l), &f.exp(&self.u, &self.r)), &f.exp(g, &(&alpha * &self.r)));
+        let left = f.multi_exp(&[(&self.q, &l), (&self.u, &self.r), (g, &(&alpha * &self.r))]);
 
         // v * z^x
         let right = f.mul(&value, &f.exp(&self.z, &alpha));
@@ -167,28 +545,126 @@ impl Poke2Proof {
     }
 
     /// Serialize this to bytes
-    pub fn to_bytes(&self) -> Vec<u8> {
-        let mut output = b2fa(&self.u, 2 * FACTOR_SIZE);
-        output.append(&mut b2fa(&self.z, 2 * FACTOR_SIZE));
-        output.append(&mut b2fa(&self.q, 2 * FACTOR_SIZE));
-        output.append(&mut b2fa(&self.r, MEMBER_SIZE));
-        output
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = b2fa(&self.u, 2 * FACTOR_SIZE)?;
+        output.append(&mut b2fa(&self.z, 2 * FACTOR_SIZE)?);
+        output.append(&mut b2fa(&self.q, 2 * FACTOR_SIZE)?);
+        output.append(&mut b2fa(&self.r, self.size.challenge_bytes())?);
+        Ok(output)
     }
 
-    fn get_prime_and_alpha(u: &BigInteger, a: &BigInteger, z: &BigInteger, nonce: &[u8]) -> (BigInteger, BigInteger) {
+    /// Write the same bytes as `to_bytes` to `w`
+    #[cfg(feature = "std")]
+    pub fn write_bytes<W: Write>(&self, mut w: W) -> Result<(), AccumulatorError> {
+        for e in &[&self.u, &self.z, &self.q] {
+            write_element(&mut w, e, 2 * FACTOR_SIZE)?;
+        }
+        write_element(&mut w, &self.r, self.size.challenge_bytes())
+    }
+
+    /// Parse a proof of `size` written by `to_bytes`
+    pub(crate) fn parse(data: &[u8], size: ProofSize) -> Result<Self, AccumulatorError> {
+        if data.len() != Self::size_bytes(size) {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: Self::size_bytes(size),
+                actual: data.len(),
+            }
+            .into());
+        }
+        let u = proof_element(&data[..(2 * FACTOR_SIZE)])?;
+        let z = proof_element(&data[(2 * FACTOR_SIZE)..(4 * FACTOR_SIZE)])?;
+        let q = proof_element(&data[(4 * FACTOR_SIZE)..(6 * FACTOR_SIZE)])?;
+        let r = BigInteger::try_from(&data[(6 * FACTOR_SIZE)..])?;
+        Ok(Self { u, z, q, r, size })
+    }
+
+    fn get_prime_and_alpha(
+        u: &BigInteger,
+        a: &BigInteger,
+        z: &BigInteger,
+        nonce: &[u8],
+        size: ProofSize,
+    ) -> Result<(BigInteger, BigInteger), AccumulatorError> {
         let mut data = u.to_bytes();
         data.append(&mut a.to_bytes());
         data.append(&mut z.to_bytes());
         data.extend_from_slice(nonce);
 
         // l = H2P( u || A || z || n1 )
-        let l = hash_to_prime(data.as_slice());
+        let l = match size {
+            ProofSize::Standard => hash_to_prime(data.as_slice()),
+            ProofSize::Compact => hash_to_prime_bytes(data.as_slice(), size.challenge_bytes()),
+        };
 
         data.append(&mut l.to_bytes());
         // Fiat-Shamir
         // alpha = H(u || A || z || n1 || l)
-        let alpha = BigInteger::try_from(Blake2b::digest(data.as_slice()).as_slice()).unwrap();
-        (l, alpha)
+        let digest = Blake2b::digest(data.as_slice());
+        let alpha = match size {
+            ProofSize::Standard => BigInteger::try_from(digest.as_slice())?,
+            ProofSize::Compact => BigInteger::try_from(&digest[..size.challenge_bytes()])?,
+        };
+        Ok((l, alpha))
+    }
+}
+
+/// A proof of knowledge of exponents after the prover committed to
+/// `z = g^x`, waiting for the challenge
+#[derive(Debug, Clone)]
+pub(crate) struct Poke2Committing {
+    x: BigInteger,
+    pub(crate) u: BigInteger,
+    a: BigInteger,
+    g: BigInteger,
+    pub(crate) z: BigInteger,
+}
+
+impl Poke2Committing {
+    /// Commit to `x` where `u^x = a`
+    pub(crate) fn new(
+        f: &common::Field,
+        x: &BigInteger,
+        u: &BigInteger,
+        a: &BigInteger,
+        g: &BigInteger,
+    ) -> Result<Self, AccumulatorError> {
+        // `exp` inverts the base for a negative exponent, check u and g
+        // have inverses so that can't fail
+        if x < &BigInteger::default() {
+            f.inv(u)?;
+            f.inv(g)?;
+        }
+        Ok(Self {
+            x: x.clone(),
+            u: u.clone(),
+            a: a.clone(),
+            g: g.clone(),
+            z: f.exp(g, x),
+        })
+    }
+
+    /// The response to `challenge` with challenges of `size`
+    pub(crate) fn respond(
+        &self,
+        f: &common::Field,
+        challenge: &[u8],
+        size: ProofSize,
+    ) -> Result<Poke2Proof, AccumulatorError> {
+        let (l, alpha) = Poke2Proof::get_prime_and_alpha(&self.u, &self.a, &self.z, challenge, size)?;
+
+        // q = x / l
+        // r = x % l
+        let (whole, r) = BigInteger::div_rem(&self.x, &l);
+
+        // Q = u ^ q * g ^ {q * alpha}
+        let q = f.multi_exp(&[(&self.u, &whole), (&self.g, &(&alpha * &whole))]);
+        Ok(Poke2Proof {
+            u: self.u.clone(),
+            q,
+            r,
+            z: self.z.clone(),
+            size,
+        })
     }
 }
 
@@ -196,14 +672,7 @@ impl TryFrom<&[u8]> for Poke2Proof {
     type Error = AccumulatorError;
 
     fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
-        if data.len() != Self::SIZE_BYTES {
-            return Err(AccumulatorErrorKind::SerializationError.into());
-        }
-        let u = BigInteger::try_from(&data[..(2 * FACTOR_SIZE)])?;
-        let z = BigInteger::try_from(&data[(2 * FACTOR_SIZE)..(4 * FACTOR_SIZE)])?;
-        let q = BigInteger::try_from(&data[(4 * FACTOR_SIZE)..(6 * FACTOR_SIZE)])?;
-        let r = BigInteger::try_from(&data[(6 * FACTOR_SIZE)..])?;
-        Ok(Self { u, z, q, r })
+        Self::parse(data, ProofSize::Standard)
     }
 }
 
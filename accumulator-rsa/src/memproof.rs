@@ -0,0 +1,664 @@
+use crate::{
+    accumulator::{Accumulator, AccumulatorPublic, PublicState},
+    b2fa, bound_nonce, in_group, proof_element,
+    common::{bigint::BigInteger, error::*},
+    format::{Header, Tag},
+    hash::{derive_nonce, hash_to_prime},
+    hashed_generator,
+    memwitness::MembershipWitness,
+    sigma::{ProofChallenge, ProofCommitting},
+    store::MemberStore,
+    telemetry, Poke2Proof, ProofSize, FACTOR_SIZE, MEMBER_SIZE,
+};
+use crate::par::*;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use blake2::{digest::Digest, Blake2b};
+use core::convert::TryFrom;
+#[cfg(feature = "std")]
+use crate::format::write_element;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+/// A proof of knowledge of exponents membership proof
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct MembershipProof(pub(crate) Poke2Proof);
+
+impl MembershipProof {
+    /// Create a new PoKE2 proof bound to the accumulator epoch.
+    /// Fails if `witness` is stale, see `MembershipWitness::refresh`
+    pub fn new<S, B: AsRef<[u8]>>(
+        witness: &MembershipWitness,
+        accumulator: &Accumulator<S>,
+        nonce: B,
+    ) -> Result<Self, AccumulatorError> {
+        Self::new_with_context(witness, accumulator, &[], &[], nonce)
+    }
+
+    /// Create a new PoKE2 proof bound to the accumulator epoch,
+    /// the registry `registry_id` and an application `context`
+    pub fn new_with_context<S, B: AsRef<[u8]>>(
+        witness: &MembershipWitness,
+        accumulator: &Accumulator<S>,
+        registry_id: &[u8],
+        context: &[u8],
+        nonce: B,
+    ) -> Result<Self, AccumulatorError> {
+        let nonce = bound_nonce(accumulator.epoch, registry_id, context, nonce.as_ref());
+        let committing = ProofCommitting::<Self>::with_bound_nonce(witness, accumulator, &nonce)?;
+        Ok(committing.respond(&ProofChallenge::new(nonce))?.into_proof())
+    }
+
+    /// Same as `new` with 128 bit challenges, see `ProofSize::Compact`
+    pub fn new_compact<S, B: AsRef<[u8]>>(
+        witness: &MembershipWitness,
+        accumulator: &Accumulator<S>,
+        nonce: B,
+    ) -> Result<Self, AccumulatorError> {
+        let nonce = bound_nonce(accumulator.epoch, &[], &[], nonce.as_ref());
+        let committing = ProofCommitting::<Self>::with_bound_nonce(witness, accumulator, &nonce)?;
+        Ok(committing
+            .with_size(ProofSize::Compact)
+            .respond(&ProofChallenge::new(nonce))?
+            .into_proof())
+    }
+
+    /// The size of the proof's challenges
+    pub fn size(&self) -> ProofSize {
+        self.0.size
+    }
+
+    /// Create proofs for many holders at once, each witness with its own
+    /// nonce. With the `parallel` feature the proofs are made in parallel
+    /// and each thread sets up the modular arithmetic once for all of its
+    /// proofs instead of once per proof
+    pub fn new_many<S, B: AsRef<[u8]> + Sync>(
+        holders: &[(MembershipWitness, B)],
+        accumulator: &Accumulator<S>,
+    ) -> Result<Vec<Self>, AccumulatorError> {
+        let (value, n, epoch) = (&accumulator.value, accumulator.modulus.as_ref(), accumulator.epoch);
+        holders
+            .par_iter()
+            .map_init(
+                || common::Field::new(n),
+                |f, (witness, nonce)| {
+                    witness.freshness.check(epoch, value)?;
+                    let nonce = bound_nonce(epoch, &[], &[], nonce.as_ref());
+                    let g = hashed_generator(&witness.u, value, n, &nonce)?;
+                    Poke2Proof::create_in(f, &witness.x, &witness.u, value, &g, &nonce).map(Self)
+                },
+            )
+            .collect()
+    }
+
+    /// Verify a set membership proof
+    pub fn verify<P: PublicState, B: AsRef<[u8]>>(&self, accumulator: &P, nonce: B) -> bool {
+        self.verify_with_context(accumulator, &[], &[], nonce)
+    }
+
+    /// Verify a set membership proof made for the accumulator epoch,
+    /// `registry_id` and `context`
+    pub fn verify_with_context<P: PublicState, B: AsRef<[u8]>>(
+        &self,
+        accumulator: &P,
+        registry_id: &[u8],
+        context: &[u8],
+        nonce: B,
+    ) -> bool {
+        telemetry::verify("membership", || {
+            self.0.verify(
+                accumulator.value(),
+                accumulator.modulus(),
+                bound_nonce(accumulator.epoch(), registry_id, context, nonce.as_ref()),
+            )
+        })
+    }
+
+    /// Create a proof for `verifier_id` with the nonce derived from the
+    /// accumulator value and epoch, see `hash::derive_nonce`
+    pub fn new_for_verifier<S>(
+        witness: &MembershipWitness,
+        accumulator: &Accumulator<S>,
+        verifier_id: &[u8],
+    ) -> Result<Self, AccumulatorError> {
+        let nonce = derive_nonce(&accumulator.value, accumulator.epoch, verifier_id, &witness.u);
+        Self::new(witness, accumulator, nonce)
+    }
+
+    /// Verify a proof made by `new_for_verifier` for `verifier_id`
+    pub fn verify_for_verifier<P: PublicState>(&self, accumulator: &P, verifier_id: &[u8]) -> bool {
+        let nonce = derive_nonce(accumulator.value(), accumulator.epoch(), verifier_id, &self.0.u);
+        self.verify(accumulator, nonce)
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Header::with_size(Tag::MembershipProof, self.0.size).to_bytes();
+        output.append(&mut self.0.to_bytes()?);
+        Ok(output)
+    }
+
+    /// Write the same bytes as `to_bytes` to `w`
+    #[cfg(feature = "std")]
+    pub fn write_bytes<W: Write>(&self, mut w: W) -> Result<(), AccumulatorError> {
+        Header::with_size(Tag::MembershipProof, self.0.size).write_to(&mut w)?;
+        self.0.write_bytes(w)
+    }
+}
+
+impl TryFrom<&[u8]> for MembershipProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let (data, size) = Header::check_sized(data, Tag::MembershipProof)?;
+        let proof = Poke2Proof::parse(data, size)?;
+        Ok(Self(proof))
+    }
+}
+
+serdes_impl!(MembershipProof);
+hash_ord_impl!(MembershipProof; 0);
+hex_impl!(MembershipProof);
+
+/// A proof that the same hidden value is a member of two accumulators,
+/// which may use different moduli. Both PoKE2 instances share one challenge
+/// and one remainder `r` which ties them to the same exponent
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct LinkedMembershipProof {
+    u: [BigInteger; 2],
+    z: [BigInteger; 2],
+    q: [BigInteger; 2],
+    r: BigInteger,
+}
+
+impl LinkedMembershipProof {
+    /// The size of this proof serialized without the header
+    pub const SIZE_BYTES: usize = 12 * FACTOR_SIZE + MEMBER_SIZE;
+
+    /// Create a proof that the member of `witness_a` in `accumulator_a`
+    /// is the member of `witness_b` in `accumulator_b`
+    pub fn new<B: AsRef<[u8]>>(
+        witness_a: &MembershipWitness,
+        accumulator_a: &AccumulatorPublic,
+        witness_b: &MembershipWitness,
+        accumulator_b: &AccumulatorPublic,
+        nonce: B,
+    ) -> Result<Self, AccumulatorError> {
+        if witness_a.x != witness_b.x {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "witnesses are for different members",
+            ));
+        }
+        witness_a.freshness.check(accumulator_a.epoch, &accumulator_a.value)?;
+        witness_b.freshness.check(accumulator_b.epoch, &accumulator_b.value)?;
+        let nonce = nonce.as_ref();
+        let x = &witness_a.x;
+        let accumulators = [accumulator_a, accumulator_b];
+        let u = [witness_a.u.clone(), witness_b.u.clone()];
+        let mut g = Vec::with_capacity(2);
+        let mut z = Vec::with_capacity(2);
+        for (acc, u) in accumulators.iter().zip(u.iter()) {
+            let gi = hashed_generator(u, &acc.value, &acc.modulus, nonce)?;
+            z.push(common::Field::new(&acc.modulus).exp(&gi, x));
+            g.push(gi);
+        }
+        let z = [z[0].clone(), z[1].clone()];
+        let (l, alpha) = Self::get_prime_and_alpha(&u, &accumulators, &z, nonce)?;
+
+        // q = x / l
+        // r = x % l
+        let (whole, r) = BigInteger::div_rem(x, &l);
+        let exp = &alpha * &whole;
+
+        // Q = u ^ q * g ^ {q * alpha} in each group
+        let q = [
+            common::Field::new(&accumulator_a.modulus).multi_exp(&[(&u[0], &whole), (&g[0], &exp)]),
+            common::Field::new(&accumulator_b.modulus).multi_exp(&[(&u[1], &whole), (&g[1], &exp)]),
+        ];
+        Ok(Self { u, z, q, r })
+    }
+
+    /// Verify both accumulators contain the same hidden member
+    pub fn verify<B: AsRef<[u8]>>(
+        &self,
+        accumulator_a: &AccumulatorPublic,
+        accumulator_b: &AccumulatorPublic,
+        nonce: B,
+    ) -> bool {
+        telemetry::verify("linked_membership", || {
+            self.check(accumulator_a, accumulator_b, nonce.as_ref())
+        })
+    }
+
+    fn check(
+        &self,
+        accumulator_a: &AccumulatorPublic,
+        accumulator_b: &AccumulatorPublic,
+        nonce: &[u8],
+    ) -> bool {
+        let accumulators = [accumulator_a, accumulator_b];
+        let (l, alpha) = match Self::get_prime_and_alpha(&self.u, &accumulators, &self.z, nonce) {
+            Ok(r) => r,
+            Err(_) => return false,
+        };
+        if self.r >= l {
+            return false;
+        }
+        let exp = &alpha * &self.r;
+        accumulators.iter().enumerate().all(|(i, acc)| {
+            if !in_group(&[&self.u[i], &self.z[i], &self.q[i]], &acc.modulus) {
+                return false;
+            }
+            let g = match hashed_generator(&self.u[i], &acc.value, &acc.modulus, nonce) {
+                Ok(g) => g,
+                Err(_) => return false,
+            };
+            let f = common::Field::new(&acc.modulus);
+            // Q^l * u^r * g^{alpha * r} == A * z^alpha
+            let left = f.multi_exp(&[(&self.q[i], &l), (&self.u[i], &self.r), (&g, &exp)]);
+            let right = f.mul(&acc.value, &f.exp(&self.z[i], &alpha));
+            left == right
+        })
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Header::new(Tag::LinkedMembershipProof).to_bytes();
+        for i in 0..2 {
+            output.append(&mut b2fa(&self.u[i], 2 * FACTOR_SIZE)?);
+            output.append(&mut b2fa(&self.z[i], 2 * FACTOR_SIZE)?);
+            output.append(&mut b2fa(&self.q[i], 2 * FACTOR_SIZE)?);
+        }
+        output.append(&mut b2fa(&self.r, MEMBER_SIZE)?);
+        Ok(output)
+    }
+
+    /// Write the same bytes as `to_bytes` to `w`
+    #[cfg(feature = "std")]
+    pub fn write_bytes<W: Write>(&self, mut w: W) -> Result<(), AccumulatorError> {
+        Header::new(Tag::LinkedMembershipProof).write_to(&mut w)?;
+        for i in 0..2 {
+            for e in &[&self.u[i], &self.z[i], &self.q[i]] {
+                write_element(&mut w, e, 2 * FACTOR_SIZE)?;
+            }
+        }
+        write_element(&mut w, &self.r, MEMBER_SIZE)
+    }
+
+    /// l = H2P( u_a || A || z_a || u_b || B || z_b || nonce )
+    /// alpha = H( u_a || A || z_a || u_b || B || z_b || nonce || l )
+    fn get_prime_and_alpha(
+        u: &[BigInteger; 2],
+        accumulators: &[&AccumulatorPublic; 2],
+        z: &[BigInteger; 2],
+        nonce: &[u8],
+    ) -> Result<(BigInteger, BigInteger), AccumulatorError> {
+        let mut data = Vec::new();
+        for i in 0..2 {
+            data.append(&mut u[i].to_bytes());
+            data.append(&mut accumulators[i].value.to_bytes());
+            data.append(&mut z[i].to_bytes());
+        }
+        data.extend_from_slice(nonce);
+        let l = hash_to_prime(data.as_slice());
+
+        data.append(&mut l.to_bytes());
+        let alpha = BigInteger::try_from(Blake2b::digest(data.as_slice()).as_slice())?;
+        Ok((l, alpha))
+    }
+}
+
+impl TryFrom<&[u8]> for LinkedMembershipProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::LinkedMembershipProof)?;
+        if data.len() != Self::SIZE_BYTES {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: Self::SIZE_BYTES,
+                actual: data.len(),
+            }
+            .into());
+        }
+        let value = |i: usize| proof_element(&data[(2 * FACTOR_SIZE * i)..(2 * FACTOR_SIZE * (i + 1))]);
+        Ok(Self {
+            u: [value(0)?, value(3)?],
+            z: [value(1)?, value(4)?],
+            q: [value(2)?, value(5)?],
+            r: BigInteger::try_from(&data[(12 * FACTOR_SIZE)..])?,
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for LinkedMembershipProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+serdes_impl!(LinkedMembershipProof);
+hash_ord_impl!(LinkedMembershipProof; u, z, q, r);
+hex_impl!(LinkedMembershipProof);
+
+/// A proof that every member of one accumulator is also a member of another.
+/// Both must start from the same generator and modulus, then the superset
+/// value is the subset value raised to the product of the remaining members
+/// and a PoKE2 proof shows the prover knows that exponent
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct SubsetProof(pub(crate) Poke2Proof);
+
+impl SubsetProof {
+    /// Create a proof that the members of `subset` are all in `superset`
+    pub fn new<S: MemberStore, T: MemberStore, B: AsRef<[u8]>>(
+        subset: &Accumulator<S>,
+        superset: &Accumulator<T>,
+        nonce: B,
+    ) -> Result<Self, AccumulatorError> {
+        if subset.modulus != superset.modulus || subset.generator != superset.generator {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::ParameterMismatch,
+                "accumulators have different generators or moduli",
+            ));
+        }
+        for m in subset.members.iter() {
+            if !superset.members.contains(&m?)? {
+                return Err(AccumulatorError::from_msg(
+                    AccumulatorErrorKind::InvalidMemberSupplied,
+                    "subset has a value that is not in the superset",
+                ));
+            }
+        }
+        let mut k = BigInteger::from(1u32);
+        for m in superset.members.iter() {
+            let m = m?;
+            if !subset.members.contains(&m)? {
+                k *= &m;
+            }
+        }
+        let proof = Poke2Proof::new(&k, &subset.value, &superset.value, &superset.modulus, nonce)?;
+        Ok(Self(proof))
+    }
+
+    /// Verify every member of `subset` is in `superset`
+    pub fn verify<B: AsRef<[u8]>>(
+        &self,
+        subset: &AccumulatorPublic,
+        superset: &AccumulatorPublic,
+        nonce: B,
+    ) -> bool {
+        telemetry::verify("subset", || {
+            subset.modulus == superset.modulus
+                && subset.generator == superset.generator
+                && self.0.u == subset.value
+                && self.0.verify(&superset.value, &superset.modulus, nonce)
+        })
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Header::new(Tag::SubsetProof).to_bytes();
+        output.append(&mut self.0.to_bytes()?);
+        Ok(output)
+    }
+
+    /// Write the same bytes as `to_bytes` to `w`
+    #[cfg(feature = "std")]
+    pub fn write_bytes<W: Write>(&self, mut w: W) -> Result<(), AccumulatorError> {
+        Header::new(Tag::SubsetProof).write_to(&mut w)?;
+        self.0.write_bytes(w)
+    }
+}
+
+impl TryFrom<&[u8]> for SubsetProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::SubsetProof)?;
+        let proof = Poke2Proof::try_from(data)?;
+        Ok(Self(proof))
+    }
+}
+
+impl TryFrom<Vec<u8>> for SubsetProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+serdes_impl!(SubsetProof);
+hash_ord_impl!(SubsetProof; 0);
+hex_impl!(SubsetProof);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{format::HEADER_SIZE, key::AccumulatorSecretKey, MEMBER_SIZE_BITS};
+    use common::bigint::BigInteger;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn proof_test() {
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = vec![
+            3u64.to_be_bytes(),
+            7u64.to_be_bytes(),
+            11u64.to_be_bytes(),
+            13u64.to_be_bytes(),
+        ];
+        let mut acc = Accumulator::with_members(&key, &members).unwrap();
+        let witness = MembershipWitness::new(&acc, &members[0]).unwrap();
+        let nonce = b"proof_test";
+
+        let proof = MembershipProof::new(&witness, &acc, nonce).unwrap();
+        assert!(proof.verify(&acc, nonce));
+        acc.remove_assign(&key, &members[0]).unwrap();
+
+        assert!(!proof.verify(&acc, nonce));
+        assert_eq!(proof.to_bytes().unwrap().len(), HEADER_SIZE + Poke2Proof::SIZE_BYTES);
+    }
+
+    #[test]
+    fn compact() {
+        let key = AccumulatorSecretKey::default();
+        let acc = Accumulator::with_members(&key, ["a", "b", "c"]).unwrap();
+        let witness = MembershipWitness::new(&acc, "a").unwrap();
+        let proof = MembershipProof::new_compact(&witness, &acc, b"compact").unwrap();
+        assert_eq!(proof.size(), ProofSize::Compact);
+        assert!(proof.verify(&acc, b"compact"));
+        assert!(!proof.verify(&acc, b"other"));
+
+        let bytes = proof.to_bytes().unwrap();
+        let standard = MembershipProof::new(&witness, &acc, b"compact").unwrap().to_bytes().unwrap();
+        assert_eq!(bytes.len() + MEMBER_SIZE - 16, standard.len());
+        assert_eq!(bytes[4], crate::format::VERSION_COMPACT);
+        let copy = MembershipProof::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(copy, proof);
+        assert!(copy.verify(&acc, b"compact"));
+        assert!(Poke2Proof::try_from(&bytes[HEADER_SIZE..]).is_err());
+    }
+
+    #[test]
+    fn new_many_test() {
+        let key = AccumulatorSecretKey::default();
+        let members = ["a", "b", "c", "d"];
+        let acc = Accumulator::with_members(&key, members).unwrap();
+        let holders: Vec<(MembershipWitness, String)> = members
+            .iter()
+            .map(|m| (MembershipWitness::new(&acc, m).unwrap(), format!("nonce {}", m)))
+            .collect();
+
+        let proofs = MembershipProof::new_many(&holders, &acc).unwrap();
+        assert_eq!(proofs.len(), holders.len());
+        for (proof, (witness, nonce)) in proofs.iter().zip(&holders) {
+            assert_eq!(proof, &MembershipProof::new(witness, &acc, nonce).unwrap());
+            assert!(proof.verify(&acc, nonce));
+        }
+        assert!(MembershipProof::new_many::<_, &[u8]>(&[], &acc).unwrap().is_empty());
+    }
+
+    #[test]
+    fn range_checks() {
+        let key = AccumulatorSecretKey::default();
+        let acc = Accumulator::with_members(&key, ["a", "b"]).unwrap();
+        let witness = MembershipWitness::new(&acc, "a").unwrap();
+        let proof = MembershipProof::new(&witness, &acc, b"range").unwrap();
+        let bytes = proof.to_bytes().unwrap();
+
+        let mut zero = bytes.clone();
+        for b in &mut zero[(HEADER_SIZE + 2 * FACTOR_SIZE)..(HEADER_SIZE + 4 * FACTOR_SIZE)] {
+            *b = 0;
+        }
+        assert!(MembershipProof::try_from(zero.as_slice()).is_err());
+
+        // Parses but is not an element of the group
+        let mut large = bytes;
+        large[HEADER_SIZE..(HEADER_SIZE + 2 * FACTOR_SIZE)]
+            .copy_from_slice(&b2fa(&acc.modulus, 2 * FACTOR_SIZE).unwrap());
+        let large = MembershipProof::try_from(large.as_slice()).unwrap();
+        assert!(!large.verify(&acc, b"range"));
+    }
+
+    #[test]
+    fn context_test() {
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = vec![3u64.to_be_bytes(), 7u64.to_be_bytes(), 11u64.to_be_bytes()];
+        let acc = Accumulator::with_members(&key, &members).unwrap();
+        let witness = MembershipWitness::new(&acc, members[0]).unwrap();
+        let nonce = b"context_test";
+
+        let proof = MembershipProof::new_with_context(&witness, &acc, b"registry", b"login", nonce).unwrap();
+        assert!(proof.verify_with_context(&acc, b"registry", b"login", nonce));
+        assert!(!proof.verify_with_context(&acc, b"registry2", b"login", nonce));
+        assert!(!proof.verify_with_context(&acc, b"registry", b"payment", nonce));
+        assert!(!proof.verify(&acc, nonce));
+
+        // The same value with another epoch is a different state
+        let mut public = acc.public();
+        public.epoch += 1;
+        assert!(!proof.verify_with_context(&public, b"registry", b"login", nonce));
+    }
+
+    #[test]
+    fn verifier_nonce_test() {
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = vec![3u64.to_be_bytes(), 7u64.to_be_bytes(), 11u64.to_be_bytes()];
+        let mut acc = Accumulator::with_members(&key, &members).unwrap();
+        let witness = MembershipWitness::new(&acc, members[0]).unwrap();
+
+        let proof = MembershipProof::new_for_verifier(&witness, &acc, b"verifier").unwrap();
+        assert!(proof.verify_for_verifier(&acc, b"verifier"));
+        assert!(!proof.verify_for_verifier(&acc, b"another verifier"));
+
+        // Same value at a later epoch
+        acc.insert_assign(13u64.to_be_bytes()).unwrap();
+        acc.remove_assign(&key, 13u64.to_be_bytes()).unwrap();
+        assert!(!proof.verify_for_verifier(&acc, b"verifier"));
+    }
+
+    #[test]
+    fn linked_proof_test() {
+        let key_a = AccumulatorSecretKey::default();
+        let key_b = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = vec![3u64.to_be_bytes(), 7u64.to_be_bytes(), 11u64.to_be_bytes()];
+        let acc_a = Accumulator::with_members(&key_a, &members).unwrap();
+        let mut acc_b = Accumulator::with_members(&key_b, &members[1..]).unwrap();
+        let nonce = b"linked_proof_test";
+
+        let witness_a = MembershipWitness::new(&acc_a, members[1]).unwrap();
+        let witness_b = MembershipWitness::new(&acc_b, members[1]).unwrap();
+        let proof = LinkedMembershipProof::new(&witness_a, &acc_a.public(), &witness_b, &acc_b.public(), nonce).unwrap();
+        assert!(proof.verify(&acc_a.public(), &acc_b.public(), nonce));
+        assert!(!proof.verify(&acc_b.public(), &acc_a.public(), nonce));
+        assert!(!proof.verify(&acc_a.public(), &acc_b.public(), b"another nonce"));
+
+        let bytes = proof.to_bytes().unwrap();
+        assert_eq!(bytes.len(), HEADER_SIZE + LinkedMembershipProof::SIZE_BYTES);
+        assert_eq!(LinkedMembershipProof::try_from(bytes).unwrap(), proof);
+
+        let other = MembershipWitness::new(&acc_b, members[2]).unwrap();
+        assert!(LinkedMembershipProof::new(&witness_a, &acc_a.public(), &other, &acc_b.public(), nonce).is_err());
+
+        acc_b.remove_assign(&key_b, members[1]).unwrap();
+        assert!(!proof.verify(&acc_a.public(), &acc_b.public(), nonce));
+    }
+
+    #[test]
+    fn linked_proof_two_keys() {
+        let key_a = AccumulatorSecretKey::default();
+        let key_b = AccumulatorSecretKey::from_primes(BigInteger::from(23u32), BigInteger::from(47u32));
+        let members: Vec<[u8; 8]> = vec![3u64.to_be_bytes(), 7u64.to_be_bytes(), 11u64.to_be_bytes()];
+        let acc_a = Accumulator::with_members(&key_a, &members).unwrap();
+        let acc_b = Accumulator::with_members(&key_b, &members[..2]).unwrap();
+        assert_ne!(acc_a.modulus, acc_b.modulus);
+        let nonce = b"linked_proof_two_keys";
+
+        let witness_a = MembershipWitness::new(&acc_a, members[0]).unwrap();
+        let witness_b = MembershipWitness::new(&acc_b, members[0]).unwrap();
+        let proof = LinkedMembershipProof::new(&witness_a, &acc_a.public(), &witness_b, &acc_b.public(), nonce).unwrap();
+        assert!(proof.verify(&acc_a.public(), &acc_b.public(), nonce));
+        assert!(!proof.verify(&acc_b.public(), &acc_a.public(), nonce));
+        let proof = LinkedMembershipProof::try_from(proof.to_bytes().unwrap()).unwrap();
+        assert!(proof.verify(&acc_a.public(), &acc_b.public(), nonce));
+
+        // The same accumulator under the other modulus
+        let mut wrong = acc_b.public();
+        wrong.modulus = acc_a.public().modulus;
+        assert!(!proof.verify(&acc_a.public(), &wrong, nonce));
+    }
+
+    #[test]
+    fn subset_proof_test() {
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = vec![3u64.to_be_bytes(), 7u64.to_be_bytes(), 11u64.to_be_bytes()];
+        let mut superset = Accumulator::with_members(&key, &members).unwrap();
+        let mut public = superset.public();
+        public.value = public.generator.clone();
+        public.epoch = 0;
+        let mut subset = Accumulator::from_parts(public, BTreeSet::new());
+        subset.insert_assign(members[0]).unwrap();
+        subset.insert_assign(members[2]).unwrap();
+        let nonce = b"subset_proof_test";
+
+        let proof = SubsetProof::new(&subset, &superset, nonce).unwrap();
+        assert!(proof.verify(&subset.public(), &superset.public(), nonce));
+        assert!(!proof.verify(&superset.public(), &subset.public(), nonce));
+        assert!(!proof.verify(&subset.public(), &superset.public(), b"another nonce"));
+
+        let bytes = proof.to_bytes().unwrap();
+        assert_eq!(bytes.len(), HEADER_SIZE + Poke2Proof::SIZE_BYTES);
+        assert_eq!(SubsetProof::try_from(bytes).unwrap(), proof);
+
+        assert!(SubsetProof::new(&superset, &subset, nonce).is_err());
+        let mut public = subset.public();
+        public.generator = BigInteger::from(4u32);
+        assert!(SubsetProof::new(&Accumulator::from_parts(public, BTreeSet::new()), &superset, nonce).is_err());
+
+        superset.remove_assign(&key, members[2]).unwrap();
+        assert!(!proof.verify(&subset.public(), &superset.public(), nonce));
+        assert!(SubsetProof::new(&subset, &superset, nonce).is_err());
+    }
+
+    #[test]
+    fn big_proof_test() {
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<BigInteger> = (0..1_000)
+            .collect::<Vec<_>>()
+            .par_iter()
+            .map(|_| BigInteger::generate_prime(MEMBER_SIZE_BITS))
+            .collect();
+        let mut acc = Accumulator::with_prime_members(&key, &members).unwrap();
+        let witness = MembershipWitness::new_prime(&acc, &members[0]).unwrap();
+        let nonce = b"big_proof_test";
+
+        let proof = MembershipProof::new(&witness, &acc, nonce).unwrap();
+        assert!(proof.verify(&acc, nonce));
+        acc.remove_prime_assign(&key, &members[0]).unwrap();
+
+        assert!(!proof.verify(&acc, nonce));
+    }
+}
@@ -0,0 +1,450 @@
+use crate::{
+    accumulator::{Accumulator, PublicState},
+    b2fa,
+    format::{Header, Tag},
+    key::SecretKeyOps,
+    member_element, proof_element,
+    store::MemberStore,
+    FACTOR_SIZE, MEMBER_SIZE,
+};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+    Field,
+};
+use blake2::{digest::Digest, Blake2b};
+use crate::par::*;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+#[cfg(feature = "std")]
+use crate::format::{io_error, write_element};
+#[cfg(feature = "std")]
+use std::io::Write;
+
+/// A witness that can be used for membership proofs
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct MembershipWitness {
+    pub(crate) u: BigInteger,
+    pub(crate) x: BigInteger,
+    pub(crate) freshness: Freshness,
+}
+
+/// The accumulator a witness was last known to be valid for.
+/// Proofs are refused for witnesses that don't match the accumulator
+/// they are made for, see `MembershipWitness::refresh`
+#[derive(Debug, Default, Eq, PartialEq, Ord, PartialOrd, Copy, Clone)]
+pub struct Freshness {
+    /// The epoch of the accumulator
+    pub epoch: u64,
+    /// Blake2b hash of the accumulator value, all zeros when unknown
+    pub value_hash: [u8; Freshness::HASH_BYTES],
+}
+
+impl Freshness {
+    /// The number of bytes of the value hash
+    pub const HASH_BYTES: usize = 32;
+    /// The number of bytes this serializes to
+    pub const SIZE_BYTES: usize = 8 + Self::HASH_BYTES;
+
+    /// The freshness of a witness valid for `value` at `epoch`
+    pub fn new(epoch: u64, value: &BigInteger) -> Self {
+        let mut value_hash = [0u8; Self::HASH_BYTES];
+        value_hash.copy_from_slice(&Blake2b::digest(&value.to_bytes())[..Self::HASH_BYTES]);
+        Self { epoch, value_hash }
+    }
+
+    /// Check the witness is for `value` at `epoch`
+    pub(crate) fn check(&self, epoch: u64, value: &BigInteger) -> Result<(), AccumulatorError> {
+        if self.epoch != epoch {
+            return Err(AccumulatorErrorKind::EpochMismatch {
+                expected: epoch,
+                actual: self.epoch,
+            }
+            .into());
+        }
+        if *self != Self::new(epoch, value) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::ParameterMismatch,
+                "witness is for another accumulator value",
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn to_bytes(self) -> Vec<u8> {
+        let mut output = self.epoch.to_be_bytes().to_vec();
+        output.extend_from_slice(&self.value_hash);
+        output
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn write_to<W: Write>(self, w: &mut W) -> Result<(), AccumulatorError> {
+        w.write_all(&self.epoch.to_be_bytes()).map_err(io_error)?;
+        w.write_all(&self.value_hash).map_err(io_error)
+    }
+
+    pub(crate) fn parse(data: &[u8]) -> Self {
+        Self {
+            epoch: u64::from_be_bytes(*array_ref![data, 0, 8]),
+            value_hash: *array_ref![data, 8, Freshness::HASH_BYTES],
+        }
+    }
+}
+
+impl MembershipWitness {
+    /// Return a new membership witness
+    pub fn new<S: MemberStore, B: AsRef<[u8]>>(accumulator: &Accumulator<S>, x: B) -> Result<Self, AccumulatorError> {
+        let x = accumulator.hash_member(x);
+        Self::new_prime(accumulator, &x)
+    }
+
+    /// Return a new membership witness with a value that is already prime
+    pub fn new_prime<S: MemberStore>(accumulator: &Accumulator<S>, x: &BigInteger) -> Result<Self, AccumulatorError> {
+        if !accumulator.members.contains(x)? {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "value is not in the accumulator",
+            ));
+        }
+        let exp = accumulator.member_product(Some(x))?;
+        let u = accumulator.fixed_exp(&accumulator.generator, &exp);
+        Ok(Self {
+            u,
+            x: x.clone(),
+            freshness: Freshness::new(accumulator.epoch, &accumulator.value),
+        })
+    }
+
+    /// Return a new membership witness. This is more efficient that `new` due to
+    /// the ability to reduce by the totient
+    pub fn with_secret_key<S: MemberStore, K: SecretKeyOps, B: AsRef<[u8]>>(
+        accumulator: &Accumulator<S>,
+        secret_key: &K,
+        x: B,
+    ) -> Result<Self, AccumulatorError> {
+        let x = accumulator.hash_member(x);
+        Self::with_prime_and_secret_key(accumulator, secret_key, &x)
+    }
+
+    /// Return a new membership witness with a value already prime.
+    /// This is more efficient that `new` since it takes the `x`-th
+    /// root of the value and doesn't need to read the other members
+    pub fn with_prime_and_secret_key<S: MemberStore, K: SecretKeyOps>(
+        accumulator: &Accumulator<S>,
+        secret_key: &K,
+        x: &BigInteger,
+    ) -> Result<Self, AccumulatorError> {
+        let freshness = Freshness::new(accumulator.epoch, &accumulator.value);
+        if !accumulator.members.contains(x)? {
+            return Ok(MembershipWitness {
+                u: accumulator.value.clone(),
+                x: x.clone(),
+                freshness,
+            });
+        }
+        let u = secret_key.root(&accumulator.value, x)?;
+        Ok(Self {
+            u,
+            x: x.clone(),
+            freshness,
+        })
+    }
+
+    /// The accumulator this witness was last known to be valid for
+    pub fn freshness(&self) -> Freshness {
+        self.freshness
+    }
+
+    /// True if `accumulator` changed since this witness was created or
+    /// last updated. Proofs aren't made from stale witnesses
+    pub fn is_stale<S>(&self, accumulator: &Accumulator<S>) -> bool {
+        self.freshness.check(accumulator.epoch, &accumulator.value).is_err()
+    }
+
+    /// Check this witness is valid for `accumulator` and mark it fresh.
+    /// For witnesses read from bytes written before freshness was tracked
+    /// or carried over epochs that didn't change the value
+    pub fn refresh<S>(&mut self, accumulator: &Accumulator<S>) -> Result<(), AccumulatorError> {
+        let f = Field::new(&accumulator.modulus);
+        if f.exp(&self.u, &self.x) != accumulator.value {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "witness is not valid for the accumulator",
+            ));
+        }
+        self.freshness = Freshness::new(accumulator.epoch, &accumulator.value);
+        Ok(())
+    }
+
+    /// Create a new witness to match `new_acc` from `old_acc` using this witness
+    /// by applying the methods found in 4.2 in
+    /// <https://www.cs.purdue.edu/homes/ninghui/papers/accumulator_acns07.pdf>
+    pub fn update(
+        &self,
+        old_acc: &Accumulator,
+        new_acc: &Accumulator,
+    ) -> Result<Self, AccumulatorError> {
+        let mut w = self.clone();
+        w.update_assign(old_acc, new_acc)?;
+        Ok(w)
+    }
+
+    /// Update this witness to match `new_acc` from `old_acc`
+    /// by applying the methods found in 4.2 in
+    /// <https://www.cs.purdue.edu/homes/ninghui/papers/accumulator_acns07.pdf>
+    pub fn update_assign(
+        &mut self,
+        old_acc: &Accumulator,
+        new_acc: &Accumulator,
+    ) -> Result<(), AccumulatorError> {
+        if !new_acc.members.contains(&self.x) {
+            return Err(AccumulatorErrorKind::InvalidMemberSupplied.into());
+        }
+        if !old_acc.members.contains(&self.x) {
+            return Err(AccumulatorErrorKind::InvalidMemberSupplied.into());
+        }
+
+        let additions: Vec<&BigInteger> = new_acc.members.difference(&old_acc.members).collect();
+        let deletions: Vec<&BigInteger> = old_acc.members.difference(&new_acc.members).collect();
+        self.apply_changes(additions, deletions, new_acc);
+        Ok(())
+    }
+
+    /// Update this witness for `additions` and `deletions` that turned
+    /// the previous accumulator into `new_acc`. Only the value and
+    /// modulus of `new_acc` are used, not its members
+    pub(crate) fn apply_changes<P: PublicState>(
+        &mut self,
+        additions: Vec<&BigInteger>,
+        deletions: Vec<&BigInteger>,
+        new_acc: &P,
+    ) {
+        self.freshness = Freshness::new(new_acc.epoch(), new_acc.value());
+        if additions.is_empty() && deletions.is_empty() {
+            return;
+        }
+
+        let f = Field::new(new_acc.modulus());
+
+        if !additions.is_empty() {
+            let x_a = additions.into_par_iter().product();
+            self.u = f.exp(&self.u, &x_a);
+        }
+
+        if !deletions.is_empty() {
+            let x_hat = deletions.into_par_iter().product();
+            let gcd_res = self.x.bezouts_coefficients(&x_hat);
+            assert_eq!(gcd_res.value, BigInteger::from(1u32));
+
+            self.u = f.mul(
+                &f.exp(&self.u, &gcd_res.b),
+                &new_acc.fixed_exp(new_acc.value(), &gcd_res.a),
+            );
+        }
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Header::new(Tag::MembershipWitness).to_bytes();
+        output.append(&mut b2fa(&self.u, FACTOR_SIZE * 2)?);
+        output.append(&mut b2fa(&self.x, MEMBER_SIZE)?);
+        output.append(&mut self.freshness.to_bytes());
+        Ok(output)
+    }
+
+    /// Write the same bytes as `to_bytes` to `w`
+    #[cfg(feature = "std")]
+    pub fn write_bytes<W: Write>(&self, mut w: W) -> Result<(), AccumulatorError> {
+        Header::new(Tag::MembershipWitness).write_to(&mut w)?;
+        write_element(&mut w, &self.u, FACTOR_SIZE * 2)?;
+        write_element(&mut w, &self.x, MEMBER_SIZE)?;
+        self.freshness.write_to(&mut w)
+    }
+}
+
+impl TryFrom<&[u8]> for MembershipWitness {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        const SIZE: usize = FACTOR_SIZE * 2 + MEMBER_SIZE;
+        let data = Header::check(data, Tag::MembershipWitness)?;
+        // Witnesses written before freshness was tracked are always stale
+        let freshness = match data.len() {
+            SIZE => Freshness::default(),
+            l if l == SIZE + Freshness::SIZE_BYTES => Freshness::parse(&data[SIZE..]),
+            _ => {
+                return Err(AccumulatorErrorKind::InvalidLength {
+                    expected: SIZE + Freshness::SIZE_BYTES,
+                    actual: data.len(),
+                }
+                .into())
+            }
+        };
+        let u = proof_element(&data[..(FACTOR_SIZE * 2)])?;
+        let x = member_element(&data[(FACTOR_SIZE * 2)..SIZE])?;
+        Ok(Self { u, x, freshness })
+    }
+}
+
+impl TryFrom<Vec<u8>> for MembershipWitness {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+serdes_impl!(MembershipWitness);
+hash_ord_impl!(MembershipWitness; u, x, freshness);
+hex_impl!(MembershipWitness);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{format::HEADER_SIZE, hash::hash_to_prime, key::AccumulatorSecretKey, memproof::MembershipProof};
+
+    #[test]
+    fn witnesses() {
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = vec![
+            23u64.to_be_bytes(),
+            7u64.to_be_bytes(),
+            11u64.to_be_bytes(),
+            13u64.to_be_bytes(),
+        ];
+        let mut acc = Accumulator::with_members(&key, &members).unwrap();
+        let witness = MembershipWitness::new(&acc, &members[0]).unwrap();
+        let x = hash_to_prime(&members[0]);
+        assert_eq!(witness.x, x);
+
+        acc.remove_assign(&key, &members[0]).unwrap();
+
+        assert_eq!(acc.value, witness.u);
+        assert_eq!(
+            witness.to_bytes().unwrap().len(),
+            HEADER_SIZE + 2 * FACTOR_SIZE + MEMBER_SIZE + Freshness::SIZE_BYTES
+        );
+    }
+
+    #[test]
+    fn serialization() {
+        let key = AccumulatorSecretKey::default();
+        let acc = Accumulator::with_members(&key, ["a", "b"]).unwrap();
+        let witness = MembershipWitness::new(&acc, "a").unwrap();
+        let bytes = witness.to_bytes().unwrap();
+        assert_eq!(MembershipWitness::try_from(bytes.clone()).unwrap(), witness);
+
+        let mut zero = bytes.clone();
+        zero[HEADER_SIZE..(HEADER_SIZE + FACTOR_SIZE * 2)].iter_mut().for_each(|v| *v = 0);
+        assert!(MembershipWitness::try_from(zero).is_err());
+        let mut even = bytes.clone();
+        even[HEADER_SIZE + FACTOR_SIZE * 2 + MEMBER_SIZE - 1] ^= 1;
+        assert!(MembershipWitness::try_from(even).is_err());
+        assert!(MembershipWitness::try_from(&bytes[1..]).is_err());
+    }
+
+    #[test]
+    fn updates() {
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = vec![
+            23u64.to_be_bytes(),
+            7u64.to_be_bytes(),
+            11u64.to_be_bytes(),
+            13u64.to_be_bytes(),
+            17u64.to_be_bytes(),
+            19u64.to_be_bytes(),
+        ];
+        let acc = Accumulator::with_members(&key, &members).unwrap();
+        let witness = MembershipWitness::new(&acc, &members[0]).unwrap();
+
+        let acc_prime = &acc + 29u64;
+
+        let res = witness.update(&acc, &acc_prime);
+        assert!(res.is_ok());
+        let new_w = res.unwrap();
+        let expected_witness = MembershipWitness::new(&acc_prime, &members[0]).unwrap();
+        assert_eq!(expected_witness.u, new_w.u);
+
+        let mut acc = acc_prime.remove_u64(&key, 19u64).unwrap();
+        let res = new_w.update(&acc_prime, &acc);
+        assert!(res.is_ok());
+        let new_w = res.unwrap();
+        let expected_witness = MembershipWitness::new(&acc, &members[0]).unwrap();
+        assert_eq!(expected_witness.u, new_w.u);
+
+        let old_acc = acc.clone();
+        acc.remove_u64_assign(&key, 7u64).unwrap();
+        acc.remove_u64_assign(&key, 11u64).unwrap();
+        acc.remove_u64_assign(&key, 13u64).unwrap();
+        acc += 31u64;
+        let res = new_w.update(&old_acc, &acc);
+        assert!(res.is_ok());
+        let new_w = res.unwrap();
+        let expected_witness = MembershipWitness::new(&acc, &members[0]).unwrap();
+        assert_eq!(expected_witness.u, new_w.u);
+    }
+
+    #[test]
+    fn freshness() {
+        let key = AccumulatorSecretKey::default();
+        let acc = Accumulator::with_members(&key, ["a", "b"]).unwrap();
+        let witness = MembershipWitness::new(&acc, "a").unwrap();
+        assert!(!witness.is_stale(&acc));
+        assert_eq!(witness.freshness().epoch, acc.epoch);
+
+        let next = &acc + "c";
+        assert!(witness.is_stale(&next));
+        assert!(MembershipProof::new(&witness, &next, b"stale").is_err());
+        let updated = witness.update(&acc, &next).unwrap();
+        assert!(!updated.is_stale(&next));
+        assert_eq!(updated.freshness().epoch, next.epoch);
+
+        // Witnesses written without it parse as stale until refreshed
+        let bytes = witness.to_bytes().unwrap();
+        let mut old = MembershipWitness::try_from(&bytes[..(bytes.len() - Freshness::SIZE_BYTES)]).unwrap();
+        assert!(old.is_stale(&acc));
+        assert!(old.refresh(&next).is_err());
+        old.refresh(&acc).unwrap();
+        assert_eq!(old, witness);
+    }
+
+    #[test]
+    fn hash_and_order() {
+        use std::collections::{BTreeSet, HashSet};
+
+        let key = AccumulatorSecretKey::default();
+        let members = ["a", "b", "c"];
+        let acc = Accumulator::with_members(&key, members).unwrap();
+        let witnesses: Vec<_> = members
+            .iter()
+            .chain(members.iter())
+            .map(|m| MembershipWitness::new(&acc, m).unwrap())
+            .collect();
+        let unique: HashSet<_> = witnesses.iter().cloned().collect();
+        assert_eq!(unique.len(), 3);
+
+        let sorted: BTreeSet<_> = witnesses.iter().cloned().collect();
+        let bytes: Vec<_> = sorted.iter().map(|w| w.to_bytes().unwrap()).collect();
+        assert!(bytes.windows(2).all(|w| w[0] < w[1]));
+
+        let other = &acc + "d";
+        assert_eq!(acc.cmp(&acc.clone()), core::cmp::Ordering::Equal);
+        assert_eq!(acc.cmp(&other), acc.to_bytes().unwrap().cmp(&other.to_bytes().unwrap()));
+
+        // Values without an encoding still only compare equal when they are
+        let oversized = |w: &MembershipWitness| {
+            let mut w = w.clone();
+            w.x = BigInteger::try_from(&[0xffu8; MEMBER_SIZE + 1][..]).unwrap();
+            w
+        };
+        let (a, b) = (oversized(&witnesses[0]), oversized(&witnesses[1]));
+        assert!(a.to_bytes().is_err() && b.to_bytes().is_err());
+        assert_ne!(a.cmp(&b), core::cmp::Ordering::Equal);
+        assert_eq!(a.cmp(&b), b.cmp(&a).reverse());
+        assert_eq!(a.cmp(&a.clone()), core::cmp::Ordering::Equal);
+        assert_eq!(a.cmp(&witnesses[0]), core::cmp::Ordering::Less);
+        let unique: BTreeSet<_> = vec![a.clone(), b, a].into_iter().collect();
+        assert_eq!(unique.len(), 2);
+    }
+}
@@ -0,0 +1,289 @@
+// Proves the modulus was set up honestly without revealing the factors.
+//
+// The issuer shows n = pq for two distinct primes p = q = 3 mod 4, a Blum
+// integer, which safe primes always are. This is the van de Graaf and Peralta
+// Blum integer proof combined with the square free check of Goldberg et al.,
+// made non-interactive as in Canetti et al. "UC Non-Interactive, Proactive,
+// Threshold ECDSA with Identifiable Aborts" <https://eprint.iacr.org/2021/060>
+// section 6.3. For each challenge y, derived from n and a value w with
+// Jacobi symbol -1, the issuer gives
+//
+// - z with z^n = y, an n-th root only exists for every y when
+//   gcd(n, φ(n)) = 1 so n has no square factor
+// - x with x^4 = (-1)^a w^b y, only one of the four choices of a and b is
+//   a quadratic residue when n is a Blum integer with two prime factors
+//
+// A cheating issuer passes each round with probability at most 1/2.
+// It doesn't show p and q are safe primes or of equal size, the verifier
+// also rejects moduli of the wrong size or with small factors
+use crate::{
+    b2fa,
+    format::{Header, Tag},
+    in_group,
+    key::{AccumulatorSecretKey, SecretKeyOps},
+    proof_element, random_below, telemetry, FACTOR_SIZE,
+};
+use blake2::Blake2b;
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+    Field,
+};
+use hkdf::Hkdf;
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use std::convert::TryFrom;
+
+/// The number of challenges, a cheating issuer passes with probability 2^-80
+pub const MODULUS_PROOF_ROUNDS: usize = 80;
+/// Moduli with an odd factor below this are rejected
+const SMALL_FACTOR_BOUND: u32 = 1 << 12;
+
+/// A proof by the issuer that the accumulator modulus is the product of
+/// two distinct primes it knows, so relying parties can check the
+/// published parameters weren't generated maliciously
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct ModulusProof {
+    w: BigInteger,
+    rounds: Vec<ModulusRound>,
+}
+
+/// The answer to one challenge
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone)]
+struct ModulusRound {
+    /// The fourth root of `(-1)^a w^b y`
+    x: BigInteger,
+    /// The n-th root of `y`
+    z: BigInteger,
+    /// `a` in the low bit and `b` in the next
+    flags: u8,
+}
+
+impl ModulusProof {
+    /// The number of bytes this serializes to without the header
+    pub const SIZE_BYTES: usize = 2 * FACTOR_SIZE + MODULUS_PROOF_ROUNDS * (4 * FACTOR_SIZE + 1);
+
+    /// Prove the modulus of `key` is well formed
+    pub fn new(key: &AccumulatorSecretKey) -> Result<Self, AccumulatorError> {
+        Self::new_with_rng(key, &mut OsRng)
+    }
+
+    /// Same as `new` but `w` is drawn from `rng`
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        key: &AccumulatorSecretKey,
+        rng: &mut R,
+    ) -> Result<Self, AccumulatorError> {
+        let (p, q) = key.expose_factors();
+        let n = key.modulus();
+        let one = BigInteger::from(1u32);
+        // A non-residue mod exactly one of the factors has Jacobi symbol -1
+        let w = loop {
+            let w = random_below(&n, rng)?;
+            if w > one && is_residue(&w, p) != is_residue(&w, q) {
+                break w;
+            }
+        };
+        let (e_p, e_q) = (fourth_root_exponent(p), fourth_root_exponent(q));
+        let f = Field::new(&n);
+        let minus_one = &n - &one;
+        let mut rounds = Vec::with_capacity(MODULUS_PROOF_ROUNDS);
+        for i in 0..MODULUS_PROOF_ROUNDS {
+            let y = challenge(&n, &w, i)?;
+            let z = key.root(&y, &n)?;
+            let (flags, t) = (0..4u8)
+                .map(|flags| (flags, randomize(&f, &y, &minus_one, &w, flags)))
+                .find(|(_, t)| is_residue(t, p) && is_residue(t, q))
+                .ok_or_else(|| {
+                    AccumulatorError::from_msg(
+                        AccumulatorErrorKind::InvalidType,
+                        "modulus is not the product of two primes that are 3 mod 4",
+                    )
+                })?;
+            let x = key.crt_exp(&t, &e_p, &e_q)?;
+            rounds.push(ModulusRound { x, z, flags });
+        }
+        Ok(Self { w, rounds })
+    }
+
+    /// Check `n` is the product of two distinct primes known to the prover
+    pub fn verify(&self, n: &BigInteger) -> bool {
+        telemetry::verify("modulus", || {
+            let odd = n.to_bytes().last().is_some_and(|b| b & 1 == 1);
+            if n.bits() != FACTOR_SIZE * 16
+                || !odd
+                || n.is_prime()
+                || has_small_factor(n)
+                || self.rounds.len() != MODULUS_PROOF_ROUNDS
+                || !in_group(&[&self.w], n)
+            {
+                return false;
+            }
+            let f = Field::new(n);
+            let minus_one = n - &BigInteger::from(1u32);
+            let four = BigInteger::from(4u32);
+            self.rounds.iter().enumerate().all(|(i, r)| {
+                let y = match challenge(n, &self.w, i) {
+                    Ok(y) => y,
+                    Err(_) => return false,
+                };
+                in_group(&[&r.x, &r.z], n)
+                    && f.exp(&r.z, n) == y
+                    && f.exp(&r.x, &four) == randomize(&f, &y, &minus_one, &self.w, r.flags)
+            })
+        })
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Header::new(Tag::ModulusProof).to_bytes();
+        output.append(&mut b2fa(&self.w, 2 * FACTOR_SIZE)?);
+        for r in &self.rounds {
+            output.append(&mut b2fa(&r.x, 2 * FACTOR_SIZE)?);
+            output.append(&mut b2fa(&r.z, 2 * FACTOR_SIZE)?);
+            output.push(r.flags);
+        }
+        Ok(output)
+    }
+}
+
+impl TryFrom<&[u8]> for ModulusProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::ModulusProof)?;
+        if data.len() != Self::SIZE_BYTES {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: Self::SIZE_BYTES,
+                actual: data.len(),
+            }
+            .into());
+        }
+        let (w, data) = data.split_at(2 * FACTOR_SIZE);
+        let rounds = data
+            .chunks(4 * FACTOR_SIZE + 1)
+            .map(|r| {
+                let flags = r[4 * FACTOR_SIZE];
+                if flags > 3 {
+                    return Err(AccumulatorError::from_msg(
+                        AccumulatorErrorKind::SerializationError,
+                        "invalid modulus proof flags",
+                    ));
+                }
+                Ok(ModulusRound {
+                    x: proof_element(&r[..(2 * FACTOR_SIZE)])?,
+                    z: proof_element(&r[(2 * FACTOR_SIZE)..(4 * FACTOR_SIZE)])?,
+                    flags,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            w: proof_element(w)?,
+            rounds,
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for ModulusProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+serdes_impl!(ModulusProof);
+hash_ord_impl!(ModulusProof; w, rounds);
+hex_impl!(ModulusProof);
+
+/// The `i`-th challenge, a value mod `n` hashed from `n` and `w`
+fn challenge(n: &BigInteger, w: &BigInteger, i: usize) -> Result<BigInteger, AccumulatorError> {
+    let mut input = n.to_bytes();
+    input.append(&mut w.to_bytes());
+    let h = Hkdf::<Blake2b>::new(Some(b"RSA_ACCUMULATOR_MODULUS_PROOF_"), &input);
+    // Extra bytes so the reduction mod n is close to uniform
+    let mut okm = vec![0u8; 2 * FACTOR_SIZE + 16];
+    h.expand(&(i as u64).to_be_bytes(), &mut okm).map_err(|_| {
+        AccumulatorError::from_msg(AccumulatorErrorKind::InvalidType, "modulus is too large to hash to a challenge")
+    })?;
+    Ok(&BigInteger::from(okm) % n)
+}
+
+/// `(-1)^a w^b y` for `a` and `b` in `flags`
+fn randomize(f: &Field, y: &BigInteger, minus_one: &BigInteger, w: &BigInteger, flags: u8) -> BigInteger {
+    let mut t = y.clone();
+    if flags & 1 == 1 {
+        t = f.mul(&t, minus_one);
+    }
+    if flags & 2 == 2 {
+        t = f.mul(&t, w);
+    }
+    t
+}
+
+/// Euler's criterion, `a^((p - 1) / 2) = 1 mod p`
+fn is_residue(a: &BigInteger, p: &BigInteger) -> bool {
+    let one = BigInteger::from(1u32);
+    let (e, _) = BigInteger::div_rem(&(p - &one), &BigInteger::from(2u32));
+    a.mod_exp(&e, p) == one
+}
+
+/// Raising a residue to `(p + 1) / 4` mod p gives the square root that is
+/// itself a residue when p = 3 mod 4, doing it twice gives a fourth root
+fn fourth_root_exponent(p: &BigInteger) -> BigInteger {
+    let one = BigInteger::from(1u32);
+    let (e, _) = BigInteger::div_rem(&(p + &one), &BigInteger::from(4u32));
+    e.mod_mul(&e, &(p - &one))
+}
+
+/// Trial division by the odd numbers below `SMALL_FACTOR_BOUND`
+fn has_small_factor(n: &BigInteger) -> bool {
+    let zero = BigInteger::default();
+    (3..SMALL_FACTOR_BOUND)
+        .step_by(2)
+        .any(|d| n % &BigInteger::from(d) == zero)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MIN_SIZE_PRIME;
+
+    #[test]
+    fn proves_modulus() {
+        let key = AccumulatorSecretKey::default();
+        let n = key.modulus();
+        let proof = ModulusProof::new(&key).unwrap();
+        assert!(proof.verify(&n));
+        let bytes = proof.to_bytes().unwrap();
+        let parsed = ModulusProof::try_from(bytes.clone()).unwrap();
+        assert_eq!(parsed, proof);
+        assert!(parsed.verify(&n));
+
+        // Not for another modulus, a prime or a changed answer
+        let (p, _) = key.expose_factors();
+        let other = p * &BigInteger::generate_prime(MIN_SIZE_PRIME);
+        assert!(!proof.verify(&other));
+        assert!(!proof.verify(&BigInteger::generate_prime(2 * MIN_SIZE_PRIME)));
+        let mut changed = proof.clone();
+        changed.rounds[3].flags ^= 1;
+        assert!(!changed.verify(&n));
+        let mut changed = proof.clone();
+        changed.rounds.pop();
+        assert!(!changed.verify(&n));
+        let mut flags = bytes;
+        *flags.last_mut().unwrap() = 4;
+        assert!(ModulusProof::try_from(flags).is_err());
+
+        // Nor for a factor that is 1 mod 4
+        let four = BigInteger::from(4u32);
+        let r = loop {
+            let r = BigInteger::generate_prime(MIN_SIZE_PRIME);
+            if &r % &four == BigInteger::from(1u32) {
+                break r;
+            }
+        };
+        let key = AccumulatorSecretKey::from_primes(p.clone(), r);
+        if let Ok(proof) = ModulusProof::new(&key) {
+            assert!(!proof.verify(&key.modulus()));
+        }
+    }
+}
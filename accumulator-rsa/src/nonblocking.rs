@@ -0,0 +1,183 @@
+use crate::{
+    accumulator::Accumulator,
+    key::{AccumulatorSecretKey, KeygenProgress, SecretKeyOps},
+    memwitness::MembershipWitness,
+    store::MemberStore,
+};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use tokio::{sync::watch, task};
+
+/// Sets the flag when the future holding it is dropped
+/// so the blocking task stops instead of running on unobserved
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Run `f` on the blocking pool. Panics in `f` are resumed in the caller
+pub(crate) async fn blocking<T, F>(f: F) -> Result<T, AccumulatorError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, AccumulatorError> + Send + 'static,
+{
+    match task::spawn_blocking(f).await {
+        Ok(r) => r,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(_) => Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::Cancelled,
+            "the runtime is shutting down",
+        )),
+    }
+}
+
+/// Generate a secret key on the blocking pool. Progress is published to
+/// `progress` if given. Dropping the future stops the search
+pub async fn generate_key(
+    progress: Option<watch::Sender<KeygenProgress>>,
+) -> Result<AccumulatorSecretKey, AccumulatorError> {
+    let cancel = CancelOnDrop(Arc::new(AtomicBool::new(false)));
+    let flag = cancel.0.clone();
+    blocking(move || {
+        AccumulatorSecretKey::generate_with(
+            |p| {
+                if let Some(progress) = &progress {
+                    let _ = progress.send(p);
+                }
+            },
+            &flag,
+        )
+    })
+    .await
+}
+
+/// `Accumulator::with_members` on the blocking pool
+pub async fn with_members<K, B>(key: Arc<K>, members: Vec<B>) -> Result<Accumulator, AccumulatorError>
+where
+    K: SecretKeyOps + Send + Sync + 'static,
+    B: AsRef<[u8]> + Send + 'static,
+{
+    blocking(move || Accumulator::with_members(key.as_ref(), members)).await
+}
+
+/// `Accumulator::with_store` on the blocking pool
+pub async fn with_store<K, S>(key: Arc<K>, store: S) -> Result<Accumulator<S>, AccumulatorError>
+where
+    K: SecretKeyOps + Send + Sync + 'static,
+    S: MemberStore + Send + 'static,
+{
+    blocking(move || Accumulator::with_store(key.as_ref(), store)).await
+}
+
+/// `Accumulator::update_batch_assign` on the blocking pool. The accumulator
+/// is moved to the pool and returned, unchanged if the update failed
+pub async fn update_batch<K, S>(
+    mut accumulator: Accumulator<S>,
+    key: Arc<K>,
+    additions: Vec<BigInteger>,
+    deletions: Vec<BigInteger>,
+) -> Result<(Accumulator<S>, Result<(), AccumulatorError>), AccumulatorError>
+where
+    K: SecretKeyOps + Send + Sync + 'static,
+    S: MemberStore + Send + 'static,
+{
+    blocking(move || {
+        let r = accumulator.update_batch_assign(key.as_ref(), &additions, &deletions);
+        Ok((accumulator, r))
+    })
+    .await
+}
+
+/// Create witnesses for every one of `members` on the blocking pool.
+/// The number done so far is published to `progress` if given.
+/// Dropping the future stops after the current witness
+pub async fn witnesses<K, S, B>(
+    accumulator: Arc<Accumulator<S>>,
+    key: Arc<K>,
+    members: Vec<B>,
+    progress: Option<watch::Sender<usize>>,
+) -> Result<Vec<MembershipWitness>, AccumulatorError>
+where
+    K: SecretKeyOps + Send + Sync + 'static,
+    S: MemberStore + Send + Sync + 'static,
+    B: AsRef<[u8]> + Send + 'static,
+{
+    let cancel = CancelOnDrop(Arc::new(AtomicBool::new(false)));
+    let flag = cancel.0.clone();
+    blocking(move || {
+        let mut witnesses = Vec::with_capacity(members.len());
+        for m in &members {
+            if flag.load(Ordering::Relaxed) {
+                return Err(AccumulatorError::from_msg(
+                    AccumulatorErrorKind::Cancelled,
+                    "witness generation was cancelled",
+                ));
+            }
+            let x = accumulator.hash_member(m);
+            if !accumulator.members.contains(&x)? {
+                return Err(AccumulatorError::from_msg(
+                    AccumulatorErrorKind::InvalidMemberSupplied,
+                    "value is not in the accumulator",
+                ));
+            }
+            witnesses.push(MembershipWitness::with_prime_and_secret_key(
+                accumulator.as_ref(),
+                key.as_ref(),
+                &x,
+            )?);
+            if let Some(progress) = &progress {
+                let _ = progress.send(witnesses.len());
+            }
+        }
+        Ok(witnesses)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::hash_to_prime;
+
+    fn run<F: std::future::Future>(f: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(f)
+    }
+
+    #[test]
+    fn async_operations() {
+        let key = Arc::new(AccumulatorSecretKey::default());
+        let members = vec!["a", "b", "c"];
+        let acc = run(with_members(key.clone(), members.clone())).unwrap();
+        let expected = Accumulator::with_members(key.as_ref(), &members).unwrap();
+        assert_eq!(acc.value, expected.value);
+
+        let (tx, rx) = watch::channel(0);
+        let acc = Arc::new(acc);
+        let created = run(witnesses(acc.clone(), key.clone(), members.clone(), Some(tx))).unwrap();
+        assert_eq!(*rx.borrow(), 3);
+        for (w, m) in created.iter().zip(members.iter()) {
+            assert_eq!(w, &MembershipWitness::new(acc.as_ref(), m).unwrap());
+        }
+        assert!(run(witnesses(acc.clone(), key.clone(), vec!["d"], None)).is_err());
+
+        let acc = Arc::try_unwrap(acc).unwrap();
+        let (acc, r) = run(update_batch(acc, key.clone(), vec![hash_to_prime("d")], vec![hash_to_prime("a")])).unwrap();
+        r.unwrap();
+        assert_eq!(acc.epoch, 1);
+        let (acc, r) = run(update_batch(acc, key, vec![], vec![hash_to_prime("a")])).unwrap();
+        assert!(r.is_err());
+        assert_eq!(acc.epoch, 1);
+    }
+}
@@ -0,0 +1,321 @@
+use crate::{
+    accumulator::{Accumulator, PublicState},
+    b2fa, bound_nonce, in_group, proof_element,
+    format::{Header, Tag},
+    hash::derive_nonce,
+    nonwitness::NonMembershipWitness,
+    sigma::{ProofChallenge, ProofCommitting},
+    store::MemberStore,
+    telemetry, Poke2Proof, ProofSize, FACTOR_SIZE,
+};
+use common::{bigint::BigInteger, error::*, Field};
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+#[cfg(feature = "std")]
+use crate::format::write_element;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+/// A proof of knowledge of exponents non-membership proof
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct NonMembershipProof {
+    pub(crate) v: BigInteger,
+    pub(crate) r: BigInteger,
+    pub(crate) q: BigInteger,
+    pub(crate) z: BigInteger,
+    pub(crate) proof_g: Poke2Proof,
+}
+
+impl NonMembershipProof {
+    /// Create 2 new PoKE2 proofs bound to the accumulator epoch.
+    /// Fails if `witness` is stale, see `NonMembershipWitness::refresh`
+    pub fn new<S: MemberStore, B: AsRef<[u8]>>(
+        witness: &NonMembershipWitness,
+        accumulator: &Accumulator<S>,
+        nonce: B,
+    ) -> Result<Self, AccumulatorError> {
+        Self::new_with_context(witness, accumulator, &[], &[], nonce)
+    }
+
+    /// Create 2 new PoKE2 proofs bound to the accumulator epoch,
+    /// the registry `registry_id` and an application `context`
+    pub fn new_with_context<S: MemberStore, B: AsRef<[u8]>>(
+        witness: &NonMembershipWitness,
+        accumulator: &Accumulator<S>,
+        registry_id: &[u8],
+        context: &[u8],
+        nonce: B,
+    ) -> Result<Self, AccumulatorError> {
+        let nonce = bound_nonce(accumulator.epoch, registry_id, context, nonce.as_ref());
+        Self::create(witness, accumulator, |_| nonce)
+    }
+
+    /// Create a proof for `verifier_id` with the nonce derived from the
+    /// accumulator value and epoch, see `hash::derive_nonce`
+    pub fn new_for_verifier<S: MemberStore>(
+        witness: &NonMembershipWitness,
+        accumulator: &Accumulator<S>,
+        verifier_id: &[u8],
+    ) -> Result<Self, AccumulatorError> {
+        Self::create(witness, accumulator, |v| {
+            let nonce = derive_nonce(&accumulator.value, accumulator.epoch, verifier_id, v);
+            bound_nonce(accumulator.epoch, &[], &[], &nonce)
+        })
+    }
+
+    /// Same as `new` with 128 bit challenges, see `ProofSize::Compact`
+    pub fn new_compact<S: MemberStore, B: AsRef<[u8]>>(
+        witness: &NonMembershipWitness,
+        accumulator: &Accumulator<S>,
+        nonce: B,
+    ) -> Result<Self, AccumulatorError> {
+        let nonce = bound_nonce(accumulator.epoch, &[], &[], nonce.as_ref());
+        let (committing, nonce) = ProofCommitting::<Self>::with_nonce(witness, accumulator, |_| nonce)?;
+        Ok(committing
+            .with_size(ProofSize::Compact)
+            .respond(&ProofChallenge::new(nonce))?
+            .into_proof())
+    }
+
+    /// `nonce` is called with the commitment `v` once it is known
+    fn create<S: MemberStore, F: FnOnce(&BigInteger) -> Vec<u8>>(
+        witness: &NonMembershipWitness,
+        accumulator: &Accumulator<S>,
+        nonce: F,
+    ) -> Result<Self, AccumulatorError> {
+        let (committing, nonce) = ProofCommitting::<Self>::with_nonce(witness, accumulator, nonce)?;
+        Ok(committing.respond(&ProofChallenge::new(nonce))?.into_proof())
+    }
+
+    /// The size of the proof's challenges
+    pub fn size(&self) -> ProofSize {
+        self.proof_g.size
+    }
+
+    /// Verify a set non-membership proof
+    pub fn verify<P: PublicState, B: AsRef<[u8]>>(&self, accumulator: &P, nonce: B) -> bool {
+        self.verify_with_context(accumulator, &[], &[], nonce)
+    }
+
+    /// Verify a set non-membership proof made for the accumulator epoch,
+    /// `registry_id` and `context`
+    pub fn verify_with_context<P: PublicState, B: AsRef<[u8]>>(
+        &self,
+        accumulator: &P,
+        registry_id: &[u8],
+        context: &[u8],
+        nonce: B,
+    ) -> bool {
+        telemetry::verify("non_membership", || {
+            let nonce = bound_nonce(accumulator.epoch(), registry_id, context, nonce.as_ref());
+            self.check(accumulator, &nonce, &nonce)
+        })
+    }
+
+    /// Check the proof with generators hashed from `nonce`, already bound,
+    /// that responds to `challenge`
+    pub(crate) fn check<P: PublicState>(&self, accumulator: &P, nonce: &[u8], challenge: &[u8]) -> bool {
+        if !in_group(&[&self.v], accumulator.modulus()) {
+            return false;
+        }
+        let f = Field::new(accumulator.modulus());
+        let g_inv = match f.inv(accumulator.generator()) {
+            Ok(g_inv) => g_inv,
+            Err(_) => return false,
+        };
+        let gv_inv = f.mul(&g_inv, &self.v);
+        // Copy the latest value of the accumulator so the proof will fail if
+        // the accumulator value has changed since the proof was created
+        let proof_v = Poke2Proof {
+            u: accumulator.value().clone(),
+            r: self.r.clone(),
+            q: self.q.clone(),
+            z: self.z.clone(),
+            size: self.proof_g.size,
+        };
+        let v_res = proof_v.verify_with_challenge(&self.v, accumulator.modulus(), nonce, challenge);
+        let g_res = self.proof_g.verify_with_challenge(&gv_inv, accumulator.modulus(), nonce, challenge);
+        g_res && v_res
+    }
+
+    /// Verify a proof made by `new_for_verifier` for `verifier_id`
+    pub fn verify_for_verifier<P: PublicState>(&self, accumulator: &P, verifier_id: &[u8]) -> bool {
+        let nonce = derive_nonce(accumulator.value(), accumulator.epoch(), verifier_id, &self.v);
+        self.verify(accumulator, nonce)
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let size = self.proof_g.size;
+        let mut output = Header::with_size(Tag::NonMembershipProof, size).to_bytes();
+        output.append(&mut b2fa(&self.v, 2 * FACTOR_SIZE)?);
+        output.append(&mut b2fa(&self.z, 2 * FACTOR_SIZE)?);
+        output.append(&mut b2fa(&self.q, 2 * FACTOR_SIZE)?);
+        output.append(&mut b2fa(&self.r, size.challenge_bytes())?);
+        output.append(&mut self.proof_g.to_bytes()?);
+        Ok(output)
+    }
+
+    /// Write the same bytes as `to_bytes` to `w`
+    #[cfg(feature = "std")]
+    pub fn write_bytes<W: Write>(&self, mut w: W) -> Result<(), AccumulatorError> {
+        let size = self.proof_g.size;
+        Header::with_size(Tag::NonMembershipProof, size).write_to(&mut w)?;
+        for e in &[&self.v, &self.z, &self.q] {
+            write_element(&mut w, e, 2 * FACTOR_SIZE)?;
+        }
+        write_element(&mut w, &self.r, size.challenge_bytes())?;
+        self.proof_g.write_bytes(w)
+    }
+}
+
+impl TryFrom<&[u8]> for NonMembershipProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let (data, size) = Header::check_sized(data, Tag::NonMembershipProof)?;
+        if data.len() != Poke2Proof::size_bytes(size) * 2 {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: Poke2Proof::size_bytes(size) * 2,
+                actual: data.len(),
+            }
+            .into());
+        }
+        let mut offset = 2*FACTOR_SIZE;
+        let v = proof_element(&data[..offset])?;
+        let mut end = offset + 2*FACTOR_SIZE;
+        let z = proof_element(&data[offset..end])?;
+
+        offset = end;
+        end = offset + 2*FACTOR_SIZE;
+
+        let q = proof_element(&data[offset..end])?;
+
+        offset = end;
+        end = offset + size.challenge_bytes();
+
+        let r = BigInteger::try_from(&data[offset..end])?;
+
+        // let proof_v = Poke2Proof::try_from(&data[offset..end])?;
+        let proof_g = Poke2Proof::parse(&data[end..], size)?;
+        Ok(Self {
+            v,
+            z,
+            q,
+            r,
+            proof_g,
+        })
+    }
+}
+
+serdes_impl!(NonMembershipProof);
+hash_ord_impl!(NonMembershipProof; v, r, q, z, proof_g);
+hex_impl!(NonMembershipProof);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::AccumulatorSecretKey as SecretKey;
+    use crate::format::HEADER_SIZE;
+
+    #[test]
+    fn proof_test() {
+        let key = SecretKey::default();
+        let members: Vec<[u8; 8]> = vec![
+            3u64.to_be_bytes(),
+            7u64.to_be_bytes(),
+            11u64.to_be_bytes(),
+            13u64.to_be_bytes(),
+        ];
+        let member = 17u64.to_be_bytes();
+        let mut acc = Accumulator::with_members(&key, &members).unwrap();
+        let witness = NonMembershipWitness::new(&acc, &member).unwrap();
+        let nonce = b"proof_test";
+
+        let proof = NonMembershipProof::new(&witness, &acc, nonce).unwrap();
+        assert!(proof.verify(&acc, nonce));
+        let copy = NonMembershipProof::try_from(proof.to_bytes().unwrap().as_slice()).unwrap();
+        assert_eq!(copy, proof);
+        acc += 17u64;
+
+        assert!(!proof.verify(&acc, nonce));
+        assert_eq!(
+            proof.to_bytes().unwrap().len(),
+            HEADER_SIZE + 2 * Poke2Proof::SIZE_BYTES
+        );
+    }
+
+    #[test]
+    fn compact() {
+        let key = SecretKey::default();
+        let acc = Accumulator::with_members(&key, ["a", "b", "c"]).unwrap();
+        let witness = NonMembershipWitness::new(&acc, "d").unwrap();
+        let proof = NonMembershipProof::new_compact(&witness, &acc, b"compact").unwrap();
+        assert_eq!(proof.size(), ProofSize::Compact);
+        assert!(proof.verify(&acc, b"compact"));
+        assert!(!proof.verify(&acc, b"other"));
+
+        let bytes = proof.to_bytes().unwrap();
+        assert_eq!(bytes.len(), HEADER_SIZE + 2 * Poke2Proof::size_bytes(ProofSize::Compact));
+        let copy = NonMembershipProof::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(copy, proof);
+        assert!(copy.verify(&acc, b"compact"));
+    }
+
+    #[test]
+    fn negative_coefficient() {
+        let key = SecretKey::default();
+        let acc = Accumulator::with_members(&key, ["alice", "carol", "dave"]).unwrap();
+        let witness = NonMembershipWitness::new(&acc, "bob").unwrap();
+        assert!(witness.a < BigInteger::default());
+        let proof = NonMembershipProof::new(&witness, &acc, b"negative").unwrap();
+        assert!(proof.verify(&acc, b"negative"));
+    }
+
+    #[test]
+    fn updated_and_invalid_witnesses() {
+        let key = SecretKey::default();
+        let acc = Accumulator::with_members(&key, ["alice", "carol", "dave"]).unwrap();
+        let witness = NonMembershipWitness::new(&acc, "bob").unwrap();
+
+        // Updated witnesses have other coefficients than fresh ones
+        let next = acc.insert("erin").unwrap();
+        let updated = witness.update(&acc, &next).unwrap();
+        let proof = NonMembershipProof::new(&updated, &next, b"updated").unwrap();
+        assert!(proof.verify(&next, b"updated"));
+
+        let mut invalid = witness;
+        invalid.b = &invalid.b + &BigInteger::from(1u32);
+        assert!(NonMembershipProof::new(&invalid, &acc, b"invalid").is_err());
+    }
+
+    #[test]
+    fn context_test() {
+        let key = SecretKey::default();
+        let acc = Accumulator::with_members(&key, ["alice", "carol", "dave"]).unwrap();
+        let witness = NonMembershipWitness::new(&acc, "bob").unwrap();
+        let nonce = b"context_test";
+        let proof = NonMembershipProof::new_with_context(&witness, &acc, b"registry", b"login", nonce).unwrap();
+        assert!(proof.verify_with_context(&acc, b"registry", b"login", nonce));
+        assert!(!proof.verify_with_context(&acc, b"registry2", b"login", nonce));
+        assert!(!proof.verify_with_context(&acc, b"registry", b"payment", nonce));
+        assert!(!proof.verify(&acc, nonce));
+
+        let mut public = acc.public();
+        public.epoch += 1;
+        assert!(!proof.verify_with_context(&public, b"registry", b"login", nonce));
+    }
+
+    #[test]
+    fn verifier_nonce_test() {
+        let key = SecretKey::default();
+        let acc = Accumulator::with_members(&key, ["alice", "carol", "dave"]).unwrap();
+        let witness = NonMembershipWitness::new(&acc, "bob").unwrap();
+        let proof = NonMembershipProof::new_for_verifier(&witness, &acc, b"verifier").unwrap();
+        assert!(proof.verify_for_verifier(&acc, b"verifier"));
+        assert!(!proof.verify_for_verifier(&acc, b"another verifier"));
+        assert!(!proof.verify(&acc, b"verifier"));
+    }
+
+}
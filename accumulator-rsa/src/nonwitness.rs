@@ -1,6 +1,23 @@
-use crate::{accumulator::Accumulator, b2fa, hash_to_prime, FACTOR_SIZE, MEMBER_SIZE};
+use crate::{
+    accumulator::Accumulator,
+    b2fa,
+    format::{Header, Tag},
+    member_element,
+    memwitness::Freshness,
+    prime::PrimeMember,
+    proof_element,
+    store::MemberStore,
+    FACTOR_SIZE, MEMBER_SIZE,
+};
 use common::{bigint::BigInteger, Field, error::*};
-use rayon::prelude::*;
+use crate::par::*;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+#[cfg(feature = "std")]
+use crate::format::{io_error, write_element};
+#[cfg(feature = "std")]
+use std::io::Write;
 
 /// A witness that can be used for non-membership proofs
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -8,36 +25,80 @@ pub struct NonMembershipWitness {
     pub(crate) a: BigInteger,
     pub(crate) b: BigInteger,
     pub(crate) x: BigInteger,
+    pub(crate) freshness: Freshness,
 }
 
 impl NonMembershipWitness {
     /// Return a new non-membership witness
-    pub fn new<B: AsRef<[u8]>>(accumulator: &Accumulator, x: B) -> Result<Self, AccumulatorError> {
-        let x = hash_to_prime(x.as_ref());
+    pub fn new<S: MemberStore, B: AsRef<[u8]>>(accumulator: &Accumulator<S>, x: B) -> Result<Self, AccumulatorError> {
+        let x = accumulator.hash_member(x);
         Self::new_prime(accumulator, &x)
     }
 
     /// Return a new non-membership witness with a value that is already prime
-    pub fn new_prime(accumulator: &Accumulator, x: &BigInteger) -> Result<Self, AccumulatorError> {
-        if accumulator.members.contains(&x) {
+    pub fn new_prime<S: MemberStore>(accumulator: &Accumulator<S>, x: &BigInteger) -> Result<Self, AccumulatorError> {
+        if accumulator.members.contains(x)? {
             return Err(AccumulatorError::from_msg(
                 AccumulatorErrorKind::InvalidMemberSupplied,
                 "value is in the accumulator",
             ));
         }
         let f = Field::new(&accumulator.modulus);
-        let s: BigInteger = accumulator.members.par_iter().product();
+        let s = accumulator.member_product(None)?;
         let gcd_res = s.bezouts_coefficients(x);
-        let b = f.exp(&f.inv(&accumulator.generator), &gcd_res.b);
-        debug_assert_eq!(f.exp(&b, &x), f.mul(&f.inv(&accumulator.generator), &f.exp(&accumulator.value, &gcd_res.a)));
+        let b = accumulator.fixed_exp_inv(&accumulator.generator, &gcd_res.b);
+        debug_assert_eq!(f.exp(&b, x), f.mul(&f.inv(&accumulator.generator)?, &f.exp(&accumulator.value, &gcd_res.a)));
 
         Ok(Self {
             a: gcd_res.a,
             b,
             x: x.clone(),
+            freshness: Freshness::new(accumulator.epoch, &accumulator.value),
         })
     }
 
+    /// Return a new non-membership witness for a prime representative
+    /// already checked to be a prime of the member size
+    pub fn new_checked_prime<S: MemberStore>(
+        accumulator: &Accumulator<S>,
+        x: &PrimeMember,
+    ) -> Result<Self, AccumulatorError> {
+        Self::new_prime(accumulator, x.value())
+    }
+
+    /// The accumulator this witness was last known to be valid for
+    pub fn freshness(&self) -> Freshness {
+        self.freshness
+    }
+
+    /// True if `accumulator` changed since this witness was created or
+    /// last updated. Proofs aren't made from stale witnesses
+    pub fn is_stale<S>(&self, accumulator: &Accumulator<S>) -> bool {
+        self.freshness.check(accumulator.epoch, &accumulator.value).is_err()
+    }
+
+    /// Check this witness is valid for `accumulator` and mark it fresh.
+    /// For witnesses read from bytes written before freshness was tracked
+    /// or carried over epochs that didn't change the value
+    pub fn refresh<S>(&mut self, accumulator: &Accumulator<S>) -> Result<(), AccumulatorError> {
+        let f = Field::new(&accumulator.modulus);
+        // A^a = b^x g, with A^-|a| for a negative a
+        let zero = BigInteger::default();
+        let lhs = if self.a < zero {
+            f.inv(&f.exp(&accumulator.value, &(&zero - &self.a)))?
+        } else {
+            f.exp(&accumulator.value, &self.a)
+        };
+        if lhs != f.mul(&f.exp(&self.b, &self.x), &accumulator.generator) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "witness is not valid for the accumulator",
+            ));
+        }
+        self.freshness = Freshness::new(accumulator.epoch, &accumulator.value);
+        Ok(())
+    }
+
     /// Create a new witness to match `new_acc` from `old_acc` using this witness
     /// by applying the methods found in 4.2 in
     /// <https://www.cs.purdue.edu/homes/ninghui/papers/accumulator_acns07.pdf>
@@ -63,6 +124,7 @@ impl NonMembershipWitness {
         let additions: Vec<&BigInteger> = new_acc.members.difference(&old_acc.members).collect();
         let deletions: Vec<&BigInteger> = old_acc.members.difference(&new_acc.members).collect();
 
+        self.freshness = Freshness::new(new_acc.epoch, &new_acc.value);
         if additions.is_empty() && deletions.is_empty() {
             return Ok(());
         }
@@ -73,7 +135,7 @@ impl NonMembershipWitness {
             let x_hat = deletions.into_par_iter().product();
             let r = &(&x_hat * &self.a) / &self.x;
             self.a = (&self.a * &x_hat) - (&r * &self.x);
-            self.b = f.mul(&self.b, &f.exp(&f.inv(&new_acc.value), &r));
+            self.b = f.mul(&self.b, &new_acc.fixed_exp_inv(&new_acc.value, &r));
             // Check if the assumption holds
             //\widehat{c}^\widehat{a} == g B^{x}
             debug_assert_eq!(f.exp(&new_acc.value, &self.a), f.mul(&new_acc.generator, &f.exp(&self.b, &self.x)));
@@ -99,7 +161,7 @@ impl NonMembershipWitness {
 
             debug_assert_eq!(field.mul(&a_hat, &x_hat), field.mul(&r, &self.x));
 
-            let b_hat = f.mul(&self.b, &f.exp(&old_acc.value, &r));
+            let b_hat = f.mul(&self.b, &old_acc.fixed_exp(&old_acc.value, &r));
 
             self.a = a_hat;
             self.b = b_hat;
@@ -110,20 +172,92 @@ impl NonMembershipWitness {
         Ok(())
     }
 
-    /// Serialize this to bytes
-    pub fn to_bytes(&self) -> Vec<u8> {
-        let mut output = b2fa(&self.a, FACTOR_SIZE * 2);
-        output.append(&mut b2fa(&self.b, FACTOR_SIZE * 2));
-        output.append(&mut b2fa(&self.x, MEMBER_SIZE));
-        output
+    /// Serialize this to bytes. `a` is smaller than `x` so the top bit
+    /// of its field is free and holds the sign
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Header::new(Tag::NonMembershipWitness).to_bytes();
+        let mut a = b2fa(&self.a, FACTOR_SIZE * 2)?;
+        if self.a < BigInteger::default() {
+            a[0] |= 0x80;
+        }
+        output.append(&mut a);
+        output.append(&mut b2fa(&self.b, FACTOR_SIZE * 2)?);
+        output.append(&mut b2fa(&self.x, MEMBER_SIZE)?);
+        output.append(&mut self.freshness.to_bytes());
+        Ok(output)
+    }
+
+    /// Write the same bytes as `to_bytes` to `w`
+    #[cfg(feature = "std")]
+    pub fn write_bytes<W: Write>(&self, mut w: W) -> Result<(), AccumulatorError> {
+        Header::new(Tag::NonMembershipWitness).write_to(&mut w)?;
+        // The sign bit goes on the first byte so `a` isn't streamed
+        let mut a = b2fa(&self.a, FACTOR_SIZE * 2)?;
+        if self.a < BigInteger::default() {
+            a[0] |= 0x80;
+        }
+        w.write_all(&a).map_err(io_error)?;
+        write_element(&mut w, &self.b, FACTOR_SIZE * 2)?;
+        write_element(&mut w, &self.x, MEMBER_SIZE)?;
+        self.freshness.write_to(&mut w)
     }
 }
 
+impl TryFrom<&[u8]> for NonMembershipWitness {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        const SIZE: usize = FACTOR_SIZE * 4 + MEMBER_SIZE;
+        let data = Header::check(data, Tag::NonMembershipWitness)?;
+        // Witnesses written before freshness was tracked are always stale
+        let freshness = match data.len() {
+            SIZE => Freshness::default(),
+            l if l == SIZE + Freshness::SIZE_BYTES => Freshness::parse(&data[SIZE..]),
+            _ => {
+                return Err(AccumulatorErrorKind::InvalidLength {
+                    expected: SIZE + Freshness::SIZE_BYTES,
+                    actual: data.len(),
+                }
+                .into())
+            }
+        };
+        let mut a = data[..(FACTOR_SIZE * 2)].to_vec();
+        let negative = a[0] & 0x80 != 0;
+        a[0] &= 0x7f;
+        let mut a = BigInteger::try_from(a.as_slice())?;
+        let b = proof_element(&data[(FACTOR_SIZE * 2)..(FACTOR_SIZE * 4)])?;
+        let x = member_element(&data[(FACTOR_SIZE * 4)..SIZE])?;
+        if a >= x {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::SerializationError,
+                "witness coefficient is not smaller than the member",
+            ));
+        }
+        if negative {
+            a = &BigInteger::default() - &a;
+        }
+        Ok(Self { a, b, x, freshness })
+    }
+}
+
+impl TryFrom<Vec<u8>> for NonMembershipWitness {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+serdes_impl!(NonMembershipWitness);
+hash_ord_impl!(NonMembershipWitness; a, b, x, freshness);
+hex_impl!(NonMembershipWitness);
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::key::SecretKey;
-    use crate::MEMBER_SIZE_BITS;
+    use crate::format::HEADER_SIZE;
+    use crate::key::AccumulatorSecretKey as SecretKey;
+    use crate::{hash_to_prime, MEMBER_SIZE_BITS};
 
     #[test]
     fn witnesses() {
@@ -135,7 +269,7 @@ mod tests {
             13u64.to_be_bytes(),
         ];
         let member = 17u64.to_be_bytes();
-        let acc = Accumulator::with_members(&key, &members);
+        let acc = Accumulator::with_members(&key, &members).unwrap();
         let witness = NonMembershipWitness::new(&acc, &member).unwrap();
         let x = hash_to_prime(&member);
         assert_eq!(witness.x, x);
@@ -147,7 +281,38 @@ mod tests {
         );
         assert_eq!(witness.b, BigInteger::from("19731949503840799383004983499976351402593806159011822165741044085004905054673855363251385357597006492205730905650466488066773470871149400842396325545777674920024532296129116696323189577451048138544518857167383327747625073230517859862062456981747960458354502115002928340061239460009397008827664942646578083788378616855856348273253698783745015718408649373541254454588228353839211861287000689818331397142653546216894453995644059116432377166068316662466227209474894641100413409398337545057792037057027550522667399457451683638422319281726301941188118255274194652039389040737481315040156989596592391081668567908550005221922"));
 
-        assert_eq!(witness.to_bytes().len(), 4 * FACTOR_SIZE + MEMBER_SIZE);
+        assert_eq!(witness.to_bytes().unwrap().len(), 4 * FACTOR_SIZE + MEMBER_SIZE + Freshness::SIZE_BYTES);
+    }
+
+    #[test]
+    fn serialization() {
+        let key = SecretKey::default();
+        let members = [23u64, 7, 11, 13].map(u64::to_be_bytes);
+        let acc = Accumulator::with_members(&key, members).unwrap();
+        let witness = NonMembershipWitness::new(&acc, 17u64.to_be_bytes()).unwrap();
+        // The sign of a is kept
+        assert!(witness.a < BigInteger::default());
+        let bytes = witness.to_bytes().unwrap();
+        assert_eq!(NonMembershipWitness::try_from(bytes.clone()).unwrap(), witness);
+
+        // a no smaller than x, b of zero and an even x are rejected
+        let a = HEADER_SIZE;
+        let b = a + FACTOR_SIZE * 2;
+        let x = b + FACTOR_SIZE * 2;
+        let mut large = bytes.clone();
+        large[a..b].copy_from_slice(&b2fa(&witness.x, FACTOR_SIZE * 2).unwrap());
+        assert!(NonMembershipWitness::try_from(large).is_err());
+        let mut zero = bytes.clone();
+        zero[b..x].iter_mut().for_each(|v| *v = 0);
+        assert!(NonMembershipWitness::try_from(zero).is_err());
+        let mut even = bytes.clone();
+        even[x + MEMBER_SIZE - 1] ^= 1;
+        assert!(NonMembershipWitness::try_from(even).is_err());
+
+        // Without the freshness it parses but is stale
+        let old = NonMembershipWitness::try_from(&bytes[..(x + MEMBER_SIZE)]).unwrap();
+        assert!(old.is_stale(&acc));
+        assert!(!witness.is_stale(&acc));
     }
 
     #[test]
@@ -162,7 +327,7 @@ mod tests {
             19u64.to_be_bytes(),
         ];
         let member = 37u64.to_be_bytes();
-        let acc = Accumulator::with_members(&key, &members);
+        let acc = Accumulator::with_members(&key, &members).unwrap();
         let witness = NonMembershipWitness::new(&acc, &member).unwrap();
 
         // Test add update
@@ -0,0 +1,120 @@
+// A secret key that lives in a PKCS#11 token. The factors never leave the token,
+// all operations that need the trapdoor are sent to the token as raw RSA
+// private key operations (CKM_RSA_X_509).
+//
+// Standard PKCS#11 only exposes exponentiation by the private exponent of the
+// stored key, i.e. the `e`-th root where `e` is the key's public exponent.
+// Roots for other members require the token to support them, so `root` only
+// succeeds for `x == e`. Accumulators whose members are removed through the
+// token should be built so that each removable member is the public exponent
+// of its own token key.
+use crate::{b2fa, key::SecretKeyOps};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+};
+use cryptoki::{
+    mechanism::Mechanism,
+    object::{Attribute, AttributeType, KeyType, ObjectClass, ObjectHandle},
+    session::Session,
+};
+use std::convert::TryFrom;
+
+/// An accumulator secret key stored in a PKCS#11 token
+pub struct Pkcs11SecretKey {
+    session: Session,
+    handle: ObjectHandle,
+    modulus: BigInteger,
+    exponent: BigInteger,
+}
+
+impl Pkcs11SecretKey {
+    /// Find the RSA private key with `label` using an open and
+    /// logged in session
+    pub fn open(session: Session, label: &str) -> Result<Self, AccumulatorError> {
+        let template = [
+            Attribute::Class(ObjectClass::PRIVATE_KEY),
+            Attribute::KeyType(KeyType::RSA),
+            Attribute::Label(label.as_bytes().to_vec()),
+        ];
+        let handles = session.find_objects(&template).map_err(token_error)?;
+        if handles.len() != 1 {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidType,
+                format!("expected one RSA key labeled {}, found {}", label, handles.len()),
+            ));
+        }
+        let handle = handles[0];
+        let attributes = session
+            .get_attributes(
+                handle,
+                &[AttributeType::Modulus, AttributeType::PublicExponent],
+            )
+            .map_err(token_error)?;
+        let mut modulus = None;
+        let mut exponent = None;
+        for a in attributes {
+            match a {
+                Attribute::Modulus(n) => modulus = Some(BigInteger::try_from(n.as_slice())?),
+                Attribute::PublicExponent(e) => {
+                    exponent = Some(BigInteger::try_from(e.as_slice())?)
+                }
+                _ => {}
+            }
+        }
+        match (modulus, exponent) {
+            (Some(modulus), Some(exponent)) => Ok(Self {
+                session,
+                handle,
+                modulus,
+                exponent,
+            }),
+            _ => Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidType,
+                "token key is missing its modulus or public exponent",
+            )),
+        }
+    }
+
+    /// The public exponent of the token key, the only value
+    /// this key can compute roots for
+    pub fn public_exponent(&self) -> BigInteger {
+        self.exponent.clone()
+    }
+}
+
+impl SecretKeyOps for Pkcs11SecretKey {
+    fn modulus(&self) -> BigInteger {
+        self.modulus.clone()
+    }
+
+    /// The token cannot reduce by the totient so the full product is used
+    fn exp_product(
+        &self,
+        base: &BigInteger,
+        exponents: &[BigInteger],
+    ) -> Result<BigInteger, AccumulatorError> {
+        let exp: BigInteger = exponents.iter().product();
+        Ok(base.mod_exp(&exp, &self.modulus))
+    }
+
+    fn root(&self, base: &BigInteger, x: &BigInteger) -> Result<BigInteger, AccumulatorError> {
+        if x != &self.exponent {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "token can only compute roots for its public exponent",
+            ));
+        }
+        let size = (self.modulus.bits() + 7) / 8;
+        let input = b2fa(&(base % &self.modulus), size)?;
+        let output = self
+            .session
+            .decrypt(&Mechanism::RsaX509, self.handle, &input)
+            .map_err(token_error)?;
+        BigInteger::try_from(output.as_slice())
+    }
+}
+
+fn token_error(e: cryptoki::error::Error) -> AccumulatorError {
+    AccumulatorError::from_source(AccumulatorErrorKind::InvalidType, e)
+}
@@ -0,0 +1,270 @@
+use crate::{MEMBER_PRIMALITY, MEMBER_SIZE_BITS};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+};
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// Certificates nest at most this deep, each level about halves the size
+const MAX_CERTIFICATE_DEPTH: usize = 16;
+/// Factors at most this size are proven prime by trial division
+const SMALL_PRIME_BITS: usize = 32;
+
+/// A prime representative chosen by the caller instead of `hash_to_prime`,
+/// checked to be prime and exactly `MEMBER_SIZE_BITS` long so it can't be
+/// a small prime or a composite that breaks the accumulator's soundness
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub struct PrimeMember(BigInteger);
+
+impl PrimeMember {
+    /// Check `value` with a probabilistic primality test
+    pub fn new(value: BigInteger) -> Result<Self, AccumulatorError> {
+        check_size(&value)?;
+        if !value.is_prime_with(MEMBER_PRIMALITY) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "value is not prime",
+            ));
+        }
+        Ok(Self(value))
+    }
+
+    /// Check `value` with a Pocklington certificate, a proof of primality
+    /// that doesn't depend on a probabilistic test
+    pub fn with_certificate(
+        value: BigInteger,
+        certificate: &PocklingtonCertificate,
+    ) -> Result<Self, AccumulatorError> {
+        check_size(&value)?;
+        if !certificate.verify(&value) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "primality certificate does not verify",
+            ));
+        }
+        Ok(Self(value))
+    }
+
+    /// The prime
+    pub fn value(&self) -> &BigInteger {
+        &self.0
+    }
+
+    /// Return the prime
+    pub fn into_inner(self) -> BigInteger {
+        self.0
+    }
+}
+
+impl AsRef<BigInteger> for PrimeMember {
+    fn as_ref(&self) -> &BigInteger {
+        &self.0
+    }
+}
+
+fn check_size(value: &BigInteger) -> Result<(), AccumulatorError> {
+    if value.bits() != MEMBER_SIZE_BITS {
+        return Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::InvalidMemberSupplied,
+            "value is not the size of a member",
+        ));
+    }
+    Ok(())
+}
+
+/// A Pocklington certificate that `n` is prime.
+///
+/// Lists primes `q` dividing `n - 1` whose powers in `n - 1` multiply to
+/// more than the square root of `n`, each with a witness `a` where
+/// `a^(n - 1) = 1 mod n` and `gcd(a^((n - 1) / q) - 1, n) = 1`.
+/// Factors larger than 32 bits carry their own certificate
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PocklingtonCertificate {
+    /// The factors of `n - 1` and their witnesses
+    pub factors: Vec<PocklingtonFactor>,
+}
+
+/// One prime factor of `n - 1` in a `PocklingtonCertificate`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PocklingtonFactor {
+    /// The prime factor `q`
+    pub prime: BigInteger,
+    /// The witness `a` for `q`
+    pub witness: BigInteger,
+    /// The certificate that `q` is prime, `None` if it's small enough
+    /// to check by trial division
+    pub certificate: Option<PocklingtonCertificate>,
+}
+
+impl PocklingtonCertificate {
+    /// Check the certificate proves `n` is prime
+    pub fn verify(&self, n: &BigInteger) -> bool {
+        self.verify_at(n, 0)
+    }
+
+    fn verify_at(&self, n: &BigInteger, depth: usize) -> bool {
+        let one = BigInteger::from(1u32);
+        let two = BigInteger::from(2u32);
+        if depth > MAX_CERTIFICATE_DEPTH || n <= &two {
+            return false;
+        }
+        let n_1 = n - &one;
+        let mut rest = n_1.clone();
+        let mut proven = one.clone();
+        for factor in &self.factors {
+            let q = &factor.prime;
+            if q < &two {
+                return false;
+            }
+            // All of the powers of q, a repeated factor is left with a remainder
+            let mut divides = false;
+            loop {
+                let (whole, r) = BigInteger::div_rem(&rest, q);
+                if r != BigInteger::default() {
+                    break;
+                }
+                rest = whole;
+                proven *= q;
+                divides = true;
+            }
+            let q_prime = match &factor.certificate {
+                Some(c) => c.verify_at(q, depth + 1),
+                None => is_small_prime(q),
+            };
+            let a = &factor.witness;
+            if !divides || !q_prime || a < &two || a >= n || a.mod_exp(&n_1, n) != one {
+                return false;
+            }
+            let (e, _) = BigInteger::div_rem(&n_1, q);
+            let t = &a.mod_exp(&e, n) - &one;
+            if t.bezouts_coefficients(n).value != one {
+                return false;
+            }
+        }
+        &proven * &proven > *n
+    }
+}
+
+/// Trial division for values of at most `SMALL_PRIME_BITS`
+fn is_small_prime(q: &BigInteger) -> bool {
+    if q.bits() > SMALL_PRIME_BITS {
+        return false;
+    }
+    let q = q
+        .to_bytes()
+        .iter()
+        .fold(0u64, |acc, b| (acc << 8) | u64::from(*b));
+    if q < 2 {
+        return false;
+    }
+    let mut d = 2u64;
+    while d * d <= q {
+        if q % d == 0 {
+            return false;
+        }
+        d += 1;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pow2;
+
+    // A prime of `bits` bits made as 2kq + 1 for a certified prime q
+    // of a little over half the size
+    fn certified(bits: usize) -> (BigInteger, Option<PocklingtonCertificate>) {
+        if bits <= SMALL_PRIME_BITS {
+            return (BigInteger::generate_prime(bits), None);
+        }
+        let (q, q_certificate) = certified(bits / 2 + 2);
+        let one = BigInteger::from(1u32);
+        let two = BigInteger::from(2u32);
+        let low = pow2(bits - 1).unwrap();
+        loop {
+            let k = &pow2(bits - q.bits() - 1).unwrap().rand_range() + &pow2(bits - q.bits() - 2).unwrap();
+            let n = &(&(&two * &k) * &q) + &one;
+            if n < low || n.bits() != bits || !n.is_prime() {
+                continue;
+            }
+            let certificate = PocklingtonCertificate {
+                factors: vec![
+                    PocklingtonFactor {
+                        prime: two.clone(),
+                        witness: BigInteger::from(3u32),
+                        certificate: None,
+                    },
+                    PocklingtonFactor {
+                        prime: q.clone(),
+                        witness: BigInteger::from(3u32),
+                        certificate: q_certificate.clone(),
+                    },
+                ],
+            };
+            if certificate.verify(&n) {
+                return (n, Some(certificate));
+            }
+        }
+    }
+
+    #[test]
+    fn checks_members() {
+        let (p, certificate) = certified(MEMBER_SIZE_BITS);
+        let certificate = certificate.unwrap();
+        assert_eq!(PrimeMember::new(p.clone()).unwrap().value(), &p);
+        assert_eq!(
+            PrimeMember::with_certificate(p.clone(), &certificate)
+                .unwrap()
+                .into_inner(),
+            p
+        );
+
+        // Not for another prime, a composite or the wrong size
+        let other = BigInteger::generate_prime(MEMBER_SIZE_BITS);
+        assert!(PrimeMember::with_certificate(other, &certificate).is_err());
+        let composite = &p * &BigInteger::from(3u32);
+        assert!(PrimeMember::new(pow2(MEMBER_SIZE_BITS - 1).unwrap() + BigInteger::from(1u32)).is_err());
+        assert!(PrimeMember::new(composite).is_err());
+        assert!(PrimeMember::new(BigInteger::generate_prime(128)).is_err());
+
+        // The proven part of n - 1 must exceed the square root
+        let mut partial = certificate.clone();
+        partial.factors.pop();
+        assert!(!partial.verify(&p));
+        let mut repeated = certificate.clone();
+        repeated.factors.push(repeated.factors[0].clone());
+        assert!(!repeated.verify(&p));
+        let mut bad_witness = certificate;
+        bad_witness.factors[1].witness = BigInteger::from(1u32);
+        assert!(!bad_witness.verify(&p));
+        assert!(is_small_prime(&BigInteger::from(65_521u32)));
+        assert!(!is_small_prime(&BigInteger::from(65_519u32 * 3)));
+    }
+
+    #[test]
+    fn accumulate_checked() {
+        use crate::{
+            accumulator::Accumulator, key::AccumulatorSecretKey, nonwitness::NonMembershipWitness,
+        };
+
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<PrimeMember> = (0..3)
+            .map(|_| PrimeMember::new(BigInteger::generate_prime(MEMBER_SIZE_BITS)).unwrap())
+            .collect();
+        let mut acc = Accumulator::with_checked_primes(&key, &members[..2]).unwrap();
+        assert!(NonMembershipWitness::new_checked_prime(&acc, &members[2]).is_ok());
+        acc.insert_checked_prime_assign(&members[2]).unwrap();
+        assert!(acc.insert_checked_prime_assign(&members[2]).is_err());
+        assert!(NonMembershipWitness::new_checked_prime(&acc, &members[2]).is_err());
+        let expected = Accumulator::with_prime_members(
+            &key,
+            &[members[0].value().clone(), members[1].value().clone()],
+        )
+        .unwrap()
+        .insert_prime(members[2].value())
+        .unwrap();
+        assert_eq!(acc.value, expected.value);
+    }
+}
@@ -0,0 +1,447 @@
+// The messages are what prost-build generates for proto/accumulator.proto,
+// checked in so building doesn't need protoc. Keep them in sync with the schema.
+use crate::{accumulator::{self, check_parameters}, memproof, memwitness, nonmemproof, nonwitness, registry, MEMBER_PRIMALITY};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+};
+use core::convert::TryFrom;
+use std::sync::Arc;
+
+pub use prost;
+
+/// An accumulator with its members
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Accumulator {
+    /// The initial value
+    #[prost(bytes = "vec", tag = "1")]
+    pub generator: Vec<u8>,
+    /// The RSA modulus
+    #[prost(bytes = "vec", tag = "2")]
+    pub modulus: Vec<u8>,
+    /// The current value
+    #[prost(bytes = "vec", tag = "3")]
+    pub value: Vec<u8>,
+    /// The number of updates applied to `value`
+    #[prost(uint64, tag = "4")]
+    pub epoch: u64,
+    /// Hashed member primes in ascending order
+    #[prost(bytes = "vec", repeated, tag = "5")]
+    pub members: Vec<Vec<u8>>,
+}
+
+/// An accumulator without the members
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct AccumulatorPublic {
+    /// The initial value
+    #[prost(bytes = "vec", tag = "1")]
+    pub generator: Vec<u8>,
+    /// The RSA modulus
+    #[prost(bytes = "vec", tag = "2")]
+    pub modulus: Vec<u8>,
+    /// The current value
+    #[prost(bytes = "vec", tag = "3")]
+    pub value: Vec<u8>,
+    /// The number of updates applied to `value`
+    #[prost(uint64, tag = "4")]
+    pub epoch: u64,
+}
+
+/// A membership witness
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MembershipWitness {
+    /// The accumulator value without the member
+    #[prost(bytes = "vec", tag = "1")]
+    pub u: Vec<u8>,
+    /// The hashed member
+    #[prost(bytes = "vec", tag = "2")]
+    pub x: Vec<u8>,
+    /// The epoch the witness was last valid for
+    #[prost(uint64, tag = "3")]
+    pub epoch: u64,
+    /// The hash of the value the witness was last valid for, empty if unknown
+    #[prost(bytes = "vec", tag = "4")]
+    pub value_hash: Vec<u8>,
+}
+
+/// A non-membership witness
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct NonMembershipWitness {
+    /// The magnitude of the Bézout coefficient for the members
+    #[prost(bytes = "vec", tag = "1")]
+    pub a: Vec<u8>,
+    /// True if `a` is negative
+    #[prost(bool, tag = "2")]
+    pub a_negative: bool,
+    /// The generator raised to the Bézout coefficient for the value
+    #[prost(bytes = "vec", tag = "3")]
+    pub b: Vec<u8>,
+    /// The hashed value
+    #[prost(bytes = "vec", tag = "4")]
+    pub x: Vec<u8>,
+    /// The epoch the witness was last valid for
+    #[prost(uint64, tag = "5")]
+    pub epoch: u64,
+    /// The hash of the value the witness was last valid for, empty if unknown
+    #[prost(bytes = "vec", tag = "6")]
+    pub value_hash: Vec<u8>,
+}
+
+/// A proof of knowledge of exponent
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Poke2Proof {
+    /// The base
+    #[prost(bytes = "vec", tag = "1")]
+    pub u: Vec<u8>,
+    /// The generator raised to the exponent
+    #[prost(bytes = "vec", tag = "2")]
+    pub z: Vec<u8>,
+    /// The quotient commitment
+    #[prost(bytes = "vec", tag = "3")]
+    pub q: Vec<u8>,
+    /// The exponent mod the challenge prime
+    #[prost(bytes = "vec", tag = "4")]
+    pub r: Vec<u8>,
+    /// The format version of the proof, which gives the challenge size
+    #[prost(uint32, tag = "5")]
+    pub version: u32,
+}
+
+/// A membership proof
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MembershipProof {
+    /// The proof of knowledge of the member
+    #[prost(message, optional, tag = "1")]
+    pub proof: Option<Poke2Proof>,
+}
+
+/// A non-membership proof
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct NonMembershipProof {
+    /// The accumulator raised to `a`
+    #[prost(bytes = "vec", tag = "1")]
+    pub v: Vec<u8>,
+    /// `z` of the proof for `v`
+    #[prost(bytes = "vec", tag = "2")]
+    pub z: Vec<u8>,
+    /// `q` of the proof for `v`
+    #[prost(bytes = "vec", tag = "3")]
+    pub q: Vec<u8>,
+    /// `r` of the proof for `v`
+    #[prost(bytes = "vec", tag = "4")]
+    pub r: Vec<u8>,
+    /// The proof for the generator
+    #[prost(message, optional, tag = "5")]
+    pub proof_g: Option<Poke2Proof>,
+}
+
+/// The change between two published accumulator values
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct UpdateDelta {
+    /// The epoch the delta applies to
+    #[prost(uint64, tag = "1")]
+    pub from_epoch: u64,
+    /// The epoch after applying the delta
+    #[prost(uint64, tag = "2")]
+    pub to_epoch: u64,
+    /// The accumulator value at `to_epoch`
+    #[prost(bytes = "vec", tag = "3")]
+    pub value: Vec<u8>,
+    /// Member primes added since `from_epoch`
+    #[prost(bytes = "vec", repeated, tag = "4")]
+    pub additions: Vec<Vec<u8>>,
+    /// Member primes removed since `from_epoch`
+    #[prost(bytes = "vec", repeated, tag = "5")]
+    pub deletions: Vec<Vec<u8>>,
+}
+
+fn int(b: &[u8]) -> Result<BigInteger, AccumulatorError> {
+    BigInteger::try_from(b)
+}
+
+fn freshness(epoch: u64, value_hash: &[u8]) -> Result<memwitness::Freshness, AccumulatorError> {
+    if value_hash.is_empty() {
+        return Ok(memwitness::Freshness::default());
+    }
+    if value_hash.len() != memwitness::Freshness::HASH_BYTES {
+        return Err(AccumulatorErrorKind::InvalidLength {
+            expected: memwitness::Freshness::HASH_BYTES,
+            actual: value_hash.len(),
+        }
+        .into());
+    }
+    Ok(memwitness::Freshness {
+        epoch,
+        value_hash: *array_ref![value_hash, 0, memwitness::Freshness::HASH_BYTES],
+    })
+}
+
+fn missing(field: &str) -> AccumulatorError {
+    AccumulatorError::from_msg(AccumulatorErrorKind::SerializationError, format!("missing {}", field))
+}
+
+impl From<&accumulator::Accumulator> for Accumulator {
+    fn from(a: &accumulator::Accumulator) -> Self {
+        Self {
+            generator: a.generator.to_bytes(),
+            modulus: a.modulus.to_bytes(),
+            value: a.value.to_bytes(),
+            epoch: a.epoch,
+            members: a.members.iter().map(|m| m.to_bytes()).collect(),
+        }
+    }
+}
+
+impl TryFrom<Accumulator> for accumulator::Accumulator {
+    type Error = AccumulatorError;
+
+    fn try_from(a: Accumulator) -> Result<Self, Self::Error> {
+        let generator = int(&a.generator)?;
+        let modulus = int(&a.modulus)?;
+        let value = int(&a.value)?;
+        check_parameters(&generator, &value, &modulus)?;
+        Ok(Self {
+            generator: Arc::new(generator),
+            modulus: Arc::new(modulus),
+            value,
+            epoch: a.epoch,
+            members: a.members.iter().map(|m| int(m)).collect::<Result<_, _>>()?,
+            fixed_bases: Vec::new(),
+            product: None,
+            primality: MEMBER_PRIMALITY,
+        })
+    }
+}
+
+impl From<&accumulator::AccumulatorPublic> for AccumulatorPublic {
+    fn from(a: &accumulator::AccumulatorPublic) -> Self {
+        Self {
+            generator: a.generator.to_bytes(),
+            modulus: a.modulus.to_bytes(),
+            value: a.value.to_bytes(),
+            epoch: a.epoch,
+        }
+    }
+}
+
+impl TryFrom<AccumulatorPublic> for accumulator::AccumulatorPublic {
+    type Error = AccumulatorError;
+
+    fn try_from(a: AccumulatorPublic) -> Result<Self, Self::Error> {
+        let generator = int(&a.generator)?;
+        let modulus = int(&a.modulus)?;
+        let value = int(&a.value)?;
+        check_parameters(&generator, &value, &modulus)?;
+        Ok(Self {
+            generator,
+            modulus,
+            value,
+            epoch: a.epoch,
+        })
+    }
+}
+
+impl From<&memwitness::MembershipWitness> for MembershipWitness {
+    fn from(w: &memwitness::MembershipWitness) -> Self {
+        Self {
+            u: w.u.to_bytes(),
+            x: w.x.to_bytes(),
+            epoch: w.freshness.epoch,
+            value_hash: w.freshness.value_hash.to_vec(),
+        }
+    }
+}
+
+impl TryFrom<MembershipWitness> for memwitness::MembershipWitness {
+    type Error = AccumulatorError;
+
+    fn try_from(w: MembershipWitness) -> Result<Self, Self::Error> {
+        Ok(Self {
+            u: int(&w.u)?,
+            x: int(&w.x)?,
+            freshness: freshness(w.epoch, &w.value_hash)?,
+        })
+    }
+}
+
+impl From<&nonwitness::NonMembershipWitness> for NonMembershipWitness {
+    fn from(w: &nonwitness::NonMembershipWitness) -> Self {
+        Self {
+            a: w.a.to_bytes(),
+            a_negative: w.a < BigInteger::default(),
+            b: w.b.to_bytes(),
+            x: w.x.to_bytes(),
+            epoch: w.freshness.epoch,
+            value_hash: w.freshness.value_hash.to_vec(),
+        }
+    }
+}
+
+impl TryFrom<NonMembershipWitness> for nonwitness::NonMembershipWitness {
+    type Error = AccumulatorError;
+
+    fn try_from(w: NonMembershipWitness) -> Result<Self, Self::Error> {
+        let a = int(&w.a)?;
+        Ok(Self {
+            a: if w.a_negative { &BigInteger::default() - &a } else { a },
+            b: int(&w.b)?,
+            x: int(&w.x)?,
+            freshness: freshness(w.epoch, &w.value_hash)?,
+        })
+    }
+}
+
+impl From<&crate::Poke2Proof> for Poke2Proof {
+    fn from(p: &crate::Poke2Proof) -> Self {
+        Self {
+            u: p.u.to_bytes(),
+            z: p.z.to_bytes(),
+            q: p.q.to_bytes(),
+            r: p.r.to_bytes(),
+            version: p.size.version() as u32,
+        }
+    }
+}
+
+impl TryFrom<Poke2Proof> for crate::Poke2Proof {
+    type Error = AccumulatorError;
+
+    fn try_from(p: Poke2Proof) -> Result<Self, Self::Error> {
+        let version = u8::try_from(p.version).map_err(|_| {
+            AccumulatorError::from_msg(AccumulatorErrorKind::SerializationError, "unsupported format version")
+        })?;
+        Ok(Self {
+            u: int(&p.u)?,
+            z: int(&p.z)?,
+            q: int(&p.q)?,
+            r: int(&p.r)?,
+            size: crate::ProofSize::from_version(version)?,
+        })
+    }
+}
+
+impl From<&memproof::MembershipProof> for MembershipProof {
+    fn from(p: &memproof::MembershipProof) -> Self {
+        Self {
+            proof: Some((&p.0).into()),
+        }
+    }
+}
+
+impl TryFrom<MembershipProof> for memproof::MembershipProof {
+    type Error = AccumulatorError;
+
+    fn try_from(p: MembershipProof) -> Result<Self, Self::Error> {
+        let proof = p.proof.ok_or_else(|| missing("proof"))?;
+        Ok(Self(crate::Poke2Proof::try_from(proof)?))
+    }
+}
+
+impl From<&nonmemproof::NonMembershipProof> for NonMembershipProof {
+    fn from(p: &nonmemproof::NonMembershipProof) -> Self {
+        Self {
+            v: p.v.to_bytes(),
+            z: p.z.to_bytes(),
+            q: p.q.to_bytes(),
+            r: p.r.to_bytes(),
+            proof_g: Some((&p.proof_g).into()),
+        }
+    }
+}
+
+impl TryFrom<NonMembershipProof> for nonmemproof::NonMembershipProof {
+    type Error = AccumulatorError;
+
+    fn try_from(p: NonMembershipProof) -> Result<Self, Self::Error> {
+        let proof_g = p.proof_g.ok_or_else(|| missing("proof_g"))?;
+        Ok(Self {
+            v: int(&p.v)?,
+            z: int(&p.z)?,
+            q: int(&p.q)?,
+            r: int(&p.r)?,
+            proof_g: crate::Poke2Proof::try_from(proof_g)?,
+        })
+    }
+}
+
+impl From<&registry::RegistryDelta> for UpdateDelta {
+    fn from(d: &registry::RegistryDelta) -> Self {
+        Self {
+            from_epoch: d.from_epoch,
+            to_epoch: d.to_epoch,
+            value: d.value.to_bytes(),
+            additions: d.additions.iter().map(|m| m.to_bytes()).collect(),
+            deletions: d.deletions.iter().map(|m| m.to_bytes()).collect(),
+        }
+    }
+}
+
+impl TryFrom<UpdateDelta> for registry::RegistryDelta {
+    type Error = AccumulatorError;
+
+    fn try_from(d: UpdateDelta) -> Result<Self, Self::Error> {
+        Ok(Self {
+            from_epoch: d.from_epoch,
+            to_epoch: d.to_epoch,
+            value: int(&d.value)?,
+            additions: d.additions.iter().map(|m| int(m)).collect::<Result<_, _>>()?,
+            deletions: d.deletions.iter().map(|m| int(m)).collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::AccumulatorSecretKey;
+    use prost::Message;
+
+    fn round_trip<M: Message + Default>(m: &M) -> M {
+        M::decode(m.encode_to_vec().as_slice()).unwrap()
+    }
+
+    #[test]
+    fn conversions() {
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = vec![
+            3u64.to_be_bytes(),
+            7u64.to_be_bytes(),
+            11u64.to_be_bytes(),
+            13u64.to_be_bytes(),
+        ];
+        let acc = accumulator::Accumulator::with_members(&key, &members).unwrap();
+        let msg = round_trip(&Accumulator::from(&acc));
+        assert_eq!(accumulator::Accumulator::try_from(msg).unwrap(), acc);
+        let msg = round_trip(&AccumulatorPublic::from(&acc.public()));
+        assert_eq!(accumulator::AccumulatorPublic::try_from(msg).unwrap(), acc.public());
+
+        let witness = memwitness::MembershipWitness::new(&acc, members[0]).unwrap();
+        let msg = round_trip(&MembershipWitness::from(&witness));
+        assert_eq!(memwitness::MembershipWitness::try_from(msg).unwrap(), witness);
+        let proof = memproof::MembershipProof::new(&witness, &acc, b"proto").unwrap();
+        let msg = round_trip(&MembershipProof::from(&proof));
+        assert!(memproof::MembershipProof::try_from(msg).unwrap().verify(&acc, b"proto"));
+        assert!(memproof::MembershipProof::try_from(MembershipProof::default()).is_err());
+        let compact = memproof::MembershipProof::new_compact(&witness, &acc, b"proto").unwrap();
+        let mut msg = round_trip(&MembershipProof::from(&compact));
+        assert_eq!(memproof::MembershipProof::try_from(msg.clone()).unwrap(), compact);
+        msg.proof.as_mut().unwrap().version = 3;
+        assert!(memproof::MembershipProof::try_from(msg).is_err());
+
+        let witness = nonwitness::NonMembershipWitness::new(&acc, 17u64.to_be_bytes()).unwrap();
+        let msg = round_trip(&NonMembershipWitness::from(&witness));
+        assert_eq!(nonwitness::NonMembershipWitness::try_from(msg).unwrap(), witness);
+        let proof = nonmemproof::NonMembershipProof::new(&witness, &acc, b"proto").unwrap();
+        let msg = round_trip(&NonMembershipProof::from(&proof));
+        assert!(nonmemproof::NonMembershipProof::try_from(msg).unwrap().verify(&acc, b"proto"));
+        let compact = nonmemproof::NonMembershipProof::new_compact(&witness, &acc, b"proto").unwrap();
+        let msg = round_trip(&NonMembershipProof::from(&compact));
+        assert!(nonmemproof::NonMembershipProof::try_from(msg).unwrap().verify(&acc, b"proto"));
+
+        let mut registry = registry::RevocationRegistry::new(&key);
+        registry.register(members[0]).unwrap();
+        let delta = registry.publish(&key).unwrap().unwrap();
+        let msg = round_trip(&UpdateDelta::from(delta));
+        assert_eq!(&registry::RegistryDelta::try_from(msg).unwrap(), delta);
+    }
+}
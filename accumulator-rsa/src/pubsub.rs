@@ -0,0 +1,519 @@
+// Messages issuers push to holders over publish/subscribe transports like
+// MQTT, NATS or websockets. Transports only move bytes, holders hand every
+// message to an `UpdateSubscriber` which applies it to their witness.
+//
+// Each message is wrapped in an envelope with a sequence number the
+// publisher increments per message so holders notice lost and repeated
+// messages. A stream has one publisher, hints sent to a holder's own topic
+// are numbered by a publisher for that topic.
+//
+// Transports aren't trusted, the issuer signs every envelope with its
+// Ed25519 key and subscribers drop anything else before looking at it.
+// Otherwise anyone could send a hint for a made up epoch, or a sequence
+// number near the end of the range, and every real message after it would
+// be ignored.
+//
+// Layout: header, sequence (u64), kind (u8), then
+//   announcement: epoch (u64), value
+//   delta: the `RegistryDelta` bytes after its header
+//   hint: epoch (u64), value, member prime, witness value
+// followed by the signature over everything before it
+use crate::{
+    accumulator::AccumulatorPublic,
+    b2fa,
+    format::{Header, Tag, HEADER_SIZE},
+    memwitness::{Freshness, MembershipWitness},
+    registry::RegistryDelta,
+    telemetry, FACTOR_SIZE, MEMBER_SIZE,
+};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey, SIGNATURE_LENGTH};
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+const ANNOUNCEMENT: u8 = 1;
+const DELTA: u8 = 2;
+const HINT: u8 = 3;
+
+/// A new registry epoch was published. Holders that missed the
+/// delta learn they are behind
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct EpochAnnouncement {
+    /// The published epoch
+    pub epoch: u64,
+    /// The accumulator value at `epoch`
+    pub value: BigInteger,
+}
+
+impl EpochAnnouncement {
+    /// Announce the epoch of `definition`
+    pub fn new(definition: &AccumulatorPublic) -> Self {
+        Self {
+            epoch: definition.epoch,
+            value: definition.value.clone(),
+        }
+    }
+}
+
+/// The witness of one holder at an epoch so a holder far behind
+/// doesn't replay every delta
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct WitnessHint {
+    /// The epoch the witness is for
+    pub epoch: u64,
+    /// The accumulator value at `epoch`
+    pub value: BigInteger,
+    /// The member prime of the holder
+    pub x: BigInteger,
+    /// The witness value
+    pub u: BigInteger,
+}
+
+impl WitnessHint {
+    /// The hint for `witness`, which matches `definition`
+    pub fn new(witness: &MembershipWitness, definition: &AccumulatorPublic) -> Self {
+        Self {
+            epoch: definition.epoch,
+            value: definition.value.clone(),
+            x: witness.x.clone(),
+            u: witness.u.clone(),
+        }
+    }
+}
+
+/// The messages of an update stream
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum UpdateMessage {
+    /// A new epoch was published
+    Announcement(EpochAnnouncement),
+    /// The changes of an epoch
+    Delta(RegistryDelta),
+    /// A witness for one holder
+    Hint(WitnessHint),
+}
+
+/// A message, its position in the stream and the issuer's signature
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UpdateEnvelope {
+    /// Increases by one with every message of the stream
+    pub sequence: u64,
+    /// The message
+    pub message: UpdateMessage,
+    signature: Signature,
+}
+
+impl UpdateEnvelope {
+    /// Sign `message` at `sequence` with the issuer's `key`
+    pub fn new(sequence: u64, message: UpdateMessage, key: &SigningKey) -> Result<Self, AccumulatorError> {
+        let signature = key.sign(&signed_bytes(sequence, &message)?);
+        Ok(Self {
+            sequence,
+            message,
+            signature,
+        })
+    }
+
+    /// Check the envelope was signed by `key`
+    pub fn verify(&self, key: &VerifyingKey) -> Result<(), AccumulatorError> {
+        key.verify_strict(&signed_bytes(self.sequence, &self.message)?, &self.signature)
+            .map_err(|_| {
+                AccumulatorError::from_msg(
+                    AccumulatorErrorKind::ProofVerificationFailed,
+                    "invalid update message signature",
+                )
+            })
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = signed_bytes(self.sequence, &self.message)?;
+        output.extend_from_slice(&self.signature.to_bytes());
+        Ok(output)
+    }
+}
+
+/// What the issuer signs, the envelope bytes without the signature
+fn signed_bytes(sequence: u64, message: &UpdateMessage) -> Result<Vec<u8>, AccumulatorError> {
+    let mut output = Header::new(Tag::UpdateMessage).to_bytes();
+    output.extend_from_slice(&sequence.to_be_bytes());
+    match message {
+        UpdateMessage::Announcement(a) => {
+            output.push(ANNOUNCEMENT);
+            output.extend_from_slice(&a.epoch.to_be_bytes());
+            output.append(&mut b2fa(&a.value, FACTOR_SIZE * 2)?);
+        }
+        UpdateMessage::Delta(d) => {
+            output.push(DELTA);
+            output.extend_from_slice(&d.to_bytes()?[HEADER_SIZE..]);
+        }
+        UpdateMessage::Hint(h) => {
+            output.push(HINT);
+            output.extend_from_slice(&h.epoch.to_be_bytes());
+            output.append(&mut b2fa(&h.value, FACTOR_SIZE * 2)?);
+            output.append(&mut b2fa(&h.x, MEMBER_SIZE)?);
+            output.append(&mut b2fa(&h.u, FACTOR_SIZE * 2)?);
+        }
+    }
+    Ok(output)
+}
+
+impl TryFrom<&[u8]> for UpdateEnvelope {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::UpdateMessage)?;
+        if data.len() < 9 + SIGNATURE_LENGTH {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: 9 + SIGNATURE_LENGTH,
+                actual: data.len(),
+            }
+            .into());
+        }
+        let (data, signature) = data.split_at(data.len() - SIGNATURE_LENGTH);
+        let sequence = u64::from_be_bytes(*array_ref![data, 0, 8]);
+        let body = &data[9..];
+        let expect = |size: usize| -> Result<(), AccumulatorError> {
+            if body.len() != size {
+                return Err(AccumulatorErrorKind::InvalidLength {
+                    expected: size,
+                    actual: body.len(),
+                }
+                .into());
+            }
+            Ok(())
+        };
+        let message = match data[8] {
+            ANNOUNCEMENT => {
+                expect(8 + FACTOR_SIZE * 2)?;
+                UpdateMessage::Announcement(EpochAnnouncement {
+                    epoch: u64::from_be_bytes(*array_ref![body, 0, 8]),
+                    value: BigInteger::try_from(&body[8..])?,
+                })
+            }
+            DELTA => {
+                let mut delta = Header::new(Tag::RegistryDelta).to_bytes();
+                delta.extend_from_slice(body);
+                UpdateMessage::Delta(RegistryDelta::try_from(delta)?)
+            }
+            HINT => {
+                let x_start = 8 + FACTOR_SIZE * 2;
+                let u_start = x_start + MEMBER_SIZE;
+                expect(u_start + FACTOR_SIZE * 2)?;
+                UpdateMessage::Hint(WitnessHint {
+                    epoch: u64::from_be_bytes(*array_ref![body, 0, 8]),
+                    value: BigInteger::try_from(&body[8..x_start])?,
+                    x: BigInteger::try_from(&body[x_start..u_start])?,
+                    u: BigInteger::try_from(&body[u_start..])?,
+                })
+            }
+            k => {
+                return Err(AccumulatorError::from_msg(
+                    AccumulatorErrorKind::SerializationError,
+                    format_args!("unknown update message kind {}", k),
+                ))
+            }
+        };
+        Ok(Self {
+            sequence,
+            message,
+            signature: Signature::from_bytes(array_ref![signature, 0, SIGNATURE_LENGTH]),
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for UpdateEnvelope {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+serdes_impl!(UpdateEnvelope);
+
+/// Numbers the messages of one stream on the issuer side
+#[derive(Debug, Clone, Default)]
+pub struct UpdatePublisher {
+    sequence: u64,
+}
+
+impl UpdatePublisher {
+    /// Start numbering at `sequence`. Save `sequence` with the registry
+    /// so a restarted issuer doesn't reuse numbers holders have seen
+    pub fn new(sequence: u64) -> Self {
+        Self { sequence }
+    }
+
+    /// The number of the next message
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Wrap `message` in the next envelope signed with the issuer's `key`
+    pub fn publish(&mut self, message: UpdateMessage, key: &SigningKey) -> Result<UpdateEnvelope, AccumulatorError> {
+        let envelope = UpdateEnvelope::new(self.sequence, message, key)?;
+        self.sequence += 1;
+        Ok(envelope)
+    }
+}
+
+/// What `UpdateSubscriber` did with a message
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Received {
+    /// The message was seen before and was ignored
+    Duplicate,
+    /// The witness is already at or past the message's epoch
+    Current,
+    /// The witness was updated to this epoch
+    Updated(u64),
+    /// The witness is at `from` and the stream at `to`. Fetch the deltas
+    /// in between, for example from `RevocationRegistry::deltas_since`
+    Behind {
+        /// The epoch of the witness
+        from: u64,
+        /// The epoch of the message
+        to: u64,
+    },
+}
+
+/// Applies the messages of an update stream to a holder's witness
+#[derive(Debug, Clone)]
+pub struct UpdateSubscriber {
+    witness: MembershipWitness,
+    definition: AccumulatorPublic,
+    issuer: VerifyingKey,
+    next: Option<u64>,
+    missed: u64,
+}
+
+impl UpdateSubscriber {
+    /// Keep `witness`, which matches `definition`, up to date with
+    /// messages signed by the `issuer`
+    pub fn new(witness: MembershipWitness, definition: AccumulatorPublic, issuer: VerifyingKey) -> Self {
+        Self {
+            witness,
+            definition,
+            issuer,
+            next: None,
+            missed: 0,
+        }
+    }
+
+    /// The latest witness
+    pub fn witness(&self) -> &MembershipWitness {
+        &self.witness
+    }
+
+    /// The definition the latest witness matches
+    pub fn definition(&self) -> &AccumulatorPublic {
+        &self.definition
+    }
+
+    /// The number of messages skipped by gaps in the sequence numbers
+    pub fn missed(&self) -> u64 {
+        self.missed
+    }
+
+    /// Parse and apply the message in `data`
+    pub fn receive(&mut self, data: &[u8]) -> Result<Received, AccumulatorError> {
+        self.apply(&UpdateEnvelope::try_from(data)?)
+    }
+
+    /// Apply `envelope`. Messages are applied whatever their sequence
+    /// number, old epochs are ignored and missing ones are reported as
+    /// `Received::Behind`. Fails if the envelope wasn't signed by the
+    /// issuer, the credential was revoked or a hint doesn't give a
+    /// witness for this holder
+    pub fn apply(&mut self, envelope: &UpdateEnvelope) -> Result<Received, AccumulatorError> {
+        envelope.verify(&self.issuer)?;
+        match self.next {
+            Some(next) if envelope.sequence < next => return Ok(Received::Duplicate),
+            Some(next) => self.missed += envelope.sequence - next,
+            None => {}
+        }
+        self.next = Some(envelope.sequence.saturating_add(1));
+
+        let epoch = self.definition.epoch;
+        let received = match &envelope.message {
+            UpdateMessage::Announcement(a) if a.epoch > epoch => Received::Behind { from: epoch, to: a.epoch },
+            UpdateMessage::Announcement(_) => Received::Current,
+            UpdateMessage::Delta(d) if d.to_epoch <= epoch => Received::Current,
+            UpdateMessage::Delta(d) if d.from_epoch != epoch => Received::Behind {
+                from: epoch,
+                to: d.to_epoch,
+            },
+            UpdateMessage::Delta(d) => {
+                let mut witness = self.witness.clone();
+                d.update_witness_assign(&mut witness, &self.definition)?;
+                self.definition = d.apply(&self.definition)?;
+                self.witness = witness;
+                Received::Updated(d.to_epoch)
+            }
+            UpdateMessage::Hint(h) => self.apply_hint(h)?,
+        };
+        if let Received::Updated(_) = received {
+            telemetry::count(telemetry::WITNESS_UPDATES, 1);
+        }
+        Ok(received)
+    }
+
+    fn apply_hint(&mut self, hint: &WitnessHint) -> Result<Received, AccumulatorError> {
+        if hint.x != self.witness.x {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "hint is for another member",
+            ));
+        }
+        if hint.epoch <= self.definition.epoch {
+            return Ok(Received::Current);
+        }
+        // Signed by the issuer, but check it's a witness before replacing ours
+        if hint.u.mod_exp(&hint.x, &self.definition.modulus) != hint.value {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::ProofVerificationFailed,
+                "hint does not match the accumulator value",
+            ));
+        }
+        self.definition = AccumulatorPublic {
+            value: hint.value.clone(),
+            epoch: hint.epoch,
+            ..self.definition.clone()
+        };
+        self.witness.u = hint.u.clone();
+        self.witness.freshness = Freshness::new(hint.epoch, &hint.value);
+        Ok(Received::Updated(hint.epoch))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{key::AccumulatorSecretKey, registry::RevocationRegistry};
+    use ed25519_dalek::SigningKey;
+
+    #[test]
+    fn stream() {
+        let key = AccumulatorSecretKey::default();
+        let mut registry = RevocationRegistry::new(&key);
+        for h in ["alice", "bob", "carol"] {
+            registry.register(h).unwrap();
+        }
+        registry.publish(&key).unwrap();
+        let issuer = SigningKey::from_bytes(&[7u8; 32]);
+        let mut holder = UpdateSubscriber::new(
+            registry.witness(&key, "alice").unwrap(),
+            registry.definition(),
+            issuer.verifying_key(),
+        );
+        let mut publisher = UpdatePublisher::new(10);
+
+        // Every message goes through bytes like it would on a transport
+        let send = |publisher: &mut UpdatePublisher, holder: &mut UpdateSubscriber, message: UpdateMessage| {
+            let bytes = publisher.publish(message, &issuer).unwrap().to_bytes().unwrap();
+            holder.receive(&bytes)
+        };
+
+        registry.register("dave").unwrap();
+        let delta = registry.publish(&key).unwrap().unwrap().clone();
+        let message = UpdateMessage::Delta(delta);
+        assert_eq!(send(&mut publisher, &mut holder, message.clone()).unwrap(), Received::Updated(2));
+        assert_eq!(holder.witness(), &registry.witness(&key, "alice").unwrap());
+        assert_eq!(send(&mut publisher, &mut holder, message).unwrap(), Received::Current);
+        let announcement = UpdateMessage::Announcement(EpochAnnouncement::new(&registry.definition()));
+        assert_eq!(send(&mut publisher, &mut holder, announcement).unwrap(), Received::Current);
+
+        // A replayed envelope is a duplicate
+        let announcement = UpdateMessage::Announcement(EpochAnnouncement::new(&registry.definition()));
+        let old = UpdatePublisher::new(11).publish(announcement.clone(), &issuer).unwrap();
+        assert_eq!(holder.apply(&old).unwrap(), Received::Duplicate);
+
+        // Lost messages leave the holder behind until a hint arrives
+        registry.revoke("bob").unwrap();
+        registry.publish(&key).unwrap();
+        registry.register("erin").unwrap();
+        let delta = registry.publish(&key).unwrap().unwrap().clone();
+        publisher
+            .publish(UpdateMessage::Delta(registry.deltas_since(2).unwrap()[0].clone()), &issuer)
+            .unwrap();
+        let message = UpdateMessage::Delta(delta);
+        assert_eq!(
+            send(&mut publisher, &mut holder, message).unwrap(),
+            Received::Behind { from: 2, to: 4 }
+        );
+        assert_eq!(holder.missed(), 1);
+
+        let witness = registry.witness(&key, "alice").unwrap();
+        let mut hint = WitnessHint::new(&witness, &registry.definition());
+        let forged = WitnessHint {
+            u: BigInteger::from(2u32),
+            ..hint.clone()
+        };
+        assert!(send(&mut publisher, &mut holder, UpdateMessage::Hint(forged)).is_err());
+        let received = send(&mut publisher, &mut holder, UpdateMessage::Hint(hint.clone())).unwrap();
+        assert_eq!(received, Received::Updated(4));
+        assert_eq!(holder.witness(), &witness);
+        assert_eq!(holder.definition(), &registry.definition());
+        hint.x = registry.witness(&key, "carol").unwrap().x;
+        assert!(send(&mut publisher, &mut holder, UpdateMessage::Hint(hint)).is_err());
+
+        // Revocation stops the holder
+        registry.revoke("alice").unwrap();
+        let delta = registry.publish(&key).unwrap().unwrap().clone();
+        assert!(send(&mut publisher, &mut holder, UpdateMessage::Delta(delta)).is_err());
+        assert_eq!(holder.definition().epoch, 4);
+        assert_eq!(publisher.sequence(), 19);
+
+        let bytes = publisher.publish(announcement, &issuer).unwrap().to_bytes().unwrap();
+        let mut bad = bytes.clone();
+        bad[HEADER_SIZE + 8] = 9;
+        assert!(UpdateEnvelope::try_from(bad).is_err());
+        assert!(UpdateEnvelope::try_from(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn forged() {
+        let key = AccumulatorSecretKey::default();
+        let mut registry = RevocationRegistry::new(&key);
+        for h in ["alice", "bob"] {
+            registry.register(h).unwrap();
+        }
+        registry.publish(&key).unwrap();
+        let issuer = SigningKey::from_bytes(&[7u8; 32]);
+        let other = SigningKey::from_bytes(&[8u8; 32]);
+        let witness = registry.witness(&key, "alice").unwrap();
+        let mut holder = UpdateSubscriber::new(witness.clone(), registry.definition(), issuer.verifying_key());
+
+        // A hint for a made up epoch that checks out against itself
+        let u = BigInteger::from(2u32);
+        let hint = WitnessHint {
+            epoch: u64::MAX,
+            value: u.mod_exp(&witness.x, &registry.definition().modulus),
+            x: witness.x.clone(),
+            u,
+        };
+        let forged = UpdateEnvelope::new(0, UpdateMessage::Hint(hint.clone()), &other).unwrap();
+        assert!(holder.receive(&forged.to_bytes().unwrap()).is_err());
+        let mut resigned = UpdateEnvelope::new(0, UpdateMessage::Hint(hint), &issuer).unwrap();
+        resigned.sequence = 1;
+        assert!(holder.apply(&resigned).is_err());
+
+        // As is a sequence number at the end of the range
+        let announcement = UpdateMessage::Announcement(EpochAnnouncement::new(&registry.definition()));
+        let forged = UpdateEnvelope::new(u64::MAX, announcement, &other).unwrap();
+        assert!(holder.apply(&forged).is_err());
+        assert_eq!(holder.definition(), &registry.definition());
+        assert_eq!(holder.witness(), &witness);
+
+        // Nothing was taken from them, real messages still apply
+        registry.register("carol").unwrap();
+        let delta = registry.publish(&key).unwrap().unwrap().clone();
+        let envelope = UpdatePublisher::new(0).publish(UpdateMessage::Delta(delta), &issuer).unwrap();
+        assert_eq!(holder.apply(&envelope).unwrap(), Received::Updated(2));
+        assert_eq!(holder.missed(), 0);
+        assert_eq!(holder.witness(), &registry.witness(&key, "alice").unwrap());
+    }
+}
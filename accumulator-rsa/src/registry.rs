@@ -0,0 +1,463 @@
+use crate::{
+    accumulator::{Accumulator, AccumulatorPublic},
+    b2fa,
+    batch::verify_transition,
+    format::{read_members, write_members, Header, Tag},
+    key::SecretKeyOps,
+    memwitness::MembershipWitness,
+    telemetry, Poke2Proof, FACTOR_SIZE,
+};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+};
+#[cfg(not(feature = "std"))]
+use alloc::{collections::BTreeSet, vec::Vec};
+use core::convert::TryFrom;
+#[cfg(feature = "std")]
+use std::collections::BTreeSet;
+#[cfg(feature = "std")]
+use crate::format::io_error;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+/// The issuer side of a credential revocation registry.
+///
+/// Credential handles are members of an accumulator, a credential is revoked
+/// by removing its handle. Registrations and revocations are queued and
+/// applied together by `publish` which moves the registry to the next epoch
+/// and records a `RegistryDelta` holders use to update their witnesses.
+#[derive(Debug, Clone)]
+pub struct RevocationRegistry {
+    accumulator: Accumulator,
+    pending_additions: BTreeSet<BigInteger>,
+    pending_deletions: BTreeSet<BigInteger>,
+    deltas: Vec<RegistryDelta>,
+}
+
+impl RevocationRegistry {
+    /// Create an empty registry
+    #[cfg(feature = "std")]
+    pub fn new<K: SecretKeyOps>(key: &K) -> Self {
+        Self::from_accumulator(Accumulator::new(key))
+    }
+
+    /// Continue a registry from a stored accumulator. Deltas
+    /// published before it was stored are not available
+    pub fn from_accumulator(accumulator: Accumulator) -> Self {
+        Self {
+            accumulator,
+            pending_additions: BTreeSet::new(),
+            pending_deletions: BTreeSet::new(),
+            deltas: Vec::new(),
+        }
+    }
+
+    /// Queue a credential handle to be added in the next epoch
+    pub fn register<B: AsRef<[u8]>>(&mut self, handle: B) -> Result<(), AccumulatorError> {
+        let x = self.accumulator.hash_member(handle);
+        if self.accumulator.members.contains(&x) || self.pending_additions.contains(&x) {
+            return Err(AccumulatorErrorKind::DuplicateValueSupplied.into());
+        }
+        self.pending_additions.insert(x);
+        Ok(())
+    }
+
+    /// Queue a credential handle to be revoked in the next epoch.
+    /// Revoking a handle that hasn't been published yet cancels its registration
+    pub fn revoke<B: AsRef<[u8]>>(&mut self, handle: B) -> Result<(), AccumulatorError> {
+        let x = self.accumulator.hash_member(handle);
+        if self.pending_additions.remove(&x) {
+            return Ok(());
+        }
+        if !self.accumulator.members.contains(&x) || !self.pending_deletions.insert(x) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "handle is not registered or already revoked",
+            ));
+        }
+        Ok(())
+    }
+
+    /// True if there are registrations or revocations waiting for `publish`
+    pub fn has_pending(&self) -> bool {
+        !self.pending_additions.is_empty() || !self.pending_deletions.is_empty()
+    }
+
+    /// Apply the queued registrations and revocations as one epoch
+    /// and return the delta to publish. Returns `None` if nothing is queued
+    pub fn publish<K: SecretKeyOps>(
+        &mut self,
+        key: &K,
+    ) -> Result<Option<&RegistryDelta>, AccumulatorError> {
+        if !self.has_pending() {
+            return Ok(None);
+        }
+        let additions: Vec<BigInteger> = self.pending_additions.iter().cloned().collect();
+        let deletions: Vec<BigInteger> = self.pending_deletions.iter().cloned().collect();
+        let from_epoch = self.accumulator.epoch;
+        self.accumulator.update_batch_assign(key, &additions, &deletions)?;
+        self.pending_additions.clear();
+        self.pending_deletions.clear();
+        self.deltas.push(RegistryDelta {
+            from_epoch,
+            to_epoch: self.accumulator.epoch,
+            value: self.accumulator.value.clone(),
+            additions,
+            deletions,
+        });
+        Ok(self.deltas.last())
+    }
+
+    /// The witness for a published, unrevoked credential handle
+    /// at the current epoch to give to its holder
+    pub fn witness<K: SecretKeyOps, B: AsRef<[u8]>>(
+        &self,
+        key: &K,
+        handle: B,
+    ) -> Result<MembershipWitness, AccumulatorError> {
+        let x = self.accumulator.hash_member(handle);
+        if !self.accumulator.members.contains(&x) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "handle is not published or is revoked",
+            ));
+        }
+        let witness = MembershipWitness::with_prime_and_secret_key(&self.accumulator, key, &x)?;
+        telemetry::count(telemetry::WITNESSES_ISSUED, 1);
+        Ok(witness)
+    }
+
+    /// Prove a published, unrevoked credential handle is not revoked
+    /// at the current epoch without handling its witness
+    pub fn prove_non_revocation<K: SecretKeyOps, B: AsRef<[u8]>, N: AsRef<[u8]>>(
+        &self,
+        key: &K,
+        handle: B,
+        nonce: N,
+    ) -> Result<NonRevocationProof, AccumulatorError> {
+        let witness = self.witness(key, handle)?;
+        NonRevocationProof::new(&witness, &self.definition(), nonce)
+    }
+
+    /// The registry definition to publish for the current epoch
+    pub fn definition(&self) -> AccumulatorPublic {
+        self.accumulator.public()
+    }
+
+    /// The deltas that bring a definition at `epoch` up to the current epoch.
+    /// `None` if `epoch` is from before the oldest delta this registry has
+    pub fn deltas_since(&self, epoch: u64) -> Option<&[RegistryDelta]> {
+        if epoch == self.accumulator.epoch {
+            return Some(&[]);
+        }
+        self.deltas
+            .iter()
+            .position(|d| d.from_epoch == epoch)
+            .map(|i| &self.deltas[i..])
+    }
+
+    /// The underlying accumulator, store it to continue the registry later
+    pub fn accumulator(&self) -> &Accumulator {
+        &self.accumulator
+    }
+}
+
+/// The changes published with a new registry epoch
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RegistryDelta {
+    /// The epoch the delta applies to
+    pub from_epoch: u64,
+    /// The epoch after applying the delta
+    pub to_epoch: u64,
+    /// The accumulator value at `to_epoch`
+    pub value: BigInteger,
+    /// Member primes added since `from_epoch`
+    pub additions: Vec<BigInteger>,
+    /// Member primes removed since `from_epoch`
+    pub deletions: Vec<BigInteger>,
+}
+
+impl RegistryDelta {
+    /// The registry definition after applying this delta to `definition`
+    pub fn apply(&self, definition: &AccumulatorPublic) -> Result<AccumulatorPublic, AccumulatorError> {
+        self.check_epoch(definition)?;
+        Ok(AccumulatorPublic {
+            value: self.value.clone(),
+            epoch: self.to_epoch,
+            ..definition.clone()
+        })
+    }
+
+    /// Check without the secret key that this delta turns `definition`
+    /// into the definition after it and return that definition,
+    /// see `batch::verify_transition`
+    pub fn verify(&self, definition: &AccumulatorPublic) -> Result<AccumulatorPublic, AccumulatorError> {
+        let next = self.apply(definition)?;
+        verify_transition(definition, &next, &self.additions, &self.deletions)?;
+        Ok(next)
+    }
+
+    /// Update a holder's witness that matches `definition` to match
+    /// the definition after this delta
+    pub fn update_witness(
+        &self,
+        witness: &MembershipWitness,
+        definition: &AccumulatorPublic,
+    ) -> Result<MembershipWitness, AccumulatorError> {
+        let mut w = witness.clone();
+        self.update_witness_assign(&mut w, definition)?;
+        Ok(w)
+    }
+
+    /// Update a holder's witness that matches `definition` to match
+    /// the definition after this delta. Fails if the credential was revoked
+    pub fn update_witness_assign(
+        &self,
+        witness: &mut MembershipWitness,
+        definition: &AccumulatorPublic,
+    ) -> Result<(), AccumulatorError> {
+        let next = self.apply(definition)?;
+        if self.deletions.contains(&witness.x) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "credential has been revoked",
+            ));
+        }
+        if self.additions.contains(&witness.x) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "witness was created after this delta",
+            ));
+        }
+        witness.apply_changes(self.additions.iter().collect(), self.deletions.iter().collect(), &next);
+        Ok(())
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Header::new(Tag::RegistryDelta).to_bytes();
+        output.extend_from_slice(&self.from_epoch.to_be_bytes());
+        output.extend_from_slice(&self.to_epoch.to_be_bytes());
+        output.append(&mut b2fa(&self.value, FACTOR_SIZE * 2)?);
+        write_members(&self.additions, &mut output)?;
+        write_members(&self.deletions, &mut output)?;
+        Ok(output)
+    }
+
+    fn check_epoch(&self, definition: &AccumulatorPublic) -> Result<(), AccumulatorError> {
+        if definition.epoch != self.from_epoch {
+            return Err(AccumulatorErrorKind::EpochMismatch {
+                expected: self.from_epoch,
+                actual: definition.epoch,
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<&[u8]> for RegistryDelta {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::RegistryDelta)?;
+        let fixed = 16 + FACTOR_SIZE * 2;
+        let mut offset = fixed;
+        let additions = read_members(data, &mut offset)?;
+        let deletions = read_members(data, &mut offset)?;
+        if offset != data.len() {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: offset,
+                actual: data.len(),
+            }
+            .into());
+        }
+        Ok(Self {
+            from_epoch: u64::from_be_bytes(*array_ref![data, 0, 8]),
+            to_epoch: u64::from_be_bytes(*array_ref![data, 8, 8]),
+            value: BigInteger::try_from(&data[16..fixed])?,
+            additions,
+            deletions,
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for RegistryDelta {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+serdes_impl!(RegistryDelta);
+
+/// A holder's proof that their credential is not revoked
+/// in a published registry definition
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NonRevocationProof {
+    /// The registry epoch the proof was created for
+    pub epoch: u64,
+    proof: Poke2Proof,
+}
+
+impl NonRevocationProof {
+    /// Create a proof from a witness that matches `definition`
+    pub fn new<B: AsRef<[u8]>>(
+        witness: &MembershipWitness,
+        definition: &AccumulatorPublic,
+        nonce: B,
+    ) -> Result<Self, AccumulatorError> {
+        let proof = Poke2Proof::new(
+            &witness.x,
+            &witness.u,
+            &definition.value,
+            &definition.modulus,
+            nonce,
+        )?;
+        Ok(Self {
+            epoch: definition.epoch,
+            proof,
+        })
+    }
+
+    /// Verify the proof against the definition a verifier trusts.
+    /// Fails if the proof was made for a different epoch
+    pub fn verify<B: AsRef<[u8]>>(&self, definition: &AccumulatorPublic, nonce: B) -> bool {
+        telemetry::verify("non_revocation", || {
+            self.epoch == definition.epoch
+                && self.proof.verify(&definition.value, &definition.modulus, nonce)
+        })
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Header::new(Tag::NonRevocationProof).to_bytes();
+        output.extend_from_slice(&self.epoch.to_be_bytes());
+        output.append(&mut self.proof.to_bytes()?);
+        Ok(output)
+    }
+
+    /// Write the same bytes as `to_bytes` to `w`
+    #[cfg(feature = "std")]
+    pub fn write_bytes<W: Write>(&self, mut w: W) -> Result<(), AccumulatorError> {
+        Header::new(Tag::NonRevocationProof).write_to(&mut w)?;
+        w.write_all(&self.epoch.to_be_bytes()).map_err(io_error)?;
+        self.proof.write_bytes(w)
+    }
+}
+
+impl TryFrom<&[u8]> for NonRevocationProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::NonRevocationProof)?;
+        if data.len() != 8 + Poke2Proof::SIZE_BYTES {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: 8 + Poke2Proof::SIZE_BYTES,
+                actual: data.len(),
+            }
+            .into());
+        }
+        Ok(Self {
+            epoch: u64::from_be_bytes(*array_ref![data, 0, 8]),
+            proof: Poke2Proof::try_from(&data[8..])?,
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for NonRevocationProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+serdes_impl!(NonRevocationProof);
+hash_ord_impl!(NonRevocationProof; epoch, proof);
+hex_impl!(NonRevocationProof);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::AccumulatorSecretKey;
+
+    #[test]
+    fn revocation() {
+        let key = AccumulatorSecretKey::default();
+        let mut registry = RevocationRegistry::new(&key);
+        assert!(registry.publish(&key).unwrap().is_none());
+        for h in &["alice", "bob", "carol"] {
+            registry.register(h).unwrap();
+        }
+        assert!(registry.register("bob").is_err());
+        assert!(registry.witness(&key, "alice").is_err());
+        registry.publish(&key).unwrap().unwrap();
+        assert!(registry.register("alice").is_err());
+
+        // Alice is issued her credential at epoch 1
+        let mut definition = registry.definition();
+        let mut alice = registry.witness(&key, "alice").unwrap();
+        let bob = registry.witness(&key, "bob").unwrap();
+        let proof = NonRevocationProof::new(&alice, &definition, b"verifier").unwrap();
+        assert!(proof.verify(&definition, b"verifier"));
+
+        // Bob is revoked and dave registered in one epoch
+        registry.revoke("bob").unwrap();
+        assert!(registry.revoke("bob").is_err());
+        assert!(registry.revoke("erin").is_err());
+        registry.register("dave").unwrap();
+        registry.register("erin").unwrap();
+        registry.revoke("erin").unwrap();
+        let delta = registry.publish(&key).unwrap().unwrap().clone();
+        assert_eq!((delta.from_epoch, delta.to_epoch), (1, 2));
+        assert_eq!((delta.additions.len(), delta.deletions.len()), (1, 1));
+        assert!(registry.witness(&key, "bob").is_err());
+
+        let published = registry.definition();
+        assert!(!proof.verify(&published, b"verifier"));
+        assert_eq!(registry.deltas_since(definition.epoch).unwrap(), core::slice::from_ref(&delta));
+        assert_eq!(registry.deltas_since(published.epoch).unwrap().len(), 0);
+        assert_eq!(registry.deltas_since(0).unwrap().len(), 2);
+        assert!(registry.deltas_since(7).is_none());
+
+        // Holders catch up from the published delta alone
+        let delta = RegistryDelta::try_from(delta.to_bytes().unwrap()).unwrap();
+        assert!(delta.update_witness(&bob, &definition).is_err());
+        assert!(delta.apply(&published).is_err());
+        delta.update_witness_assign(&mut alice, &definition).unwrap();
+        definition = delta.apply(&definition).unwrap();
+        assert_eq!(definition, published);
+        assert_eq!(alice, registry.witness(&key, "alice").unwrap());
+
+        let proof = NonRevocationProof::new(&alice, &definition, b"verifier").unwrap();
+        let proof = NonRevocationProof::try_from(proof.to_bytes().unwrap()).unwrap();
+        assert!(proof.verify(&definition, b"verifier"));
+        assert!(!proof.verify(&definition, b"other"));
+
+        // A revoked witness can't prove against the new definition
+        let proof = NonRevocationProof::new(&bob, &definition, b"verifier").unwrap();
+        assert!(!proof.verify(&definition, b"verifier"));
+        assert!(registry.prove_non_revocation(&key, "bob", b"verifier").is_err());
+        let proof = registry.prove_non_revocation(&key, "dave", b"verifier").unwrap();
+        assert!(proof.verify(&definition, b"verifier"));
+    }
+
+    #[test]
+    fn delta_bytes() {
+        let key = AccumulatorSecretKey::default();
+        let mut registry = RevocationRegistry::new(&key);
+        registry.register("alice").unwrap();
+        let bytes = registry.publish(&key).unwrap().unwrap().to_bytes().unwrap();
+        assert!(RegistryDelta::try_from(&bytes[..bytes.len() - 1]).is_err());
+        let mut long = bytes.clone();
+        long.push(0);
+        assert!(RegistryDelta::try_from(long).is_err());
+        let mut huge = bytes;
+        let count = crate::format::HEADER_SIZE + 16 + FACTOR_SIZE * 2;
+        huge[count..count + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(RegistryDelta::try_from(huge).is_err());
+    }
+}
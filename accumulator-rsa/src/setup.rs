@@ -0,0 +1,771 @@
+// Distributed generation of the RSA modulus following
+// Boneh and Franklin, "Efficient Generation of Shared RSA Keys"
+// <https://crypto.stanford.edu/~dabo/pubs/papers/sharedrsa.pdf>
+//
+// Each party i picks additive shares p_i and q_i of the factors.
+// N = (Σ p_i)(Σ q_i) is computed with the BGW protocol over a public prime field
+// so nobody learns anything besides N. The parties then run the distributed
+// biprimality test and retry with fresh shares until N is the product of two primes.
+//
+// For the test party i commits once to a_i = floor((p_i + q_i) / 4) as C_i = h^a_i
+// and broadcasts v_i = g^a_i for each base g, with a proof that v_i and C_i have
+// the same exponent and that it is no larger than the shares can be. Both bases
+// are derived from N. With N a Blum integer
+//
+//   g^((N - 1) / 4) / prod(v_i) = g^((N + 1 - p - q) / 4) = g^(phi(N) / 4) = ±1
+//
+// All broadcast values are recorded in a `SetupTranscript` which anyone can verify.
+// The sharing of N assumes honest-but-curious parties and an honest majority.
+use crate::{
+    accumulator::{check_parameters, Accumulator},
+    hash::hash_to_generator,
+    pow2, random_below, FACTOR_SIZE, MEMBER_PRIMALITY,
+};
+use blake2::{Blake2b, Digest};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+    Field,
+};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use std::{collections::BTreeSet, convert::TryFrom, sync::Arc};
+use zeroize::Zeroize;
+
+/// The number of biprimality tests run before accepting a modulus
+pub const BIPRIMALITY_ROUNDS: usize = 40;
+/// The size of the challenges of the biprimality share proofs
+const CHALLENGE_BITS: usize = 128;
+/// The extra bits of the proof masks that hide the exponent
+const SLACK_BITS: usize = 80;
+/// The smallest factor size for which the shares have room to be random
+const MIN_SHARE_BITS: usize = 16;
+
+/// Public parameters agreed upon before running the setup
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SetupParams {
+    /// The number of parties `n`, at least 3
+    pub parties: usize,
+    /// The BGW threshold `t = (n - 1) / 2`
+    pub threshold: usize,
+    /// The size in bits of each factor
+    pub prime_bits: usize,
+    /// A public prime larger than any possible modulus
+    pub field: BigInteger,
+}
+
+impl SetupParams {
+    /// Create new setup parameters for `parties` parties generating factors of `prime_bits`
+    pub fn new(parties: usize, prime_bits: usize) -> Result<Self, AccumulatorError> {
+        if parties < 3 {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidType,
+                "at least three parties are required",
+            ));
+        }
+        if prime_bits < bits(parties) + MIN_SHARE_BITS {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidType,
+                "the factors are too small for the number of parties",
+            ));
+        }
+        let field = BigInteger::generate_prime(2 * (prime_bits + bits(parties)) + 2);
+        Ok(Self {
+            parties,
+            threshold: (parties - 1) / 2,
+            prime_bits,
+            field,
+        })
+    }
+
+    /// Check the parameters are well formed
+    pub fn check(&self) -> Result<(), AccumulatorError> {
+        if self.parties < 3
+            || self.prime_bits < bits(self.parties) + MIN_SHARE_BITS
+            || self.threshold != (self.parties - 1) / 2
+            || self.field.bits() < 2 * (self.prime_bits + bits(self.parties)) + 1
+            || !self.field.is_prime()
+        {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidType,
+                "invalid setup parameters",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A party taking part in the setup. Holds this party's secret shares of the factors.
+pub struct SetupParty {
+    /// The 1-based index of this party
+    pub index: usize,
+    params: SetupParams,
+    p: BigInteger,
+    q: BigInteger,
+}
+
+impl std::fmt::Debug for SetupParty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SetupParty")
+            .field("index", &self.index)
+            .field("params", &self.params)
+            .field("p", &"<redacted>")
+            .field("q", &"<redacted>")
+            .finish()
+    }
+}
+
+/// The BGW shares sent from one party to another
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SetupShare {
+    /// The index of the sender
+    pub from: usize,
+    /// The index of the recipient
+    pub to: usize,
+    /// The share of the sender's `p_i`
+    pub p: BigInteger,
+    /// The share of the sender's `q_i`
+    pub q: BigInteger,
+    /// The share of a random sharing of zero used to rerandomize the product
+    pub zero: BigInteger,
+}
+
+/// A party's broadcast share of N
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ModulusShare {
+    /// The index of the sender
+    pub index: usize,
+    /// The share of N
+    pub value: BigInteger,
+}
+
+/// A party's broadcast commitment to its biprimality exponent
+/// `a_i = floor((p_i + q_i) / 4)`, made once N is known
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ExponentCommitment {
+    /// The index of the sender
+    pub index: usize,
+    /// `h^{a_i}` for the base `h` derived from N
+    pub value: BigInteger,
+}
+
+/// A party's broadcast value for one biprimality test
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BiprimalityShare {
+    /// The index of the sender
+    pub index: usize,
+    /// `g^{a_i}` for the base `g` of the round
+    pub value: BigInteger,
+    /// The challenge of the proof `value` and the commitment share `a_i`
+    pub challenge: BigInteger,
+    /// The response of the proof, small enough to bound `a_i`
+    pub response: BigInteger,
+}
+
+impl SetupParty {
+    /// Start a new attempt by sampling fresh shares of the factors
+    pub fn new(index: usize, params: &SetupParams) -> Result<Self, AccumulatorError> {
+        Self::new_with_rng(index, params, &mut OsRng)
+    }
+
+    /// Same as `new` but the shares are drawn from `rng`
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        index: usize,
+        params: &SetupParams,
+        rng: &mut R,
+    ) -> Result<Self, AccumulatorError> {
+        params.check()?;
+        if index == 0 || index > params.parties {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidType,
+                "invalid party index",
+            ));
+        }
+        // Party 1 chooses shares congruent to 3 mod 4 and the others 0 mod 4
+        // so N is a Blum integer
+        let residue = BigInteger::from(if index == 1 { 3u32 } else { 0u32 });
+        let bound = pow2(share_bits(params))?;
+        let four = BigInteger::from(4u32);
+        let mut sample = || {
+            let r = random_below(&bound, rng)?;
+            Ok::<_, AccumulatorError>(&(&r - &(&r % &four)) + &residue)
+        };
+        let p = sample()?;
+        let q = sample()?;
+        Ok(Self {
+            index,
+            params: params.clone(),
+            p,
+            q,
+        })
+    }
+
+    /// Shares to send to every party including this one
+    pub fn shares(&self) -> Result<Vec<SetupShare>, AccumulatorError> {
+        self.shares_with_rng(&mut OsRng)
+    }
+
+    /// Same as `shares` but the polynomials are drawn from `rng`
+    pub fn shares_with_rng<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Result<Vec<SetupShare>, AccumulatorError> {
+        let t = self.params.threshold;
+        let field = &self.params.field;
+        let mut random_poly = |constant: &BigInteger, degree: usize| {
+            let mut c = vec![constant.clone()];
+            for _ in 0..degree {
+                c.push(random_below(field, rng)?);
+            }
+            Ok::<_, AccumulatorError>(c)
+        };
+        let mut p_poly = random_poly(&self.p, t)?;
+        let mut q_poly = random_poly(&self.q, t)?;
+        let zero_poly = random_poly(&BigInteger::default(), 2 * t)?;
+        let shares = (1..=self.params.parties)
+            .map(|to| SetupShare {
+                from: self.index,
+                to,
+                p: evaluate(&p_poly, to, field),
+                q: evaluate(&q_poly, to, field),
+                zero: evaluate(&zero_poly, to, field),
+            })
+            .collect();
+        p_poly.iter_mut().for_each(Zeroize::zeroize);
+        q_poly.iter_mut().for_each(Zeroize::zeroize);
+        Ok(shares)
+    }
+
+    /// Compute this party's share of N from the shares received from every party
+    pub fn modulus_share(&self, incoming: &[SetupShare]) -> Result<ModulusShare, AccumulatorError> {
+        let senders: BTreeSet<usize> = incoming
+            .iter()
+            .filter(|s| s.to == self.index)
+            .map(|s| s.from)
+            .collect();
+        if senders.len() != incoming.len() || senders.len() != self.params.parties {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::ParameterMismatch,
+                "expected exactly one share from every party",
+            ));
+        }
+        let field = &self.params.field;
+        let p: BigInteger = incoming.iter().map(|s| &s.p).sum();
+        let q: BigInteger = incoming.iter().map(|s| &s.q).sum();
+        let zero: BigInteger = incoming.iter().map(|s| &s.zero).sum();
+        let value = &(&p.mod_mul(&q, field) + &zero) % field;
+        Ok(ModulusShare {
+            index: self.index,
+            value,
+        })
+    }
+
+    /// Commit to this party's biprimality exponent for `n`
+    pub fn exponent_commitment(&self, n: &BigInteger) -> ExponentCommitment {
+        let mut a = self.exponent();
+        let value = commitment_base(n).mod_exp(&a, n);
+        a.zeroize();
+        ExponentCommitment {
+            index: self.index,
+            value,
+        }
+    }
+
+    /// Compute this party's value for biprimality test `round` against `n`
+    pub fn biprimality_share(&self, n: &BigInteger, round: usize) -> Result<BiprimalityShare, AccumulatorError> {
+        self.biprimality_share_with_rng(n, round, &mut OsRng)
+    }
+
+    /// Same as `biprimality_share` but the proof is randomized with `rng`
+    pub fn biprimality_share_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        n: &BigInteger,
+        round: usize,
+        rng: &mut R,
+    ) -> Result<BiprimalityShare, AccumulatorError> {
+        // Integer Chaum-Pedersen proof with the mask `k` large enough to
+        // hide `c * a` so the response also shows `a` is small
+        let mut k = random_below(&pow2(share_bits(&self.params) + CHALLENGE_BITS + SLACK_BITS)?, rng)?;
+        let f = Field::new(n);
+        let g = biprimality_base(n, round);
+        let h = commitment_base(n);
+        let mut a = self.exponent();
+        let value = f.exp(&g, &a);
+        let commitment = f.exp(&h, &a);
+        let challenge = share_challenge(n, round, self.index, &commitment, &value, &f.exp(&g, &k), &f.exp(&h, &k));
+        let response = &k + &(&challenge * &a);
+        a.zeroize();
+        k.zeroize();
+        Ok(BiprimalityShare {
+            index: self.index,
+            value,
+            challenge,
+            response,
+        })
+    }
+
+    /// `floor((p_i + q_i) / 4)`
+    fn exponent(&self) -> BigInteger {
+        &(&self.p + &self.q) / &BigInteger::from(4u32)
+    }
+}
+
+impl Drop for SetupParty {
+    fn drop(&mut self) {
+        self.p.zeroize();
+        self.q.zeroize();
+    }
+}
+
+/// Interpolate N from the broadcast shares
+pub fn combine_modulus(
+    params: &SetupParams,
+    shares: &[ModulusShare],
+) -> Result<BigInteger, AccumulatorError> {
+    let indices: BTreeSet<usize> = shares.iter().map(|s| s.index).collect();
+    if indices.len() != shares.len()
+        || indices.len() < 2 * params.threshold + 1
+        || indices.iter().any(|&i| i == 0 || i > params.parties)
+    {
+        return Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::InvalidType,
+            "at least 2t + 1 distinct modulus shares are required",
+        ));
+    }
+    let field = &params.field;
+    let f = Field::new(field);
+    let xs: Vec<usize> = indices.into_iter().collect();
+    let (nums, dens): (Vec<BigInteger>, Vec<BigInteger>) = shares
+        .iter()
+        .map(|s| {
+            xs.iter()
+                .filter(|&&j| j != s.index)
+                .fold((BigInteger::from(1u32), BigInteger::from(1u32)), |(num, den), &j| {
+                    let j_b = BigInteger::from(j as u64);
+                    let diff = &(&(&j_b - &BigInteger::from(s.index as u64)) + field) % field;
+                    (f.mul(&num, &j_b), f.mul(&den, &diff))
+                })
+        })
+        .unzip();
+    let den_invs = f.inv_batch(&dens)?;
+    let n = shares
+        .iter()
+        .zip(nums.iter().zip(den_invs.iter()))
+        .fold(BigInteger::default(), |acc, (s, (num, den_inv))| {
+            let lambda = f.mul(num, den_inv);
+            &(&acc + &f.mul(&s.value, &lambda)) % field
+        });
+    Ok(n)
+}
+
+/// Check round `round` of the distributed biprimality test. Every party
+/// must send one share with a valid proof against its commitment
+pub fn check_biprimality(
+    params: &SetupParams,
+    n: &BigInteger,
+    commitments: &[ExponentCommitment],
+    round: usize,
+    shares: &[BiprimalityShare],
+) -> bool {
+    let four = BigInteger::from(4u32);
+    if n % &four != BigInteger::from(1u32) || shares.len() != params.parties {
+        return false;
+    }
+    let f = Field::new(n);
+    let g = biprimality_base(n, round);
+    let h = commitment_base(n);
+    let bound = match pow2(share_bits(params) + CHALLENGE_BITS + SLACK_BITS + 1) {
+        Ok(b) => b,
+        Err(_) => return false,
+    };
+    let mut indices = BTreeSet::new();
+    let mut product = BigInteger::from(1u32);
+    for s in shares {
+        let commitment = match commitments.iter().find(|c| c.index == s.index) {
+            Some(c) => &c.value,
+            None => return false,
+        };
+        if !indices.insert(s.index)
+            || trivial(&s.value, n)
+            || trivial(commitment, n)
+            || s.response < BigInteger::default()
+            || s.response >= bound
+        {
+            return false;
+        }
+        // A value that isn't invertible is a factor of N
+        let (v_inv, c_inv) = match (f.inv(&s.value), f.inv(commitment)) {
+            (Ok(v), Ok(c)) => (v, c),
+            _ => return false,
+        };
+        let t_g = f.mul(&f.exp(&g, &s.response), &f.exp(&v_inv, &s.challenge));
+        let t_h = f.mul(&f.exp(&h, &s.response), &f.exp(&c_inv, &s.challenge));
+        if share_challenge(n, round, s.index, commitment, &s.value, &t_g, &t_h) != s.challenge {
+            return false;
+        }
+        product = f.mul(&product, &s.value);
+    }
+    let left = f.exp(&g, &(&(n - &BigInteger::from(1u32)) / &four));
+    left == product || left == n - &product
+}
+
+/// The public record of a successful setup
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SetupTranscript {
+    /// The public parameters
+    pub params: SetupParams,
+    /// The broadcast shares of N
+    pub modulus_shares: Vec<ModulusShare>,
+    /// The commitments to the biprimality exponents
+    pub commitments: Vec<ExponentCommitment>,
+    /// The broadcast values for each biprimality round
+    pub biprimality: Vec<Vec<BiprimalityShare>>,
+}
+
+impl SetupTranscript {
+    /// Verify the transcript and return the modulus
+    pub fn verify(&self) -> Result<BigInteger, AccumulatorError> {
+        self.params.check()?;
+        let n = combine_modulus(&self.params, &self.modulus_shares)?;
+        if self.biprimality.len() < BIPRIMALITY_ROUNDS
+            || !self
+                .biprimality
+                .iter()
+                .enumerate()
+                .all(|(round, shares)| check_biprimality(&self.params, &n, &self.commitments, round, shares))
+        {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::ProofVerificationFailed,
+                "the transcript does not prove the modulus is a biprime",
+            ));
+        }
+        Ok(n)
+    }
+
+    /// Create an empty accumulator from this transcript. The generator
+    /// is derived from the transcript so nobody could have chosen it.
+    /// The factors must be `FACTOR_SIZE` bytes so the modulus is the size
+    /// accumulators are serialized and checked with
+    pub fn accumulator(&self) -> Result<Accumulator, AccumulatorError> {
+        if self.params.prime_bits != FACTOR_SIZE * 8 {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::ParameterMismatch,
+                "the factors are not the size accumulators use",
+            ));
+        }
+        let modulus = self.verify()?;
+        let generator = hash_to_generator(self.to_bytes(), &modulus)?;
+        let value = generator.clone();
+        check_parameters(&generator, &value, &modulus)?;
+        Ok(Accumulator {
+            generator: Arc::new(generator),
+            members: BTreeSet::new(),
+            modulus: Arc::new(modulus),
+            value,
+            epoch: 0,
+            fixed_bases: Vec::new(),
+            product: None,
+            primality: MEMBER_PRIMALITY,
+        })
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&(self.params.parties as u32).to_be_bytes());
+        out.extend_from_slice(&(self.params.prime_bits as u32).to_be_bytes());
+        write_int(&mut out, &self.params.field);
+        out.extend_from_slice(&(self.modulus_shares.len() as u32).to_be_bytes());
+        for s in &self.modulus_shares {
+            out.extend_from_slice(&(s.index as u32).to_be_bytes());
+            write_int(&mut out, &s.value);
+        }
+        out.extend_from_slice(&(self.commitments.len() as u32).to_be_bytes());
+        for c in &self.commitments {
+            out.extend_from_slice(&(c.index as u32).to_be_bytes());
+            write_int(&mut out, &c.value);
+        }
+        out.extend_from_slice(&(self.biprimality.len() as u32).to_be_bytes());
+        for round in &self.biprimality {
+            out.extend_from_slice(&(round.len() as u32).to_be_bytes());
+            for s in round {
+                out.extend_from_slice(&(s.index as u32).to_be_bytes());
+                write_int(&mut out, &s.value);
+                write_int(&mut out, &s.challenge);
+                write_int(&mut out, &s.response);
+            }
+        }
+        out
+    }
+}
+
+impl TryFrom<&[u8]> for SetupTranscript {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let mut reader = Reader { data, offset: 0 };
+        let parties = reader.u32()? as usize;
+        let prime_bits = reader.u32()? as usize;
+        let field = reader.int()?;
+        let params = SetupParams {
+            parties,
+            threshold: parties.saturating_sub(1) / 2,
+            prime_bits,
+            field,
+        };
+        let count = reader.u32()? as usize;
+        let mut modulus_shares = Vec::new();
+        for _ in 0..count {
+            let index = reader.u32()? as usize;
+            let value = reader.int()?;
+            modulus_shares.push(ModulusShare { index, value });
+        }
+        let count = reader.u32()? as usize;
+        let mut commitments = Vec::new();
+        for _ in 0..count {
+            let index = reader.u32()? as usize;
+            let value = reader.int()?;
+            commitments.push(ExponentCommitment { index, value });
+        }
+        let rounds = reader.u32()? as usize;
+        let mut biprimality = Vec::new();
+        for _ in 0..rounds {
+            let count = reader.u32()? as usize;
+            let mut round = Vec::new();
+            for _ in 0..count {
+                let index = reader.u32()? as usize;
+                let value = reader.int()?;
+                let challenge = reader.int()?;
+                let response = reader.int()?;
+                round.push(BiprimalityShare {
+                    index,
+                    value,
+                    challenge,
+                    response,
+                });
+            }
+            biprimality.push(round);
+        }
+        if reader.offset != data.len() {
+            return Err(AccumulatorErrorKind::SerializationError.into());
+        }
+        Ok(Self {
+            params,
+            modulus_shares,
+            commitments,
+            biprimality,
+        })
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], AccumulatorError> {
+        if self.data.len() - self.offset < len {
+            return Err(AccumulatorErrorKind::SerializationError.into());
+        }
+        let d = &self.data[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(d)
+    }
+
+    fn u32(&mut self) -> Result<u32, AccumulatorError> {
+        Ok(u32::from_be_bytes(*array_ref![self.take(4)?, 0, 4]))
+    }
+
+    fn int(&mut self) -> Result<BigInteger, AccumulatorError> {
+        let len = self.u32()? as usize;
+        BigInteger::try_from(self.take(len)?)
+    }
+}
+
+fn write_int(out: &mut Vec<u8>, b: &BigInteger) {
+    let bytes = b.to_bytes();
+    out.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    out.extend_from_slice(&bytes);
+}
+
+/// Derive the base for biprimality test `round` from N with Jacobi symbol 1
+fn biprimality_base(n: &BigInteger, round: usize) -> BigInteger {
+    derive_base(n, &(round as u32).to_be_bytes())
+}
+
+/// Derive the base of the exponent commitments from N
+fn commitment_base(n: &BigInteger) -> BigInteger {
+    derive_base(n, b"exponent commitment")
+}
+
+/// Hash N and `label` to an element with Jacobi symbol 1
+fn derive_base(n: &BigInteger, label: &[u8]) -> BigInteger {
+    let f = Field::new(n);
+    let mut counter = 0u32;
+    loop {
+        let mut data = n.to_bytes();
+        data.extend_from_slice(label);
+        data.extend_from_slice(&counter.to_be_bytes());
+        let g = &BigInteger::try_from(Blake2b::digest(&data).as_slice()).unwrap() % n;
+        if f.jacobi(&g) == 1 {
+            return g;
+        }
+        counter += 1;
+    }
+}
+
+/// The Fiat-Shamir challenge of a biprimality share proof
+fn share_challenge(
+    n: &BigInteger,
+    round: usize,
+    index: usize,
+    commitment: &BigInteger,
+    value: &BigInteger,
+    t_g: &BigInteger,
+    t_h: &BigInteger,
+) -> BigInteger {
+    let mut h = Blake2b::new();
+    h.input((round as u64).to_be_bytes());
+    h.input((index as u64).to_be_bytes());
+    for b in &[n, commitment, value, t_g, t_h] {
+        let bytes = b.to_bytes();
+        h.input((bytes.len() as u64).to_be_bytes());
+        h.input(bytes);
+    }
+    BigInteger::try_from(&h.result()[..CHALLENGE_BITS / 8]).unwrap()
+}
+
+/// Zero, one or -1 mod `n`, values that pass any product check
+fn trivial(v: &BigInteger, n: &BigInteger) -> bool {
+    let one = BigInteger::from(1u32);
+    *v <= one || *v >= n - &one
+}
+
+/// The bits of each additive share of a factor
+fn share_bits(params: &SetupParams) -> usize {
+    params.prime_bits - bits(params.parties)
+}
+
+/// Evaluate the polynomial with `coefficients` at `x` mod `field`
+fn evaluate(coefficients: &[BigInteger], x: usize, field: &BigInteger) -> BigInteger {
+    let x = BigInteger::from(x as u64);
+    coefficients
+        .iter()
+        .rev()
+        .fold(BigInteger::default(), |acc, c| &(&acc.mod_mul(&x, field) + c) % field)
+}
+
+/// The number of bits needed to represent `n`
+fn bits(n: usize) -> usize {
+    (0usize.leading_zeros() - n.leading_zeros()) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(params: &SetupParams) -> SetupTranscript {
+        loop {
+            let parties: Vec<SetupParty> = (1..=params.parties)
+                .map(|i| SetupParty::new(i, params).unwrap())
+                .collect();
+            let shares: Vec<SetupShare> = parties.iter().flat_map(|p| p.shares().unwrap()).collect();
+            let modulus_shares: Vec<ModulusShare> = parties
+                .iter()
+                .map(|p| {
+                    let incoming: Vec<SetupShare> =
+                        shares.iter().filter(|s| s.to == p.index).cloned().collect();
+                    p.modulus_share(&incoming).unwrap()
+                })
+                .collect();
+            let n = combine_modulus(params, &modulus_shares).unwrap();
+            let commitments: Vec<ExponentCommitment> =
+                parties.iter().map(|p| p.exponent_commitment(&n)).collect();
+            let mut biprimality = Vec::new();
+            for round in 0..BIPRIMALITY_ROUNDS {
+                let round_shares: Vec<BiprimalityShare> = parties
+                    .iter()
+                    .map(|p| p.biprimality_share(&n, round).unwrap())
+                    .collect();
+                if !check_biprimality(params, &n, &commitments, round, &round_shares) {
+                    break;
+                }
+                biprimality.push(round_shares);
+            }
+            if biprimality.len() == BIPRIMALITY_ROUNDS {
+                return SetupTranscript {
+                    params: params.clone(),
+                    modulus_shares,
+                    commitments,
+                    biprimality,
+                };
+            }
+        }
+    }
+
+    #[test]
+    fn distributed_setup() {
+        let params = SetupParams::new(3, 48).unwrap();
+        let transcript = run(&params);
+        let n = transcript.verify().unwrap();
+        assert!(!n.is_prime());
+
+        let bytes = transcript.to_bytes();
+        let transcript2 = SetupTranscript::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(transcript, transcript2);
+
+        // Too small to be read back as an accumulator
+        assert!(transcript.accumulator().is_err());
+    }
+
+    #[test]
+    fn tampered_transcript() {
+        let params = SetupParams::new(3, 48).unwrap();
+        let mut transcript = run(&params);
+        transcript.modulus_shares[0].value += BigInteger::from(1u32);
+        assert!(transcript.verify().is_err());
+    }
+
+    #[test]
+    fn forged_biprimality() {
+        let params = SetupParams::new(3, 48).unwrap();
+        let transcript = run(&params);
+        let n = transcript.verify().unwrap();
+
+        // Shares of one multiply to one
+        let mut forged = transcript.clone();
+        for c in forged.commitments.iter_mut() {
+            c.value = BigInteger::from(1u32);
+        }
+        for round in forged.biprimality.iter_mut() {
+            for s in round.iter_mut() {
+                s.value = BigInteger::from(1u32);
+            }
+        }
+        assert!(forged.verify().is_err());
+
+        // Shares of another round don't match the base
+        let mut forged = transcript.clone();
+        forged.biprimality.swap(0, 1);
+        assert!(forged.verify().is_err());
+
+        // Shares with the same product but not the committed exponents
+        let mut forged = transcript.clone();
+        let f = Field::new(&n);
+        let gx = f.exp(&biprimality_base(&n, 0), &BigInteger::from(12345u32));
+        let round = &mut forged.biprimality[0];
+        round[0].value = f.mul(&f.mul(&round[0].value, &round[1].value), &f.inv(&gx).unwrap());
+        round[1].value = gx;
+        assert!(forged.verify().is_err());
+
+        // A share that exceeds the size of the response
+        let mut forged = transcript;
+        forged.biprimality[0][2].response += pow2(share_bits(&params) + CHALLENGE_BITS + SLACK_BITS + 1).unwrap();
+        assert!(forged.verify().is_err());
+    }
+
+    #[test]
+    fn small_factors() {
+        assert!(SetupParams::new(3, 8).is_err());
+        let mut params = SetupParams::new(3, 48).unwrap();
+        params.prime_bits = 4;
+        assert!(SetupParty::new(1, &params).is_err());
+    }
+}
@@ -0,0 +1,399 @@
+use crate::{
+    accumulator::{Accumulator, AccumulatorPublic, PublicState},
+    format::{io_error, Header, Tag},
+    hash::hash_to_prime,
+    key::SecretKeyOps,
+    memproof::MembershipProof,
+    memwitness::MembershipWitness,
+    store::MemberStore,
+    Poke2Proof,
+};
+use blake2::{Blake2b, Digest};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+};
+use crate::par::*;
+use core::convert::TryFrom;
+use std::{collections::BTreeSet, io::Write};
+
+/// Members split across independent accumulators.
+///
+/// Each member belongs to the shard picked by a hash of its prime, so
+/// the same member always lands in the same shard for a given number of
+/// shards. Every shard has its own generator, value and epoch and batch
+/// updates change the shards in parallel, so no single accumulator holds
+/// every member. Proofs name their shard which tells a verifier a few
+/// bits of the hash of the member
+#[derive(Debug, Clone)]
+pub struct ShardedAccumulator<S = BTreeSet<BigInteger>> {
+    shards: Vec<Accumulator<S>>,
+}
+
+impl ShardedAccumulator {
+    /// `shards` empty accumulators for `key`
+    pub fn new<K: SecretKeyOps>(key: &K, shards: usize) -> Result<Self, AccumulatorError> {
+        check_count(shards)?;
+        Ok(Self {
+            shards: (0..shards).map(|_| Accumulator::new(key)).collect(),
+        })
+    }
+
+    /// `shards` accumulators for `key` prefilled with `members`
+    pub fn with_members<K: SecretKeyOps + Sync, M: AsRef<[B]>, B: AsRef<[u8]> + Sync>(
+        key: &K,
+        shards: usize,
+        members: M,
+    ) -> Result<Self, AccumulatorError> {
+        check_count(shards)?;
+        let members: Vec<BigInteger> = members
+            .as_ref()
+            .par_iter()
+            .map(|b| hash_to_prime(b.as_ref()))
+            .collect();
+        let mut split = vec![Vec::new(); shards];
+        for m in members {
+            split[shard_index(&m, shards)].push(m);
+        }
+        let shards = split
+            .par_iter()
+            .map(|m| Accumulator::with_prime_members(key, m))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { shards })
+    }
+}
+
+impl<S: MemberStore> ShardedAccumulator<S> {
+    /// Use existing accumulators as the shards, in order. Members must
+    /// already be in the shard `shard_of` picks for them
+    pub fn from_shards(shards: Vec<Accumulator<S>>) -> Result<Self, AccumulatorError> {
+        check_count(shards.len())?;
+        Ok(Self { shards })
+    }
+
+    /// The shards in order
+    pub fn shards(&self) -> &[Accumulator<S>] {
+        self.shards.as_slice()
+    }
+
+    /// The shard at `index`
+    pub fn shard(&self, index: usize) -> Option<&Accumulator<S>> {
+        self.shards.get(index)
+    }
+
+    /// The public state of every shard to publish for verifiers
+    pub fn public(&self) -> Vec<AccumulatorPublic> {
+        self.shards.iter().map(Accumulator::public).collect()
+    }
+
+    /// The number of shards
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// The number of members in every shard
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(Accumulator::len).sum()
+    }
+
+    /// True if no shard has members
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(Accumulator::is_empty)
+    }
+
+    /// The shard `value` belongs to
+    pub fn shard_of<B: AsRef<[u8]>>(&self, value: B) -> usize {
+        self.shard_of_prime(&self.shards[0].hash_member(value))
+    }
+
+    /// The shard the prime `value` belongs to
+    pub fn shard_of_prime(&self, value: &BigInteger) -> usize {
+        shard_index(value, self.shards.len())
+    }
+
+    /// True if `value` is a member of its shard
+    pub fn contains<B: AsRef<[u8]>>(&self, value: B) -> Result<bool, AccumulatorError> {
+        let x = self.shards[0].hash_member(value);
+        self.shards[self.shard_of_prime(&x)].contains_prime(&x)
+    }
+
+    /// Add `value` to its shard
+    pub fn insert_assign<B: AsRef<[u8]>>(&mut self, value: B) -> Result<(), AccumulatorError> {
+        let index = self.shard_of(value.as_ref());
+        self.shards[index].insert_assign(value)
+    }
+
+    /// Add the prime `value` to its shard
+    pub fn insert_prime_assign(&mut self, value: &BigInteger) -> Result<(), AccumulatorError> {
+        let index = self.shard_of_prime(value);
+        self.shards[index].insert_prime_assign(value)
+    }
+
+    /// Remove `value` from its shard
+    pub fn remove_assign<K: SecretKeyOps, B: AsRef<[u8]>>(
+        &mut self,
+        key: &K,
+        value: B,
+    ) -> Result<(), AccumulatorError> {
+        self.remove_prime_assign(key, &self.shards[0].hash_member(value))
+    }
+
+    /// Remove the prime `value` from its shard
+    pub fn remove_prime_assign<K: SecretKeyOps>(
+        &mut self,
+        key: &K,
+        value: &BigInteger,
+    ) -> Result<(), AccumulatorError> {
+        let index = self.shard_of_prime(value);
+        self.shards[index].remove_prime_assign(key, value)
+    }
+
+    /// Add and remove members of any shard. Each shard with changes gets
+    /// one `Accumulator::update_batch_assign` and its epoch advances, the
+    /// other shards are unchanged. Every change is checked before any
+    /// shard is updated so a bad value leaves them all as they were
+    pub fn update_batch_assign<K: SecretKeyOps + Sync>(
+        &mut self,
+        key: &K,
+        additions: &[BigInteger],
+        deletions: &[BigInteger],
+    ) -> Result<(), AccumulatorError>
+    where
+        S: Send,
+    {
+        let count = self.shards.len();
+        let mut split = vec![(Vec::new(), Vec::new()); count];
+        for a in additions {
+            split[shard_index(a, count)].0.push(a.clone());
+        }
+        for d in deletions {
+            split[shard_index(d, count)].1.push(d.clone());
+        }
+        if additions.par_iter().any(|b| !b.is_prime()) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "Some additions are not prime",
+            ));
+        }
+        let mut seen = BTreeSet::new();
+        for (shard, (additions, deletions)) in self.shards.iter().zip(&split) {
+            for b in additions {
+                if !seen.insert(b) || shard.members.contains(b)? {
+                    return Err(AccumulatorErrorKind::DuplicateValueSupplied.into());
+                }
+            }
+            for b in deletions {
+                if !seen.insert(b) || !shard.members.contains(b)? {
+                    return Err(AccumulatorError::from_msg(
+                        AccumulatorErrorKind::InvalidMemberSupplied,
+                        "Some deletions are not members or are repeated",
+                    ));
+                }
+            }
+        }
+        self.shards
+            .par_iter_mut()
+            .zip(split.par_iter())
+            .filter(|(_, (additions, deletions))| !additions.is_empty() || !deletions.is_empty())
+            .map(|(shard, (additions, deletions))| {
+                shard.update_batch_assign(key, additions, deletions)
+            })
+            .collect()
+    }
+
+    /// The witness for `value` in its shard
+    pub fn witness<K: SecretKeyOps, B: AsRef<[u8]>>(
+        &self,
+        key: &K,
+        value: B,
+    ) -> Result<MembershipWitness, AccumulatorError> {
+        let x = self.shards[0].hash_member(value);
+        let shard = &self.shards[self.shard_of_prime(&x)];
+        if !shard.members.contains(&x)? {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "value is not in the accumulator",
+            ));
+        }
+        MembershipWitness::with_prime_and_secret_key(shard, key, &x)
+    }
+}
+
+fn check_count(shards: usize) -> Result<(), AccumulatorError> {
+    if shards == 0 || shards > u32::MAX as usize {
+        return Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::ParameterMismatch,
+            "the number of shards must be between 1 and 2^32 - 1",
+        ));
+    }
+    Ok(())
+}
+
+/// The first 8 bytes of Blake2b over the member reduced by the shard count
+fn shard_index(x: &BigInteger, shards: usize) -> usize {
+    let mut data = b"accumulator shard".to_vec();
+    data.append(&mut x.to_bytes());
+    let digest = Blake2b::digest(data.as_slice());
+    (u64::from_be_bytes(*array_ref![digest, 0, 8]) % shards as u64) as usize
+}
+
+/// A membership proof for one shard of a `ShardedAccumulator`. The shard
+/// index is bound into the proof so it can't be moved to another shard
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct ShardedMembershipProof {
+    shard: u32,
+    proof: MembershipProof,
+}
+
+impl ShardedMembershipProof {
+    /// The size of this proof serialized without the header
+    pub const SIZE_BYTES: usize = 4 + Poke2Proof::SIZE_BYTES;
+
+    /// Create a proof for `witness` against the shard its member belongs to
+    pub fn new<S: MemberStore, B: AsRef<[u8]>>(
+        witness: &MembershipWitness,
+        accumulator: &ShardedAccumulator<S>,
+        nonce: B,
+    ) -> Result<Self, AccumulatorError> {
+        let shard = accumulator.shard_of_prime(&witness.x);
+        let proof = MembershipProof::new_with_context(
+            witness,
+            &accumulator.shards[shard],
+            &[],
+            &(shard as u32).to_be_bytes(),
+            nonce,
+        )?;
+        Ok(Self {
+            shard: shard as u32,
+            proof,
+        })
+    }
+
+    /// The shard the proof is for
+    pub fn shard(&self) -> usize {
+        self.shard as usize
+    }
+
+    /// Verify the proof against its shard. `shards` are the shard states
+    /// in order, like `ShardedAccumulator::shards` or the published `public`
+    pub fn verify<P: PublicState, B: AsRef<[u8]>>(&self, shards: &[P], nonce: B) -> bool {
+        match shards.get(self.shard as usize) {
+            Some(shard) => {
+                self.proof
+                    .verify_with_context(shard, &[], &self.shard.to_be_bytes(), nonce)
+            }
+            None => false,
+        }
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Header::new(Tag::ShardedMembershipProof).to_bytes();
+        output.extend_from_slice(&self.shard.to_be_bytes());
+        output.append(&mut self.proof.0.to_bytes()?);
+        Ok(output)
+    }
+
+    /// Write the same bytes as `to_bytes` to `w`
+    pub fn write_bytes<W: Write>(&self, mut w: W) -> Result<(), AccumulatorError> {
+        Header::new(Tag::ShardedMembershipProof).write_to(&mut w)?;
+        w.write_all(&self.shard.to_be_bytes()).map_err(io_error)?;
+        self.proof.0.write_bytes(w)
+    }
+}
+
+impl TryFrom<&[u8]> for ShardedMembershipProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::ShardedMembershipProof)?;
+        if data.len() != Self::SIZE_BYTES {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: Self::SIZE_BYTES,
+                actual: data.len(),
+            }
+            .into());
+        }
+        Ok(Self {
+            shard: u32::from_be_bytes(*array_ref![data, 0, 4]),
+            proof: MembershipProof(Poke2Proof::try_from(&data[4..])?),
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for ShardedMembershipProof {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+serdes_impl!(ShardedMembershipProof);
+hash_ord_impl!(ShardedMembershipProof; shard, proof);
+hex_impl!(ShardedMembershipProof);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::AccumulatorSecretKey;
+
+    #[test]
+    fn routes_to_shards() {
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = (0..16u64).map(|m| m.to_be_bytes()).collect();
+        let mut sharded = ShardedAccumulator::with_members(&key, 4, &members).unwrap();
+        assert_eq!(sharded.shard_count(), 4);
+        assert_eq!(sharded.len(), members.len());
+        for m in &members {
+            let shard = sharded.shard(sharded.shard_of(m)).unwrap();
+            assert!(shard.contains(m).unwrap());
+        }
+        assert!(ShardedAccumulator::new(&key, 0).is_err());
+
+        let witness = sharded.witness(&key, members[3]).unwrap();
+        let proof = ShardedMembershipProof::new(&witness, &sharded, b"shard").unwrap();
+        assert_eq!(proof.shard(), sharded.shard_of(members[3]));
+        let verifier = sharded.public();
+        assert!(proof.verify(&verifier, b"shard"));
+        assert!(!proof.verify(&verifier, b"other"));
+        assert!(proof.verify(sharded.shards(), b"shard"));
+        let parsed = ShardedMembershipProof::try_from(proof.to_bytes().unwrap()).unwrap();
+        assert_eq!(parsed, proof);
+
+        // The proof is bound to its shard index
+        let mut moved = proof.clone();
+        moved.shard = (moved.shard + 1) % 4;
+        let mut publics = sharded.public();
+        publics[moved.shard()] = publics[proof.shard()].clone();
+        assert!(!moved.verify(&publics, b"shard"));
+
+        // Only the shards with changes advance
+        let epochs: Vec<u64> = sharded.shards().iter().map(|s| s.epoch).collect();
+        let additions = [hash_to_prime("new")];
+        let deletions = [hash_to_prime(members[3])];
+        sharded
+            .update_batch_assign(&key, &additions, &deletions)
+            .unwrap();
+        for (i, shard) in sharded.shards().iter().enumerate() {
+            let changed = i == sharded.shard_of_prime(&additions[0])
+                || i == sharded.shard_of_prime(&deletions[0]);
+            assert_eq!(shard.epoch, epochs[i] + changed as u64);
+        }
+        assert!(sharded.contains("new").unwrap());
+        assert!(!sharded.contains(members[3]).unwrap());
+        assert!(!proof.verify(sharded.shards(), b"shard"));
+
+        // A bad deletion leaves every shard unchanged
+        let before = sharded.public();
+        assert!(sharded
+            .update_batch_assign(&key, &[hash_to_prime("newer")], &deletions)
+            .is_err());
+        assert_eq!(sharded.public(), before);
+
+        sharded.remove_assign(&key, "new").unwrap();
+        sharded.insert_assign("newer").unwrap();
+        assert!(sharded.contains("newer").unwrap());
+        assert!(sharded.witness(&key, "new").is_err());
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{
+    accumulator::{Accumulator, AccumulatorPublic},
+    key::SecretKeyOps,
+    store::MemberStore,
+};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+};
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock},
+};
+
+/// An accumulator shared between threads.
+///
+/// Mutations are serialized behind a lock while readers take cheap snapshots
+/// of the public state that stay consistent for as long as they are held, so
+/// verifying proofs never waits for an update. Insertions and removals can be
+/// queued by any thread and applied together by `flush` as a single epoch,
+/// servers call it on their own schedule.
+///
+/// Locks are always taken in the order queue then accumulator. If a thread
+/// panics while holding one the state it guards may be half updated, so every
+/// later call fails with `Poisoned` instead of using it
+#[derive(Debug)]
+pub struct SharedAccumulator<S = BTreeSet<BigInteger>> {
+    accumulator: RwLock<Accumulator<S>>,
+    snapshot: RwLock<Arc<AccumulatorPublic>>,
+    pending: Mutex<Pending>,
+}
+
+#[derive(Debug, Default)]
+struct Pending {
+    additions: BTreeSet<BigInteger>,
+    deletions: BTreeSet<BigInteger>,
+}
+
+impl<S: MemberStore> SharedAccumulator<S> {
+    /// Share `accumulator`
+    pub fn new(accumulator: Accumulator<S>) -> Self {
+        let snapshot = RwLock::new(Arc::new(accumulator.public()));
+        Self {
+            accumulator: RwLock::new(accumulator),
+            snapshot,
+            pending: Mutex::new(Pending::default()),
+        }
+    }
+
+    /// The public state after the last mutation, for verifying proofs
+    pub fn snapshot(&self) -> Result<Arc<AccumulatorPublic>, AccumulatorError> {
+        Ok(self.snapshot.read().map_err(poisoned)?.clone())
+    }
+
+    /// Read the full accumulator, like to create witnesses or proofs.
+    /// Mutations wait until `f` returns
+    pub fn read<R, F: FnOnce(&Accumulator<S>) -> R>(&self, f: F) -> Result<R, AccumulatorError> {
+        let accumulator = self.accumulator.read().map_err(poisoned)?;
+        Ok(f(&accumulator))
+    }
+
+    /// Change the accumulator right away instead of queuing the change.
+    /// Readers keep the previous snapshot until `f` returns
+    pub fn update<R, F: FnOnce(&mut Accumulator<S>) -> R>(&self, f: F) -> Result<R, AccumulatorError> {
+        let mut accumulator = self.accumulator.write().map_err(poisoned)?;
+        let r = f(&mut accumulator);
+        self.publish(&accumulator)?;
+        Ok(r)
+    }
+
+    /// Queue `value` to be added by the next `flush`
+    pub fn queue_insert<B: AsRef<[u8]>>(&self, value: B) -> Result<(), AccumulatorError> {
+        let x = self.read(|acc| acc.hash_member(value))?;
+        let mut pending = self.pending()?;
+        if pending.deletions.remove(&x) {
+            return Ok(());
+        }
+        if pending.additions.contains(&x) || self.read(|acc| acc.members.contains(&x))?? {
+            return Err(AccumulatorErrorKind::DuplicateValueSupplied.into());
+        }
+        pending.additions.insert(x);
+        Ok(())
+    }
+
+    /// Queue `value` to be removed by the next `flush`.
+    /// Removing a value that is still queued for insertion cancels it
+    pub fn queue_remove<B: AsRef<[u8]>>(&self, value: B) -> Result<(), AccumulatorError> {
+        let x = self.read(|acc| acc.hash_member(value))?;
+        let mut pending = self.pending()?;
+        if pending.additions.remove(&x) {
+            return Ok(());
+        }
+        if pending.deletions.contains(&x) || !self.read(|acc| acc.members.contains(&x))?? {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "value is not a member or is already queued for removal",
+            ));
+        }
+        pending.deletions.insert(x);
+        Ok(())
+    }
+
+    /// The number of queued insertions and removals
+    pub fn pending_len(&self) -> Result<usize, AccumulatorError> {
+        let pending = self.pending()?;
+        Ok(pending.additions.len() + pending.deletions.len())
+    }
+
+    /// Apply every queued change as one epoch. Returns the new epoch
+    /// or `None` if nothing was queued. The queue is kept if the update fails
+    pub fn flush<K: SecretKeyOps>(&self, key: &K) -> Result<Option<u64>, AccumulatorError> {
+        let mut pending = self.pending()?;
+        if pending.additions.is_empty() && pending.deletions.is_empty() {
+            return Ok(None);
+        }
+        let additions: Vec<BigInteger> = pending.additions.iter().cloned().collect();
+        let deletions: Vec<BigInteger> = pending.deletions.iter().cloned().collect();
+        let epoch = self.update(|acc| {
+            acc.update_batch_assign(key, &additions, &deletions)?;
+            Ok::<_, AccumulatorError>(acc.epoch)
+        })??;
+        *pending = Pending::default();
+        Ok(Some(epoch))
+    }
+
+    /// Stop sharing and return the accumulator. Queued changes are dropped
+    pub fn into_inner(self) -> Result<Accumulator<S>, AccumulatorError> {
+        self.accumulator.into_inner().map_err(poisoned)
+    }
+
+    fn pending(&self) -> Result<MutexGuard<'_, Pending>, AccumulatorError> {
+        self.pending.lock().map_err(poisoned)
+    }
+
+    fn publish(&self, accumulator: &Accumulator<S>) -> Result<(), AccumulatorError> {
+        let snapshot = Arc::new(accumulator.public());
+        *self.snapshot.write().map_err(poisoned)? = snapshot;
+        Ok(())
+    }
+}
+
+fn poisoned<T>(_: PoisonError<T>) -> AccumulatorError {
+    AccumulatorErrorKind::Poisoned.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hash::hash_to_prime, key::AccumulatorSecretKey, memproof::MembershipProof, memwitness::MembershipWitness};
+    use std::thread;
+
+    #[test]
+    fn queued_updates() {
+        let key = AccumulatorSecretKey::default();
+        let shared = Arc::new(SharedAccumulator::new(Accumulator::with_members(&key, ["a", "b"]).unwrap()));
+        let before = shared.snapshot().unwrap();
+
+        let handles: Vec<_> = (0..4u64)
+            .map(|i| {
+                let shared = shared.clone();
+                thread::spawn(move || shared.queue_insert(i.to_be_bytes()).unwrap())
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        shared.queue_remove("a").unwrap();
+        assert!(shared.queue_insert(1u64.to_be_bytes()).is_err());
+        assert!(shared.queue_remove("c").is_err());
+        assert_eq!(shared.pending_len().unwrap(), 5);
+        assert_eq!(shared.snapshot().unwrap().value, before.value);
+
+        assert_eq!(shared.flush(&key).unwrap(), Some(1));
+        assert_eq!(shared.flush(&key).unwrap(), None);
+        assert_eq!(shared.pending_len().unwrap(), 0);
+
+        let mut expected = Accumulator::with_members(&key, ["a", "b"]).unwrap();
+        let additions: Vec<BigInteger> = (0..4u64).map(|i| hash_to_prime(i.to_be_bytes())).collect();
+        expected.update_batch_assign(&key, &additions, &[hash_to_prime("a")]).unwrap();
+        let snapshot = shared.snapshot().unwrap();
+        assert_eq!(snapshot.value, expected.value);
+        assert_eq!(snapshot.epoch, 1);
+        // Earlier snapshots are unchanged
+        assert_eq!(before.epoch, 0);
+
+        let witness = shared.read(|acc| MembershipWitness::new(acc, "b")).unwrap().unwrap();
+        let proof = shared.read(|acc| MembershipProof::new(&witness, acc, b"shared")).unwrap().unwrap();
+        assert!(proof.verify(snapshot.as_ref(), b"shared"));
+
+        shared.update(|acc| acc.insert_assign("c")).unwrap().unwrap();
+        assert!(!proof.verify(shared.snapshot().unwrap().as_ref(), b"shared"));
+        let shared = Arc::try_unwrap(shared).unwrap();
+        assert_eq!(shared.into_inner().unwrap().epoch, 2);
+    }
+
+    #[test]
+    fn poisoned() {
+        let key = AccumulatorSecretKey::default();
+        let shared = Arc::new(SharedAccumulator::new(Accumulator::with_members(&key, ["a", "b"]).unwrap()));
+        shared.queue_insert("c").unwrap();
+        let other = shared.clone();
+        let r = thread::spawn(move || {
+            other.update(|acc| {
+                acc.insert_assign("d").unwrap();
+                panic!("interrupted mid update");
+            })
+        })
+        .join();
+        assert!(r.is_err());
+
+        let kind = |e: AccumulatorError| e.kind();
+        assert_eq!(shared.read(|acc| acc.epoch).map_err(kind), Err(AccumulatorErrorKind::Poisoned));
+        assert_eq!(shared.flush(&key).map_err(kind), Err(AccumulatorErrorKind::Poisoned));
+        assert_eq!(shared.queue_insert("e").map_err(kind), Err(AccumulatorErrorKind::Poisoned));
+        // The last published snapshot is still readable
+        assert_eq!(shared.snapshot().unwrap().epoch, 0);
+        let shared = Arc::try_unwrap(shared).unwrap();
+        assert!(shared.into_inner().is_err());
+    }
+}
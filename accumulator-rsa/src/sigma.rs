@@ -0,0 +1,339 @@
+// Membership and non-membership proofs as three move protocols.
+//
+// The prover commits, receives a challenge and responds. The one-shot
+// proofs use the nonce as the challenge. Here the challenge can come from
+// an interactive verifier or be hashed from the commitments of several
+// proofs so they all answer the same challenge, an AND composition inside a
+// larger protocol. The prime and alpha of each PoKE2 are hashed from the
+// challenge together with that PoKE2's commitment, as the one-shot proofs do
+// with the nonce, so a challenge chosen before the commitment can't be used
+// to forge a response
+use crate::{
+    accumulator::{Accumulator, PublicState},
+    b2fa, bound_nonce, hashed_generator,
+    memproof::MembershipProof,
+    memwitness::MembershipWitness,
+    nonmemproof::NonMembershipProof,
+    nonwitness::NonMembershipWitness,
+    store::MemberStore,
+    telemetry, Poke2Committing, ProofSize, FACTOR_SIZE,
+};
+use blake2::{digest::Digest, Blake2b};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+    Field,
+};
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::fmt::Debug;
+#[cfg(feature = "std")]
+use rand::{rngs::OsRng, RngCore};
+
+/// A proof with commit, challenge and response phases
+pub trait SigmaProof {
+    /// What the prover keeps from committing until the response
+    type Committed: Debug + Clone;
+}
+
+impl SigmaProof for MembershipProof {
+    type Committed = MembershipCommitted;
+}
+
+impl SigmaProof for NonMembershipProof {
+    type Committed = NonMembershipCommitted;
+}
+
+/// The committed PoKE2 of a membership proof
+#[derive(Debug, Clone)]
+pub struct MembershipCommitted(Poke2Committing);
+
+/// The commitment `v = A^a` of a non-membership proof and the committed
+/// PoKE2s for `v` and for `b`
+#[derive(Debug, Clone)]
+pub struct NonMembershipCommitted {
+    v: BigInteger,
+    proof_v: Poke2Committing,
+    proof_g: Poke2Committing,
+}
+
+/// The prover's state after sending the commitment of a proof `P`,
+/// `MembershipProof` or `NonMembershipProof`, waiting for the challenge
+#[derive(Debug, Clone)]
+pub struct ProofCommitting<P: SigmaProof> {
+    modulus: BigInteger,
+    committed: P::Committed,
+    size: ProofSize,
+}
+
+/// The challenge the prover responds to
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ProofChallenge(Vec<u8>);
+
+/// The prover's response to a challenge, the proof `P`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ProofFinal<P>(P);
+
+impl<P: SigmaProof> ProofCommitting<P> {
+    /// Respond with challenges of `size`, standard unless set
+    pub fn with_size(mut self, size: ProofSize) -> Self {
+        self.size = size;
+        self
+    }
+}
+
+impl ProofCommitting<MembershipProof> {
+    /// Commit to a membership proof for `witness`. The generator of the
+    /// proof is hashed from `nonce` and the accumulator epoch
+    pub fn new<S>(
+        witness: &MembershipWitness,
+        accumulator: &Accumulator<S>,
+        nonce: &[u8],
+    ) -> Result<Self, AccumulatorError> {
+        Self::with_bound_nonce(witness, accumulator, &bound_nonce(accumulator.epoch, &[], &[], nonce))
+    }
+
+    pub(crate) fn with_bound_nonce<S>(
+        witness: &MembershipWitness,
+        accumulator: &Accumulator<S>,
+        nonce: &[u8],
+    ) -> Result<Self, AccumulatorError> {
+        witness.freshness.check(accumulator.epoch, &accumulator.value)?;
+        let (value, n) = (&accumulator.value, accumulator.modulus.as_ref());
+        let g = hashed_generator(&witness.u, value, n, nonce)?;
+        let part = Poke2Committing::new(&Field::new(n), &witness.x, &witness.u, value, &g)?;
+        Ok(Self {
+            modulus: n.clone(),
+            committed: MembershipCommitted(part),
+            size: ProofSize::Standard,
+        })
+    }
+
+    /// The commitment to send to the verifier or hash into the challenge
+    pub fn commitment(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let part = &self.committed.0;
+        commitment(&[&part.u, &part.z])
+    }
+
+    /// Respond to `challenge`
+    pub fn respond(&self, challenge: &ProofChallenge) -> Result<ProofFinal<MembershipProof>, AccumulatorError> {
+        let proof = self.committed.0.respond(&Field::new(&self.modulus), &challenge.0, self.size)?;
+        Ok(ProofFinal(MembershipProof(proof)))
+    }
+}
+
+impl ProofCommitting<NonMembershipProof> {
+    /// Commit to a non-membership proof for `witness`. The generators of
+    /// the proof are hashed from `nonce` and the accumulator epoch
+    pub fn new<S: MemberStore>(
+        witness: &NonMembershipWitness,
+        accumulator: &Accumulator<S>,
+        nonce: &[u8],
+    ) -> Result<Self, AccumulatorError> {
+        let nonce = bound_nonce(accumulator.epoch, &[], &[], nonce);
+        Ok(Self::with_nonce(witness, accumulator, |_| nonce)?.0)
+    }
+
+    /// `nonce` is called with the commitment `v` once it is known,
+    /// the bound nonce is returned with the state
+    pub(crate) fn with_nonce<S: MemberStore, F: FnOnce(&BigInteger) -> Vec<u8>>(
+        witness: &NonMembershipWitness,
+        accumulator: &Accumulator<S>,
+        nonce: F,
+    ) -> Result<(Self, Vec<u8>), AccumulatorError> {
+        witness.freshness.check(accumulator.epoch, &accumulator.value)?;
+        let n = accumulator.modulus.as_ref();
+        let f = Field::new(n);
+        let g_inv = f.inv(&accumulator.generator)?;
+
+        // The proof needs a positive exponent. a' = a mod x and b' = b * A^((a' - a) / x)
+        // satisfy A^a' = b'^x * g the same as a and b
+        let zero = BigInteger::default();
+        let (a, b) = if witness.a < zero {
+            let a = &(&(&witness.a % &witness.x) + &witness.x) % &witness.x;
+            let k = &(&a - &witness.a) / &witness.x;
+            let b = f.mul(&witness.b, &accumulator.fixed_exp(&accumulator.value, &k));
+            (a, b)
+        } else {
+            (witness.a.clone(), witness.b.clone())
+        };
+        let v = accumulator.fixed_exp(&accumulator.value, &a);
+
+        // A^a = b^x * g, the proofs would not verify otherwise
+        let gv_inv = f.mul(&g_inv, &v);
+        if gv_inv != f.exp(&b, &witness.x) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "witness is not for the accumulator",
+            ));
+        }
+
+        let nonce = nonce(&v);
+        let g_v = hashed_generator(&accumulator.value, &v, n, &nonce)?;
+        let g_g = hashed_generator(&b, &gv_inv, n, &nonce)?;
+        let proof_v = Poke2Committing::new(&f, &a, &accumulator.value, &v, &g_v)?;
+        let proof_g = Poke2Committing::new(&f, &witness.x, &b, &gv_inv, &g_g)?;
+        let committing = Self {
+            modulus: n.clone(),
+            committed: NonMembershipCommitted { v, proof_v, proof_g },
+            size: ProofSize::Standard,
+        };
+        Ok((committing, nonce))
+    }
+
+    /// The commitment to send to the verifier or hash into the challenge.
+    /// The base of the first PoKE2 is the accumulator value so it's left out
+    pub fn commitment(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let c = &self.committed;
+        commitment(&[&c.v, &c.proof_v.z, &c.proof_g.u, &c.proof_g.z])
+    }
+
+    /// Respond to `challenge`
+    pub fn respond(&self, challenge: &ProofChallenge) -> Result<ProofFinal<NonMembershipProof>, AccumulatorError> {
+        let f = Field::new(&self.modulus);
+        let c = &self.committed;
+        let proof_v = c.proof_v.respond(&f, &challenge.0, self.size)?;
+        let proof_g = c.proof_g.respond(&f, &challenge.0, self.size)?;
+        Ok(ProofFinal(NonMembershipProof {
+            v: c.v.clone(),
+            r: proof_v.r,
+            q: proof_v.q,
+            z: proof_v.z,
+            proof_g,
+        }))
+    }
+}
+
+impl ProofChallenge {
+    /// A challenge chosen by the verifier
+    pub fn new<B: AsRef<[u8]>>(challenge: B) -> Self {
+        Self(challenge.as_ref().to_vec())
+    }
+
+    /// A random challenge for an interactive verifier
+    #[cfg(feature = "std")]
+    pub fn random() -> Self {
+        let mut challenge = vec![0u8; 32];
+        OsRng.fill_bytes(&mut challenge);
+        Self(challenge)
+    }
+
+    /// The Fiat-Shamir challenge hashed from the commitments of every
+    /// proof answering it and any other public values of the protocol
+    pub fn hash<B: AsRef<[u8]>>(transcript: &[B]) -> Self {
+        let mut data = Vec::new();
+        for t in transcript {
+            let t = t.as_ref();
+            data.extend_from_slice(&(t.len() as u64).to_be_bytes());
+            data.extend_from_slice(t);
+        }
+        Self(Blake2b::digest(data.as_slice()).to_vec())
+    }
+
+    /// The challenge bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<P> ProofFinal<P> {
+    /// The proof
+    pub fn proof(&self) -> &P {
+        &self.0
+    }
+
+    /// Return the proof
+    pub fn into_proof(self) -> P {
+        self.0
+    }
+}
+
+impl<P> From<P> for ProofFinal<P> {
+    fn from(proof: P) -> Self {
+        Self(proof)
+    }
+}
+
+impl ProofFinal<MembershipProof> {
+    /// The commitment the prover sent, the same as `ProofCommitting::commitment`
+    pub fn commitment(&self) -> Result<Vec<u8>, AccumulatorError> {
+        commitment(&[&self.0 .0.u, &self.0 .0.z])
+    }
+
+    /// Verify the response to `challenge` for a commitment made with `nonce`
+    pub fn verify<P: PublicState>(&self, accumulator: &P, nonce: &[u8], challenge: &ProofChallenge) -> bool {
+        telemetry::verify("membership", || {
+            let nonce = bound_nonce(accumulator.epoch(), &[], &[], nonce);
+            self.0 .0.verify_with_challenge(accumulator.value(), accumulator.modulus(), &nonce, &challenge.0)
+        })
+    }
+}
+
+impl ProofFinal<NonMembershipProof> {
+    /// The commitment the prover sent, the same as `ProofCommitting::commitment`
+    pub fn commitment(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let p = &self.0;
+        commitment(&[&p.v, &p.z, &p.proof_g.u, &p.proof_g.z])
+    }
+
+    /// Verify the response to `challenge` for a commitment made with `nonce`
+    pub fn verify<P: PublicState>(&self, accumulator: &P, nonce: &[u8], challenge: &ProofChallenge) -> bool {
+        telemetry::verify("non_membership", || {
+            let nonce = bound_nonce(accumulator.epoch(), &[], &[], nonce);
+            self.0.check(accumulator, &nonce, &challenge.0)
+        })
+    }
+}
+
+/// Group elements written one after the other
+fn commitment(elements: &[&BigInteger]) -> Result<Vec<u8>, AccumulatorError> {
+    let mut output = Vec::with_capacity(elements.len() * 2 * FACTOR_SIZE);
+    for e in elements {
+        output.append(&mut b2fa(e, 2 * FACTOR_SIZE)?);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::AccumulatorSecretKey;
+
+    #[test]
+    fn phases() {
+        let key = AccumulatorSecretKey::default();
+        let acc = Accumulator::with_members(&key, ["a", "b", "c"]).unwrap();
+        let witness = MembershipWitness::new(&acc, "a").unwrap();
+        let non_witness = NonMembershipWitness::new(&acc, "d").unwrap();
+
+        // The one-shot proofs answer the nonce as the challenge
+        let committing = ProofCommitting::<MembershipProof>::new(&witness, &acc, b"nonce").unwrap();
+        let bound = ProofChallenge::new(bound_nonce(acc.epoch, &[], &[], b"nonce"));
+        let proof = committing.respond(&bound).unwrap();
+        assert_eq!(proof.proof(), &MembershipProof::new(&witness, &acc, b"nonce").unwrap());
+        assert_eq!(proof.commitment().unwrap(), committing.commitment().unwrap());
+
+        // Both proofs answer one challenge hashed from their commitments
+        let member = ProofCommitting::<MembershipProof>::new(&witness, &acc, b"nonce").unwrap();
+        let non_member = ProofCommitting::<NonMembershipProof>::new(&non_witness, &acc, b"nonce").unwrap();
+        let commitments = [member.commitment().unwrap(), non_member.commitment().unwrap()];
+        let challenge = ProofChallenge::hash(&commitments);
+        let member = member.respond(&challenge).unwrap();
+        let non_member = non_member.respond(&challenge).unwrap();
+        assert_eq!(non_member.commitment().unwrap(), commitments[1]);
+        assert!(member.verify(&acc, b"nonce", &challenge));
+        assert!(non_member.verify(&acc, b"nonce", &challenge));
+        let verified = ProofChallenge::hash(&[member.commitment().unwrap(), non_member.commitment().unwrap()]);
+        assert_eq!(verified, challenge);
+
+        // Not for another challenge, nonce or accumulator
+        let other = ProofChallenge::random();
+        assert!(!member.verify(&acc, b"nonce", &other));
+        assert!(!non_member.verify(&acc, b"nonce", &other));
+        assert!(!member.verify(&acc, b"other", &challenge));
+        let acc = acc.insert("e").unwrap();
+        assert!(!member.verify(&acc, b"nonce", &challenge));
+        assert!(!non_member.verify(&acc, b"nonce", &challenge));
+        assert!(!ProofFinal::from(member.into_proof()).verify(&acc, b"nonce", &bound));
+    }
+}
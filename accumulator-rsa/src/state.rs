@@ -0,0 +1,244 @@
+use crate::{
+    accumulator::AccumulatorPublic,
+    b2fa,
+    format::{Header, Tag},
+    FACTOR_SIZE,
+};
+use blake2::{digest::Digest, Blake2b};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+};
+#[cfg(feature = "ed25519")]
+use crate::format::HEADER_SIZE;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+/// The published state of an accumulator at one epoch. Issuers sign it
+/// so holders and verifiers can reject a spoofed value before checking
+/// any proof against it
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct AccumulatorState {
+    /// The accumulator value
+    pub value: BigInteger,
+    /// The epoch of `value`
+    pub epoch: u64,
+    /// When the state was published, seconds since the unix epoch
+    pub timestamp: u64,
+    /// Blake2b hash of the generator and modulus
+    pub params_hash: [u8; AccumulatorState::PARAMS_HASH_BYTES],
+}
+
+impl AccumulatorState {
+    /// The size of `params_hash`
+    pub const PARAMS_HASH_BYTES: usize = 64;
+    /// The number of bytes this serializes to without the header
+    pub const SIZE_BYTES: usize = 2 * FACTOR_SIZE + 16 + Self::PARAMS_HASH_BYTES;
+
+    /// The state of `accumulator` published at `timestamp`
+    pub fn new(accumulator: &AccumulatorPublic, timestamp: u64) -> Self {
+        Self {
+            value: accumulator.value.clone(),
+            epoch: accumulator.epoch,
+            timestamp,
+            params_hash: Self::hash_params(accumulator),
+        }
+    }
+
+    /// True if `accumulator` has the value, epoch and parameters of this state
+    pub fn matches(&self, accumulator: &AccumulatorPublic) -> bool {
+        self.value == accumulator.value
+            && self.epoch == accumulator.epoch
+            && self.params_hash == Self::hash_params(accumulator)
+    }
+
+    /// H( generator || modulus )
+    fn hash_params(accumulator: &AccumulatorPublic) -> [u8; Self::PARAMS_HASH_BYTES] {
+        let mut data = accumulator.generator.to_bytes();
+        data.append(&mut accumulator.modulus.to_bytes());
+        let mut hash = [0u8; Self::PARAMS_HASH_BYTES];
+        hash.copy_from_slice(Blake2b::digest(data.as_slice()).as_slice());
+        hash
+    }
+
+    /// Serialize this to bytes, also the message that is signed
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Header::new(Tag::AccumulatorState).to_bytes();
+        output.append(&mut b2fa(&self.value, 2 * FACTOR_SIZE)?);
+        output.extend_from_slice(&self.epoch.to_be_bytes());
+        output.extend_from_slice(&self.timestamp.to_be_bytes());
+        output.extend_from_slice(&self.params_hash);
+        Ok(output)
+    }
+
+    /// Sign this state with the issuer's key
+    #[cfg(feature = "ed25519")]
+    pub fn sign(self, key: &ed25519_dalek::SigningKey) -> Result<SignedAccumulatorState, AccumulatorError> {
+        use ed25519_dalek::Signer;
+
+        let signature = key.sign(&self.to_bytes()?);
+        Ok(SignedAccumulatorState { state: self, signature })
+    }
+}
+
+impl TryFrom<&[u8]> for AccumulatorState {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::AccumulatorState)?;
+        if data.len() != Self::SIZE_BYTES {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: Self::SIZE_BYTES,
+                actual: data.len(),
+            }
+            .into());
+        }
+        let offset = 2 * FACTOR_SIZE;
+        Ok(Self {
+            value: BigInteger::try_from(&data[..offset])?,
+            epoch: u64::from_be_bytes(*array_ref![data, offset, 8]),
+            timestamp: u64::from_be_bytes(*array_ref![data, offset + 8, 8]),
+            params_hash: *array_ref![data, offset + 16, AccumulatorState::PARAMS_HASH_BYTES],
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for AccumulatorState {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+serdes_impl!(AccumulatorState);
+
+/// An `AccumulatorState` with the issuer's Ed25519 signature
+#[cfg(feature = "ed25519")]
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct SignedAccumulatorState {
+    state: AccumulatorState,
+    signature: ed25519_dalek::Signature,
+}
+
+#[cfg(feature = "ed25519")]
+impl SignedAccumulatorState {
+    /// The number of bytes this serializes to without the header
+    pub const SIZE_BYTES: usize = AccumulatorState::SIZE_BYTES + ed25519_dalek::SIGNATURE_LENGTH;
+
+    /// The state if it was signed by `key`
+    pub fn verify(&self, key: &ed25519_dalek::VerifyingKey) -> Result<&AccumulatorState, AccumulatorError> {
+        key.verify_strict(&self.state.to_bytes()?, &self.signature)
+            .map_err(|_| AccumulatorError::from_msg(AccumulatorErrorKind::ProofVerificationFailed, "invalid state signature"))?;
+        Ok(&self.state)
+    }
+
+    /// The state if it was signed by `key` and describes `accumulator`
+    pub fn verify_for(
+        &self,
+        key: &ed25519_dalek::VerifyingKey,
+        accumulator: &AccumulatorPublic,
+    ) -> Result<&AccumulatorState, AccumulatorError> {
+        let state = self.verify(key)?;
+        if !state.matches(accumulator) {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::ParameterMismatch,
+                "signed state is for another accumulator value",
+            ));
+        }
+        Ok(state)
+    }
+
+    /// Serialize this to bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AccumulatorError> {
+        let mut output = Header::new(Tag::SignedAccumulatorState).to_bytes();
+        output.extend_from_slice(&self.state.to_bytes()?[HEADER_SIZE..]);
+        output.extend_from_slice(&self.signature.to_bytes());
+        Ok(output)
+    }
+}
+
+#[cfg(feature = "ed25519")]
+impl TryFrom<&[u8]> for SignedAccumulatorState {
+    type Error = AccumulatorError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data = Header::check(data, Tag::SignedAccumulatorState)?;
+        if data.len() != Self::SIZE_BYTES {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: Self::SIZE_BYTES,
+                actual: data.len(),
+            }
+            .into());
+        }
+        let mut state = Header::new(Tag::AccumulatorState).to_bytes();
+        state.extend_from_slice(&data[..AccumulatorState::SIZE_BYTES]);
+        Ok(Self {
+            state: AccumulatorState::try_from(state)?,
+            signature: ed25519_dalek::Signature::from_bytes(array_ref![
+                data,
+                AccumulatorState::SIZE_BYTES,
+                ed25519_dalek::SIGNATURE_LENGTH
+            ]),
+        })
+    }
+}
+
+#[cfg(feature = "ed25519")]
+impl TryFrom<Vec<u8>> for SignedAccumulatorState {
+    type Error = AccumulatorError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
+}
+
+#[cfg(feature = "ed25519")]
+serdes_impl!(SignedAccumulatorState);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{accumulator::Accumulator, format::HEADER_SIZE, key::AccumulatorSecretKey};
+
+    #[test]
+    fn state_bytes() {
+        let key = AccumulatorSecretKey::default();
+        let mut acc = Accumulator::with_members(&key, ["a", "b"]).unwrap();
+        let state = AccumulatorState::new(&acc.public(), 1_600_000_000);
+        assert!(state.matches(&acc.public()));
+        let bytes = state.to_bytes().unwrap();
+        assert_eq!(bytes.len(), HEADER_SIZE + AccumulatorState::SIZE_BYTES);
+        assert_eq!(AccumulatorState::try_from(bytes).unwrap(), state);
+
+        acc.insert_assign("c").unwrap();
+        assert!(!state.matches(&acc.public()));
+    }
+
+    #[cfg(feature = "ed25519")]
+    #[test]
+    fn signed_state() {
+        let key = AccumulatorSecretKey::default();
+        let mut acc = Accumulator::with_members(&key, ["a", "b"]).unwrap();
+        let issuer = ed25519_dalek::SigningKey::from_bytes(&[7u8; 32]);
+        let signed = AccumulatorState::new(&acc.public(), 1_600_000_000).sign(&issuer).unwrap();
+
+        assert!(signed.verify(&issuer.verifying_key()).is_ok());
+        assert!(signed.verify_for(&issuer.verifying_key(), &acc.public()).is_ok());
+        let other = ed25519_dalek::SigningKey::from_bytes(&[8u8; 32]);
+        assert!(signed.verify(&other.verifying_key()).is_err());
+
+        let bytes = signed.to_bytes().unwrap();
+        assert_eq!(bytes.len(), HEADER_SIZE + SignedAccumulatorState::SIZE_BYTES);
+        assert_eq!(SignedAccumulatorState::try_from(bytes.as_slice()).unwrap(), signed);
+
+        // A spoofed value with the issuer's old signature
+        let mut spoofed = signed.clone();
+        spoofed.state.value = BigInteger::from(4u32);
+        assert!(spoofed.verify(&issuer.verifying_key()).is_err());
+
+        acc.insert_assign("c").unwrap();
+        assert!(signed.verify_for(&issuer.verifying_key(), &acc.public()).is_err());
+    }
+}
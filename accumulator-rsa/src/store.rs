@@ -0,0 +1,1159 @@
+use common::{bigint::BigInteger, error::AccumulatorError};
+use crate::par::*;
+use core::ops::Bound;
+#[cfg(not(feature = "std"))]
+use alloc::{
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet},
+    vec,
+    vec::Vec,
+};
+#[cfg(feature = "std")]
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Members of a store in ascending order
+pub type MemberIter<'a> = Box<dyn Iterator<Item = Result<BigInteger, AccumulatorError>> + 'a>;
+
+/// Where an accumulator keeps its members. The default is an in-memory
+/// `BTreeSet`, registries too large for memory can keep them on disk.
+pub trait MemberStore {
+    /// True if `member` is in the set
+    fn contains(&self, member: &BigInteger) -> Result<bool, AccumulatorError>;
+
+    /// Add `member`, false if it was already in the set
+    fn insert(&mut self, member: BigInteger) -> Result<bool, AccumulatorError>;
+
+    /// Remove `member`, false if it wasn't in the set
+    fn remove(&mut self, member: &BigInteger) -> Result<bool, AccumulatorError>;
+
+    /// The number of members
+    fn len(&self) -> usize;
+
+    /// True if there are no members
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every member in ascending order
+    fn iter(&self) -> MemberIter<'_>;
+
+    /// The members greater than `after` in ascending order. The default
+    /// skips through `iter`, ordered stores seek straight to `after`
+    fn iter_after(&self, after: &BigInteger) -> MemberIter<'_> {
+        let after = after.clone();
+        Box::new(self.iter().skip_while(move |m| m.as_ref().is_ok_and(|m| *m <= after)))
+    }
+
+    /// The product of all members if the store can compute it faster than
+    /// multiplying the members from `iter` one at a time, like a parallel
+    /// product or a cached value
+    fn product_hint(&self) -> Option<BigInteger> {
+        None
+    }
+
+    /// Add `member` which was hashed from `preimage`. Stores that
+    /// keep preimages override this, the default only keeps the member
+    fn insert_preimage(&mut self, member: BigInteger, preimage: &[u8]) -> Result<bool, AccumulatorError> {
+        let _ = preimage;
+        self.insert(member)
+    }
+
+    /// The value `member` was hashed from, `None` if the store
+    /// doesn't keep them or the member was added as a prime
+    fn preimage(&self, member: &BigInteger) -> Result<Option<Vec<u8>>, AccumulatorError> {
+        let _ = member;
+        Ok(None)
+    }
+
+    /// Called after a batch of members was added and removed so stores
+    /// with an index over the members can rebuild it once
+    fn batch_applied(&mut self) -> Result<(), AccumulatorError> {
+        Ok(())
+    }
+}
+
+impl MemberStore for BTreeSet<BigInteger> {
+    fn contains(&self, member: &BigInteger) -> Result<bool, AccumulatorError> {
+        Ok(BTreeSet::contains(self, member))
+    }
+
+    fn insert(&mut self, member: BigInteger) -> Result<bool, AccumulatorError> {
+        Ok(BTreeSet::insert(self, member))
+    }
+
+    fn remove(&mut self, member: &BigInteger) -> Result<bool, AccumulatorError> {
+        Ok(BTreeSet::remove(self, member))
+    }
+
+    fn len(&self) -> usize {
+        BTreeSet::len(self)
+    }
+
+    fn iter(&self) -> MemberIter<'_> {
+        Box::new(BTreeSet::iter(self).cloned().map(Ok))
+    }
+
+    fn iter_after(&self, after: &BigInteger) -> MemberIter<'_> {
+        Box::new(self.range((Bound::Excluded(after), Bound::Unbounded)).cloned().map(Ok))
+    }
+
+    fn product_hint(&self) -> Option<BigInteger> {
+        Some(self.par_iter().product())
+    }
+}
+
+/// Keeps the values members were hashed from next to the members
+/// in `S` so operators can list and audit them. Preimages aren't part
+/// of the accumulator's serialization, keep them with the store
+#[derive(Debug, Clone, Default)]
+pub struct PreimageStore<S = BTreeSet<BigInteger>> {
+    members: S,
+    preimages: BTreeMap<BigInteger, Vec<u8>>,
+}
+
+impl<S: MemberStore> PreimageStore<S> {
+    /// Keep preimages for members added to `members` from now on
+    pub fn new(members: S) -> Self {
+        Self {
+            members,
+            preimages: BTreeMap::new(),
+        }
+    }
+
+    /// The members that have a preimage with their preimage in ascending order
+    pub fn preimages(&self) -> impl Iterator<Item = (&BigInteger, &[u8])> {
+        self.preimages.iter().map(|(m, v)| (m, v.as_slice()))
+    }
+
+    /// The member hashed from `preimage` if it is in the store
+    pub fn find(&self, preimage: &[u8]) -> Result<Option<BigInteger>, AccumulatorError> {
+        let member = crate::hash::hash_to_prime(preimage);
+        Ok(if self.members.contains(&member)? { Some(member) } else { None })
+    }
+
+    /// The store without the preimages
+    pub fn into_inner(self) -> S {
+        self.members
+    }
+}
+
+impl<S: MemberStore> MemberStore for PreimageStore<S> {
+    fn contains(&self, member: &BigInteger) -> Result<bool, AccumulatorError> {
+        self.members.contains(member)
+    }
+
+    fn insert(&mut self, member: BigInteger) -> Result<bool, AccumulatorError> {
+        self.members.insert(member)
+    }
+
+    fn remove(&mut self, member: &BigInteger) -> Result<bool, AccumulatorError> {
+        self.preimages.remove(member);
+        self.members.remove(member)
+    }
+
+    fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    fn iter(&self) -> MemberIter<'_> {
+        self.members.iter()
+    }
+
+    fn iter_after(&self, after: &BigInteger) -> MemberIter<'_> {
+        self.members.iter_after(after)
+    }
+
+    fn product_hint(&self) -> Option<BigInteger> {
+        self.members.product_hint()
+    }
+
+    fn insert_preimage(&mut self, member: BigInteger, preimage: &[u8]) -> Result<bool, AccumulatorError> {
+        let inserted = self.members.insert_preimage(member.clone(), preimage)?;
+        if inserted {
+            self.preimages.insert(member, preimage.to_vec());
+        }
+        Ok(inserted)
+    }
+
+    fn preimage(&self, member: &BigInteger) -> Result<Option<Vec<u8>>, AccumulatorError> {
+        Ok(self.preimages.get(member).cloned())
+    }
+
+    fn batch_applied(&mut self) -> Result<(), AccumulatorError> {
+        self.members.batch_applied()
+    }
+}
+
+/// Members of `S` behind a Bloom filter so `contains` and the duplicate
+/// checks of insertions answer most non-members without asking `S`, which
+/// helps when `S` is on disk. Members found in the filter are still looked
+/// up in `S` so answers are exact. Removed members stay in the filter until
+/// it is rebuilt, which happens after a batch update, when the filter is
+/// full, or when a quarter of its entries are stale
+#[derive(Debug, Clone)]
+pub struct BloomStore<S = BTreeSet<BigInteger>> {
+    members: S,
+    bits: Vec<u64>,
+    capacity: usize,
+    stale: usize,
+}
+
+impl<S: MemberStore> BloomStore<S> {
+    /// Filter bits per member, with `HASHES` about 1% of non-members pass
+    const BITS_PER_MEMBER: usize = 10;
+    /// Bits set per member
+    const HASHES: u64 = 7;
+    /// Members a filter has room for at least
+    const MIN_CAPACITY: usize = 64;
+
+    /// Filter the members of `members`
+    pub fn new(members: S) -> Result<Self, AccumulatorError> {
+        let mut store = Self {
+            members,
+            bits: Vec::new(),
+            capacity: 0,
+            stale: 0,
+        };
+        store.rebuild()?;
+        Ok(store)
+    }
+
+    /// False if `member` is certainly not in the store
+    pub fn might_contain(&self, member: &BigInteger) -> bool {
+        Self::positions(&self.bits, member).all(|(w, b)| self.bits[w] & b != 0)
+    }
+
+    /// Fill a new filter with room for twice the current members
+    pub fn rebuild(&mut self) -> Result<(), AccumulatorError> {
+        let capacity = core::cmp::max(2 * self.members.len(), Self::MIN_CAPACITY);
+        let mut bits = vec![0u64; (capacity * Self::BITS_PER_MEMBER).div_ceil(64)];
+        for m in self.members.iter() {
+            Self::set(&mut bits, &m?);
+        }
+        self.bits = bits;
+        self.capacity = capacity;
+        self.stale = 0;
+        Ok(())
+    }
+
+    /// The store without the filter
+    pub fn into_inner(self) -> S {
+        self.members
+    }
+
+    fn set(bits: &mut [u64], member: &BigInteger) {
+        let positions: Vec<(usize, u64)> = Self::positions(bits, member).collect();
+        for (w, b) in positions {
+            bits[w] |= b;
+        }
+    }
+
+    /// The word and bit of each hash of `member`, two hashes from one
+    /// digest combined as h1 + i * h2
+    fn positions(bits: &[u64], member: &BigInteger) -> impl Iterator<Item = (usize, u64)> {
+        use blake2::{
+            digest::{Input, VariableOutput},
+            VarBlake2b,
+        };
+
+        let mut h = VarBlake2b::new(16).unwrap();
+        h.input(member.to_bytes());
+        let mut out = [0u8; 16];
+        h.variable_result(|r| out.copy_from_slice(r));
+        let h1 = u64::from_be_bytes(*array_ref![out, 0, 8]);
+        let h2 = u64::from_be_bytes(*array_ref![out, 8, 8]) | 1;
+        let size = bits.len() as u64 * 64;
+        (0..Self::HASHES).map(move |i| {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % size;
+            ((bit / 64) as usize, 1u64 << (bit % 64))
+        })
+    }
+
+    /// Add `member` to the filter after it was added to the store
+    fn index(&mut self, member: &BigInteger) -> Result<(), AccumulatorError> {
+        if self.members.len() + self.stale > self.capacity {
+            self.rebuild()
+        } else {
+            Self::set(&mut self.bits, member);
+            Ok(())
+        }
+    }
+}
+
+impl<S: MemberStore> MemberStore for BloomStore<S> {
+    fn contains(&self, member: &BigInteger) -> Result<bool, AccumulatorError> {
+        if !self.might_contain(member) {
+            return Ok(false);
+        }
+        self.members.contains(member)
+    }
+
+    fn insert(&mut self, member: BigInteger) -> Result<bool, AccumulatorError> {
+        if !self.members.insert(member.clone())? {
+            return Ok(false);
+        }
+        self.index(&member)?;
+        Ok(true)
+    }
+
+    fn remove(&mut self, member: &BigInteger) -> Result<bool, AccumulatorError> {
+        if !self.might_contain(member) || !self.members.remove(member)? {
+            return Ok(false);
+        }
+        self.stale += 1;
+        if 4 * self.stale > self.capacity {
+            self.rebuild()?;
+        }
+        Ok(true)
+    }
+
+    fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    fn iter(&self) -> MemberIter<'_> {
+        self.members.iter()
+    }
+
+    fn iter_after(&self, after: &BigInteger) -> MemberIter<'_> {
+        self.members.iter_after(after)
+    }
+
+    fn product_hint(&self) -> Option<BigInteger> {
+        self.members.product_hint()
+    }
+
+    fn insert_preimage(&mut self, member: BigInteger, preimage: &[u8]) -> Result<bool, AccumulatorError> {
+        if !self.members.insert_preimage(member.clone(), preimage)? {
+            return Ok(false);
+        }
+        self.index(&member)?;
+        Ok(true)
+    }
+
+    fn preimage(&self, member: &BigInteger) -> Result<Option<Vec<u8>>, AccumulatorError> {
+        self.members.preimage(member)
+    }
+
+    fn batch_applied(&mut self) -> Result<(), AccumulatorError> {
+        self.members.batch_applied()?;
+        self.rebuild()
+    }
+}
+
+/// Members of `S` with an index that answers `contains` in the same
+/// time whether or not the member is present, for servers where
+/// untrusted clients can time lookups. Members hash with a secret key to
+/// fixed size buckets and every slot of a bucket is compared in constant
+/// time. Lookups only reveal how many members there are. Insertions and
+/// removals still go through `S` and are not constant time
+#[cfg(feature = "ct-store")]
+#[derive(Clone)]
+pub struct ConstantTimeStore<S = BTreeSet<BigInteger>> {
+    members: S,
+    key: [u8; 32],
+    slots: Vec<[u8; crate::MEMBER_SIZE]>,
+}
+
+#[cfg(feature = "ct-store")]
+impl<S: core::fmt::Debug> core::fmt::Debug for ConstantTimeStore<S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // The slots a member lands in depend on the key
+        f.debug_struct("ConstantTimeStore")
+            .field("members", &self.members)
+            .field("key", &"<redacted>")
+            .field("slots", &self.slots.len())
+            .finish()
+    }
+}
+
+#[cfg(feature = "ct-store")]
+impl<S: MemberStore> ConstantTimeStore<S> {
+    /// Slots per bucket, a bucket that fills doubles the table
+    const BUCKET_SLOTS: usize = 8;
+    /// Buckets in an empty table
+    const MIN_BUCKETS: usize = 16;
+
+    /// Index the members of `members` with the secret `key`
+    pub fn new(members: S, key: [u8; 32]) -> Result<Self, AccumulatorError> {
+        let mut store = Self {
+            members,
+            key,
+            slots: Vec::new(),
+        };
+        store.rebuild(Self::MIN_BUCKETS)?;
+        Ok(store)
+    }
+
+    /// Index the members of `members` with a random key
+    #[cfg(feature = "std")]
+    pub fn with_random_key(members: S) -> Result<Self, AccumulatorError> {
+        use rand::RngCore;
+
+        let mut key = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut key);
+        Self::new(members, key)
+    }
+
+    /// The store without the index
+    pub fn into_inner(self) -> S {
+        self.members
+    }
+
+    fn buckets(&self) -> usize {
+        self.slots.len() / Self::BUCKET_SLOTS
+    }
+
+    /// The first slot of the bucket for `member` in a table of `slots`
+    fn bucket(key: &[u8; 32], slots: usize, member: &[u8; crate::MEMBER_SIZE]) -> usize {
+        use blake2::{
+            digest::{Input, VariableOutput},
+            VarBlake2b,
+        };
+
+        let mut h = VarBlake2b::new_keyed(key, 8);
+        h.input(member);
+        let mut out = [0u8; 8];
+        h.variable_result(|r| out.copy_from_slice(r));
+        let buckets = (slots / Self::BUCKET_SLOTS) as u64;
+        (u64::from_be_bytes(out) % buckets) as usize * Self::BUCKET_SLOTS
+    }
+
+    /// The slots of the bucket for `member`
+    fn slots(&self, member: &[u8; crate::MEMBER_SIZE]) -> &[[u8; crate::MEMBER_SIZE]] {
+        let start = Self::bucket(&self.key, self.slots.len(), member);
+        &self.slots[start..(start + Self::BUCKET_SLOTS)]
+    }
+
+    /// Put `member` in an empty slot of its bucket, false if it is full
+    fn place(key: &[u8; 32], slots: &mut [[u8; crate::MEMBER_SIZE]], member: [u8; crate::MEMBER_SIZE]) -> bool {
+        let start = Self::bucket(key, slots.len(), &member);
+        match slots[start..(start + Self::BUCKET_SLOTS)]
+            .iter_mut()
+            .find(|s| **s == [0u8; crate::MEMBER_SIZE])
+        {
+            Some(slot) => {
+                *slot = member;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Add `member` to the index, growing it if it's half full or the bucket is full
+    fn index(&mut self, member: [u8; crate::MEMBER_SIZE]) -> Result<(), AccumulatorError> {
+        if 2 * self.members.len() > self.slots.len() || !Self::place(&self.key, &mut self.slots, member) {
+            self.rebuild(2 * self.buckets())?;
+        }
+        Ok(())
+    }
+
+    /// Index every member in a table of at least `buckets` buckets
+    fn rebuild(&mut self, mut buckets: usize) -> Result<(), AccumulatorError> {
+        buckets = core::cmp::max(buckets, 2 * self.members.len() / Self::BUCKET_SLOTS);
+        'grow: loop {
+            let mut slots = Vec::new();
+            slots.resize(buckets * Self::BUCKET_SLOTS, [0u8; crate::MEMBER_SIZE]);
+            for m in self.members.iter() {
+                if !Self::place(&self.key, &mut slots, member_bytes(&m?)?) {
+                    buckets *= 2;
+                    continue 'grow;
+                }
+            }
+            self.slots = slots;
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(feature = "ct-store")]
+impl<S: MemberStore> MemberStore for ConstantTimeStore<S> {
+    fn contains(&self, member: &BigInteger) -> Result<bool, AccumulatorError> {
+        use subtle::{Choice, ConstantTimeEq};
+
+        // Members are never larger, the size of a query isn't secret
+        let member = match member_bytes(member) {
+            Ok(m) => m,
+            Err(_) => return Ok(false),
+        };
+        let found = self
+            .slots(&member)
+            .iter()
+            .fold(Choice::from(0), |found, slot| found | slot.ct_eq(&member));
+        Ok(found.into())
+    }
+
+    fn insert(&mut self, member: BigInteger) -> Result<bool, AccumulatorError> {
+        let bytes = member_bytes(&member)?;
+        if !self.members.insert(member)? {
+            return Ok(false);
+        }
+        self.index(bytes)?;
+        Ok(true)
+    }
+
+    fn remove(&mut self, member: &BigInteger) -> Result<bool, AccumulatorError> {
+        if !self.members.remove(member)? {
+            return Ok(false);
+        }
+        let bytes = member_bytes(member)?;
+        let start = Self::bucket(&self.key, self.slots.len(), &bytes);
+        for slot in self.slots[start..(start + Self::BUCKET_SLOTS)].iter_mut() {
+            if *slot == bytes {
+                *slot = [0u8; crate::MEMBER_SIZE];
+            }
+        }
+        Ok(true)
+    }
+
+    fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    fn iter(&self) -> MemberIter<'_> {
+        self.members.iter()
+    }
+
+    fn iter_after(&self, after: &BigInteger) -> MemberIter<'_> {
+        self.members.iter_after(after)
+    }
+
+    fn product_hint(&self) -> Option<BigInteger> {
+        self.members.product_hint()
+    }
+
+    fn insert_preimage(&mut self, member: BigInteger, preimage: &[u8]) -> Result<bool, AccumulatorError> {
+        let bytes = member_bytes(&member)?;
+        if !self.members.insert_preimage(member, preimage)? {
+            return Ok(false);
+        }
+        self.index(bytes)?;
+        Ok(true)
+    }
+
+    fn preimage(&self, member: &BigInteger) -> Result<Option<Vec<u8>>, AccumulatorError> {
+        self.members.preimage(member)
+    }
+
+    fn batch_applied(&mut self) -> Result<(), AccumulatorError> {
+        self.members.batch_applied()
+    }
+}
+
+/// `member` as `crate::MEMBER_SIZE` big-endian bytes
+#[cfg(feature = "ct-store")]
+fn member_bytes(member: &BigInteger) -> Result<[u8; crate::MEMBER_SIZE], AccumulatorError> {
+    let b = crate::b2fa(member, crate::MEMBER_SIZE)?;
+    Ok(*array_ref![b, 0, crate::MEMBER_SIZE])
+}
+
+/// A persistent set that shares its structure between clones, so
+/// `insert` and `remove`, which return a changed copy, don't copy every
+/// member. Use it as `Accumulator<im::OrdSet<BigInteger>>`
+#[cfg(feature = "persistent")]
+impl MemberStore for im::OrdSet<BigInteger> {
+    fn contains(&self, member: &BigInteger) -> Result<bool, AccumulatorError> {
+        Ok(im::OrdSet::contains(self, member))
+    }
+
+    fn insert(&mut self, member: BigInteger) -> Result<bool, AccumulatorError> {
+        Ok(im::OrdSet::insert(self, member).is_none())
+    }
+
+    fn remove(&mut self, member: &BigInteger) -> Result<bool, AccumulatorError> {
+        Ok(im::OrdSet::remove(self, member).is_some())
+    }
+
+    fn len(&self) -> usize {
+        im::OrdSet::len(self)
+    }
+
+    fn iter(&self) -> MemberIter<'_> {
+        Box::new(im::OrdSet::iter(self).cloned().map(Ok))
+    }
+
+    fn iter_after(&self, after: &BigInteger) -> MemberIter<'_> {
+        Box::new(self.range((Bound::Excluded(after), Bound::Unbounded)).cloned().map(Ok))
+    }
+}
+
+/// The product of `values`, reduced mod `modulus` if given, computed as a
+/// balanced tree so each multiplication is between numbers of about the same
+/// size, which is much faster than a running product for large sets. Products
+/// are only reduced once they outgrow the modulus. Halves are computed in parallel
+pub(crate) fn product_tree(values: &[BigInteger], modulus: Option<&BigInteger>) -> BigInteger {
+    // 8 hashed members are about the size of the modulus
+    const LEAF_SIZE: usize = 8;
+
+    let reduce = |v: BigInteger| match modulus {
+        Some(m) if v.bits() > m.bits() => &v % m,
+        _ => v,
+    };
+    if values.len() <= LEAF_SIZE {
+        return reduce(values.iter().product());
+    }
+    let (left, right) = values.split_at(values.len() / 2);
+    let (left, right) = join(|| product_tree(left, modulus), || product_tree(right, modulus));
+    reduce(&left * &right)
+}
+
+/// The product of every member of `store` except `except`
+pub(crate) fn product<S: MemberStore + ?Sized>(
+    store: &S,
+    except: Option<&BigInteger>,
+) -> Result<BigInteger, AccumulatorError> {
+    if let Some(p) = store.product_hint() {
+        return Ok(match except {
+            Some(x) if store.contains(x)? => p.div_rem(x).0,
+            _ => p,
+        });
+    }
+    let mut p = BigInteger::from(1u32);
+    for m in store.iter() {
+        let m = m?;
+        if Some(&m) != except {
+            p *= &m;
+        }
+    }
+    Ok(p)
+}
+
+/// Members kept in a sled tree. Keys are the members as `MEMBER_SIZE`
+/// big-endian bytes so the tree's order is the numeric order
+#[cfg(feature = "sled-store")]
+#[derive(Debug)]
+pub struct SledStore {
+    tree: sled::Tree,
+    len: usize,
+}
+
+#[cfg(feature = "sled-store")]
+impl SledStore {
+    /// Keep members in `tree`. Counts the members already
+    /// in the tree which reads every key once
+    pub fn new(tree: sled::Tree) -> Self {
+        let len = tree.len();
+        Self { tree, len }
+    }
+
+    /// The underlying tree
+    pub fn tree(&self) -> &sled::Tree {
+        &self.tree
+    }
+
+    /// Write pending changes to disk
+    pub fn flush(&self) -> Result<(), AccumulatorError> {
+        self.tree.flush().map_err(sled_error)?;
+        Ok(())
+    }
+
+    fn key(member: &BigInteger) -> Result<Vec<u8>, AccumulatorError> {
+        crate::b2fa(member, crate::MEMBER_SIZE)
+    }
+}
+
+#[cfg(feature = "sled-store")]
+impl MemberStore for SledStore {
+    fn contains(&self, member: &BigInteger) -> Result<bool, AccumulatorError> {
+        self.tree.contains_key(Self::key(member)?).map_err(sled_error)
+    }
+
+    fn insert(&mut self, member: BigInteger) -> Result<bool, AccumulatorError> {
+        let added = self.tree.insert(Self::key(&member)?, &b""[..]).map_err(sled_error)?.is_none();
+        if added {
+            self.len += 1;
+        }
+        Ok(added)
+    }
+
+    fn remove(&mut self, member: &BigInteger) -> Result<bool, AccumulatorError> {
+        let removed = self.tree.remove(Self::key(member)?).map_err(sled_error)?.is_some();
+        if removed {
+            self.len -= 1;
+        }
+        Ok(removed)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn iter(&self) -> MemberIter<'_> {
+        Box::new(self.tree.iter().keys().map(|k| {
+            let k = k.map_err(sled_error)?;
+            core::convert::TryFrom::try_from(&k[..])
+        }))
+    }
+
+    fn iter_after(&self, after: &BigInteger) -> MemberIter<'_> {
+        let key = match Self::key(after) {
+            Ok(key) => key,
+            // Larger than every member
+            Err(_) => return Box::new(core::iter::empty()),
+        };
+        Box::new(self.tree.range((Bound::Excluded(key), Bound::Unbounded)).keys().map(|k| {
+            let k = k.map_err(sled_error)?;
+            core::convert::TryFrom::try_from(&k[..])
+        }))
+    }
+}
+
+#[cfg(feature = "sled-store")]
+fn sled_error(e: sled::Error) -> AccumulatorError {
+    AccumulatorError::from_source(common::error::AccumulatorErrorKind::StorageError, e)
+}
+
+/// Members kept in a memory-mapped file as sorted `MEMBER_SIZE` byte
+/// big-endian records, for sets larger than memory. Lookups are a binary
+/// search and iterating streams the records in order. Inserting or removing
+/// one member moves every record after it, load many with `extend_sorted`.
+///
+/// The file must not be changed by anything else while it's open
+#[cfg(feature = "mmap-store")]
+#[derive(Debug)]
+pub struct MmapStore {
+    file: std::fs::File,
+    map: Option<memmap2::MmapMut>,
+}
+
+#[cfg(feature = "mmap-store")]
+impl MmapStore {
+    /// Open or create the store at `path`
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self, AccumulatorError> {
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .map_err(io_error)?;
+        let mut store = Self { file, map: None };
+        if store.file_len()? % crate::MEMBER_SIZE as u64 != 0 {
+            return Err(AccumulatorError::from_msg(
+                common::error::AccumulatorErrorKind::StorageError,
+                "file is not a list of members",
+            ));
+        }
+        store.remap()?;
+        Ok(store)
+    }
+
+    /// Append `members` which must be in ascending order
+    /// and greater than every member already in the store
+    pub fn extend_sorted<I>(&mut self, members: I) -> Result<(), AccumulatorError>
+    where
+        I: IntoIterator<Item = BigInteger>,
+    {
+        use std::io::{Seek, SeekFrom, Write};
+
+        let mut last = self.records().last().map(|r| r.to_vec());
+        let mut out = std::io::BufWriter::new(&self.file);
+        out.seek(SeekFrom::End(0)).map_err(io_error)?;
+        for m in members {
+            let record = crate::b2fa(&m, crate::MEMBER_SIZE)?;
+            if last.as_ref().is_some_and(|l| l >= &record) {
+                return Err(AccumulatorError::from_msg(
+                    common::error::AccumulatorErrorKind::InvalidMemberSupplied,
+                    "members are not ascending",
+                ));
+            }
+            out.write_all(&record).map_err(io_error)?;
+            last = Some(record);
+        }
+        out.flush().map_err(io_error)?;
+        drop(out);
+        self.remap()
+    }
+
+    /// Write pending changes to disk
+    pub fn flush(&self) -> Result<(), AccumulatorError> {
+        match &self.map {
+            Some(map) => map.flush().map_err(io_error),
+            None => Ok(()),
+        }
+    }
+
+    fn records(&self) -> std::slice::ChunksExact<'_, u8> {
+        self.map.as_deref().unwrap_or(&[]).chunks_exact(crate::MEMBER_SIZE)
+    }
+
+    /// The index of `record` or where it would be inserted
+    fn search(&self, record: &[u8]) -> Result<usize, usize> {
+        let map = self.map.as_deref().unwrap_or(&[]);
+        let (mut lo, mut hi) = (0, map.len() / crate::MEMBER_SIZE);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            let start = mid * crate::MEMBER_SIZE;
+            match map[start..start + crate::MEMBER_SIZE].cmp(record) {
+                core::cmp::Ordering::Less => lo = mid + 1,
+                core::cmp::Ordering::Greater => hi = mid,
+                core::cmp::Ordering::Equal => return Ok(mid),
+            }
+        }
+        Err(lo)
+    }
+
+    fn file_len(&self) -> Result<u64, AccumulatorError> {
+        Ok(self.file.metadata().map_err(io_error)?.len())
+    }
+
+    fn resize(&mut self, len: u64) -> Result<(), AccumulatorError> {
+        self.map = None;
+        self.file.set_len(len).map_err(io_error)?;
+        self.remap()
+    }
+
+    #[allow(unsafe_code)]
+    fn remap(&mut self) -> Result<(), AccumulatorError> {
+        self.map = None;
+        if self.file_len()? == 0 {
+            return Ok(());
+        }
+        // Safe as long as nothing else changes the file while it's mapped
+        let map = unsafe { memmap2::MmapMut::map_mut(&self.file) }.map_err(io_error)?;
+        self.map = Some(map);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "mmap-store")]
+impl MemberStore for MmapStore {
+    fn contains(&self, member: &BigInteger) -> Result<bool, AccumulatorError> {
+        match crate::b2fa(member, crate::MEMBER_SIZE) {
+            Ok(record) => Ok(self.search(&record).is_ok()),
+            // Too large to be a member
+            Err(_) => Ok(false),
+        }
+    }
+
+    fn insert(&mut self, member: BigInteger) -> Result<bool, AccumulatorError> {
+        let record = crate::b2fa(&member, crate::MEMBER_SIZE)?;
+        let index = match self.search(&record) {
+            Ok(_) => return Ok(false),
+            Err(i) => i * crate::MEMBER_SIZE,
+        };
+        let len = self.file_len()? as usize;
+        self.resize((len + crate::MEMBER_SIZE) as u64)?;
+        let map = self.map.as_mut().expect("the file isn't empty");
+        map.copy_within(index..len, index + crate::MEMBER_SIZE);
+        map[index..index + crate::MEMBER_SIZE].copy_from_slice(&record);
+        Ok(true)
+    }
+
+    fn remove(&mut self, member: &BigInteger) -> Result<bool, AccumulatorError> {
+        let record = match crate::b2fa(member, crate::MEMBER_SIZE) {
+            Ok(record) => record,
+            Err(_) => return Ok(false),
+        };
+        let index = match self.search(&record) {
+            Ok(i) => i * crate::MEMBER_SIZE,
+            Err(_) => return Ok(false),
+        };
+        let len = self.file_len()? as usize;
+        let map = self.map.as_mut().expect("the file isn't empty");
+        map.copy_within(index + crate::MEMBER_SIZE..len, index);
+        self.resize((len - crate::MEMBER_SIZE) as u64)?;
+        Ok(true)
+    }
+
+    fn len(&self) -> usize {
+        self.map.as_ref().map_or(0, |m| m.len() / crate::MEMBER_SIZE)
+    }
+
+    fn iter(&self) -> MemberIter<'_> {
+        Box::new(self.records().map(core::convert::TryFrom::try_from))
+    }
+
+    fn iter_after(&self, after: &BigInteger) -> MemberIter<'_> {
+        let start = match crate::b2fa(after, crate::MEMBER_SIZE) {
+            Ok(record) => match self.search(&record) {
+                Ok(i) => i + 1,
+                Err(i) => i,
+            },
+            // Larger than every member
+            Err(_) => self.len(),
+        };
+        let map = self.map.as_deref().unwrap_or(&[]);
+        Box::new(map[start * crate::MEMBER_SIZE..].chunks_exact(crate::MEMBER_SIZE).map(core::convert::TryFrom::try_from))
+    }
+}
+
+#[cfg(feature = "mmap-store")]
+fn io_error(e: std::io::Error) -> AccumulatorError {
+    AccumulatorError::from_source(common::error::AccumulatorErrorKind::StorageError, e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::hash_to_prime;
+
+    /// Check `iter_after` matches filtering `iter` for members, values
+    /// between them and values past either end
+    fn check_iter_after<S: MemberStore>(store: &S) {
+        let members: Vec<BigInteger> = store.iter().map(|m| m.unwrap()).collect();
+        let one = BigInteger::from(1u32);
+        let mut points = vec![BigInteger::default(), crate::pow2(crate::MEMBER_SIZE * 8 + 8).unwrap()];
+        for m in &members {
+            points.push(m - &one);
+            points.push(m.clone());
+            points.push(m + &one);
+        }
+        for after in &points {
+            let expected: Vec<&BigInteger> = members.iter().filter(|m| *m > after).collect();
+            let found: Vec<BigInteger> = store.iter_after(after).map(|m| m.unwrap()).collect();
+            assert_eq!(found.iter().collect::<Vec<_>>(), expected);
+        }
+    }
+
+    #[test]
+    fn iter_after() {
+        let set: BTreeSet<BigInteger> = (0..10u64).map(|i| hash_to_prime(i.to_be_bytes())).collect();
+        check_iter_after(&set);
+        check_iter_after(&BTreeSet::<BigInteger>::new());
+        check_iter_after(&PreimageStore::new(set.clone()));
+        #[cfg(feature = "persistent")]
+        check_iter_after(&set.iter().cloned().collect::<im::OrdSet<BigInteger>>());
+    }
+
+    #[test]
+    fn btree_product() {
+        let set: BTreeSet<BigInteger> = [3u32, 5, 7].iter().map(|p| BigInteger::from(*p)).collect();
+        assert_eq!(product(&set, None).unwrap(), BigInteger::from(105u32));
+        assert_eq!(product(&set, Some(&BigInteger::from(5u32))).unwrap(), BigInteger::from(21u32));
+        assert_eq!(product(&set, Some(&BigInteger::from(11u32))).unwrap(), BigInteger::from(105u32));
+        let members: Vec<BigInteger> = MemberStore::iter(&set).collect::<Result<_, _>>().unwrap();
+        assert_eq!(members, set.iter().cloned().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn product_tree_matches_fold() {
+        let totient = crate::key::AccumulatorSecretKey::default().totient();
+        let values: Vec<BigInteger> = (0..100u64).map(|i| hash_to_prime(i.to_be_bytes())).collect();
+        for n in &[0, 1, 8, 9, 100] {
+            let expected = values[..*n]
+                .iter()
+                .fold(BigInteger::from(1u32), |a, b| a.mod_mul(b, &totient));
+            assert_eq!(&product_tree(&values[..*n], Some(&totient)) % &totient, expected);
+            assert_eq!(product_tree(&values[..*n], None), values[..*n].iter().product());
+        }
+    }
+
+    #[test]
+    fn preimages() {
+        use crate::{accumulator::Accumulator, key::AccumulatorSecretKey};
+
+        let key = AccumulatorSecretKey::default();
+        let mut acc = Accumulator::with_store(&key, PreimageStore::new(BTreeSet::new())).unwrap();
+        acc.insert_assign("alice").unwrap();
+        acc.insert_assign("bob").unwrap();
+        let carol = hash_to_prime("carol");
+        acc.insert_prime_assign(&carol).unwrap();
+
+        let alice = hash_to_prime("alice");
+        assert_eq!(acc.preimage(&alice).unwrap(), Some(b"alice".to_vec()));
+        assert_eq!(acc.preimage(&carol).unwrap(), None);
+        assert_eq!(acc.members.find(b"bob").unwrap(), Some(hash_to_prime("bob")));
+        assert_eq!(acc.members.preimages().count(), 2);
+
+        acc.remove_assign(&key, "alice").unwrap();
+        assert_eq!(acc.preimage(&alice).unwrap(), None);
+        assert_eq!(acc.members.find(b"alice").unwrap(), None);
+        let mut expected = Accumulator::with_members(&key, ["bob"]).unwrap();
+        expected.insert_prime_assign(&carol).unwrap();
+        assert_eq!(acc.value, expected.value);
+    }
+
+    #[test]
+    fn bloom_filter() {
+        use crate::{accumulator::Accumulator, key::AccumulatorSecretKey};
+
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<BigInteger> = (0..100u64).map(|m| hash_to_prime(m.to_be_bytes())).collect();
+        let mut store = BloomStore::new(BTreeSet::new()).unwrap();
+        for m in &members {
+            assert!(store.insert(m.clone()).unwrap());
+        }
+        assert!(!store.insert(members[0].clone()).unwrap());
+        assert!(store.capacity >= members.len());
+        assert!(members.iter().all(|m| store.might_contain(m) && store.contains(m).unwrap()));
+        let others: Vec<BigInteger> = (100..1100u64).map(|m| hash_to_prime(m.to_be_bytes())).collect();
+        assert!(others.iter().filter(|m| store.might_contain(m)).count() < 50);
+        assert!(others.iter().all(|m| !store.contains(m).unwrap()));
+
+        // Removed members pass the filter but aren't found
+        assert!(store.remove(&members[0]).unwrap());
+        assert!(!store.remove(&members[0]).unwrap());
+        assert!(store.might_contain(&members[0]));
+        assert!(!store.contains(&members[0]).unwrap());
+
+        let mut acc = Accumulator::with_store(&key, BloomStore::new(store.into_inner()).unwrap()).unwrap();
+        acc.update_batch_assign(&key, &others[..10], &members[1..50]).unwrap();
+        assert_eq!(acc.members.stale, 0);
+        assert!(!acc.members.might_contain(&members[1]));
+        assert!(acc.contains_prime(&others[0]).unwrap());
+        let mut expected: BTreeSet<BigInteger> = members[50..].iter().cloned().collect();
+        expected.extend(others[..10].iter().cloned());
+        assert_eq!(acc.members.into_inner(), expected);
+    }
+
+    #[cfg(feature = "ct-store")]
+    #[test]
+    fn constant_time() {
+        use crate::{accumulator::Accumulator, key::AccumulatorSecretKey};
+
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = (0..200u64).map(|m| m.to_be_bytes()).collect();
+        let mut store = ConstantTimeStore::new(BTreeSet::new(), [7u8; 32]).unwrap();
+        assert!(!format!("{:?}", store).contains(&format!("{:?}", [7u8; 32])));
+        for m in &members {
+            assert!(store.insert(hash_to_prime(m)).unwrap());
+        }
+        assert!(!store.insert(hash_to_prime(members[0])).unwrap());
+        assert!(members.iter().all(|m| store.contains(&hash_to_prime(m)).unwrap()));
+        assert!(!store.contains(&hash_to_prime(500u64.to_be_bytes())).unwrap());
+        let too_large: BigInteger = core::convert::TryFrom::try_from(&[0xffu8; 40][..]).unwrap();
+        assert!(!store.contains(&too_large).unwrap());
+
+        let mut acc = Accumulator::with_store(&key, store).unwrap();
+        acc.remove_assign(&key, members[3]).unwrap();
+        assert!(!acc.contains(members[3]).unwrap());
+        assert!(acc.contains(members[4]).unwrap());
+        let expected = Accumulator::with_members(&key, [&members[..3], &members[4..]].concat()).unwrap();
+        assert_eq!(acc.value, expected.value);
+
+        let reindexed = ConstantTimeStore::new(acc.members.into_inner(), [8u8; 32]).unwrap();
+        assert!(reindexed.contains(&hash_to_prime(members[4])).unwrap());
+        assert_eq!(reindexed.len(), 199);
+    }
+
+    #[cfg(feature = "persistent")]
+    #[test]
+    fn persistent() {
+        use crate::{accumulator::Accumulator, key::AccumulatorSecretKey};
+
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = vec![3u64.to_be_bytes(), 7u64.to_be_bytes(), 11u64.to_be_bytes()];
+        let store: im::OrdSet<BigInteger> = members.iter().map(hash_to_prime).collect();
+        let acc = Accumulator::with_store(&key, store).unwrap();
+        let expected = Accumulator::with_members(&key, &members).unwrap();
+        assert_eq!(acc.value, expected.value);
+
+        let next = acc.insert(13u64.to_be_bytes()).unwrap();
+        assert_eq!(next.value, expected.insert(13u64.to_be_bytes()).unwrap().value);
+        assert!(std::sync::Arc::ptr_eq(&next.modulus, &acc.modulus));
+        assert_eq!(acc.members.len(), 3);
+        assert_eq!(next.members.len(), 4);
+        let next = next.remove(&key, members[0]).unwrap();
+        assert!(!next.members.contains(&hash_to_prime(members[0])));
+        assert!(acc.members.contains(&hash_to_prime(members[0])));
+    }
+
+    #[cfg(feature = "persistent")]
+    #[test]
+    fn persistent_matches_btree() {
+        let mut set = im::OrdSet::new();
+        let mut btree = BTreeSet::new();
+        // Insert and remove an overlapping mix so both hits and misses happen
+        for i in 0..200u64 {
+            let m = hash_to_prime((i % 70).to_be_bytes());
+            if i % 3 == 2 {
+                assert_eq!(MemberStore::remove(&mut set, &m).unwrap(), MemberStore::remove(&mut btree, &m).unwrap());
+            } else {
+                assert_eq!(MemberStore::insert(&mut set, m.clone()).unwrap(), MemberStore::insert(&mut btree, m).unwrap());
+            }
+            assert_eq!(MemberStore::len(&set), MemberStore::len(&btree));
+        }
+        let a: Vec<BigInteger> = MemberStore::iter(&set).map(|m| m.unwrap()).collect();
+        let b: Vec<BigInteger> = MemberStore::iter(&btree).map(|m| m.unwrap()).collect();
+        assert_eq!(a, b);
+
+        // A clone is the same tree until one of them changes
+        let mut copy = set.clone();
+        assert!(copy.ptr_eq(&set));
+        let m = hash_to_prime(1000u64.to_be_bytes());
+        assert!(MemberStore::insert(&mut copy, m.clone()).unwrap());
+        assert!(!copy.ptr_eq(&set));
+        assert!(!MemberStore::contains(&set, &m).unwrap());
+        assert_eq!(MemberStore::len(&copy), MemberStore::len(&set) + 1);
+    }
+
+    #[cfg(feature = "sled-store")]
+    #[test]
+    fn sled() {
+        use crate::{accumulator::Accumulator, key::AccumulatorSecretKey, memwitness::MembershipWitness};
+
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = vec![3u64.to_be_bytes(), 7u64.to_be_bytes(), 11u64.to_be_bytes()];
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let mut store = SledStore::new(db.open_tree("members").unwrap());
+        for m in &members {
+            assert!(store.insert(hash_to_prime(m)).unwrap());
+        }
+        assert!(!store.insert(hash_to_prime(members[0])).unwrap());
+        assert_eq!(store.len(), members.len());
+
+        let mut acc = Accumulator::with_store(&key, store).unwrap();
+        let mut expected = Accumulator::with_members(&key, &members).unwrap();
+        assert_eq!(acc.value, expected.value);
+
+        acc.insert_assign(13u64.to_be_bytes()).unwrap();
+        expected.insert_assign(13u64.to_be_bytes()).unwrap();
+        acc.remove_assign(&key, members[1]).unwrap();
+        expected.remove_assign(&key, members[1]).unwrap();
+        assert!(acc.insert_assign(13u64.to_be_bytes()).is_err());
+        assert_eq!(acc.to_bytes().unwrap(), expected.to_bytes().unwrap());
+        assert_eq!(
+            MembershipWitness::new(&acc, members[0]).unwrap(),
+            MembershipWitness::new(&expected, members[0]).unwrap()
+        );
+
+        let reopened = SledStore::new(acc.members.tree().clone());
+        assert_eq!(reopened.len(), 3);
+        check_iter_after(&reopened);
+    }
+
+    #[cfg(feature = "mmap-store")]
+    #[test]
+    fn mmap() {
+        use crate::{accumulator::Accumulator, key::AccumulatorSecretKey, memwitness::MembershipWitness};
+
+        let path = std::env::temp_dir().join(format!("mmap-store-{}", std::process::id()));
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = (0..20u64).map(|m| m.to_be_bytes()).collect();
+        let mut primes: Vec<BigInteger> = members.iter().map(hash_to_prime).collect();
+        primes.sort();
+
+        let mut store = MmapStore::open(&path).unwrap();
+        assert!(store.is_empty());
+        store.extend_sorted(primes[..10].iter().cloned()).unwrap();
+        assert!(store.extend_sorted(vec![primes[0].clone()]).is_err());
+        for p in primes[10..].iter().rev() {
+            assert!(store.insert(p.clone()).unwrap());
+        }
+        assert!(!store.insert(primes[3].clone()).unwrap());
+        assert_eq!(store.len(), members.len());
+        assert!(store.contains(&primes[7]).unwrap());
+        assert!(!store.contains(&hash_to_prime(b"absent")).unwrap());
+
+        let mut acc = Accumulator::with_store(&key, store).unwrap();
+        let mut expected = Accumulator::with_members(&key, &members).unwrap();
+        assert_eq!(acc.value, expected.value);
+        acc.remove_assign(&key, members[1]).unwrap();
+        expected.remove_assign(&key, members[1]).unwrap();
+        acc.insert_assign(b"new").unwrap();
+        expected.insert_assign(b"new").unwrap();
+        assert_eq!(acc.to_bytes().unwrap(), expected.to_bytes().unwrap());
+        assert_eq!(
+            MembershipWitness::new(&acc, members[0]).unwrap(),
+            MembershipWitness::new(&expected, members[0]).unwrap()
+        );
+        acc.members.flush().unwrap();
+        drop(acc);
+
+        let reopened = MmapStore::open(&path).unwrap();
+        assert_eq!(reopened.len(), members.len());
+        check_iter_after(&reopened);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,378 @@
+// Tails files hold the witness update factors of many epochs in one file
+// holders download once and read offline, like the tails files of
+// Hyperledger Indy. Entry i moves a witness from epoch `first_epoch + i`
+// to the next with the accumulator value after it and the products of the
+// members added and removed. An index of entry offsets follows the header
+// so a holder seeks straight to the epoch its witness is at. The file
+// isn't authenticated, updated witnesses are checked against a definition
+// the holder got from the issuer.
+//
+// Layout: header, first epoch (u64), entry count (u32), one u64 offset
+// from the start of the file per entry, then the entries. Each entry is
+// the value followed by the two products, each prefixed with its length
+// as a u32
+use crate::{
+    accumulator::AccumulatorPublic,
+    b2fa,
+    format::{Header, Tag, HEADER_SIZE},
+    memwitness::MembershipWitness,
+    registry::RegistryDelta,
+    FACTOR_SIZE,
+};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+};
+use std::{
+    convert::TryFrom,
+    io::{Read, Seek, SeekFrom, Write},
+};
+
+/// The witness update factors of one epoch
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TailsEntry {
+    /// The accumulator value after the epoch
+    pub value: BigInteger,
+    /// The product of the member primes added, one if there are none
+    pub additions: BigInteger,
+    /// The product of the member primes removed, one if there are none
+    pub deletions: BigInteger,
+}
+
+impl TailsEntry {
+    /// The factors of `delta`
+    pub fn new(delta: &RegistryDelta) -> Self {
+        Self {
+            value: delta.value.clone(),
+            additions: delta.additions.iter().product(),
+            deletions: delta.deletions.iter().product(),
+        }
+    }
+
+    /// Update `witness` for `definition` to `next`, the definition the
+    /// issuer published for the epoch after this entry. Fails if the member
+    /// was removed or added in this epoch, or the result isn't a witness
+    /// for `next`
+    pub fn update_witness_assign(
+        &self,
+        witness: &mut MembershipWitness,
+        definition: &AccumulatorPublic,
+        next: &AccumulatorPublic,
+    ) -> Result<(), AccumulatorError> {
+        if next.epoch != definition.epoch + 1 {
+            return Err(AccumulatorErrorKind::EpochMismatch {
+                expected: definition.epoch + 1,
+                actual: next.epoch,
+            }
+            .into());
+        }
+        if next.modulus != definition.modulus || next.generator != definition.generator {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::ParameterMismatch,
+                "definitions have different parameters",
+            ));
+        }
+        let zero = BigInteger::default();
+        if &self.deletions % &witness.x == zero {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "member was removed in this epoch",
+            ));
+        }
+        if &self.additions % &witness.x == zero {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidMemberSupplied,
+                "witness was created after this epoch",
+            ));
+        }
+        let one = BigInteger::from(1u32);
+        let factor = |p| if p == &one { Vec::new() } else { vec![p] };
+        let mut updated = witness.clone();
+        updated.apply_changes(
+            factor(&self.additions),
+            factor(&self.deletions),
+            next,
+        );
+        // The file isn't authenticated, check the factors gave a witness
+        if self.value != next.value || updated.u.mod_exp(&updated.x, &next.modulus) != next.value {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::ProofVerificationFailed,
+                "tails entry does not match the accumulator",
+            ));
+        }
+        *witness = updated;
+        Ok(())
+    }
+
+    /// The definition after this entry if its value is taken from the file
+    fn next(&self, definition: &AccumulatorPublic) -> AccumulatorPublic {
+        AccumulatorPublic {
+            value: self.value.clone(),
+            epoch: definition.epoch + 1,
+            ..definition.clone()
+        }
+    }
+
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<(), AccumulatorError> {
+        w.write_all(&b2fa(&self.value, FACTOR_SIZE * 2)?).map_err(io_error)?;
+        for p in &[&self.additions, &self.deletions] {
+            let bytes = p.to_bytes();
+            w.write_all(&(bytes.len() as u32).to_be_bytes()).map_err(io_error)?;
+            w.write_all(&bytes).map_err(io_error)?;
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        FACTOR_SIZE * 2 + 8 + self.additions.to_bytes().len() + self.deletions.to_bytes().len()
+    }
+}
+
+/// Builds a tails file on the issuer side from published deltas
+#[derive(Debug, Clone)]
+pub struct TailsWriter {
+    first_epoch: u64,
+    entries: Vec<TailsEntry>,
+}
+
+impl TailsWriter {
+    /// A file starting with the delta from `first_epoch`
+    pub fn new(first_epoch: u64) -> Self {
+        Self {
+            first_epoch,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Add the delta for the next epoch
+    pub fn push(&mut self, delta: &RegistryDelta) -> Result<(), AccumulatorError> {
+        let expected = self.first_epoch + self.entries.len() as u64;
+        if delta.from_epoch != expected || delta.to_epoch != expected + 1 {
+            return Err(AccumulatorErrorKind::EpochMismatch {
+                expected,
+                actual: delta.from_epoch,
+            }
+            .into());
+        }
+        self.entries.push(TailsEntry::new(delta));
+        Ok(())
+    }
+
+    /// Write the file to `w`
+    pub fn write_to<W: Write>(&self, w: &mut W) -> Result<(), AccumulatorError> {
+        w.write_all(&Header::new(Tag::TailsFile).to_bytes()).map_err(io_error)?;
+        w.write_all(&self.first_epoch.to_be_bytes()).map_err(io_error)?;
+        w.write_all(&(self.entries.len() as u32).to_be_bytes()).map_err(io_error)?;
+        let mut offset = (HEADER_SIZE + 12 + self.entries.len() * 8) as u64;
+        for e in &self.entries {
+            w.write_all(&offset.to_be_bytes()).map_err(io_error)?;
+            offset += e.size() as u64;
+        }
+        for e in &self.entries {
+            e.write_to(w)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads entries of a tails file on the holder side without loading it
+#[derive(Debug)]
+pub struct TailsReader<R> {
+    reader: R,
+    first_epoch: u64,
+    offsets: Vec<u64>,
+    len: u64,
+}
+
+impl<R: Read + Seek> TailsReader<R> {
+    /// Read the header and index of the file in `reader`
+    pub fn new(mut reader: R) -> Result<Self, AccumulatorError> {
+        let len = reader.seek(SeekFrom::End(0)).map_err(io_error)?;
+        reader.seek(SeekFrom::Start(0)).map_err(io_error)?;
+        let mut header = [0u8; HEADER_SIZE + 12];
+        reader.read_exact(&mut header).map_err(io_error)?;
+        let fixed = Header::check(&header, Tag::TailsFile)?;
+        let first_epoch = u64::from_be_bytes(*array_ref![fixed, 0, 8]);
+        let count = u32::from_be_bytes(*array_ref![fixed, 8, 4]) as u64;
+        let entries = header.len() as u64 + count * 8;
+        if entries > len {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: entries as usize,
+                actual: len as usize,
+            }
+            .into());
+        }
+        let mut offsets = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let mut offset = [0u8; 8];
+            reader.read_exact(&mut offset).map_err(io_error)?;
+            offsets.push(u64::from_be_bytes(offset));
+        }
+        Ok(Self {
+            reader,
+            first_epoch,
+            offsets,
+            len,
+        })
+    }
+
+    /// The epoch the first entry updates from
+    pub fn first_epoch(&self) -> u64 {
+        self.first_epoch
+    }
+
+    /// The epoch after the last entry
+    pub fn last_epoch(&self) -> u64 {
+        self.first_epoch + self.offsets.len() as u64
+    }
+
+    /// The entry that updates witnesses from `epoch`
+    pub fn entry(&mut self, epoch: u64) -> Result<TailsEntry, AccumulatorError> {
+        if epoch < self.first_epoch || epoch >= self.last_epoch() {
+            return Err(AccumulatorErrorKind::EpochMismatch {
+                expected: self.first_epoch,
+                actual: epoch,
+            }
+            .into());
+        }
+        let offset = self.offsets[(epoch - self.first_epoch) as usize];
+        self.reader.seek(SeekFrom::Start(offset)).map_err(io_error)?;
+        let mut value = [0u8; FACTOR_SIZE * 2];
+        self.reader.read_exact(&mut value).map_err(io_error)?;
+        let additions = self.read_product()?;
+        let deletions = self.read_product()?;
+        Ok(TailsEntry {
+            value: BigInteger::try_from(&value[..])?,
+            additions,
+            deletions,
+        })
+    }
+
+    /// Update `witness` for `definition` to `target`, the definition the
+    /// issuer published for a later epoch in the file. The values of the
+    /// epochs in between come from the file, a wrong one leaves a witness
+    /// that fails the check against `target`
+    pub fn update_witness_assign(
+        &mut self,
+        witness: &mut MembershipWitness,
+        definition: &AccumulatorPublic,
+        target: &AccumulatorPublic,
+    ) -> Result<(), AccumulatorError> {
+        if target.epoch <= definition.epoch || target.epoch > self.last_epoch() {
+            return Err(AccumulatorErrorKind::EpochMismatch {
+                expected: self.last_epoch(),
+                actual: target.epoch,
+            }
+            .into());
+        }
+        let mut definition = definition.clone();
+        let mut updated = witness.clone();
+        while definition.epoch < target.epoch {
+            let entry = self.entry(definition.epoch)?;
+            let next = if definition.epoch + 1 == target.epoch {
+                target.clone()
+            } else {
+                entry.next(&definition)
+            };
+            entry.update_witness_assign(&mut updated, &definition, &next)?;
+            definition = next;
+        }
+        *witness = updated;
+        Ok(())
+    }
+
+    fn read_product(&mut self) -> Result<BigInteger, AccumulatorError> {
+        let mut size = [0u8; 4];
+        self.reader.read_exact(&mut size).map_err(io_error)?;
+        let size = u32::from_be_bytes(size) as u64;
+        let end = self.reader.stream_position().map_err(io_error)? + size;
+        if end > self.len {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: end as usize,
+                actual: self.len as usize,
+            }
+            .into());
+        }
+        let mut bytes = vec![0u8; size as usize];
+        self.reader.read_exact(&mut bytes).map_err(io_error)?;
+        BigInteger::try_from(bytes.as_slice())
+    }
+}
+
+fn io_error(e: std::io::Error) -> AccumulatorError {
+    AccumulatorError::from_source(AccumulatorErrorKind::SerializationError, e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hash::hash_to_prime, key::AccumulatorSecretKey, registry::RevocationRegistry};
+    use std::io::Cursor;
+
+    #[test]
+    fn updates_offline() {
+        let key = AccumulatorSecretKey::default();
+        let mut registry = RevocationRegistry::new(&key);
+        for h in ["a", "b", "c", "d"] {
+            registry.register(h).unwrap();
+        }
+        registry.publish(&key).unwrap();
+        let start = registry.definition();
+        let mut witness = registry.witness(&key, "a").unwrap();
+        let original = witness.clone();
+        let mut revoked = registry.witness(&key, "b").unwrap();
+
+        let mut writer = TailsWriter::new(start.epoch);
+        registry.register("e").unwrap();
+        registry.revoke("c").unwrap();
+        let first = registry.publish(&key).unwrap().unwrap().clone();
+        let next = registry.definition();
+        writer.push(&first).unwrap();
+        registry.revoke("b").unwrap();
+        writer.push(registry.publish(&key).unwrap().unwrap()).unwrap();
+        registry.register("f").unwrap();
+        let delta = registry.publish(&key).unwrap().unwrap().clone();
+        assert!(writer.push(&RegistryDelta { from_epoch: 0, ..delta.clone() }).is_err());
+        writer.push(&delta).unwrap();
+        let mut file = Vec::new();
+        writer.write_to(&mut file).unwrap();
+
+        let mut reader = TailsReader::new(Cursor::new(file.clone())).unwrap();
+        assert_eq!(reader.first_epoch(), start.epoch);
+        assert_eq!(reader.last_epoch(), registry.definition().epoch);
+        let target = registry.definition();
+        reader.update_witness_assign(&mut witness, &start, &target).unwrap();
+        assert_eq!(witness.u, registry.witness(&key, "a").unwrap().u);
+        assert!(reader.update_witness_assign(&mut revoked, &start, &target).is_err());
+        assert!(reader.update_witness_assign(&mut original.clone(), &start, &start).is_err());
+        reader.update_witness_assign(&mut original.clone(), &start, &next).unwrap();
+        assert!(reader.entry(reader.last_epoch()).is_err());
+
+        // A changed value is caught when it's applied
+        let mut bad = file.clone();
+        bad[HEADER_SIZE + 12 + 3 * 8 + 10] ^= 1;
+        let mut witness = original.clone();
+        let mut reader = TailsReader::new(Cursor::new(bad)).unwrap();
+        assert!(reader.entry(start.epoch).is_ok());
+        assert!(reader.update_witness_assign(&mut witness, &start, &target).is_err());
+        assert_eq!(witness, original);
+
+        // A file that is consistent with itself but not with the
+        // published definition is rejected
+        let mut forged = first.clone();
+        let x = hash_to_prime(b"x");
+        forged.value = forged.value.mod_exp(&x, &start.modulus);
+        forged.additions.push(x);
+        let mut writer = TailsWriter::new(start.epoch);
+        writer.push(&forged).unwrap();
+        let mut forged_file = Vec::new();
+        writer.write_to(&mut forged_file).unwrap();
+        let mut reader = TailsReader::new(Cursor::new(forged_file)).unwrap();
+        let entry = reader.entry(start.epoch).unwrap();
+        assert!(entry.update_witness_assign(&mut witness.clone(), &start, &entry.next(&start)).is_ok());
+        assert!(entry.update_witness_assign(&mut witness.clone(), &start, &next).is_err());
+        assert!(reader.update_witness_assign(&mut witness, &start, &next).is_err());
+        assert_eq!(witness, original);
+        assert!(TailsReader::new(Cursor::new(file[..30].to_vec())).is_err());
+    }
+}
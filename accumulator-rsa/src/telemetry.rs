@@ -0,0 +1,105 @@
+// Nothing is recorded unless the `metrics` feature is on, then the values
+// go to whatever recorder the application installed with the `metrics` crate
+
+/// Counter of members added to an accumulator
+pub const INSERTIONS: &str = "accumulator_insertions_total";
+/// Counter of members removed from an accumulator
+pub const REMOVALS: &str = "accumulator_removals_total";
+/// Counter of proofs checked, labeled with the `kind` of proof
+/// and whether it was `valid`
+pub const PROOFS_VERIFIED: &str = "accumulator_proofs_verified_total";
+/// Histogram of the seconds taken to check a proof, labeled with its `kind`
+pub const VERIFICATION_SECONDS: &str = "accumulator_verification_seconds";
+/// Histogram of the candidates hashed before a prime was found
+pub const HASH_TO_PRIME_ITERATIONS: &str = "accumulator_hash_to_prime_iterations";
+/// Counter of witnesses created by a revocation registry
+pub const WITNESSES_ISSUED: &str = "accumulator_witnesses_issued_total";
+/// Counter of deltas applied to a holder's witness
+pub const WITNESS_UPDATES: &str = "accumulator_witness_updates_total";
+
+pub(crate) fn count(name: &'static str, n: usize) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!(name).increment(n as u64);
+    #[cfg(not(feature = "metrics"))]
+    let _ = (name, n);
+}
+
+pub(crate) fn record(name: &'static str, value: usize) {
+    #[cfg(feature = "metrics")]
+    metrics::histogram!(name).record(value as f64);
+    #[cfg(not(feature = "metrics"))]
+    let _ = (name, value);
+}
+
+/// Run the check of a `kind` of proof, recording the result and how long it took
+pub(crate) fn verify<F: FnOnce() -> bool>(kind: &'static str, f: F) -> bool {
+    #[cfg(feature = "metrics")]
+    {
+        let start = std::time::Instant::now();
+        let valid = f();
+        metrics::histogram!(VERIFICATION_SECONDS, "kind" => kind).record(start.elapsed().as_secs_f64());
+        metrics::counter!(PROOFS_VERIFIED, "kind" => kind, "valid" => if valid { "true" } else { "false" })
+            .increment(1);
+        valid
+    }
+    #[cfg(not(feature = "metrics"))]
+    {
+        let _ = kind;
+        f()
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::*;
+    use crate::{
+        accumulator::Accumulator, key::AccumulatorSecretKey, memproof::MembershipProof,
+        memwitness::MembershipWitness,
+    };
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    #[test]
+    fn records_operations() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        metrics::with_local_recorder(&recorder, || {
+            let key = AccumulatorSecretKey::default();
+            let mut acc = Accumulator::new(&key);
+            acc.insert_assign("a").unwrap();
+            acc.insert_assign("b").unwrap();
+            acc.remove_assign(&key, "b").unwrap();
+            let witness = MembershipWitness::new(&acc, "a").unwrap();
+            let proof = MembershipProof::new(&witness, &acc, b"metrics").unwrap();
+            assert!(proof.verify(&acc, b"metrics"));
+            assert!(!proof.verify(&acc, b"other"));
+        });
+
+        let metrics = snapshotter.snapshot().into_vec();
+        let counter = |name: &str| -> u64 {
+            metrics
+                .iter()
+                .filter(|(k, ..)| k.key().name() == name)
+                .map(|(.., v)| match v {
+                    DebugValue::Counter(c) => *c,
+                    _ => 0,
+                })
+                .sum()
+        };
+        assert_eq!(counter(INSERTIONS), 2);
+        assert_eq!(counter(REMOVALS), 1);
+        assert_eq!(counter(PROOFS_VERIFIED), 2);
+        let invalid = metrics.iter().any(|(k, _, _, v)| {
+            k.key().name() == PROOFS_VERIFIED
+                && k.key().labels().any(|l| l.key() == "valid" && l.value() == "false")
+                && *v == DebugValue::Counter(1)
+        });
+        assert!(invalid);
+        let histogram = |name: &str| {
+            metrics.iter().any(|(k, _, _, v)| {
+                k.key().name() == name && matches!(v, DebugValue::Histogram(h) if !h.is_empty())
+            })
+        };
+        assert!(histogram(VERIFICATION_SECONDS));
+        assert!(histogram(HASH_TO_PRIME_ITERATIONS));
+    }
+}
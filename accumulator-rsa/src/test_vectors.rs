@@ -0,0 +1,317 @@
+use crate::{
+    accumulator::{Accumulator, AccumulatorPublic},
+    key::AccumulatorSecretKey,
+    memproof::MembershipProof,
+    memwitness::MembershipWitness,
+    nonmemproof::NonMembershipProof,
+    nonwitness::NonMembershipWitness,
+};
+use common::error::AccumulatorError;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+// Every vector is hex of the serialized value including its header. The
+// accumulator is built from `SECRET_KEY` and `MEMBERS`, the witnesses are
+// for `MEMBERS[0]` and `NON_MEMBER` and the proofs use `NONCE`.
+// Changing any serialization or proof transcript changes these.
+
+/// The accumulated members, big endian u64s
+pub const MEMBERS: [&str; 4] = [
+    "0000000000000003",
+    "0000000000000007",
+    "000000000000000b",
+    "000000000000000d",
+];
+
+/// `hash_to_prime` of each of `MEMBERS`
+pub const MEMBER_PRIMES: [&str; 4] = [
+    "d9f4a94d3dd169c772877b4b3ebdbdd09656e4ed9687fb4e61af8bb1b39a65cb",
+    "73810afd10ab55b6dc916fed2fd058134fae8ee3ea5a5ba4810ad704080c80a1",
+    "23fca2f5ce9f5caaf4262038ffce965e12185bb554613e383106a580eae48c89",
+    "75f59c44016d9e922fad13a300196b873de54bb8dbc98b958137cfc7ac571665",
+];
+
+/// A value that is not accumulated, a big endian u64
+pub const NON_MEMBER: &str = "0000000000000011";
+
+/// `hash_to_prime` of `NON_MEMBER`
+pub const NON_MEMBER_PRIME: &str = "5ab20c832bccd28bd4fc39ae8d2fa8ab92d1737d8fe01d5f7ce1e13fd72391a7";
+
+/// The nonce of both proofs, "test_vectors"
+pub const NONCE: &str = "746573745f766563746f7273";
+
+/// The secret key. It is public, never use it outside of tests
+pub const SECRET_KEY: &str = "5253414101020800010001bcd09939e9debf6aace2b64279731bdd848cd08d03\
+    a4944fa02d78e4edd0bfa5174a3ef8d02a3b33fdcb5ec53f7b4a0fe08531277a\
+    4641154b984feeecac0ac91732136215028e069d61b8dbe5f8b2d8980f91fe21\
+    d5b33afaa93de184de9409a3b36fa5139e81dd75acc74331f9bf96c71c6d9367\
+    7718d2267b1e270f3c6077d48b5981c2f269a15de5b71e2b6b861be5aae377a8\
+    421003040f3f1cef83386786f00383b08c1cddc21aada1eee438a3b7df928b28\
+    f1c8e9408f56d8a45bfa557bcddbb8529311485d4ec5f20db4d81e5282b5b817\
+    581694f644b75893a3308c0dab72593cbcd452391c6492fc8b29bb37d9436c19\
+    ed8fedc9c0b6c0138ed317";
+
+/// The accumulator with its members
+pub const ACCUMULATOR: &str = "5253414101010800010001747bb0822a1b0e37afcc5745a6ccd5b0ce2a1a1ce2\
+    9c4d5e2aee43f1626fe0c5b20263eade7ba88ef2142bfb00162eb6303b6dde33\
+    2d4980277bff263025115158463fefae2b098b86d606a9cae030427accfb6f3a\
+    9b4afba8730f9f75e4fa39bcc10d6f7209ea01db3d4e4c1a1f17d616b3f95d1b\
+    fa4688aa9d296642f0069a4376d0a404e4bc2f93e898d23a95d8c12482c4c367\
+    c496b5a4db715abf717aac895e6e3ac3e619c416fcb8f2132d5b0cca7cf1878f\
+    3b5efa1a7d2b605f6a8711e95838d555433b17b9f31d59452738a7c0ef3b5c2b\
+    5ef8ecb4f96c46129fdcad3008f07bb33c585ee814e9e3694ac48232dec86b8e\
+    3b0b5194533b92ebd5f56e74350170519fd776be4891dc379e8044c49266e291\
+    545611c1b7b7db70a91db0a3dd46fca597f6354ebe256a60a071e96410db7484\
+    2aba6e8d9de60bff2d36e60d581acc519bb49a1a99b2dfe31fbefbda7443f70c\
+    b841069f395131d46aabe02460aca54ff3e1ba36fcfab556c60fab1a7081ca44\
+    a671646ec1786606c33f5fa68d4fdc172fd2da26bfc6aaad33e57a63353a1f30\
+    5185c3f3a84d149b0a96e0726ade46d89d2ea23d1cd4ba61330c278f3c5c5e21\
+    29100f3dab4013a40a30b97d699f303ba2275c062fa05b441ecf997de5238c0a\
+    ce5ff50bb78522a29ad96b8b4650836d713596cd4a05c8edaa3954faa65861fb\
+    5560dfe346a798ab4e66479cc3862b6249aa2adf265e2ea67f6a5354338aa80a\
+    113cbf58f03b8f7157d1b848fca17e545acbdde3d40cddf892ddf4caa6003084\
+    76def0bf6ac211157a0f042e23d84f29004675d68fc650529c560da177f1ce28\
+    3bc5e8655a6e7dc9a8f81c93f09009574d1ee3f84877ba2624b720251b619122\
+    3a863762f6ca0b48f1002dfd946f72d2b6390ad2c52f5ebc2325475c2b1a70b6\
+    ba38d182a8882263413703f3080aabe90f94cd6b6e618b90aadb62f3b19cba42\
+    9e6c72c72f1fcd70fdbd57b72dbbc5d22d4c8cf3ff99b068d14d8c9533416940\
+    ffc38786253c7013636c5d11c4229cad13c52fa3034f0fcc967ebf9e2ab09940\
+    8fea71b0d2c8f778f0bfb10000000000000000000000040023fca2f5ce9f5caa\
+    f4262038ffce965e12185bb554613e383106a580eae48c8973810afd10ab55b6\
+    dc916fed2fd058134fae8ee3ea5a5ba4810ad704080c80a175f59c44016d9e92\
+    2fad13a300196b873de54bb8dbc98b958137cfc7ac571665d9f4a94d3dd169c7\
+    72877b4b3ebdbdd09656e4ed9687fb4e61af8bb1b39a65cb";
+
+/// The public part of `ACCUMULATOR`
+pub const ACCUMULATOR_PUBLIC: &str = "5253414101070800010001747bb0822a1b0e37afcc5745a6ccd5b0ce2a1a1ce2\
+    9c4d5e2aee43f1626fe0c5b20263eade7ba88ef2142bfb00162eb6303b6dde33\
+    2d4980277bff263025115158463fefae2b098b86d606a9cae030427accfb6f3a\
+    9b4afba8730f9f75e4fa39bcc10d6f7209ea01db3d4e4c1a1f17d616b3f95d1b\
+    fa4688aa9d296642f0069a4376d0a404e4bc2f93e898d23a95d8c12482c4c367\
+    c496b5a4db715abf717aac895e6e3ac3e619c416fcb8f2132d5b0cca7cf1878f\
+    3b5efa1a7d2b605f6a8711e95838d555433b17b9f31d59452738a7c0ef3b5c2b\
+    5ef8ecb4f96c46129fdcad3008f07bb33c585ee814e9e3694ac48232dec86b8e\
+    3b0b5194533b92ebd5f56e74350170519fd776be4891dc379e8044c49266e291\
+    545611c1b7b7db70a91db0a3dd46fca597f6354ebe256a60a071e96410db7484\
+    2aba6e8d9de60bff2d36e60d581acc519bb49a1a99b2dfe31fbefbda7443f70c\
+    b841069f395131d46aabe02460aca54ff3e1ba36fcfab556c60fab1a7081ca44\
+    a671646ec1786606c33f5fa68d4fdc172fd2da26bfc6aaad33e57a63353a1f30\
+    5185c3f3a84d149b0a96e0726ade46d89d2ea23d1cd4ba61330c278f3c5c5e21\
+    29100f3dab4013a40a30b97d699f303ba2275c062fa05b441ecf997de5238c0a\
+    ce5ff50bb78522a29ad96b8b4650836d713596cd4a05c8edaa3954faa65861fb\
+    5560dfe346a798ab4e66479cc3862b6249aa2adf265e2ea67f6a5354338aa80a\
+    113cbf58f03b8f7157d1b848fca17e545acbdde3d40cddf892ddf4caa6003084\
+    76def0bf6ac211157a0f042e23d84f29004675d68fc650529c560da177f1ce28\
+    3bc5e8655a6e7dc9a8f81c93f09009574d1ee3f84877ba2624b720251b619122\
+    3a863762f6ca0b48f1002dfd946f72d2b6390ad2c52f5ebc2325475c2b1a70b6\
+    ba38d182a8882263413703f3080aabe90f94cd6b6e618b90aadb62f3b19cba42\
+    9e6c72c72f1fcd70fdbd57b72dbbc5d22d4c8cf3ff99b068d14d8c9533416940\
+    ffc38786253c7013636c5d11c4229cad13c52fa3034f0fcc967ebf9e2ab09940\
+    8fea71b0d2c8f778f0bfb10000000000000000";
+
+/// The membership witness for `MEMBERS[0]`
+pub const MEMBERSHIP_WITNESS: &str = "5253414101030800010001842da7f8e6ba2682c6a91eeb8b716d3d4fbade6844\
+    4d2fb8dbe8507ff42d4175922feb55896567943937eb35953bafeccbdfd104cd\
+    3d7eb3ca2bc6c92af39170b4d82f6b900c973cc9c3652153b6136cfcd0fdb7a1\
+    a9074e411a252cb4a049426fa26334508d0dc0fe0b804185e5432d26f7487fcd\
+    b92ac174d1b66bc476eea8b78441550c4170c6a79ff4d9ba51da9536f90f51cd\
+    1ce7cde725ea30f5738098cfd5127204cce1d6feac7e11fb221a10f47beb156d\
+    b05824a1174d0c11d093042306e17cfcfdaa52f77c2d77a9c3b40a4988572499\
+    9f5201055f243dad15c104d65603d2463ddc20bc184876d7c20b1267173a1b92\
+    fc79e29c460ebbcb8c6592d9f4a94d3dd169c772877b4b3ebdbdd09656e4ed96\
+    87fb4e61af8bb1b39a65cb0000000000000000ab97da73bd0260c66b56b29e0b\
+    a4d7b62e26e7778bf011693372b65f43d33b51";
+
+/// The non-membership witness for `NON_MEMBER`
+pub const NON_MEMBERSHIP_WITNESS: &str = "5253414101040800010001000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    000000000000000000000022ec37e8aa986040e849b5ff3351a80f7438d6ff38\
+    7779846f43d34d99543bd9329fd1408580cf811d0e2a6e38a3110757aa730b40\
+    98579ab8f2aa398e71dd786432f68779a3b4707cb3838b01294f3efbc5938aad\
+    554298aab061cffe9f0924f587524944ae7e2df9eacb990890f21045cf36cc9a\
+    7c1c2702867f443cffa2e6aec2f612c929630c956aa0effca8760ba96183bf6c\
+    5cd6e0fd70c97ee1c2fd96f5a2cc11418e42996189151451ba46b507e66bd706\
+    ee577c2cf099e225d94f33e6a256cdeb9b07eb2b5fc3abe68ac3b8936f9d5080\
+    3e481e0695e262d0c33633dcb72c9c8389d7d3da7f99ae73ebfea59f2a6fce1f\
+    fd502f8822c498b9f74b202899eac5502fde99993f3309503592c6db8114e49e\
+    e4642725e904bfaf89e3a95ab20c832bccd28bd4fc39ae8d2fa8ab92d1737d8f\
+    e01d5f7ce1e13fd72391a70000000000000000ab97da73bd0260c66b56b29e0b\
+    a4d7b62e26e7778bf011693372b65f43d33b51";
+
+/// The membership proof for `MEMBERSHIP_WITNESS`
+pub const MEMBERSHIP_PROOF: &str = "5253414101050800010001842da7f8e6ba2682c6a91eeb8b716d3d4fbade6844\
+    4d2fb8dbe8507ff42d4175922feb55896567943937eb35953bafeccbdfd104cd\
+    3d7eb3ca2bc6c92af39170b4d82f6b900c973cc9c3652153b6136cfcd0fdb7a1\
+    a9074e411a252cb4a049426fa26334508d0dc0fe0b804185e5432d26f7487fcd\
+    b92ac174d1b66bc476eea8b78441550c4170c6a79ff4d9ba51da9536f90f51cd\
+    1ce7cde725ea30f5738098cfd5127204cce1d6feac7e11fb221a10f47beb156d\
+    b05824a1174d0c11d093042306e17cfcfdaa52f77c2d77a9c3b40a4988572499\
+    9f5201055f243dad15c104d65603d2463ddc20bc184876d7c20b1267173a1b92\
+    fc79e29c460ebbcb8c65927c0c3694ae36eb8372dc51bccbe0e25529b786c318\
+    f65e834b66e451dcacdea251ca8d71c3daee910370cafd5ceccf1d580f8cc1aa\
+    2cd596f2ae6dd1ab9ec05a219a90ce7fe36d45d6877e638556481f69ea551db4\
+    46bc5f28b5d8bf0968a86d81f3689504da102014a11696632444ec0c708b9754\
+    a65cbec800dd444f2ed4f1e17fb00d079f5872fcd455df62fac97eaad1d98e00\
+    8cc2cfc65872e11b2c0d6c9e8a7d90cfcc8ff26018f2e2e0f7ceb9b4483eeace\
+    1af39c9e510082f90695d367bcbfd7c60079e785db6d1687d9ca6f048a1413f2\
+    28b28422ee6c105df953aba205ea8d8ab04e1d5652dad46065035132428b930a\
+    af136ca1c7fec3300426594c109b593ea102a6bb654c181ac6cd8d36d0b62a83\
+    b445a2230113052be1fbc1ea9672bb421a4e8b663bcb47d21ccb835c8cc31898\
+    e978349029919ae3add572110cbe5c463120384b11519adcb05345634bc0495b\
+    ad2a774c7b9ab75b8818dcb1928e435d10c4b1ad254953ee61792657ba0bc0f1\
+    0b4388543266edbb381d92922e054c9e04b9d7ac47e06db981473e308042dc7d\
+    9bd7b89c855d28a843647a7eec2fa86a72dc9b1f469e00236cd66fe078312a35\
+    20070673582dbd5d98f2bdb935e7b0004f8031cb05e77634914d11493b798e1b\
+    d3200217bbecb24419489b50953a4383ca662930d1448618f77ff0a1a49b7c10\
+    5943cf8357c71bfa7aaca5339bd190c34202d695e8582f5e0c28d270d89ac567\
+    902334a733bc7458fbaa24";
+
+/// The non-membership proof for `NON_MEMBERSHIP_WITNESS`
+pub const NON_MEMBERSHIP_PROOF: &str = "525341410106080001000125953c36ea9111816d8f86f536fbdc97be42fd577e\
+    1bd3edf64528bf9691e1ad0f85452b7e3c2a35a25a6e4634ceab6df114140f3b\
+    7021ea35c0f381812ddee46d155f34de0451ef952de5e76793a6d906f5f3e471\
+    150772c8657fb3364395e56c536a93ee666cdc818361937eb2b3b2c1a4837285\
+    aaaba25e55314adc227d4a84133cccbbaf887b6ad3de4f92d495258e0b560f6a\
+    ee64f7ac5a869d7ee9543c6880b6d5e7ca26c603ffc9f840ab7e8d27ef8f13c2\
+    8539e8d3137ad1583d279af4acb8e4564a0089b949df8f7d3ae35c5eb623720c\
+    b6592c075a0d761ef3ca9cbd4cf79dbc5569c8a3805c3e196136c8285a80e617\
+    20e2a0c1b92a670e18128a0f66e5c2406c6d4b04d64e9624ed58f7dd9809440d\
+    167fa6590c8beb3ba456541b5880ab8db48c20cd4846a5b7dede1d245e1c1ae8\
+    a7bc9d1730a94888f443035a862bb91bcc0ff8e66470e3a97fc8f866bb5dd2e5\
+    22f186be4090ceaa99119142e9febfc77918e5fd6f17dd80c657301114410b5d\
+    614997280f9cac19eb9074c52fcb46d2e898e36926f7da30fabc2d78795b4e85\
+    63622a4cb4cf70fb8fa19b7d830f24ed2bb0cf02d7d54ae3c8a57470ee747882\
+    8a800eb63b6ba7314dfaaba973bcaafd73c0494ebdaa5f00ca80ed402c903a5c\
+    d63d38e4195e262e067cd6a3c62cebd1c74a267593c0555bdb808692c8c61e36\
+    8b3766daa1584c6cb290a0000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    000000000000000000000122ec37e8aa986040e849b5ff3351a80f7438d6ff38\
+    7779846f43d34d99543bd9329fd1408580cf811d0e2a6e38a3110757aa730b40\
+    98579ab8f2aa398e71dd786432f68779a3b4707cb3838b01294f3efbc5938aad\
+    554298aab061cffe9f0924f587524944ae7e2df9eacb990890f21045cf36cc9a\
+    7c1c2702867f443cffa2e6aec2f612c929630c956aa0effca8760ba96183bf6c\
+    5cd6e0fd70c97ee1c2fd96f5a2cc11418e42996189151451ba46b507e66bd706\
+    ee577c2cf099e225d94f33e6a256cdeb9b07eb2b5fc3abe68ac3b8936f9d5080\
+    3e481e0695e262d0c33633dcb72c9c8389d7d3da7f99ae73ebfea59f2a6fce1f\
+    fd502f8822c498b9f74b202899eac5502fde99993f3309503592c6db8114e49e\
+    e4642725e904bfaf89e3a94421c928aae3523f7718d5304118502069d9090f51\
+    db2c0d0ea8a21c6a9d5a556240d6f5b99479a87e2167dd0ce9d73f360478b83c\
+    9ff5c406a9c82b50db9a48b6d3bbe010e2cbfedc2f823680c06542f775d95fa3\
+    e267f09d7b08e219065c9d7a0246e34f7aca2caa103313d9de824b166064d325\
+    2ade223f6f50e5550a6ba312baca3b6730a72c27e71466b8a8f9100d413dfbee\
+    47f24cbd67c6671d5712e95d68c0b198893b69557c53aa6f6d8553d2ccd89957\
+    c21f4d6a19b098363ecdf126dc32cfe3e82cdb57ed7a6f109a9dd8e1733762f5\
+    de389b9bac58386ebad85defcb1b33d9c7f08330796225e56181861f5af55f8d\
+    5e53ee19db6864a1050b93000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    00000000000000000000015ab20c832bccd28bd4fc39ae8d2fa8ab92d1737d8f\
+    e01d5f7ce1e13fd72391a7";
+
+/// Decode a hex vector
+pub fn decode(vector: &str) -> Vec<u8> {
+    hex::decode(vector).expect("vectors are valid hex")
+}
+
+/// Deserialize a vector
+pub fn load<T>(vector: &str) -> T
+where
+    T: for<'a> TryFrom<&'a [u8], Error = AccumulatorError>,
+{
+    T::try_from(decode(vector).as_slice()).expect("vectors deserialize")
+}
+
+/// `SECRET_KEY` deserialized
+pub fn secret_key() -> AccumulatorSecretKey {
+    load(SECRET_KEY)
+}
+
+/// `ACCUMULATOR` deserialized
+pub fn accumulator() -> Accumulator {
+    load(ACCUMULATOR)
+}
+
+/// `ACCUMULATOR_PUBLIC` deserialized
+pub fn accumulator_public() -> AccumulatorPublic {
+    load(ACCUMULATOR_PUBLIC)
+}
+
+/// `MEMBERSHIP_WITNESS` deserialized
+pub fn membership_witness() -> MembershipWitness {
+    load(MEMBERSHIP_WITNESS)
+}
+
+/// `NON_MEMBERSHIP_WITNESS` deserialized
+pub fn non_membership_witness() -> NonMembershipWitness {
+    load(NON_MEMBERSHIP_WITNESS)
+}
+
+/// `MEMBERSHIP_PROOF` deserialized
+pub fn membership_proof() -> MembershipProof {
+    load(MEMBERSHIP_PROOF)
+}
+
+/// `NON_MEMBERSHIP_PROOF` deserialized
+pub fn non_membership_proof() -> NonMembershipProof {
+    load(NON_MEMBERSHIP_PROOF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::hash_to_prime;
+
+    /// The vectors must be what this crate produces today
+    #[test]
+    fn vectors_match() {
+        let key = AccumulatorSecretKey::default();
+        assert_eq!(key.to_bytes().unwrap(), decode(SECRET_KEY));
+        let members: Vec<Vec<u8>> = MEMBERS.iter().map(|m| decode(m)).collect();
+        for (m, p) in members.iter().zip(MEMBER_PRIMES.iter()) {
+            assert_eq!(hash_to_prime(m).to_bytes(), decode(p));
+        }
+        assert_eq!(hash_to_prime(decode(NON_MEMBER)).to_bytes(), decode(NON_MEMBER_PRIME));
+
+        let acc = Accumulator::with_members(&key, &members).unwrap();
+        assert_eq!(acc.to_bytes().unwrap(), decode(ACCUMULATOR));
+        assert_eq!(acc.public().to_bytes().unwrap(), decode(ACCUMULATOR_PUBLIC));
+        assert_eq!(accumulator(), acc);
+
+        let witness = MembershipWitness::new(&acc, &members[0]).unwrap();
+        assert_eq!(witness, membership_witness());
+        let witness_non = NonMembershipWitness::new(&acc, decode(NON_MEMBER)).unwrap();
+        assert_eq!(witness_non, non_membership_witness());
+
+        let nonce = decode(NONCE);
+        let proof = MembershipProof::new(&witness, &acc, &nonce).unwrap();
+        assert_eq!(proof.to_bytes().unwrap(), decode(MEMBERSHIP_PROOF));
+        let proof_non = NonMembershipProof::new(&witness_non, &acc, &nonce).unwrap();
+        assert_eq!(proof_non.to_bytes().unwrap(), decode(NON_MEMBERSHIP_PROOF));
+    }
+
+    #[test]
+    fn vectors_verify() {
+        let acc = accumulator_public();
+        let nonce = decode(NONCE);
+        assert!(membership_proof().verify(&acc, &nonce));
+        assert!(non_membership_proof().verify(&acc, &nonce));
+        assert!(!membership_proof().verify(&acc, b"other"));
+    }
+}
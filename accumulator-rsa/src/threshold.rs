@@ -0,0 +1,486 @@
+// A trusted dealer splits the order of QR_N, m = (p - 1)(q - 1) / 4, into
+// Shamir shares over the integers. Any 2t - 1 share holders can then jointly
+// compute a^{1/x} mod N for a quadratic residue a and a member prime x, which
+// is the only operation that needs the trapdoor: removing x sets the
+// accumulator value to value^{1/x} and the membership witness for x is the same root.
+// Participants multiply their shares of m with shares of a jointly sampled mask ρ
+// to get additive shares of Δ·m·ρ, a random multiple of the group order, of which
+// only the sum modulo x is revealed.
+// The protocol assumes honest-but-curious participants.
+use crate::{accumulator::Accumulator, key::AccumulatorSecretKey, memwitness::{Freshness, MembershipWitness}, random_below};
+use common::{
+    bigint::BigInteger,
+    error::{AccumulatorError, AccumulatorErrorKind},
+};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use std::collections::BTreeSet;
+use zeroize::Zeroize;
+
+/// A participant's share of the accumulator trapdoor
+#[derive(Eq, PartialEq)]
+pub struct KeyShare {
+    /// The 1-based index of this share
+    pub index: usize,
+    /// The threshold `t`. `2t - 1` participants are required
+    pub threshold: usize,
+    /// The total number of shares `n`
+    pub parties: usize,
+    /// The public RSA modulus
+    pub modulus: BigInteger,
+    value: BigInteger,
+}
+
+impl core::fmt::Debug for KeyShare {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("KeyShare")
+            .field("index", &self.index)
+            .field("threshold", &self.threshold)
+            .field("parties", &self.parties)
+            .field("modulus", &self.modulus)
+            .field("value", &"<redacted>")
+            .finish()
+    }
+}
+
+/// A share of the jointly sampled mask sent from one participant to another
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MaskShare {
+    /// The index of the sender
+    pub from: usize,
+    /// The index of the recipient
+    pub to: usize,
+    /// The share value
+    pub value: BigInteger,
+}
+
+/// The residue of a participant's additive share modulo the member prime
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PartialResidue {
+    /// The index of the sender
+    pub index: usize,
+    /// The residue
+    pub residue: BigInteger,
+}
+
+/// A participant's contribution to the root
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PartialRoot {
+    /// The index of the sender
+    pub index: usize,
+    /// The partial root
+    pub value: BigInteger,
+}
+
+/// The local state held by a participant while computing a root
+pub struct RootSession {
+    index: usize,
+    participants: Vec<usize>,
+    modulus: BigInteger,
+    a: BigInteger,
+    x: BigInteger,
+    share: BigInteger,
+    lagrange: BigInteger,
+    quotient: Option<BigInteger>,
+}
+
+impl core::fmt::Debug for RootSession {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("RootSession")
+            .field("index", &self.index)
+            .field("participants", &self.participants)
+            .field("modulus", &self.modulus)
+            .field("a", &self.a)
+            .field("x", &self.x)
+            .field("share", &"<redacted>")
+            .field("lagrange", &self.lagrange)
+            .field("quotient", &self.quotient.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+impl AccumulatorSecretKey {
+    /// Split the trapdoor into `parties` shares such that any `2 * threshold - 1`
+    /// of them can compute roots. The dealer must destroy this key afterwards.
+    pub fn split(&self, threshold: usize, parties: usize) -> Result<Vec<KeyShare>, AccumulatorError> {
+        self.split_with_rng(threshold, parties, &mut OsRng)
+    }
+
+    /// Same as `split` but the coefficients are drawn from `rng`
+    pub fn split_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        threshold: usize,
+        parties: usize,
+        rng: &mut R,
+    ) -> Result<Vec<KeyShare>, AccumulatorError> {
+        if threshold < 2 || 2 * threshold - 1 > parties {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidType,
+                "threshold must be at least 2 and 2 * threshold - 1 must not exceed parties",
+            ));
+        }
+        let modulus = self.modulus();
+        let order = self.totient() / BigInteger::from(4u32);
+        let bound = &modulus * &modulus;
+        let mut coefficients = Vec::with_capacity(threshold);
+        coefficients.push(order);
+        for _ in 1..threshold {
+            coefficients.push(random_below(&bound, rng)?);
+        }
+        let shares = (1..=parties)
+            .map(|i| KeyShare {
+                index: i,
+                threshold,
+                parties,
+                modulus: modulus.clone(),
+                value: evaluate(&coefficients, i),
+            })
+            .collect();
+        for c in coefficients.iter_mut() {
+            c.zeroize();
+        }
+        Ok(shares)
+    }
+}
+
+impl KeyShare {
+    /// Start computing `a^{1/x} mod N` together with `participants`.
+    /// Returns the local session and the mask shares to send, one per participant
+    /// including this one.
+    ///
+    /// The remaining rounds are `RootSession::receive_masks` which yields the residue
+    /// to broadcast, `RootSession::finish` which yields the partial root, and
+    /// `combine_roots`.
+    pub fn begin_root(
+        &self,
+        a: &BigInteger,
+        x: &BigInteger,
+        participants: &[usize],
+    ) -> Result<(RootSession, Vec<MaskShare>), AccumulatorError> {
+        self.begin_root_with_rng(a, x, participants, &mut OsRng)
+    }
+
+    /// Same as `begin_root` but the mask is drawn from `rng`
+    pub fn begin_root_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        a: &BigInteger,
+        x: &BigInteger,
+        participants: &[usize],
+        rng: &mut R,
+    ) -> Result<(RootSession, Vec<MaskShare>), AccumulatorError> {
+        let participants = self.check_participants(participants)?;
+        let bound = &self.modulus * &self.modulus;
+        let mut coefficients = (0..self.threshold)
+            .map(|_| random_below(&bound, rng))
+            .collect::<Result<Vec<BigInteger>, AccumulatorError>>()?;
+        let outgoing = participants
+            .iter()
+            .map(|&to| MaskShare {
+                from: self.index,
+                to,
+                value: evaluate(&coefficients, to),
+            })
+            .collect();
+        for c in coefficients.iter_mut() {
+            c.zeroize();
+        }
+        let lagrange = lagrange_coefficient(self.index, &participants, self.parties);
+        let session = RootSession {
+            index: self.index,
+            participants,
+            modulus: self.modulus.clone(),
+            a: a.clone(),
+            x: x.clone(),
+            share: self.value.clone(),
+            lagrange,
+            quotient: None,
+        };
+        Ok((session, outgoing))
+    }
+
+    fn check_participants(&self, participants: &[usize]) -> Result<Vec<usize>, AccumulatorError> {
+        let set: BTreeSet<usize> = participants.iter().copied().collect();
+        if set.len() != participants.len()
+            || set.len() < 2 * self.threshold - 1
+            || !set.contains(&self.index)
+            || set.iter().any(|&i| i == 0 || i > self.parties)
+        {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::ParameterMismatch,
+                "participants must be 2 * threshold - 1 distinct valid indices including this share",
+            ));
+        }
+        Ok(set.into_iter().collect())
+    }
+}
+
+impl RootSession {
+    /// Combine the mask shares received from every participant and
+    /// compute the residue to broadcast
+    pub fn receive_masks(&mut self, incoming: &[MaskShare]) -> Result<PartialResidue, AccumulatorError> {
+        let senders: BTreeSet<usize> = incoming
+            .iter()
+            .filter(|m| m.to == self.index)
+            .map(|m| m.from)
+            .collect();
+        if senders.len() != incoming.len()
+            || senders.iter().copied().collect::<Vec<usize>>() != self.participants
+        {
+            return Err(AccumulatorError::from_msg(
+                AccumulatorErrorKind::ParameterMismatch,
+                "expected exactly one mask share from every participant",
+            ));
+        }
+        let mask: BigInteger = incoming.iter().map(|m| &m.value).sum();
+        // Additive share of Δ·m·ρ
+        let mut product = &(&self.lagrange * &self.share) * &mask;
+        let residue = reduce(&product, &self.x);
+        self.quotient = Some((&product - &residue) / self.x.clone());
+        product.zeroize();
+        Ok(PartialResidue {
+            index: self.index,
+            residue,
+        })
+    }
+
+    /// Compute this participant's partial root once every residue is known
+    pub fn finish(mut self, residues: &[PartialResidue]) -> Result<PartialRoot, AccumulatorError> {
+        let quotient = self.quotient.take().ok_or_else(|| {
+            AccumulatorError::from_msg(
+                AccumulatorErrorKind::InvalidType,
+                "mask shares have not been received",
+            )
+        })?;
+        let (u, _, _) = residue_inverse(&self.participants, residues, &self.x)?;
+        let value = self
+            .a
+            .mod_exp(&(&BigInteger::default() - &(&quotient * &u)), &self.modulus);
+        Ok(PartialRoot {
+            index: self.index,
+            value,
+        })
+    }
+}
+
+impl Drop for RootSession {
+    fn drop(&mut self) {
+        self.share.zeroize();
+        if let Some(q) = self.quotient.as_mut() {
+            q.zeroize();
+        }
+    }
+}
+
+impl Zeroize for KeyShare {
+    fn zeroize(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+impl Drop for KeyShare {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+/// Combine the partial roots into `a^{1/x} mod N`.
+/// The result is checked before it is returned.
+pub fn combine_roots(
+    a: &BigInteger,
+    x: &BigInteger,
+    modulus: &BigInteger,
+    residues: &[PartialResidue],
+    partials: &[PartialRoot],
+) -> Result<BigInteger, AccumulatorError> {
+    let participants: Vec<usize> = partials.iter().map(|p| p.index).collect();
+    let (u, v, t) = residue_inverse(&participants, residues, x)?;
+    let correction = a.mod_exp(&(&BigInteger::default() - &(&(&t * &u) + &v)), modulus);
+    let root = partials
+        .iter()
+        .fold(correction, |r, p| r.mod_mul(&p.value, modulus));
+    if root.mod_exp(x, modulus) != a.clone() % modulus.clone() {
+        return Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::InvalidMemberSupplied,
+            "the partial roots do not combine to a valid root",
+        ));
+    }
+    Ok(root)
+}
+
+impl Accumulator {
+    /// Remove a prime value using the root `value^{1/x}` computed by the share holders
+    pub fn remove_with_root(&self, value: &BigInteger, root: &BigInteger) -> Result<Self, AccumulatorError> {
+        let mut a = self.clone();
+        a.remove_with_root_assign(value, root)?;
+        Ok(a)
+    }
+
+    /// Remove a prime value using the root `value^{1/x}` computed by the share holders
+    pub fn remove_with_root_assign(&mut self, value: &BigInteger, root: &BigInteger) -> Result<(), AccumulatorError> {
+        self._remove_with_root(value, root)
+    }
+}
+
+impl MembershipWitness {
+    /// Create a membership witness from the root `accumulator^{1/x}`
+    /// computed by the share holders
+    pub fn with_root(accumulator: &Accumulator, x: &BigInteger, root: &BigInteger) -> Result<Self, AccumulatorError> {
+        if !accumulator.members.contains(x) || root.mod_exp(x, &accumulator.modulus) != accumulator.value {
+            return Err(AccumulatorErrorKind::InvalidMemberSupplied.into());
+        }
+        Ok(Self {
+            u: root.clone(),
+            x: x.clone(),
+            freshness: Freshness::new(accumulator.epoch, &accumulator.value),
+        })
+    }
+}
+
+/// Evaluate the polynomial with `coefficients` at `x`
+fn evaluate(coefficients: &[BigInteger], x: usize) -> BigInteger {
+    let x = BigInteger::from(x as u64);
+    coefficients
+        .iter()
+        .rev()
+        .fold(BigInteger::default(), |acc, c| &(&acc * &x) + c)
+}
+
+/// Δ times the Lagrange coefficient at 0 for `index`, with Δ = parties!
+/// which makes it an integer
+fn lagrange_coefficient(index: usize, participants: &[usize], parties: usize) -> BigInteger {
+    let delta: BigInteger = (1..=parties as u64).map(BigInteger::from).product();
+    let i = index as i64;
+    let (num, den) = participants
+        .iter()
+        .filter(|&&j| j != index)
+        .fold((delta, BigInteger::from(1u32)), |(num, den), &j| {
+            let j = j as i64;
+            (&num * &BigInteger::from(j), &den * &BigInteger::from(j - i))
+        });
+    num / den
+}
+
+/// Reduce `a` into [0, m)
+fn reduce(a: &BigInteger, m: &BigInteger) -> BigInteger {
+    &(&(a % m) + m) % m
+}
+
+/// Returns u = c^-1 mod x, v = (c·u - 1) / x and t = (Σ c_j - c) / x
+/// where c = Σ c_j mod x
+fn residue_inverse(
+    participants: &[usize],
+    residues: &[PartialResidue],
+    x: &BigInteger,
+) -> Result<(BigInteger, BigInteger, BigInteger), AccumulatorError> {
+    let senders: BTreeSet<usize> = residues.iter().map(|r| r.index).collect();
+    let expected: BTreeSet<usize> = participants.iter().copied().collect();
+    if senders != expected || residues.len() != expected.len() {
+        return Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::ParameterMismatch,
+            "expected exactly one residue from every participant",
+        ));
+    }
+    let sum: BigInteger = residues.iter().map(|r| &r.residue).sum();
+    let c = reduce(&sum, x);
+    if c == BigInteger::default() {
+        return Err(AccumulatorError::from_msg(
+            AccumulatorErrorKind::InvalidType,
+            "degenerate mask, restart the protocol",
+        ));
+    }
+    let u = c.mod_inverse(x);
+    let v = (&(&c * &u) - &BigInteger::from(1u32)) / x.clone();
+    let t = (&sum - &c) / x.clone();
+    Ok((u, v, t))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hash::hash_to_prime, key::SecretKeyOps};
+
+    fn run(shares: &[KeyShare], participants: &[usize], a: &BigInteger, x: &BigInteger) -> BigInteger {
+        let mut sessions = Vec::new();
+        let mut masks = Vec::new();
+        for &i in participants {
+            let (s, m) = shares[i - 1].begin_root(a, x, participants).unwrap();
+            sessions.push(s);
+            masks.extend(m);
+        }
+        let residues: Vec<PartialResidue> = sessions
+            .iter_mut()
+            .map(|s| {
+                let incoming: Vec<MaskShare> =
+                    masks.iter().filter(|m| m.to == s.index).cloned().collect();
+                s.receive_masks(&incoming).unwrap()
+            })
+            .collect();
+        let partials: Vec<PartialRoot> = sessions
+            .into_iter()
+            .map(|s| s.finish(&residues).unwrap())
+            .collect();
+        combine_roots(a, x, &shares[0].modulus, &residues, &partials).unwrap()
+    }
+
+    #[test]
+    fn split_with_seeded_rng() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let key = AccumulatorSecretKey::default();
+        let shares = key.split_with_rng(2, 3, &mut StdRng::seed_from_u64(1)).unwrap();
+        assert_eq!(shares, key.split_with_rng(2, 3, &mut StdRng::seed_from_u64(1)).unwrap());
+        assert_ne!(shares, key.split_with_rng(2, 3, &mut StdRng::seed_from_u64(2)).unwrap());
+
+        let a = BigInteger::from(4u32);
+        let x = hash_to_prime(b"seeded");
+        assert_eq!(run(&shares, &[1, 2, 3], &a, &x), SecretKeyOps::root(&key, &a, &x).unwrap());
+    }
+
+    #[test]
+    fn threshold_remove() {
+        let key = AccumulatorSecretKey::default();
+        let members: Vec<[u8; 8]> = vec![
+            3u64.to_be_bytes(),
+            7u64.to_be_bytes(),
+            11u64.to_be_bytes(),
+        ];
+        let acc = Accumulator::with_members(&key, &members).unwrap();
+        let shares = key.split(2, 4).unwrap();
+        let x = hash_to_prime(members[1]);
+
+        let root = run(&shares, &[1, 3, 4], &acc.value, &x);
+        let witness = MembershipWitness::with_root(&acc, &x, &root).unwrap();
+        assert_eq!(witness, MembershipWitness::new(&acc, members[1]).unwrap());
+
+        let removed = acc.remove_with_root(&x, &root).unwrap();
+        assert_eq!(removed, acc.remove(&key, members[1]).unwrap());
+    }
+
+    #[test]
+    fn debug_redacts_session() {
+        let key = AccumulatorSecretKey::default();
+        let shares = key.split(2, 3).unwrap();
+        let x = hash_to_prime(b"debug_redacts_session");
+        let a = BigInteger::from(4u32);
+        let (mut session, mut masks) = shares[0].begin_root(&a, &x, &[1, 2, 3]).unwrap();
+        for share in &shares[1..] {
+            masks.extend(share.begin_root(&a, &x, &[1, 2, 3]).unwrap().1);
+        }
+        let incoming: Vec<MaskShare> = masks.into_iter().filter(|m| m.to == 1).collect();
+        session.receive_masks(&incoming).unwrap();
+        let out = format!("{:?}", session);
+        assert!(!out.contains(&format!("{:?}", session.share)));
+        assert!(!out.contains(&format!("{:?}", session.quotient.as_ref().unwrap())));
+        assert!(out.contains("<redacted>"));
+    }
+
+    #[test]
+    fn too_few_participants() {
+        let key = AccumulatorSecretKey::default();
+        let shares = key.split(2, 3).unwrap();
+        let x = hash_to_prime(b"too_few_participants");
+        assert!(shares[0]
+            .begin_root(&BigInteger::from(4u32), &x, &[1, 2])
+            .is_err());
+        assert!(key.split(3, 4).is_err());
+    }
+}
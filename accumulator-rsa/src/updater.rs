@@ -0,0 +1,307 @@
+use crate::{
+    accumulator::AccumulatorPublic, memwitness::MembershipWitness, nonblocking::blocking,
+    registry::RegistryDelta, telemetry,
+};
+use common::error::{AccumulatorError, AccumulatorErrorKind};
+use core::convert::TryFrom;
+use std::{
+    future::Future,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::{
+    sync::{mpsc, watch},
+    task::JoinHandle,
+};
+
+/// Where a `WitnessUpdater` saves the witness after each applied delta
+/// so a holder restarts from the last applied epoch
+pub trait Checkpoint: Send + 'static {
+    /// Save `witness` and the `definition` it matches
+    fn save(&mut self, witness: &MembershipWitness, definition: &AccumulatorPublic) -> Result<(), AccumulatorError>;
+}
+
+/// Don't persist anything
+impl Checkpoint for () {
+    fn save(&mut self, _: &MembershipWitness, _: &AccumulatorPublic) -> Result<(), AccumulatorError> {
+        Ok(())
+    }
+}
+
+/// Keeps the latest witness and definition in a file.
+/// The file is replaced by a rename so a crash never leaves it half written
+#[derive(Debug, Clone)]
+pub struct FileCheckpoint {
+    path: PathBuf,
+}
+
+impl FileCheckpoint {
+    /// Checkpoint to the file at `path`
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// The saved witness and definition, `None` if nothing was saved yet
+    pub fn load(&self) -> Result<Option<(MembershipWitness, AccumulatorPublic)>, AccumulatorError> {
+        let data = match std::fs::read(&self.path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(io_error(e)),
+        };
+        if data.len() < AccumulatorPublic::SIZE_BYTES {
+            return Err(AccumulatorErrorKind::InvalidLength {
+                expected: AccumulatorPublic::SIZE_BYTES,
+                actual: data.len(),
+            }
+            .into());
+        }
+        let (definition, witness) = data.split_at(AccumulatorPublic::SIZE_BYTES);
+        Ok(Some((
+            MembershipWitness::try_from(witness)?,
+            AccumulatorPublic::try_from(definition)?,
+        )))
+    }
+}
+
+impl Checkpoint for FileCheckpoint {
+    fn save(&mut self, witness: &MembershipWitness, definition: &AccumulatorPublic) -> Result<(), AccumulatorError> {
+        let mut data = definition.to_bytes()?;
+        data.append(&mut witness.to_bytes()?);
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, data).map_err(io_error)?;
+        std::fs::rename(&tmp, &self.path).map_err(io_error)
+    }
+}
+
+fn io_error(e: std::io::Error) -> AccumulatorError {
+    AccumulatorError::from_source(AccumulatorErrorKind::SerializationError, e)
+}
+
+/// Keeps a holder's witness up to date in a background task.
+///
+/// Deltas come from a channel or are fetched periodically and are
+/// applied in order on the blocking pool. Deltas for epochs the witness
+/// already has are skipped. The task stops when the source is exhausted,
+/// the credential is revoked, a delta doesn't follow the current epoch
+/// or this handle is dropped. Must be created inside a tokio runtime
+#[derive(Debug)]
+pub struct WitnessUpdater {
+    current: watch::Receiver<(MembershipWitness, AccumulatorPublic)>,
+    task: Option<JoinHandle<Result<(), AccumulatorError>>>,
+}
+
+impl WitnessUpdater {
+    /// Apply the deltas received from `deltas`
+    pub fn spawn<C: Checkpoint>(
+        witness: MembershipWitness,
+        definition: AccumulatorPublic,
+        mut deltas: mpsc::Receiver<RegistryDelta>,
+        checkpoint: C,
+    ) -> Self {
+        let (tx, current) = watch::channel((witness, definition));
+        let task = tokio::spawn(async move {
+            let mut checkpoint = Some(checkpoint);
+            while let Some(delta) = deltas.recv().await {
+                apply(&tx, &mut checkpoint, vec![delta]).await?;
+            }
+            Ok(())
+        });
+        Self {
+            current,
+            task: Some(task),
+        }
+    }
+
+    /// Every `interval` call `fetch` with the current epoch and apply the
+    /// deltas it returns, for example by requesting them from the issuer.
+    /// Errors from `fetch` are retried at the next interval
+    pub fn spawn_polling<C, F, R>(
+        witness: MembershipWitness,
+        definition: AccumulatorPublic,
+        interval: Duration,
+        mut fetch: F,
+        checkpoint: C,
+    ) -> Self
+    where
+        C: Checkpoint,
+        F: FnMut(u64) -> R + Send + 'static,
+        R: Future<Output = Result<Vec<RegistryDelta>, AccumulatorError>> + Send,
+    {
+        let (tx, current) = watch::channel((witness, definition));
+        let task = tokio::spawn(async move {
+            let mut checkpoint = Some(checkpoint);
+            loop {
+                let epoch = tx.borrow().1.epoch;
+                if let Ok(deltas) = fetch(epoch).await {
+                    apply(&tx, &mut checkpoint, deltas).await?;
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+        Self {
+            current,
+            task: Some(task),
+        }
+    }
+
+    /// The latest witness
+    pub fn witness(&self) -> MembershipWitness {
+        self.current.borrow().0.clone()
+    }
+
+    /// The definition the latest witness matches
+    pub fn definition(&self) -> AccumulatorPublic {
+        self.current.borrow().1.clone()
+    }
+
+    /// The epoch of the latest witness
+    pub fn epoch(&self) -> u64 {
+        self.current.borrow().1.epoch
+    }
+
+    /// Wait until the witness is updated. Returns false if the task
+    /// stopped and there won't be any more updates
+    pub async fn changed(&mut self) -> bool {
+        self.current.changed().await.is_ok()
+    }
+
+    /// Wait for the task to stop and return why. `Ok` if the channel
+    /// of deltas was closed
+    pub async fn join(mut self) -> Result<(MembershipWitness, AccumulatorPublic), AccumulatorError> {
+        let task = self.task.take().expect("task is only taken here");
+        match task.await {
+            Ok(r) => r?,
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            Err(_) => return Err(AccumulatorErrorKind::Cancelled.into()),
+        }
+        Ok(self.current.borrow().clone())
+    }
+}
+
+impl Drop for WitnessUpdater {
+    fn drop(&mut self) {
+        if let Some(task) = &self.task {
+            task.abort();
+        }
+    }
+}
+
+/// Apply `deltas` in order, saving and publishing after each one
+async fn apply<C: Checkpoint>(
+    tx: &watch::Sender<(MembershipWitness, AccumulatorPublic)>,
+    checkpoint: &mut Option<C>,
+    deltas: Vec<RegistryDelta>,
+) -> Result<(), AccumulatorError> {
+    for delta in deltas {
+        let (mut witness, definition) = tx.borrow().clone();
+        if delta.to_epoch <= definition.epoch {
+            continue;
+        }
+        // The checkpoint moves to the blocking pool with the work and back
+        let mut c = checkpoint.take().expect("checkpoint is returned after every delta");
+        let (witness, definition, c) = blocking(move || {
+            let next = delta.apply(&definition)?;
+            delta.update_witness_assign(&mut witness, &definition)?;
+            c.save(&witness, &next)?;
+            Ok((witness, next, c))
+        })
+        .await?;
+        *checkpoint = Some(c);
+        telemetry::count(telemetry::WITNESS_UPDATES, 1);
+        tx.send_replace((witness, definition));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{key::AccumulatorSecretKey, registry::RevocationRegistry};
+
+    fn run<F: Future>(f: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(f)
+    }
+
+    #[test]
+    fn updates_from_channel() {
+        let key = AccumulatorSecretKey::default();
+        let mut registry = RevocationRegistry::new(&key);
+        registry.register("alice").unwrap();
+        registry.register("bob").unwrap();
+        registry.publish(&key).unwrap();
+        let witness = registry.witness(&key, "alice").unwrap();
+        let definition = registry.definition();
+
+        let path = std::env::temp_dir().join(format!("witness-updater-{}", std::process::id()));
+        let checkpoint = FileCheckpoint::new(&path);
+        assert!(checkpoint.load().unwrap().is_none());
+
+        run(async {
+            let (tx, rx) = mpsc::channel(4);
+            let mut updater = WitnessUpdater::spawn(witness, definition, rx, checkpoint.clone());
+            registry.register("carol").unwrap();
+            let delta = registry.publish(&key).unwrap().unwrap().clone();
+            tx.send(delta.clone()).await.unwrap();
+            assert!(updater.changed().await);
+            assert_eq!(updater.epoch(), 2);
+            // Already applied
+            tx.send(delta).await.unwrap();
+            drop(tx);
+            let (witness, definition) = updater.join().await.unwrap();
+            assert_eq!(definition, registry.definition());
+            assert_eq!(witness, registry.witness(&key, "alice").unwrap());
+        });
+
+        let (witness, definition) = checkpoint.load().unwrap().unwrap();
+        assert_eq!(definition, registry.definition());
+
+        run(async {
+            let (tx, rx) = mpsc::channel(4);
+            let updater = WitnessUpdater::spawn(witness, definition, rx, ());
+            registry.revoke("alice").unwrap();
+            tx.send(registry.publish(&key).unwrap().unwrap().clone()).await.unwrap();
+            assert!(updater.join().await.is_err());
+        });
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn updates_by_polling() {
+        let key = AccumulatorSecretKey::default();
+        let mut registry = RevocationRegistry::new(&key);
+        registry.register("alice").unwrap();
+        registry.publish(&key).unwrap();
+        let witness = registry.witness(&key, "alice").unwrap();
+        let definition = registry.definition();
+        registry.register("bob").unwrap();
+        registry.publish(&key).unwrap();
+        registry.register("carol").unwrap();
+        registry.publish(&key).unwrap();
+
+        let issuer = std::sync::Arc::new(registry);
+        let source = issuer.clone();
+        run(async move {
+            let mut updater = WitnessUpdater::spawn_polling(
+                witness,
+                definition,
+                Duration::from_millis(1),
+                move |epoch| {
+                    let deltas = source.deltas_since(epoch).unwrap_or(&[]).to_vec();
+                    async move { Ok(deltas) }
+                },
+                (),
+            );
+            while updater.epoch() < 3 {
+                assert!(updater.changed().await);
+            }
+            assert_eq!(updater.witness(), issuer.witness(&key, "alice").unwrap());
+        });
+    }
+}
@@ -0,0 +1,321 @@
+#![deny(
+    missing_docs,
+    unsafe_code,
+    unused_import_braces,
+    unused_lifetimes,
+    unused_qualifications
+)]
+//! JavaScript bindings for the RSA accumulator
+//!
+//! Every class reads and writes `Uint8Array`s and errors are thrown as JS `Error`s.
+//! Witness updates have `updateAsync` variants that wait for the next
+//! macrotask first so updating many witnesses doesn't freeze the page.
+use js_sys::{Function, Promise, Reflect};
+use accumulator_rsa::{
+    accumulator, common::error::AccumulatorError, key::AccumulatorSecretKey, memproof, memwitness,
+    nonmemproof, nonwitness,
+};
+use std::{convert::TryFrom, rc::Rc};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::{future_to_promise, JsFuture};
+
+fn js_error(err: AccumulatorError) -> JsError {
+    JsError::new(&err.to_string())
+}
+
+/// Resolves on the next macrotask so the caller gives the event loop a chance to run
+async fn next_task() -> Result<(), JsValue> {
+    let set_timeout: Function = Reflect::get(&js_sys::global(), &JsValue::from_str("setTimeout"))?.dyn_into()?;
+    let tick = Promise::new(&mut |resolve, _| {
+        let _ = set_timeout.call2(&JsValue::UNDEFINED, &resolve, &JsValue::from(0));
+    });
+    JsFuture::from(tick).await?;
+    Ok(())
+}
+
+/// An accumulator secret key
+#[wasm_bindgen]
+pub struct SecretKey(AccumulatorSecretKey);
+
+#[wasm_bindgen]
+impl SecretKey {
+    /// Generate a new secret key. This searches for two safe primes and takes a while.
+    #[wasm_bindgen(constructor)]
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> SecretKey {
+        SecretKey(AccumulatorSecretKey::new())
+    }
+
+    /// Read a secret key from bytes
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<SecretKey, JsError> {
+        AccumulatorSecretKey::try_from(bytes).map(SecretKey).map_err(js_error)
+    }
+
+    /// Serialize this key
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, JsError> {
+        self.0.to_bytes().map_err(js_error)
+    }
+}
+
+/// An accumulator with its members. Cloning for the async helpers is cheap
+/// since the accumulator is shared until it is changed
+#[wasm_bindgen]
+pub struct Accumulator(Rc<accumulator::Accumulator>);
+
+#[wasm_bindgen]
+impl Accumulator {
+    /// Create an empty accumulator
+    #[wasm_bindgen(constructor)]
+    pub fn new(key: &SecretKey) -> Accumulator {
+        Accumulator(Rc::new(accumulator::Accumulator::new(&key.0)))
+    }
+
+    /// Read an accumulator from bytes
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Accumulator, JsError> {
+        accumulator::Accumulator::try_from(bytes)
+            .map(|a| Accumulator(Rc::new(a)))
+            .map_err(js_error)
+    }
+
+    /// Serialize this accumulator with its members
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, JsError> {
+        self.0.to_bytes().map_err(js_error)
+    }
+
+    /// Serialize the value and parameters without the members
+    #[wasm_bindgen(js_name = toPublicBytes)]
+    pub fn to_public_bytes(&self) -> Result<Vec<u8>, JsError> {
+        self.0.to_public_bytes().map_err(js_error)
+    }
+
+    /// The number of updates applied to this accumulator
+    #[wasm_bindgen(getter)]
+    pub fn epoch(&self) -> u64 {
+        self.0.epoch
+    }
+
+    /// Add a value
+    pub fn insert(&mut self, value: &[u8]) -> Result<(), JsError> {
+        Rc::make_mut(&mut self.0).insert_assign(value).map_err(js_error)
+    }
+
+    /// Remove a value
+    pub fn remove(&mut self, key: &SecretKey, value: &[u8]) -> Result<(), JsError> {
+        Rc::make_mut(&mut self.0).remove_assign(&key.0, value).map_err(js_error)
+    }
+}
+
+/// A witness that a value is in an accumulator
+#[wasm_bindgen]
+pub struct MembershipWitness(memwitness::MembershipWitness);
+
+#[wasm_bindgen]
+impl MembershipWitness {
+    /// Create a witness for a member of `accumulator`
+    #[wasm_bindgen(constructor)]
+    pub fn new(accumulator: &Accumulator, value: &[u8]) -> Result<MembershipWitness, JsError> {
+        memwitness::MembershipWitness::new(&accumulator.0, value)
+            .map(MembershipWitness)
+            .map_err(js_error)
+    }
+
+    /// Read a witness from bytes
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<MembershipWitness, JsError> {
+        memwitness::MembershipWitness::try_from(bytes)
+            .map(MembershipWitness)
+            .map_err(js_error)
+    }
+
+    /// Serialize this witness
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, JsError> {
+        self.0.to_bytes().map_err(js_error)
+    }
+
+    /// A copy of this witness updated from `old_acc` to `new_acc`
+    pub fn update(&self, old_acc: &Accumulator, new_acc: &Accumulator) -> Result<MembershipWitness, JsError> {
+        self.0.update(&old_acc.0, &new_acc.0).map(MembershipWitness).map_err(js_error)
+    }
+
+    /// Like `update` but resolves to the updated witness after
+    /// yielding to the event loop
+    #[wasm_bindgen(js_name = updateAsync)]
+    pub fn update_async(&self, old_acc: &Accumulator, new_acc: &Accumulator) -> Promise {
+        let (witness, old_acc, new_acc) = (self.0.clone(), old_acc.0.clone(), new_acc.0.clone());
+        future_to_promise(async move {
+            next_task().await?;
+            witness
+                .update(&old_acc, &new_acc)
+                .map(|w| MembershipWitness(w).into())
+                .map_err(|e| js_error(e).into())
+        })
+    }
+}
+
+/// A witness that a value is not in an accumulator
+#[wasm_bindgen]
+pub struct NonMembershipWitness(nonwitness::NonMembershipWitness);
+
+#[wasm_bindgen]
+impl NonMembershipWitness {
+    /// Create a witness for a value that is not a member of `accumulator`
+    #[wasm_bindgen(constructor)]
+    pub fn new(accumulator: &Accumulator, value: &[u8]) -> Result<NonMembershipWitness, JsError> {
+        nonwitness::NonMembershipWitness::new(&accumulator.0, value)
+            .map(NonMembershipWitness)
+            .map_err(js_error)
+    }
+
+    /// Read a witness from bytes
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<NonMembershipWitness, JsError> {
+        nonwitness::NonMembershipWitness::try_from(bytes)
+            .map(NonMembershipWitness)
+            .map_err(js_error)
+    }
+
+    /// Serialize this witness
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, JsError> {
+        self.0.to_bytes().map_err(js_error)
+    }
+
+    /// A copy of this witness updated from `old_acc` to `new_acc`
+    pub fn update(&self, old_acc: &Accumulator, new_acc: &Accumulator) -> Result<NonMembershipWitness, JsError> {
+        self.0
+            .update(&old_acc.0, &new_acc.0)
+            .map(NonMembershipWitness)
+            .map_err(js_error)
+    }
+
+    /// Like `update` but resolves to the updated witness after
+    /// yielding to the event loop
+    #[wasm_bindgen(js_name = updateAsync)]
+    pub fn update_async(&self, old_acc: &Accumulator, new_acc: &Accumulator) -> Promise {
+        let (witness, old_acc, new_acc) = (self.0.clone(), old_acc.0.clone(), new_acc.0.clone());
+        future_to_promise(async move {
+            next_task().await?;
+            witness
+                .update(&old_acc, &new_acc)
+                .map(|w| NonMembershipWitness(w).into())
+                .map_err(|e| js_error(e).into())
+        })
+    }
+}
+
+/// A zero-knowledge proof that a value is in an accumulator
+#[wasm_bindgen]
+pub struct MembershipProof(memproof::MembershipProof);
+
+#[wasm_bindgen]
+impl MembershipProof {
+    /// Create a proof bound to `nonce`
+    #[wasm_bindgen(constructor)]
+    pub fn new(witness: &MembershipWitness, accumulator: &Accumulator, nonce: &[u8]) -> Result<MembershipProof, JsError> {
+        memproof::MembershipProof::new(&witness.0, &accumulator.0, nonce)
+            .map(MembershipProof)
+            .map_err(js_error)
+    }
+
+    /// Check the proof against `accumulator` and `nonce`
+    pub fn verify(&self, accumulator: &Accumulator, nonce: &[u8]) -> bool {
+        self.0.verify(accumulator.0.as_ref(), nonce)
+    }
+
+    /// Read a proof from bytes
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<MembershipProof, JsError> {
+        memproof::MembershipProof::try_from(bytes)
+            .map(MembershipProof)
+            .map_err(js_error)
+    }
+
+    /// Serialize this proof
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, JsError> {
+        self.0.to_bytes().map_err(js_error)
+    }
+}
+
+/// A zero-knowledge proof that a value is not in an accumulator
+#[wasm_bindgen]
+pub struct NonMembershipProof(nonmemproof::NonMembershipProof);
+
+#[wasm_bindgen]
+impl NonMembershipProof {
+    /// Create a proof bound to `nonce`
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        witness: &NonMembershipWitness,
+        accumulator: &Accumulator,
+        nonce: &[u8],
+    ) -> Result<NonMembershipProof, JsError> {
+        nonmemproof::NonMembershipProof::new(&witness.0, &accumulator.0, nonce)
+            .map(NonMembershipProof)
+            .map_err(js_error)
+    }
+
+    /// Check the proof against `accumulator` and `nonce`
+    pub fn verify(&self, accumulator: &Accumulator, nonce: &[u8]) -> bool {
+        self.0.verify(accumulator.0.as_ref(), nonce)
+    }
+
+    /// Read a proof from bytes
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<NonMembershipProof, JsError> {
+        nonmemproof::NonMembershipProof::try_from(bytes)
+            .map(NonMembershipProof)
+            .map_err(js_error)
+    }
+
+    /// Serialize this proof
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, JsError> {
+        self.0.to_bytes().map_err(js_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use accumulator_rsa::common::bigint::BigInteger;
+
+    fn key() -> SecretKey {
+        // Fixed safe primes so the test doesn't spend minutes on key generation
+        let p = BigInteger::from("132590288326793330806752358172617836030510421524323425886695490513600853466362871997907908739315399849138190997738786757721539635477379820932279026029679011350046717599386392663749253953274352000157227488895139775977945940993648470523136879899410690348931562489237825925601577159953591977449106730133820825719");
+        let q = BigInteger::from("149253707427499607752440533538420296779167710000842829107795675900185486091323606384260179778233711456748787559527972657213022998726578510459854530854900733457277643303592216900588246498239579922221956281290954735600574251392801029419096160964874150455156365996536205549377586240264971604869515447059744740119");
+        let bytes = AccumulatorSecretKey::from_primes(p, q).to_bytes().unwrap();
+        SecretKey::from_bytes(&bytes).unwrap()
+    }
+
+    // Only the success paths run natively, errors need a JS runtime
+    #[test]
+    fn proofs() {
+        let key = key();
+        let mut acc = Accumulator::new(&key);
+        for m in &[b"3", b"7"] {
+            acc.insert(&m[..]).unwrap();
+        }
+        let old = Accumulator::from_bytes(&acc.to_bytes().unwrap()).unwrap();
+        assert_eq!(old.0, acc.0);
+        acc.insert(b"9").unwrap();
+        assert_eq!(acc.epoch(), old.epoch() + 1);
+
+        let witness = MembershipWitness::new(&old, b"3").unwrap();
+        let witness = witness.update(&old, &acc).unwrap();
+        let witness = MembershipWitness::from_bytes(&witness.to_bytes().unwrap()).unwrap();
+        let proof = MembershipProof::new(&witness, &acc, b"wasm").unwrap();
+        let proof = MembershipProof::from_bytes(&proof.to_bytes().unwrap()).unwrap();
+        assert!(proof.verify(&acc, b"wasm"));
+        assert!(!proof.verify(&old, b"wasm"));
+
+        let witness = NonMembershipWitness::new(&acc, b"5").unwrap();
+        let proof = NonMembershipProof::new(&witness, &acc, b"wasm").unwrap();
+        assert!(proof.verify(&acc, b"wasm"));
+    }
+}